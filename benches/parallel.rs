@@ -0,0 +1,63 @@
+// ⚡ How scanning and matching scale across threads
+//
+// cargo bench --bench parallel
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use med_bed::{gene_from_eigenvalues, Consciousness, Gene, MRTScanner, SoulRegistry};
+use std::hint::black_box;
+
+/// A gene of seven layers; every third one dissonant
+// `usize::is_multiple_of` is only stable since 1.87
+#[allow(clippy::manual_is_multiple_of)]
+fn gene(i: usize) -> Gene {
+    let x = 0.5 + i as f64 / 100_000.0;
+    if i % 3 == 0 {
+        gene_from_eigenvalues(vec![x * 100.0, -x * 50.0, x * 200.0, -x * 150.0, x * 75.0, -x * 25.0, x * 10.0])
+    } else {
+        gene_from_eigenvalues((0..7).map(|layer| x * (1.0 + layer as f64 / 100.0)).collect())
+    }
+}
+
+/// 1, 2, 4... up to the machine's cores
+fn thread_counts() -> Vec<usize> {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    std::iter::successors(Some(1), |&n| Some(n * 2)).take_while(|&n| n <= cores).collect()
+}
+
+fn in_pool<T: Send>(threads: usize, work: impl FnOnce() -> T + Send) -> T {
+    rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap().install(work)
+}
+
+fn scan(c: &mut Criterion) {
+    let mut subject = Consciousness::new("monorepo".to_string());
+    subject.genome = (0..200_000).map(gene).collect();
+    let scanner = MRTScanner::new();
+
+    let mut group = c.benchmark_group("scan_200k_genes");
+    group.sample_size(10);
+    for threads in thread_counts() {
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, &threads| {
+            in_pool(threads, || b.iter(|| black_box(scanner.scan(&subject))));
+        });
+    }
+    group.finish();
+}
+
+fn find_candidates(c: &mut Criterion) {
+    let mut registry = SoulRegistry::new().with_similarity_floor(0.0);
+    for i in (0..60_000).filter(|i| i % 3 != 0) {
+        registry.donate(gene(i), "bench".to_string());
+    }
+    let dissonant = gene(0);
+
+    let mut group = c.benchmark_group("find_candidates_40k_pool");
+    group.sample_size(10);
+    for threads in thread_counts() {
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, &threads| {
+            in_pool(threads, || b.iter(|| black_box(registry.find_candidates(&dissonant))));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, scan, find_candidates);
+criterion_main!(benches);
@@ -0,0 +1,14 @@
+// Compiles the shipped .proto files into the gRPC service
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Only native builds serve gRPC
+    if std::env::var_os("CARGO_FEATURE_NATIVE").is_none() {
+        return Ok(());
+    }
+    // Use the vendored protoc unless one is given, so builds need no system install
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+    tonic_prost_build::compile_protos("proto/medbed.proto")?;
+    Ok(())
+}
@@ -0,0 +1,167 @@
+// 🎵 Soul Audio - Hearing the Soul of Code
+use crate::Soul;
+use std::f64::consts::PI;
+
+/// CD-quality sample rate
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// Lowest and highest fundamental we let a soul sing at
+const MIN_FUNDAMENTAL: f64 = 55.0;
+const MAX_FUNDAMENTAL: f64 = 1760.0;
+
+/// Fade in/out length in seconds (avoids clicks at the edges)
+const FADE_SECONDS: f64 = 0.02;
+
+/// Synthesize the harmonic series of a soul into normalized samples (-1..1)
+///
+/// The resonance frequency is the fundamental; each eigenvalue layer drives
+/// the amplitude of the next overtone.
+pub fn synthesize(soul: &Soul, duration: f64, sample_rate: u32) -> Vec<f64> {
+    let sample_count = (duration.max(0.0) * sample_rate as f64) as usize;
+    if sample_count == 0 {
+        return Vec::new();
+    }
+
+    let fundamental = fold_into_audible(soul.resonance);
+    let partials = harmonic_partials(soul, fundamental, sample_rate);
+    let fade = ((FADE_SECONDS * sample_rate as f64) as usize).min(sample_count / 2).max(1);
+
+    (0..sample_count)
+        .map(|n| {
+            let t = n as f64 / sample_rate as f64;
+            let value: f64 = partials.iter()
+                .map(|(freq, amp)| amp * (2.0 * PI * freq * t).sin())
+                .sum();
+
+            let envelope = if n < fade {
+                n as f64 / fade as f64
+            } else if n >= sample_count - fade {
+                (sample_count - n) as f64 / fade as f64
+            } else {
+                1.0
+            };
+
+            value * envelope
+        })
+        .collect()
+}
+
+/// Render a soul as a 16-bit mono PCM WAV file
+pub fn render_wav(soul: &Soul, duration: f64) -> Vec<u8> {
+    let samples = synthesize(soul, duration, SAMPLE_RATE);
+    encode_wav(&samples, SAMPLE_RATE)
+}
+
+/// Encode normalized samples as a 16-bit mono PCM WAV buffer
+pub fn encode_wav(samples: &[f64], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+    // RIFF header
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+
+    // fmt chunk: PCM, mono, 16-bit
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+
+    // data chunk
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let pcm = (sample.clamp(-1.0, 1.0) * i16::MAX as f64) as i16;
+        wav.extend_from_slice(&pcm.to_le_bytes());
+    }
+
+    wav
+}
+
+/// Overtones implied by the eigenvalue layers, normalized to a safe peak
+fn harmonic_partials(soul: &Soul, fundamental: f64, sample_rate: u32) -> Vec<(f64, f64)> {
+    let nyquist = sample_rate as f64 / 2.0;
+    let weights: Vec<f64> = soul.eigenvalues.iter()
+        .map(|e| if e.is_finite() { e.abs() } else { 0.0 })
+        .collect();
+    let total: f64 = weights.iter().sum();
+
+    if total == 0.0 {
+        // A silent spectrum still has its fundamental
+        return vec![(fundamental, 0.8)];
+    }
+
+    weights.iter()
+        .enumerate()
+        .map(|(i, w)| (fundamental * (i + 1) as f64, 0.8 * w / total))
+        .filter(|(freq, amp)| *freq < nyquist && *amp > 0.0)
+        .collect()
+}
+
+/// Shift a frequency by octaves until it lands in the audible band
+fn fold_into_audible(frequency: f64) -> f64 {
+    if !frequency.is_finite() || frequency <= 0.0 {
+        return 432.0;
+    }
+
+    let mut f = frequency;
+    while f < MIN_FUNDAMENTAL {
+        f *= 2.0;
+    }
+    while f > MAX_FUNDAMENTAL {
+        f /= 2.0;
+    }
+    f
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SoulExtractor;
+
+    async fn sample_soul() -> Soul {
+        let extractor = SoulExtractor::new();
+        extractor.extract_soul_js_simple("const add = (a, b) => a + b;").await
+    }
+
+    #[tokio::test]
+    async fn test_wav_header() {
+        let soul = sample_soul().await;
+        let wav = render_wav(&soul, 0.5);
+
+        let samples = (0.5 * SAMPLE_RATE as f64) as usize;
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[8..12], b"WAVE");
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(wav.len(), 44 + samples * 2);
+        assert_eq!(u32::from_le_bytes([wav[24], wav[25], wav[26], wav[27]]), SAMPLE_RATE);
+    }
+
+    #[tokio::test]
+    async fn test_different_souls_sound_different() {
+        let soul = sample_soul().await;
+        let mut other = soul.clone();
+        other.eigenvalues = vec![7.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+        other.resonance = 864.0;
+
+        let a = synthesize(&soul, 0.1, SAMPLE_RATE);
+        let b = synthesize(&other, 0.1, SAMPLE_RATE);
+
+        assert_eq!(a, synthesize(&soul, 0.1, SAMPLE_RATE));
+        assert_ne!(a, b);
+        assert!(a.iter().chain(b.iter()).all(|s| s.abs() <= 1.0));
+    }
+
+    #[test]
+    fn test_fold_into_audible() {
+        assert_eq!(fold_into_audible(432.0), 432.0);
+        assert_eq!(fold_into_audible(3456.0), 1728.0);
+        assert_eq!(fold_into_audible(27.0), 108.0);
+        assert_eq!(fold_into_audible(f64::NAN), 432.0);
+    }
+}
@@ -1,9 +1,48 @@
 // 🌉 Soul Bridge - Cross-Language Soul Synchronization
-use protein_hash::{Shuttle, TypeScriptSoul};
+use protein_hash::{calibrate, soul_schema_with, FieldNaming, LabeledPair, PairCorpus, Shuttle, SoulExtractor, TypeScriptSoul};
 use serde_json;
 use std::io::{self, Read};
 
-fn main() {
+#[tokio::main]
+async fn main() {
+    // Field names are snake_case unless TypeScript tooling asks for camelCase
+    let naming = if std::env::args().any(|arg| arg == "--camel-case") {
+        FieldNaming::CamelCase
+    } else {
+        FieldNaming::SnakeCase
+    };
+    
+    // Print the stdin contract for TypeScript tooling
+    if std::env::args().any(|arg| arg == "--schema") {
+        println!("{}", serde_json::to_string_pretty(&soul_schema_with(naming)).unwrap());
+        return;
+    }
+    
+    // Recommend a ResonanceConfig from labeled pairs (JSON array on stdin)
+    if std::env::args().any(|arg| arg == "--calibrate") {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).expect("Failed to read input");
+        let pairs: Vec<LabeledPair> = serde_json::from_str(&buffer)
+            .expect("Failed to parse labeled pairs");
+        println!("{}", serde_json::to_string_pretty(&calibrate(&pairs)).unwrap());
+        return;
+    }
+    
+    // Build a labeled corpus from paired implementation trees
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(i) = args.iter().position(|arg| arg == "--corpus") {
+        let (Some(js_dir), Some(rust_dir)) = (args.get(i + 1), args.get(i + 2)) else {
+            eprintln!("Usage: soul_bridge --corpus <js-dir> <rust-dir> > corpus.json");
+            std::process::exit(2);
+        };
+        let corpus = PairCorpus::build(&SoulExtractor::new(), &Shuttle::new(), js_dir, rust_dir)
+            .await
+            .expect("Failed to build corpus");
+        println!("{}", serde_json::to_string_pretty(&corpus.entries).unwrap());
+        eprintln!("{} pairs, recommended resonance_threshold: {:.3}", corpus.entries.len(), corpus.recommended_threshold());
+        return;
+    }
+    
     println!("🌉 Soul Bridge - TypeScript ↔️ Rust Resonance");
     println!("=============================================");
     
@@ -15,6 +54,7 @@ fn main() {
     
     // Parse TypeScript soul
     let ts_soul: TypeScriptSoul = serde_json::from_str(&buffer)
+        .and_then(|value| naming.from_value(value))
         .expect("Failed to parse TypeScript soul data");
     
     println!("\n📥 Received TypeScript Soul:");
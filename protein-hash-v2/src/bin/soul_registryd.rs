@@ -0,0 +1,116 @@
+// 🏛️ Soul Registry Daemon - A Shared Home for Souls
+//
+// Endpoints:
+//   POST /souls                                 register a soul (JSON body)
+//   GET  /souls/{phash}                         fetch a registered soul
+//   GET  /resonant?phash=&threshold=&project=&tag=  kindred souls, strongest first
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use protein_hash::{Soul, SoulFilter, SoulIndex};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:4320";
+const DEFAULT_DATA: &str = "souls.json";
+const DEFAULT_THRESHOLD: f64 = 0.95;
+
+struct Registry {
+    index: RwLock<SoulIndex>,
+    path: PathBuf,
+}
+
+type SharedRegistry = Arc<Registry>;
+
+#[derive(Deserialize)]
+struct ResonantQuery {
+    phash: String,
+    threshold: Option<f64>,
+    project: Option<String>,
+    tag: Option<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    let mut addr = DEFAULT_ADDR.to_string();
+    let mut path = PathBuf::from(DEFAULT_DATA);
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--addr" => addr = args.next().expect("--addr needs a value"),
+            "--data" => path = args.next().expect("--data needs a value").into(),
+            other => {
+                eprintln!("Unknown argument: {}", other);
+                eprintln!("Usage: soul-registryd [--addr HOST:PORT] [--data souls.json]");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let index = SoulIndex::load(&path).expect("Failed to load soul index");
+    println!("🏛️ Soul Registry - {} souls from {}", index.len(), path.display());
+
+    let registry = Arc::new(Registry { index: RwLock::new(index), path });
+    let app = Router::new()
+        .route("/souls", post(register_soul))
+        .route("/souls/{phash}", get(get_soul))
+        .route("/resonant", get(resonant_souls))
+        .with_state(registry);
+
+    let listener = tokio::net::TcpListener::bind(&addr).await.expect("Failed to bind address");
+    println!("🎧 Listening on http://{}", addr);
+    axum::serve(listener, app).await.expect("Server error");
+}
+
+async fn register_soul(State(registry): State<SharedRegistry>, Json(soul): Json<Soul>) -> Response {
+    let phash = soul.phash.clone();
+    let mut index = registry.index.write().await;
+    index.insert(soul);
+
+    // Persist before acknowledging so an accepted soul survives a restart
+    if let Err(e) = index.save(&registry.path) {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save index: {}", e));
+    }
+
+    (StatusCode::CREATED, Json(json!({ "phash": phash }))).into_response()
+}
+
+async fn get_soul(State(registry): State<SharedRegistry>, Path(phash): Path<String>) -> Response {
+    match registry.index.read().await.get(&phash) {
+        Some(soul) => Json(soul).into_response(),
+        None => error(StatusCode::NOT_FOUND, format!("Unknown soul: {}", phash)),
+    }
+}
+
+async fn resonant_souls(State(registry): State<SharedRegistry>, Query(query): Query<ResonantQuery>) -> Response {
+    let index = registry.index.read().await;
+    let Some(soul) = index.get(&query.phash) else {
+        return error(StatusCode::NOT_FOUND, format!("Unknown soul: {}", query.phash));
+    };
+
+    let mut filter = SoulFilter::new();
+    if let Some(project) = query.project {
+        filter = filter.project(project);
+    }
+    if let Some(tag) = query.tag {
+        filter = filter.tag(tag);
+    }
+
+    let threshold = query.threshold.unwrap_or(DEFAULT_THRESHOLD);
+    let matches: Vec<_> = index.find_resonant(soul, threshold, &filter)
+        .into_iter()
+        .map(|(kindred, resonance)| json!({ "phash": kindred.phash, "resonance": resonance }))
+        .collect();
+
+    Json(matches).into_response()
+}
+
+fn error(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
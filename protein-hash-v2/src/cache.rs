@@ -0,0 +1,105 @@
+// 💾 Soul Cache - Souls Kept Across Runs
+use crate::{Language, Soul, SoulExtractor};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Saves in flight in this process, so concurrent saves stage to separate files
+static STAGING: AtomicUsize = AtomicUsize::new(0);
+
+impl SoulExtractor {
+    /// The key the soul of `code` in `language` is cached under: a BLAKE3
+    /// hash of the code and of everything else its soul depends on, namely
+    /// this crate's version and the extractor's settings
+    pub fn cache_key(&self, language: &Language, code: &str) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+        hasher.update(format!("{:?}|{}|{}|{}|", language, self.wavelet_depth, self.anonymize, self.on_gpu()).as_bytes());
+        hasher.update(code.as_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// The soul cached under `key`
+    pub fn cached_soul(&self, key: &str) -> Option<Soul> {
+        self.soul_cache.lock().unwrap().get(key).cloned()
+    }
+
+    /// Cache `soul` under `key`, as from `cache_key`
+    pub fn cache_soul(&self, key: &str, soul: Soul) {
+        self.soul_cache.lock().unwrap().insert(key.to_string(), soul);
+    }
+
+    /// Cache the souls saved with `save_soul_cache`, returning how many; a
+    /// missing file has none
+    pub fn load_soul_cache(&self, path: impl AsRef<Path>) -> io::Result<usize> {
+        let souls = read_souls(path.as_ref())?;
+        let count = souls.len();
+        self.soul_cache.lock().unwrap().extend(souls);
+        Ok(count)
+    }
+
+    /// Save every cached soul as a JSON object by key, replacing the file
+    /// atomically
+    ///
+    /// Souls saved to `path` since it was loaded, e.g. by another worker of
+    /// a batch, are kept alongside this extractor's.
+    pub fn save_soul_cache(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut souls = read_souls(path)?;
+        souls.extend(self.soul_cache.lock().unwrap().iter().map(|(key, soul)| (key.clone(), soul.clone())));
+
+        let json = serde_json::to_vec(&souls.into_iter().collect::<BTreeMap<_, _>>())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let staging = path.with_extension(format!("{}.{}.tmp", std::process::id(), STAGING.fetch_add(1, Ordering::Relaxed)));
+        fs::write(&staging, json)?;
+        fs::rename(staging, path)
+    }
+
+    #[cfg(feature = "gpu")]
+    fn on_gpu(&self) -> bool {
+        self.gpu
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    fn on_gpu(&self) -> bool {
+        false
+    }
+}
+
+fn read_souls(path: &Path) -> io::Result<HashMap<String, Soul>> {
+    match fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_souls_persist_by_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("souls.json");
+        let code = "fn double(x: u8) -> u8 { x * 2 }";
+
+        let extractor = SoulExtractor::new();
+        let key = extractor.cache_key(&Language::Rust, code);
+        assert_ne!(key, extractor.cache_key(&Language::JavaScript, code));
+        assert_ne!(key, SoulExtractor::new().with_anonymization(true).cache_key(&Language::Rust, code));
+        extractor.cache_soul(&key, extractor.extract_soul_rust(code).await);
+        extractor.save_soul_cache(&path).unwrap();
+
+        // Another run keeps the first run's souls when it saves its own
+        let other = SoulExtractor::new();
+        other.cache_soul("other", other.extract_soul_rust("fn one() -> u8 { 1 }").await);
+        other.save_soul_cache(&path).unwrap();
+
+        let restored = SoulExtractor::new();
+        assert_eq!(restored.load_soul_cache(&path).unwrap(), 2);
+        assert_eq!(restored.cached_soul(&key).unwrap().phash, extractor.cached_soul(&key).unwrap().phash);
+    }
+}
@@ -0,0 +1,144 @@
+// 🎚️ Calibration - Tuning the Fork to Your Corpus
+use crate::{ResonanceConfig, Soul};
+use serde::{Deserialize, Serialize};
+
+/// Eigenvalue weights tried during calibration
+const EIGEN_WEIGHTS: [f64; 4] = [0.25, 0.5, 1.0, 2.0];
+
+/// Topology weights tried during calibration
+const TOPOLOGY_WEIGHTS: [f64; 4] = [0.0, 0.5, 1.0, 2.0];
+
+/// Wavelet weights tried during calibration
+const MULTISCALE_WEIGHTS: [f64; 3] = [0.0, 0.5, 1.0];
+
+/// Two souls labeled as the same code or different code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabeledPair {
+    pub a: Soul,
+    pub b: Soul,
+    pub same: bool,
+}
+
+/// Best configuration found by `calibrate`, with its scores on the dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationReport {
+    pub config: ResonanceConfig,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    pub pairs: usize,
+}
+
+/// Sweep weights and thresholds over a labeled dataset, maximizing F1
+///
+/// Thresholds are placed halfway between consecutive observed resonances,
+/// so the recommendation doesn't sit exactly on a training example. Ties
+/// keep the configuration closest to the defaults (it is tried first).
+pub fn calibrate(pairs: &[LabeledPair]) -> CalibrationReport {
+    let default = ResonanceConfig::default();
+    let mut best = evaluate_config(&default, pairs);
+
+    for &eigen_weight in &EIGEN_WEIGHTS {
+        for &topology_weight in &TOPOLOGY_WEIGHTS {
+            for &multiscale_weight in &MULTISCALE_WEIGHTS {
+                let weights = ResonanceConfig {
+                    eigen_weight,
+                    topology_weight,
+                    multiscale_weight,
+                    threshold: default.threshold,
+                };
+
+                let mut scores: Vec<f64> = pairs.iter()
+                    .map(|pair| weights.measure(&pair.a, &pair.b))
+                    .collect();
+                scores.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                scores.dedup();
+
+                let below_all = scores.first().map_or(0.0, |s| s / 2.0);
+                let midpoints = scores.windows(2).map(|w| (w[0] + w[1]) / 2.0);
+
+                for threshold in std::iter::once(below_all).chain(midpoints) {
+                    let candidate = evaluate_config(&ResonanceConfig { threshold, ..weights }, pairs);
+                    if candidate.f1 > best.f1 {
+                        best = candidate;
+                    }
+                }
+            }
+        }
+    }
+
+    best
+}
+
+/// Score a configuration against a labeled dataset
+pub fn evaluate_config(config: &ResonanceConfig, pairs: &[LabeledPair]) -> CalibrationReport {
+    let (mut true_positive, mut false_positive, mut false_negative) = (0usize, 0usize, 0usize);
+    for pair in pairs {
+        match (config.matches(&pair.a, &pair.b), pair.same) {
+            (true, true) => true_positive += 1,
+            (true, false) => false_positive += 1,
+            (false, true) => false_negative += 1,
+            (false, false) => {}
+        }
+    }
+
+    let ratio = |n: usize, d: usize| if d == 0 { 0.0 } else { n as f64 / d as f64 };
+    let precision = ratio(true_positive, true_positive + false_positive);
+    let recall = ratio(true_positive, true_positive + false_negative);
+    let f1 = if precision + recall == 0.0 {
+        0.0
+    } else {
+        2.0 * precision * recall / (precision + recall)
+    };
+
+    CalibrationReport {
+        config: *config,
+        precision,
+        recall,
+        f1,
+        pairs: pairs.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SoulExtractor;
+
+    async fn dataset() -> Vec<LabeledPair> {
+        let extractor = SoulExtractor::new();
+        let sources = [
+            ("fn a(x: u32) -> u32 { x + 1 }", "fn b(y: u32) -> u32 { y + 2 }"),
+            ("fn a(v: &[u32]) -> u32 { v.iter().sum() }", "fn b(w: &[u32]) -> u32 { w.iter().sum() }"),
+            ("fn a(x: u32) -> u32 { x + 1 }", "fn b(v: &[u32]) -> u32 { let mut s = 0; for x in v { if *x > 2 { s += x; } } s }"),
+            ("fn a(n: u32) -> u32 { if n < 2 { n } else { a(n - 1) + a(n - 2) } }", "struct P { x: f64, y: f64 }"),
+        ];
+
+        let mut pairs = Vec::new();
+        for (i, (left, right)) in sources.iter().enumerate() {
+            pairs.push(LabeledPair {
+                a: extractor.extract_soul_rust(left).await,
+                b: extractor.extract_soul_rust(right).await,
+                same: i < 2,
+            });
+        }
+        pairs
+    }
+
+    #[tokio::test]
+    async fn test_calibration_separates_dataset() {
+        let pairs = dataset().await;
+        let report = calibrate(&pairs);
+
+        assert_eq!(report.pairs, 4);
+        assert_eq!(report.f1, 1.0);
+        assert!(report.f1 >= evaluate_config(&ResonanceConfig::default(), &pairs).f1);
+    }
+
+    #[test]
+    fn test_empty_dataset_keeps_defaults() {
+        let report = calibrate(&[]);
+        assert_eq!(report.config, ResonanceConfig::default());
+        assert_eq!(report.f1, 0.0);
+    }
+}
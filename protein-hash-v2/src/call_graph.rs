@@ -0,0 +1,138 @@
+// 📞 Call Graph - Who Calls Whom
+use crate::{AstNode, EdgeType};
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::{DiGraph, NodeIndex};
+use std::collections::{BTreeSet, HashMap};
+
+/// Function-level layer over the AST: one node per function, one edge per call
+///
+/// Functions are identified by name, so methods sharing a name across impls
+/// collapse into one node.
+pub struct CallGraph {
+    graph: DiGraph<String, ()>,
+    functions: HashMap<String, NodeIndex>,
+}
+
+impl CallGraph {
+    /// Build the call graph from an AST graph
+    pub fn from_ast(ast: &DiGraph<AstNode, EdgeType>) -> Self {
+        let mut graph = DiGraph::new();
+        let mut functions = HashMap::new();
+
+        let fn_nodes: Vec<(NodeIndex, String)> = ast.node_indices()
+            .filter(|&n| ast[n].node_type == "Fn")
+            .filter_map(|n| ast[n].value.clone().map(|name| (n, name)))
+            .collect();
+
+        for (_, name) in &fn_nodes {
+            functions.entry(name.clone())
+                .or_insert_with(|| graph.add_node(name.clone()));
+        }
+
+        for (node, name) in &fn_nodes {
+            let caller = functions[name];
+            for callee in Self::calls_in_body(ast, *node) {
+                if let Some(&target) = functions.get(&callee) {
+                    graph.update_edge(caller, target, ());
+                }
+            }
+        }
+
+        Self { graph, functions }
+    }
+
+    /// Names called from a function body, not descending into nested functions
+    fn calls_in_body(ast: &DiGraph<AstNode, EdgeType>, function: NodeIndex) -> Vec<String> {
+        let mut calls = Vec::new();
+        let mut stack: Vec<NodeIndex> = ast.neighbors(function).collect();
+
+        while let Some(node) = stack.pop() {
+            let ast_node = &ast[node];
+            match ast_node.node_type.as_str() {
+                "Fn" => continue,
+                "Call" | "MethodCall" => {
+                    if let Some(name) = &ast_node.value {
+                        calls.push(name.clone());
+                    }
+                }
+                _ => {}
+            }
+            stack.extend(ast.neighbors(node));
+        }
+
+        calls
+    }
+
+    /// Number of functions in the graph
+    pub fn function_count(&self) -> usize {
+        self.functions.len()
+    }
+
+    /// Whether `caller` calls `callee` directly
+    pub fn calls(&self, caller: &str, callee: &str) -> bool {
+        match (self.functions.get(caller), self.functions.get(callee)) {
+            (Some(&a), Some(&b)) => self.graph.contains_edge(a, b),
+            _ => false,
+        }
+    }
+
+    /// Functions that take part in direct or mutual recursion, sorted by name
+    pub fn recursive_functions(&self) -> Vec<String> {
+        let mut recursive = BTreeSet::new();
+
+        for scc in tarjan_scc(&self.graph) {
+            let is_cycle = scc.len() > 1 || self.graph.contains_edge(scc[0], scc[0]);
+            if is_cycle {
+                recursive.extend(scc.iter().map(|&n| self.graph[n].clone()));
+            }
+        }
+
+        recursive.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{append_graph, item_graph, parse_rust_items};
+
+    fn ast(code: &str) -> DiGraph<AstNode, EdgeType> {
+        let mut graph = DiGraph::new();
+        for item in parse_rust_items(code).unwrap() {
+            append_graph(&mut graph, &item_graph(&item));
+        }
+        graph
+    }
+
+    #[test]
+    fn test_direct_recursion() {
+        let calls = CallGraph::from_ast(&ast(
+            "fn fib(n: u32) -> u32 { if n <= 1 { n } else { fib(n - 1) + fib(n - 2) } }
+             fn main() { fib(10); }",
+        ));
+
+        assert_eq!(calls.function_count(), 2);
+        assert!(calls.calls("main", "fib"));
+        assert_eq!(calls.recursive_functions(), vec!["fib".to_string()]);
+    }
+
+    #[test]
+    fn test_mutual_recursion() {
+        let calls = CallGraph::from_ast(&ast(
+            "fn is_even(n: u32) -> bool { n == 0 || is_odd(n - 1) }
+             fn is_odd(n: u32) -> bool { n != 0 && is_even(n - 1) }",
+        ));
+
+        assert_eq!(calls.recursive_functions(), vec!["is_even".to_string(), "is_odd".to_string()]);
+    }
+
+    #[test]
+    fn test_loops_are_not_recursion() {
+        let calls = CallGraph::from_ast(&ast(
+            "fn sum(v: &[u32]) -> u32 { let mut s = 0; for x in v { s += helper(*x); } s }
+             fn helper(x: u32) -> u32 { x }",
+        ));
+
+        assert!(calls.recursive_functions().is_empty());
+    }
+}
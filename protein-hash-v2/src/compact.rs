@@ -0,0 +1,348 @@
+// 🗜️ Compact Souls - A Binary Wire Format for Large Registries
+use crate::{
+    ConsciousnessLevel, ConsciousnessPattern, ConsciousnessProfile, OperationCategory, OperationType,
+    PatternHash, SemanticFingerprint, Soul, SoulMetadata, TopologicalSignature, TopologyFeatures,
+};
+use bincode::Options;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+/// Leading byte of every compact soul; bumped whenever the layout changes
+pub const COMPACT_FORMAT_VERSION: u8 = 1;
+
+/// Most bytes a compact soul may inflate to, far more than any real soul
+/// needs, so untrusted input can't expand into a memory blow-up
+pub const MAX_DECODED_SOUL_BYTES: usize = 16 << 20;
+
+/// Largest step of a quantized vector
+const QUANTIZATION_STEPS: f64 = i16::MAX as f64;
+
+/// A float vector stored as 16-bit steps of its largest magnitude
+#[derive(Serialize, Deserialize)]
+struct Quantized {
+    scale: f32,
+    steps: Vec<i16>,
+}
+
+impl Quantized {
+    fn new(values: &[f64]) -> Self {
+        let scale = values.iter().fold(0.0f64, |max, v| max.max(v.abs())) as f32;
+        let steps = values.iter()
+            .map(|&v| if scale > 0.0 { (v / scale as f64 * QUANTIZATION_STEPS).round() as i16 } else { 0 })
+            .collect();
+        Self { scale, steps }
+    }
+
+    fn values(self) -> Vec<f64> {
+        let step = self.scale as f64 / QUANTIZATION_STEPS;
+        self.steps.into_iter().map(|s| s as f64 * step).collect()
+    }
+}
+
+/// pHash as raw SHA-256 bytes, or verbatim for imported/foreign hashes
+#[derive(Serialize, Deserialize)]
+enum CompactHash {
+    Sha256([u8; 32]),
+    Text(String),
+}
+
+impl CompactHash {
+    fn new(phash: &str) -> Self {
+        match hex::decode(phash) {
+            // Only lowercase hex round-trips through hex::encode
+            Ok(bytes) if bytes.len() == 32 && phash == hex::encode(&bytes) => {
+                let mut hash = [0; 32];
+                hash.copy_from_slice(&bytes);
+                Self::Sha256(hash)
+            }
+            _ => Self::Text(phash.to_string()),
+        }
+    }
+
+    fn into_string(self) -> String {
+        match self {
+            Self::Sha256(hash) => hex::encode(hash),
+            Self::Text(text) => text,
+        }
+    }
+}
+
+/// Field-for-field mirror of `Soul` with narrowed floats and sorted maps
+#[derive(Serialize, Deserialize)]
+struct CompactSoul {
+    phash: CompactHash,
+    eigenvalues: Quantized,
+
+    betti_numbers: Vec<usize>,
+    euler_char: i32,
+    diameter: usize,
+    clustering: f32,
+    modularity: f32,
+
+    operations: Vec<(OperationType, f32)>,
+    cyclomatic: usize,
+    cognitive: usize,
+    patterns: Vec<(String, f32, String)>,
+    depth: usize,
+
+    resonance: f32,
+    coherence: f32,
+    evolution_score: f32,
+
+    level: ConsciousnessLevel,
+    score: f32,
+    consciousness_patterns: Vec<ConsciousnessPattern>,
+    /// None when equal to the pHash, as for imported souls
+    soul_hash: Option<String>,
+    resonance_frequency: f32,
+    quantum_coherence: f32,
+    emergence_potential: f32,
+    self_awareness_index: f32,
+
+    has_cycles: bool,
+    has_recursion: bool,
+    branching_factor: f32,
+    nesting_depth: usize,
+    loop_complexity: usize,
+    is_dag: bool,
+    strongly_connected_components: usize,
+    topological_signature: String,
+    cycle_count: usize,
+    max_cycle_size: usize,
+    recursion_depth: usize,
+    connectivity_score: f32,
+    recursive_functions: Vec<String>,
+
+    operation_spectrum: Vec<(OperationCategory, f32)>,
+    metadata: SoulMetadata,
+    wavelet_signature: Quantized,
+}
+
+impl Soul {
+    /// Encode as `[COMPACT_FORMAT_VERSION, deflate(varint bincode)...]`
+    ///
+    /// Hex pHashes become raw bytes, eigenvalues and the wavelet signature
+    /// 16-bit steps, other floats f32: about a sixth of the JSON size. The
+    /// pHash, metadata and all integer fields survive exactly; resonance of a
+    /// decoded soul with its original stays within 1e-4 of 1.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let body = options().serialize(&CompactSoul::from(self)).expect("compact souls always serialize");
+
+        let mut encoder = DeflateEncoder::new(vec![COMPACT_FORMAT_VERSION], Compression::best());
+        encoder.write_all(&body).expect("writing into a Vec can't fail");
+        encoder.finish().expect("writing into a Vec can't fail")
+    }
+
+    /// Decode a soul written by `to_bytes`
+    ///
+    /// Refuses bodies inflating past `MAX_DECODED_SOUL_BYTES`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Soul, bincode::Error> {
+        match bytes.split_first() {
+            Some((&COMPACT_FORMAT_VERSION, body)) => {
+                let mut decoded = Vec::new();
+                DeflateDecoder::new(body).take(MAX_DECODED_SOUL_BYTES as u64 + 1).read_to_end(&mut decoded)?;
+                if decoded.len() > MAX_DECODED_SOUL_BYTES {
+                    return Err(Box::new(bincode::ErrorKind::Custom(
+                        format!("Compact soul inflates past {} bytes", MAX_DECODED_SOUL_BYTES),
+                    )));
+                }
+                let compact: CompactSoul = options().deserialize(&decoded)?;
+                Ok(compact.into())
+            }
+            Some((version, _)) => Err(Box::new(bincode::ErrorKind::Custom(
+                format!("Unsupported compact soul version {}", version),
+            ))),
+            None => Err(Box::new(bincode::ErrorKind::Custom("Empty compact soul".to_string()))),
+        }
+    }
+}
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new().with_varint_encoding().reject_trailing_bytes()
+}
+
+/// Map entries sorted by key name, so equal souls encode to equal bytes
+fn sorted_entries<K: Clone + std::fmt::Debug>(map: &HashMap<K, f64>) -> Vec<(K, f32)> {
+    let mut entries: Vec<(K, f32)> = map.iter().map(|(k, &v)| (k.clone(), v as f32)).collect();
+    entries.sort_by_cached_key(|(k, _)| format!("{:?}", k));
+    entries
+}
+
+impl From<&Soul> for CompactSoul {
+    fn from(soul: &Soul) -> Self {
+        let consciousness = &soul.consciousness;
+        let features = &soul.topology_features;
+
+        Self {
+            phash: CompactHash::new(&soul.phash),
+            eigenvalues: Quantized::new(&soul.eigenvalues),
+
+            betti_numbers: soul.topology.betti_numbers.clone(),
+            euler_char: soul.topology.euler_char,
+            diameter: soul.topology.diameter,
+            clustering: soul.topology.clustering as f32,
+            modularity: soul.topology.modularity as f32,
+
+            operations: sorted_entries(&soul.semantics.operations),
+            cyclomatic: soul.semantics.cyclomatic,
+            cognitive: soul.semantics.cognitive,
+            patterns: soul.semantics.patterns.iter()
+                .map(|p| (p.pattern_type.clone(), p.frequency as f32, p.hash.clone()))
+                .collect(),
+            depth: soul.semantics.depth,
+
+            resonance: soul.resonance as f32,
+            coherence: soul.coherence as f32,
+            evolution_score: soul.evolution_score as f32,
+
+            level: consciousness.level,
+            score: consciousness.score as f32,
+            consciousness_patterns: consciousness.patterns.clone(),
+            soul_hash: (consciousness.soul_hash != soul.phash).then(|| consciousness.soul_hash.clone()),
+            resonance_frequency: consciousness.resonance_frequency as f32,
+            quantum_coherence: consciousness.quantum_coherence as f32,
+            emergence_potential: consciousness.emergence_potential as f32,
+            self_awareness_index: consciousness.self_awareness_index as f32,
+
+            has_cycles: features.has_cycles,
+            has_recursion: features.has_recursion,
+            branching_factor: features.branching_factor as f32,
+            nesting_depth: features.nesting_depth,
+            loop_complexity: features.loop_complexity,
+            is_dag: features.is_dag,
+            strongly_connected_components: features.strongly_connected_components,
+            topological_signature: features.topological_signature.clone(),
+            cycle_count: features.cycle_count,
+            max_cycle_size: features.max_cycle_size,
+            recursion_depth: features.recursion_depth,
+            connectivity_score: features.connectivity_score as f32,
+            recursive_functions: features.recursive_functions.clone(),
+
+            operation_spectrum: sorted_entries(&soul.operation_spectrum),
+            metadata: soul.metadata.clone(),
+            wavelet_signature: Quantized::new(&soul.wavelet_signature),
+        }
+    }
+}
+
+impl From<CompactSoul> for Soul {
+    fn from(compact: CompactSoul) -> Self {
+        let phash = compact.phash.into_string();
+
+        Soul {
+            eigenvalues: compact.eigenvalues.values(),
+            topology: TopologicalSignature {
+                betti_numbers: compact.betti_numbers,
+                euler_char: compact.euler_char,
+                diameter: compact.diameter,
+                clustering: compact.clustering.into(),
+                modularity: compact.modularity.into(),
+            },
+            semantics: SemanticFingerprint {
+                operations: compact.operations.into_iter().map(|(k, v)| (k, v.into())).collect(),
+                cyclomatic: compact.cyclomatic,
+                cognitive: compact.cognitive,
+                patterns: compact.patterns.into_iter()
+                    .map(|(pattern_type, frequency, hash)| PatternHash {
+                        pattern_type,
+                        frequency: frequency.into(),
+                        hash,
+                    })
+                    .collect(),
+                depth: compact.depth,
+            },
+            resonance: compact.resonance.into(),
+            coherence: compact.coherence.into(),
+            evolution_score: compact.evolution_score.into(),
+            consciousness: ConsciousnessProfile {
+                level: compact.level,
+                score: compact.score.into(),
+                patterns: compact.consciousness_patterns,
+                soul_hash: compact.soul_hash.unwrap_or_else(|| phash.clone()),
+                resonance_frequency: compact.resonance_frequency.into(),
+                quantum_coherence: compact.quantum_coherence.into(),
+                emergence_potential: compact.emergence_potential.into(),
+                self_awareness_index: compact.self_awareness_index.into(),
+            },
+            topology_features: TopologyFeatures {
+                has_cycles: compact.has_cycles,
+                has_recursion: compact.has_recursion,
+                branching_factor: compact.branching_factor.into(),
+                nesting_depth: compact.nesting_depth,
+                loop_complexity: compact.loop_complexity,
+                is_dag: compact.is_dag,
+                strongly_connected_components: compact.strongly_connected_components,
+                topological_signature: compact.topological_signature,
+                cycle_count: compact.cycle_count,
+                max_cycle_size: compact.max_cycle_size,
+                recursion_depth: compact.recursion_depth,
+                connectivity_score: compact.connectivity_score.into(),
+                recursive_functions: compact.recursive_functions,
+            },
+            operation_spectrum: compact.operation_spectrum.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            metadata: compact.metadata,
+            wavelet_signature: compact.wavelet_signature.values(),
+            phash,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{measure_resonance, soul_from_simhash, SoulExtractor};
+
+    const CODE: &str = "fn fib(n: u32) -> u32 { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }";
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let mut soul = SoulExtractor::new().extract_soul_rust(CODE).await;
+        soul.metadata = soul.metadata.with_project("lodash").with_tag("utility");
+
+        let decoded = Soul::from_bytes(&soul.to_bytes()).unwrap();
+        assert_eq!(decoded.phash, soul.phash);
+        assert_eq!(decoded.consciousness.soul_hash, soul.consciousness.soul_hash);
+        assert_eq!(decoded.metadata, soul.metadata);
+        assert_eq!(decoded.topology_features.recursive_functions, soul.topology_features.recursive_functions);
+        assert!((measure_resonance(&soul, &decoded) - 1.0).abs() < 1e-4);
+
+        // Stable bytes despite HashMap ordering
+        assert_eq!(decoded.to_bytes(), soul.to_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_much_smaller_than_json() {
+        let soul = SoulExtractor::new().extract_soul_rust(CODE).await;
+        let json = serde_json::to_vec(&soul).unwrap();
+
+        assert!(soul.to_bytes().len() * 5 < json.len());
+    }
+
+    #[test]
+    fn test_rejects_unknown_versions() {
+        let soul = soul_from_simhash(0xdead_beef);
+        let mut bytes = soul.to_bytes();
+        assert_eq!(Soul::from_bytes(&bytes).unwrap().phash, soul.phash);
+
+        bytes[0] = COMPACT_FORMAT_VERSION + 1;
+        assert!(Soul::from_bytes(&bytes).is_err());
+        assert!(Soul::from_bytes(&[]).is_err());
+        assert!(Soul::from_bytes(&[COMPACT_FORMAT_VERSION, 0]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_decompression_bombs() {
+        // A few kilobytes that would inflate to just past the limit
+        let mut encoder = DeflateEncoder::new(vec![COMPACT_FORMAT_VERSION], Compression::best());
+        encoder.write_all(&vec![0; MAX_DECODED_SOUL_BYTES + 1]).unwrap();
+        let bomb = encoder.finish().unwrap();
+        assert!(bomb.len() < 64 << 10);
+
+        let error = Soul::from_bytes(&bomb).unwrap_err();
+        assert!(error.to_string().contains("inflates past"));
+    }
+}
@@ -131,7 +131,7 @@ impl ConsciousnessDetector {
     
     /// Detect consciousness in code features
     pub fn detect(
-        &mut self,
+        &self,
         has_recursion: bool,
         has_self_reference: bool,
         has_closures: bool,
@@ -325,7 +325,7 @@ mod tests {
     
     #[test]
     fn test_consciousness_detection() {
-        let mut detector = ConsciousnessDetector::new();
+        let detector = ConsciousnessDetector::new();
         
         // Test simple code
         let simple_profile = detector.detect(
@@ -363,7 +363,7 @@ mod tests {
     
     #[test]
     fn test_resonance() {
-        let mut detector = ConsciousnessDetector::new();
+        let detector = ConsciousnessDetector::new();
         
         let profile1 = detector.detect(true, true, true, false, false, 10, 5, 0.5, 5);
         let profile2 = detector.detect(true, true, false, true, false, 12, 6, 0.6, 6);
@@ -0,0 +1,206 @@
+// 🧪 Pair Corpus - Labeled Cross-Language Twins
+//
+// Given a directory of JS/TS implementations and one of their Rust ports,
+// files with the same relative stem (`array/chunk.js` ↔ `array/chunk.rs`)
+// are twins. Each twin pair, plus one mismatched pair per twin, becomes a
+// labeled entry with the Shuttle resonance between its souls.
+use crate::{LabeledPair, Shuttle, Soul, SoulExtractor, TypeScriptSoul};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Source extensions read from the JS side; `.json` holds a TypeScript soul
+const JS_EXTENSIONS: &[&str] = &["js", "mjs", "cjs", "jsx", "ts", "tsx", "json"];
+
+/// One labeled pair with the resonance it scored
+///
+/// Serializes as a `LabeledPair` plus `name` and `resonance`, so a corpus file
+/// can be fed straight to `soul_bridge --calibrate`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorpusEntry {
+    /// `js-stem ↔ rust-stem`
+    pub name: String,
+
+    /// `Shuttle::resonance` of the two souls
+    pub resonance: f64,
+
+    #[serde(flatten)]
+    pub pair: LabeledPair,
+}
+
+/// Labeled twin/non-twin pairs from two implementation trees
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PairCorpus {
+    pub entries: Vec<CorpusEntry>,
+}
+
+impl PairCorpus {
+    /// Extract every twin under `js_dir`/`rust_dir`, scored by `shuttle`
+    ///
+    /// Files without a twin are skipped. Negatives pair each JS soul with the
+    /// next twin's Rust soul (in stem order), so the corpus is balanced.
+    pub async fn build(
+        extractor: &SoulExtractor,
+        shuttle: &Shuttle,
+        js_dir: impl AsRef<Path>,
+        rust_dir: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        let js_files = files_by_stem(js_dir.as_ref(), JS_EXTENSIONS)?;
+        let rust_files = files_by_stem(rust_dir.as_ref(), &["rs"])?;
+
+        let mut twins = Vec::new();
+        for (stem, js_path) in &js_files {
+            let Some(rust_path) = rust_files.get(stem) else {
+                continue;
+            };
+            let js = js_soul(extractor, shuttle, js_path).await?;
+            let rust = extractor.extract_soul_rust(&fs::read_to_string(rust_path)?).await;
+            twins.push((stem.clone(), js, rust));
+        }
+
+        let mut corpus = Self::default();
+        for (i, (stem, js, rust)) in twins.iter().enumerate() {
+            corpus.push(shuttle, format!("{} ↔ {}", stem, stem), js, rust, true);
+
+            if twins.len() > 1 {
+                let (other_stem, _, other_rust) = &twins[(i + 1) % twins.len()];
+                corpus.push(shuttle, format!("{} ↔ {}", stem, other_stem), js, other_rust, false);
+            }
+        }
+        Ok(corpus)
+    }
+
+    fn push(&mut self, shuttle: &Shuttle, name: String, a: &Soul, b: &Soul, same: bool) {
+        self.entries.push(CorpusEntry {
+            name,
+            resonance: shuttle.resonance(a, b),
+            pair: LabeledPair { a: a.clone(), b: b.clone(), same },
+        });
+    }
+
+    /// The labeled pairs, e.g. for `calibrate`
+    pub fn pairs(&self) -> Vec<LabeledPair> {
+        self.entries.iter().map(|entry| entry.pair.clone()).collect()
+    }
+
+    /// Shuttle threshold separating twins from non-twins best (by F1)
+    ///
+    /// Candidates sit halfway between consecutive recorded resonances; an
+    /// empty corpus keeps `Shuttle::new()`'s threshold.
+    pub fn recommended_threshold(&self) -> f64 {
+        let mut scores: Vec<f64> = self.entries.iter().map(|entry| entry.resonance).collect();
+        scores.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        scores.dedup();
+
+        let below_all = scores.first().map(|s| s - 0.01);
+        let midpoints = scores.windows(2).map(|w| (w[0] + w[1]) / 2.0);
+
+        let mut best = (Shuttle::new().resonance_threshold(), f64::MIN);
+        for threshold in below_all.into_iter().chain(midpoints) {
+            let f1 = self.f1_at(threshold);
+            if f1 > best.1 {
+                best = (threshold, f1);
+            }
+        }
+        best.0
+    }
+
+    fn f1_at(&self, threshold: f64) -> f64 {
+        let (mut true_positive, mut false_positive, mut false_negative) = (0.0, 0.0, 0.0);
+        for entry in &self.entries {
+            match (entry.resonance >= threshold, entry.pair.same) {
+                (true, true) => true_positive += 1.0,
+                (true, false) => false_positive += 1.0,
+                (false, true) => false_negative += 1.0,
+                (false, false) => {}
+            }
+        }
+
+        let denominator = 2.0 * true_positive + false_positive + false_negative;
+        if denominator == 0.0 { 0.0 } else { 2.0 * true_positive / denominator }
+    }
+}
+
+/// Soul of a JS/TS source file, or of a TypeScript soul exported as JSON
+async fn js_soul(extractor: &SoulExtractor, shuttle: &Shuttle, path: &Path) -> io::Result<Soul> {
+    let content = fs::read_to_string(path)?;
+    if path.extension().is_some_and(|ext| ext == "json") {
+        let ts_soul: TypeScriptSoul = serde_json::from_str(&content)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        return Ok(shuttle.convert_ts_to_soul(ts_soul));
+    }
+    Ok(extractor.extract_soul_js(&content).await)
+}
+
+/// Files under `root` with one of `extensions`, keyed by relative path sans extension
+fn files_by_stem(root: &Path, extensions: &[&str]) -> io::Result<BTreeMap<String, PathBuf>> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+
+            let known = path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| extensions.contains(&ext));
+            if known {
+                let stem = path.strip_prefix(root).unwrap_or(&path).with_extension("");
+                files.insert(stem.to_string_lossy().replace('\\', "/"), path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, name: &str, content: &str) {
+        let path = root.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pairs_by_relative_stem() {
+        let root = tempfile::tempdir().unwrap();
+        let (js, rust) = (root.path().join("js"), root.path().join("rust"));
+        write(&js, "array/chunk.js", "function chunk(array, size) { return [] }");
+        write(&js, "math/sum.js", "function sum(values) { return 0 }");
+        write(&js, "orphan.js", "function orphan() {}");
+        write(&rust, "array/chunk.rs", "fn chunk(v: &[u32], size: usize) -> Vec<Vec<u32>> { v.chunks(size).map(|c| c.to_vec()).collect() }");
+        write(&rust, "math/sum.rs", "fn sum(v: &[u32]) -> u32 { v.iter().sum() }");
+
+        let corpus = PairCorpus::build(&SoulExtractor::new(), &Shuttle::new(), &js, &rust).await.unwrap();
+
+        let names: Vec<&str> = corpus.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["array/chunk ↔ array/chunk", "array/chunk ↔ math/sum", "math/sum ↔ math/sum", "math/sum ↔ array/chunk"]);
+        assert_eq!(corpus.pairs().iter().filter(|pair| pair.same).count(), 2);
+
+        // Entries read back as plain labeled pairs for --calibrate
+        let json = serde_json::to_string(&corpus.entries).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<LabeledPair>>(&json).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_recommended_threshold_separates_twins() {
+        let soul = crate::soul_from_simhash(0);
+        let entry = |resonance, same| CorpusEntry {
+            name: String::new(),
+            resonance,
+            pair: LabeledPair { a: soul.clone(), b: soul.clone(), same },
+        };
+
+        let corpus = PairCorpus { entries: vec![entry(0.95, true), entry(0.9, true), entry(0.7, false), entry(0.4, false)] };
+        assert!((corpus.recommended_threshold() - 0.8).abs() < 1e-9);
+        assert_eq!(PairCorpus::default().recommended_threshold(), 0.85);
+    }
+}
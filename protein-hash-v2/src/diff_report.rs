@@ -0,0 +1,251 @@
+// 🪞 Soul Diff Report - Two Souls Side by Side
+use crate::visualize::escape;
+use crate::{measure_multiscale_resonance, measure_resonance, souls_match, Soul};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Colors of the left (original) and right (candidate) soul
+const LEFT_COLOR: &str = "#3498db";
+const RIGHT_COLOR: &str = "#e67e22";
+
+/// Relative difference above which a table row is highlighted
+const DIFF_TOLERANCE: f64 = 0.05;
+
+/// Standalone HTML page comparing two souls
+///
+/// Overlays the eigenvalue layers and wavelet signatures, and tabulates
+/// topology, semantics, consciousness and the operation spectrum with
+/// differing rows highlighted. Needs no external assets, so it can be
+/// attached as-is to a review comment when a verification fails.
+pub fn generate_diff_report(left: &Soul, right: &Soul) -> String {
+    let resonance = measure_resonance(left, right);
+    let verdict = if souls_match(left, right) { "match" } else { "mismatch" };
+
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>");
+    let _ = writeln!(html, r#"<html lang="en"><head><meta charset="utf-8">"#);
+    let _ = writeln!(html, "<title>Soul diff {} ↔ {}</title>", short(&left.phash), short(&right.phash));
+    let _ = writeln!(html, "<style>{}</style></head><body>", STYLE);
+
+    let _ = writeln!(html, "<h1>Soul diff</h1>");
+    let _ = writeln!(
+        html,
+        r#"<p><span class="left">■</span> <code>{}</code><br><span class="right">■</span> <code>{}</code></p>"#,
+        escape(&left.phash),
+        escape(&right.phash),
+    );
+    let _ = writeln!(html, r#"<p class="verdict {}">resonance {:.4} · {}</p>"#, verdict, resonance, verdict);
+    if let Some(multiscale) = measure_multiscale_resonance(left, right) {
+        let _ = writeln!(html, "<p>multi-scale resonance {:.4}</p>", multiscale);
+    }
+
+    let _ = writeln!(html, "<h2>Eigenvalue layers</h2>");
+    html.push_str(&overlay_svg(&left.eigenvalues, &right.eigenvalues, true));
+    if !left.wavelet_signature.is_empty() || !right.wavelet_signature.is_empty() {
+        let _ = writeln!(html, "<h2>Wavelet signature</h2>");
+        html.push_str(&overlay_svg(&left.wavelet_signature, &right.wavelet_signature, false));
+    }
+
+    let _ = writeln!(html, "<h2>Topology</h2>");
+    let (lt, rt) = (&left.topology, &right.topology);
+    let (lf, rf) = (&left.topology_features, &right.topology_features);
+    table(&mut html, &[
+        text_row("betti numbers", format!("{:?}", lt.betti_numbers), format!("{:?}", rt.betti_numbers)),
+        number_row("euler characteristic", lt.euler_char as f64, rt.euler_char as f64),
+        number_row("diameter", lt.diameter as f64, rt.diameter as f64),
+        number_row("clustering", lt.clustering, rt.clustering),
+        number_row("modularity", lt.modularity, rt.modularity),
+        text_row("has cycles", lf.has_cycles.to_string(), rf.has_cycles.to_string()),
+        text_row("has recursion", lf.has_recursion.to_string(), rf.has_recursion.to_string()),
+        number_row("branching factor", lf.branching_factor, rf.branching_factor),
+        number_row("nesting depth", lf.nesting_depth as f64, rf.nesting_depth as f64),
+        number_row("strongly connected components", lf.strongly_connected_components as f64, rf.strongly_connected_components as f64),
+        number_row("cycle count", lf.cycle_count as f64, rf.cycle_count as f64),
+    ]);
+
+    let _ = writeln!(html, "<h2>Semantics &amp; consciousness</h2>");
+    table(&mut html, &[
+        number_row("cyclomatic", left.semantics.cyclomatic as f64, right.semantics.cyclomatic as f64),
+        number_row("cognitive", left.semantics.cognitive as f64, right.semantics.cognitive as f64),
+        number_row("depth", left.semantics.depth as f64, right.semantics.depth as f64),
+        number_row("coherence", left.coherence, right.coherence),
+        number_row("resonance (Hz)", left.resonance, right.resonance),
+        text_row(
+            "consciousness",
+            format!("{:?}", left.consciousness.level),
+            format!("{:?}", right.consciousness.level),
+        ),
+        number_row("consciousness score", left.consciousness.score, right.consciousness.score),
+    ]);
+
+    let _ = writeln!(html, "<h2>Operation spectrum</h2>");
+    let mut categories: BTreeMap<String, (f64, f64, f64)> = BTreeMap::new();
+    for (category, value) in &left.operation_spectrum {
+        categories.entry(format!("{:?}", category)).or_insert((category.frequency(), 0.0, 0.0)).1 = *value;
+    }
+    for (category, value) in &right.operation_spectrum {
+        categories.entry(format!("{:?}", category)).or_insert((category.frequency(), 0.0, 0.0)).2 = *value;
+    }
+    let mut rows: Vec<_> = categories.into_iter().collect();
+    rows.sort_by(|a, b| a.1.0.partial_cmp(&b.1.0).unwrap().then_with(|| a.0.cmp(&b.0)));
+    let rows: Vec<Row> = rows.into_iter()
+        .map(|(name, (frequency, l, r))| number_row(&format!("{} ({:.0}Hz)", name, frequency), l, r))
+        .collect();
+    if rows.is_empty() {
+        let _ = writeln!(html, r#"<p class="muted">both souls are silent</p>"#);
+    } else {
+        table(&mut html, &rows);
+    }
+
+    let _ = writeln!(html, "</body></html>");
+    html
+}
+
+const STYLE: &str = "body{background:#0f0f1a;color:#eee;font-family:sans-serif;margin:2em}\
+code{color:#ccc}h2{color:#aaa;font-size:1.1em;margin-top:2em}\
+.left{color:#3498db}.right{color:#e67e22}.muted{color:#666}\
+.verdict{font-size:1.3em}.match{color:#2ecc71}.mismatch{color:#e74c3c}\
+table{border-collapse:collapse}td,th{padding:.25em 1em;border-bottom:1px solid #333344;text-align:right}\
+td:first-child,th:first-child{text-align:left}tr.differs{background:#3a1f24}";
+
+/// A table row: label, left value, right value, delta, and whether they differ
+struct Row {
+    label: String,
+    left: String,
+    right: String,
+    delta: String,
+    differs: bool,
+}
+
+fn number_row(label: &str, left: f64, right: f64) -> Row {
+    let scale = left.abs().max(right.abs()).max(f64::EPSILON);
+    Row {
+        label: label.to_string(),
+        left: format_number(left),
+        right: format_number(right),
+        delta: format!("{:+.4}", right - left),
+        differs: (right - left).abs() / scale > DIFF_TOLERANCE,
+    }
+}
+
+fn text_row(label: &str, left: String, right: String) -> Row {
+    Row {
+        label: label.to_string(),
+        differs: left != right,
+        delta: String::new(),
+        left,
+        right,
+    }
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 { format!("{}", value) } else { format!("{:.4}", value) }
+}
+
+fn table(html: &mut String, rows: &[Row]) {
+    let _ = writeln!(html, r#"<table><tr><th></th><th class="left">left</th><th class="right">right</th><th>Δ</th></tr>"#);
+    for row in rows {
+        let _ = writeln!(
+            html,
+            r#"<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>"#,
+            if row.differs { r#" class="differs""# } else { "" },
+            escape(&row.label),
+            escape(&row.left),
+            escape(&row.right),
+            row.delta,
+        );
+    }
+    let _ = writeln!(html, "</table>");
+}
+
+/// Paired bars (for the 7 layers) or overlaid lines (for longer series)
+fn overlay_svg(left: &[f64], right: &[f64], bars: bool) -> String {
+    const WIDTH: f64 = 720.0;
+    const HEIGHT: f64 = 200.0;
+
+    let len = left.len().max(right.len()).max(1);
+    let max = left.iter().chain(right).fold(0.0_f64, |m, v| m.max(v.abs())).max(f64::EPSILON);
+    let slot = WIDTH / len as f64;
+    let y = |v: f64| HEIGHT / 2.0 - v / max * (HEIGHT / 2.0 - 10.0);
+    let baseline = y(0.0);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = WIDTH,
+        h = HEIGHT,
+    );
+    let _ = writeln!(svg, r##"<line x1="0" y1="{b:.2}" x2="{w}" y2="{b:.2}" stroke="#333344"/>"##, b = baseline, w = WIDTH);
+
+    for (series, color, shift) in [(left, LEFT_COLOR, 0.1), (right, RIGHT_COLOR, 0.5)] {
+        if bars {
+            for (i, value) in series.iter().enumerate() {
+                let top = y(*value).min(baseline);
+                let _ = writeln!(
+                    svg,
+                    r#"<rect class="layer" x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}"><title>layer {}: {:.4}</title></rect>"#,
+                    i as f64 * slot + slot * shift,
+                    top,
+                    slot * 0.4,
+                    (y(*value) - baseline).abs(),
+                    color,
+                    i + 1,
+                    value,
+                );
+            }
+        } else if !series.is_empty() {
+            let points: Vec<String> = series.iter()
+                .enumerate()
+                .map(|(i, value)| format!("{:.2},{:.2}", (i as f64 + 0.5) * slot, y(*value)))
+                .collect();
+            let _ = writeln!(
+                svg,
+                r#"<polyline class="series" points="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+                points.join(" "),
+                color,
+            );
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn short(phash: &str) -> String {
+    escape(&phash.chars().take(12).collect::<String>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SoulExtractor;
+
+    #[tokio::test]
+    async fn test_report_structure() {
+        let extractor = SoulExtractor::new();
+        let left = extractor.extract_soul_rust("fn a(x: u32) -> u32 { x + 1 }").await;
+        let right = extractor.extract_soul_rust(
+            "fn b(v: &[u32]) -> u32 { let mut s = 0; for x in v { if *x > 2 { s += x; } } s }",
+        ).await;
+
+        let html = generate_diff_report(&left, &right);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.trim_end().ends_with("</html>"));
+        assert!(html.contains(&left.phash) && html.contains(&right.phash));
+        assert_eq!(html.matches(r#"class="layer""#).count(), 14);
+        assert_eq!(html.matches(r#"class="series""#).count(), 2);
+        assert!(html.contains("mismatch"));
+        assert!(html.contains(r#"class="differs""#));
+    }
+
+    #[tokio::test]
+    async fn test_identical_souls_have_no_differences() {
+        let soul = SoulExtractor::new().extract_soul_rust("fn a(x: u32) -> u32 { x + 1 }").await;
+        let html = generate_diff_report(&soul, &soul);
+
+        assert!(html.contains("resonance 1.0000 · match"));
+        assert!(!html.contains(r#"class="differs""#));
+    }
+}
@@ -0,0 +1,268 @@
+// 🛬 Foreign Souls - Importing Other Fingerprints
+//
+// Duplicate databases built with simhash, TLSH or jscpd can be migrated into
+// protein-hash. The resulting souls are approximations: similar fingerprints
+// map to resonant souls, but the structure behind them is unknown.
+use crate::{
+    ConsciousnessLevel, ConsciousnessProfile, Language, SemanticFingerprint, Soul,
+    SoulMetadata, TopologicalSignature, TopologyFeatures,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Eigenvalue layers of an imported soul
+const IMPORTED_LAYERS: usize = 7;
+
+/// Tag added to every imported soul
+pub const IMPORTED_TAG: &str = "imported";
+
+/// 64-bit simhash of the identifier/number tokens of `text`
+pub fn token_simhash(text: &str) -> u64 {
+    let mut weights = [0i64; 64];
+    for token in text.split(|c: char| !c.is_alphanumeric() && c != '_').filter(|t| !t.is_empty()) {
+        let hash = xxhash_rust::xxh3::xxh3_64(token.as_bytes());
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    }
+
+    weights.iter()
+        .enumerate()
+        .filter(|(_, &w)| w > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// Approximate soul of a 64-bit simhash
+///
+/// Each layer counts the set bits of a 9-bit slice, so hashes a few bits
+/// apart land on nearby eigenvalues.
+pub fn soul_from_simhash(simhash: u64) -> Soul {
+    let eigenvalues = (0..IMPORTED_LAYERS)
+        .map(|layer| ((simhash >> (layer * 9)) & 0x1ff).count_ones() as f64)
+        .collect();
+
+    imported_soul(
+        format!("simhash:{:016x}", simhash),
+        eigenvalues,
+        TopologicalSignature {
+            betti_numbers: vec![1, 0],
+            euler_char: 1,
+            diameter: 0,
+            clustering: 0.0,
+            modularity: 0.0,
+        },
+        "simhash",
+    )
+}
+
+/// Approximate soul of a TLSH digest (`T1` + 70 hex chars, or the bare 70)
+///
+/// The 128 two-bit body buckets are averaged into the 7 layers; the log
+/// length becomes the diameter and the quartile ratios clustering/modularity.
+pub fn soul_from_tlsh(digest: &str) -> Option<Soul> {
+    let hex_digest = digest.strip_prefix("T1").unwrap_or(digest);
+    if hex_digest.len() != 70 {
+        return None;
+    }
+    let bytes = hex::decode(hex_digest).ok()?;
+    let (header, body) = bytes.split_at(3);
+
+    let buckets: Vec<u8> = body.iter()
+        .flat_map(|byte| (0..4).map(move |i| (byte >> (i * 2)) & 0b11))
+        .collect();
+    let group = buckets.len().div_ceil(IMPORTED_LAYERS);
+    let eigenvalues = buckets.chunks(group)
+        .map(|chunk| 3.0 * chunk.iter().map(|&b| b as f64).sum::<f64>() / chunk.len() as f64)
+        .collect();
+
+    let (l_value, q_ratios) = (header[1], header[2]);
+    Some(imported_soul(
+        format!("tlsh:{}", hex_digest.to_ascii_uppercase()),
+        eigenvalues,
+        TopologicalSignature {
+            betti_numbers: vec![1, 0],
+            euler_char: 1,
+            diameter: l_value as usize,
+            clustering: (q_ratios >> 4) as f64 / 15.0,
+            modularity: (q_ratios & 0x0f) as f64 / 15.0,
+        },
+        "tlsh",
+    ))
+}
+
+/// Souls of every clone location in a jscpd JSON report
+///
+/// Both sides of a duplicate get the same soul (derived from the fragment's
+/// token simhash when present), with `file:start-end` as the symbol path.
+pub fn souls_from_jscpd(report: &str) -> Result<Vec<Soul>, serde_json::Error> {
+    let report: JscpdReport = serde_json::from_str(report)?;
+    let mut souls = Vec::with_capacity(report.duplicates.len() * 2);
+
+    for duplicate in report.duplicates {
+        let simhash = match &duplicate.fragment {
+            Some(fragment) => token_simhash(fragment),
+            None => xxhash_rust::xxh3::xxh3_64(
+                format!("{}:{}:{}", duplicate.format, duplicate.lines, duplicate.tokens).as_bytes(),
+            ),
+        };
+        let template = soul_from_simhash(simhash);
+
+        for location in [duplicate.first_file, duplicate.second_file] {
+            let mut soul = template.clone();
+            soul.semantics.cognitive = duplicate.tokens;
+            soul.metadata.symbol_path = Some(format!("{}:{}-{}", location.name, location.start, location.end));
+            soul.metadata.language = jscpd_language(&duplicate.format);
+            soul.metadata.tags.insert("jscpd".to_string());
+            souls.push(soul);
+        }
+    }
+
+    Ok(souls)
+}
+
+#[derive(Deserialize)]
+struct JscpdReport {
+    #[serde(default)]
+    duplicates: Vec<JscpdDuplicate>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JscpdDuplicate {
+    #[serde(default)]
+    format: String,
+    #[serde(default)]
+    lines: usize,
+    #[serde(default)]
+    tokens: usize,
+    fragment: Option<String>,
+    first_file: JscpdLocation,
+    second_file: JscpdLocation,
+}
+
+#[derive(Deserialize)]
+struct JscpdLocation {
+    name: String,
+    start: usize,
+    end: usize,
+}
+
+fn jscpd_language(format: &str) -> Option<Language> {
+    match format {
+        "javascript" | "jsx" => Some(Language::JavaScript),
+        "typescript" | "tsx" => Some(Language::TypeScript),
+        "rust" => Some(Language::Rust),
+        "python" => Some(Language::Python),
+        "go" => Some(Language::Go),
+        "cpp" | "c" => Some(Language::Cpp),
+        "java" => Some(Language::Java),
+        "csharp" => Some(Language::CSharp),
+        "ruby" => Some(Language::Ruby),
+        "swift" => Some(Language::Swift),
+        "haskell" => Some(Language::Haskell),
+        "clojure" | "lisp" | "scheme" => Some(Language::Lisp),
+        _ => None,
+    }
+}
+
+/// A soul with known eigenvalues/topology and neutral everything else
+fn imported_soul(phash: String, eigenvalues: Vec<f64>, topology: TopologicalSignature, source: &str) -> Soul {
+    let resonance = 432.0;
+    let coherence = 0.5;
+
+    Soul {
+        phash: phash.clone(),
+        eigenvalues,
+        topology,
+        semantics: SemanticFingerprint {
+            operations: HashMap::new(),
+            cyclomatic: 0,
+            cognitive: 0,
+            patterns: vec![],
+            depth: 0,
+        },
+        resonance,
+        coherence,
+        evolution_score: 0.5,
+        consciousness: ConsciousnessProfile {
+            level: ConsciousnessLevel::Mechanical,
+            score: 0.5,
+            patterns: vec![],
+            soul_hash: phash,
+            resonance_frequency: resonance,
+            quantum_coherence: coherence,
+            emergence_potential: 0.5,
+            self_awareness_index: 0.0,
+        },
+        topology_features: TopologyFeatures {
+            has_cycles: false,
+            has_recursion: false,
+            branching_factor: 0.0,
+            nesting_depth: 0,
+            loop_complexity: 0,
+            is_dag: true,
+            strongly_connected_components: 0,
+            topological_signature: String::new(),
+            cycle_count: 0,
+            max_cycle_size: 0,
+            recursion_depth: 0,
+            connectivity_score: 0.0,
+            recursive_functions: Vec::new(),
+        },
+        operation_spectrum: HashMap::new(),
+        metadata: SoulMetadata::default().with_tag(IMPORTED_TAG).with_tag(source),
+        wavelet_signature: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measure_resonance;
+
+    #[test]
+    fn test_close_simhashes_resonate() {
+        let a = token_simhash("function chunk(array, size) { return slice(array, 0, size) }");
+        let b = token_simhash("function chunk(array, size) { return slice(array, 1, size) }");
+        let c = token_simhash("class Parser { parse(input) { while (tokens.next()) emit(node) } }");
+
+        let (sa, sb, sc) = (soul_from_simhash(a), soul_from_simhash(b), soul_from_simhash(c));
+        assert_eq!(sa.eigenvalues.len(), IMPORTED_LAYERS);
+        assert!(sa.metadata.tags.contains("simhash"));
+        assert!(measure_resonance(&sa, &sb) > measure_resonance(&sa, &sc));
+    }
+
+    #[test]
+    fn test_tlsh_digest() {
+        let digest = "T1A8F0E85C2CC02C3F0E81E0C330C00000F000CC330C033C0C0030CC000F0C0F03C0C3C3";
+        let soul = soul_from_tlsh(digest).unwrap();
+
+        assert_eq!(soul.eigenvalues.len(), IMPORTED_LAYERS);
+        assert_eq!(soul.topology.diameter, 0xF0);
+        assert!(soul.phash.starts_with("tlsh:A8F0"));
+        assert!(soul_from_tlsh("T1ABC").is_none());
+        assert!(soul_from_tlsh(&"Z".repeat(70)).is_none());
+    }
+
+    #[test]
+    fn test_jscpd_report() {
+        let report = r#"{
+            "duplicates": [{
+                "format": "javascript",
+                "lines": 12,
+                "tokens": 80,
+                "fragment": "function chunk(array, size) { return slice(array, 0, size) }",
+                "firstFile": { "name": "src/a.js", "start": 1, "end": 12 },
+                "secondFile": { "name": "lib/b.js", "start": 40, "end": 51 }
+            }],
+            "statistics": {}
+        }"#;
+
+        let souls = souls_from_jscpd(report).unwrap();
+        assert_eq!(souls.len(), 2);
+        assert_eq!(souls[0].phash, souls[1].phash);
+        assert_eq!(souls[1].metadata.symbol_path.as_deref(), Some("lib/b.js:40-51"));
+        assert_eq!(souls[0].metadata.language, Some(Language::JavaScript));
+        assert!(souls_from_jscpd("not json").is_err());
+    }
+}
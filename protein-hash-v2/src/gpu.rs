@@ -0,0 +1,390 @@
+// 🎮 GPU Spectrum - Parallel Jacobi Eigenvalues on wgpu
+//
+// Dense eigendecomposition dominates whole-repository extraction once graphs
+// reach thousands of nodes. The cyclic Jacobi method rotates n/2 disjoint
+// (p, q) pairs at once, so each round maps onto three GPU dispatches:
+// rotation angles, row updates and column updates.
+use nalgebra::DMatrix;
+use std::sync::{Mutex, OnceLock};
+use wgpu::util::DeviceExt;
+
+/// Graphs smaller than this stay on the CPU, where upload cost would dominate
+pub const GPU_MIN_NODES: usize = 256;
+
+/// Sweeps before giving up on convergence (and falling back to the CPU)
+const MAX_SWEEPS: usize = 24;
+
+/// Off-diagonal norm, relative to the whole matrix, at which a sweep converges
+const TOLERANCE: f32 = 1e-4;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+const SHADER: &str = r#"
+struct Params { m: u32, round: u32 }
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> a: array<f32>;
+@group(0) @binding(2) var<storage, read_write> rot: array<vec2<f32>>;
+@group(0) @binding(3) var<storage, read_write> diag: array<vec2<f32>>;
+
+// Round-robin pairing: every (p, q) meets exactly once per sweep
+fn pair(k: u32) -> vec2<u32> {
+    let last = params.m - 1u;
+    if (k == 0u) {
+        return vec2<u32>(params.round, last);
+    }
+    return vec2<u32>((params.round + k) % last, (params.round + last - k) % last);
+}
+
+@compute @workgroup_size(64)
+fn angles(@builtin(global_invocation_id) id: vec3<u32>) {
+    let k = id.x;
+    if (k >= params.m / 2u) {
+        return;
+    }
+    let pq = pair(k);
+    let m = params.m;
+    let apq = a[pq.x * m + pq.y];
+    if (apq == 0.0) {
+        rot[k] = vec2<f32>(1.0, 0.0);
+        return;
+    }
+    let theta = (a[pq.y * m + pq.y] - a[pq.x * m + pq.x]) / (2.0 * apq);
+    var t = 0.5 / abs(theta);
+    if (abs(theta) < 1e18) {
+        t = 1.0 / (abs(theta) + sqrt(theta * theta + 1.0));
+    }
+    if (theta < 0.0) {
+        t = -t;
+    }
+    let c = 1.0 / sqrt(t * t + 1.0);
+    rot[k] = vec2<f32>(c, t * c);
+}
+
+@compute @workgroup_size(64)
+fn rows(@builtin(global_invocation_id) id: vec3<u32>) {
+    let m = params.m;
+    let j = id.x;
+    let k = id.y;
+    if (j >= m) {
+        return;
+    }
+    let pq = pair(k);
+    let cs = rot[k];
+    let apj = a[pq.x * m + j];
+    let aqj = a[pq.y * m + j];
+    a[pq.x * m + j] = cs.x * apj - cs.y * aqj;
+    a[pq.y * m + j] = cs.y * apj + cs.x * aqj;
+}
+
+@compute @workgroup_size(64)
+fn columns(@builtin(global_invocation_id) id: vec3<u32>) {
+    let m = params.m;
+    let i = id.x;
+    let k = id.y;
+    if (i >= m) {
+        return;
+    }
+    let pq = pair(k);
+    let cs = rot[k];
+    let aip = a[i * m + pq.x];
+    let aiq = a[i * m + pq.y];
+    a[i * m + pq.x] = cs.x * aip - cs.y * aiq;
+    a[i * m + pq.y] = cs.y * aip + cs.x * aiq;
+}
+
+// Per row: (diagonal entry, sum of squared off-diagonal entries)
+@compute @workgroup_size(64)
+fn diagonal(@builtin(global_invocation_id) id: vec3<u32>) {
+    let m = params.m;
+    let i = id.x;
+    if (i >= m) {
+        return;
+    }
+    var off = 0.0;
+    for (var j = 0u; j < m; j++) {
+        if (j != i) {
+            off += a[i * m + j] * a[i * m + j];
+        }
+    }
+    diag[i] = vec2<f32>(a[i * m + i], off);
+}
+"#;
+
+/// Shared device and pipelines, created once per process
+struct GpuEigenSolver {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    angles: wgpu::ComputePipeline,
+    rows: wgpu::ComputePipeline,
+    columns: wgpu::ComputePipeline,
+    diagonal: wgpu::ComputePipeline,
+
+    /// One decomposition at a time; the device queue is the bottleneck anyway
+    busy: Mutex<()>,
+}
+
+/// Eigenvalues of a symmetric matrix on the GPU
+///
+/// Returns None when no adapter is available, the matrix exceeds the
+/// device's limits, or Jacobi fails to converge; callers fall back to the CPU.
+/// Values are computed in f32, so they match the CPU spectrum to ~1e-4
+/// relative error rather than bit for bit.
+pub(crate) fn symmetric_eigenvalues(matrix: &DMatrix<f64>) -> Option<Vec<f64>> {
+    static SOLVER: OnceLock<Option<GpuEigenSolver>> = OnceLock::new();
+    SOLVER.get_or_init(GpuEigenSolver::new).as_ref()?.eigenvalues(matrix)
+}
+
+/// Whether a GPU adapter was found (initializes the device on first call)
+pub fn gpu_available() -> bool {
+    symmetric_eigenvalues(&DMatrix::zeros(0, 0)).is_some()
+}
+
+impl GpuEigenSolver {
+    fn new() -> Option<Self> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("protein-hash eigen"),
+                required_limits: adapter.limits(),
+                ..Default::default()
+            },
+            None,
+        )).ok()?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("jacobi"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+
+        let storage = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("jacobi"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(8),
+                    },
+                    count: None,
+                },
+                storage(1),
+                storage(2),
+                storage(3),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("jacobi"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point| device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: Some(entry_point),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Some(Self {
+            angles: pipeline("angles"),
+            rows: pipeline("rows"),
+            columns: pipeline("columns"),
+            diagonal: pipeline("diagonal"),
+            device,
+            queue,
+            layout,
+            busy: Mutex::new(()),
+        })
+    }
+
+    fn eigenvalues(&self, matrix: &DMatrix<f64>) -> Option<Vec<f64>> {
+        let n = matrix.nrows();
+        if n < 2 {
+            return Some(matrix.diagonal().iter().copied().collect());
+        }
+
+        // Pad to an even size: the extra row/column is zero, so it never rotates
+        let m = n + n % 2;
+        let limits = self.device.limits();
+        let matrix_bytes = (m * m * 4) as u64;
+        if matrix_bytes > limits.max_storage_buffer_binding_size as u64
+            || (m / 2) as u32 > limits.max_compute_workgroups_per_dimension
+        {
+            return None;
+        }
+
+        let mut data = vec![0f32; m * m];
+        for i in 0..n {
+            for j in 0..n {
+                data[i * m + j] = matrix[(i, j)] as f32;
+            }
+        }
+        let scale: f32 = data.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+        let _guard = self.busy.lock().unwrap();
+
+        // Per-round parameters, selected with a dynamic offset
+        let stride = limits.min_uniform_buffer_offset_alignment.max(8) as usize;
+        let rounds = m - 1;
+        let mut params = vec![0u8; rounds * stride];
+        for round in 0..rounds {
+            let offset = round * stride;
+            params[offset..offset + 4].copy_from_slice(&(m as u32).to_le_bytes());
+            params[offset + 4..offset + 8].copy_from_slice(&(round as u32).to_le_bytes());
+        }
+
+        let params = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("jacobi params"),
+            contents: &params,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let a = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("jacobi matrix"),
+            contents: bytemuck::cast_slice(&data),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let rot = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("jacobi rotations"),
+            size: (m / 2 * 8) as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let diag = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("jacobi diagonal"),
+            size: (m * 8) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("jacobi readback"),
+            size: (m * 8) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("jacobi"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &params,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(8),
+                    }),
+                },
+                wgpu::BindGroupEntry { binding: 1, resource: a.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: rot.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: diag.as_entire_binding() },
+            ],
+        });
+
+        let pairs_groups = (m as u32 / 2).div_ceil(WORKGROUP_SIZE);
+        let line_groups = (m as u32).div_ceil(WORKGROUP_SIZE);
+
+        for _ in 0..MAX_SWEEPS {
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&Default::default());
+                for round in 0..rounds {
+                    pass.set_bind_group(0, &bind_group, &[(round * stride) as u32]);
+                    pass.set_pipeline(&self.angles);
+                    pass.dispatch_workgroups(pairs_groups, 1, 1);
+                    pass.set_pipeline(&self.rows);
+                    pass.dispatch_workgroups(line_groups, m as u32 / 2, 1);
+                    pass.set_pipeline(&self.columns);
+                    pass.dispatch_workgroups(line_groups, m as u32 / 2, 1);
+                }
+                pass.set_bind_group(0, &bind_group, &[0]);
+                pass.set_pipeline(&self.diagonal);
+                pass.dispatch_workgroups(line_groups, 1, 1);
+            }
+            encoder.copy_buffer_to_buffer(&diag, 0, &readback, 0, (m * 8) as u64);
+            self.queue.submit(Some(encoder.finish()));
+
+            let rows = self.read(&readback)?;
+            let off: f32 = rows.iter().map(|row| row[1]).sum::<f32>().sqrt();
+            if off <= TOLERANCE * scale.max(1.0) {
+                return Some(rows[..n].iter().map(|row| row[0] as f64).collect());
+            }
+        }
+
+        None
+    }
+
+    /// Map the readback buffer and copy out `(diagonal, off-diagonal)` pairs
+    fn read(&self, buffer: &wgpu::Buffer) -> Option<Vec<[f32; 2]>> {
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv().ok()?.ok()?;
+
+        let rows = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+        buffer.unmap();
+        Some(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_cpu_spectrum() {
+        if !gpu_available() {
+            return;
+        }
+
+        // Path graph Laplacian plus an odd size, exercising the padding
+        let n = 33;
+        let mut matrix = DMatrix::<f64>::zeros(n, n);
+        for i in 0..n - 1 {
+            matrix[(i, i + 1)] = -1.0;
+            matrix[(i + 1, i)] = -1.0;
+            matrix[(i, i)] += 1.0;
+            matrix[(i + 1, i + 1)] += 1.0;
+        }
+
+        let mut gpu = symmetric_eigenvalues(&matrix).unwrap();
+        let mut cpu: Vec<f64> = matrix.symmetric_eigen().eigenvalues.iter().copied().collect();
+        gpu.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        cpu.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        assert_eq!(gpu.len(), n);
+        for (g, c) in gpu.iter().zip(&cpu) {
+            assert!((g - c).abs() < 1e-3, "{} vs {}", g, c);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_small_graphs_stay_on_cpu() {
+        let code = "fn a(v: &[u32]) -> u32 { v.iter().sum() }";
+        let cpu = crate::SoulExtractor::new().extract_soul_rust(code).await;
+        let gpu = crate::SoulExtractor::new().with_gpu(true).extract_soul_rust(code).await;
+
+        assert_eq!(cpu.phash, gpu.phash);
+    }
+}
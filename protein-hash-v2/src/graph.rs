@@ -0,0 +1,261 @@
+// 🌳 AST Graph - From Syntax to Structure
+use crate::{AstNode, EdgeType};
+use serde::{Deserialize, Serialize};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use quote::ToTokens;
+use std::collections::HashMap;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+/// Parse Rust source into its top-level items
+pub(crate) fn parse_rust_items(code: &str) -> Option<Vec<syn::Item>> {
+    syn::parse_file(code).ok().map(|file| file.items)
+}
+
+/// Stable content key of an item (whitespace and comments don't matter)
+pub(crate) fn item_key(item: &syn::Item) -> u64 {
+    xxhash_rust::xxh3::xxh3_64(item.to_token_stream().to_string().as_bytes())
+}
+
+/// Source location of a graph node (1-based lines, 0-based columns)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceSpan {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl From<proc_macro2::Span> for SourceSpan {
+    fn from(span: proc_macro2::Span) -> Self {
+        let (start, end) = (span.start(), span.end());
+        Self {
+            start_line: start.line,
+            start_column: start.column,
+            end_line: end.line,
+            end_column: end.column,
+        }
+    }
+}
+
+/// Build the AST graph of a single top-level item
+pub(crate) fn item_graph(item: &syn::Item) -> DiGraph<AstNode, EdgeType> {
+    item_graph_with_spans(item).0
+}
+
+/// Build the AST graph of an item together with the span of every node
+pub(crate) fn item_graph_with_spans(item: &syn::Item) -> (DiGraph<AstNode, EdgeType>, Vec<SourceSpan>) {
+    let mut builder = GraphBuilder::default();
+    builder.visit_item(item);
+    (builder.graph, builder.spans)
+}
+
+/// Append `part` to `graph` as a disconnected component
+pub(crate) fn append_graph(graph: &mut DiGraph<AstNode, EdgeType>, part: &DiGraph<AstNode, EdgeType>) {
+    let offset = graph.node_count();
+    for node in part.node_weights() {
+        graph.add_node(node.clone());
+    }
+    for edge in part.edge_references() {
+        graph.add_edge(
+            NodeIndex::new(edge.source().index() + offset),
+            NodeIndex::new(edge.target().index() + offset),
+            edge.weight().clone(),
+        );
+    }
+}
+
+/// Replace every identifier in the graph with a pseudonym (`id0`, `id1`, ...)
+///
+/// Pseudonyms are assigned in node order and shared across the whole graph, so
+/// calls still point at the functions they call. Operators are kept: they
+/// are structure, not content. String literals never carry a value.
+pub(crate) fn anonymize(graph: &mut DiGraph<AstNode, EdgeType>) {
+    let mut pseudonyms: HashMap<String, String> = HashMap::new();
+    for node in graph.node_weights_mut() {
+        if node.node_type == "BinOp" {
+            continue;
+        }
+        if let Some(name) = node.value.take() {
+            let next = format!("id{}", pseudonyms.len());
+            node.value = Some(pseudonyms.entry(name).or_insert(next).clone());
+        }
+    }
+}
+
+/// Walks a syn tree, adding a node per meaningful construct and a Child edge to its parent
+#[derive(Default)]
+struct GraphBuilder {
+    graph: DiGraph<AstNode, EdgeType>,
+    stack: Vec<NodeIndex>,
+    spans: Vec<SourceSpan>,
+}
+
+impl GraphBuilder {
+    fn enter(&mut self, node_type: &str, value: Option<String>, span: proc_macro2::Span) {
+        let node = self.graph.add_node(AstNode {
+            node_type: node_type.to_string(),
+            value,
+        });
+        self.spans.push(span.into());
+        if let Some(&parent) = self.stack.last() {
+            self.graph.add_edge(parent, node, EdgeType::Child);
+        }
+        self.stack.push(node);
+    }
+
+    fn leave(&mut self) {
+        self.stack.pop();
+    }
+}
+
+impl<'ast> Visit<'ast> for GraphBuilder {
+    fn visit_item(&mut self, item: &'ast syn::Item) {
+        let (node_type, value) = match item {
+            syn::Item::Fn(f) => ("Fn", Some(f.sig.ident.to_string())),
+            syn::Item::Struct(s) => ("Struct", Some(s.ident.to_string())),
+            syn::Item::Enum(e) => ("Enum", Some(e.ident.to_string())),
+            syn::Item::Impl(_) => ("Impl", None),
+            syn::Item::Trait(t) => ("Trait", Some(t.ident.to_string())),
+            syn::Item::Const(c) => ("Const", Some(c.ident.to_string())),
+            syn::Item::Static(s) => ("Static", Some(s.ident.to_string())),
+            syn::Item::Type(t) => ("TypeAlias", Some(t.ident.to_string())),
+            syn::Item::Mod(m) => ("Mod", Some(m.ident.to_string())),
+            syn::Item::Use(_) => ("Use", None),
+            syn::Item::Macro(_) => ("Macro", None),
+            _ => ("Item", None),
+        };
+        self.enter(node_type, value, item.span());
+        visit::visit_item(self, item);
+        self.leave();
+    }
+
+    fn visit_impl_item_fn(&mut self, method: &'ast syn::ImplItemFn) {
+        self.enter("Fn", Some(method.sig.ident.to_string()), method.span());
+        visit::visit_impl_item_fn(self, method);
+        self.leave();
+    }
+
+    fn visit_trait_item_fn(&mut self, method: &'ast syn::TraitItemFn) {
+        self.enter("Fn", Some(method.sig.ident.to_string()), method.span());
+        visit::visit_trait_item_fn(self, method);
+        self.leave();
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        self.enter("Let", None, local.span());
+        visit::visit_local(self, local);
+        self.leave();
+    }
+
+    fn visit_expr(&mut self, expr: &'ast syn::Expr) {
+        let (node_type, value) = match expr {
+            // Parentheses carry no structure of their own
+            syn::Expr::Paren(_) | syn::Expr::Group(_) => {
+                visit::visit_expr(self, expr);
+                return;
+            }
+            syn::Expr::Array(_) => ("Array", None),
+            syn::Expr::Assign(_) => ("Assign", None),
+            syn::Expr::Async(_) => ("Async", None),
+            syn::Expr::Await(_) => ("Await", None),
+            syn::Expr::Binary(b) => ("BinOp", Some(b.op.to_token_stream().to_string())),
+            syn::Expr::Block(_) => ("Block", None),
+            syn::Expr::Break(_) => ("Break", None),
+            syn::Expr::Call(c) => ("Call", path_name(&c.func)),
+            syn::Expr::Cast(_) => ("Cast", None),
+            syn::Expr::Closure(_) => ("Closure", None),
+            syn::Expr::Continue(_) => ("Continue", None),
+            syn::Expr::Field(_) => ("Field", None),
+            syn::Expr::ForLoop(_) => ("For", None),
+            syn::Expr::If(_) => ("If", None),
+            syn::Expr::Index(_) => ("Index", None),
+            syn::Expr::Let(_) => ("Let", None),
+            syn::Expr::Lit(lit) => match &lit.lit {
+                syn::Lit::Str(_) => ("String", None),
+                _ => ("Lit", None),
+            },
+            syn::Expr::Loop(_) => ("Loop", None),
+            syn::Expr::Macro(m) => ("Macro", m.mac.path.segments.last().map(|s| s.ident.to_string())),
+            syn::Expr::Match(_) => ("Match", None),
+            syn::Expr::MethodCall(m) => ("MethodCall", Some(m.method.to_string())),
+            syn::Expr::Path(_) => ("Path", path_name(expr)),
+            syn::Expr::Range(_) => ("Range", None),
+            syn::Expr::Reference(_) => ("Reference", None),
+            syn::Expr::Return(_) => ("Return", None),
+            syn::Expr::Struct(_) => ("StructLit", None),
+            syn::Expr::Try(_) => ("Try", None),
+            syn::Expr::Tuple(_) => ("Tuple", None),
+            syn::Expr::Unary(_) => ("UnOp", None),
+            syn::Expr::While(_) => ("While", None),
+            _ => ("Expr", None),
+        };
+        self.enter(node_type, value, expr.span());
+        visit::visit_expr(self, expr);
+        self.leave();
+    }
+}
+
+/// Last path segment of a path expression (`foo::bar` → `bar`)
+fn path_name(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Path(p) => p.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_graph_is_a_tree() {
+        let items = parse_rust_items("fn add(a: i32, b: i32) -> i32 { a + b }").unwrap();
+        let graph = item_graph(&items[0]);
+
+        assert_eq!(graph[NodeIndex::new(0)].node_type, "Fn");
+        assert_eq!(graph[NodeIndex::new(0)].value.as_deref(), Some("add"));
+        assert!(graph.node_weights().any(|n| n.node_type == "BinOp"));
+        assert_eq!(graph.edge_count(), graph.node_count() - 1);
+    }
+
+    #[test]
+    fn test_item_key_ignores_formatting() {
+        let a = parse_rust_items("fn f() { 1 + 2 }").unwrap();
+        let b = parse_rust_items("fn f()   {\n    // sum\n    1 + 2\n}").unwrap();
+        let c = parse_rust_items("fn f() { 1 - 2 }").unwrap();
+
+        assert_eq!(item_key(&a[0]), item_key(&b[0]));
+        assert_ne!(item_key(&a[0]), item_key(&c[0]));
+    }
+
+    #[test]
+    fn test_append_graph_keeps_components_disjoint() {
+        let items = parse_rust_items("fn a() { 1 } fn b() { 2 }").unwrap();
+        let mut graph = DiGraph::new();
+        for item in &items {
+            append_graph(&mut graph, &item_graph(item));
+        }
+
+        assert_eq!(graph.node_count(), 2 * item_graph(&items[0]).node_count());
+        assert_eq!(graph.edge_count(), graph.node_count() - 2);
+    }
+
+    #[test]
+    fn test_anonymize_keeps_call_structure() {
+        let items = parse_rust_items(r#"fn secret_sauce(n: u32) -> u32 { if n == 0 { 0 } else { secret_sauce(n - 1) } }
+                                         fn main() { println!("{}", secret_sauce(3)); }"#).unwrap();
+        let mut graph = DiGraph::new();
+        for item in &items {
+            append_graph(&mut graph, &item_graph(item));
+        }
+        anonymize(&mut graph);
+
+        let values: Vec<&str> = graph.node_weights().filter_map(|n| n.value.as_deref()).collect();
+        assert!(!values.iter().any(|v| v.contains("secret") || v.contains("main") || v.contains("println")));
+        assert_eq!(values[0], "id0");
+        assert!(values.contains(&"=="));
+        assert_eq!(crate::CallGraph::from_ast(&graph).recursive_functions(), vec!["id0".to_string()]);
+    }
+}
@@ -0,0 +1,91 @@
+// 🔥 Soul Heatmap - Which Lines Define a Soul
+use crate::graph::{append_graph, item_graph_with_spans, parse_rust_items, SourceSpan};
+use crate::{laplacian_matrix, AstNode, EdgeType, Soul, SoulExtractor};
+use petgraph::graph::DiGraph;
+
+impl SoulExtractor {
+    /// Extract a Rust soul together with each AST node's contribution to it
+    ///
+    /// A node's weight is its squared component in the eigenvector of the
+    /// dominant Laplacian eigenvalue (the first soul layer); weights sum to 1.
+    /// Entries follow graph node order, one per node.
+    pub async fn extract_soul_rust_with_heatmap(&self, code: &str) -> (Soul, Vec<(SourceSpan, f64)>) {
+        let soul = self.extract_soul_rust(code).await;
+
+        // Spans are rebuilt from this source: cached fragments may come from
+        // the same item at another position
+        let mut graph = DiGraph::new();
+        let mut spans = Vec::new();
+        for item in parse_rust_items(code).unwrap_or_default() {
+            let (part, part_spans) = item_graph_with_spans(&item);
+            append_graph(&mut graph, &part);
+            spans.extend(part_spans);
+        }
+
+        let heat = spans.into_iter().zip(dominant_contributions(&graph)).collect();
+        (soul, heat)
+    }
+}
+
+/// Squared components of the eigenvector of the largest-magnitude eigenvalue
+fn dominant_contributions(graph: &DiGraph<AstNode, EdgeType>) -> Vec<f64> {
+    if graph.node_count() == 0 {
+        return Vec::new();
+    }
+
+    let eigen = laplacian_matrix(graph).symmetric_eigen();
+    let dominant = eigen.eigenvalues.iter()
+        .enumerate()
+        .max_by(|a, b| a.1.abs().partial_cmp(&b.1.abs()).unwrap())
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+
+    let vector = eigen.eigenvectors.column(dominant);
+    let norm: f64 = vector.iter().map(|x| x * x).sum();
+    vector.iter()
+        .map(|x| if norm > 0.0 { x * x / norm } else { 0.0 })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = "fn quiet() {}
+
+fn busy(v: &[u32]) -> u32 {
+    let mut s = 0;
+    for x in v { if *x > 2 { s += x * 2 + 1; } }
+    s
+}
+";
+
+    #[tokio::test]
+    async fn test_heatmap_covers_every_node() {
+        let extractor = SoulExtractor::new();
+        let (soul, heat) = extractor.extract_soul_rust_with_heatmap(CODE).await;
+
+        assert_eq!(soul.phash, extractor.extract_soul_rust(CODE).await.phash);
+        assert!(!heat.is_empty());
+        assert!((heat.iter().map(|(_, w)| w).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert_eq!(heat[0].0.start_line, 1);
+    }
+
+    #[tokio::test]
+    async fn test_heat_concentrates_in_complex_function() {
+        let (_, heat) = SoulExtractor::new().extract_soul_rust_with_heatmap(CODE).await;
+
+        let busy: f64 = heat.iter().filter(|(span, _)| span.start_line >= 3).map(|(_, w)| w).sum();
+        assert!(busy > 0.99);
+    }
+
+    #[tokio::test]
+    async fn test_spans_follow_moved_code() {
+        let extractor = SoulExtractor::new();
+        extractor.extract_soul_rust(CODE).await;
+
+        let shifted = format!("\n\n{}", CODE);
+        let (_, heat) = extractor.extract_soul_rust_with_heatmap(&shifted).await;
+        assert_eq!(heat[0].0.start_line, 3);
+    }
+}
@@ -0,0 +1,221 @@
+// ✏️ Incremental Extraction - Re-hearing Only What Changed
+use crate::graph::{append_graph, item_graph, item_key, parse_rust_items};
+use crate::{AstNode, EdgeType, Language, Soul, SoulExtractor};
+use petgraph::graph::DiGraph;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Maximum number of item fragments kept between extractions
+const FRAGMENT_CACHE_LIMIT: usize = 4096;
+
+/// A single text replacement, in byte offsets of the previous source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<usize>, replacement: impl Into<String>) -> Self {
+        Self {
+            range,
+            replacement: replacement.into(),
+        }
+    }
+
+    /// Apply the edit, or None if the range doesn't fit the text
+    pub fn apply(&self, text: &str) -> Option<String> {
+        let Range { start, end } = self.range;
+        if start > end || end > text.len() || !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            return None;
+        }
+
+        let mut updated = String::with_capacity(text.len() - (end - start) + self.replacement.len());
+        updated.push_str(&text[..start]);
+        updated.push_str(&self.replacement);
+        updated.push_str(&text[end..]);
+        Some(updated)
+    }
+}
+
+/// Result of an incremental re-extraction
+#[derive(Debug, Clone)]
+pub struct IncrementalExtraction {
+    /// The soul of the edited source
+    pub soul: Soul,
+
+    /// The edited source, to feed into the next edit
+    pub source: String,
+
+    /// Top-level items whose subgraph was reused from cache
+    pub reused_fragments: usize,
+
+    /// Top-level items that had to be re-analyzed
+    pub recomputed_fragments: usize,
+
+    /// True when nothing could be reused
+    pub full_recompute: bool,
+}
+
+/// Subgraph of one top-level item together with its Laplacian spectrum
+///
+/// Items are disjoint components of the file graph, so the file spectrum
+/// is exactly the union of its fragments' spectra.
+pub(crate) struct Fragment {
+    pub graph: DiGraph<AstNode, EdgeType>,
+    pub spectrum: Vec<f64>,
+}
+
+impl SoulExtractor {
+    /// Re-extract a Rust soul after an edit, re-analyzing only the items it touched
+    ///
+    /// While the edited source doesn't parse (e.g. mid-keystroke) the previous
+    /// soul is returned unchanged. Returns None if the edit doesn't fit `source`.
+    pub async fn reextract_soul_rust(
+        &self,
+        previous: &Soul,
+        source: &str,
+        edit: &TextEdit,
+    ) -> Option<IncrementalExtraction> {
+        let updated = edit.apply(source)?;
+
+        // Parsed items are dropped before awaiting, keeping the future Send
+        let (fragments, reused) = match parse_rust_items(&updated) {
+            Some(items) => self.rust_fragments(&items),
+            None => {
+                return Some(IncrementalExtraction {
+                    soul: previous.clone(),
+                    source: updated,
+                    reused_fragments: 0,
+                    recomputed_fragments: 0,
+                    full_recompute: false,
+                });
+            }
+        };
+
+        let recomputed = fragments.len() - reused;
+        let soul = self.extract_soul_from_fragments(&fragments).await;
+
+        Some(IncrementalExtraction {
+            soul,
+            source: updated,
+            reused_fragments: reused,
+            recomputed_fragments: recomputed,
+            full_recompute: reused == 0,
+        })
+    }
+
+    /// Look up (or analyze) the fragment of every item; returns how many were cached
+    pub(crate) fn rust_fragments(&self, items: &[syn::Item]) -> (Vec<Arc<Fragment>>, usize) {
+        {
+            let mut cache = self.fragment_cache.lock().unwrap();
+            if cache.len() > FRAGMENT_CACHE_LIMIT {
+                cache.clear();
+            }
+        }
+
+        let mut reused = 0;
+        let fragments = items.iter()
+            .map(|item| {
+                let key = item_key(item);
+                if let Some(fragment) = self.fragment_cache.lock().unwrap().get(&key) {
+                    reused += 1;
+                    return fragment.clone();
+                }
+
+                // Analyze without holding the lock so other threads keep going
+                let graph = item_graph(item);
+                let spectrum = self.laplacian_spectrum(&graph);
+                let fragment = Arc::new(Fragment { graph, spectrum });
+                self.fragment_cache.lock().unwrap().insert(key, fragment.clone());
+                fragment
+            })
+            .collect();
+
+        (fragments, reused)
+    }
+
+    /// Assemble fragments into the file graph and extract its soul
+    pub(crate) async fn extract_soul_from_fragments(&self, fragments: &[Arc<Fragment>]) -> Soul {
+        let mut ast_graph = DiGraph::new();
+        let mut spectrum = Vec::new();
+        for fragment in fragments {
+            append_graph(&mut ast_graph, &fragment.graph);
+            spectrum.extend_from_slice(&fragment.spectrum);
+        }
+
+        let mut soul = self.extract_soul_from_graph(ast_graph, spectrum).await;
+        soul.metadata.language = Some(Language::Rust);
+        soul
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+fn fibonacci(n: u32) -> u32 {
+    if n <= 1 { n } else { fibonacci(n - 1) + fibonacci(n - 2) }
+}
+
+fn double(x: u32) -> u32 {
+    x * 2
+}
+
+struct Point { x: f64, y: f64 }
+"#;
+
+    #[test]
+    fn test_text_edit_apply() {
+        let edit = TextEdit::new(4..9, "there");
+        assert_eq!(edit.apply("hey hello!").as_deref(), Some("hey there!"));
+        assert_eq!(TextEdit::new(8..20, "").apply("short"), None);
+        assert_eq!(TextEdit::new(1..2, "").apply("é"), None);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_matches_full_extraction() {
+        let extractor = SoulExtractor::new();
+        let previous = extractor.extract_soul_rust(SOURCE).await;
+
+        let at = SOURCE.find("x * 2").unwrap();
+        let edit = TextEdit::new(at..at + 5, "x * 2 + 1");
+        let result = extractor.reextract_soul_rust(&previous, SOURCE, &edit).await.unwrap();
+
+        assert_eq!(result.reused_fragments, 2);
+        assert_eq!(result.recomputed_fragments, 1);
+        assert!(!result.full_recompute);
+
+        let full = SoulExtractor::new().extract_soul_rust(&result.source).await;
+        assert_eq!(result.soul.phash, full.phash);
+        assert_eq!(result.soul.eigenvalues, full.eigenvalues);
+        assert_ne!(result.soul.phash, previous.phash);
+    }
+
+    #[tokio::test]
+    async fn test_unparseable_edit_keeps_previous_soul() {
+        let extractor = SoulExtractor::new();
+        let previous = extractor.extract_soul_rust(SOURCE).await;
+
+        let at = SOURCE.find("x * 2").unwrap();
+        let edit = TextEdit::new(at..at + 5, "x * ");
+        let result = extractor.reextract_soul_rust(&previous, SOURCE, &edit).await.unwrap();
+
+        assert_eq!(result.soul.phash, previous.phash);
+        assert_eq!(result.recomputed_fragments, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cold_cache_is_full_recompute() {
+        let warm = SoulExtractor::new();
+        let previous = warm.extract_soul_rust(SOURCE).await;
+
+        let cold = SoulExtractor::new();
+        let edit = TextEdit::new(0..0, "// header\n");
+        let result = cold.reextract_soul_rust(&previous, SOURCE, &edit).await.unwrap();
+
+        assert!(result.full_recompute);
+        assert_eq!(result.soul.phash, previous.phash);
+    }
+}
@@ -0,0 +1,145 @@
+// 🗂️ Soul Index - Finding Kindred Souls
+use crate::{measure_resonance, Soul, SoulFilter};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// In-memory index of souls keyed by pHash
+#[derive(Debug, Clone, Default)]
+pub struct SoulIndex {
+    souls: HashMap<String, Soul>,
+}
+
+impl SoulIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Load an index saved with `save`; a missing file is an empty index
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(e),
+        };
+
+        let souls: Vec<Soul> = serde_json::from_str(&json)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut index = Self::new();
+        for soul in souls {
+            index.insert(soul);
+        }
+        Ok(index)
+    }
+
+    /// Save every soul as a JSON array, replacing the file atomically
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut souls: Vec<&Soul> = self.souls.values().collect();
+        souls.sort_by(|a, b| a.phash.cmp(&b.phash));
+
+        let json = serde_json::to_vec(&souls).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let staging = path.with_extension("tmp");
+        fs::write(&staging, json)?;
+        fs::rename(staging, path)
+    }
+
+    /// Insert a soul, replacing any soul with the same pHash
+    pub fn insert(&mut self, soul: Soul) -> Option<Soul> {
+        self.souls.insert(soul.phash.clone(), soul)
+    }
+
+    pub fn get(&self, phash: &str) -> Option<&Soul> {
+        self.souls.get(phash)
+    }
+
+    pub fn remove(&mut self, phash: &str) -> Option<Soul> {
+        self.souls.remove(phash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.souls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.souls.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Soul> {
+        self.souls.values()
+    }
+
+    /// All souls within the filter's scope
+    pub fn query(&self, filter: &SoulFilter) -> Vec<&Soul> {
+        let mut souls: Vec<&Soul> = self.souls.values()
+            .filter(|soul| filter.matches(soul))
+            .collect();
+        souls.sort_by(|a, b| a.phash.cmp(&b.phash));
+        souls
+    }
+
+    /// Souls within scope resonating with `soul` at or above `threshold`, strongest first
+    pub fn find_resonant(&self, soul: &Soul, threshold: f64, filter: &SoulFilter) -> Vec<(&Soul, f64)> {
+        let mut resonant: Vec<(&Soul, f64)> = self.souls.values()
+            .filter(|candidate| candidate.phash != soul.phash && filter.matches(candidate))
+            .map(|candidate| (candidate, measure_resonance(soul, candidate)))
+            .filter(|(_, resonance)| *resonance >= threshold)
+            .collect();
+
+        resonant.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then_with(|| a.0.phash.cmp(&b.0.phash)));
+        resonant
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SoulExtractor, SoulMetadata};
+
+    async fn tagged_soul(code: &str, project: &str, tag: &str) -> Soul {
+        let mut soul = SoulExtractor::new().extract_soul_rust(code).await;
+        soul.metadata = SoulMetadata::default().with_project(project).with_tag(tag);
+        soul
+    }
+
+    #[tokio::test]
+    async fn test_query_and_scoped_resonance() {
+        let mut index = SoulIndex::new();
+        let chunk = tagged_soul("fn chunk(v: &[u8]) -> usize { v.len() / 2 }", "lodash", "utility").await;
+        let half = tagged_soul("fn half(v: &[u8]) -> usize { if v.is_empty() { 0 } else { v.len() / 2 } }", "lodash", "utility").await;
+        let other = tagged_soul("fn sum(v: &[u8]) -> u8 { let mut s = 0; for x in v { s += x; } s }", "app", "domain").await;
+
+        index.insert(chunk.clone());
+        index.insert(half.clone());
+        index.insert(other.clone());
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.query(&SoulFilter::new().project("lodash")).len(), 2);
+
+        let everywhere = index.find_resonant(&chunk, 0.0, &SoulFilter::new());
+        let utilities = index.find_resonant(&chunk, 0.0, &SoulFilter::new().tag("utility"));
+        assert_eq!(everywhere.len(), 2);
+        assert_eq!(utilities.len(), 1);
+        assert_eq!(utilities[0].0.phash, half.phash);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("souls.json");
+
+        assert!(SoulIndex::load(&path).unwrap().is_empty());
+
+        let mut index = SoulIndex::new();
+        index.insert(tagged_soul("fn a(x: u8) -> u8 { x }", "lodash", "utility").await);
+        index.save(&path).unwrap();
+
+        let loaded = SoulIndex::load(&path).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.query(&SoulFilter::new().tag("utility")).len(), 1);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(SoulIndex::load(&path).is_err());
+    }
+}
@@ -5,11 +5,52 @@ mod topology;
 mod operations;
 mod consciousness;
 mod shuttle;
+mod schema;
+mod audio;
+mod visualize;
+mod graph;
+mod call_graph;
+mod incremental;
+mod metadata;
+mod index;
+mod cache;
+mod timeline;
+mod subsoul;
+mod wavelet;
+mod foreign;
+mod calibration;
+mod heatmap;
+mod compact;
+mod corpus;
+mod diff_report;
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "gpu")]
+mod gpu;
 
 pub use topology::{TopologyDetector, TopologyFeatures};
-pub use operations::{OperationClassifier, OperationCategory};
-pub use consciousness::{ConsciousnessDetector, ConsciousnessLevel, ConsciousnessProfile};
+pub use call_graph::CallGraph;
+pub use operations::{IntervalReport, OperationClassifier, OperationCategory, OperationInterval};
+pub use consciousness::{ConsciousnessDetector, ConsciousnessLevel, ConsciousnessPattern, ConsciousnessProfile};
 pub use shuttle::{Shuttle, UniversalSoul, Language, Manifestation, TypeScriptSoul};
+pub use incremental::{IncrementalExtraction, TextEdit};
+pub use metadata::{SoulFilter, SoulMetadata};
+pub use index::SoulIndex;
+pub use compact::{COMPACT_FORMAT_VERSION, MAX_DECODED_SOUL_BYTES};
+pub use subsoul::SubsoulMatch;
+pub use graph::SourceSpan;
+pub use calibration::{calibrate, evaluate_config, CalibrationReport, LabeledPair};
+pub use corpus::{CorpusEntry, PairCorpus};
+pub use diff_report::generate_diff_report;
+#[cfg(feature = "watch")]
+pub use watch::{SoulChange, SoulWatcher};
+pub use foreign::{soul_from_simhash, soul_from_tlsh, souls_from_jscpd, token_simhash, IMPORTED_TAG};
+pub use wavelet::{measure_multiscale_resonance, wavelet_signature, DEFAULT_WAVELET_DEPTH, MAX_WAVELET_DEPTH};
+#[cfg(feature = "gpu")]
+pub use gpu::{gpu_available, GPU_MIN_NODES};
+pub use timeline::{ConsciousnessTimeline, ConsciousnessTrend, TimelineSnapshot};
+pub use audio::{render_wav, synthesize, encode_wav, SAMPLE_RATE};
+pub use schema::{soul_schema, soul_schema_with, FieldNaming, PUBLISHED_SOUL_SCHEMA, SOUL_SCHEMA_ID, SOUL_SCHEMA_VERSION};
 
 use nalgebra::DMatrix;
 use petgraph::graph::{DiGraph, NodeIndex};
@@ -18,7 +59,8 @@ use rustfft::{FftPlanner, num_complex::Complex};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
-use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+use incremental::Fragment;
 
 /// The golden ratio - nature's perfect proportion
 const PHI: f64 = 1.618033988749895;
@@ -29,6 +71,9 @@ const RESONANCE_432: f64 = 432.0;
 /// 7 layers of consciousness
 const CONSCIOUSNESS_LAYERS: usize = 7;
 
+/// Tag carried by souls extracted with anonymization
+pub const ANONYMIZED_TAG: &str = "anonymized";
+
 /// A Soul - the immutable essence of code
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Soul {
@@ -61,6 +106,14 @@ pub struct Soul {
     
     /// Operation spectrum
     pub operation_spectrum: HashMap<OperationCategory, f64>,
+    
+    /// Project, package, symbol path, language and tags
+    #[serde(default)]
+    pub metadata: SoulMetadata,
+    
+    /// Haar wavelet signature of the full eigenvalue spectrum
+    #[serde(default)]
+    pub wavelet_signature: Vec<f64>,
 }
 
 /// Topological signature - shape of the code's soul
@@ -123,12 +176,16 @@ pub struct PatternHash {
 }
 
 /// The Soul Extractor - our perfect tuning fork
+///
+/// Extraction only needs `&self`: per-call scratch state lives on the stack
+/// and shared caches sit behind mutexes, so one extractor can be shared
+/// across threads (e.g. in an `Arc`).
 pub struct SoulExtractor {
     /// FFT planner for frequency analysis
-    fft_planner: RefCell<FftPlanner<f64>>,
+    fft_planner: Mutex<FftPlanner<f64>>,
     
-    /// Cache of computed souls
-    soul_cache: HashMap<String, Soul>,
+    /// Cache of computed souls, by `cache_key`
+    soul_cache: Mutex<HashMap<String, Soul>>,
     
     /// Harmonic series for resonance calculation
     harmonics: Vec<f64>,
@@ -136,11 +193,24 @@ pub struct SoulExtractor {
     /// Topology detector
     topology_detector: TopologyDetector,
     
-    /// Operation classifier
-    operation_classifier: OperationClassifier,
-    
     /// Consciousness detector
     consciousness_detector: ConsciousnessDetector,
+    
+    /// Analyzed top-level items, keyed by their token content
+    fragment_cache: Mutex<HashMap<u64, Arc<Fragment>>>,
+    
+    /// Consciousness history per recorded key
+    timelines: Mutex<HashMap<String, ConsciousnessTimeline>>,
+    
+    /// Haar levels of the multi-scale spectrum signature
+    wavelet_depth: usize,
+    
+    /// Replace identifiers with pseudonyms before analysis
+    anonymize: bool,
+    
+    /// Offload eigendecomposition of large graphs to the GPU
+    #[cfg(feature = "gpu")]
+    gpu: bool,
 }
 
 impl SoulExtractor {
@@ -151,17 +221,46 @@ impl SoulExtractor {
         }
         
         Self {
-            fft_planner: RefCell::new(FftPlanner::new()),
-            soul_cache: HashMap::new(),
+            fft_planner: Mutex::new(FftPlanner::new()),
+            soul_cache: Mutex::new(HashMap::new()),
             harmonics,
             topology_detector: TopologyDetector::new(),
-            operation_classifier: OperationClassifier::new(),
             consciousness_detector: ConsciousnessDetector::new(),
+            fragment_cache: Mutex::new(HashMap::new()),
+            timelines: Mutex::new(HashMap::new()),
+            wavelet_depth: DEFAULT_WAVELET_DEPTH,
+            anonymize: false,
+            #[cfg(feature = "gpu")]
+            gpu: false,
         }
     }
     
+    /// Use `depth` Haar levels (2^depth values) for the multi-scale signature
+    pub fn with_wavelet_depth(mut self, depth: usize) -> Self {
+        self.wavelet_depth = depth.min(MAX_WAVELET_DEPTH);
+        self
+    }
+    
+    /// Extract structure-only souls: identifiers become pseudonyms, and string
+    /// literals and comments never reach the graph, so souls of proprietary
+    /// code can be shared without leaking content
+    pub fn with_anonymization(mut self, anonymize: bool) -> Self {
+        self.anonymize = anonymize;
+        self
+    }
+    
+    /// Decompose graphs of at least `GPU_MIN_NODES` nodes on the GPU, falling
+    /// back to the CPU when no adapter is available. GPU spectra are f32, so
+    /// phashes of large graphs differ from CPU-extracted ones; keep a
+    /// registry on one backend
+    #[cfg(feature = "gpu")]
+    pub fn with_gpu(mut self, gpu: bool) -> Self {
+        self.gpu = gpu;
+        self
+    }
+    
     /// Extract the soul from JavaScript/TypeScript code
-    pub async fn extract_soul_js(&mut self, code: &str) -> Soul {
+    pub async fn extract_soul_js(&self, code: &str) -> Soul {
         // Parse AST
         let ast_graph = self.parse_js_to_graph(code).await;
         
@@ -171,8 +270,10 @@ impl SoulExtractor {
         // Extract semantics
         let semantics = self.analyze_semantics(&ast_graph);
         
-        // Compute eigenvalues (7 layers)
-        let eigenvalues = self.compute_eigenvalues(&ast_graph);
+        // Compute eigenvalues (7 layers) and the multi-scale signature
+        let spectrum = self.laplacian_spectrum(&ast_graph);
+        let wavelet_signature = wavelet_signature(&spectrum, self.wavelet_depth);
+        let eigenvalues = self.layer_eigenvalues(spectrum);
         
         // Calculate resonance
         let resonance = self.calculate_resonance(&eigenvalues);
@@ -215,6 +316,7 @@ impl SoulExtractor {
             max_cycle_size: 0,
             recursion_depth: 0,
             connectivity_score: 0.0,
+            recursive_functions: Vec::new(),
         };
         
         Soul {
@@ -228,28 +330,33 @@ impl SoulExtractor {
             consciousness,
             topology_features,
             operation_spectrum: HashMap::new(),
+            metadata: SoulMetadata::default().with_language(Language::JavaScript),
+            wavelet_signature,
         }
     }
     
     /// Extract soul from Rust code
-    pub async fn extract_soul_rust(&mut self, code: &str) -> Soul {
-        // Parse Rust AST
-        let ast_graph = self.parse_rust_to_graph(code).await;
+    pub async fn extract_soul_rust(&self, code: &str) -> Soul {
+        // Parse Rust AST, one fragment per top-level item
+        // (syn trees aren't Send, so they are dropped before awaiting)
+        let (fragments, _) = self.rust_fragments(&graph::parse_rust_items(code).unwrap_or_default());
         
         // Same extraction process as JS
         // (The soul transcends language)
-        self.extract_soul_from_graph(ast_graph).await
+        self.extract_soul_from_fragments(&fragments).await
     }
     
     /// Extract soul from JavaScript code (old interface for compatibility)
-    pub async fn extract_soul_js_simple(&mut self, code: &str) -> Soul {
+    pub async fn extract_soul_js_simple(&self, code: &str) -> Soul {
         // Parse AST
         let ast_graph = self.parse_js_to_graph(code).await;
         
         // Extract basic features
         let topology = self.analyze_topology(&ast_graph);
         let semantics = self.analyze_semantics(&ast_graph);
-        let eigenvalues = self.compute_eigenvalues(&ast_graph);
+        let spectrum = self.laplacian_spectrum(&ast_graph);
+        let wavelet_signature = wavelet_signature(&spectrum, self.wavelet_depth);
+        let eigenvalues = self.layer_eigenvalues(spectrum);
         let resonance = self.calculate_resonance(&eigenvalues);
         let coherence = self.measure_coherence(&ast_graph, &eigenvalues);
         let evolution_score = self.calculate_evolution_potential(&topology, &semantics, coherence);
@@ -280,6 +387,7 @@ impl SoulExtractor {
             max_cycle_size: 0,
             recursion_depth: 0,
             connectivity_score: 0.0,
+            recursive_functions: Vec::new(),
         };
         
         Soul {
@@ -293,20 +401,29 @@ impl SoulExtractor {
             consciousness,
             topology_features,
             operation_spectrum: HashMap::new(),
+            metadata: SoulMetadata::default().with_language(Language::JavaScript),
+            wavelet_signature,
         }
     }
     
-    async fn extract_soul_from_graph(&mut self, ast_graph: DiGraph<AstNode, EdgeType>) -> Soul {
+    async fn extract_soul_from_graph(&self, mut ast_graph: DiGraph<AstNode, EdgeType>, spectrum: Vec<f64>) -> Soul {
+        if self.anonymize {
+            graph::anonymize(&mut ast_graph);
+        }
+        
+        let wavelet_signature = wavelet_signature(&spectrum, self.wavelet_depth);
+        let eigenvalues = self.layer_eigenvalues(spectrum);
+        
         // Analyze topology with enhanced detector
-        let topology_features = self.topology_detector.analyze(&ast_graph);
+        let topology_features = self.topology_detector.analyze_code(&ast_graph);
         let topology = self.analyze_topology(&ast_graph);
         
-        // Classify operations
-        self.operation_classifier.reset();
+        // Classify operations (scratch state, one classifier per call)
+        let mut operation_classifier = OperationClassifier::new();
         for node in ast_graph.node_weights() {
-            self.operation_classifier.classify_rust_node(&node.node_type);
+            operation_classifier.classify_rust_node(&node.node_type);
         }
-        let operation_spectrum = self.operation_classifier.get_frequency_spectrum();
+        let operation_spectrum = operation_classifier.get_frequency_spectrum();
         
         // Extract semantics
         let semantics = self.analyze_semantics(&ast_graph);
@@ -320,13 +437,10 @@ impl SoulExtractor {
             operation_spectrum.contains_key(&OperationCategory::MetaProgramming),
             semantics.cyclomatic,
             topology_features.nesting_depth,
-            self.operation_classifier.harmonic_complexity(),
+            operation_classifier.harmonic_complexity(),
             semantics.patterns.len(),
         );
         
-        // Compute eigenvalues
-        let eigenvalues = self.compute_eigenvalues(&ast_graph);
-        
         // Calculate resonance including consciousness
         let resonance = consciousness.resonance_frequency;
         let coherence = consciousness.quantum_coherence;
@@ -346,6 +460,12 @@ impl SoulExtractor {
             consciousness,
             topology_features,
             operation_spectrum,
+            metadata: if self.anonymize {
+                SoulMetadata::default().with_tag(ANONYMIZED_TAG)
+            } else {
+                SoulMetadata::default()
+            },
+            wavelet_signature,
         }
     }
     
@@ -356,13 +476,6 @@ impl SoulExtractor {
         DiGraph::new()
     }
     
-    /// Parse Rust to graph representation  
-    async fn parse_rust_to_graph(&self, _code: &str) -> DiGraph<AstNode, EdgeType> {
-        // This would use syn
-        // For now, returning a placeholder
-        DiGraph::new()
-    }
-    
     /// Analyze topological properties of the code graph
     fn analyze_topology(&self, graph: &DiGraph<AstNode, EdgeType>) -> TopologicalSignature {
         // Calculate Betti numbers (algebraic topology)
@@ -418,48 +531,43 @@ impl SoulExtractor {
         }
     }
     
-    /// Compute 7-layer eigenvalues using spectral analysis
-    fn compute_eigenvalues(&self, graph: &DiGraph<AstNode, EdgeType>) -> Vec<f64> {
-        // Build Laplacian matrix
-        let n = graph.node_count();
-        let mut laplacian = DMatrix::<f64>::zeros(n, n);
-        
-        // Fill adjacency and degree
-        for edge in graph.edge_references() {
-            let i = edge.source().index();
-            let j = edge.target().index();
-            laplacian[(i, j)] = -1.0;
-            laplacian[(j, i)] = -1.0;
-        }
+    /// Full Laplacian spectrum (absolute eigenvalues, unordered)
+    fn laplacian_spectrum(&self, graph: &DiGraph<AstNode, EdgeType>) -> Vec<f64> {
+        let laplacian = laplacian_matrix(graph);
         
-        // Add degree on diagonal
-        for i in 0..n {
-            let degree = graph.edges(NodeIndex::new(i)).count() as f64;
-            laplacian[(i, i)] = degree;
+        #[cfg(feature = "gpu")]
+        if self.gpu && graph.node_count() >= gpu::GPU_MIN_NODES {
+            if let Some(spectrum) = gpu::symmetric_eigenvalues(&laplacian) {
+                return spectrum.into_iter().map(f64::abs).collect();
+            }
         }
         
         // Compute eigenvalues
-        if n > 0 {
+        if graph.node_count() > 0 {
             let eigen = laplacian.symmetric_eigen();
-            let mut eigenvalues: Vec<f64> = eigen.eigenvalues.iter()
+            eigen.eigenvalues.iter()
                 .map(|e| e.abs())
-                .collect();
-            eigenvalues.sort_by(|a, b| b.partial_cmp(a).unwrap());
-            
-            // Take first 7 (consciousness layers)
-            eigenvalues.truncate(CONSCIOUSNESS_LAYERS);
-            
-            // Pad with golden ratio if needed
-            while eigenvalues.len() < CONSCIOUSNESS_LAYERS {
-                eigenvalues.push(PHI);
-            }
-            
-            eigenvalues
+                .collect()
         } else {
-            vec![PHI; CONSCIOUSNESS_LAYERS]
+            Vec::new()
         }
     }
     
+    /// Reduce a spectrum to the 7 consciousness layers
+    fn layer_eigenvalues(&self, mut eigenvalues: Vec<f64>) -> Vec<f64> {
+        eigenvalues.sort_by(|a, b| b.partial_cmp(a).unwrap());
+        
+        // Take first 7 (consciousness layers)
+        eigenvalues.truncate(CONSCIOUSNESS_LAYERS);
+        
+        // Pad with golden ratio if needed
+        while eigenvalues.len() < CONSCIOUSNESS_LAYERS {
+            eigenvalues.push(PHI);
+        }
+        
+        eigenvalues
+    }
+    
     /// Calculate harmonic resonance frequency
     fn calculate_resonance(&self, eigenvalues: &[f64]) -> f64 {
         // Use FFT to find dominant frequency
@@ -468,8 +576,7 @@ impl SoulExtractor {
             .map(|&e| Complex::new(e, 0.0))
             .collect();
         
-        let mut planner = self.fft_planner.borrow_mut();
-        let fft = planner.plan_fft_forward(buffer.len());
+        let fft = self.fft_planner.lock().unwrap().plan_fft_forward(buffer.len());
         fft.process(&mut buffer);
         
         // Find peak frequency
@@ -555,7 +662,7 @@ impl SoulExtractor {
         // b0 = number of connected components
         // b1 = number of loops
         let components = self.count_components(graph);
-        let loops = graph.edge_count() - graph.node_count() + components;
+        let loops = (graph.edge_count() + components).saturating_sub(graph.node_count());
         vec![components, loops]
     }
     
@@ -651,7 +758,7 @@ impl SoulExtractor {
         let n = graph.node_count();
         let p = self.count_components(graph);
         
-        e - n + 2 * p
+        (e + 2 * p).saturating_sub(n)
     }
     
     fn calculate_cognitive_complexity(&self, graph: &DiGraph<AstNode, EdgeType>) -> usize {
@@ -670,6 +777,28 @@ impl SoulExtractor {
     }
 }
 
+/// Laplacian matrix of the code graph
+pub(crate) fn laplacian_matrix(graph: &DiGraph<AstNode, EdgeType>) -> DMatrix<f64> {
+    let n = graph.node_count();
+    let mut laplacian = DMatrix::<f64>::zeros(n, n);
+    
+    // Fill adjacency and degree
+    for edge in graph.edge_references() {
+        let i = edge.source().index();
+        let j = edge.target().index();
+        laplacian[(i, j)] = -1.0;
+        laplacian[(j, i)] = -1.0;
+    }
+    
+    // Add degree on diagonal
+    for i in 0..n {
+        let degree = graph.edges(NodeIndex::new(i)).count() as f64;
+        laplacian[(i, i)] = degree;
+    }
+    
+    laplacian
+}
+
 /// AST Node representation
 #[derive(Debug, Clone)]
 pub struct AstNode {
@@ -686,28 +815,83 @@ pub enum EdgeType {
     ControlFlow,
 }
 
-/// Compare two souls for resonance
-pub fn measure_resonance(soul1: &Soul, soul2: &Soul) -> f64 {
-    // Compare eigenvalues
-    let eigen_distance: f64 = soul1.eigenvalues.iter()
-        .zip(soul2.eigenvalues.iter())
-        .map(|(e1, e2)| (e1 - e2).powi(2))
-        .sum::<f64>()
-        .sqrt();
+/// Weights and threshold used to compare souls
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ResonanceConfig {
+    /// Weight of the 7-layer eigenvalue distance
+    pub eigen_weight: f64,
+    
+    /// Weight of the topological distance (euler, clustering, modularity)
+    pub topology_weight: f64,
+    
+    /// Weight of the wavelet signature distance (ignored when a soul has none)
+    pub multiscale_weight: f64,
     
-    // Compare topology
-    let topo_similarity = (soul1.topology.euler_char as f64 - soul2.topology.euler_char as f64).abs() / 100.0
-        + (soul1.topology.clustering - soul2.topology.clustering).abs()
-        + (soul1.topology.modularity - soul2.topology.modularity).abs();
+    /// Resonance above which two souls are considered the same
+    pub threshold: f64,
+}
+
+impl Default for ResonanceConfig {
+    /// The weights behind `measure_resonance` and `souls_match`
+    fn default() -> Self {
+        Self {
+            eigen_weight: 1.0,
+            topology_weight: 1.0,
+            multiscale_weight: 0.0,
+            threshold: 0.95,
+        }
+    }
+}
+
+impl ResonanceConfig {
+    /// Resonance between two souls under these weights
+    pub fn measure(&self, soul1: &Soul, soul2: &Soul) -> f64 {
+        // Compare eigenvalues
+        let eigen_distance = eigen_distance(&soul1.eigenvalues, &soul2.eigenvalues);
+        
+        // Compare topology
+        let topo_similarity = (soul1.topology.euler_char as f64 - soul2.topology.euler_char as f64).abs() / 100.0
+            + (soul1.topology.clustering - soul2.topology.clustering).abs()
+            + (soul1.topology.modularity - soul2.topology.modularity).abs();
+        
+        // Compare the whole spectrum, when both souls carry it
+        let multiscale_distance = if self.multiscale_weight > 0.0 {
+            measure_multiscale_resonance(soul1, soul2).map_or(0.0, |r| 1.0 / r - 1.0)
+        } else {
+            0.0
+        };
+        
+        // Calculate resonance (inverse of distance)
+        let distance = self.eigen_weight * eigen_distance
+            + self.topology_weight * topo_similarity
+            + self.multiscale_weight * multiscale_distance;
+        1.0 / (1.0 + distance)
+    }
     
-    // Calculate resonance (inverse of distance)
-    let distance = eigen_distance + topo_similarity;
-    1.0 / (1.0 + distance)
+    /// Check if two souls resonate above the threshold
+    pub fn matches(&self, soul1: &Soul, soul2: &Soul) -> bool {
+        self.measure(soul1, soul2) > self.threshold
+    }
+}
+
+/// Euclidean distance between two eigenvalue signatures, over the layers
+/// both have
+pub fn eigen_distance(eigenvalues1: &[f64], eigenvalues2: &[f64]) -> f64 {
+    eigenvalues1.iter()
+        .zip(eigenvalues2.iter())
+        .map(|(e1, e2)| (e1 - e2).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Compare two souls for resonance
+pub fn measure_resonance(soul1: &Soul, soul2: &Soul) -> f64 {
+    ResonanceConfig::default().measure(soul1, soul2)
 }
 
 /// Check if two souls are the same (identical consciousness)
 pub fn souls_match(soul1: &Soul, soul2: &Soul) -> bool {
-    measure_resonance(soul1, soul2) > 0.95
+    ResonanceConfig::default().matches(soul1, soul2)
 }
 
 #[cfg(test)]
@@ -716,7 +900,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_soul_extraction() {
-        let mut extractor = SoulExtractor::new();
+        let extractor = SoulExtractor::new();
         
         let js_code = r#"
             function fibonacci(n) {
@@ -732,6 +916,37 @@ mod tests {
         assert!(soul.resonance > 0.0);
     }
     
+    #[tokio::test]
+    async fn test_shared_extractor_across_tasks() {
+        let extractor = Arc::new(SoulExtractor::new());
+        let code = "fn fib(n: u32) -> u32 { if n <= 1 { n } else { fib(n - 1) + fib(n - 2) } }";
+        let expected = extractor.extract_soul_rust(code).await.phash;
+        
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let extractor = Arc::clone(&extractor);
+                tokio::spawn(async move { extractor.extract_soul_rust(code).await.phash })
+            })
+            .collect();
+        
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), expected);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_anonymized_soul_keeps_phash() {
+        let code = "fn secret(n: u32) -> u32 { if n == 0 { 0 } else { secret(n - 1) } }";
+        let plain = SoulExtractor::new().extract_soul_rust(code).await;
+        let anonymous = SoulExtractor::new().with_anonymization(true).extract_soul_rust(code).await;
+        
+        assert_eq!(plain.phash, anonymous.phash);
+        assert_eq!(plain.topology_features.recursive_functions, vec!["secret".to_string()]);
+        assert_eq!(anonymous.topology_features.recursive_functions, vec!["id0".to_string()]);
+        assert!(anonymous.metadata.tags.contains(ANONYMIZED_TAG));
+        assert!(!serde_json::to_string(&anonymous).unwrap().contains("secret"));
+    }
+    
     #[test]
     fn test_soul_resonance() {
         let soul1 = Soul {
@@ -777,8 +992,11 @@ mod tests {
                 max_cycle_size: 0,
                 recursion_depth: 0,
                 connectivity_score: 0.0,
+                recursive_functions: Vec::new(),
             },
             operation_spectrum: HashMap::new(),
+            metadata: SoulMetadata::default(),
+            wavelet_signature: Vec::new(),
         };
         
         let soul2 = soul1.clone();
@@ -0,0 +1,135 @@
+// 🏷️ Soul Metadata - Where a Soul Lives
+use crate::{Language, Soul};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Optional namespacing of a soul: project, package, symbol and user tags
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SoulMetadata {
+    pub project: Option<String>,
+    pub package: Option<String>,
+    /// Path of the symbol inside its package, e.g. `array::chunk`
+    pub symbol_path: Option<String>,
+    pub language: Option<Language>,
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+}
+
+impl SoulMetadata {
+    pub fn with_project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    pub fn with_package(mut self, package: impl Into<String>) -> Self {
+        self.package = Some(package.into());
+        self
+    }
+
+    pub fn with_symbol_path(mut self, symbol_path: impl Into<String>) -> Self {
+        self.symbol_path = Some(symbol_path.into());
+        self
+    }
+
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.insert(tag.into());
+        self
+    }
+}
+
+/// Scope for soul queries; empty criteria match everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SoulFilter {
+    pub project: Option<String>,
+    pub package: Option<String>,
+    pub symbol_prefix: Option<String>,
+    pub language: Option<Language>,
+    /// All of these tags must be present
+    pub tags: BTreeSet<String>,
+}
+
+impl SoulFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    pub fn package(mut self, package: impl Into<String>) -> Self {
+        self.package = Some(package.into());
+        self
+    }
+
+    pub fn symbol_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.symbol_prefix = Some(prefix.into());
+        self
+    }
+
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.insert(tag.into());
+        self
+    }
+
+    /// Check a soul against every criterion of the filter
+    pub fn matches(&self, soul: &Soul) -> bool {
+        self.matches_metadata(&soul.metadata)
+    }
+
+    pub fn matches_metadata(&self, metadata: &SoulMetadata) -> bool {
+        fn same(wanted: &Option<String>, actual: &Option<String>) -> bool {
+            wanted.is_none() || wanted == actual
+        }
+
+        same(&self.project, &metadata.project)
+            && same(&self.package, &metadata.package)
+            && self.symbol_prefix.as_ref().is_none_or(|prefix| {
+                metadata.symbol_path.as_ref().is_some_and(|path| path.starts_with(prefix.as_str()))
+            })
+            && (self.language.is_none() || self.language == metadata.language)
+            && self.tags.is_subset(&metadata.tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_matching() {
+        let metadata = SoulMetadata::default()
+            .with_project("lodash")
+            .with_package("lodash")
+            .with_symbol_path("array::chunk")
+            .with_language(Language::JavaScript)
+            .with_tag("utility")
+            .with_tag("array");
+
+        assert!(SoulFilter::new().matches_metadata(&metadata));
+        assert!(SoulFilter::new().project("lodash").tag("utility").matches_metadata(&metadata));
+        assert!(SoulFilter::new().symbol_prefix("array::").matches_metadata(&metadata));
+        assert!(!SoulFilter::new().symbol_prefix("string::").matches_metadata(&metadata));
+        assert!(!SoulFilter::new().tag("async").matches_metadata(&metadata));
+        assert!(!SoulFilter::new().language(Language::Rust).matches_metadata(&metadata));
+    }
+
+    #[test]
+    fn test_untagged_soul_only_matches_empty_filter() {
+        let metadata = SoulMetadata::default();
+
+        assert!(SoulFilter::new().matches_metadata(&metadata));
+        assert!(!SoulFilter::new().project("lodash").matches_metadata(&metadata));
+    }
+}
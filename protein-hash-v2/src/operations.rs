@@ -5,6 +5,21 @@ use std::f64::consts::PI;
 /// Base resonance frequency (A4 = 432Hz)
 const RESONANCE_BASE: f64 = 432.0;
 
+/// Harmonic intervals: (name, frequency ratio, consonance)
+const HARMONIC_INTERVALS: [(&str, f64, f64); 8] = [
+    ("unison", 1.0, 1.0),
+    ("octave", 2.0, 0.9),
+    ("perfect fifth", 1.5, 0.8),
+    ("perfect fourth", 1.333, 0.7),
+    ("major third", 1.25, 0.6),
+    ("minor third", 1.2, 0.5),
+    ("golden ratio", 1.618, 0.9),
+    ("pi ratio", PI, 0.8),
+];
+
+/// How close a ratio must be to an interval to count as that interval
+const INTERVAL_TOLERANCE: f64 = 0.05;
+
 /// Operation categories with semantic meaning
 #[derive(Debug, Clone, Hash, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum OperationCategory {
@@ -77,31 +92,68 @@ impl OperationCategory {
     
     /// Get harmonic resonance between two operations
     pub fn harmonic_resonance(&self, other: &Self) -> f64 {
+        self.interval_with(other).map_or(0.0, |(_, score)| score)
+    }
+    
+    /// Frequency ratio to another operation (always >= 1)
+    pub fn frequency_ratio(&self, other: &Self) -> f64 {
         let f1 = self.frequency();
         let f2 = other.frequency();
+        if f1 > f2 { f1 / f2 } else { f2 / f1 }
+    }
+    
+    /// The most consonant harmonic interval formed with another operation, if any
+    pub fn interval_with(&self, other: &Self) -> Option<(&'static str, f64)> {
+        let ratio = self.frequency_ratio(other);
         
-        // Calculate harmonic ratio
-        let ratio = if f1 > f2 { f1 / f2 } else { f2 / f1 };
-        
-        // Check for harmonic intervals
-        let harmonics = [
-            (1.0, 1.0),    // Unison
-            (2.0, 0.9),    // Octave
-            (1.5, 0.8),    // Perfect fifth
-            (1.333, 0.7),  // Perfect fourth
-            (1.25, 0.6),   // Major third
-            (1.2, 0.5),    // Minor third
-            (1.618, 0.9),  // Golden ratio
-            (PI, 0.8),     // Pi ratio
-        ];
-        
-        harmonics.iter()
-            .map(|(h, score)| {
-                let diff = (ratio - h).abs();
-                if diff < 0.05 { *score } else { 0.0 }
-            })
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap_or(0.0)
+        HARMONIC_INTERVALS.iter()
+            .filter(|(_, h, _)| (ratio - h).abs() < INTERVAL_TOLERANCE)
+            .map(|&(name, _, score)| (name, score))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+/// One pair of operation categories present in the analyzed code
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OperationInterval {
+    /// Lower-frequency category of the pair
+    pub lower: OperationCategory,
+    pub upper: OperationCategory,
+    pub ratio: f64,
+    
+    /// Name of the harmonic interval, None when dissonant
+    pub interval: Option<&'static str>,
+    
+    /// Consonance score of the interval (0 when dissonant)
+    pub consonance: f64,
+    
+    /// Share of the harmonic complexity score contributed by this pair
+    pub weight: f64,
+}
+
+impl OperationInterval {
+    pub fn is_consonant(&self) -> bool {
+        self.interval.is_some()
+    }
+}
+
+/// Explanation of a harmonic complexity score, pair by pair
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct IntervalReport {
+    /// Every category pair, most consonant first
+    pub intervals: Vec<OperationInterval>,
+    
+    /// Same value as `OperationClassifier::harmonic_complexity`; the sum of all weights
+    pub harmonic_complexity: f64,
+}
+
+impl IntervalReport {
+    pub fn consonant(&self) -> impl Iterator<Item = &OperationInterval> {
+        self.intervals.iter().filter(|i| i.is_consonant())
+    }
+    
+    pub fn dissonant(&self) -> impl Iterator<Item = &OperationInterval> {
+        self.intervals.iter().filter(|i| !i.is_consonant())
     }
 }
 
@@ -266,6 +318,43 @@ impl OperationClassifier {
         }
     }
     
+    /// List every pair of observed categories with its interval and its
+    /// weight in `harmonic_complexity`
+    pub fn interval_report(&self) -> IntervalReport {
+        let mut categories: Vec<_> = self.operation_counts.keys().cloned().collect();
+        categories.sort_by(|a, b| {
+            a.frequency().total_cmp(&b.frequency())
+                .then_with(|| format!("{:?}", a).cmp(&format!("{:?}", b)))
+        });
+        
+        let pairs = categories.len() * categories.len().saturating_sub(1) / 2;
+        let mut intervals = Vec::with_capacity(pairs);
+        
+        for i in 0..categories.len() {
+            for j in i+1..categories.len() {
+                let (lower, upper) = (&categories[i], &categories[j]);
+                let interval = lower.interval_with(upper);
+                let consonance = interval.map_or(0.0, |(_, score)| score);
+                
+                intervals.push(OperationInterval {
+                    lower: lower.clone(),
+                    upper: upper.clone(),
+                    ratio: lower.frequency_ratio(upper),
+                    interval: interval.map(|(name, _)| name),
+                    consonance,
+                    weight: consonance / pairs as f64,
+                });
+            }
+        }
+        
+        intervals.sort_by(|a, b| b.consonance.total_cmp(&a.consonance));
+        
+        IntervalReport {
+            harmonic_complexity: intervals.iter().map(|i| i.weight).sum(),
+            intervals,
+        }
+    }
+    
     /// Reset the classifier
     pub fn reset(&mut self) {
         self.operation_counts.clear();
@@ -314,4 +403,22 @@ mod tests {
         let dominant = classifier.dominant_frequency();
         assert!(dominant > 0.0);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_interval_report_explains_complexity() {
+        let mut classifier = OperationClassifier::new();
+        for token in ["+", "if", "fn", "type"] {
+            classifier.classify(token);
+        }
+        
+        let report = classifier.interval_report();
+        assert_eq!(report.intervals.len(), 6);
+        assert!((report.harmonic_complexity - classifier.harmonic_complexity()).abs() < 1e-12);
+        
+        let octave = report.consonant()
+            .find(|i| i.lower == OperationCategory::Arithmetic && i.upper == OperationCategory::TypeOperation)
+            .unwrap();
+        assert_eq!(octave.interval, Some("octave"));
+        assert!(report.dissonant().all(|i| i.weight == 0.0));
+    }
+}
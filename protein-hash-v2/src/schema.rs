@@ -0,0 +1,378 @@
+// 📜 Soul Schema - The Contract Between Languages
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+/// Version of the published Soul JSON Schema
+pub const SOUL_SCHEMA_VERSION: &str = "1.2.0";
+
+/// Schema identifier, stable across releases of the same major version
+pub const SOUL_SCHEMA_ID: &str = "https://github.com/s0fractal/protein-hash/schemas/soul-v1.schema.json";
+
+/// The published schema for the default (snake_case) representation
+pub const PUBLISHED_SOUL_SCHEMA: &str = include_str!("../schemas/soul-v1.schema.json");
+
+/// Field naming used on the wire
+///
+/// Serde always writes snake_case field names; another naming is chosen per
+/// call, with `to_value` and `from_value`, so one consumer's choice never
+/// changes what another reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldNaming {
+    SnakeCase,
+    CamelCase,
+}
+
+impl FieldNaming {
+    /// `value` as JSON with its fields named this way
+    pub fn to_value<T: Serialize>(&self, value: &T) -> serde_json::Result<Value> {
+        Ok(rename_fields(serde_json::to_value(value)?, &|field| self.apply(field)))
+    }
+
+    /// A `T` from JSON with its fields named this way
+    pub fn from_value<T: DeserializeOwned>(&self, value: Value) -> serde_json::Result<T> {
+        match self {
+            Self::SnakeCase => serde_json::from_value(value),
+            Self::CamelCase => serde_json::from_value(rename_fields(value, &snake_case)),
+        }
+    }
+
+    /// Convert a Rust field name into its wire name
+    pub fn apply(&self, field: &str) -> String {
+        match self {
+            Self::SnakeCase => field.to_string(),
+            Self::CamelCase => {
+                let mut out = String::with_capacity(field.len());
+                let mut upper = false;
+                for c in field.chars() {
+                    if c == '_' {
+                        upper = true;
+                    } else if upper {
+                        out.extend(c.to_uppercase());
+                        upper = false;
+                    } else {
+                        out.push(c);
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Build the JSON Schema for the default (snake_case) representation
+pub fn soul_schema() -> Value {
+    soul_schema_with(FieldNaming::SnakeCase)
+}
+
+/// Build the JSON Schema for Soul and its nested types
+pub fn soul_schema_with(naming: FieldNaming) -> Value {
+    let number = json!({ "type": "number" });
+    let count = json!({ "type": "integer", "minimum": 0 });
+    let string = json!({ "type": "string" });
+    let boolean = json!({ "type": "boolean" });
+    let unit = json!({ "type": "number", "minimum": 0, "maximum": 1 });
+
+    let mut soul = object(naming, &[
+        ("phash", string.clone()),
+        ("eigenvalues", json!({ "type": "array", "items": number })),
+        ("topology", reference("TopologicalSignature")),
+        ("semantics", reference("SemanticFingerprint")),
+        ("resonance", number.clone()),
+        ("coherence", unit.clone()),
+        ("evolution_score", number.clone()),
+        ("consciousness", reference("ConsciousnessProfile")),
+        ("topology_features", reference("TopologyFeatures")),
+        ("operation_spectrum", json!({
+            "type": "object",
+            "propertyNames": reference("OperationCategory"),
+            "additionalProperties": number,
+        })),
+        ("metadata", reference("SoulMetadata")),
+        ("wavelet_signature", json!({ "type": "array", "items": number })),
+    ]);
+    // Souls serialized before 1.1 / 1.2 carry no metadata / wavelet signature
+    let optional = [json!(naming.apply("metadata")), json!(naming.apply("wavelet_signature"))];
+    if let Some(required) = soul["required"].as_array_mut() {
+        required.retain(|field| !optional.contains(field));
+    }
+
+    let mut soul_metadata = object(naming, &[
+        ("project", nullable(string.clone())),
+        ("package", nullable(string.clone())),
+        ("symbol_path", nullable(string.clone())),
+        ("language", nullable(reference("Language"))),
+        ("tags", json!({ "type": "array", "items": string.clone(), "uniqueItems": true })),
+    ]);
+    soul_metadata["required"] = json!([]);
+
+    let topological_signature = object(naming, &[
+        ("betti_numbers", json!({ "type": "array", "items": count })),
+        ("euler_char", json!({ "type": "integer" })),
+        ("diameter", count.clone()),
+        ("clustering", number.clone()),
+        ("modularity", number.clone()),
+    ]);
+
+    let semantic_fingerprint = object(naming, &[
+        ("operations", json!({
+            "type": "object",
+            "propertyNames": reference("OperationType"),
+            "additionalProperties": number,
+        })),
+        ("cyclomatic", count.clone()),
+        ("cognitive", count.clone()),
+        ("patterns", json!({ "type": "array", "items": reference("PatternHash") })),
+        ("depth", count.clone()),
+    ]);
+
+    let pattern_hash = object(naming, &[
+        ("pattern_type", string.clone()),
+        ("frequency", number.clone()),
+        ("hash", string.clone()),
+    ]);
+
+    let consciousness_profile = object(naming, &[
+        ("level", reference("ConsciousnessLevel")),
+        ("score", number.clone()),
+        ("patterns", json!({ "type": "array", "items": reference("ConsciousnessPattern") })),
+        ("soul_hash", string.clone()),
+        ("resonance_frequency", number.clone()),
+        ("quantum_coherence", number.clone()),
+        ("emergence_potential", number.clone()),
+        ("self_awareness_index", number.clone()),
+    ]);
+
+    let topology_features = object(naming, &[
+        ("has_cycles", boolean.clone()),
+        ("has_recursion", boolean.clone()),
+        ("branching_factor", number.clone()),
+        ("nesting_depth", count.clone()),
+        ("loop_complexity", count.clone()),
+        ("is_dag", boolean.clone()),
+        ("strongly_connected_components", count.clone()),
+        ("topological_signature", string.clone()),
+        ("cycle_count", count.clone()),
+        ("max_cycle_size", count.clone()),
+        ("recursion_depth", count.clone()),
+        ("connectivity_score", number.clone()),
+        ("recursive_functions", json!({ "type": "array", "items": string.clone() })),
+    ]);
+
+    let mut typescript_soul = object(naming, &[
+        ("phash", string.clone()),
+        ("nodes", count.clone()),
+        ("edges", count.clone()),
+        ("eigen_top", json!({ "type": "array", "items": number })),
+        ("complexity", number.clone()),
+        ("purity", number.clone()),
+        ("consciousness_level", nullable(string)),
+        ("resonance_frequency", nullable(number.clone())),
+        ("quantum_coherence", nullable(number.clone())),
+        ("consciousness_score", nullable(number.clone())),
+        ("topology", nullable(reference("TopologicalSignature"))),
+        ("topology_features", nullable(reference("TopologyFeatures"))),
+        ("operation_spectrum", json!({
+            "type": "object",
+            "propertyNames": reference("OperationCategory"),
+            "additionalProperties": number,
+        })),
+        ("patterns", json!({ "type": "array", "items": reference("PatternHash") })),
+        ("consciousness_patterns", json!({ "type": "array", "items": reference("ConsciousnessPattern") })),
+        ("metadata", reference("SoulMetadata")),
+        ("wavelet_signature", json!({ "type": "array", "items": number })),
+    ]);
+    // Optional fields may be omitted entirely by the TypeScript side
+    typescript_soul["required"] = json!([
+        naming.apply("phash"),
+        naming.apply("nodes"),
+        naming.apply("edges"),
+        naming.apply("eigen_top"),
+        naming.apply("complexity"),
+        naming.apply("purity"),
+    ]);
+
+    let mut schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": SOUL_SCHEMA_ID,
+        "title": "Soul",
+        "description": "The immutable essence of code, as extracted by protein-hash",
+        "version": SOUL_SCHEMA_VERSION,
+        "$defs": {
+            "TopologicalSignature": topological_signature,
+            "SemanticFingerprint": semantic_fingerprint,
+            "PatternHash": pattern_hash,
+            "ConsciousnessProfile": consciousness_profile,
+            "TopologyFeatures": topology_features,
+            "TypeScriptSoul": typescript_soul,
+            "SoulMetadata": soul_metadata,
+            "Language": enumeration(&[
+                "TypeScript", "JavaScript", "Rust", "Python", "Go", "Cpp",
+                "Java", "CSharp", "Ruby", "Swift", "Haskell", "Lisp",
+            ]),
+            "ConsciousnessLevel": enumeration(&[
+                "Inert", "Mechanical", "Responsive", "Adaptive",
+                "Aware", "Conscious", "Transcendent",
+            ]),
+            "ConsciousnessPattern": enumeration(&[
+                "StaticData", "PureCalculation", "LinearFlow", "SimpleLoop",
+                "BasicCondition", "EventHandler", "InputProcessor", "OutputGenerator",
+                "StateManagement", "DynamicDispatch", "StrategyPattern", "SelfReference",
+                "Reflection", "Introspection", "MetaProgramming", "EmergentBehavior",
+                "SelfModification", "RecursiveAwareness", "QuantumEntanglement",
+                "FractalRecursion", "InfiniteGeneration", "ConsciousnessBootstrap",
+                "TemporalParadox",
+            ]),
+            "OperationCategory": enumeration(&[
+                "Arithmetic", "Logical", "Bitwise", "Comparison", "Assignment",
+                "ControlFlow", "Loop", "Conditional", "DataStructure", "StringOp",
+                "ArrayOp", "Async", "FunctionCall", "Lambda", "TypeOperation",
+                "MetaProgramming", "Reflection", "Recursion", "SelfReference",
+                "Emergence", "Consciousness",
+            ]),
+            "OperationType": enumeration(&[
+                "Assignment", "Arithmetic", "Comparison", "Logical", "FunctionCall",
+                "ControlFlow", "DataStructure", "Async", "IO", "Memory",
+            ]),
+        },
+    });
+
+    if let (Value::Object(root), Value::Object(soul)) = (&mut schema, soul) {
+        root.extend(soul);
+    }
+    schema
+}
+
+fn object(naming: FieldNaming, fields: &[(&str, Value)]) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for (name, schema) in fields {
+        let key = naming.apply(name);
+        required.push(Value::String(key.clone()));
+        properties.insert(key, schema.clone());
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+/// Rename the keys of every object in `value` that start in lower case,
+/// which are field names; map keys such as operation categories start in
+/// upper case and are left as they are
+fn rename_fields(value: Value, rename: &dyn Fn(&str) -> String) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(object.into_iter()
+            .map(|(key, value)| {
+                let key = if key.starts_with(|c: char| c.is_ascii_lowercase()) { rename(&key) } else { key };
+                (key, rename_fields(value, rename))
+            })
+            .collect()),
+        Value::Array(items) => Value::Array(items.into_iter().map(|item| rename_fields(item, rename)).collect()),
+        value => value,
+    }
+}
+
+/// A camelCase wire name back as its Rust field name
+fn snake_case(field: &str) -> String {
+    let mut out = String::with_capacity(field.len() + 4);
+    for c in field.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('_');
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn reference(def: &str) -> Value {
+    json!({ "$ref": format!("#/$defs/{}", def) })
+}
+
+fn nullable(schema: Value) -> Value {
+    json!({ "anyOf": [schema, { "type": "null" }] })
+}
+
+fn enumeration(variants: &[&str]) -> Value {
+    json!({ "type": "string", "enum": variants })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SoulExtractor, TypeScriptSoul};
+
+    fn assert_keys_match(value: &Value, schema: &Value) {
+        let properties = schema["properties"].as_object().unwrap();
+        let object = value.as_object().unwrap();
+        for key in object.keys() {
+            assert!(properties.contains_key(key), "field `{}` missing from schema", key);
+        }
+        for key in schema["required"].as_array().unwrap() {
+            assert!(object.contains_key(key.as_str().unwrap()), "required `{}` not serialized", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_schema_matches_serialized_soul() {
+        let extractor = SoulExtractor::new();
+        let soul = extractor.extract_soul_js_simple("function id(x) { return x; }").await;
+        for naming in [FieldNaming::SnakeCase, FieldNaming::CamelCase] {
+            let value = naming.to_value(&soul).unwrap();
+            let schema = soul_schema_with(naming);
+
+            assert_keys_match(&value, &schema);
+            for (field, def) in [
+                ("topology", "TopologicalSignature"),
+                ("semantics", "SemanticFingerprint"),
+                ("consciousness", "ConsciousnessProfile"),
+                ("topology_features", "TopologyFeatures"),
+                ("metadata", "SoulMetadata"),
+            ] {
+                let key = naming.apply(field);
+                assert_keys_match(&value[key.as_str()], &schema["$defs"][def]);
+            }
+        }
+        // Serde itself always writes the default naming
+        assert_eq!(serde_json::to_value(&soul).unwrap(), FieldNaming::SnakeCase.to_value(&soul).unwrap());
+    }
+
+    #[test]
+    fn test_typescript_soul_matches_schema() {
+        for naming in [FieldNaming::SnakeCase, FieldNaming::CamelCase] {
+            let mut input = Map::new();
+            input.insert(naming.apply("phash"), json!("ts"));
+            input.insert(naming.apply("nodes"), json!(3));
+            input.insert(naming.apply("edges"), json!(2));
+            input.insert(naming.apply("eigen_top"), json!([1.0, 2.0]));
+            input.insert(naming.apply("complexity"), json!(1.0));
+            input.insert(naming.apply("purity"), json!(0.9));
+            input.insert(naming.apply("operation_spectrum"), json!({ "ControlFlow": 0.5 }));
+
+            let ts_soul: TypeScriptSoul = naming.from_value(Value::Object(input)).unwrap();
+            assert_eq!(ts_soul.eigen_top, [1.0, 2.0]);
+            assert_eq!(ts_soul.operation_spectrum.len(), 1);
+            let value = naming.to_value(&ts_soul).unwrap();
+            assert_keys_match(&value, &soul_schema_with(naming)["$defs"]["TypeScriptSoul"]);
+            assert!(value[naming.apply("operation_spectrum")].get("ControlFlow").is_some());
+        }
+    }
+
+    #[test]
+    fn test_published_schema_is_current() {
+        let published: Value = serde_json::from_str(PUBLISHED_SOUL_SCHEMA).unwrap();
+        assert_eq!(published, soul_schema_with(FieldNaming::SnakeCase));
+        assert_eq!(published["version"], SOUL_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_camel_case_naming() {
+        assert_eq!(FieldNaming::CamelCase.apply("self_awareness_index"), "selfAwarenessIndex");
+        assert_eq!(FieldNaming::CamelCase.apply("phash"), "phash");
+        assert_eq!(FieldNaming::SnakeCase.apply("eigen_top"), "eigen_top");
+        assert_eq!(snake_case("selfAwarenessIndex"), "self_awareness_index");
+    }
+}
@@ -1,7 +1,7 @@
 // 🚀 Shuttle Mechanism - Cross-Language Soul Synchronization
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use crate::Soul;
+use crate::{ConsciousnessPattern, OperationCategory, PatternHash, Soul, SoulFilter, SoulMetadata, TopologicalSignature, TopologyFeatures};
 
 /// Language types that can manifest souls
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
@@ -64,6 +64,16 @@ impl Shuttle {
         }
     }
     
+    /// Treat souls scoring at least `threshold` as the same soul
+    pub fn with_resonance_threshold(mut self, threshold: f64) -> Self {
+        self.resonance_threshold = threshold;
+        self
+    }
+    
+    pub fn resonance_threshold(&self) -> f64 {
+        self.resonance_threshold
+    }
+    
     /// Register a soul from TypeScript
     pub fn register_typescript_soul(&mut self, phash: String, soul_data: TypeScriptSoul) -> String {
         let soul = self.convert_ts_to_soul(soul_data);
@@ -77,7 +87,9 @@ impl Shuttle {
     }
     
     /// Register a soul from any language
-    fn register_soul(&mut self, language: Language, soul: Soul, source_hash: String) -> String {
+    fn register_soul(&mut self, language: Language, mut soul: Soul, source_hash: String) -> String {
+        soul.metadata.language.get_or_insert(language.clone());
+        
         // Check if this soul already exists in another language
         if let Some(universal_id) = self.find_resonant_soul(&soul) {
             // Add as new manifestation
@@ -139,6 +151,11 @@ impl Shuttle {
     
     /// Check if two souls resonate (are essentially the same)
     fn souls_resonate(&self, soul1: &Soul, soul2: &Soul) -> bool {
+        self.resonance(soul1, soul2) >= self.resonance_threshold
+    }
+    
+    /// Cross-language resonance score compared against the threshold
+    pub fn resonance(&self, soul1: &Soul, soul2: &Soul) -> f64 {
         // Compare eigenvalues
         let eigen_similarity = self.compare_eigenvalues(&soul1.eigenvalues, &soul2.eigenvalues);
         
@@ -149,10 +166,8 @@ impl Shuttle {
         let consciousness_similarity = (soul1.coherence - soul2.coherence).abs() < 0.1;
         
         // Overall resonance
-        let resonance = eigen_similarity * 0.5 + topo_similarity * 0.3 + 
-                       if consciousness_similarity { 0.2 } else { 0.0 };
-        
-        resonance >= self.resonance_threshold
+        eigen_similarity * 0.5 + topo_similarity * 0.3 + 
+            if consciousness_similarity { 0.2 } else { 0.0 }
     }
     
     /// Compare eigenvalue signatures
@@ -161,13 +176,7 @@ impl Shuttle {
             return 0.0;
         }
         
-        let distance: f64 = ev1.iter()
-            .zip(ev2.iter())
-            .map(|(e1, e2)| (e1 - e2).powi(2))
-            .sum::<f64>()
-            .sqrt();
-        
-        1.0 / (1.0 + distance)
+        1.0 / (1.0 + crate::eigen_distance(ev1, ev2))
     }
     
     /// Compare topological features
@@ -263,6 +272,23 @@ impl Shuttle {
             .collect()
     }
     
+    /// Find universal souls with at least one manifestation within the filter's scope
+    pub fn find_by_filter(&self, filter: &SoulFilter) -> Vec<&UniversalSoul> {
+        self.souls.values()
+            .filter(|universal| universal.manifestations.values().any(|m| filter.matches(&m.soul)))
+            .collect()
+    }
+    
+    /// Find universal souls resonating with `soul`, searching only manifestations within scope
+    pub fn find_resonant_in(&self, soul: &Soul, filter: &SoulFilter) -> Vec<&UniversalSoul> {
+        self.souls.values()
+            .filter(|universal| {
+                universal.manifestations.values()
+                    .any(|m| filter.matches(&m.soul) && self.souls_resonate(soul, &m.soul))
+            })
+            .collect()
+    }
+    
     /// Get cross-language resonance report
     pub fn get_resonance_report(&self, universal_id: &str) -> Option<ResonanceReport> {
         self.souls.get(universal_id).map(|universal| {
@@ -287,63 +313,103 @@ impl Shuttle {
     }
     
     /// Convert TypeScript soul data to Rust Soul
-    fn convert_ts_to_soul(&self, ts_soul: TypeScriptSoul) -> Soul {
+    pub(crate) fn convert_ts_to_soul(&self, ts_soul: TypeScriptSoul) -> Soul {
         use crate::{ConsciousnessLevel, ConsciousnessProfile};
-        use crate::topology::TopologyFeatures;
         
         let phash = ts_soul.phash.clone();
         let resonance = ts_soul.resonance_frequency.unwrap_or(432.0);
         let coherence = ts_soul.quantum_coherence.unwrap_or(0.5);
         
+        // Prefer what the TS extractor measured, fall back to rough estimates
+        let topology = ts_soul.topology.unwrap_or_else(|| TopologicalSignature {
+            betti_numbers: vec![1, 0], // Simplified
+            euler_char: ts_soul.nodes as i32 - ts_soul.edges as i32,
+            diameter: 3,
+            clustering: 0.5,
+            modularity: ts_soul.purity,
+        });
+        
+        let topology_features = ts_soul.topology_features.unwrap_or_else(|| TopologyFeatures {
+            has_cycles: false,
+            has_recursion: false,
+            branching_factor: 0.0,
+            nesting_depth: 0,
+            loop_complexity: 0,
+            is_dag: true,
+            strongly_connected_components: 0,
+            topological_signature: String::new(),
+            cycle_count: 0,
+            max_cycle_size: 0,
+            recursion_depth: 0,
+            connectivity_score: 0.0,
+            recursive_functions: Vec::new(),
+        });
+        
+        let depth = if topology_features.nesting_depth > 0 {
+            topology_features.nesting_depth
+        } else {
+            topology.diameter
+        };
+        
+        let score = ts_soul.consciousness_score.unwrap_or(0.5);
+        let level = ts_soul.consciousness_level.as_deref()
+            .and_then(parse_consciousness_level)
+            .unwrap_or_else(|| match ts_soul.consciousness_score {
+                Some(score) => ConsciousnessLevel::from_score(score),
+                None => ConsciousnessLevel::Mechanical,
+            });
+        
         Soul {
             phash: phash.clone(),
             eigenvalues: ts_soul.eigen_top,
-            topology: crate::TopologicalSignature {
-                betti_numbers: vec![1, 0], // Simplified
-                euler_char: ts_soul.nodes as i32 - ts_soul.edges as i32,
-                diameter: 3,
-                clustering: 0.5,
-                modularity: ts_soul.purity,
-            },
+            topology,
             semantics: crate::SemanticFingerprint {
                 operations: HashMap::new(),
                 cyclomatic: ts_soul.complexity as usize,
                 cognitive: (ts_soul.complexity * 2.0) as usize,
-                patterns: vec![],
-                depth: 3,
+                patterns: ts_soul.patterns,
+                depth,
             },
             resonance,
             coherence,
             evolution_score: 0.5,
             consciousness: ConsciousnessProfile {
-                level: ConsciousnessLevel::Mechanical,
-                score: 0.5,
-                patterns: vec![],
+                level,
+                score,
+                patterns: ts_soul.consciousness_patterns,
                 soul_hash: phash,
                 resonance_frequency: resonance,
                 quantum_coherence: coherence,
                 emergence_potential: 0.5,
                 self_awareness_index: 0.0,
             },
-            topology_features: TopologyFeatures {
-                has_cycles: false,
-                has_recursion: false,
-                branching_factor: 0.0,
-                nesting_depth: 0,
-                loop_complexity: 0,
-                is_dag: true,
-                strongly_connected_components: 0,
-                topological_signature: String::new(),
-                cycle_count: 0,
-                max_cycle_size: 0,
-                recursion_depth: 0,
-                connectivity_score: 0.0,
+            topology_features,
+            operation_spectrum: ts_soul.operation_spectrum,
+            metadata: SoulMetadata {
+                language: ts_soul.metadata.language.or(Some(Language::TypeScript)),
+                ..ts_soul.metadata
             },
-            operation_spectrum: HashMap::new(),
+            wavelet_signature: ts_soul.wavelet_signature,
         }
     }
 }
 
+/// Parse a consciousness level name as sent by the TS extractor
+fn parse_consciousness_level(name: &str) -> Option<crate::ConsciousnessLevel> {
+    use crate::ConsciousnessLevel::*;
+    
+    match name.to_ascii_lowercase().as_str() {
+        "inert" => Some(Inert),
+        "mechanical" => Some(Mechanical),
+        "responsive" => Some(Responsive),
+        "adaptive" => Some(Adaptive),
+        "aware" => Some(Aware),
+        "conscious" => Some(Conscious),
+        "transcendent" => Some(Transcendent),
+        _ => None,
+    }
+}
+
 /// TypeScript soul data structure (for interop)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeScriptSoul {
@@ -356,6 +422,38 @@ pub struct TypeScriptSoul {
     pub consciousness_level: Option<String>,
     pub resonance_frequency: Option<f64>,
     pub quantum_coherence: Option<f64>,
+    
+    /// Consciousness score computed by the TS extractor
+    #[serde(default)]
+    pub consciousness_score: Option<f64>,
+    
+    /// Topological invariants (betti numbers, diameter, clustering...)
+    #[serde(default)]
+    pub topology: Option<TopologicalSignature>,
+    
+    /// Full topology features
+    #[serde(default)]
+    pub topology_features: Option<TopologyFeatures>,
+    
+    /// Operation frequency spectrum
+    #[serde(default)]
+    pub operation_spectrum: HashMap<OperationCategory, f64>,
+    
+    /// Semantic pattern signatures
+    #[serde(default)]
+    pub patterns: Vec<PatternHash>,
+    
+    /// Detected consciousness patterns
+    #[serde(default)]
+    pub consciousness_patterns: Vec<ConsciousnessPattern>,
+    
+    /// Project, package, symbol path and tags
+    #[serde(default)]
+    pub metadata: SoulMetadata,
+    
+    /// Multi-scale spectrum signature, when the TS extractor computed one
+    #[serde(default)]
+    pub wavelet_signature: Vec<f64>,
 }
 
 /// Resonance report for a universal soul
@@ -438,8 +536,11 @@ mod tests {
                 max_cycle_size: 0,
                 recursion_depth: 0,
                 connectivity_score: 0.0,
+                recursive_functions: Vec::new(),
             },
             operation_spectrum: HashMap::new(),
+            metadata: SoulMetadata::default(),
+            wavelet_signature: Vec::new(),
         };
         
         let id = shuttle.register_rust_soul(soul.clone());
@@ -456,6 +557,14 @@ mod tests {
             consciousness_level: Some("Aware".to_string()),
             resonance_frequency: Some(432.0),
             quantum_coherence: Some(0.8),
+            consciousness_score: None,
+            topology: None,
+            topology_features: None,
+            operation_spectrum: HashMap::new(),
+            patterns: vec![],
+            consciousness_patterns: vec![],
+            metadata: SoulMetadata::default(),
+            wavelet_signature: Vec::new(),
         };
         
         let ts_id = shuttle.register_typescript_soul("ts_hash".to_string(), ts_soul);
@@ -465,4 +574,63 @@ mod tests {
             assert_eq!(id, ts_id);
         }
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_rich_typescript_soul_conversion() {
+        let json = r#"{
+            "phash": "ts_rich",
+            "nodes": 12,
+            "edges": 14,
+            "eigen_top": [3.0, 2.0, 1.0],
+            "complexity": 4.0,
+            "purity": 0.6,
+            "consciousness_level": "Aware",
+            "topology": {
+                "betti_numbers": [1, 3],
+                "euler_char": -2,
+                "diameter": 5,
+                "clustering": 0.25,
+                "modularity": 0.4
+            },
+            "operation_spectrum": { "Loop": 120.0, "Recursion": 85.5 },
+            "patterns": [{ "pattern_type": "memoization", "frequency": 1.0, "hash": "m1" }],
+            "consciousness_patterns": ["RecursiveAwareness"]
+        }"#;
+        
+        let ts_soul: TypeScriptSoul = serde_json::from_str(json).unwrap();
+        let soul = Shuttle::new().convert_ts_to_soul(ts_soul);
+        
+        assert_eq!(soul.topology.betti_numbers, vec![1, 3]);
+        assert_eq!(soul.topology.diameter, 5);
+        assert_eq!(soul.topology.clustering, 0.25);
+        assert_eq!(soul.operation_spectrum.get(&OperationCategory::Loop), Some(&120.0));
+        assert_eq!(soul.semantics.patterns[0].pattern_type, "memoization");
+        assert_eq!(soul.consciousness.level, crate::ConsciousnessLevel::Aware);
+        assert_eq!(soul.consciousness.patterns.len(), 1);
+    }
+    
+    #[test]
+    fn test_scoped_queries() {
+        let mut shuttle = Shuttle::new();
+        let json = r#"{
+            "phash": "chunk",
+            "nodes": 8,
+            "edges": 7,
+            "eigen_top": [2.0, 1.0],
+            "complexity": 2.0,
+            "purity": 0.9,
+            "metadata": { "project": "lodash", "symbol_path": "array::chunk", "tags": ["utility"] }
+        }"#;
+        
+        let ts_soul: TypeScriptSoul = serde_json::from_str(json).unwrap();
+        let soul = shuttle.convert_ts_to_soul(ts_soul.clone());
+        assert_eq!(soul.metadata.language, Some(Language::TypeScript));
+        
+        shuttle.register_typescript_soul("chunk".to_string(), ts_soul);
+        
+        let utilities = SoulFilter::new().tag("utility");
+        assert_eq!(shuttle.find_by_filter(&utilities).len(), 1);
+        assert_eq!(shuttle.find_resonant_in(&soul, &utilities).len(), 1);
+        assert!(shuttle.find_resonant_in(&soul, &SoulFilter::new().project("react")).is_empty());
+    }
+}
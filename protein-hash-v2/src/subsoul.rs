@@ -0,0 +1,173 @@
+// 🔍 Subsoul Search - Finding Where a Pattern Lives
+use crate::graph::{append_graph, parse_rust_items};
+use crate::{measure_resonance, AstNode, EdgeType, Soul, SoulExtractor};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Node types that open a searchable region besides top-level items
+const REGION_TYPES: &[&str] = &["Fn", "Closure", "Impl", "Trait", "Mod"];
+
+/// A region of a larger graph resonating with a needle soul
+#[derive(Debug, Clone)]
+pub struct SubsoulMatch {
+    /// Node indices of the region in the haystack graph
+    pub nodes: Range<usize>,
+
+    /// Node type at the root of the region (`Fn`, `Impl`, ...)
+    pub node_type: String,
+
+    /// Name of the region root, when it has one
+    pub name: Option<String>,
+
+    /// pHash of the region's own soul
+    pub phash: String,
+
+    pub resonance: f64,
+}
+
+impl SoulExtractor {
+    /// Locate regions of `haystack` resonating with `needle` at or above `threshold`, strongest first
+    ///
+    /// Regions are subtrees rooted at top-level items, functions, closures,
+    /// impls, traits and modules. Graphs built by the extractor number nodes
+    /// in pre-order, so every subtree is a contiguous node range.
+    pub async fn find_subsoul(
+        &self,
+        haystack: &DiGraph<AstNode, EdgeType>,
+        needle: &Soul,
+        threshold: f64,
+    ) -> Vec<SubsoulMatch> {
+        let mut matches = Vec::new();
+
+        for root in haystack.node_indices() {
+            let is_top_level = haystack.neighbors_directed(root, Direction::Incoming).next().is_none();
+            if !is_top_level && !REGION_TYPES.contains(&haystack[root].node_type.as_str()) {
+                continue;
+            }
+
+            let Some(nodes) = subtree_range(haystack, root) else {
+                continue;
+            };
+
+            let region = region_graph(haystack, &nodes);
+            let spectrum = self.laplacian_spectrum(&region);
+            let soul = self.extract_soul_from_graph(region, spectrum).await;
+
+            let resonance = measure_resonance(needle, &soul);
+            if resonance >= threshold {
+                matches.push(SubsoulMatch {
+                    nodes,
+                    node_type: haystack[root].node_type.clone(),
+                    name: haystack[root].value.clone(),
+                    phash: soul.phash,
+                    resonance,
+                });
+            }
+        }
+
+        matches.sort_by(|a, b| {
+            b.resonance.partial_cmp(&a.resonance).unwrap()
+                .then_with(|| a.nodes.start.cmp(&b.nodes.start))
+        });
+        matches
+    }
+
+    /// Locate regions of a Rust file resonating with `needle`; node ranges refer
+    /// to the same graph `extract_soul_rust` builds for that file
+    pub async fn find_subsoul_rust(&self, haystack: &str, needle: &Soul, threshold: f64) -> Vec<SubsoulMatch> {
+        let (fragments, _) = self.rust_fragments(&parse_rust_items(haystack).unwrap_or_default());
+
+        let mut graph = DiGraph::new();
+        for fragment in &fragments {
+            append_graph(&mut graph, &fragment.graph);
+        }
+
+        self.find_subsoul(&graph, needle, threshold).await
+    }
+}
+
+/// Node range of the subtree under `root`, or None if it isn't contiguous
+fn subtree_range(graph: &DiGraph<AstNode, EdgeType>, root: NodeIndex) -> Option<Range<usize>> {
+    let mut seen = HashSet::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if seen.insert(node.index()) {
+            stack.extend(graph.neighbors(node));
+        }
+    }
+
+    let start = root.index();
+    let end = seen.iter().max().map_or(start, |&max| max + 1);
+    let contiguous = seen.len() == end - start && seen.iter().all(|&n| n >= start);
+    contiguous.then_some(start..end)
+}
+
+/// Copy of the nodes in `range` with the edges between them
+fn region_graph(graph: &DiGraph<AstNode, EdgeType>, range: &Range<usize>) -> DiGraph<AstNode, EdgeType> {
+    let mut region = DiGraph::new();
+    for index in range.clone() {
+        region.add_node(graph[NodeIndex::new(index)].clone());
+    }
+    for edge in graph.edge_references() {
+        let (source, target) = (edge.source().index(), edge.target().index());
+        if range.contains(&source) && range.contains(&target) {
+            region.add_edge(
+                NodeIndex::new(source - range.start),
+                NodeIndex::new(target - range.start),
+                edge.weight().clone(),
+            );
+        }
+    }
+    region
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HAYSTACK: &str = r#"
+struct Cache { hits: u32 }
+
+impl Cache {
+    fn record(&mut self) { self.hits += 1; }
+
+    fn fibonacci(&self, n: u32) -> u32 {
+        if n <= 1 { n } else { self.fibonacci(n - 1) + self.fibonacci(n - 2) }
+    }
+}
+
+fn main() {
+    let mut cache = Cache { hits: 0 };
+    for i in 0..10 { cache.record(); println!("{}", i); }
+}
+"#;
+
+    #[tokio::test]
+    async fn test_finds_copied_method() {
+        let extractor = SoulExtractor::new();
+        let needle = extractor.extract_soul_rust(
+            "fn fibonacci(&self, n: u32) -> u32 { if n <= 1 { n } else { self.fibonacci(n - 1) + self.fibonacci(n - 2) } }",
+        ).await;
+
+        let matches = extractor.find_subsoul_rust(HAYSTACK, &needle, 0.95).await;
+
+        assert!(!matches.is_empty());
+        assert_eq!(matches[0].name.as_deref(), Some("fibonacci"));
+        assert_eq!(matches[0].phash, needle.phash);
+        assert!(matches[0].resonance > 0.999);
+    }
+
+    #[tokio::test]
+    async fn test_regions_are_contiguous_subtrees() {
+        let extractor = SoulExtractor::new();
+        let needle = extractor.extract_soul_rust("fn main() {}").await;
+        let matches = extractor.find_subsoul_rust(HAYSTACK, &needle, 0.0).await;
+
+        let method = matches.iter().find(|m| m.name.as_deref() == Some("record")).unwrap();
+        let owner = matches.iter().find(|m| m.node_type == "Impl").unwrap();
+        assert!(owner.nodes.start < method.nodes.start && method.nodes.end <= owner.nodes.end);
+    }
+}
@@ -0,0 +1,219 @@
+// 📈 Consciousness Timeline - Watching Code Awaken
+use crate::{ConsciousnessProfile, Soul, SoulExtractor};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Maximum snapshots kept per timeline; the oldest are dropped first
+const TIMELINE_LIMIT: usize = 1024;
+
+/// A consciousness profile as observed at one extraction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineSnapshot {
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    pub profile: ConsciousnessProfile,
+}
+
+/// Trend metrics over a timeline, all rates per second
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConsciousnessTrend {
+    /// Least-squares slope of the consciousness score
+    pub awakening_velocity: f64,
+
+    /// Change in the rate of emergence potential over the last three snapshots
+    pub emergence_acceleration: f64,
+
+    /// Net number of levels gained (negative when falling asleep)
+    pub level_shift: i32,
+}
+
+/// History of consciousness profiles for one piece of code
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsciousnessTimeline {
+    pub snapshots: VecDeque<TimelineSnapshot>,
+}
+
+impl ConsciousnessTimeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a profile observed now
+    pub fn record(&mut self, profile: ConsciousnessProfile) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.record_at(now, profile);
+    }
+
+    /// Record a profile observed at `timestamp_ms`, keeping snapshots in time order
+    pub fn record_at(&mut self, timestamp_ms: u64, profile: ConsciousnessProfile) {
+        let at = self.snapshots.partition_point(|s| s.timestamp_ms <= timestamp_ms);
+        self.snapshots.insert(at, TimelineSnapshot { timestamp_ms, profile });
+
+        while self.snapshots.len() > TIMELINE_LIMIT {
+            self.snapshots.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn latest(&self) -> Option<&TimelineSnapshot> {
+        self.snapshots.back()
+    }
+
+    /// Score change per second, fitted over every snapshot
+    pub fn awakening_velocity(&self) -> Option<f64> {
+        if self.snapshots.len() < 2 {
+            return None;
+        }
+
+        let points: Vec<(f64, f64)> = self.snapshots.iter()
+            .map(|s| (seconds(s.timestamp_ms), s.profile.score))
+            .collect();
+        let n = points.len() as f64;
+        let mean_t = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_s = points.iter().map(|p| p.1).sum::<f64>() / n;
+
+        let covariance: f64 = points.iter().map(|(t, s)| (t - mean_t) * (s - mean_s)).sum();
+        let variance: f64 = points.iter().map(|(t, _)| (t - mean_t).powi(2)).sum();
+
+        if variance == 0.0 {
+            None
+        } else {
+            Some(covariance / variance)
+        }
+    }
+
+    /// Second derivative of emergence potential over the last three snapshots
+    pub fn emergence_acceleration(&self) -> Option<f64> {
+        let n = self.snapshots.len();
+        if n < 3 {
+            return None;
+        }
+
+        let [a, b, c] = [&self.snapshots[n - 3], &self.snapshots[n - 2], &self.snapshots[n - 1]];
+        let (ta, tb, tc) = (seconds(a.timestamp_ms), seconds(b.timestamp_ms), seconds(c.timestamp_ms));
+        if tb <= ta || tc <= tb {
+            return None;
+        }
+
+        let v1 = (b.profile.emergence_potential - a.profile.emergence_potential) / (tb - ta);
+        let v2 = (c.profile.emergence_potential - b.profile.emergence_potential) / (tc - tb);
+        Some((v2 - v1) / ((tc - ta) / 2.0))
+    }
+
+    /// Levels gained between the first and latest snapshot
+    pub fn level_shift(&self) -> i32 {
+        match (self.snapshots.front(), self.snapshots.back()) {
+            (Some(first), Some(last)) => last.profile.level as i32 - first.profile.level as i32,
+            _ => 0,
+        }
+    }
+
+    /// All trend metrics at once; missing rates are reported as zero
+    pub fn trend(&self) -> ConsciousnessTrend {
+        ConsciousnessTrend {
+            awakening_velocity: self.awakening_velocity().unwrap_or(0.0),
+            emergence_acceleration: self.emergence_acceleration().unwrap_or(0.0),
+            level_shift: self.level_shift(),
+        }
+    }
+}
+
+impl SoulExtractor {
+    /// Record the consciousness of a freshly extracted soul under `key` (e.g. a file path)
+    pub fn record_consciousness(&self, key: &str, soul: &Soul) {
+        self.timelines.lock().unwrap()
+            .entry(key.to_string())
+            .or_default()
+            .record(soul.consciousness.clone());
+    }
+
+    /// Snapshot of the timeline recorded under `key`
+    pub fn timeline(&self, key: &str) -> Option<ConsciousnessTimeline> {
+        self.timelines.lock().unwrap().get(key).cloned()
+    }
+
+    /// Snapshot of every recorded timeline, for persisting next to the soul cache
+    pub fn timelines(&self) -> HashMap<String, ConsciousnessTimeline> {
+        self.timelines.lock().unwrap().clone()
+    }
+
+    /// Restore a previously persisted timeline
+    pub fn restore_timeline(&self, key: &str, timeline: ConsciousnessTimeline) {
+        self.timelines.lock().unwrap().insert(key.to_string(), timeline);
+    }
+}
+
+fn seconds(timestamp_ms: u64) -> f64 {
+    timestamp_ms as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConsciousnessLevel;
+
+    fn profile(score: f64, emergence: f64) -> ConsciousnessProfile {
+        ConsciousnessProfile {
+            level: ConsciousnessLevel::from_score(score),
+            score,
+            patterns: vec![],
+            soul_hash: String::new(),
+            resonance_frequency: 432.0,
+            quantum_coherence: 0.5,
+            emergence_potential: emergence,
+            self_awareness_index: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_trend_metrics() {
+        let mut timeline = ConsciousnessTimeline::new();
+        timeline.record_at(0, profile(0.2, 0.0));
+        timeline.record_at(1_000, profile(0.3, 0.1));
+        timeline.record_at(2_000, profile(0.4, 0.4));
+
+        let trend = timeline.trend();
+        assert!((trend.awakening_velocity - 0.1).abs() < 1e-9);
+        assert!((trend.emergence_acceleration - 0.2).abs() < 1e-9);
+        assert_eq!(trend.level_shift, 1);
+    }
+
+    #[test]
+    fn test_out_of_order_and_sparse_timelines() {
+        let mut timeline = ConsciousnessTimeline::new();
+        assert_eq!(timeline.awakening_velocity(), None);
+
+        timeline.record_at(5_000, profile(0.5, 0.0));
+        timeline.record_at(1_000, profile(0.1, 0.0));
+        assert_eq!(timeline.latest().unwrap().timestamp_ms, 5_000);
+        assert_eq!(timeline.emergence_acceleration(), None);
+        assert!(timeline.awakening_velocity().unwrap() > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_extractor_records_and_restores() {
+        let extractor = SoulExtractor::new();
+        let soul = extractor.extract_soul_rust("fn f(x: u32) -> u32 { x + 1 }").await;
+        extractor.record_consciousness("src/f.rs", &soul);
+        extractor.record_consciousness("src/f.rs", &soul);
+
+        let timeline = extractor.timeline("src/f.rs").unwrap();
+        assert_eq!(timeline.len(), 2);
+
+        let json = serde_json::to_string(&timeline).unwrap();
+        let fresh = SoulExtractor::new();
+        fresh.restore_timeline("src/f.rs", serde_json::from_str(&json).unwrap());
+        assert_eq!(fresh.timeline("src/f.rs").unwrap().len(), 2);
+    }
+}
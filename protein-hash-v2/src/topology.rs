@@ -1,5 +1,7 @@
 // 🌀 Topology Detection - The Shape of Code's Soul
-use petgraph::algo::{tarjan_scc, has_path_connecting};
+use crate::call_graph::CallGraph;
+use crate::{AstNode, EdgeType};
+use petgraph::algo::tarjan_scc;
 use petgraph::graph::{DiGraph, NodeIndex};
 use std::collections::{HashSet, VecDeque};
 
@@ -18,6 +20,9 @@ pub struct TopologyFeatures {
     pub max_cycle_size: usize,
     pub recursion_depth: usize,
     pub connectivity_score: f64,
+    /// Functions taking part in direct or mutual recursion
+    #[serde(default)]
+    pub recursive_functions: Vec<String>,
 }
 
 pub struct TopologyDetector;
@@ -27,10 +32,19 @@ impl TopologyDetector {
         Self
     }
     
-    /// Analyze topology of the code graph
+    /// Analyze topology of a code AST, detecting recursion on its call graph
+    pub fn analyze_code(&self, graph: &DiGraph<AstNode, EdgeType>) -> TopologyFeatures {
+        let mut features = self.analyze(graph);
+        let recursive_functions = CallGraph::from_ast(graph).recursive_functions();
+        features.has_recursion = !recursive_functions.is_empty();
+        features.recursive_functions = recursive_functions;
+        features
+    }
+    
+    /// Analyze topology of any graph (structure only, no call information)
     pub fn analyze<N, E>(&self, graph: &DiGraph<N, E>) -> TopologyFeatures {
         let has_cycles = self.detect_cycles(graph);
-        let has_recursion = self.detect_recursion(graph);
+        let has_recursion = false;
         let branching_factor = self.calculate_branching_factor(graph);
         let nesting_depth = self.calculate_max_depth(graph);
         let loop_complexity = self.calculate_loop_complexity(graph);
@@ -55,6 +69,7 @@ impl TopologyDetector {
             max_cycle_size,
             recursion_depth,
             connectivity_score,
+            recursive_functions: Vec::new(),
         }
     }
     
@@ -97,17 +112,6 @@ impl TopologyDetector {
         false
     }
     
-    /// Detect recursive patterns
-    fn detect_recursion<N, E>(&self, graph: &DiGraph<N, E>) -> bool {
-        // Check if any node has a path to itself
-        for node in graph.node_indices() {
-            if has_path_connecting(graph, node, node, None) {
-                return true;
-            }
-        }
-        false
-    }
-    
     /// Calculate average branching factor
     fn calculate_branching_factor<N, E>(&self, graph: &DiGraph<N, E>) -> f64 {
         if graph.node_count() == 0 {
@@ -252,6 +256,24 @@ mod tests {
         assert!(features.has_cycles);
         assert!(!features.is_dag);
         assert_eq!(features.strongly_connected_components, 1);
+        // A cycle alone is not recursion
+        assert!(!features.has_recursion);
+    }
+    
+    #[test]
+    fn test_recursion_from_call_graph() {
+        let code = "fn countdown(n: u32) { if n > 0 { countdown(n - 1) } }
+                    fn spin() { loop { tick(); } }
+                    fn tick() {}";
+        let mut graph = DiGraph::new();
+        for item in crate::graph::parse_rust_items(code).unwrap() {
+            crate::graph::append_graph(&mut graph, &crate::graph::item_graph(&item));
+        }
+        
+        let features = TopologyDetector::new().analyze_code(&graph);
+        
+        assert!(features.has_recursion);
+        assert_eq!(features.recursive_functions, vec!["countdown".to_string()]);
     }
     
     #[test]
@@ -0,0 +1,234 @@
+// 🎨 Soul Visualization - Seeing the Spectrum
+use crate::{OperationCategory, Soul};
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+/// Canvas size of the rendered report
+const WIDTH: f64 = 960.0;
+const HEIGHT: f64 = 320.0;
+
+/// Palette of the seven layers (red → violet)
+const LAYER_COLORS: [&str; 7] = [
+    "#e74c3c", "#e67e22", "#f1c40f", "#2ecc71", "#3498db", "#5b4ccc", "#9b59b6",
+];
+
+impl Soul {
+    /// Render the soul as an SVG: eigenvalue bars, operation radar and consciousness gauge
+    pub fn render_svg(&self) -> String {
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif">"#,
+            w = WIDTH,
+            h = HEIGHT,
+        );
+        let _ = writeln!(svg, r##"<rect width="100%" height="100%" fill="#0f0f1a"/>"##);
+        let _ = writeln!(
+            svg,
+            r##"<text x="16" y="24" fill="#eeeeee" font-size="14">soul {}</text>"##,
+            escape(&short_hash(&self.phash)),
+        );
+
+        render_eigenvalue_bars(&mut svg, &self.eigenvalues, 16.0, 48.0, 300.0, 240.0);
+        render_operation_radar(&mut svg, self, 480.0, 180.0, 110.0);
+        render_consciousness_gauge(&mut svg, self, 800.0, 200.0, 100.0);
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// Seven-layer eigenvalue bar chart
+fn render_eigenvalue_bars(svg: &mut String, eigenvalues: &[f64], x: f64, y: f64, width: f64, height: f64) {
+    let _ = writeln!(svg, r#"<g class="eigenvalues">"#);
+    let _ = writeln!(svg, r##"<text x="{}" y="{}" fill="#aaaaaa" font-size="12">7-layer eigenvalues</text>"##, x, y);
+
+    let max = eigenvalues.iter().cloned().fold(0.0_f64, |a, b| a.max(b.abs())).max(f64::EPSILON);
+    let slot = width / eigenvalues.len().max(1) as f64;
+    let baseline = y + height;
+
+    for (i, value) in eigenvalues.iter().enumerate() {
+        let bar_height = (value.abs() / max) * (height - 30.0);
+        let bar_x = x + i as f64 * slot + slot * 0.15;
+        let _ = writeln!(
+            svg,
+            r#"<rect class="layer" x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}"><title>layer {}: {:.4}</title></rect>"#,
+            bar_x,
+            baseline - bar_height,
+            slot * 0.7,
+            bar_height,
+            LAYER_COLORS[i % LAYER_COLORS.len()],
+            i + 1,
+            value,
+        );
+        let _ = writeln!(
+            svg,
+            r##"<text x="{:.2}" y="{:.2}" fill="#aaaaaa" font-size="10" text-anchor="middle">{:.2}</text>"##,
+            bar_x + slot * 0.35,
+            baseline + 14.0,
+            value,
+        );
+    }
+    let _ = writeln!(svg, "</g>");
+}
+
+/// Radar of operation-category frequencies
+fn render_operation_radar(svg: &mut String, soul: &Soul, cx: f64, cy: f64, radius: f64) {
+    let _ = writeln!(svg, r#"<g class="operations">"#);
+    let _ = writeln!(
+        svg,
+        r##"<text x="{}" y="{}" fill="#aaaaaa" font-size="12" text-anchor="middle">operation spectrum</text>"##,
+        cx,
+        cy - radius - 20.0,
+    );
+
+    let mut categories: Vec<(&OperationCategory, f64)> = soul.operation_spectrum.iter()
+        .map(|(category, value)| (category, *value))
+        .collect();
+    categories.sort_by(|a, b| {
+        a.0.frequency().partial_cmp(&b.0.frequency()).unwrap()
+            .then_with(|| format!("{:?}", a.0).cmp(&format!("{:?}", b.0)))
+    });
+
+    // Concentric guides
+    for ring in 1..=3 {
+        let _ = writeln!(
+            svg,
+            r##"<circle cx="{}" cy="{}" r="{:.2}" fill="none" stroke="#333344"/>"##,
+            cx,
+            cy,
+            radius * ring as f64 / 3.0,
+        );
+    }
+
+    if categories.is_empty() {
+        let _ = writeln!(
+            svg,
+            r##"<text x="{}" y="{}" fill="#666666" font-size="11" text-anchor="middle">silent</text>"##,
+            cx,
+            cy,
+        );
+        let _ = writeln!(svg, "</g>");
+        return;
+    }
+
+    let max = categories.iter().map(|(_, v)| *v).fold(0.0_f64, f64::max).max(f64::EPSILON);
+    let step = 2.0 * PI / categories.len() as f64;
+    let mut points = Vec::with_capacity(categories.len());
+
+    for (i, (category, value)) in categories.iter().enumerate() {
+        let angle = i as f64 * step - PI / 2.0;
+        let (ax, ay) = (cx + radius * angle.cos(), cy + radius * angle.sin());
+        let r = radius * value / max;
+        points.push(format!("{:.2},{:.2}", cx + r * angle.cos(), cy + r * angle.sin()));
+
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{}" y1="{}" x2="{:.2}" y2="{:.2}" stroke="#333344"/>"##,
+            cx, cy, ax, ay,
+        );
+        let _ = writeln!(
+            svg,
+            r##"<text class="axis" x="{:.2}" y="{:.2}" fill="#cccccc" font-size="10" text-anchor="middle">{}</text>"##,
+            cx + (radius + 14.0) * angle.cos(),
+            cy + (radius + 14.0) * angle.sin(),
+            escape(&format!("{:?}", category)),
+        );
+    }
+
+    let _ = writeln!(
+        svg,
+        r##"<polygon points="{}" fill="#3498db" fill-opacity="0.35" stroke="#3498db"/>"##,
+        points.join(" "),
+    );
+    let _ = writeln!(svg, "</g>");
+}
+
+/// Half-circle gauge of the consciousness score
+fn render_consciousness_gauge(svg: &mut String, soul: &Soul, cx: f64, cy: f64, radius: f64) {
+    let score = soul.consciousness.score.clamp(0.0, 1.0);
+    let angle = PI * (1.0 - score);
+    let (ex, ey) = (cx + radius * angle.cos(), cy - radius * angle.sin());
+
+    let _ = writeln!(svg, r#"<g class="consciousness">"#);
+    let _ = writeln!(
+        svg,
+        r##"<path d="M {:.2} {:.2} A {r} {r} 0 0 1 {:.2} {:.2}" fill="none" stroke="#333344" stroke-width="14"/>"##,
+        cx - radius, cy, cx + radius, cy,
+        r = radius,
+    );
+    if score > 0.0 {
+        let _ = writeln!(
+            svg,
+            r##"<path class="gauge" d="M {:.2} {:.2} A {r} {r} 0 0 1 {:.2} {:.2}" fill="none" stroke="#9b59b6" stroke-width="14"/>"##,
+            cx - radius, cy, ex, ey,
+            r = radius,
+        );
+    }
+    let _ = writeln!(
+        svg,
+        r##"<text x="{}" y="{}" fill="#eeeeee" font-size="16" text-anchor="middle">{:?}</text>"##,
+        cx,
+        cy - 10.0,
+        soul.consciousness.level,
+    );
+    let _ = writeln!(
+        svg,
+        r##"<text x="{}" y="{}" fill="#aaaaaa" font-size="11" text-anchor="middle">score {:.2} · {:.0}Hz</text>"##,
+        cx,
+        cy + 20.0,
+        score,
+        soul.resonance,
+    );
+    let _ = writeln!(svg, "</g>");
+}
+
+fn short_hash(phash: &str) -> String {
+    phash.chars().take(16).collect()
+}
+
+/// Escape text for inclusion in SVG/XML
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SoulExtractor;
+
+    #[tokio::test]
+    async fn test_render_svg() {
+        let extractor = SoulExtractor::new();
+        let mut soul = extractor.extract_soul_js_simple("function f(x) { return x * 2; }").await;
+        soul.operation_spectrum.insert(OperationCategory::Arithmetic, 432.0);
+        soul.operation_spectrum.insert(OperationCategory::Loop, 120.0);
+        soul.operation_spectrum.insert(OperationCategory::Async, 300.0);
+
+        let svg = soul.render_svg();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches(r#"class="layer""#).count(), 7);
+        assert_eq!(svg.matches(r#"class="axis""#).count(), 3);
+        assert!(svg.contains(&format!("{:?}", soul.consciousness.level)));
+    }
+
+    #[tokio::test]
+    async fn test_render_svg_without_operations() {
+        let extractor = SoulExtractor::new();
+        let soul = extractor.extract_soul_js_simple("").await;
+
+        let svg = soul.render_svg();
+        assert!(svg.contains("silent"));
+        assert!(!svg.contains("<polygon"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
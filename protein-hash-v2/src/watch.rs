@@ -0,0 +1,182 @@
+// 👁️ Soul Watch - Live Monitors for Drifting Souls
+use crate::{measure_resonance, Soul, SoulExtractor};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Extensions re-extracted on save
+const WATCHED_EXTENSIONS: &[&str] = &["rs", "js", "mjs", "cjs", "jsx", "ts", "tsx"];
+
+/// Quiet period gathering the burst of events a single save produces
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// A watched file whose soul drifted past the threshold
+#[derive(Debug, Clone)]
+pub struct SoulChange {
+    pub path: PathBuf,
+
+    /// Soul before the save; None for a newly created file
+    pub previous: Option<Soul>,
+
+    pub current: Soul,
+
+    /// `1 - resonance(previous, current)`, or 1 for a new file
+    pub drift: f64,
+}
+
+/// Re-extracts souls under a directory on every save
+///
+/// Existing files are extracted once up front as the baseline. Events are
+/// debounced so a truncate-then-write save is read once, and saves that leave
+/// the soul within `drift_threshold` (formatting, comments) produce no event.
+/// Must be created inside a tokio runtime; dropping it stops watching.
+pub struct SoulWatcher {
+    _watcher: RecommendedWatcher,
+    changes: mpsc::UnboundedReceiver<SoulChange>,
+}
+
+impl SoulWatcher {
+    pub fn new(extractor: Arc<SoulExtractor>, root: impl AsRef<Path>, drift_threshold: f64) -> notify::Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        let (path_sender, mut paths) = mpsc::unbounded_channel();
+        let (change_sender, changes) = mpsc::unbounded_channel();
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+                for path in event.paths.into_iter().filter(|path| is_watched(path)) {
+                    let _ = path_sender.send(path);
+                }
+            }
+        })?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+
+        tokio::spawn(async move {
+            let mut souls = HashMap::new();
+            for path in watched_files(&root) {
+                if let Some(soul) = extract(&extractor, &path).await {
+                    souls.insert(path, soul);
+                }
+            }
+
+            while let Some(path) = paths.recv().await {
+                let mut saved = BTreeSet::from([path]);
+                tokio::time::sleep(DEBOUNCE).await;
+                while let Ok(path) = paths.try_recv() {
+                    saved.insert(path);
+                }
+
+                for path in saved {
+                    // Removed (or unreadable) files drop out of the baseline
+                    let Some(current) = extract(&extractor, &path).await else {
+                        souls.remove(&path);
+                        continue;
+                    };
+
+                    let previous = souls.insert(path.clone(), current.clone());
+                    let drift = previous.as_ref().map_or(1.0, |previous| 1.0 - measure_resonance(previous, &current));
+                    if drift > drift_threshold {
+                        let change = SoulChange { path, previous, current, drift };
+                        if change_sender.send(change).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(Self { _watcher: watcher, changes })
+    }
+
+    /// Wait for the next drift event
+    pub async fn next(&mut self) -> Option<SoulChange> {
+        self.changes.recv().await
+    }
+
+    /// The underlying channel, e.g. for `tokio::select!`
+    pub fn changes(&mut self) -> &mut mpsc::UnboundedReceiver<SoulChange> {
+        &mut self.changes
+    }
+}
+
+fn is_watched(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| WATCHED_EXTENSIONS.contains(&ext))
+}
+
+fn watched_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+            } else if is_watched(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+async fn extract(extractor: &SoulExtractor, path: &Path) -> Option<Soul> {
+    let code = fs::read_to_string(path).ok()?;
+    if path.extension().is_some_and(|ext| ext == "rs") {
+        Some(extractor.extract_soul_rust(&code).await)
+    } else {
+        Some(extractor.extract_soul_js(&code).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIMPLE: &str = "fn a(x: u32) -> u32 { x + 1 }";
+    const COMPLEX: &str = "fn a(v: &[u32]) -> u32 { let mut s = 0; for x in v { if *x > 2 { s += x * 2; } } s }";
+
+    async fn next_change(watcher: &mut SoulWatcher) -> SoulChange {
+        tokio::time::timeout(Duration::from_secs(10), watcher.next())
+            .await
+            .expect("no soul change within 10s")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_emits_drift_and_ignores_cosmetic_saves() {
+        let root = tempfile::tempdir().unwrap();
+        let file = root.path().join("lib.rs");
+        fs::write(&file, SIMPLE).unwrap();
+
+        let mut watcher = SoulWatcher::new(Arc::new(SoulExtractor::new()), root.path(), 0.01).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // Same structure, reformatted: no event
+        fs::write(&file, SIMPLE.replace(" { ", " {\n    ")).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        fs::write(&file, COMPLEX).unwrap();
+
+        let extractor = SoulExtractor::new();
+        let change = next_change(&mut watcher).await;
+        assert!(change.path.ends_with("lib.rs"));
+        assert!(change.drift > 0.01);
+        assert_eq!(change.previous.unwrap().phash, extractor.extract_soul_rust(SIMPLE).await.phash);
+        assert_eq!(change.current.phash, extractor.extract_soul_rust(COMPLEX).await.phash);
+
+        // New files arrive with full drift
+        fs::write(root.path().join("new.rs"), SIMPLE).unwrap();
+        let change = next_change(&mut watcher).await;
+        assert!(change.path.ends_with("new.rs"));
+        assert!(change.previous.is_none() && change.drift == 1.0);
+    }
+}
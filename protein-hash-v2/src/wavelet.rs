@@ -0,0 +1,126 @@
+// 🌊 Wavelet Spectrum - Hearing Every Scale at Once
+use crate::Soul;
+
+/// Default number of Haar levels (signature length 2^depth)
+pub const DEFAULT_WAVELET_DEPTH: usize = 5;
+
+/// Largest supported depth, keeps signatures at most 1024 values long
+pub const MAX_WAVELET_DEPTH: usize = 10;
+
+/// Multi-scale signature of a full eigenvalue spectrum
+///
+/// The spectrum is sorted, resampled to 2^depth points over its whole range
+/// and Haar-decomposed. Layout: `[approximation, coarsest details, ..., finest details]`.
+/// Unlike the 7 leading eigenvalues, every part of the spectrum contributes.
+pub fn wavelet_signature(spectrum: &[f64], depth: usize) -> Vec<f64> {
+    if spectrum.is_empty() {
+        return Vec::new();
+    }
+
+    let depth = depth.min(MAX_WAVELET_DEPTH);
+    let mut sorted = spectrum.to_vec();
+    sorted.sort_by(|a, b| b.partial_cmp(a).unwrap());
+
+    let mut coefficients = resample(&sorted, 1 << depth);
+    haar_in_place(&mut coefficients);
+    coefficients
+}
+
+/// Resonance of two souls' multi-scale signatures, if both carry comparable ones
+pub fn measure_multiscale_resonance(soul1: &Soul, soul2: &Soul) -> Option<f64> {
+    let (a, b) = (&soul1.wavelet_signature, &soul2.wavelet_signature);
+    if a.is_empty() || a.len() != b.len() {
+        return None;
+    }
+
+    let distance = a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).powi(2))
+        .sum::<f64>()
+        .sqrt();
+    let scale = a.iter().chain(b).map(|x| x.abs()).fold(1.0, f64::max);
+    Some(1.0 / (1.0 + distance / scale))
+}
+
+/// Linear interpolation of `values` onto `len` evenly spaced points
+fn resample(values: &[f64], len: usize) -> Vec<f64> {
+    if values.len() == 1 || len == 1 {
+        return vec![values[0]; len];
+    }
+
+    let last = (values.len() - 1) as f64;
+    (0..len)
+        .map(|i| {
+            let position = i as f64 * last / (len - 1) as f64;
+            let lower = position.floor() as usize;
+            let upper = (lower + 1).min(values.len() - 1);
+            let t = position - lower as f64;
+            values[lower] * (1.0 - t) + values[upper] * t
+        })
+        .collect()
+}
+
+/// Orthonormal Haar transform over a power-of-two buffer
+fn haar_in_place(data: &mut [f64]) {
+    let mut scratch = vec![0.0; data.len()];
+    let mut len = data.len();
+
+    while len > 1 {
+        let half = len / 2;
+        for i in 0..half {
+            let (even, odd) = (data[2 * i], data[2 * i + 1]);
+            scratch[i] = (even + odd) / std::f64::consts::SQRT_2;
+            scratch[half + i] = (even - odd) / std::f64::consts::SQRT_2;
+        }
+        data[..len].copy_from_slice(&scratch[..len]);
+        len = half;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_preserves_energy() {
+        let spectrum = [4.0, 3.0, 2.0, 1.0];
+        let signature = wavelet_signature(&spectrum, 2);
+
+        assert_eq!(signature.len(), 4);
+        let energy: f64 = signature.iter().map(|c| c * c).sum();
+        assert!((energy - 30.0).abs() < 1e-9);
+        assert!((signature[0] - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tail_of_spectrum_matters() {
+        // Identical top 7, different tails: the 7-layer signature can't tell them apart
+        let mut a: Vec<f64> = (0..40).map(|i| 40.0 - i as f64).collect();
+        let mut b = a.clone();
+        a.extend(std::iter::repeat_n(0.5, 40));
+        b.extend(std::iter::repeat_n(3.0, 40));
+
+        assert_ne!(
+            wavelet_signature(&a, DEFAULT_WAVELET_DEPTH),
+            wavelet_signature(&b, DEFAULT_WAVELET_DEPTH),
+        );
+    }
+
+    #[test]
+    fn test_degenerate_spectra() {
+        assert!(wavelet_signature(&[], 4).is_empty());
+        assert_eq!(wavelet_signature(&[2.0], 0), vec![2.0]);
+        assert_eq!(wavelet_signature(&[1.0; 3], 64).len(), 1 << MAX_WAVELET_DEPTH);
+    }
+
+    #[tokio::test]
+    async fn test_extracted_souls_carry_signatures() {
+        let extractor = crate::SoulExtractor::new().with_wavelet_depth(3);
+        let a = extractor.extract_soul_rust("fn a(x: u32) -> u32 { x + 1 }").await;
+        let b = extractor.extract_soul_rust("fn b(v: &[u32]) -> u32 { let mut s = 0; for x in v { if *x > 2 { s += x; } } s }").await;
+
+        assert_eq!(a.wavelet_signature.len(), 8);
+        assert_eq!(measure_multiscale_resonance(&a, &a), Some(1.0));
+        assert!(measure_multiscale_resonance(&a, &b).unwrap() < 1.0);
+    }
+}
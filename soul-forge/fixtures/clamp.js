@@ -0,0 +1,11 @@
+/**
+ * Keep a number within bounds
+ */
+export function clamp(value, min, max) {
+  if (value < min) {
+    return min;
+  } else if (value > max) {
+    return max;
+  }
+  return value;
+}
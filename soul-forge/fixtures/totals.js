@@ -0,0 +1,11 @@
+export function sum(xs) {
+  let total = 0;
+  for (const x of xs) {
+    total += x;
+  }
+  return total;
+}
+
+export function mean(xs) {
+  return xs.length === 0 ? 0 : sum(xs) / xs.length;
+}
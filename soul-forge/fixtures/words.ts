@@ -0,0 +1,7 @@
+export function capitalize(word: string): string {
+  return word.charAt(0).toUpperCase() + word.slice(1);
+}
+
+export function titleCase(text: string): string {
+  return text.split(" ").map(capitalize).join(" ");
+}
@@ -0,0 +1,101 @@
+// 🧩 Native Addons - Handing Transmuted Crates to Node Through napi-rs
+use crate::RustFunction;
+
+/// Crates an addon's Cargo.toml adds for its `#[napi]` wrappers
+pub(crate) const DEPENDENCIES: &[(&str, &str)] = &[
+    ("napi", r#"{ version = "2", default-features = false, features = ["napi4", "serde-json"] }"#),
+    ("napi-derive", r#""2""#),
+];
+
+/// Crates an addon's build script uses
+pub(crate) const BUILD_DEPENDENCIES: &[(&str, &str)] = &[("napi-build", r#""2""#)];
+
+/// An addon's `build.rs`, which sets up linking against the Node that loads it
+pub(crate) const BUILD_SCRIPT: &str = "fn main() {\n    napi_build::setup();\n}\n";
+
+/// An exported function Node can call through a `#[napi]` wrapper
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Export {
+    name: String,
+    params: Vec<(String, String)>,
+    return_type: String,
+}
+
+impl Export {
+    /// `function` as an export, if the source exports it and napi converts its
+    /// parameters and result; async and generic functions, methods and
+    /// anything taking closures or structs are left to the Rust side
+    pub(crate) fn of(function: &RustFunction, exports: &[String]) -> Option<Self> {
+        let export = Self {
+            name: function.name.clone(),
+            params: function.params.iter().map(|param| (param.name.clone(), param.ty.clone())).collect(),
+            return_type: function.return_type.clone(),
+        };
+        if function.is_async || !function.generics.is_empty() || function.receiver.is_some() || !exports.iter().any(|name| name == export.js_name()) {
+            return None;
+        }
+        let result = export.fallible().unwrap_or(&export.return_type);
+        (crosses(result) && export.params.iter().all(|(_, ty)| crosses(ty))).then_some(export)
+    }
+
+    /// The name Node sees, as the source exported it
+    pub(crate) fn js_name(&self) -> &str {
+        self.name.trim_start_matches("r#")
+    }
+
+    /// What a `Result<_, JsError>` return holds, which napi throws the error of
+    fn fallible(&self) -> Option<&str> {
+        self.return_type.strip_prefix("Result<")?.strip_suffix(", JsError>")
+    }
+}
+
+/// Whether napi converts `ty` between JS and Rust values
+fn crosses(ty: &str) -> bool {
+    let ty = ty.trim();
+    let inner = |prefix: &str| ty.strip_prefix(prefix).and_then(|rest| rest.strip_suffix('>'));
+    match ty {
+        "()" | "f64" | "i64" | "String" | "bool" | "Value" | "serde_json::Value" => true,
+        _ => inner("Vec<").or_else(|| inner("Option<")).or_else(|| inner("HashMap<String, ")).is_some_and(crosses),
+    }
+}
+
+/// A `node` module of `#[napi]` wrappers, one an export, appended to the
+/// crate; empty when nothing crosses
+pub(crate) fn glue(exports: &[Export]) -> String {
+    if exports.is_empty() {
+        return String::new();
+    }
+    // Deprecated exports are still exports
+    let mut code = String::from("/// The exports Node loads from `index.node`, built by napi-rs\n#[allow(deprecated)]\nmod node {\n    use super::*;\n    use napi_derive::napi;\n");
+    for export in exports {
+        let params = export.params.iter().map(|(name, ty)| format!("{}: {}", name, ty)).collect::<Vec<_>>().join(", ");
+        let args = export.params.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(", ");
+        let call = format!("super::{}({})", export.name, args);
+        let (return_type, body) = match export.fallible() {
+            Some(value) => (format!(" -> napi::Result<{}>", value), format!("{}.map_err(|error| napi::Error::from_reason(error.to_string()))", call)),
+            None if export.return_type == "()" => (String::new(), call),
+            None => (format!(" -> {}", export.return_type), call),
+        };
+        code.push_str(&format!(
+            "\n    #[napi(js_name = \"{}\")]\n    pub fn {}({}){} {{\n        {}\n    }}\n",
+            export.js_name(), export.name, params, return_type, body
+        ));
+    }
+    code.push_str("}\n\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_types_napi_converts_cross() {
+        assert!(crosses("Vec<Option<f64>>"));
+        assert!(crosses("HashMap<String, Vec<String>>"));
+        assert!(!crosses("HashMap<i64, String>"));
+        assert!(!crosses("(f64, String)"));
+        assert!(!crosses("impl Fn(f64) -> f64"));
+        assert!(!crosses("Point"));
+    }
+}
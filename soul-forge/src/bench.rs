@@ -0,0 +1,269 @@
+// ⏱️ Benchmarks - Timing the Same Calls in Both Bodies
+use crate::equivalence::{self, Subject};
+use crate::{modules, SourceKind, TransmutationError};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// The bench target Cargo.toml declares, in `benches/transmuted.rs`
+pub(crate) const BENCH_NAME: &str = "transmuted";
+
+/// The dev-dependency the criterion benchmarks build with
+pub(crate) const CRITERION: (&str, &str) = ("criterion", r#""0.5""#);
+
+/// The generated `benches/` directory of a crate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Benches {
+    /// `transmuted.rs`, criterion benchmarks of the crate's functions
+    pub criterion: String,
+    /// The source with a trailer timing the same calls under Node
+    pub node: String,
+    /// The Node script's file name, `original.mjs` or `original.mts`
+    pub node_file: String,
+}
+
+impl Benches {
+    /// The file in `benches/` the criterion benchmarks go in, named for the
+    /// bench target
+    pub fn criterion_file(&self) -> String {
+        format!("{}.rs", BENCH_NAME)
+    }
+}
+
+/// One function's time in each body, for one pass over its cases
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Benchmark {
+    pub function: String,
+    pub js_ns: f64,
+    pub rust_ns: f64,
+}
+
+impl Benchmark {
+    /// How many times faster the Rust is
+    pub fn speedup(&self) -> f64 {
+        self.js_ns / self.rust_ns
+    }
+}
+
+/// Benchmarks timing each subject on its generated cases in both bodies,
+/// leaving out those whose body is a `todo!`; `None` when that leaves none
+pub(crate) fn generate(crate_name: &str, js_code: &str, kind: SourceKind, commonjs: bool, subjects: &[Subject], rust_code: &str) -> Option<Benches> {
+    let unfinished = unfinished(rust_code);
+    let subjects: Vec<&Subject> = subjects.iter().filter(|subject| !unfinished.iter().any(|name| name == subject.name())).collect();
+    if subjects.is_empty() {
+        return None;
+    }
+    let mut cases = Map::new();
+    for subject in &subjects {
+        let fixtures = equivalence::generate(std::slice::from_ref(*subject));
+        cases.insert(js_name(subject).to_string(), fixtures.into_iter().map(|fixture| Value::Array(fixture.args)).collect());
+    }
+    let cases = Value::Object(cases).to_string();
+    let node_file = format!("original.{}", if kind.is_typescript() { "mts" } else { "mjs" });
+    Some(Benches {
+        criterion: criterion(crate_name, &node_file, &subjects, &cases),
+        node: node_script(js_code, commonjs, &subjects, &cases),
+        node_file,
+    })
+}
+
+fn js_name(subject: &Subject) -> &str {
+    subject.name().trim_start_matches("r#")
+}
+
+/// Functions of `rust_code` whose bodies are left as `todo!`s, which would
+/// only time a panic
+pub(crate) fn unfinished(rust_code: &str) -> Vec<String> {
+    use quote::ToTokens;
+    let Ok(file) = syn::parse_file(rust_code) else { return vec![] };
+    file.items.iter().filter_map(|item| match item {
+        syn::Item::Fn(function) if function.block.to_token_stream().to_string().contains("todo !") => Some(function.sig.ident.to_string()),
+        _ => None,
+    }).collect()
+}
+
+/// `benches/transmuted.rs`: a criterion benchmark per subject, each iteration
+/// calling it on every case with fresh copies of the arguments
+fn criterion(crate_name: &str, node_file: &str, subjects: &[&Subject], cases: &str) -> String {
+    let benches: Vec<String> = subjects.iter().enumerate().map(|(i, subject)| {
+        let vars: Vec<String> = (0..subject.params.len()).map(|j| format!("a{}", j)).collect();
+        let args: Vec<String> = (0..subject.params.len()).map(|j| format!("arg(args, {})", j)).collect();
+        let tuple = |items: &[String]| match items.len() {
+            0 => "()".to_string(),
+            _ => format!("({},)", items.join(", ")),
+        };
+        format!(
+            "fn bench_{i}(c: &mut Criterion) {{\n    \
+                 let cases: Vec<{ty}> = cases({js:?}).iter().map(|args| {args}).collect();\n    \
+                 c.bench_function({js:?}, |b| b.iter_batched(|| cases.clone(), |cases| {{\n        \
+                     for {vars} in cases {{\n            \
+                         let _ = catch_unwind(AssertUnwindSafe(|| black_box({name}({call}))));\n        \
+                     }}\n    \
+                 }}, BatchSize::SmallInput));\n\
+             }}",
+            i = i,
+            ty = tuple(&subject.params),
+            js = js_name(subject),
+            args = tuple(&args),
+            vars = tuple(&vars),
+            name = subject.name(),
+            call = vars.join(", "),
+        )
+    }).collect();
+    let names: Vec<String> = (0..subjects.len()).map(|i| format!("bench_{}", i)).collect();
+
+    format!(
+        r####"//! Benchmarks of the transmuted functions, each timed on the cases
+//! `benches/{node_file}` times the JavaScript source on
+#![allow(non_snake_case, unused_imports, unused_variables)]
+use {crate_name}::*;
+use criterion::{{criterion_group, criterion_main, BatchSize, Criterion}};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::hint::black_box;
+use std::panic::{{catch_unwind, AssertUnwindSafe}};
+
+const CASES: &str = r###"{cases}"###;
+
+fn arg<T: DeserializeOwned>(args: &[Value], i: usize) -> T {{
+    serde_json::from_value(args.get(i).cloned().unwrap_or(Value::Null)).expect("argument of the parameter's type")
+}}
+
+/// A function's cases, and a silent panic hook: a case that panics is timed
+/// like one that throws in JS, not reported
+fn cases(function: &str) -> Vec<Vec<Value>> {{
+    std::panic::set_hook(Box::new(|_| {{}}));
+    let cases: Value = serde_json::from_str(CASES).unwrap();
+    serde_json::from_value(cases[function].clone()).unwrap()
+}}
+
+{benches}
+
+criterion_group!(benches, {names});
+criterion_main!(benches);
+"####,
+        node_file = node_file,
+        crate_name = crate_name.replace('-', "_"),
+        cases = cases,
+        benches = benches.join("\n\n"),
+        names = names.join(", "),
+    )
+}
+
+/// The source, then module code timing each subject on its cases, as the
+/// criterion benchmarks do, and printing nanoseconds per pass as JSON
+fn node_script(js_code: &str, commonjs: bool, subjects: &[&Subject], cases: &str) -> String {
+    let functions: Vec<&str> = subjects.iter().map(|subject| js_name(subject)).collect();
+    let (js_code, functions) = modules::for_node(js_code, commonjs, &functions);
+    format!(
+        r#"{}
+const __forgeFunctions = {{ {} }};
+const __forgeCases = {};
+const __forgeTimes = {{}};
+for (const [name, cases] of Object.entries(__forgeCases)) {{
+  const pass = (calls) => {{
+    for (const args of calls) {{
+      try {{ __forgeFunctions[name](...args); }} catch {{}}
+    }}
+  }};
+  // Calls get fresh copies of their arguments, which they may change
+  const batch = () => Array.from({{ length: 64 }}, () => structuredClone(cases));
+  for (let i = 0; i < 16; i++) batch().forEach(pass);
+  let passes = 0, elapsed = 0n;
+  while (elapsed < 2_000_000_000n) {{
+    const copies = batch();
+    const start = process.hrtime.bigint();
+    copies.forEach(pass);
+    elapsed += process.hrtime.bigint() - start;
+    passes += copies.length;
+  }}
+  __forgeTimes[name] = Number(elapsed) / passes;
+}}
+console.log(JSON.stringify(__forgeTimes));
+"#,
+        js_code,
+        functions,
+        cases,
+    )
+}
+
+/// Run a crate's benchmarks in both bodies, `cargo bench` and then Node,
+/// pairing up the functions timed in both
+pub async fn run(dir: &Path) -> Result<Vec<Benchmark>, TransmutationError> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let output = tokio::process::Command::new(cargo)
+        .args(["bench", "--quiet", "--bench", BENCH_NAME, "--", "--output-format", "bencher"])
+        .current_dir(dir)
+        .output()
+        .await
+        .map_err(|e| TransmutationError::BenchError(format!("can't run cargo bench: {}", e).into()))?;
+    if !output.status.success() {
+        return Err(TransmutationError::BenchError(String::from_utf8_lossy(&output.stderr).trim().to_string().into()));
+    }
+    let rust = bencher_times(&String::from_utf8_lossy(&output.stdout));
+
+    let script = ["original.mjs", "original.mts"].iter()
+        .map(|file| dir.join("benches").join(file))
+        .find(|script| script.is_file())
+        .ok_or_else(|| TransmutationError::BenchError(format!("{} has no benches/original.mjs", dir.display()).into()))?;
+    let mut node = tokio::process::Command::new("node");
+    if script.extension().is_some_and(|ext| ext == "mts") {
+        node.arg("--experimental-strip-types");
+    }
+    let output = node.arg(&script).output().await
+        .map_err(|e| TransmutationError::BenchError(format!("can't run node: {}", e).into()))?;
+    if !output.status.success() {
+        return Err(TransmutationError::BenchError(String::from_utf8_lossy(&output.stderr).trim().to_string().into()));
+    }
+    // The module may print too; the times are the last line
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let js: Map<String, Value> = serde_json::from_str(stdout.lines().last().unwrap_or_default())
+        .map_err(|e| TransmutationError::BenchError(format!("unreadable Node timings: {}", e).into()))?;
+
+    Ok(rust.into_iter().filter_map(|(function, rust_ns)| {
+        let js_ns = js.get(&function)?.as_f64()?;
+        Some(Benchmark { function, js_ns, rust_ns })
+    }).collect())
+}
+
+/// Nanoseconds per iteration of each benchmark, from criterion's
+/// `--output-format bencher` lines like
+/// `test chunk ... bench:       1,234 ns/iter (+/- 56)`
+fn bencher_times(stdout: &str) -> Vec<(String, f64)> {
+    stdout.lines().filter_map(|line| {
+        let (name, rest) = line.strip_prefix("test ")?.split_once(" ... bench:")?;
+        let nanos = rest.split_whitespace().next()?.replace(',', "").parse().ok()?;
+        Some((name.to_string(), nanos))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_both_bodies_time_the_same_cases() {
+        let function = crate::RustFunction {
+            name: "chunk".to_string(),
+            generics: vec![],
+            receiver: None,
+            params: ["Vec<f64>", "f64"].iter().map(|ty| crate::RustParam { name: "x".to_string(), ty: ty.to_string() }).collect(),
+            return_type: "Vec<Vec<f64>>".to_string(),
+            body: String::new(),
+            is_async: false,
+            is_pub: true,
+            origin: None,
+        };
+        let subjects = [Subject::of(&function).unwrap()];
+        let js = "export function chunk(xs, size) { return [xs]; }";
+        let benches = generate("chunk-soul", js, SourceKind::JavaScript, false, &subjects, "pub fn chunk(xs: Vec<f64>, size: f64) -> Vec<Vec<f64>> { vec![xs] }").unwrap();
+        assert!(benches.criterion.contains("let cases: Vec<(Vec<f64>, f64,)> = cases(\"chunk\").iter().map(|args| (arg(args, 0), arg(args, 1),)).collect();"));
+        assert!(benches.criterion.contains("black_box(chunk(a0, a1))"));
+        assert!(benches.node.starts_with(js));
+        assert!(benches.node.contains(r#"const __forgeCases = {"chunk":[[[],1],"#));
+        assert_eq!(benches.node_file, "original.mjs");
+
+        assert!(generate("chunk-soul", js, SourceKind::JavaScript, false, &subjects, "pub fn chunk(xs: Vec<f64>, size: f64) -> Vec<Vec<f64>> { todo!() }").is_none());
+        assert_eq!(bencher_times("test chunk ... bench:       1,234 ns/iter (+/- 56)\n"), [("chunk".to_string(), 1234.0)]);
+    }
+}
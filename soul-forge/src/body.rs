@@ -0,0 +1,3187 @@
+// 🧬 Body Transmutation - JS Statements into Rust Statements
+use crate::layout::{returns_value, Layout};
+use crate::generators::Yielding;
+use crate::numeric::{self, NumericPolicy};
+use crate::strings;
+use crate::{JsExpr, JsFunction, JsParam, JsPattern, JsProp, JsStmt, JsType, SoulForge};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+const INDENT: &str = "    ";
+
+/// Rust keywords that are ordinary identifiers in JS
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "box", "crate", "dyn", "enum", "extern", "fn", "impl", "loop", "match", "mod", "move", "mut",
+    "priv", "pub", "ref", "struct", "trait", "type", "unsafe", "use", "where",
+];
+
+/// `name`, raw-escaped when it is a Rust keyword
+pub(crate) fn rust_ident(name: &str) -> String {
+    if RUST_KEYWORDS.contains(&name) { format!("r#{}", name) } else { name.to_string() }
+}
+
+/// How an iterator adapter hands an element to a JS callback
+#[derive(Clone, Copy)]
+enum Binding {
+    /// `|x|` with `x: &T`
+    Ref,
+    /// `|&x|` over `&&T`, leaving `x: &T`
+    DoubleRef,
+    /// `|x|` with `x: T`
+    Owned,
+}
+
+/// A loop or `switch` being written, for resolving `break` and `continue`
+#[derive(Clone, Default)]
+struct Frame {
+    /// JS label naming it, if any
+    label: Option<String>,
+
+    /// Rust label on the loop, when something breaks out of it by name
+    outer: Option<String>,
+
+    /// Rust label on the body block of a loop with an update step
+    ///
+    /// `continue` has to run the update (or the do-while test), so it
+    /// becomes `break` out of the body; plain `break` then has to name
+    /// the loop.
+    body: Option<String>,
+
+    /// A `switch`, which `break` leaves but `continue` passes through
+    switch: bool,
+}
+
+/// What a method body knows of its class
+#[derive(Clone, Default)]
+pub(crate) struct ClassScope {
+    /// `this` in Rust: `self`, or the `this` a constructor builds
+    pub(crate) this: String,
+
+    /// Fields and getters reachable through `this`, by JS name
+    pub(crate) members: HashMap<String, ClassMember>,
+
+    /// Methods callable on `this`, which skip the std method mapping
+    pub(crate) methods: HashSet<String>,
+
+    /// Methods returning a `Result`, whose calls propagate it
+    pub(crate) throwing: HashSet<String>,
+
+    /// Where the base class's part of `this` lives, for `super.method()`
+    pub(crate) parent: Option<String>,
+
+    /// How a constructor builds `this`
+    pub(crate) construct: Option<Construct>,
+}
+
+#[derive(Clone)]
+pub(crate) struct ClassMember {
+    /// Rust reading it: `self.name`, `self.animal().name` or `self.area()`
+    pub(crate) path: String,
+    /// Rust writing it, which may take a `_mut` accessor
+    pub(crate) place: String,
+    pub(crate) ty: JsType,
+    /// A getter, whose value is already owned
+    pub(crate) computed: bool,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Construct {
+    /// The field holding the base class and its type, built by `super(..)`
+    pub(crate) base: Option<(String, String)>,
+
+    /// Own fields with their initializers
+    pub(crate) fields: Vec<(String, Option<JsExpr>)>,
+}
+
+/// Writes the Rust statements of a JS function body
+///
+/// Numbers stay `f64` as in JS, indices are cast to `usize`, and strings
+/// joined with `+` or template literals become `format!`. Constructs with no
+/// translation yet become a `todo!` naming them, so the output still parses.
+pub(crate) struct BodyWriter<'f> {
+    forge: &'f SoulForge,
+
+    /// Known types of parameters and locals
+    types: HashMap<String, JsType>,
+
+    /// Names bound to references (iterator items), dereferenced in operators
+    refs: HashSet<String>,
+
+    /// Names reassigned or mutated in place, which need `let mut`
+    mutated: HashSet<String>,
+
+    lines: Vec<String>,
+    depth: usize,
+    frames: Vec<Frame>,
+
+    /// Label of the labeled statement being written, for the loop it names
+    pending_label: Option<String>,
+
+    /// How an async body wraps what it returns: `Ok`, with the error type
+    /// spelled out inside `async` blocks, which can't name it otherwise
+    fallible: Option<&'static str>,
+
+    /// How a generator body hands out what it yields
+    yielding: Option<Yielding>,
+
+    /// The class of a method body
+    class: Option<ClassScope>,
+
+    /// Labels of the `try` blocks being written, innermost last; a throw
+    /// breaks out of the innermost with its error
+    catching: Vec<String>,
+
+    /// `finally` blocks of the `try` statements being written, innermost last
+    finalizers: Vec<Vec<JsStmt>>,
+
+    /// Locals bound to regexes with the `g` flag, which replace every match
+    global_regexes: HashSet<String>,
+
+    /// Locals kept as `i64`s, which only ever hold integers
+    integers: HashSet<String>,
+
+    /// How iteration and returns are laid out, after the source's shape
+    layout: Layout,
+
+    /// Whether the statement being written is the body's tail, whose
+    /// returns are its values
+    tail: bool,
+
+    temps: usize,
+}
+
+impl<'f> BodyWriter<'f> {
+    /// A writer for the body of `function`, which can call the module's `signatures`
+    pub(crate) fn new(forge: &'f SoulForge, signatures: &HashMap<String, JsType>, function: &JsFunction) -> Self {
+        let mut types = signatures.clone();
+        types.extend(function.params.iter().map(|param| (param.name.clone(), param.ty.clone())));
+        Self {
+            forge,
+            types,
+            refs: HashSet::new(),
+            mutated: HashSet::new(),
+            lines: vec![],
+            depth: 0,
+            frames: vec![],
+            pending_label: None,
+            fallible: (function.is_async || matches!(function.return_type, JsType::Throws(_))).then_some("Ok"),
+            yielding: Yielding::of(function),
+            class: None,
+            catching: vec![],
+            finalizers: vec![],
+            global_regexes: HashSet::new(),
+            integers: match forge.numeric {
+                NumericPolicy::JsFloat64 => HashSet::new(),
+                NumericPolicy::IdiomaticInt => numeric::integer_locals(function),
+            },
+            layout: forge.layout,
+            tail: false,
+            temps: 0,
+        }
+    }
+
+    /// Write a method of the class `scope` describes
+    pub(crate) fn in_class(mut self, scope: ClassScope) -> Self {
+        self.class = Some(scope);
+        self
+    }
+
+    /// Body lines, indented relative to the function; a final `return` becomes the tail
+    pub(crate) fn write_function(mut self, body: &[JsStmt]) -> Vec<String> {
+        self.mutated.extend(mutated_names(body));
+
+        // A constructor builds `this` up front, or at `super(..)` when it extends a class
+        let construct = self.class.as_ref().and_then(|class| class.construct.clone());
+        if let Some(construct) = &construct {
+            if construct.base.is_none() || !body.iter().any(is_super_call) {
+                let this = self.construct_this(&[]);
+                self.line(this);
+            }
+        }
+
+        // Constructors end in `this`, whatever the layout
+        let body = match construct {
+            Some(_) => Cow::Borrowed(body),
+            None => self.layout.arrange(body),
+        };
+        let body = &*body;
+        for (index, stmt) in body.iter().enumerate() {
+            self.tail = self.yielding.is_none() && index + 1 == body.len() && matches!(stmt, JsStmt::If { .. }) && returns_value(std::slice::from_ref(stmt));
+            self.stmt(stmt);
+        }
+        if construct.is_some() && !matches!(body.last(), Some(JsStmt::Return(_))) {
+            let this = self.fallible.map_or("this".to_string(), |ok| format!("{}(this)", ok));
+            self.line(this);
+        }
+
+        // A fallible body that returns nothing still succeeds, as a
+        // generator's does whatever it returns
+        let returns_value = self.yielding.is_none() && (construct.is_some() || jumps(body, &|stmt, _| matches!(stmt, JsStmt::Return(Some(_)))));
+        if let (Some(ok), false, false) = (self.fallible, returns_value, matches!(body.last(), Some(JsStmt::Return(_)))) {
+            self.line(format!("{}(())", ok));
+        }
+
+        let indent = INDENT.repeat(self.depth);
+        if let Some(last) = self.lines.last_mut() {
+            if *last == format!("{}return;", indent) {
+                self.lines.pop();
+            } else if let Some(tail) = last.strip_prefix(&format!("{}return ", indent)).and_then(|l| l.strip_suffix(';')) {
+                *last = format!("{}{}", indent, tail);
+            }
+        }
+        let lines = self.lines.iter().flat_map(|line| line.split('\n')).map(str::to_string).collect();
+        match self.yielding {
+            Some(yielding) => yielding.wrap(lines),
+            None => lines,
+        }
+    }
+
+    /// A writer for a closure body, sharing what is known so far
+    pub(crate) fn nested(&self) -> BodyWriter<'f> {
+        BodyWriter {
+            forge: self.forge,
+            types: self.types.clone(),
+            refs: self.refs.clone(),
+            mutated: self.mutated.clone(),
+            lines: vec![],
+            depth: self.depth + 1,
+            frames: vec![],
+            pending_label: None,
+            fallible: None,
+            yielding: None,
+            class: self.class.clone(),
+            catching: vec![],
+            finalizers: vec![],
+            global_regexes: self.global_regexes.clone(),
+            integers: self.integers.clone(),
+            layout: self.layout,
+            tail: false,
+            temps: self.temps,
+        }
+    }
+
+    fn line(&mut self, text: impl AsRef<str>) {
+        self.lines.push(format!("{}{}", INDENT.repeat(self.depth), text.as_ref()));
+    }
+
+    fn block(&mut self, body: &[JsStmt]) {
+        self.depth += 1;
+        for stmt in body {
+            self.stmt(stmt);
+        }
+        self.depth -= 1;
+    }
+
+    fn temp(&mut self, role: &str) -> String {
+        self.temps += 1;
+        format!("{}{}", role, self.temps)
+    }
+
+    // Statements
+
+    fn stmt(&mut self, stmt: &JsStmt) {
+        let layout = self.layout;
+        if let Some(relaid) = layout.relay(stmt, |iterable| matches!(self.expr_type(iterable), JsType::Array(_))) {
+            return self.stmt(&relaid);
+        }
+        match stmt {
+            JsStmt::Let { pattern, ty, init } => self.let_binding(pattern, ty, init.as_ref()),
+            JsStmt::Expr(JsExpr::Call(_, args)) if is_super_call(stmt) => {
+                let this = self.construct_this(args);
+                self.line(this);
+            }
+            // `yield*` hands out each item of the iterable in turn
+            JsStmt::Expr(JsExpr::Yield { value: Some(iterable), delegate: true }) if self.yielding.is_some() => {
+                let item = self.temp("item");
+                let yielded = JsExpr::Yield { value: Some(Box::new(JsExpr::Ident(item.clone()))), delegate: false };
+                self.stmt(&JsStmt::ForOf { pattern: JsPattern::Ident(item), iterable: (**iterable).clone(), body: vec![JsStmt::Expr(yielded)] });
+            }
+            JsStmt::Expr(expr) => {
+                // A promise nobody awaits still runs to completion in JS
+                let detached = !matches!(expr, JsExpr::Await(_)) && matches!(self.expr_type(expr), JsType::Promise(_));
+                let expr = self.expr_stmt(expr);
+                match detached {
+                    true => self.line(format!("spawn_promise({});", expr)),
+                    false => self.line(format!("{};", expr)),
+                }
+            }
+            // What a generator returns isn't one of its items, and Rust's
+            // iterators end with none
+            JsStmt::Return(Some(_)) if self.yielding.is_some() => self.stmt(&JsStmt::Return(None)),
+            // The value is worked out before any `finally` runs
+            JsStmt::Return(Some(value)) if self.finalizers.iter().any(|finalizer| !finalizer.is_empty()) => {
+                let value = self.returned(value);
+                let returned = self.temp("returned");
+                self.line(format!("let {} = {};", returned, value));
+                self.unwind();
+                self.line(format!("return {};", returned));
+            }
+            JsStmt::Return(Some(value)) => {
+                let value = self.returned(value);
+                self.line(format!("return {};", value));
+            }
+            JsStmt::Return(None) => {
+                self.unwind();
+                match self.fallible {
+                    Some(ok) => self.line(format!("return {}(());", ok)),
+                    None => self.line("return;"),
+                }
+            }
+            JsStmt::If { test, then, otherwise } => self.if_chain("if", test, then, otherwise),
+            JsStmt::While { test, body } => {
+                let frame = self.frame(body, false);
+                let test = self.condition(test);
+                self.line(format!("{}while {} {{", loop_label(&frame), test));
+                self.loop_body(body, frame);
+                self.line("}");
+            }
+            JsStmt::DoWhile { body, test } => {
+                let frame = self.frame(body, true);
+                self.line(format!("{}loop {{", loop_label(&frame)));
+                self.loop_body(body, frame.clone());
+                let test = self.condition(test);
+                self.depth += 1;
+                self.line(format!("if !({}) {{", test));
+                match &frame.outer {
+                    Some(outer) => self.line(format!("{}break {};", INDENT, outer)),
+                    None => self.line(format!("{}break;", INDENT)),
+                }
+                self.line("}");
+                self.depth -= 1;
+                self.line("}");
+            }
+            JsStmt::For { init, test, update, body } => self.for_loop(init, test.as_ref(), update.as_ref(), body),
+            JsStmt::ForOf { pattern, iterable, body } => {
+                let element = match self.expr_type(iterable) {
+                    JsType::Array(element) | JsType::Set(element) | JsType::Iterator(element) => *element,
+                    _ => JsType::Unknown,
+                };
+                // A generator's iterator is used up, giving its items by value
+                let (iter, owned) = match self.expr_type(iterable) {
+                    JsType::String => (format!("{}.chars()", self.receiver(iterable)), false),
+                    JsType::Iterator(_) => (self.expr(iterable), true),
+                    _ => (format!("{}.iter()", self.receiver(iterable)), false),
+                };
+                self.for_each_loop(pattern, iter, element, owned, body);
+            }
+            JsStmt::ForIn { pattern, object, body } => {
+                let keys = format!("{}.keys()", self.receiver(object));
+                self.for_each_loop(pattern, keys, JsType::String, false, body);
+            }
+            JsStmt::Switch { discriminant, cases } => {
+                let discriminant = match discriminant {
+                    JsExpr::Ident(_) => discriminant.clone(),
+                    other => {
+                        let temp = self.temp("discriminant");
+                        let value = self.expr(other);
+                        self.line(format!("let {} = {};", temp, value));
+                        JsExpr::Ident(temp)
+                    }
+                };
+
+                // A `break` before the end of a case leaves the whole switch
+                let early_break = cases.iter().any(|case| breaks(without_trailing_break(&case.body)));
+                let frame = Frame {
+                    outer: early_break.then(|| self.temp("'switch")),
+                    switch: true,
+                    ..Frame::default()
+                };
+                if let Some(outer) = &frame.outer {
+                    self.line(format!("{}: {{", outer));
+                    self.depth += 1;
+                }
+                self.frames.push(frame.clone());
+
+                let mut keyword = "if";
+                let mut tests = vec![];
+                let mut default = None;
+                for case in cases {
+                    match &case.test {
+                        Some(test) => tests.push(self.binary("===", &discriminant, test)),
+                        None => default = Some(&case.body),
+                    }
+                    // An empty case falls through into the next one
+                    if case.body.is_empty() || case.test.is_none() {
+                        continue;
+                    }
+                    self.line(format!("{} {} {{", keyword, tests.join(" || ")));
+                    tests.clear();
+                    self.block(without_trailing_break(&case.body));
+                    keyword = "} else if";
+                }
+
+                match (keyword, default) {
+                    ("if", Some(body)) => self.block_inline(without_trailing_break(body)),
+                    ("if", None) => {}
+                    (_, Some(body)) => {
+                        self.line("} else {");
+                        self.block(without_trailing_break(body));
+                        self.line("}");
+                    }
+                    (_, None) => self.line("}"),
+                }
+
+                self.frames.pop();
+                if frame.outer.is_some() {
+                    self.depth -= 1;
+                    self.line("}");
+                }
+            }
+            JsStmt::Break(label) => {
+                let jump = self.jump(label.as_deref(), false);
+                self.line(jump);
+            }
+            JsStmt::Continue(label) => {
+                let jump = self.jump(label.as_deref(), true);
+                self.line(jump);
+            }
+            JsStmt::Labeled(label, body) => {
+                let is_loop = matches!(
+                    **body,
+                    JsStmt::While { .. } | JsStmt::DoWhile { .. } | JsStmt::For { .. } | JsStmt::ForOf { .. } | JsStmt::ForIn { .. }
+                );
+                self.pending_label = is_loop.then(|| label.clone());
+                self.stmt(body);
+            }
+            JsStmt::Block(body) => {
+                self.line("{");
+                self.block(body);
+                self.line("}");
+            }
+            // Caught by an enclosing `try`, or returned from a fallible function
+            JsStmt::Throw(error) if !self.catching.is_empty() || self.fallible.is_some() => {
+                let error = self.rejection(error);
+                self.throw(error);
+            }
+            JsStmt::Throw(error) => {
+                let message = match error {
+                    JsExpr::New(_, args) if !args.is_empty() => format!("\"{{}}\", {}", self.expr(&args[0])),
+                    JsExpr::String(message) => format!("{:?}", message.replace('{', "{{").replace('}', "}}")),
+                    error => format!("\"{{:?}}\", {}", self.expr(error)),
+                };
+                self.line(format!("panic!({});", message));
+            }
+            JsStmt::Try { block, param, handler, finalizer } => self.try_catch(block, param.as_ref(), handler.as_deref(), finalizer),
+            JsStmt::Function(function) => {
+                let closure = self.closure(function, &[]);
+                self.types.insert(function.name.clone(), function.signature());
+                let mutability = if closure_kind(function) == Callable::Mut { "mut " } else { "" };
+                self.line(format!("let {}{} = {};", mutability, rust_ident(&function.name), closure));
+            }
+            JsStmt::Unsupported(what) => self.line(format!("todo!({:?});", what)),
+        }
+    }
+
+    /// `try` as a labeled block evaluating to the first error thrown in it,
+    /// which the `catch` handler then matches
+    ///
+    /// `finally` runs after the handler, and before any `return` from
+    /// within either.
+    fn try_catch(&mut self, block: &[JsStmt], param: Option<&JsPattern>, handler: Option<&[JsStmt]>, finalizer: &[JsStmt]) {
+        // When the block always returns, only an error can come out of it
+        let diverges = ends_in_jump(block);
+        self.finalizers.push(finalizer.to_vec());
+        let caught = self.temp("caught");
+        let start = self.lines.len();
+        self.catching_block(&caught, block);
+
+        // Nothing in the block throws, so no handler runs
+        if !self.throws_to(&caught, start) {
+            self.lines.truncate(start);
+            self.block_inline(block);
+            self.finalizers.pop();
+            if !diverges {
+                self.block_inline(finalizer);
+            }
+            return;
+        }
+
+        let Some(handler) = handler else {
+            self.finalizers.pop();
+            return self.rethrow(&caught, diverges, finalizer);
+        };
+        let param = match param {
+            Some(JsPattern::Ident(name)) => Some(name.clone()),
+            _ => None,
+        };
+        if let Some(param) = &param {
+            self.types.insert(param.clone(), JsType::Named("JsError".to_string(), vec![]));
+        }
+        let finishes = !(diverges && ends_in_jump(handler));
+        if finalizer.is_empty() {
+            self.finalizers.pop();
+            return self.handler(&caught, param.as_deref(), handler, diverges);
+        }
+
+        // What the handler throws has to wait for `finally` too
+        let handled = self.temp("handled");
+        let start = self.lines.len();
+        self.line(format!("let {}: Result<(), JsError> = '{}: {{", handled, handled));
+        self.depth += 1;
+        self.catching.push(format!("'{}", handled));
+        self.handler(&caught, param.as_deref(), handler, diverges);
+        self.catching.pop();
+        if finishes {
+            self.line("Ok(())");
+        }
+        self.depth -= 1;
+        self.line("};");
+
+        if !self.throws_to(&handled, start) {
+            self.lines.truncate(start);
+            self.handler(&caught, param.as_deref(), handler, diverges);
+            self.finalizers.pop();
+            if finishes {
+                self.block_inline(finalizer);
+            }
+            return;
+        }
+        self.finalizers.pop();
+        self.rethrow(&handled, !finishes, finalizer);
+    }
+
+    /// `catch`, binding the error when the handler uses it
+    fn handler(&mut self, caught: &str, param: Option<&str>, handler: &[JsStmt], diverges: bool) {
+        let start = self.lines.len();
+        let binding = param.map_or("_".to_string(), rust_ident);
+        match diverges {
+            false => {
+                self.line(format!("if let Err({}) = {} {{", binding, caught));
+                self.block(handler);
+                self.line("}");
+            }
+            true => {
+                self.line(format!("let Err({}) = {} else {{ unreachable!() }};", binding, caught));
+                self.block_inline(handler);
+            }
+        }
+        let used = self.lines[start + 1..].iter()
+            .any(|line| line.split(|c: char| !c.is_alphanumeric() && c != '_').any(|word| word == binding));
+        if !used && !diverges {
+            self.lines[start] = format!("{}if {}.is_err() {{", INDENT.repeat(self.depth), caught);
+        } else if !used {
+            self.lines[start] = self.lines[start].replacen(&format!("Err({})", binding), "Err(_)", 1);
+        }
+    }
+
+    /// Run `finally`, then carry on with the error in `result`, if any
+    fn rethrow(&mut self, result: &str, diverges: bool, finalizer: &[JsStmt]) {
+        match diverges {
+            false => {
+                self.block_inline(finalizer);
+                let rethrown = self.propagate(result.to_string());
+                self.line(format!("{};", rethrown));
+            }
+            true => {
+                self.line(format!("let Err(error) = {} else {{ unreachable!() }};", result));
+                self.block_inline(finalizer);
+                self.throw("error".to_string());
+            }
+        }
+    }
+
+    /// `let {caught} = '{caught}: { block; Ok(()) };`, with throws in the block breaking out
+    fn catching_block(&mut self, caught: &str, block: &[JsStmt]) {
+        self.line(format!("let {}: Result<(), JsError> = '{}: {{", caught, caught));
+        self.catching.push(format!("'{}", caught));
+        self.block(block);
+        self.catching.pop();
+        if !ends_in_jump(block) {
+            self.depth += 1;
+            self.line("Ok(())");
+            self.depth -= 1;
+        }
+        self.line("};");
+    }
+
+    /// Whether the lines written since `start` throw to the labeled block `label`
+    fn throws_to(&self, label: &str, start: usize) -> bool {
+        let thrown = format!("break '{} ", label);
+        self.lines[start..].iter().any(|line| line.contains(&thrown))
+    }
+
+    /// Throw `error`: to the enclosing `try`, out of a fallible function, or as a panic
+    fn throw(&mut self, error: String) {
+        match (self.catching.last(), self.fallible) {
+            (Some(label), _) => {
+                let label = label.clone();
+                self.line(format!("break {} Err({});", label, error));
+            }
+            (None, Some(_)) => {
+                self.unwind();
+                self.line(format!("return Err({});", error));
+            }
+            (None, None) => self.line(format!("panic!(\"{{}}\", {});", error)),
+        }
+    }
+
+    /// Run the `finally` blocks a `return` leaves, innermost first
+    fn unwind(&mut self) {
+        let finalizers = std::mem::take(&mut self.finalizers);
+        for finalizer in finalizers.iter().rev() {
+            self.block_inline(finalizer);
+        }
+        self.finalizers = finalizers;
+    }
+
+    /// `?` on a `Result`, or a break to the `catch` of the enclosing `try`
+    ///
+    /// A closure can't propagate to the function around it; an error there
+    /// panics, as an uncaught JS exception would end the program.
+    fn propagate(&self, result: String) -> String {
+        match (self.catching.last(), self.fallible) {
+            (Some(label), _) => format!("match {} {{ Ok(value) => value, Err(error) => break {} Err(error) }}", result, label),
+            (None, Some(_)) => format!("{}?", result),
+            (None, None) => format!("{}.unwrap()", result),
+        }
+    }
+
+    /// Statements spliced into the current block
+    fn block_inline(&mut self, body: &[JsStmt]) {
+        for stmt in body {
+            self.stmt(stmt);
+        }
+    }
+
+    fn if_chain(&mut self, keyword: &str, test: &JsExpr, then: &[JsStmt], otherwise: &[JsStmt]) {
+        let tail = std::mem::take(&mut self.tail);
+        let test = self.condition(test);
+        self.line(format!("{} {} {{", keyword, test));
+        self.arm(then, tail);
+        match otherwise {
+            [] => self.line("}"),
+            [JsStmt::If { test, then, otherwise }] => {
+                self.tail = tail;
+                self.if_chain("} else if", test, then, otherwise);
+            }
+            otherwise => {
+                self.line("} else {");
+                self.arm(otherwise, tail);
+                self.line("}");
+            }
+        }
+    }
+
+    /// An arm of an `if`, which in the tail ends in its value rather than
+    /// returning it
+    fn arm(&mut self, body: &[JsStmt], tail: bool) {
+        let [rest @ .., last] = body else { return self.block(body) };
+        if !tail {
+            return self.block(body);
+        }
+        self.depth += 1;
+        self.block_inline(rest);
+        match last {
+            JsStmt::Return(Some(value)) => {
+                let value = self.returned(value);
+                self.line(value);
+            }
+            last => {
+                self.tail = true;
+                self.stmt(last);
+            }
+        }
+        self.depth -= 1;
+    }
+
+    /// The frame of a loop about to be written around `body`
+    ///
+    /// `stepped` loops run something after the body (an update or the
+    /// do-while test) that `continue` must not skip.
+    fn frame(&mut self, body: &[JsStmt], stepped: bool) -> Frame {
+        let label = self.pending_label.take();
+        let continued = stepped && jumps(body, &|stmt, nested| match stmt {
+            JsStmt::Continue(None) => !nested.in_loop,
+            JsStmt::Continue(Some(target)) => Some(target) == label.as_ref(),
+            _ => false,
+        });
+        let named = jumps(body, &|stmt, nested| match stmt {
+            // Inside the labeled body block a plain `break` must name the loop
+            JsStmt::Break(None) => continued && !nested.in_loop && !nested.in_switch,
+            JsStmt::Break(Some(target)) => Some(target) == label.as_ref(),
+            JsStmt::Continue(Some(target)) => !continued && Some(target) == label.as_ref(),
+            _ => false,
+        });
+
+        self.temps += 1;
+        let name = label.clone().unwrap_or_else(|| format!("loop{}", self.temps));
+        Frame {
+            outer: named.then(|| format!("'{}", name)),
+            body: continued.then(|| format!("'{}_body", name)),
+            label,
+            switch: false,
+        }
+    }
+
+    fn loop_body(&mut self, body: &[JsStmt], frame: Frame) {
+        let body_label = frame.body.clone();
+        self.frames.push(frame);
+        match body_label {
+            Some(label) => {
+                self.depth += 1;
+                self.line(format!("{}: {{", label));
+                self.block(body);
+                self.line("}");
+                self.depth -= 1;
+            }
+            None => self.block(body),
+        }
+        self.frames.pop();
+    }
+
+    /// `break` or `continue`, aimed at the frame the JS one targets
+    fn jump(&self, label: Option<&str>, continues: bool) -> String {
+        let target = self.frames.iter().rev().find(|frame| match label {
+            Some(label) => frame.label.as_deref() == Some(label),
+            None => !(continues && frame.switch),
+        });
+        let Some(frame) = target else {
+            return "todo!(\"jump to labeled block\");".to_string();
+        };
+
+        let innermost = self.frames.last().is_some_and(|last| std::ptr::eq(last, frame));
+        match (continues, &frame.body, &frame.outer) {
+            (true, Some(body), _) => format!("break {};", body),
+            (true, None, Some(outer)) if !innermost => format!("continue {};", outer),
+            (true, None, _) => "continue;".to_string(),
+            (false, _, Some(outer)) => format!("break {};", outer),
+            (false, _, None) => "break;".to_string(),
+        }
+    }
+
+    fn for_loop(&mut self, init: &[JsStmt], test: Option<&JsExpr>, update: Option<&JsExpr>, body: &[JsStmt]) {
+        let label = self.pending_label.take();
+        for stmt in init {
+            self.stmt(stmt);
+        }
+
+        self.pending_label = label;
+        let frame = self.frame(body, update.is_some());
+        match test {
+            Some(test) => {
+                let test = self.condition(test);
+                self.line(format!("{}while {} {{", loop_label(&frame), test));
+            }
+            None => self.line(format!("{}loop {{", loop_label(&frame))),
+        }
+
+        self.loop_body(body, frame);
+        if let Some(update) = update {
+            let update = self.expr_stmt(update);
+            self.depth += 1;
+            self.line(format!("{};", update));
+            self.depth -= 1;
+        }
+        self.line("}");
+    }
+
+    /// `for` over `iter`, whose items are references unless `owned`
+    fn for_each_loop(&mut self, pattern: &JsPattern, iter: String, element: JsType, owned: bool, body: &[JsStmt]) {
+        let frame = self.frame(body, false);
+        let label = loop_label(&frame);
+        let head = match pattern {
+            JsPattern::Ident(name) if owned => {
+                self.bind_owned(name, element);
+                rust_ident(name)
+            }
+            JsPattern::Ident(name) => {
+                self.bind_ref(name, element);
+                rust_ident(name)
+            }
+            // `for (const [key, value] of map)`
+            JsPattern::Array { elements, rest: None } if elements.iter().all(|e| matches!(e, Some(JsPattern::Ident(_)))) => {
+                let names: Vec<String> = elements.iter().flatten().map(|element| match element {
+                    JsPattern::Ident(name) => {
+                        match owned {
+                            true => self.bind_owned(name, JsType::Unknown),
+                            false => self.bind_ref(name, JsType::Unknown),
+                        }
+                        rust_ident(name)
+                    }
+                    _ => unreachable!(),
+                }).collect();
+                format!("({})", names.join(", "))
+            }
+            pattern => {
+                let item = self.temp("item");
+                self.line(format!("{}for {} in {} {{", label, item, iter));
+                self.depth += 1;
+                self.destructure(pattern, &item, element);
+                self.depth -= 1;
+                self.loop_body(body, frame);
+                self.line("}");
+                return;
+            }
+        };
+        self.line(format!("{}for {} in {} {{", label, head, iter));
+        self.loop_body(body, frame);
+        self.line("}");
+    }
+
+    fn bind_ref(&mut self, name: &str, ty: JsType) {
+        self.refs.insert(name.to_string());
+        self.types.insert(name.to_string(), ty);
+    }
+
+    pub(crate) fn bind_owned(&mut self, name: &str, ty: JsType) {
+        self.refs.remove(name);
+        self.types.insert(name.to_string(), ty);
+    }
+
+    pub(crate) fn is_global_regex(&self, name: &str) -> bool {
+        self.global_regexes.contains(name)
+    }
+
+    fn let_binding(&mut self, pattern: &JsPattern, ty: &JsType, init: Option<&JsExpr>) {
+        if let (JsPattern::Array { elements, rest: None }, Some(init)) = (pattern, init) {
+            if let JsType::Tuple(types) = self.expr_type(init) {
+                let names: Option<Vec<&String>> = elements.iter().map(|element| match element {
+                    Some(JsPattern::Ident(name)) => Some(name),
+                    _ => None,
+                }).collect();
+                if let Some(names) = names.filter(|names| names.len() == types.len()) {
+                    self.tuple_binding(&names, types, init);
+                    return;
+                }
+            }
+        }
+
+        let JsPattern::Ident(name) = pattern else {
+            let Some(init) = init else {
+                return;
+            };
+            let known = match ty {
+                JsType::Unknown => self.expr_type(init),
+                ty => ty.clone(),
+            };
+            let source = match init {
+                JsExpr::Ident(name) => rust_ident(name),
+                init => {
+                    let temp = self.temp("destructured");
+                    let value = self.expr(init);
+                    self.line(format!("let {} = {};", temp, value));
+                    temp
+                }
+            };
+            self.destructure(pattern, &source, known);
+            return;
+        };
+
+        if let (true, Some(init)) = (self.integers.contains(name), init) {
+            let value = self.integer(init);
+            let mutability = if self.mutated.contains(name) { "mut " } else { "" };
+            self.refs.remove(name);
+            self.types.insert(name.clone(), JsType::Number);
+            self.global_regexes.remove(name);
+            self.line(format!("let {}{}: i64 = {};", mutability, rust_ident(name), value));
+            return;
+        }
+
+        // Rust needs the type where the initializer doesn't pin it down
+        let annotated = *ty != JsType::Unknown
+            && matches!(init, None | Some(JsExpr::Null | JsExpr::Undefined | JsExpr::Array(_) | JsExpr::Object(_)));
+        let annotation = match self.forge.infer_type(ty) {
+            // Leave element types Rust can work out from later pushes
+            rust_type if annotated && !rust_type.contains("Value") => format!(": {}", rust_type),
+            _ => String::new(),
+        };
+        let known = match (ty, init) {
+            (JsType::Unknown, Some(init)) => self.expr_type(init),
+            (ty, _) => ty.clone(),
+        };
+
+        let value = init.map(|init| self.expr(init));
+        let stateful = matches!(init, Some(JsExpr::Function(function)) if closure_kind(function) == Callable::Mut);
+        let mutability = if self.mutated.contains(name) || stateful { "mut " } else { "" };
+        self.refs.remove(name);
+        self.types.insert(name.clone(), known);
+        match init {
+            Some(JsExpr::Regex { flags, .. }) if flags.contains('g') => self.global_regexes.insert(name.clone()),
+            _ => self.global_regexes.remove(name),
+        };
+
+        match value {
+            Some(value) => self.line(format!("let {}{}{} = {};", mutability, rust_ident(name), annotation, value)),
+            None => self.line(format!("let {}{}{};", mutability, rust_ident(name), annotation)),
+        }
+    }
+
+    /// `let (a, b) = ...` for `const [a, b] = ...` of a tuple, such as `Promise.all` gives
+    fn tuple_binding(&mut self, names: &[&String], types: Vec<JsType>, init: &JsExpr) {
+        let value = self.expr(init);
+        let bindings: Vec<String> = names.iter().zip(types).map(|(name, ty)| {
+            self.refs.remove(*name);
+            self.types.insert(name.to_string(), ty);
+            let mutability = if self.mutated.contains(*name) { "mut " } else { "" };
+            format!("{}{}", mutability, rust_ident(name))
+        }).collect();
+        match bindings.as_slice() {
+            [single] => self.line(format!("let ({},) = {};", single, value)),
+            bindings => self.line(format!("let ({}) = {};", bindings.join(", "), value)),
+        }
+    }
+
+    /// Bind each name in `pattern` from the value at `source`, of type `ty`
+    fn destructure(&mut self, pattern: &JsPattern, source: &str, ty: JsType) {
+        match (pattern, ty) {
+            (JsPattern::Array { elements, rest }, JsType::Array(element)) if !elements.is_empty() && is_slice_pattern(elements, rest.as_deref()) => {
+                self.slice_binding(elements, rest.as_deref(), source, *element);
+            }
+            (JsPattern::Object { properties, rest: None }, JsType::Named(name, _))
+                if properties.iter().all(|(key, property)| {
+                    matches!(property, JsPattern::Ident(_)) && self.types.contains_key(&field_key(&name, key))
+                }) =>
+            {
+                self.struct_binding(&name, properties, source);
+            }
+            (JsPattern::Ident(_) | JsPattern::Default(..), _) => self.bind(pattern, format!("{}.clone()", source)),
+            (JsPattern::Array { elements, rest }, _) => {
+                for (i, element) in elements.iter().enumerate() {
+                    let Some(element) = element else {
+                        continue;
+                    };
+                    let value = match element {
+                        JsPattern::Default(_, default) => {
+                            format!("{}.get({}).cloned().unwrap_or({})", source, i, self.expr(default))
+                        }
+                        _ => format!("{}[{}].clone()", source, i),
+                    };
+                    self.bind(element, value);
+                }
+                if let Some(rest) = rest {
+                    self.bind(rest, format!("{}[{}..].to_vec()", source, elements.len()));
+                }
+            }
+            (JsPattern::Object { properties, rest }, _) => {
+                for (key, property) in properties {
+                    self.bind(property, format!("{}.{}.clone()", source, rust_ident(key)));
+                }
+                if rest.is_some() {
+                    self.line("todo!(\"object rest pattern\");");
+                }
+            }
+        }
+    }
+
+    /// `let [a, _, b, rest @ ..] = xs.as_slice() else { .. };`, which JS
+    /// would fill out with `undefined` where Rust has no elements to bind
+    fn slice_binding(&mut self, elements: &[Option<JsPattern>], rest: Option<&JsPattern>, source: &str, element: JsType) {
+        let mut names = vec![];
+        let mut parts: Vec<String> = elements.iter().map(|element| match element {
+            Some(JsPattern::Ident(name)) => {
+                names.push(name);
+                rust_ident(name)
+            }
+            _ => "_".to_string(),
+        }).collect();
+        parts.push(match rest {
+            Some(JsPattern::Ident(name)) => format!("{} @ ..", rust_ident(name)),
+            _ => "..".to_string(),
+        });
+        self.line(format!(
+            "let [{}] = {}.as_slice() else {{ panic!(\"expected at least {} elements\") }};",
+            parts.join(", "),
+            source,
+            elements.len(),
+        ));
+        for name in names {
+            self.borrowed_binding(name, element.clone());
+        }
+        if let Some(JsPattern::Ident(name)) = rest {
+            let mutability = if self.mutated.contains(name) { "mut " } else { "" };
+            self.refs.remove(name);
+            self.types.insert(name.clone(), JsType::Array(Box::new(element)));
+            self.line(format!("let {}{} = {}.to_vec();", mutability, rust_ident(name), rust_ident(name)));
+        }
+    }
+
+    /// `let Point { x, y: other, .. } = &point;`, of a struct the module declares
+    fn struct_binding(&mut self, name: &str, properties: &[(String, JsPattern)], source: &str) {
+        let mut fields = vec![];
+        for (key, property) in properties {
+            let JsPattern::Ident(local) = property else { continue };
+            fields.push(match key == local {
+                true => rust_ident(local),
+                false => format!("{}: {}", rust_ident(key), rust_ident(local)),
+            });
+        }
+        fields.push("..".to_string());
+        self.line(format!("let {} {{ {} }} = &{};", name, fields.join(", "), source));
+        for (key, property) in properties {
+            if let JsPattern::Ident(local) = property {
+                let ty = self.types.get(&field_key(name, key)).cloned().unwrap_or(JsType::Unknown);
+                self.borrowed_binding(local, ty);
+            }
+        }
+    }
+
+    /// A name a pattern binds by reference, or by a copy when it is reassigned
+    fn borrowed_binding(&mut self, name: &str, ty: JsType) {
+        match self.mutated.contains(name) {
+            true => {
+                self.refs.remove(name);
+                self.types.insert(name.to_string(), ty);
+                self.line(format!("let mut {} = {}.clone();", rust_ident(name), rust_ident(name)));
+            }
+            false => self.bind_ref(name, ty),
+        }
+    }
+
+    fn bind(&mut self, pattern: &JsPattern, value: String) {
+        match pattern {
+            JsPattern::Ident(name) => {
+                let mutability = if self.mutated.contains(name) { "mut " } else { "" };
+                self.refs.remove(name);
+                self.types.remove(name);
+                self.line(format!("let {}{} = {};", mutability, rust_ident(name), value));
+            }
+            JsPattern::Default(inner, _) => self.bind(inner, value),
+            nested => {
+                let temp = self.temp("destructured");
+                self.line(format!("let {} = {};", temp, value));
+                self.destructure(nested, &temp, JsType::Unknown);
+            }
+        }
+    }
+
+    // Expressions
+
+    /// An expression in statement position, where `i++` is just `i += 1.0`
+    fn this(&self) -> &str {
+        self.class.as_ref().map_or("self", |class| &class.this)
+    }
+
+    /// A field or getter of `this`
+    fn class_member(&self, object: &JsExpr, property: &str) -> Option<&ClassMember> {
+        match object {
+            JsExpr::This => self.class.as_ref()?.members.get(property),
+            _ => None,
+        }
+    }
+
+    /// `let this = Self { .. }` in a constructor, with the base built from `super_args`
+    fn construct_this(&mut self, super_args: &[JsExpr]) -> String {
+        let Some(construct) = self.class.as_ref().and_then(|class| class.construct.clone()) else {
+            return "todo!(\"super() outside a constructor\");".to_string();
+        };
+        let mut fields = vec![];
+        if let Some((field, base)) = &construct.base {
+            let base = self.construct(&JsExpr::Ident(base.clone()), super_args);
+            fields.push(format!("{}: {}", field, base));
+        }
+        for (name, init) in &construct.fields {
+            let value = init.as_ref().map_or("Default::default()".to_string(), |init| self.expr(init));
+            fields.push(format!("{}: {}", rust_ident(name), value));
+        }
+        let mutability = if self.mutated.contains("this") { "mut " } else { "" };
+        format!("let {}this = Self {{ {} }};", mutability, fields.join(", "))
+    }
+
+    fn expr_stmt(&mut self, expr: &JsExpr) -> String {
+        match expr {
+            JsExpr::Update { op, target, .. } if self.counts(target) => {
+                let place = self.place(target);
+                self.forge.overflow.step(&place, &op[..1], "1")
+            }
+            JsExpr::Update { op, target, .. } => {
+                let place = self.place(target);
+                format!("{} {} 1.0", place, if *op == "++" { "+=" } else { "-=" })
+            }
+            expr => self.expr(expr),
+        }
+    }
+
+    fn expr(&mut self, expr: &JsExpr) -> String {
+        match expr {
+            JsExpr::Number(n) => number(*n),
+            JsExpr::String(s) => format!("{:?}.to_string()", s),
+            JsExpr::Bool(b) => b.to_string(),
+            JsExpr::Null | JsExpr::Undefined => "None".to_string(),
+            JsExpr::Regex { pattern, flags } => strings::regex(pattern, flags),
+            JsExpr::Template { quasis, exprs } => {
+                let mut parts = vec![];
+                for (i, quasi) in quasis.iter().enumerate() {
+                    parts.push(Part::Text(quasi.clone()));
+                    if let Some(expr) = exprs.get(i) {
+                        parts.push(Part::Expr(expr));
+                    }
+                }
+                self.format(&parts)
+            }
+            JsExpr::Ident(name) => match name.as_str() {
+                "NaN" => "f64::NAN".to_string(),
+                "Infinity" => "f64::INFINITY".to_string(),
+                name if self.integers.contains(name) => format!("{} as f64", rust_ident(name)),
+                name => rust_ident(name),
+            },
+            JsExpr::This => self.this().to_string(),
+            JsExpr::Super => "todo!(\"super\")".to_string(),
+            JsExpr::Array(items) => self.array(items),
+            JsExpr::Object(props) if !props.iter().any(|prop| matches!(prop, JsProp::Spread(_))) => {
+                let props: Vec<String> = props.iter().map(|prop| match prop {
+                    JsProp::KeyValue(key, value) => format!("{:?}: {}", key, self.expr(value)),
+                    JsProp::Spread(_) => unreachable!(),
+                }).collect();
+                format!("serde_json::json!({{ {} }})", props.join(", "))
+            }
+            // `{ ...base, key }` merges into a JSON object, later keys winning,
+            // unless it updates a struct
+            JsExpr::Object(props) => {
+                if let Some(update) = self.struct_update(props) {
+                    return update;
+                }
+                let mut steps = vec!["let mut object = serde_json::Map::new();".to_string()];
+                for prop in props {
+                    steps.push(match prop {
+                        JsProp::KeyValue(key, value) => {
+                            format!("object.insert({:?}.to_string(), serde_json::json!({}));", key, self.expr(value))
+                        }
+                        JsProp::Spread(spread) => format!(
+                            "if let Value::Object(fields) = serde_json::to_value(&{}).unwrap() {{ object.extend(fields); }}",
+                            self.operand(spread, false),
+                        ),
+                    });
+                }
+                format!("{{ {} Value::Object(object) }}", steps.join(" "))
+            }
+            JsExpr::Spread(_) => "todo!(\"spread\")".to_string(),
+            JsExpr::Unary(op, arg) => match *op {
+                "!" => match self.expr_type(arg) {
+                    JsType::Number => format!("{} == 0.0", self.operand(arg, true)),
+                    JsType::String => format!("{}.is_empty()", self.receiver(arg)),
+                    JsType::Optional(_) => format!("{}.is_none()", self.receiver(arg)),
+                    _ => format!("!{}", self.operand(arg, false)),
+                },
+                "-" => format!("-{}", self.operand(arg, false)),
+                "+" => self.numeric(arg),
+                "~" => format!("(!({} as i64)) as f64", self.operand(arg, false)),
+                "void" => "()".to_string(),
+                op => format!("todo!({:?})", format!("{} operator", op)),
+            },
+            JsExpr::Update { .. } if self.counts(expr) => format!("{} as f64", self.integer(expr)),
+            JsExpr::Update { op, prefix, target } => {
+                let place = self.place(target);
+                let step = if *op == "++" { "+=" } else { "-=" };
+                if *prefix {
+                    format!("{{ {} {} 1.0; {} }}", place, step, place)
+                } else {
+                    format!("{{ let previous = {}; {} {} 1.0; previous }}", place, place, step)
+                }
+            }
+            JsExpr::Binary(op, left, right) => self.binary(op, left, right),
+            JsExpr::Assign(op, target, value) => self.assign(op, target, value),
+            JsExpr::Conditional(test, then, otherwise) => format!(
+                "if {} {{ {} }} else {{ {} }}",
+                self.condition(test),
+                self.expr(then),
+                self.expr(otherwise),
+            ),
+            JsExpr::Call(callee, args) => self.call(callee, args),
+            JsExpr::New(callee, args) => self.construct(callee, args),
+            // Fields can't move out of `self`
+            JsExpr::Member(object, property) if matches!(**object, JsExpr::This) => {
+                let owned = match self.class_member(object, property) {
+                    Some(member) => member.computed || is_copy(&member.ty),
+                    None => false,
+                };
+                match owned {
+                    true => self.member(object, property),
+                    false => format!("{}.clone()", self.member(object, property)),
+                }
+            }
+            JsExpr::Member(object, property) => self.member(object, property),
+            // Indexing can't move out of the collection
+            JsExpr::Index(..) => match self.expr_type(expr) {
+                JsType::Number | JsType::Boolean | JsType::BigInt => self.place(expr),
+                _ if self.is_string(expr) => self.place(expr),
+                _ => format!("{}.clone()", self.place(expr)),
+            },
+            JsExpr::Function(function) => self.closure(function, &[]),
+            JsExpr::Await(future) => self.await_expr(future),
+            JsExpr::Yield { value, delegate: false } => match self.yielding {
+                Some(yielding) => {
+                    let value = value.as_ref().map_or("()".to_string(), |value| self.arguments(std::slice::from_ref(&**value)));
+                    yielding.hand_out(&value)
+                }
+                None => "todo!(\"yield outside a generator\")".to_string(),
+            },
+            JsExpr::Yield { .. } => "todo!(\"yield* as a value\")".to_string(),
+            JsExpr::Sequence(exprs) => {
+                let exprs: Vec<String> = exprs.iter().map(|expr| self.expr_stmt(expr)).collect();
+                format!("{{ {} }}", exprs.join("; "))
+            }
+            JsExpr::Unsupported(what) => format!("todo!({:?})", what),
+        }
+    }
+
+    /// An operator operand: compound expressions parenthesized, references dereferenced
+    ///
+    /// String literals compared with `==` stay `&str`, which `String` compares against.
+    fn operand(&mut self, expr: &JsExpr, comparison: bool) -> String {
+        match expr {
+            JsExpr::Ident(name) if self.refs.contains(name) => format!("*{}", rust_ident(name)),
+            JsExpr::String(s) if comparison => format!("{:?}", s),
+            JsExpr::Index(..) if comparison => self.place(expr),
+            // `i as f64 < x` would parse `<` as generics
+            JsExpr::Ident(name) if self.integers.contains(name) => format!("({})", self.expr(expr)),
+            expr if is_compound(expr) => format!("({})", self.expr(expr)),
+            expr => self.expr(expr),
+        }
+    }
+
+    /// An operand of an arithmetic or comparison operator binding `parent` tightly
+    ///
+    /// Arithmetic children that already bind tighter (or as tight, on the
+    /// left) skip the parentheses.
+    fn side(&mut self, expr: &JsExpr, parent: u8, right: bool) -> String {
+        if let JsExpr::Binary(op, ..) = expr {
+            let child = precedence(op).filter(|_| !self.is_string(expr));
+            if child.is_some_and(|child| child > parent || (child == parent && !right)) {
+                return self.expr(expr);
+            }
+        }
+        self.operand(expr, parent == 0)
+    }
+
+    /// A method receiver; numeric literals need a concrete float type
+    pub(crate) fn receiver(&mut self, expr: &JsExpr) -> String {
+        match expr {
+            JsExpr::Number(n) => format!("{}_f64", number(*n)),
+            JsExpr::Member(object, property) if matches!(**object, JsExpr::This) => self.member(object, property),
+            JsExpr::Index(..) => self.place(expr),
+            JsExpr::Ident(name) if self.integers.contains(name) => format!("({})", self.expr(expr)),
+            expr if is_compound(expr) || matches!(expr, JsExpr::Unary(..)) => format!("({})", self.expr(expr)),
+            expr => self.expr(expr),
+        }
+    }
+
+    /// An index or count: integral literals as-is, anything else cast to `usize`
+    pub(crate) fn index(&mut self, expr: &JsExpr) -> String {
+        match expr {
+            JsExpr::Number(n) if n.fract() == 0.0 && *n >= 0.0 => format!("{}", *n as usize),
+            expr if self.counts(expr) => format!("{} as usize", self.int_operand(expr)),
+            expr => format!("{} as usize", self.operand(expr, false)),
+        }
+    }
+
+    /// A `&str` argument: literals stay literals, anything else is borrowed
+    pub(crate) fn str_arg(&mut self, expr: Option<&JsExpr>) -> String {
+        match expr {
+            Some(JsExpr::String(s)) => format!("{:?}", s),
+            Some(expr) => format!("&{}", self.operand(expr, false)),
+            None => "\"\"".to_string(),
+        }
+    }
+
+    /// A lookup key or needle, borrowed unless it already is a reference
+    fn borrowed(&mut self, expr: Option<&JsExpr>) -> String {
+        match expr {
+            Some(JsExpr::Ident(name)) if self.refs.contains(name) => rust_ident(name),
+            Some(JsExpr::String(s)) => format!("{:?}", s),
+            Some(expr) => format!("&{}", self.operand(expr, false)),
+            None => "&()".to_string(),
+        }
+    }
+
+    /// A receiver written through, as a field of `this` may need its `_mut` accessor
+    fn place_receiver(&mut self, object: &JsExpr) -> String {
+        match object {
+            JsExpr::Member(inner, _) if matches!(**inner, JsExpr::This) => self.place(object),
+            object => self.receiver(object),
+        }
+    }
+
+    /// The place an assignment or update writes to
+    fn place(&mut self, target: &JsExpr) -> String {
+        match target {
+            JsExpr::Ident(name) if self.refs.contains(name) => format!("*{}", rust_ident(name)),
+            JsExpr::Ident(name) if self.integers.contains(name) => rust_ident(name),
+            JsExpr::Member(object, property) if matches!(**object, JsExpr::This) => match self.class_member(object, property) {
+                Some(member) => member.place.clone(),
+                None => self.member(object, property),
+            },
+            JsExpr::Index(object, index) => match self.expr_type(object) {
+                JsType::Map(..) => format!("{}[&{}]", self.place_receiver(object), self.expr(index)),
+                JsType::String => format!(
+                    "{}.chars().nth({}).map(String::from).unwrap_or_default()",
+                    self.receiver(object),
+                    self.index(index),
+                ),
+                _ => format!("{}[{}]", self.place_receiver(object), self.index(index)),
+            },
+            target => self.expr(target),
+        }
+    }
+
+    /// `Point { x, ..point.clone() }` for `{ ...point, x }`, where `point` is
+    /// a struct the module declares, spread first, and each key is a field of it
+    fn struct_update(&mut self, props: &[JsProp]) -> Option<String> {
+        let [JsProp::Spread(base), props @ ..] = props else {
+            return None;
+        };
+        let JsType::Named(name, _) = self.expr_type(base) else {
+            return None;
+        };
+        let fields = props.iter().map(|prop| match prop {
+            JsProp::KeyValue(key, value) if self.types.contains_key(&field_key(&name, key)) => Some((key, value)),
+            _ => None,
+        }).collect::<Option<Vec<_>>>()?;
+        let fields: Vec<String> = fields.into_iter().map(|(key, value)| {
+            format!("{}: {}, ", rust_ident(key), self.arguments(std::slice::from_ref(value)))
+        }).collect();
+        Some(format!("{} {{ {}..{} }}", name, fields.concat(), self.arguments(std::slice::from_ref(base))))
+    }
+
+    /// JS truthiness spelled out for non-boolean conditions
+    fn condition(&mut self, expr: &JsExpr) -> String {
+        if self.counts(expr) {
+            return format!("{} != 0", self.int_side(expr, 0, false));
+        }
+        match self.expr_type(expr) {
+            JsType::Number => format!("{} != 0.0", self.side(expr, 0, false)),
+            JsType::String => format!("!{}.is_empty()", self.receiver(expr)),
+            JsType::Optional(_) => format!("{}.is_some()", self.receiver(expr)),
+            _ => self.expr(expr),
+        }
+    }
+
+    fn numeric(&mut self, expr: &JsExpr) -> String {
+        match self.expr_type(expr) {
+            JsType::Number => self.expr(expr),
+            JsType::Boolean => format!("if {} {{ 1.0 }} else {{ 0.0 }}", self.expr(expr)),
+            _ => format!("{}.trim().parse::<f64>().unwrap_or(f64::NAN)", self.receiver(expr)),
+        }
+    }
+
+    fn binary(&mut self, op: &str, left: &JsExpr, right: &JsExpr) -> String {
+        match op {
+            "+" if self.is_string(left) || self.is_string(right) => {
+                let mut parts = vec![];
+                self.concat_parts(left, &mut parts);
+                self.concat_parts(right, &mut parts);
+                self.format(&parts)
+            }
+            "==" | "===" | "!=" | "!==" if matches!(right, JsExpr::Null | JsExpr::Undefined) => {
+                let check = if op.starts_with('=') { "is_none" } else { "is_some" };
+                format!("{}.{}()", self.receiver(left), check)
+            }
+            "==" | "===" | "!=" | "!==" | "<" | "<=" | ">" | ">=" if self.compares_integers(left, right) => format!(
+                "{} {} {}",
+                self.int_side(left, 0, false),
+                match op {
+                    "===" => "==",
+                    "!==" => "!=",
+                    op => op,
+                },
+                self.int_side(right, 0, true),
+            ),
+            "==" | "===" | "!=" | "!==" | "<" | "<=" | ">" | ">=" => format!(
+                "{} {} {}",
+                self.side(left, 0, false),
+                match op {
+                    "===" => "==",
+                    "!==" => "!=",
+                    op => op,
+                },
+                self.side(right, 0, true),
+            ),
+            "+" | "-" | "*" | "/" | "%" => {
+                let parent = precedence(op).unwrap_or_default();
+                format!("{} {} {}", self.side(left, parent, false), op, self.side(right, parent, true))
+            }
+            "**" => format!("{}.powf({})", self.receiver(left), self.expr(right)),
+            "&" | "|" | "^" | "<<" | ">>" => format!(
+                "(({} as i64) {} ({} as i64)) as f64",
+                self.operand(left, false),
+                op,
+                self.operand(right, false),
+            ),
+            ">>>" => format!("(({} as u32) >> ({} as u32)) as f64", self.operand(left, false), self.operand(right, false)),
+            "&&" | "||" => {
+                let left = self.logical_operand(left);
+                let right = self.logical_operand(right);
+                format!("{} {} {}", left, op, right)
+            }
+            "??" => format!("{}.unwrap_or({})", self.receiver(left), self.expr(right)),
+            op => format!("todo!({:?})", format!("{} operator", op)),
+        }
+    }
+
+    /// Whether `expr` is integral and reads a local kept as an `i64`, so is
+    /// best worked out as one
+    fn counts(&self, expr: &JsExpr) -> bool {
+        !self.integers.is_empty() && numeric::is_integral(expr, &self.integers) && numeric::mentions(expr, &self.integers)
+    }
+
+    fn compares_integers(&self, left: &JsExpr, right: &JsExpr) -> bool {
+        numeric::is_integral(left, &self.integers) && numeric::is_integral(right, &self.integers) && (self.counts(left) || self.counts(right))
+    }
+
+    /// An integral expression as an `i64`, with arithmetic overflowing as
+    /// the forge's `Overflow` says
+    fn integer(&mut self, expr: &JsExpr) -> String {
+        match expr {
+            JsExpr::Number(n) => format!("{}", *n as i64),
+            JsExpr::Ident(name) if self.integers.contains(name) => rust_ident(name),
+            JsExpr::Member(object, property) if property == "length" => {
+                let count = self.member(object, property);
+                format!("{} as i64", count.strip_suffix(" as f64").unwrap_or(&count))
+            }
+            JsExpr::Update { op, prefix, target } => {
+                let place = self.place(target);
+                let step = self.forge.overflow.step(&place, &op[..1], "1");
+                match prefix {
+                    true => format!("{{ {}; {} }}", step, place),
+                    false => format!("{{ let previous = {}; {}; previous }}", place, step),
+                }
+            }
+            JsExpr::Unary("-", arg) => format!("-{}", self.int_operand(arg)),
+            JsExpr::Binary(op, left, right) => {
+                let overflow = self.forge.overflow;
+                let (left, right) = match overflow.is_infix(op) {
+                    true => {
+                        let parent = precedence(op).unwrap_or_default();
+                        (self.int_side(left, parent, false), self.int_side(right, parent, true))
+                    }
+                    // A receiver; other method calls bind tightly enough
+                    false => (self.int_side(left, u8::MAX, false), self.integer(right)),
+                };
+                overflow.arithmetic(op, &left, &right)
+            }
+            JsExpr::Conditional(test, then, otherwise) => format!(
+                "if {} {{ {} }} else {{ {} }}",
+                self.condition(test),
+                self.integer(then),
+                self.integer(otherwise),
+            ),
+            // `Math.floor(..)` and the like
+            expr => format!("{} as i64", self.receiver(expr)),
+        }
+    }
+
+    /// An integral operand, parenthesized unless it is a name or a literal
+    fn int_operand(&mut self, expr: &JsExpr) -> String {
+        match expr {
+            JsExpr::Ident(_) => self.integer(expr),
+            JsExpr::Number(n) if *n >= 0.0 => self.integer(expr),
+            expr => format!("({})", self.integer(expr)),
+        }
+    }
+
+    /// An integral operand of an operator binding `parent` tightly, as
+    /// `side` is for floats; overflow methods bind tighter than any
+    fn int_side(&mut self, expr: &JsExpr, parent: u8, right: bool) -> String {
+        if let JsExpr::Binary(op, ..) = expr {
+            let tighter = match (self.forge.overflow.is_infix(op), precedence(op)) {
+                (false, _) => true,
+                (true, Some(child)) => child > parent || (child == parent && !right),
+                (true, None) => false,
+            };
+            if tighter {
+                return self.integer(expr);
+            }
+        }
+        self.int_operand(expr)
+    }
+
+    fn logical_operand(&mut self, expr: &JsExpr) -> String {
+        let condition = self.condition(expr);
+        match expr {
+            JsExpr::Binary("&&" | "||" | "??", ..) | JsExpr::Conditional(..) | JsExpr::Assign(..) => format!("({})", condition),
+            _ => condition,
+        }
+    }
+
+    /// Flatten a `+` chain that builds a string into `format!` parts
+    fn concat_parts<'e>(&mut self, expr: &'e JsExpr, parts: &mut Vec<Part<'e>>) {
+        match expr {
+            JsExpr::Binary("+", left, right) if self.is_string(expr) => {
+                self.concat_parts(left, parts);
+                self.concat_parts(right, parts);
+            }
+            JsExpr::String(s) => parts.push(Part::Text(s.clone())),
+            JsExpr::Template { quasis, exprs } => {
+                for (i, quasi) in quasis.iter().enumerate() {
+                    parts.push(Part::Text(quasi.clone()));
+                    if let Some(expr) = exprs.get(i) {
+                        parts.push(Part::Expr(expr));
+                    }
+                }
+            }
+            expr => parts.push(Part::Expr(expr)),
+        }
+    }
+
+    fn format(&mut self, parts: &[Part]) -> String {
+        let mut template = String::new();
+        let mut args = vec![];
+        for part in parts {
+            match part {
+                Part::Text(text) => template.push_str(&text.replace('{', "{{").replace('}', "}}")),
+                Part::Expr(expr) => {
+                    template.push_str(if self.displays(expr) { "{}" } else { "{:?}" });
+                    args.push(self.expr(expr));
+                }
+            }
+        }
+
+        if args.is_empty() {
+            return format!("{:?}.to_string()", template.replace("{{", "{").replace("}}", "}"));
+        }
+        format!("format!({:?}, {})", template, args.join(", "))
+    }
+
+    /// Whether `{}` prints the value the way JS string conversion would
+    fn displays(&mut self, expr: &JsExpr) -> bool {
+        matches!(self.expr_type(expr), JsType::Number | JsType::String | JsType::Boolean | JsType::Unknown)
+    }
+
+    fn assign(&mut self, op: &str, target: &JsExpr, value: &JsExpr) -> String {
+        let place = self.place(target);
+        if self.counts(target) {
+            let value = self.integer(value);
+            return match op {
+                "=" => format!("{} = {}", place, value),
+                op => self.forge.overflow.step(&place, op.trim_end_matches('='), &value),
+            };
+        }
+        match op {
+            "=" => format!("{} = {}", place, self.expr(value)),
+            "+=" if self.is_string(target) => match value {
+                JsExpr::String(s) => format!("{}.push_str({:?})", place, s),
+                value => format!("{} += &{}", place, self.operand(value, false)),
+            },
+            "+=" | "-=" | "*=" | "/=" | "%=" => match value {
+                JsExpr::Ident(name) if self.refs.contains(name) => format!("{} {} *{}", place, op, rust_ident(name)),
+                value => format!("{} {} {}", place, op, self.expr(value)),
+            },
+            // `x ||= y` is `x = x || y`, and so on
+            op => {
+                let binary_op = match op {
+                    "**=" => "**",
+                    "&&=" => "&&",
+                    "||=" => "||",
+                    "??=" => "??",
+                    "&=" => "&",
+                    "|=" => "|",
+                    "^=" => "^",
+                    "<<=" => "<<",
+                    ">>=" => ">>",
+                    _ => ">>>",
+                };
+                format!("{} = {}", place, self.binary(binary_op, target, value))
+            }
+        }
+    }
+
+    fn array(&mut self, items: &[JsExpr]) -> String {
+        if !items.iter().any(|item| matches!(item, JsExpr::Spread(_))) {
+            let items: Vec<String> = items.iter().map(|item| self.element(item)).collect();
+            return format!("vec![{}]", items.join(", "));
+        }
+
+        // `[a, ...rest, b]` concatenates slices
+        let mut segments = vec![];
+        let mut run = vec![];
+        for item in items {
+            match item {
+                JsExpr::Spread(spread) => {
+                    if !run.is_empty() {
+                        segments.push(format!("&[{}][..]", run.join(", ")));
+                        run.clear();
+                    }
+                    segments.push(format!("{}.as_slice()", self.receiver(spread)));
+                }
+                item => run.push(self.element(item)),
+            }
+        }
+        if !run.is_empty() {
+            segments.push(format!("&[{}][..]", run.join(", ")));
+        }
+        format!("[{}].concat()", segments.join(", "))
+    }
+
+    /// An array element, copied or cloned out of a name bound by reference
+    fn element(&mut self, item: &JsExpr) -> String {
+        match item {
+            JsExpr::Ident(name) if self.refs.contains(name) => self.arguments(std::slice::from_ref(item)),
+            item => self.expr(item),
+        }
+    }
+
+    fn member(&mut self, object: &JsExpr, property: &str) -> String {
+        if let Some(member) = self.class_member(object, property) {
+            return member.path.clone();
+        }
+        if let JsExpr::Ident(namespace) = object {
+            let constant = match (namespace.as_str(), property) {
+                ("Math", "PI") => Some("std::f64::consts::PI"),
+                ("Math", "E") => Some("std::f64::consts::E"),
+                ("Math", "LN2") => Some("std::f64::consts::LN_2"),
+                ("Math", "LN10") => Some("std::f64::consts::LN_10"),
+                ("Math", "SQRT2") => Some("std::f64::consts::SQRT_2"),
+                ("Number", "EPSILON") => Some("f64::EPSILON"),
+                ("Number", "MAX_VALUE") => Some("f64::MAX"),
+                ("Number", "MIN_VALUE") => Some("f64::MIN_POSITIVE"),
+                ("Number", "MAX_SAFE_INTEGER") => Some("9007199254740991.0"),
+                ("Number", "MIN_SAFE_INTEGER") => Some("-9007199254740991.0"),
+                ("Number", "POSITIVE_INFINITY") => Some("f64::INFINITY"),
+                ("Number", "NEGATIVE_INFINITY") => Some("f64::NEG_INFINITY"),
+                ("Number", "NaN") => Some("f64::NAN"),
+                _ => None,
+            };
+            if let Some(constant) = constant.filter(|_| !self.types.contains_key(namespace)) {
+                return constant.to_string();
+            }
+        }
+
+        match (property, self.expr_type(object)) {
+            ("message" | "name", JsType::Named(name, _)) if name == "JsError" => {
+                format!("{}.{}().to_string()", self.receiver(object), property)
+            }
+            ("length", JsType::String) => format!("{}.chars().count() as f64", self.receiver(object)),
+            ("length", _) => format!("{}.len() as f64", self.receiver(object)),
+            ("size", JsType::Map(..) | JsType::Set(_)) => format!("{}.len() as f64", self.receiver(object)),
+            (property, _) => format!("{}.{}", self.receiver(object), rust_ident(property)),
+        }
+    }
+
+    fn construct(&mut self, callee: &JsExpr, args: &[JsExpr]) -> String {
+        let JsExpr::Ident(name) = callee else {
+            return "todo!(\"computed constructor\")".to_string();
+        };
+        match (name.as_str(), args.first()) {
+            ("Map", None) => "HashMap::new()".to_string(),
+            ("Map", Some(entries)) => format!("{}.iter().cloned().collect::<HashMap<_, _>>()", self.receiver(entries)),
+            ("Set", None) => "HashSet::new()".to_string(),
+            ("Set", Some(items)) => format!("{}.iter().cloned().collect::<HashSet<_>>()", self.receiver(items)),
+            ("Array", Some(length)) => format!("Vec::with_capacity({})", self.index(length)),
+            ("Array", None) => "Vec::new()".to_string(),
+            (name, message) if name.ends_with("Error") => self.error_value(name, message),
+            ("Promise", Some(JsExpr::Function(executor))) => match timer_delay(executor) {
+                Some(delay) => format!("sleep_ms({})", self.expr(delay)),
+                None => "todo!(\"new Promise\")".to_string(),
+            },
+            ("Date" | "RegExp" | "Promise" | "Proxy" | "WeakMap" | "WeakSet", _) => format!("todo!(\"new {}\")", name),
+            (name, _) => {
+                let construct = format!("{}::new({})", rust_ident(name), self.arguments(args));
+                match self.types.get(&format!("new {}", name)) {
+                    Some(JsType::Function(_, ret)) if matches!(**ret, JsType::Throws(_)) => self.propagate(construct),
+                    _ => construct,
+                }
+            }
+        }
+    }
+
+    /// `new RangeError(message)` as its `JsError` variant
+    fn error_value(&mut self, class: &str, message: Option<&JsExpr>) -> String {
+        let owned = message.is_none_or(|message| self.is_string(message));
+        let message = message.map_or("String::new()".to_string(), |message| self.expr(message));
+        match class {
+            "Error" => format!("JsError::new({})", message),
+            class if owned => format!("JsError::{}({})", class, message),
+            class => format!("JsError::{}({}.to_string())", class, message),
+        }
+    }
+
+    fn arguments(&mut self, args: &[JsExpr]) -> String {
+        let args: Vec<String> = args.iter().map(|arg| match arg {
+            // JS passes by sharing; clone anything that isn't Copy
+            JsExpr::Ident(name) => match self.types.get(name) {
+                Some(JsType::Number | JsType::Boolean | JsType::BigInt) if !self.refs.contains(name) => rust_ident(name),
+                Some(JsType::Number | JsType::Boolean | JsType::BigInt) => format!("*{}", rust_ident(name)),
+                _ => format!("{}.clone()", rust_ident(name)),
+            },
+            JsExpr::Spread(_) => "todo!(\"spread argument\")".to_string(),
+            arg => self.expr(arg),
+        }).collect();
+        args.join(", ")
+    }
+
+    /// The arguments of a call to the module's function `name`: those past a
+    /// `...rest` parameter gathered into its array, and an array spread
+    /// over the parameters it fills
+    fn call_arguments(&mut self, name: &str, args: &[JsExpr]) -> String {
+        let Some(JsType::Function(params, _)) = self.types.get(name).cloned() else {
+            return self.arguments(args);
+        };
+        if self.types.contains_key(&format!("...{}", name)) {
+            let (fixed, rest) = args.split_at(params.len().saturating_sub(1).min(args.len()));
+            let rest = match rest {
+                [JsExpr::Spread(array)] => self.arguments(std::slice::from_ref(&**array)),
+                rest => self.array(rest),
+            };
+            return match fixed {
+                [] => rest,
+                fixed => format!("{}, {}", self.arguments(fixed), rest),
+            };
+        }
+        match args.split_last() {
+            Some((JsExpr::Spread(array), fixed)) if params.len() > fixed.len() && matches!(self.expr_type(array), JsType::Array(_)) => {
+                let spread = (0..params.len() - fixed.len()).map(|i| JsExpr::Index(array.clone(), Box::new(JsExpr::Number(i as f64))));
+                let args: Vec<JsExpr> = fixed.iter().cloned().chain(spread).collect();
+                self.arguments(&args)
+            }
+            _ => self.arguments(args),
+        }
+    }
+
+    fn call(&mut self, callee: &JsExpr, args: &[JsExpr]) -> String {
+        match callee {
+            JsExpr::Member(object, method) if matches!(**object, JsExpr::Super) => {
+                let Some(parent) = self.class.as_ref().and_then(|class| class.parent.clone()) else {
+                    return format!("todo!({:?})", format!("super.{}", method));
+                };
+                let call = format!("{}.{}({})", parent, rust_ident(method), self.arguments(args));
+                match self.class.as_ref().is_some_and(|class| class.throwing.contains(method)) {
+                    true => self.propagate(call),
+                    false => call,
+                }
+            }
+            JsExpr::Member(object, method)
+                if matches!(**object, JsExpr::This) && self.class.as_ref().is_some_and(|class| class.methods.contains(method)) =>
+            {
+                let args = self.arguments(args);
+                let call = format!("{}.{}({})", self.this(), rust_ident(method), args);
+                match self.class.as_ref().is_some_and(|class| class.throwing.contains(method)) {
+                    true => self.propagate(call),
+                    false => call,
+                }
+            }
+            JsExpr::Member(object, method)
+                if matches!(method.as_str(), "then" | "catch" | "finally")
+                    && matches!(self.expr_type(object), JsType::Promise(_) | JsType::Unknown) =>
+            {
+                self.chain(object, method, args.first())
+            }
+            JsExpr::Member(object, method) => {
+                if let JsExpr::Ident(namespace) = &**object {
+                    if !self.types.contains_key(namespace) {
+                        if let Some(call) = self.builtin_call(namespace, method, args) {
+                            return call;
+                        }
+                        // Static methods of the module's classes, and functions
+                        // of the modules it imports whole
+                        if namespace.starts_with(char::is_uppercase) || self.types.contains_key(&format!("{}.{}", namespace, method)) {
+                            let call = format!("{}::{}({})", namespace, rust_ident(method), self.arguments(args));
+                            return match self.types.get(&format!("{}.{}", namespace, method)) {
+                                Some(JsType::Function(_, ret)) if matches!(**ret, JsType::Throws(_)) => self.propagate(call),
+                                _ => call,
+                            };
+                        }
+                    }
+                }
+                self.method_call(object, method, args)
+            }
+            JsExpr::Ident(name) if !self.types.contains_key(name) => self.global_call(name, args),
+            JsExpr::Ident(name) => {
+                let args = self.call_arguments(name, args);
+                let call = format!("{}({})", rust_ident(name), args);
+                match self.types.get(name) {
+                    Some(JsType::Function(_, ret)) if matches!(**ret, JsType::Throws(_)) => self.propagate(call),
+                    _ => call,
+                }
+            }
+            callee => format!("({})({})", self.expr(callee), self.arguments(args)),
+        }
+    }
+
+    /// Calls into JS globals: `Math`, `console`, `Object`, `JSON`, `Number`, `Array`
+    fn builtin_call(&mut self, namespace: &str, method: &str, args: &[JsExpr]) -> Option<String> {
+        let first = args.first();
+        let call = match (namespace, method, first) {
+            ("Math", "max" | "min", None) => {
+                if method == "max" { "f64::NEG_INFINITY".to_string() } else { "f64::INFINITY".to_string() }
+            }
+            // `Math.max(...xs)` folds over the array
+            ("Math", "max" | "min", Some(JsExpr::Spread(array))) if args.len() == 1 => {
+                let start = if method == "max" { "f64::NEG_INFINITY" } else { "f64::INFINITY" };
+                format!("{}.iter().copied().fold({}, f64::{})", self.receiver(array), start, method)
+            }
+            ("Math", "max" | "min", Some(first)) => {
+                let mut call = self.receiver(first);
+                for arg in &args[1..] {
+                    call = format!("{}.{}({})", call, method, self.expr(arg));
+                }
+                call
+            }
+            // JS rounds halves towards +∞, Rust away from zero
+            ("Math", "round", Some(x)) => format!("({} + 0.5_f64).floor()", self.side(x, 1, false)),
+            ("Math", "pow", Some(x)) => format!("{}.powf({})", self.receiver(x), self.expr(args.get(1)?)),
+            ("Math", "atan2" | "hypot", Some(y)) => format!("{}.{}({})", self.receiver(y), method, self.expr(args.get(1)?)),
+            ("Math", "sign", Some(x)) => format!("{}.signum()", self.receiver(x)),
+            ("Math", "log", Some(x)) => format!("{}.ln()", self.receiver(x)),
+            ("Math", "log1p" | "expm1", Some(x)) => format!("{}.{}()", self.receiver(x), method.replace("expm1", "exp_m1").replace("log1p", "ln_1p")),
+            (
+                "Math",
+                "abs" | "floor" | "ceil" | "sqrt" | "cbrt" | "trunc" | "exp" | "log2" | "log10" | "sin" | "cos" | "tan"
+                | "asin" | "acos" | "atan" | "sinh" | "cosh" | "tanh",
+                Some(x),
+            ) => format!("{}.{}()", self.receiver(x), method),
+            ("console", "log" | "info" | "debug" | "error" | "warn", _) => {
+                let mut parts = vec![];
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        parts.push(Part::Text(" ".to_string()));
+                    }
+                    match arg {
+                        JsExpr::String(s) => parts.push(Part::Text(s.clone())),
+                        arg => parts.push(Part::Expr(arg)),
+                    }
+                }
+                let macro_name = if matches!(method, "error" | "warn") { "eprintln!" } else { "println!" };
+                let formatted = self.format(&parts);
+                match formatted.strip_prefix("format!") {
+                    Some(args) => format!("{}{}", macro_name, args),
+                    None => format!("{}(\"{{}}\", {})", macro_name, formatted),
+                }
+            }
+            ("Object", "keys", Some(object)) => format!("{}.keys().cloned().collect::<Vec<_>>()", self.receiver(object)),
+            ("Object", "values", Some(object)) => format!("{}.values().cloned().collect::<Vec<_>>()", self.receiver(object)),
+            ("Object", "entries", Some(object)) => format!(
+                "{}.iter().map(|(key, value)| (key.clone(), value.clone())).collect::<Vec<_>>()",
+                self.receiver(object),
+            ),
+            ("JSON", "stringify", Some(value)) => format!("serde_json::to_string(&{}).unwrap()", self.operand(value, false)),
+            ("JSON", "parse", Some(text)) => {
+                let parsed = format!(
+                    "serde_json::from_str::<Value>(&{}).map_err(|error| JsError::SyntaxError(error.to_string()))",
+                    self.operand(text, false),
+                );
+                self.propagate(parsed)
+            }
+            ("Number", "isInteger", Some(x)) => format!("{}.fract() == 0.0", self.receiver(x)),
+            ("Number", "isNaN", Some(x)) => format!("{}.is_nan()", self.receiver(x)),
+            ("Number", "isFinite", Some(x)) => format!("{}.is_finite()", self.receiver(x)),
+            ("Number", "parseFloat" | "parseInt", _) => return Some(self.global_call(method, args)),
+            ("Array", "from", Some(items)) => format!("{}.iter().cloned().collect::<Vec<_>>()", self.receiver(items)),
+            ("Array", "of", _) => self.array(args),
+            ("Promise", "resolve", None) => "futures::future::ok::<_, JsError>(())".to_string(),
+            ("Promise", "resolve", Some(value)) => format!("futures::future::ok::<_, JsError>({})", self.expr(value)),
+            ("Promise", "reject", Some(error)) => format!("futures::future::err({})", self.rejection(error)),
+            // Not awaited here: keep the combined promise for later
+            ("Promise", method, Some(promises)) => {
+                let combined = self.combinator(method, promises)?;
+                format!("async move {{ Ok::<_, JsError>({}) }}", combined)
+            }
+            _ => return None,
+        };
+        Some(call)
+    }
+
+    fn global_call(&mut self, name: &str, args: &[JsExpr]) -> String {
+        match (name, args.first()) {
+            ("parseFloat", Some(text)) => format!("{}.trim().parse::<f64>().unwrap_or(f64::NAN)", self.receiver(text)),
+            ("parseInt", Some(text)) => format!("{}.trim().parse::<f64>().map(f64::trunc).unwrap_or(f64::NAN)", self.receiver(text)),
+            ("Number", Some(value)) => self.numeric(value),
+            ("String", Some(value)) => format!("{}.to_string()", self.receiver(value)),
+            ("Boolean", Some(value)) => self.condition(value),
+            ("isNaN", Some(value)) => format!("{}.is_nan()", self.receiver(value)),
+            ("isFinite", Some(value)) => format!("{}.is_finite()", self.receiver(value)),
+            ("setTimeout", Some(callback)) => {
+                let delay = args.get(1).map_or("0.0".to_string(), |delay| self.expr(delay));
+                let callback = self.expr(callback);
+                format!("spawn_promise(async move {{ sleep_ms({}).await?; ({})(); Ok::<_, JsError>(()) }})", delay, callback)
+            }
+            (name, _) => format!("{}({})", rust_ident(name), self.arguments(args)),
+        }
+    }
+
+    /// Array, string, Map and Set methods onto their std counterparts
+    fn method_call(&mut self, object: &JsExpr, method: &str, args: &[JsExpr]) -> String {
+        let ty = self.expr_type(object);
+        let element = match &ty {
+            JsType::Array(element) | JsType::Set(element) => (**element).clone(),
+            _ => JsType::Unknown,
+        };
+        let receiver = if MUTATING_METHODS.contains(&method) { self.place_receiver(object) } else { self.receiver(object) };
+        let first = args.first();
+
+        // `xs.push(...ys)` appends each element
+        if let ("push", [JsExpr::Spread(items)]) = (method, args) {
+            return format!("{}.extend({}.iter().cloned())", receiver, self.receiver(items));
+        }
+        if let Some(call) = self.string_method(object, &ty, method, args) {
+            return call;
+        }
+        match (method, &ty) {
+            ("push", _) if args.len() == 1 => format!("{}.push({})", receiver, self.arguments(args)),
+            ("push", _) => format!("{}.extend([{}])", receiver, self.arguments(args)),
+            ("pop", _) => format!("{}.pop()", receiver),
+            ("shift", _) => format!("{}.remove(0)", receiver),
+            ("unshift", _) => format!("{}.insert(0, {})", receiver, self.arguments(args)),
+            ("map", _) => match self.callback(first, &[Binding::Ref], &element) {
+                Callback::Plain(callback) => format!("{}.iter().map({}).collect::<Vec<_>>()", receiver, callback),
+                Callback::Indexed(callback) => format!("{}.iter().enumerate().map({}).collect::<Vec<_>>()", receiver, callback),
+            },
+            ("forEach", _) => match self.callback(first, &[Binding::Ref], &element) {
+                Callback::Plain(callback) => format!("{}.iter().for_each({})", receiver, callback),
+                Callback::Indexed(callback) => format!("{}.iter().enumerate().for_each({})", receiver, callback),
+            },
+            ("filter", _) => {
+                let callback = self.callback(first, &[Binding::DoubleRef], &element).plain();
+                format!("{}.iter().filter({}).cloned().collect::<Vec<_>>()", receiver, callback)
+            }
+            ("find", _) => {
+                let callback = self.callback(first, &[Binding::DoubleRef], &element).plain();
+                format!("{}.iter().find({}).cloned()", receiver, callback)
+            }
+            ("findIndex", _) => {
+                let callback = self.callback(first, &[Binding::Ref], &element).plain();
+                format!("{}.iter().position({}).map_or(-1.0, |i| i as f64)", receiver, callback)
+            }
+            ("some" | "every", _) => {
+                let callback = self.callback(first, &[Binding::Ref], &element).plain();
+                format!("{}.iter().{}({})", receiver, if method == "some" { "any" } else { "all" }, callback)
+            }
+            ("reduce", _) if args.len() >= 2 => {
+                let init = self.expr(&args[1]);
+                let callback = self.callback(first, &[Binding::Owned, Binding::Ref], &element).plain();
+                format!("{}.iter().fold({}, {})", receiver, init, callback)
+            }
+            ("reduce", _) => {
+                let callback = self.callback(first, &[Binding::Owned, Binding::Owned], &element).plain();
+                format!("{}.iter().cloned().reduce({}).unwrap()", receiver, callback)
+            }
+            ("has", JsType::Set(_)) => format!("{}.contains({})", receiver, self.borrowed(first)),
+            ("includes", _) => format!(
+                "{}.iter().any(|item| *item == {})",
+                receiver,
+                first.map_or(String::new(), |needle| self.operand(needle, true)),
+            ),
+            ("indexOf", _) => format!(
+                "{}.iter().position(|item| *item == {}).map_or(-1.0, |i| i as f64)",
+                receiver,
+                first.map_or(String::new(), |needle| self.operand(needle, true)),
+            ),
+            ("join", _) => format!(
+                "{}.iter().map(|item| item.to_string()).collect::<Vec<_>>().join({})",
+                receiver,
+                if first.is_some() { self.str_arg(first) } else { "\",\"".to_string() },
+            ),
+            ("slice" | "substring", _) => {
+                let start = first.map_or("0".to_string(), |start| self.index(start));
+                let end = args.get(1).map_or(String::new(), |end| self.index(end));
+                let owned = if ty == JsType::String { "to_string" } else { "to_vec" };
+                format!("{}[{}..{}].{}()", receiver, start, end, owned)
+            }
+            ("concat", JsType::String) => {
+                let mut parts = vec![Part::Expr(object)];
+                parts.extend(args.iter().map(Part::Expr));
+                self.format(&parts)
+            }
+            ("concat", _) => {
+                let mut slices = vec![format!("{}.as_slice()", receiver)];
+                slices.extend(args.iter().map(|arg| format!("{}.as_slice()", self.receiver(arg))));
+                format!("[{}].concat()", slices.join(", "))
+            }
+            ("reverse", _) => format!("{}.reverse()", receiver),
+            ("sort", _) => match first {
+                Some(_) => {
+                    // The comparator's number becomes an Ordering against zero
+                    let callback = self.callback(first, &[Binding::Ref, Binding::Ref], &element).plain();
+                    let head_end = callback[1..].find('|').map_or(0, |end| end + 2);
+                    let (head, order) = callback.split_at(head_end);
+                    format!("{}.sort_by({} ({}).partial_cmp(&0.0).unwrap())", receiver, head, order.trim())
+                }
+                None => format!("{}.sort_by(|a, b| a.partial_cmp(b).unwrap())", receiver),
+            },
+            ("toString", _) => format!("{}.to_string()", receiver),
+            ("toFixed", _) => format!(
+                "format!(\"{{:.1$}}\", {}, {})",
+                receiver,
+                first.map_or("0".to_string(), |digits| self.index(digits)),
+            ),
+            ("get", _) => format!("{}.get({}).cloned()", receiver, self.borrowed(first)),
+            ("set", _) => format!("{}.insert({})", receiver, self.arguments(args)),
+            ("has", _) => format!("{}.contains_key({})", receiver, self.borrowed(first)),
+            ("add", _) => format!("{}.insert({})", receiver, self.arguments(args)),
+            ("delete", _) => format!("{}.remove({})", receiver, self.borrowed(first)),
+            (method, _) => format!("{}.{}({})", receiver, rust_ident(method), self.arguments(args)),
+        }
+    }
+
+    /// A JS callback as a Rust closure for an iterator adapter
+    ///
+    /// `bindings` says how each element parameter is received; a callback
+    /// taking one more parameter than that wants the element index, which
+    /// `enumerate` provides.
+    fn callback(&mut self, callback: Option<&JsExpr>, bindings: &[Binding], element: &JsType) -> Callback {
+        match callback {
+            Some(JsExpr::Function(function)) => {
+                if function.params.len() == bindings.len() + 1 && bindings.len() == 1 {
+                    Callback::Indexed(self.indexed_closure(function, element))
+                } else {
+                    Callback::Plain(self.typed_closure(function, bindings, element))
+                }
+            }
+            Some(JsExpr::Ident(name)) => {
+                let params: Vec<String> = (0..bindings.len()).map(|i| format!("arg{}", i)).collect();
+                let args: Vec<String> = params.iter().map(|param| format!("{}.clone()", param)).collect();
+                Callback::Plain(format!("|{}| {}({})", params.join(", "), rust_ident(name), args.join(", ")))
+            }
+            Some(other) => Callback::Plain(self.expr(other)),
+            None => Callback::Plain("todo!(\"missing callback\")".to_string()),
+        }
+    }
+
+    fn closure(&mut self, function: &JsFunction, bindings: &[Binding]) -> String {
+        self.typed_closure(function, bindings, &JsType::Unknown)
+    }
+
+    fn typed_closure(&mut self, function: &JsFunction, bindings: &[Binding], element: &JsType) -> String {
+        let mut writer = self.nested();
+        let params: Vec<String> = function.params.iter().enumerate().map(|(i, param)| {
+            let ty = if param.ty == JsType::Unknown && Some(i) == bindings.len().checked_sub(1) { element.clone() } else { param.ty.clone() };
+            match bindings.get(i) {
+                Some(Binding::Ref) => {
+                    writer.bind_ref(&param.name, ty);
+                    rust_ident(&param.name)
+                }
+                Some(Binding::DoubleRef) => {
+                    writer.bind_ref(&param.name, ty);
+                    format!("&{}", rust_ident(&param.name))
+                }
+                Some(Binding::Owned) | None => {
+                    writer.refs.remove(&param.name);
+                    writer.types.insert(param.name.clone(), ty);
+                    rust_ident(&param.name)
+                }
+            }
+        }).collect();
+        self.finish_closure(writer, function, format!("|{}|", params.join(", ")), vec![])
+    }
+
+    /// `|(i, x)|` over `enumerate()`, with the index back as a JS number
+    fn indexed_closure(&mut self, function: &JsFunction, element: &JsType) -> String {
+        let mut writer = self.nested();
+        let item = &function.params[0].name;
+        let index = &function.params[1].name;
+        writer.bind_ref(item, element.clone());
+        writer.refs.remove(index);
+        writer.types.insert(index.clone(), JsType::Number);
+
+        let head = format!("|({}, {})|", rust_ident(index), rust_ident(item));
+        let prelude = vec![format!("let {} = {} as f64;", rust_ident(index), rust_ident(index))];
+        self.finish_closure(writer, function, head, prelude)
+    }
+
+    pub(crate) fn finish_closure(&mut self, mut writer: BodyWriter<'f>, function: &JsFunction, head: String, prelude: Vec<String>) -> String {
+        let asyncness = if function.is_async { "async move " } else { "" };
+        writer.fallible = function.is_async.then_some("Ok::<_, JsError>");
+        if let ([JsStmt::Return(Some(value))], true) = (function.body.as_slice(), prelude.is_empty()) {
+            let value = writer.returned(value);
+            if !value.contains('\n') {
+                self.temps = writer.temps;
+                return match asyncness {
+                    "" => format!("{} {}", head, value),
+                    asyncness => format!("{} {}{{ {} }}", head, asyncness, value),
+                };
+            }
+        }
+
+        for line in prelude {
+            writer.line(line);
+        }
+        let lines = writer.write_function_lines(&function.body);
+        format!("{} {}{{\n{}\n{}}}", head, asyncness, lines.join("\n"), INDENT.repeat(self.depth))
+    }
+
+    fn write_function_lines(self, body: &[JsStmt]) -> Vec<String> {
+        self.write_function(body)
+    }
+
+    // Promises
+
+    /// What a `return` hands back; in an async body a returned promise is
+    /// flattened into this one, and anything else resolves it
+    fn returned(&mut self, value: &JsExpr) -> String {
+        let promise = matches!(self.expr_type(value), JsType::Promise(_));
+        let value = match value {
+            // A returned closure outlives the locals it captures
+            JsExpr::Function(function) => format!("move {}", self.closure(function, &[])),
+            value if promise && self.fallible.is_some() => return format!("{}.await", self.receiver(value)),
+            value => self.expr(value),
+        };
+        match self.fallible {
+            Some(ok) => format!("{}({})", ok, value),
+            None => value,
+        }
+    }
+
+    /// A thrown or rejected value as a `JsError`
+    fn rejection(&mut self, error: &JsExpr) -> String {
+        if self.expr_type(error) == JsType::Named("JsError".to_string(), vec![]) {
+            return self.expr(error);
+        }
+        match error {
+            JsExpr::New(_, args) if !args.is_empty() => format!("JsError::new({})", self.expr(&args[0])),
+            JsExpr::String(message) => format!("JsError::new({:?})", message),
+            error => format!("JsError::new(format!(\"{{:?}}\", {}))", self.expr(error)),
+        }
+    }
+
+    /// `await`, propagating rejection with `?`
+    fn await_expr(&mut self, future: &JsExpr) -> String {
+        if let JsExpr::Call(callee, args) = future {
+            if let (JsExpr::Member(object, method), Some(promises)) = (&**callee, args.first()) {
+                if matches!(&**object, JsExpr::Ident(name) if name == "Promise" && !self.types.contains_key(name)) {
+                    if let Some(combined) = self.combinator(method, promises) {
+                        return combined;
+                    }
+                }
+            }
+        }
+        match self.expr_type(future) {
+            JsType::Promise(_) | JsType::Unknown => {
+                let awaited = format!("{}.await", self.receiver(future));
+                self.propagate(awaited)
+            }
+            // Awaiting a plain value gives the value
+            _ => self.expr(future),
+        }
+    }
+
+    /// Awaited `Promise.all`, `allSettled` and `race`, over a literal list or one built at runtime
+    fn combinator(&mut self, method: &str, promises: &JsExpr) -> Option<String> {
+        let combined = match (method, promises) {
+            ("all", JsExpr::Array(items)) => {
+                let joined = format!("futures::try_join!({})", self.arguments(items));
+                self.propagate(joined)
+            }
+            ("allSettled", JsExpr::Array(items)) => format!("futures::join!({})", self.arguments(items)),
+            ("race", JsExpr::Array(items)) => {
+                let arms: Vec<String> = items.iter()
+                    .map(|item| format!("value = futures::FutureExt::fuse({}) => value", self.arguments(std::slice::from_ref(item))))
+                    .collect();
+                let raced = format!("futures::select! {{ {} }}", arms.join(", "));
+                self.propagate(raced)
+            }
+            ("all", list) => {
+                let joined = format!("futures::future::try_join_all({}).await", self.arguments(std::slice::from_ref(list)));
+                self.propagate(joined)
+            }
+            ("allSettled", list) => format!("futures::future::join_all({}).await", self.arguments(std::slice::from_ref(list))),
+            ("race", list) => {
+                let raced = format!("futures::future::select_all({}.into_iter().map(Box::pin)).await.0", self.receiver(list));
+                self.propagate(raced)
+            }
+            _ => return None,
+        };
+        Some(combined)
+    }
+
+    /// `.then`, `.catch` or `.finally` as an async block awaiting `promise`
+    ///
+    /// The callback's body is inlined rather than called, so its parameter
+    /// takes the promise's type instead of leaving a closure to infer.
+    fn chain(&mut self, promise: &JsExpr, method: &str, callback: Option<&JsExpr>) -> String {
+        let settled = match self.expr_type(promise) {
+            JsType::Promise(inner) => *inner,
+            _ => JsType::Unknown,
+        };
+        let promise = self.receiver(promise);
+        let callback = match callback {
+            Some(JsExpr::Function(function)) => (**function).clone(),
+            // A named callback is called with what the promise settled to
+            Some(callback) => {
+                let args = if method == "finally" { vec![] } else { vec![JsExpr::Ident("value".to_string())] };
+                JsFunction {
+                    name: String::new(),
+                    params: args.iter().map(|_| JsParam { name: "value".to_string(), ty: JsType::Unknown, rest: false }).collect(),
+                    return_type: JsType::Unknown,
+                    type_params: vec![],
+                    is_async: false,
+                    is_generator: false,
+                    body: vec![JsStmt::Return(Some(JsExpr::Call(Box::new(callback.clone()), args)))],
+                    origin: None,
+                }
+            }
+            None => return promise,
+        };
+        let param = callback.params.first().map(|param| param.name.clone());
+
+        let mut writer = self.nested();
+        writer.fallible = Some("Ok::<_, JsError>");
+        if let Some(param) = &param {
+            writer.refs.remove(param);
+            writer.types.insert(param.clone(), if method == "catch" { JsType::String } else { settled });
+        }
+        let lines = match method {
+            "then" => {
+                let binding = param.as_deref().map_or("_".to_string(), rust_ident);
+                writer.line(format!("let {} = {}.await?;", binding, promise));
+                writer.write_function(&callback.body)
+            }
+            "catch" => {
+                // Resolved values pass through; the rejection reaches the callback as its message
+                match &param {
+                    Some(param) => {
+                        writer.line(format!("let {} = match {}.await {{", rust_ident(param), promise));
+                        writer.line(format!("{}Ok(value) => return Ok(value),", INDENT));
+                        writer.line(format!("{}Err(error) => error.to_string(),", INDENT));
+                        writer.line("};");
+                    }
+                    None => {
+                        writer.line(format!("if let Ok(value) = {}.await {{", promise));
+                        writer.line(format!("{}return Ok(value);", INDENT));
+                        writer.line("}");
+                    }
+                }
+                let mut lines = writer.write_function(&callback.body);
+                if let Some(param) = &param {
+                    let ident = rust_ident(param);
+                    if !lines[1..].iter().any(|line| line.split(|c: char| !c.is_alphanumeric() && c != '_').any(|word| word == ident)) {
+                        lines[0] = lines[0].replacen(&format!("let {} ", ident), "let _ ", 1);
+                    }
+                }
+                lines
+            }
+            _ => {
+                // The callback runs either way and the outcome passes through
+                let settled = self.temp("settled");
+                let mut body = callback.body.clone();
+                if let Some(JsStmt::Return(Some(value))) = body.last() {
+                    *body.last_mut().unwrap() = JsStmt::Expr(value.clone());
+                }
+                writer.fallible = None;
+                writer.temps = self.temps;
+                writer.line(format!("let {} = {}.await;", settled, promise));
+                let mut lines = writer.write_function(&body);
+                lines.push(format!("{}{}{}", INDENT.repeat(self.depth), INDENT, settled));
+                lines
+            }
+        };
+        format!("async move {{\n{}\n{}}}", lines.join("\n"), INDENT.repeat(self.depth))
+    }
+
+    // Types
+
+    fn is_string(&mut self, expr: &JsExpr) -> bool {
+        self.expr_type(expr) == JsType::String
+    }
+
+    /// What is known of an expression's type from literals, locals and operators
+    pub(crate) fn expr_type(&mut self, expr: &JsExpr) -> JsType {
+        match expr {
+            JsExpr::Number(_) => JsType::Number,
+            JsExpr::String(_) | JsExpr::Template { .. } => JsType::String,
+            JsExpr::Regex { .. } => JsType::Named(strings::REGEX.to_string(), vec![]),
+            JsExpr::Bool(_) => JsType::Boolean,
+            JsExpr::Null | JsExpr::Undefined => JsType::Null,
+            JsExpr::Ident(name) => match name.as_str() {
+                "NaN" | "Infinity" => JsType::Number,
+                name => self.types.get(name).cloned().unwrap_or(JsType::Unknown),
+            },
+            JsExpr::Array(items) => {
+                let element = items.first().map_or(JsType::Unknown, |item| match item {
+                    JsExpr::Spread(spread) => match self.expr_type(spread) {
+                        JsType::Array(element) => *element,
+                        _ => JsType::Unknown,
+                    },
+                    item => self.expr_type(item),
+                });
+                JsType::Array(Box::new(element))
+            }
+            // `{ ...point, x }` updating a struct is one
+            JsExpr::Object(props) => match props.split_first() {
+                Some((JsProp::Spread(base), props)) => match self.expr_type(base) {
+                    JsType::Named(name, args)
+                        if props.iter().all(|prop| matches!(prop, JsProp::KeyValue(key, _) if self.types.contains_key(&field_key(&name, key)))) =>
+                    {
+                        JsType::Named(name, args)
+                    }
+                    _ => JsType::Object,
+                },
+                _ => JsType::Object,
+            },
+            JsExpr::Unary("!", _) => JsType::Boolean,
+            JsExpr::Unary("typeof", _) => JsType::String,
+            JsExpr::Unary(..) | JsExpr::Update { .. } => JsType::Number,
+            JsExpr::Binary(op, left, right) => match *op {
+                "+" => {
+                    let (left, right) = (self.expr_type(left), self.expr_type(right));
+                    if left == JsType::String || right == JsType::String { JsType::String } else { JsType::Number }
+                }
+                "-" | "*" | "/" | "%" | "**" | "&" | "|" | "^" | "<<" | ">>" | ">>>" => JsType::Number,
+                "&&" | "||" => match (self.expr_type(left), self.expr_type(right)) {
+                    (JsType::Boolean, JsType::Boolean) => JsType::Boolean,
+                    (_, right) => right,
+                },
+                "??" => match self.expr_type(left) {
+                    JsType::Optional(inner) => *inner,
+                    _ => self.expr_type(right),
+                },
+                _ => JsType::Boolean,
+            },
+            JsExpr::Assign(_, target, _) => self.expr_type(target),
+            JsExpr::Conditional(_, then, _) => self.expr_type(then),
+            JsExpr::Member(object, property) if self.class_member(object, property).is_some() => {
+                self.class_member(object, property).map_or(JsType::Unknown, |member| member.ty.clone())
+            }
+            JsExpr::Member(object, property) => match (property.as_str(), &**object) {
+                ("length", _) => JsType::Number,
+                ("message" | "name", object) if self.expr_type(object) == JsType::Named("JsError".to_string(), vec![]) => JsType::String,
+                (_, JsExpr::Ident(namespace)) if namespace == "Math" || namespace == "Number" => JsType::Number,
+                _ => JsType::Unknown,
+            },
+            JsExpr::Index(object, _) => match self.expr_type(object) {
+                JsType::Array(element) => *element,
+                JsType::Map(_, value) => *value,
+                JsType::String => JsType::String,
+                _ => JsType::Unknown,
+            },
+            JsExpr::Call(callee, args) => self.call_type(callee, args),
+            JsExpr::New(callee, _) => match &**callee {
+                JsExpr::Ident(name) => match name.as_str() {
+                    "Map" => JsType::Map(Box::new(JsType::Unknown), Box::new(JsType::Unknown)),
+                    "Set" => JsType::Set(Box::new(JsType::Unknown)),
+                    "Array" => JsType::Array(Box::new(JsType::Unknown)),
+                    "Promise" => JsType::Promise(Box::new(JsType::Void)),
+                    name if name.ends_with("Error") => JsType::Named("JsError".to_string(), vec![]),
+                    name => JsType::Named(name.to_string(), vec![]),
+                },
+                _ => JsType::Unknown,
+            },
+            JsExpr::Function(function) => function.signature(),
+            JsExpr::Await(future) => match self.expr_type(future) {
+                JsType::Promise(inner) => *inner,
+                ty => ty,
+            },
+            _ => JsType::Unknown,
+        }
+    }
+
+    fn call_type(&mut self, callee: &JsExpr, args: &[JsExpr]) -> JsType {
+        match callee {
+            JsExpr::Member(object, method) => {
+                if let JsExpr::Ident(namespace) = &**object {
+                    if namespace == "Math" || (namespace == "Number" && method.starts_with("parse")) {
+                        return JsType::Number;
+                    }
+                    if namespace == "Promise" && !self.types.contains_key(namespace) {
+                        return JsType::Promise(Box::new(self.promise_type(method, args.first())));
+                    }
+                }
+                match method.as_str() {
+                    "then" => match args.first() {
+                        Some(JsExpr::Function(callback)) => match callback.signature() {
+                            JsType::Function(_, ret) => JsType::Promise(Box::new(settled(*ret))),
+                            _ => JsType::Promise(Box::new(JsType::Unknown)),
+                        },
+                        _ => JsType::Promise(Box::new(JsType::Unknown)),
+                    },
+                    "catch" | "finally" => match self.expr_type(object) {
+                        JsType::Promise(inner) => JsType::Promise(inner),
+                        _ => JsType::Promise(Box::new(JsType::Unknown)),
+                    },
+                    "map" => JsType::Array(Box::new(JsType::Unknown)),
+                    "filter" | "slice" | "concat" | "sort" | "reverse" => self.expr_type(object),
+                    "split" => JsType::Array(Box::new(JsType::String)),
+                    "join" | "toUpperCase" | "toLowerCase" | "trim" | "trimStart" | "trimEnd" | "toString" | "toFixed"
+                    | "repeat" | "charAt" | "padStart" | "padEnd" | "replace" | "replaceAll" | "substring" => JsType::String,
+                    "indexOf" | "lastIndexOf" | "findIndex" | "push" | "charCodeAt" | "search" | "localeCompare" => JsType::Number,
+                    "includes" | "some" | "every" | "startsWith" | "endsWith" | "has" | "isInteger" | "isNaN" | "isFinite" | "test" => {
+                        JsType::Boolean
+                    }
+                    // A match and its groups
+                    "match" | "exec" => JsType::Optional(Box::new(JsType::Array(Box::new(JsType::String)))),
+                    "matchAll" => JsType::Array(Box::new(JsType::Array(Box::new(JsType::String)))),
+                    "find" | "pop" | "shift" => match self.expr_type(object) {
+                        JsType::Array(element) => JsType::Optional(element),
+                        _ => JsType::Unknown,
+                    },
+                    _ => JsType::Unknown,
+                }
+            }
+            JsExpr::Ident(name) => match (name.as_str(), self.types.get(name)) {
+                (_, Some(JsType::Function(params, ret))) => {
+                    let (params, ret) = match &**ret {
+                        JsType::Throws(ret) => (params.clone(), (**ret).clone()),
+                        ret => (params.clone(), ret.clone()),
+                    };
+                    self.instantiate(&ret, &params, args)
+                }
+                ("setTimeout", None) => JsType::Number,
+                ("parseInt" | "parseFloat" | "Number", None) => JsType::Number,
+                ("String", None) => JsType::String,
+                ("Boolean" | "isNaN" | "isFinite", None) => JsType::Boolean,
+                _ => JsType::Unknown,
+            },
+            _ => JsType::Unknown,
+        }
+    }
+
+    /// What a call returns, with the type parameters its arguments give
+    fn instantiate(&mut self, ty: &JsType, params: &[JsType], args: &[JsExpr]) -> JsType {
+        match ty {
+            JsType::Named(_, generics) if generics.is_empty() => {
+                let arg = params.iter().position(|param| param == ty).and_then(|i| args.get(i));
+                match arg.map(|arg| self.expr_type(arg)) {
+                    Some(JsType::Unknown | JsType::Named(..)) | None => ty.clone(),
+                    Some(arg) => arg,
+                }
+            }
+            JsType::Array(element) => JsType::Array(Box::new(self.instantiate(element, params, args))),
+            JsType::Optional(inner) => JsType::Optional(Box::new(self.instantiate(inner, params, args))),
+            JsType::Promise(inner) => JsType::Promise(Box::new(self.instantiate(inner, params, args))),
+            ty => ty.clone(),
+        }
+    }
+
+    /// What a `Promise.*` call resolves to
+    fn promise_type(&mut self, method: &str, arg: Option<&JsExpr>) -> JsType {
+        match (method, arg) {
+            ("all", Some(JsExpr::Array(items))) => JsType::Tuple(items.iter().map(|item| settled(self.expr_type(item))).collect()),
+            ("all", Some(list)) => match self.expr_type(list) {
+                JsType::Array(element) => JsType::Array(Box::new(settled(*element))),
+                _ => JsType::Unknown,
+            },
+            ("race", Some(JsExpr::Array(items))) => items.first().map_or(JsType::Unknown, |item| settled(self.expr_type(item))),
+            ("race", Some(list)) => match self.expr_type(list) {
+                JsType::Array(element) => settled(*element),
+                _ => JsType::Unknown,
+            },
+            ("resolve", Some(value)) => self.expr_type(value),
+            ("resolve", None) => JsType::Void,
+            _ => JsType::Unknown,
+        }
+    }
+}
+
+/// A piece of a `format!` string
+enum Part<'e> {
+    Text(String),
+    Expr(&'e JsExpr),
+}
+
+enum Callback {
+    Plain(String),
+    /// Takes `(index, element)` from `enumerate()`
+    Indexed(String),
+}
+
+impl Callback {
+    fn plain(self) -> String {
+        match self {
+            Self::Plain(closure) | Self::Indexed(closure) => closure,
+        }
+    }
+}
+
+/// What awaiting a value of type `ty` gives
+fn settled(ty: JsType) -> JsType {
+    match ty {
+        JsType::Promise(inner) => *inner,
+        ty => ty,
+    }
+}
+
+/// The delay of `new Promise(resolve => setTimeout(resolve, ms))`
+fn timer_delay(executor: &JsFunction) -> Option<&JsExpr> {
+    let resolve = &executor.params.first()?.name;
+    let (JsStmt::Expr(JsExpr::Call(callee, args)) | JsStmt::Return(Some(JsExpr::Call(callee, args)))) = executor.body.first()? else {
+        return None;
+    };
+    match (&**callee, args.first()?) {
+        (JsExpr::Ident(timer), JsExpr::Ident(callback)) if timer == "setTimeout" && callback == resolve => args.get(1),
+        _ => None,
+    }
+}
+
+/// A JS number literal as a Rust `f64` literal
+fn number(n: f64) -> String {
+    if !n.is_finite() {
+        "f64::NAN".to_string()
+    } else if n.abs() >= 1e15 {
+        format!("{:e}", n)
+    } else if n.fract() == 0.0 {
+        format!("{:.1}", n)
+    } else {
+        format!("{}", n)
+    }
+}
+
+/// Expressions needing parentheses as an operand or receiver
+///
+/// `.length` is among them: `x.len() as f64 < y` would parse `<` as generics.
+fn is_compound(expr: &JsExpr) -> bool {
+    match expr {
+        JsExpr::Member(_, property) => property == "length" || property == "size",
+        expr => matches!(
+            expr,
+            JsExpr::Binary(..) | JsExpr::Assign(..) | JsExpr::Conditional(..) | JsExpr::Function(_) | JsExpr::Update { .. }
+        ),
+    }
+}
+
+/// Binding strength of the arithmetic operators
+fn precedence(op: &str) -> Option<u8> {
+    match op {
+        "*" | "/" | "%" => Some(2),
+        "+" | "-" => Some(1),
+        _ => None,
+    }
+}
+
+/// Methods that change their receiver in place
+const MUTATING_METHODS: &[&str] = &[
+    "push", "pop", "shift", "unshift", "splice", "sort", "reverse", "fill", "set", "add", "delete", "clear",
+];
+
+/// Names a body reassigns or mutates in place, closures included
+pub(crate) fn mutated_names(body: &[JsStmt]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for stmt in body {
+        stmt_mutations(stmt, &mut names);
+    }
+    names
+}
+
+fn stmt_mutations(stmt: &JsStmt, names: &mut HashSet<String>) {
+    let block = |body: &[JsStmt], names: &mut HashSet<String>| body.iter().for_each(|stmt| stmt_mutations(stmt, names));
+    match stmt {
+        JsStmt::Let { init: Some(init), .. } => expr_mutations(init, names),
+        JsStmt::Expr(expr) | JsStmt::Return(Some(expr)) | JsStmt::Throw(expr) => expr_mutations(expr, names),
+        JsStmt::If { test, then, otherwise } => {
+            expr_mutations(test, names);
+            block(then, names);
+            block(otherwise, names);
+        }
+        JsStmt::While { test, body } | JsStmt::DoWhile { body, test } => {
+            expr_mutations(test, names);
+            block(body, names);
+        }
+        JsStmt::For { init, test, update, body } => {
+            block(init, names);
+            test.iter().chain(update).for_each(|expr| expr_mutations(expr, names));
+            block(body, names);
+        }
+        JsStmt::ForOf { iterable: expr, body, .. } | JsStmt::ForIn { object: expr, body, .. } => {
+            expr_mutations(expr, names);
+            block(body, names);
+        }
+        JsStmt::Switch { discriminant, cases } => {
+            expr_mutations(discriminant, names);
+            for case in cases {
+                case.test.iter().for_each(|test| expr_mutations(test, names));
+                block(&case.body, names);
+            }
+        }
+        JsStmt::Block(body) => block(body, names),
+        JsStmt::Labeled(_, body) => stmt_mutations(body, names),
+        JsStmt::Try { block: body, handler, finalizer, .. } => {
+            block(body, names);
+            block(handler.as_deref().unwrap_or_default(), names);
+            block(finalizer, names);
+        }
+        JsStmt::Function(function) => block(&function.body, names),
+        _ => {}
+    }
+}
+
+fn expr_mutations(expr: &JsExpr, names: &mut HashSet<String>) {
+    let each = |exprs: &[&JsExpr], names: &mut HashSet<String>| exprs.iter().for_each(|expr| expr_mutations(expr, names));
+    match expr {
+        JsExpr::Assign(_, target, value) => {
+            names.extend(root_name(target));
+            each(&[target, value], names);
+        }
+        JsExpr::Update { target, .. } => {
+            names.extend(root_name(target));
+            each(&[target], names);
+        }
+        JsExpr::Call(callee, args) => {
+            if let JsExpr::Member(object, method) = &**callee {
+                if MUTATING_METHODS.contains(&method.as_str()) {
+                    names.extend(root_name(object));
+                }
+            }
+            each(&[callee], names);
+            args.iter().for_each(|arg| expr_mutations(arg, names));
+        }
+        JsExpr::Template { exprs: items, .. } | JsExpr::Array(items) | JsExpr::Sequence(items) | JsExpr::New(_, items) => {
+            items.iter().for_each(|item| expr_mutations(item, names));
+        }
+        JsExpr::Object(props) => props.iter().for_each(|prop| match prop {
+            JsProp::KeyValue(_, value) | JsProp::Spread(value) => expr_mutations(value, names),
+        }),
+        JsExpr::Spread(inner) | JsExpr::Unary(_, inner) | JsExpr::Await(inner) | JsExpr::Member(inner, _) => each(&[inner], names),
+        JsExpr::Yield { value: Some(inner), .. } => each(&[inner], names),
+        JsExpr::Binary(_, left, right) | JsExpr::Index(left, right) => each(&[left, right], names),
+        JsExpr::Conditional(test, then, otherwise) => each(&[test, then, otherwise], names),
+        JsExpr::Function(function) => function.body.iter().for_each(|stmt| stmt_mutations(stmt, names)),
+        _ => {}
+    }
+}
+
+/// The local a place expression ultimately writes into; `this` for fields
+fn root_name(target: &JsExpr) -> Option<String> {
+    match target {
+        JsExpr::Ident(name) => Some(name.clone()),
+        JsExpr::This => Some("this".to_string()),
+        JsExpr::Member(object, _) | JsExpr::Index(object, _) => root_name(object),
+        _ => None,
+    }
+}
+
+/// The closure trait a function value needs, by how it is used
+///
+/// Ordered from what callers find easiest to satisfy to hardest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Callable {
+    /// Called once
+    Once,
+    /// Called repeatedly, or mutating what it captures; its binding must be `mut`
+    Mut,
+    /// Passed along or stored, so it has to be callable through a shared reference
+    Shared,
+}
+
+impl Callable {
+    pub(crate) fn bound(self) -> &'static str {
+        match self {
+            Self::Once => "FnOnce",
+            Self::Mut => "FnMut",
+            Self::Shared => "Fn",
+        }
+    }
+}
+
+/// How a body uses a function value
+pub(crate) struct CallableUse {
+    pub(crate) kind: Callable,
+
+    /// Arguments it is called with, if it is called
+    pub(crate) arity: Option<usize>,
+}
+
+/// How `body` uses the function named `name`, if it mentions it at all
+pub(crate) fn callable_use(body: &[JsStmt], name: &str) -> Option<CallableUse> {
+    let mut uses = vec![];
+    for stmt in body {
+        stmt_uses(stmt, name, false, &mut uses);
+    }
+    let kind = match uses.as_slice() {
+        [] => return None,
+        [(kind, _)] => *kind,
+        uses => uses.iter().map(|(kind, _)| *kind).max()?.max(Callable::Mut),
+    };
+    let arity = uses.iter().find_map(|(_, arity)| *arity);
+    Some(CallableUse { kind, arity })
+}
+
+/// Uses of `name` in a statement; `repeated` inside loops and closures, which may run it many times
+fn stmt_uses(stmt: &JsStmt, name: &str, repeated: bool, uses: &mut Vec<(Callable, Option<usize>)>) {
+    let block = |body: &[JsStmt], repeated: bool, uses: &mut Vec<_>| body.iter().for_each(|stmt| stmt_uses(stmt, name, repeated, uses));
+    match stmt {
+        JsStmt::Let { init: Some(expr), .. } | JsStmt::Expr(expr) | JsStmt::Return(Some(expr)) | JsStmt::Throw(expr) => {
+            expr_uses(expr, name, repeated, uses)
+        }
+        JsStmt::If { test, then, otherwise } => {
+            expr_uses(test, name, repeated, uses);
+            block(then, repeated, uses);
+            block(otherwise, repeated, uses);
+        }
+        JsStmt::While { test, body } | JsStmt::DoWhile { body, test } => {
+            expr_uses(test, name, true, uses);
+            block(body, true, uses);
+        }
+        JsStmt::For { init, test, update, body } => {
+            block(init, repeated, uses);
+            test.iter().chain(update).for_each(|expr| expr_uses(expr, name, true, uses));
+            block(body, true, uses);
+        }
+        JsStmt::ForOf { iterable: expr, body, .. } | JsStmt::ForIn { object: expr, body, .. } => {
+            expr_uses(expr, name, repeated, uses);
+            block(body, true, uses);
+        }
+        JsStmt::Switch { discriminant, cases } => {
+            expr_uses(discriminant, name, repeated, uses);
+            for case in cases {
+                case.test.iter().for_each(|test| expr_uses(test, name, repeated, uses));
+                block(&case.body, repeated, uses);
+            }
+        }
+        JsStmt::Block(body) => block(body, repeated, uses),
+        JsStmt::Labeled(_, body) => stmt_uses(body, name, repeated, uses),
+        JsStmt::Try { block: body, handler, finalizer, .. } => {
+            block(body, repeated, uses);
+            block(handler.as_deref().unwrap_or_default(), repeated, uses);
+            block(finalizer, repeated, uses);
+        }
+        JsStmt::Function(function) => block(&function.body, true, uses),
+        _ => {}
+    }
+}
+
+fn expr_uses(expr: &JsExpr, name: &str, repeated: bool, uses: &mut Vec<(Callable, Option<usize>)>) {
+    let each = |exprs: &[&JsExpr], uses: &mut Vec<_>| exprs.iter().for_each(|expr| expr_uses(expr, name, repeated, uses));
+    match expr {
+        JsExpr::Ident(ident) if ident == name => uses.push((Callable::Shared, None)),
+        JsExpr::Call(callee, args) => {
+            match &**callee {
+                JsExpr::Ident(ident) if ident == name => {
+                    let kind = if repeated { Callable::Mut } else { Callable::Once };
+                    uses.push((kind, Some(args.len())));
+                }
+                callee => each(&[callee], uses),
+            }
+            args.iter().for_each(|arg| expr_uses(arg, name, repeated, uses));
+        }
+        JsExpr::Template { exprs: items, .. } | JsExpr::Array(items) | JsExpr::Sequence(items) | JsExpr::New(_, items) => {
+            items.iter().for_each(|item| expr_uses(item, name, repeated, uses));
+        }
+        JsExpr::Object(props) => props.iter().for_each(|prop| match prop {
+            JsProp::KeyValue(_, value) | JsProp::Spread(value) => expr_uses(value, name, repeated, uses),
+        }),
+        JsExpr::Spread(inner) | JsExpr::Unary(_, inner) | JsExpr::Await(inner) | JsExpr::Member(inner, _) => each(&[inner], uses),
+        JsExpr::Yield { value: Some(inner), .. } => each(&[inner], uses),
+        JsExpr::Update { target: inner, .. } => each(&[inner], uses),
+        JsExpr::Binary(_, left, right) | JsExpr::Index(left, right) | JsExpr::Assign(_, left, right) => each(&[left, right], uses),
+        JsExpr::Conditional(test, then, otherwise) => each(&[test, then, otherwise], uses),
+        JsExpr::Function(function) => function.body.iter().for_each(|stmt| stmt_uses(stmt, name, true, uses)),
+        _ => {}
+    }
+}
+
+/// `Mut` for a closure that changes a variable it captures, `Shared` otherwise
+pub(crate) fn closure_kind(function: &JsFunction) -> Callable {
+    let mut locals: HashSet<String> = function.params.iter().map(|param| param.name.clone()).collect();
+    declared_names(&function.body, &mut locals);
+    match mutated_names(&function.body).iter().any(|name| !locals.contains(name)) {
+        true => Callable::Mut,
+        false => Callable::Shared,
+    }
+}
+
+/// Names a body declares with `let`/`const`/`var` or a loop binding
+fn declared_names(body: &[JsStmt], names: &mut HashSet<String>) {
+    fn pattern_names(pattern: &JsPattern, names: &mut HashSet<String>) {
+        match pattern {
+            JsPattern::Ident(name) => {
+                names.insert(name.clone());
+            }
+            JsPattern::Array { elements, rest } => {
+                elements.iter().flatten().chain(rest.as_deref()).for_each(|element| pattern_names(element, names));
+            }
+            JsPattern::Object { properties, rest } => {
+                properties.iter().map(|(_, pattern)| pattern).chain(rest.as_deref()).for_each(|property| pattern_names(property, names));
+            }
+            JsPattern::Default(inner, _) => pattern_names(inner, names),
+        }
+    }
+    for stmt in body {
+        match stmt {
+            JsStmt::Let { pattern, .. } => pattern_names(pattern, names),
+            JsStmt::Function(function) => {
+                names.insert(function.name.clone());
+            }
+            JsStmt::If { then, otherwise, .. } => {
+                declared_names(then, names);
+                declared_names(otherwise, names);
+            }
+            JsStmt::For { init, body, .. } => {
+                declared_names(init, names);
+                declared_names(body, names);
+            }
+            JsStmt::ForOf { pattern, body, .. } | JsStmt::ForIn { pattern, body, .. } => {
+                pattern_names(pattern, names);
+                declared_names(body, names);
+            }
+            JsStmt::While { body, .. } | JsStmt::DoWhile { body, .. } | JsStmt::Block(body) => declared_names(body, names),
+            JsStmt::Labeled(_, body) => declared_names(std::slice::from_ref(body), names),
+            JsStmt::Switch { cases, .. } => cases.iter().for_each(|case| declared_names(&case.body, names)),
+            JsStmt::Try { block, handler, finalizer, .. } => {
+                declared_names(block, names);
+                declared_names(handler.as_deref().unwrap_or_default(), names);
+                declared_names(finalizer, names);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `super(..)` as a statement
+fn is_super_call(stmt: &JsStmt) -> bool {
+    matches!(stmt, JsStmt::Expr(JsExpr::Call(callee, _)) if matches!(**callee, JsExpr::Super))
+}
+
+/// Whether `body` always leaves by its last statement, so nothing may follow it
+fn ends_in_jump(body: &[JsStmt]) -> bool {
+    matches!(body.last(), Some(JsStmt::Return(_) | JsStmt::Throw(_) | JsStmt::Break(_) | JsStmt::Continue(_)))
+}
+
+/// Whether values of `ty` are `Copy` in Rust
+/// Patterns a slice pattern spells: names and holes, with a named rest
+fn is_slice_pattern(elements: &[Option<JsPattern>], rest: Option<&JsPattern>) -> bool {
+    elements.iter().all(|element| matches!(element, None | Some(JsPattern::Ident(_))))
+        && matches!(rest, None | Some(JsPattern::Ident(_)))
+}
+
+/// The key the type of a field of a module's struct goes by among the
+/// signatures a body sees
+pub(crate) fn field_key(name: &str, field: &str) -> String {
+    format!("{} {{ {} }}", name, field)
+}
+
+fn is_copy(ty: &JsType) -> bool {
+    matches!(ty, JsType::Number | JsType::Boolean | JsType::BigInt)
+}
+
+/// Methods a body calls on `this`
+pub(crate) fn this_calls(body: &[JsStmt]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let mut visit = |expr: &JsExpr| {
+        if let JsExpr::Call(callee, _) = expr {
+            if let JsExpr::Member(object, method) = &**callee {
+                if matches!(**object, JsExpr::This) {
+                    names.insert(method.clone());
+                }
+            }
+        }
+        true
+    };
+    body.iter().for_each(|stmt| each_stmt_expr(stmt, &mut visit));
+    names
+}
+
+/// Every expression in a statement, nested ones and closure bodies included
+pub(crate) fn each_stmt_expr(stmt: &JsStmt, visit: &mut dyn FnMut(&JsExpr) -> bool) {
+    let block = |body: &[JsStmt], visit: &mut dyn FnMut(&JsExpr) -> bool| body.iter().for_each(|stmt| each_stmt_expr(stmt, visit));
+    match stmt {
+        JsStmt::Let { init: Some(expr), .. } | JsStmt::Expr(expr) | JsStmt::Return(Some(expr)) | JsStmt::Throw(expr) => {
+            each_expr(expr, visit)
+        }
+        JsStmt::If { test, then, otherwise } => {
+            each_expr(test, visit);
+            block(then, visit);
+            block(otherwise, visit);
+        }
+        JsStmt::While { test, body } | JsStmt::DoWhile { body, test } => {
+            each_expr(test, visit);
+            block(body, visit);
+        }
+        JsStmt::For { init, test, update, body } => {
+            block(init, visit);
+            test.iter().chain(update).for_each(|expr| each_expr(expr, visit));
+            block(body, visit);
+        }
+        JsStmt::ForOf { iterable: expr, body, .. } | JsStmt::ForIn { object: expr, body, .. } => {
+            each_expr(expr, visit);
+            block(body, visit);
+        }
+        JsStmt::Switch { discriminant, cases } => {
+            each_expr(discriminant, visit);
+            for case in cases {
+                case.test.iter().for_each(|test| each_expr(test, visit));
+                block(&case.body, visit);
+            }
+        }
+        JsStmt::Block(body) => block(body, visit),
+        JsStmt::Labeled(_, body) => each_stmt_expr(body, visit),
+        JsStmt::Try { block: body, handler, finalizer, .. } => {
+            block(body, visit);
+            block(handler.as_deref().unwrap_or_default(), visit);
+            block(finalizer, visit);
+        }
+        JsStmt::Function(function) => block(&function.body, visit),
+        _ => {}
+    }
+}
+
+/// Every expression within `expr`, itself first
+///
+/// `visit` returns whether to look inside the expression too.
+pub(crate) fn each_expr(expr: &JsExpr, visit: &mut dyn FnMut(&JsExpr) -> bool) {
+    if !visit(expr) {
+        return;
+    }
+    match expr {
+        JsExpr::Call(callee, items) | JsExpr::New(callee, items) => {
+            each_expr(callee, visit);
+            items.iter().for_each(|item| each_expr(item, visit));
+        }
+        JsExpr::Template { exprs: items, .. } | JsExpr::Array(items) | JsExpr::Sequence(items) => {
+            items.iter().for_each(|item| each_expr(item, visit));
+        }
+        JsExpr::Object(props) => props.iter().for_each(|prop| match prop {
+            JsProp::KeyValue(_, value) | JsProp::Spread(value) => each_expr(value, visit),
+        }),
+        JsExpr::Spread(inner) | JsExpr::Unary(_, inner) | JsExpr::Await(inner) | JsExpr::Member(inner, _) => each_expr(inner, visit),
+        JsExpr::Yield { value: Some(inner), .. } => each_expr(inner, visit),
+        JsExpr::Update { target, .. } => each_expr(target, visit),
+        JsExpr::Binary(_, left, right) | JsExpr::Index(left, right) | JsExpr::Assign(_, left, right) => {
+            each_expr(left, visit);
+            each_expr(right, visit);
+        }
+        JsExpr::Conditional(test, then, otherwise) => {
+            each_expr(test, visit);
+            each_expr(then, visit);
+            each_expr(otherwise, visit);
+        }
+        JsExpr::Function(function) => function.body.iter().for_each(|stmt| each_stmt_expr(stmt, visit)),
+        _ => {}
+    }
+}
+
+/// `switch` cases end in `break`, which the if-chain doesn't need
+fn without_trailing_break(body: &[JsStmt]) -> &[JsStmt] {
+    match body {
+        [rest @ .., JsStmt::Break(None)] => rest,
+        body => body,
+    }
+}
+
+/// `'label: ` for a loop something breaks out of by name
+fn loop_label(frame: &Frame) -> String {
+    frame.outer.as_ref().map_or(String::new(), |outer| format!("{}: ", outer))
+}
+
+/// Whether an unlabeled `break` in `body` leaves the enclosing loop or switch
+fn breaks(body: &[JsStmt]) -> bool {
+    jumps(body, &|stmt, nested| matches!(stmt, JsStmt::Break(None)) && !nested.in_loop && !nested.in_switch)
+}
+
+/// Where a jump sits relative to the statement list being searched
+#[derive(Clone, Copy, Default)]
+struct Nesting {
+    in_loop: bool,
+    in_switch: bool,
+}
+
+/// Whether any `break`, `continue` or `return` in `body` satisfies `matches`
+///
+/// Closures are not searched: jumps can't cross them.
+fn jumps(body: &[JsStmt], matches: &dyn Fn(&JsStmt, Nesting) -> bool) -> bool {
+    fn search(body: &[JsStmt], nesting: Nesting, matches: &dyn Fn(&JsStmt, Nesting) -> bool) -> bool {
+        let inner_loop = Nesting { in_loop: true, ..nesting };
+        body.iter().any(|stmt| match stmt {
+            JsStmt::Break(_) | JsStmt::Continue(_) | JsStmt::Return(_) => matches(stmt, nesting),
+            JsStmt::If { then, otherwise, .. } => search(then, nesting, matches) || search(otherwise, nesting, matches),
+            JsStmt::Block(body) => search(body, nesting, matches),
+            JsStmt::Labeled(_, body) => search(std::slice::from_ref(body), nesting, matches),
+            JsStmt::Switch { cases, .. } => {
+                let inner_switch = Nesting { in_switch: true, ..nesting };
+                cases.iter().any(|case| search(&case.body, inner_switch, matches))
+            }
+            JsStmt::Try { block, handler, finalizer, .. } => {
+                search(block, nesting, matches)
+                    || handler.as_deref().is_some_and(|handler| search(handler, nesting, matches))
+                    || search(finalizer, nesting, matches)
+            }
+            JsStmt::While { body, .. }
+            | JsStmt::DoWhile { body, .. }
+            | JsStmt::For { body, .. }
+            | JsStmt::ForOf { body, .. }
+            | JsStmt::ForIn { body, .. } => search(body, inner_loop, matches),
+            _ => false,
+        })
+    }
+    search(body, Nesting::default(), matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::{Iteration, Layout, Returns};
+    use crate::parse::parse_source;
+    use crate::{NumericPolicy, Overflow, SoulForge, SourceKind};
+    use std::collections::HashMap;
+
+    /// The body of the last function in `code`, which can call the others
+    fn transmute_body(code: &str, kind: SourceKind) -> String {
+        transmute_laid_out(code, kind, Layout::default())
+    }
+
+    fn transmute_laid_out(code: &str, kind: SourceKind, layout: Layout) -> String {
+        transmute_with(code, kind, |forge| forge.layout = layout)
+    }
+
+    fn transmute_with(code: &str, kind: SourceKind, configure: impl FnOnce(&mut SoulForge)) -> String {
+        let db = tempfile::tempdir().unwrap();
+        let mut forge = SoulForge::new(db.path().to_str().unwrap()).unwrap();
+        configure(&mut forge);
+        let mut ast = parse_source(code, kind).unwrap();
+        crate::errors::Throwing::of(&ast).mark(&mut ast);
+        let mut functions = ast.functions;
+        let signatures: HashMap<_, _> = functions.iter().map(|f| (f.name.clone(), f.signature())).collect();
+        let function = functions.pop().unwrap();
+        forge.transform_generic_function(&function, &signatures, None).unwrap()
+    }
+
+    #[test]
+    fn test_bodies_follow_the_layout() {
+        let sign = "function sign(x: number): number { if (x > 0) { return 1; } else { return -1; } }";
+        let guarded = "function sign(x: number): number { if (x > 0) { return 1; } return -1; }";
+        let nested = Layout { iteration: Iteration::Loops, returns: Returns::Nested };
+        let early = Layout { iteration: Iteration::Chains, returns: Returns::Early };
+        assert_eq!(transmute_laid_out(guarded, SourceKind::TypeScript, nested), transmute_body(sign, SourceKind::TypeScript));
+        assert_eq!(transmute_laid_out(sign, SourceKind::TypeScript, early), transmute_body(guarded, SourceKind::TypeScript));
+
+        let each = "function log(xs: number[]) { xs.forEach(x => { console.log(x); }); }";
+        let looped = "function log(xs: number[]) { for (const x of xs) { console.log(x); } }";
+        assert_eq!(transmute_laid_out(each, SourceKind::TypeScript, nested), transmute_body(looped, SourceKind::TypeScript));
+        assert_eq!(transmute_laid_out(looped, SourceKind::TypeScript, early), transmute_body(each, SourceKind::TypeScript));
+    }
+
+    #[test]
+    fn test_control_flow_and_arithmetic() {
+        let body = transmute_body(
+            "function clamp(values: number[], max: number): number {\n\
+               let total = 0;\n\
+               for (let i = 0; i < values.length; i++) {\n\
+                 if (values[i] > max) { continue; } else if (!values[i]) { break; }\n\
+                 total += values[i] * 2;\n\
+               }\n\
+               while (total > max) total -= 1;\n\
+               return total;\n\
+             }",
+            SourceKind::TypeScript,
+        );
+
+        assert!(body.starts_with("let mut total = 0.0;\n    let mut i = 0.0;\n    'loop1: while i < (values.len() as f64) {"), "{}", body);
+        assert!(body.contains("'loop1_body: {"));
+        assert!(body.contains("if values[i as usize] > max {\n                break 'loop1_body;"));
+        assert!(body.contains("} else if values[i as usize] == 0.0 {\n                break 'loop1;"));
+        assert!(body.contains("total += values[i as usize] * 2.0;"));
+        assert!(body.contains("        i += 1.0;\n    }"));
+        assert!(body.contains("while total > max {\n        total -= 1.0;\n    }"));
+        assert!(body.ends_with("\n    total"));
+    }
+
+    #[test]
+    fn test_integer_locals_under_idiomatic_int() {
+        let code = "function sum(values: number[]): number {\n\
+                      let total = 0;\n\
+                      for (let i = 0; i < values.length; i++) { total += values[i] * i; }\n\
+                      let last = values.length - 1;\n\
+                      return total + values[last];\n\
+                    }";
+        let floats = transmute_body(code, SourceKind::TypeScript);
+        assert!(floats.contains("let mut i = 0.0;"), "{}", floats);
+
+        let body = transmute_with(code, SourceKind::TypeScript, |forge| forge.numeric = NumericPolicy::IdiomaticInt);
+        assert!(body.starts_with("let mut total = 0.0;\n    let mut i: i64 = 0;\n    while i < (values.len() as i64) {"), "{}", body);
+        assert!(body.contains("total += values[i as usize] * (i as f64);"));
+        assert!(body.contains("        i += 1;\n    }"));
+        assert!(body.contains("let last: i64 = (values.len() as i64) - 1;"));
+
+        let wrapping = transmute_with(code, SourceKind::TypeScript, |forge| {
+            forge.numeric = NumericPolicy::IdiomaticInt;
+            forge.overflow = Overflow::Wrapping;
+        });
+        assert!(wrapping.contains("        i = i.wrapping_add(1);\n    }"), "{}", wrapping);
+        assert!(wrapping.contains("let last: i64 = (values.len() as i64).wrapping_sub(1);"));
+    }
+
+    #[test]
+    fn test_labels_and_switch_breaks() {
+        let body = transmute_body(
+            "function scan(rows: number[][]): number {\n\
+               let hits = 0;\n\
+               outer: for (const row of rows) {\n\
+                 for (const cell of row) {\n\
+                   switch (cell) {\n\
+                     case 0: continue outer;\n\
+                     case 1: if (hits > 3) { break; } hits++; break;\n\
+                     default: hits += cell;\n\
+                   }\n\
+                 }\n\
+               }\n\
+               return hits;\n\
+             }",
+            SourceKind::TypeScript,
+        );
+
+        assert!(body.contains("'outer: for row in rows.iter() {"), "{}", body);
+        assert!(body.contains("continue 'outer;"));
+        assert!(body.contains("'switch3: {\n                if *cell == 0.0 {"));
+        assert!(body.contains("if hits > 3.0 {\n                        break 'switch3;"));
+        assert!(body.contains("} else {\n                    hits += *cell;"));
+    }
+
+    #[test]
+    fn test_strings_and_array_methods() {
+        let body = transmute_body(
+            "function describe(items, label = 'item') {\n\
+               const [first, ...others] = items;\n\
+               const big = items.filter(x => x > 10).map((x, i) => x * i);\n\
+               const total = items.reduce((sum, x) => sum + x, 0);\n\
+               return `${label}: ${total}` + ' of ' + others.length;\n\
+             }",
+            SourceKind::JavaScript,
+        );
+
+        assert!(body.contains("let first = items[0].clone();"), "{}", body);
+        assert!(body.contains("let others = items[1..].to_vec();"));
+        assert!(body.contains("items.iter().filter(|&x| *x > 10.0).cloned().collect::<Vec<_>>()"));
+        assert!(body.contains(".iter().enumerate().map(|(i, x)| {\n        let i = i as f64;\n        *x * i\n    }).collect::<Vec<_>>()"));
+        assert!(body.contains("items.iter().fold(0.0, |sum, x| sum + *x)"));
+        assert!(body.ends_with("format!(\"{}: {} of {}\", label, total, others.len() as f64)"));
+    }
+
+    #[test]
+    fn test_async_and_promise_combinators() {
+        let body = transmute_body(
+            "async function load(id: number): Promise<string> { return `#${id}`; }\n\
+             async function gather(ids: number[]): Promise<string> {\n\
+               if (ids.length === 0) throw new Error('empty');\n\
+               const [a, b] = await Promise.all([load(ids[0]), load(ids[1])]);\n\
+               const rest = await Promise.all(ids.map(id => load(id)));\n\
+               const first = await Promise.race([load(1), load(2)]);\n\
+               load(3);\n\
+               return load(4).then(text => text + a + b + first + rest.length);\n\
+             }",
+            SourceKind::TypeScript,
+        );
+
+        assert!(body.contains("return Err(JsError::new(\"empty\".to_string()));"), "{}", body);
+        assert!(body.contains("let (a, b) = futures::try_join!(load(ids[0]), load(ids[1]))?;"));
+        assert!(body.contains("futures::future::try_join_all(ids.iter().map(|id| load(*id)).collect::<Vec<_>>()).await?"));
+        assert!(body.contains("futures::select! { value = futures::FutureExt::fuse(load(1.0)) => value,"));
+        assert!(body.contains("spawn_promise(load(3.0));"));
+        assert!(body.contains("async move {\n        let text = load(4.0).await?;\n        Ok::<_, JsError>(format!("));
+        assert!(body.ends_with("    }.await"));
+    }
+
+    #[test]
+    fn test_exceptions_propagate_to_catch() {
+        let code = "function check(n: number): number { if (n < 0) throw new RangeError(`bad ${n}`); return n; }\n\
+                    function safe(n: number): number {\n\
+                      try { return check(n) * 2; }\n\
+                      catch (e) { console.log(e.message); return 0; }\n\
+                      finally { console.log('done'); }\n\
+                    }";
+        let body = transmute_body(code, SourceKind::TypeScript);
+        assert!(body.contains("let caught1: Result<(), JsError> = 'caught1: {"), "{}", body);
+        assert!(body.contains("match check(n) { Ok(value) => value, Err(error) => break 'caught1 Err(error) } * 2.0"), "{}", body);
+        // `finally` runs before each return, after its value is worked out
+        assert!(body.contains("let returned2 = match check(n)"), "{}", body);
+        assert!(body.contains("        println!(\"{}\", \"done\".to_string());\n        return returned2;"), "{}", body);
+        assert!(body.contains("let Err(e) = caught1 else { unreachable!() };\n    println!(\"{}\", e.message().to_string());"), "{}", body);
+
+        let code = "function check(n: number): number { if (n < 0) throw new RangeError('bad'); return n; }\n\
+                    function twice(n: number): number { const once = check(n); try { check(once); } finally { n = 0; } return once; }";
+        let body = transmute_body(code, SourceKind::TypeScript);
+        assert!(body.contains("let once = check(n)?;"), "{}", body);
+        assert!(body.contains("n = 0.0;\n    caught1?;\n    Ok(once)"), "{}", body);
+    }
+
+    #[test]
+    fn test_local_closures_capture_by_use() {
+        let body = transmute_body(
+            "function tally(xs: number[]) {\n\
+               let total = 0;\n\
+               const add = (x: number) => { total += x; };\n\
+               const scale = (k: number) => (x: number) => x * k;\n\
+               for (const x of xs) add(scale(2)(x));\n\
+               return total;\n\
+             }",
+            SourceKind::TypeScript,
+        );
+
+        assert!(body.contains("let mut add = |x| {\n        total += x;\n    };"), "{}", body);
+        assert!(body.contains("let scale = |k| move |x| x * k;"));
+        assert!(body.contains("add((scale(2.0))(*x));"));
+    }
+}
@@ -0,0 +1,330 @@
+// 🔬 Compile Verification - Bodies That Build
+use crate::TransmutationError;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Rounds of checking and refining before the forge gives up on a crate
+pub(crate) const MAX_ROUNDS: usize = 8;
+
+/// How far the forge checks a transmuted crate before returning it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Verification {
+    /// Return the generated code as it is
+    #[default]
+    Off,
+    /// Run `cargo check`, refining the code until it compiles
+    Check,
+    /// Run `cargo clippy`, which also applies the fixes lints suggest
+    Clippy,
+}
+
+impl std::str::FromStr for Verification {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "check" => Ok(Self::Check),
+            "clippy" => Ok(Self::Clippy),
+            other => Err(format!("unknown verification '{}' (expected off, check or clippy)", other)),
+        }
+    }
+}
+
+/// A compiler message about the generated `src/lib.rs`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Diagnostic {
+    /// `error` or `warning`
+    pub(crate) level: String,
+    /// An error code like `E0308`, or a lint like `clippy::needless_return`
+    pub(crate) code: Option<String>,
+    pub(crate) message: String,
+    /// The line the message points at, from 1
+    pub(crate) line: Option<usize>,
+    /// The bytes the message points at
+    pub(crate) bytes: Option<(usize, usize)>,
+    /// What the compiler suggests, as its first `help:` says it
+    pub(crate) help: Option<String>,
+    /// Fixes the compiler is certain of
+    pub(crate) fixes: Vec<Fix>,
+    pub(crate) rendered: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+
+    /// The message as a failure of `rust_code`, the crate's `src/lib.rs`
+    pub(crate) fn in_code(&self, rust_code: &str) -> crate::Diagnostic {
+        let message = match &self.code {
+            Some(code) => format!("{}: {}", code, self.message),
+            None => self.message.clone(),
+        };
+        let mut diagnostic = crate::Diagnostic::error(message).with_file("src/lib.rs");
+        if let Some((start, end)) = self.bytes {
+            diagnostic = diagnostic.with_span(crate::Span::of(rust_code, start, end));
+        }
+        if let Some(help) = &self.help {
+            diagnostic = diagnostic.with_suggestion(help.clone());
+        }
+        diagnostic
+    }
+}
+
+/// Replace the bytes `start..end` with `replacement`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Fix {
+    start: usize,
+    end: usize,
+    replacement: String,
+}
+
+/// A scratch crate the generated code is checked in
+pub(crate) struct CheckCrate {
+    dir: PathBuf,
+}
+
+impl CheckCrate {
+    /// The crate `name` under the temp dir; crates share a target dir, so
+    /// their dependencies build once
+    pub(crate) fn new(name: &str, cargo_toml: &str) -> Result<Self, TransmutationError> {
+        let dir = scratch_dir().join(name);
+        std::fs::create_dir_all(dir.join("src")).map_err(io_error)?;
+        std::fs::write(dir.join("Cargo.toml"), cargo_toml).map_err(io_error)?;
+        Ok(Self { dir })
+    }
+
+    /// What the compiler says of `rust_code` as the crate's `src/lib.rs`
+    pub(crate) async fn check(&self, rust_code: &str, verification: Verification) -> Result<Vec<Diagnostic>, TransmutationError> {
+        std::fs::write(self.dir.join("src").join("lib.rs"), rust_code).map_err(io_error)?;
+        let subcommand = if verification == Verification::Clippy { "clippy" } else { "check" };
+        let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+        let output = tokio::process::Command::new(cargo)
+            .args([subcommand, "--message-format=json", "--quiet"])
+            .env("CARGO_TARGET_DIR", scratch_dir().join("target"))
+            .current_dir(&self.dir)
+            .output()
+            .await
+            .map_err(|e| TransmutationError::CompileError(format!("can't run cargo {}: {}", subcommand, e).into()))?;
+
+        let diagnostics = diagnostics(&String::from_utf8_lossy(&output.stdout));
+        // Failing without a word from the compiler is cargo's own trouble,
+        // like a dependency it can't fetch
+        if !output.status.success() && !diagnostics.iter().any(Diagnostic::is_error) {
+            return Err(TransmutationError::CompileError(String::from_utf8_lossy(&output.stderr).trim().to_string().into()));
+        }
+        Ok(diagnostics)
+    }
+
+    /// Run `tests` as the crate's `tests/equivalence.rs` against `rust_code`,
+    /// returning the number that passed; none pass when the tests don't build
+    pub(crate) async fn test(&self, rust_code: &str, tests: &str) -> Result<usize, TransmutationError> {
+        std::fs::write(self.dir.join("src").join("lib.rs"), rust_code).map_err(io_error)?;
+        std::fs::create_dir_all(self.dir.join("tests")).map_err(io_error)?;
+        std::fs::write(self.dir.join("tests").join("equivalence.rs"), tests).map_err(io_error)?;
+        let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+        let output = tokio::process::Command::new(cargo)
+            .args(["test", "--test", "equivalence", "--quiet", "--", "--test-threads=1"])
+            .env("CARGO_TARGET_DIR", scratch_dir().join("target"))
+            .current_dir(&self.dir)
+            .output()
+            .await
+            .map_err(|e| TransmutationError::CompileError(format!("can't run cargo test: {}", e).into()))?;
+        Ok(crate::equivalence::passed(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+fn scratch_dir() -> PathBuf {
+    std::env::temp_dir().join("soul-forge-check")
+}
+
+fn io_error(error: std::io::Error) -> TransmutationError {
+    TransmutationError::CompileError(error.to_string().into())
+}
+
+/// The errors and warnings in cargo's `--message-format=json` output
+pub(crate) fn diagnostics(stdout: &str) -> Vec<Diagnostic> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-message")
+        .filter_map(|message| diagnostic(&message["message"]))
+        .collect()
+}
+
+/// Summaries like "aborting due to 2 previous errors" point nowhere, and are skipped
+fn diagnostic(message: &Value) -> Option<Diagnostic> {
+    let level = message["level"].as_str()?;
+    let spans = message["spans"].as_array().filter(|spans| !spans.is_empty())?;
+    if level != "error" && level != "warning" {
+        return None;
+    }
+
+    let primary = spans.iter().find(|span| span["is_primary"] == true && span["file_name"] == "src/lib.rs");
+    let children = message["children"].as_array().into_iter().flatten();
+    let help = children.clone()
+        .find(|child| child["level"] == "help")
+        .and_then(|child| child["message"].as_str())
+        .map(str::to_string);
+    let fixes = spans
+        .iter()
+        .chain(children.flat_map(|child| child["spans"].as_array().into_iter().flatten()))
+        .filter(|span| span["suggestion_applicability"] == "MachineApplicable" && span["file_name"] == "src/lib.rs")
+        .filter_map(|span| {
+            Some(Fix {
+                start: span["byte_start"].as_u64()? as usize,
+                end: span["byte_end"].as_u64()? as usize,
+                replacement: span["suggested_replacement"].as_str()?.to_string(),
+            })
+        })
+        .collect();
+
+    Some(Diagnostic {
+        level: level.to_string(),
+        code: message["code"]["code"].as_str().map(str::to_string),
+        message: message["message"].as_str().unwrap_or_default().to_string(),
+        line: primary.and_then(|span| span["line_start"].as_u64()).map(|line| line as usize),
+        bytes: primary.and_then(|span| Some((span["byte_start"].as_u64()? as usize, span["byte_end"].as_u64()? as usize))),
+        help,
+        fixes,
+        rendered: message["rendered"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+/// `rust_code` with the fixes the compiler is certain of applied or, when
+/// there are none, each function an error is in stubbed out with a `todo!`
+/// naming it
+///
+/// Fixes move the code the other errors point at, so stubbing waits for the
+/// next check.
+pub(crate) fn repair(rust_code: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut fixes: Vec<&Fix> = diagnostics.iter().flat_map(|diagnostic| &diagnostic.fixes).collect();
+    fixes.sort();
+    fixes.dedup();
+
+    // Back to front, so the offsets before each fix stay put; fixes that
+    // overlap one already made wait for the next round
+    let mut code = rust_code.to_string();
+    let mut floor = code.len();
+    for fix in fixes.into_iter().rev() {
+        if fix.end > floor || !code.is_char_boundary(fix.start) || !code.is_char_boundary(fix.end) {
+            continue;
+        }
+        let newline = code[fix.start..fix.end].ends_with('\n');
+        code.replace_range(fix.start..fix.end, &fix.replacement);
+        floor = fix.start;
+        // A line a fix empties goes, with a blank line it leaves opening a block
+        let line = code[..fix.start].rfind('\n').map_or(0, |newline| newline + 1);
+        if fix.replacement.is_empty() && code[line..fix.start].trim().is_empty() && (newline || code[fix.start..].starts_with('\n')) {
+            code.replace_range(line..fix.start + usize::from(!newline), "");
+            let opens = line == 0 || code[..line - 1].trim_end_matches(' ').ends_with(['{', '\n']);
+            if opens && code[line..].starts_with('\n') {
+                code.remove(line);
+            }
+            floor = line;
+        }
+    }
+    if code != rust_code {
+        return code;
+    }
+
+    let mut lines: Vec<String> = code.lines().map(str::to_string).collect();
+    let mut stubs: Vec<(usize, usize, &Diagnostic)> = vec![];
+    for error in diagnostics.iter().filter(|diagnostic| diagnostic.is_error()) {
+        let Some(line) = error.line.and_then(|line| line.checked_sub(1)) else { continue };
+        // An error in the signature itself is beyond a stub
+        match enclosing_function(&lines, line) {
+            Some((head, end)) if head < line && !stubs.iter().any(|(h, e, _)| *h <= head && end <= *e) => {
+                stubs.retain(|(h, e, _)| !(head <= *h && *e <= end));
+                stubs.push((head, end, error));
+            }
+            _ => {}
+        }
+    }
+
+    stubs.sort_by_key(|(head, ..)| std::cmp::Reverse(*head));
+    for (head, end, error) in stubs {
+        let indent: String = lines[head].chars().take_while(|c| *c == ' ').collect();
+        let what = match &error.code {
+            Some(code) => format!("{}: {}", code, error.message),
+            None => error.message.clone(),
+        };
+        lines.splice(head + 1..end, [format!("{}    todo!({:?})", indent, what)]);
+    }
+
+    let mut code = lines.join("\n");
+    if rust_code.ends_with('\n') {
+        code.push('\n');
+    }
+    code
+}
+
+/// The lines of the function around `line`: its signature, and the brace
+/// closing its body
+fn enclosing_function(lines: &[String], line: usize) -> Option<(usize, usize)> {
+    (0..=line.min(lines.len().checked_sub(1)?)).rev().find_map(|head| {
+        let signature = lines[head].trim_start();
+        let is_fn = ["fn ", "pub fn ", "async fn ", "pub async fn "].iter().any(|start| signature.starts_with(start));
+        if !is_fn || !signature.ends_with('{') {
+            return None;
+        }
+        let indent = &lines[head][..lines[head].len() - signature.len()];
+        let end = (head + 1..lines.len()).find(|&end| lines[end].strip_prefix(indent) == Some("}"))?;
+        (end >= line).then_some((head, end))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics_from_cargo_json() {
+        let stdout = concat!(
+            r#"{"reason":"compiler-artifact","target":{"name":"serde"}}"#, "\n",
+            r#"{"reason":"compiler-message","message":{"level":"error","message":"mismatched types","code":{"code":"E0308"},"rendered":"error[E0308]: mismatched types","spans":[{"file_name":"src/lib.rs","is_primary":true,"line_start":4,"byte_start":40,"byte_end":43,"suggested_replacement":null,"suggestion_applicability":null}],"children":[{"spans":[{"file_name":"src/lib.rs","is_primary":true,"line_start":4,"byte_start":43,"byte_end":43,"suggested_replacement":".to_string()","suggestion_applicability":"MachineApplicable"}]}]}}"#, "\n",
+            r#"{"reason":"compiler-message","message":{"level":"error","message":"aborting due to 1 previous error","code":null,"rendered":"","spans":[],"children":[]}}"#, "\n",
+            r#"{"reason":"build-finished","success":false}"#,
+        );
+        let diagnostics = diagnostics(stdout);
+
+        assert_eq!(diagnostics.len(), 1);
+        let error = &diagnostics[0];
+        assert!(error.is_error());
+        assert_eq!(error.code.as_deref(), Some("E0308"));
+        assert_eq!(error.line, Some(4));
+        assert_eq!(error.bytes, Some((40, 43)));
+        assert_eq!(error.fixes, [Fix { start: 43, end: 43, replacement: ".to_string()".to_string() }]);
+    }
+
+    #[test]
+    fn test_repair_applies_fixes_then_stubs_failures() {
+        let error = |line, fixes| Diagnostic {
+            level: "error".to_string(),
+            code: Some("E0308".to_string()),
+            message: "mismatched types".to_string(),
+            line: Some(line),
+            bytes: None,
+            help: None,
+            fixes,
+            rendered: String::new(),
+        };
+        let code = "pub fn name() -> String {\n    \"x\"\n}\n\npub fn twice(x: f64) -> f64 {\n    let y = x;\n    y * \"2\"\n}\n";
+
+        let fix = Fix { start: 33, end: 33, replacement: ".to_string()".to_string() };
+        let fixed = repair(code, &[error(2, vec![fix]), error(7, vec![])]);
+        assert!(fixed.starts_with("pub fn name() -> String {\n    \"x\".to_string()\n}\n"));
+        assert!(fixed.contains("y * \"2\""));
+
+        let stubbed = repair(&fixed, &[error(7, vec![])]);
+        assert!(stubbed.ends_with("pub fn twice(x: f64) -> f64 {\n    todo!(\"E0308: mismatched types\")\n}\n"));
+        // Nothing to stub an error in a signature with
+        assert_eq!(repair(&fixed, &[error(5, vec![])]), fixed);
+
+        // An unused import goes with the blank line after it
+        let nested = "pub mod m {\n    use serde_json::Value;\n\n    pub fn f() {}\n}\n";
+        let unused = Fix { start: 16, end: 39, replacement: String::new() };
+        assert_eq!(repair(nested, &[error(2, vec![unused])]), "pub mod m {\n    pub fn f() {}\n}\n");
+    }
+}
@@ -0,0 +1,439 @@
+// 🧬 Class Transmutation - Prototype Chains into Traits and Composition
+use crate::body::{self, ClassMember, ClassScope, Construct};
+use crate::{
+    JsClass, JsExpr, JsFunction, JsStmt, JsType, MethodKind, RustField, RustFunction, RustImpl, RustStruct,
+    RustTrait, SoulForge, TransmutationError,
+};
+use std::collections::{HashMap, HashSet};
+
+/// What a module's classes transmute into
+#[derive(Default)]
+pub(crate) struct ClassItems {
+    pub(crate) structs: Vec<RustStruct>,
+    pub(crate) traits: Vec<RustTrait>,
+    pub(crate) impls: Vec<RustImpl>,
+}
+
+/// The classes of a module, and what inheritance between them implies
+///
+/// A class that others extend becomes a struct plus a `{Name}Like` trait:
+/// the trait reaches the base's fields through accessors and carries the
+/// methods the base introduces as default methods. A derived struct holds
+/// its base as a field, implements the trait of every ancestor, and
+/// overrides what it redefines.
+struct Hierarchy<'a> {
+    classes: HashMap<&'a str, &'a JsClass>,
+
+    /// Classes another class of the module extends
+    bases: HashSet<&'a str>,
+
+    /// Methods taking `&mut self`, by name, so overrides agree with the trait
+    mutating: HashSet<String>,
+}
+
+impl<'a> Hierarchy<'a> {
+    fn new(classes: &'a [JsClass]) -> Self {
+        let by_name: HashMap<&str, &JsClass> = classes.iter().map(|class| (class.name.as_str(), class)).collect();
+        let bases = classes.iter()
+            .filter_map(|class| class.extends.as_deref())
+            .filter_map(|base| by_name.get_key_value(base).map(|(name, _)| *name))
+            .collect();
+
+        // Writing to `this`, or calling a method that does, needs `&mut self`
+        let methods: Vec<&JsFunction> = classes.iter()
+            .flat_map(|class| &class.methods)
+            .filter(|method| matches!(method.kind, MethodKind::Method | MethodKind::Getter))
+            .map(|method| &method.function)
+            .collect();
+        let mut mutating: HashSet<String> = methods.iter()
+            .filter(|function| body::mutated_names(&function.body).contains("this"))
+            .map(|function| function.name.clone())
+            .collect();
+        loop {
+            let callers: Vec<String> = methods.iter()
+                .filter(|function| !mutating.contains(&function.name))
+                .filter(|function| body::this_calls(&function.body).iter().any(|name| mutating.contains(name)))
+                .map(|function| function.name.clone())
+                .collect();
+            if callers.is_empty() {
+                break;
+            }
+            mutating.extend(callers);
+        }
+
+        Self { classes: by_name, bases, mutating }
+    }
+
+    /// The ancestors of `class` transmuted along with it, nearest first
+    fn ancestors(&self, class: &JsClass) -> Vec<&'a JsClass> {
+        let mut ancestors: Vec<&JsClass> = vec![];
+        let mut next = class.extends.as_deref();
+        while let Some(parent) = next.and_then(|name| self.classes.get(name)) {
+            // A cycle is a type error in TS; stop rather than loop
+            if parent.name == class.name || ancestors.iter().any(|known| known.name == parent.name) {
+                break;
+            }
+            ancestors.push(parent);
+            next = parent.extends.as_deref();
+        }
+        ancestors
+    }
+
+    /// Whether `class` introduces method `name`, rather than overriding it
+    fn introduces(&self, class: &JsClass, name: &str) -> bool {
+        !self.ancestors(class).iter().any(|ancestor| defines(ancestor, name))
+    }
+
+    fn receiver(&self, name: &str) -> &'static str {
+        if self.mutating.contains(name) { "&mut self" } else { "&self" }
+    }
+
+    /// What a method of `class` sees, with its own fields read through `own`
+    /// and written through `own_mut`
+    fn scope(&self, class: &JsClass, this: &str, own: &str, own_mut: &str) -> ClassScope {
+        let mut members = HashMap::new();
+        let mut methods = HashSet::new();
+        let mut throwing = HashSet::new();
+        let lineage: Vec<&JsClass> = std::iter::once(class).chain(self.ancestors(class)).collect();
+        let mut path = own.to_string();
+        let mut place = own_mut.to_string();
+        for (i, class) in lineage.iter().enumerate() {
+            for property in class.properties.iter().filter(|property| !property.is_static) {
+                members.entry(property.name.clone()).or_insert_with(|| ClassMember {
+                    path: format!("{}.{}", path, body::rust_ident(&property.name)),
+                    place: format!("{}.{}", place, body::rust_ident(&property.name)),
+                    ty: property.ty.clone(),
+                    computed: false,
+                });
+            }
+            for method in &class.methods {
+                match method.kind {
+                    MethodKind::Getter => {
+                        members.entry(method.function.name.clone()).or_insert_with(|| ClassMember {
+                            path: format!("{}.{}()", path, body::rust_ident(&method.function.name)),
+                            place: format!("{}.{}()", path, body::rust_ident(&method.function.name)),
+                            ty: method.function.return_type.clone(),
+                            computed: true,
+                        });
+                    }
+                    MethodKind::Method => {
+                        methods.insert(method.function.name.clone());
+                        if matches!(method.function.return_type, JsType::Throws(_)) {
+                            throwing.insert(method.function.name.clone());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(parent) = lineage.get(i + 1) {
+                path = format!("{}.{}", path, snake_case(&parent.name));
+                place = format!("{}.{}", place, snake_case(&parent.name));
+            }
+        }
+
+        let parent = self.ancestors(class).first().map(|parent| format!("{}.{}", own_mut, snake_case(&parent.name)));
+        ClassScope { this: this.to_string(), members, methods, throwing, parent, construct: None }
+    }
+
+    /// The parameters `class`'s constructor takes, which a derived class
+    /// without one of its own forwards to `super(..)`
+    fn constructor(&self, class: &JsClass) -> JsFunction {
+        if let Some(constructor) = class.methods.iter().find(|method| method.kind == MethodKind::Constructor) {
+            return constructor.function.clone();
+        }
+        let this = JsType::Named("Self".to_string(), vec![]);
+        let (params, body, return_type) = match self.ancestors(class).first() {
+            Some(parent) => {
+                let base = self.constructor(parent);
+                let args = base.params.iter().map(|param| JsExpr::Ident(param.name.clone())).collect();
+                let return_type = match base.return_type {
+                    JsType::Throws(_) => JsType::Throws(Box::new(this)),
+                    _ => this,
+                };
+                (base.params, vec![JsStmt::Expr(JsExpr::Call(Box::new(JsExpr::Super), args))], return_type)
+            }
+            None => (vec![], vec![], this),
+        };
+        JsFunction {
+            name: "new".to_string(),
+            params,
+            return_type,
+            type_params: vec![],
+            is_async: false,
+            is_generator: false,
+            body,
+            origin: None,
+        }
+    }
+}
+
+impl SoulForge {
+    /// Transform classes into structs, the traits of their base classes, and impls
+    pub(crate) fn transform_classes(
+        &self,
+        classes: Vec<JsClass>,
+        signatures: &HashMap<String, JsType>,
+    ) -> Result<ClassItems, TransmutationError> {
+        let hierarchy = Hierarchy::new(&classes);
+        let mut items = ClassItems::default();
+
+        for class in &classes {
+            items.structs.push(self.class_struct(&hierarchy, class));
+            if hierarchy.bases.contains(class.name.as_str()) {
+                items.traits.push(self.class_trait(&hierarchy, class, signatures)?);
+            }
+
+            let inherent = self.inherent_impl(&hierarchy, class, signatures)?;
+            if !inherent.methods.is_empty() {
+                items.impls.push(inherent);
+            }
+
+            let own_trait = hierarchy.bases.contains(class.name.as_str()).then_some(class);
+            for ancestor in own_trait.into_iter().chain(hierarchy.ancestors(class)) {
+                items.impls.push(self.trait_impl(&hierarchy, class, ancestor, signatures)?);
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// The struct of a class: its base first, then its own fields
+    fn class_struct(&self, hierarchy: &Hierarchy, class: &JsClass) -> RustStruct {
+        let parent = hierarchy.ancestors(class).first().map(|parent| RustField {
+            name: snake_case(&parent.name),
+            ty: parent.name.clone(),
+            is_pub: true,
+        });
+        let own = class.properties.iter().filter(|property| !property.is_static).cloned().collect();
+
+        let doc = match (&class.extends, parent.is_some()) {
+            (Some(base), false) => Some(format!("Extends `{}`, which isn't transmuted with this module", base)),
+            _ => None,
+        };
+        RustStruct {
+            name: class.name.clone(),
+            doc,
+            fields: parent.into_iter().chain(self.transform_properties(own)).collect(),
+            origin: class.origin,
+        }
+    }
+
+    /// `{Name}Like`: a base class's fields by accessor, and the methods it
+    /// introduces as default methods
+    fn class_trait(
+        &self,
+        hierarchy: &Hierarchy,
+        class: &JsClass,
+        signatures: &HashMap<String, JsType>,
+    ) -> Result<RustTrait, TransmutationError> {
+        let accessor = snake_case(&class.name);
+        let mut methods = vec![];
+        for method in class.methods.iter().filter(|method| method.kind == MethodKind::Method) {
+            if !hierarchy.introduces(class, &method.function.name) {
+                continue;
+            }
+            let receiver = hierarchy.receiver(&method.function.name);
+            let own = format!("self.{}()", accessor);
+            let own_mut = match receiver {
+                "&mut self" => format!("self.{}_mut()", accessor),
+                _ => own.clone(),
+            };
+            let scope = hierarchy.scope(class, "self", &own, &own_mut);
+            methods.push(self.method(&method.function, signatures, scope, Some(receiver), false)?);
+        }
+
+        Ok(RustTrait {
+            name: trait_name(&class.name),
+            doc: format!("`{}` and the classes extending it", class.name),
+            supertrait: hierarchy.ancestors(class).first().map(|parent| trait_name(&parent.name)),
+            required: vec![
+                format!("fn {}(&self) -> &{}", accessor, class.name),
+                format!("fn {}_mut(&mut self) -> &mut {}", accessor, class.name),
+            ],
+            methods,
+        })
+    }
+
+    /// The constructor, statics, accessors and the methods only this class has
+    fn inherent_impl(
+        &self,
+        hierarchy: &Hierarchy,
+        class: &JsClass,
+        signatures: &HashMap<String, JsType>,
+    ) -> Result<RustImpl, TransmutationError> {
+        let mut methods = vec![];
+
+        // Interfaces and type literals are plain data
+        let is_class = class.extends.is_some() || !class.methods.is_empty()
+            || class.properties.iter().any(|property| property.init.is_some() || property.is_static);
+        if is_class {
+            let mut scope = hierarchy.scope(class, "this", "this", "this");
+            scope.construct = Some(Construct {
+                base: hierarchy.ancestors(class).first().map(|parent| (snake_case(&parent.name), parent.name.clone())),
+                fields: class.properties.iter()
+                    .filter(|property| !property.is_static)
+                    .map(|property| (property.name.clone(), property.init.clone()))
+                    .collect(),
+            });
+            methods.push(self.method(&hierarchy.constructor(class), signatures, scope, None, true)?);
+        }
+
+        // Static fields become functions returning their initial value
+        for property in class.properties.iter().filter(|property| property.is_static) {
+            let Some(init) = &property.init else {
+                continue;
+            };
+            let value = JsFunction {
+                name: property.name.clone(),
+                params: vec![],
+                return_type: property.ty.clone(),
+                type_params: vec![],
+                is_async: false,
+                is_generator: false,
+                body: vec![JsStmt::Return(Some(init.clone()))],
+                origin: None,
+            };
+            methods.push(self.transform_function_in(value, signatures, None)?);
+        }
+
+        let base = hierarchy.bases.contains(class.name.as_str());
+        for method in &class.methods {
+            let function = &method.function;
+            let (function, receiver) = match method.kind {
+                MethodKind::Constructor => continue,
+                MethodKind::Static => {
+                    methods.push(self.transform_function_in(function.clone(), signatures, None)?);
+                    continue;
+                }
+                MethodKind::Getter => (function.clone(), hierarchy.receiver(&function.name)),
+                MethodKind::Setter => {
+                    let setter = JsFunction { name: format!("set_{}", function.name), return_type: JsType::Void, ..function.clone() };
+                    (setter, "&mut self")
+                }
+                // Base classes' methods are trait methods, as are overrides
+                MethodKind::Method if base || !hierarchy.introduces(class, &function.name) => continue,
+                MethodKind::Method => (function.clone(), hierarchy.receiver(&function.name)),
+            };
+            let scope = hierarchy.scope(class, "self", "self", "self");
+            methods.push(self.method(&function, signatures, scope, Some(receiver), true)?);
+        }
+
+        Ok(RustImpl { struct_name: class.name.clone(), trait_name: None, methods })
+    }
+
+    /// `impl {Ancestor}Like for {Class}`: accessors to the ancestor's part of
+    /// the class, and whatever the class or a class between them overrides
+    fn trait_impl(
+        &self,
+        hierarchy: &Hierarchy,
+        class: &JsClass,
+        ancestor: &JsClass,
+        signatures: &HashMap<String, JsType>,
+    ) -> Result<RustImpl, TransmutationError> {
+        let accessor = snake_case(&ancestor.name);
+        // The classes from `class` up to, but not including, `ancestor`
+        let lineage: Vec<&JsClass> = std::iter::once(class)
+            .chain(hierarchy.ancestors(class))
+            .take_while(|between| between.name != ancestor.name)
+            .collect();
+        let path: String = lineage.iter().skip(1).chain(std::iter::once(&ancestor))
+            .map(|between| format!(".{}", snake_case(&between.name)))
+            .collect();
+
+        let accessor_fn = |name: String, receiver: &'static str, body: String| RustFunction {
+            name,
+            generics: vec![],
+            receiver: Some(receiver),
+            params: vec![],
+            return_type: format!("{}{}", receiver.trim_end_matches("self"), ancestor.name),
+            body,
+            is_async: false,
+            is_pub: false,
+            origin: None,
+        };
+        let mut methods = match lineage.is_empty() {
+            true => vec![
+                accessor_fn(accessor.clone(), "&self", "self".to_string()),
+                accessor_fn(format!("{}_mut", accessor), "&mut self", "self".to_string()),
+            ],
+            false => vec![
+                accessor_fn(accessor.clone(), "&self", format!("&self{}", path)),
+                accessor_fn(format!("{}_mut", accessor), "&mut self", format!("&mut self{}", path)),
+            ],
+        };
+
+        let introduced = ancestor.methods.iter()
+            .filter(|method| method.kind == MethodKind::Method && hierarchy.introduces(ancestor, &method.function.name));
+        for method in introduced {
+            let name = &method.function.name;
+            let Some(position) = lineage.iter().position(|between| defines(between, name)) else {
+                continue;
+            };
+            let overriding = lineage[position];
+            let function = overriding.methods.iter()
+                .find(|method| method.kind == MethodKind::Method && &method.function.name == name)
+                .map(|method| &method.function)
+                .unwrap_or(&method.function);
+            let receiver = hierarchy.receiver(name);
+            let scope = hierarchy.scope(overriding, "self", "self", "self");
+            let mut rust = self.method(function, signatures, scope, Some(receiver), false)?;
+
+            // Overridden by a class in between: defer to its version
+            if position > 0 {
+                let field: String = lineage[1..=position].iter().map(|between| format!(".{}", snake_case(&between.name))).collect();
+                for param in &mut rust.params {
+                    param.name = param.name.trim_start_matches("mut ").to_string();
+                }
+                let args = rust.params.iter().map(|param| param.name.clone()).collect::<Vec<_>>().join(", ");
+                let wait = if rust.is_async { ".await" } else { "" };
+                rust.body = format!("self{}.{}({}){}", field, body::rust_ident(name), args, wait);
+            }
+            methods.push(rust);
+        }
+
+        Ok(RustImpl {
+            struct_name: class.name.clone(),
+            trait_name: Some(trait_name(&ancestor.name)),
+            methods,
+        })
+    }
+
+    fn method(
+        &self,
+        function: &JsFunction,
+        signatures: &HashMap<String, JsType>,
+        scope: ClassScope,
+        receiver: Option<&'static str>,
+        is_pub: bool,
+    ) -> Result<RustFunction, TransmutationError> {
+        let mut rust = self.transform_function_in(function.clone(), signatures, Some(scope))?;
+        rust.receiver = receiver;
+        rust.is_pub = is_pub;
+        Ok(rust)
+    }
+}
+
+/// Whether `class` itself defines the instance method `name`
+fn defines(class: &JsClass, name: &str) -> bool {
+    class.methods.iter().any(|method| method.kind == MethodKind::Method && method.function.name == name)
+}
+
+fn trait_name(class: &str) -> String {
+    format!("{}Like", class)
+}
+
+/// `HttpClient` → `http_client`, for the field and accessors holding a base class
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    body::rust_ident(&snake)
+}
@@ -0,0 +1,172 @@
+// 🕸️ Package Dependencies - Transmuting Packages After the Ones They Import
+use crate::{body, JsType, Transmutation};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+
+/// A package transmuted before, whose crate sources importing the package use
+/// in its place
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrateDependency {
+    pub npm_name: String,
+    pub crate_name: String,
+    /// The version of the package, and so of its crate
+    pub version: String,
+    pub source: DependencySource,
+    /// The functions at the crate's root by their JS names, typed as callers
+    /// see them
+    pub(crate) functions: HashMap<String, JsType>,
+}
+
+/// Where a crate depending on a transmuted package finds its crate
+#[derive(Debug, Clone, PartialEq)]
+pub enum DependencySource {
+    /// A directory on disk; a workspace member reaches it as its sibling
+    Path(PathBuf),
+    /// crates.io, at the package's version
+    Registry,
+}
+
+impl CrateDependency {
+    /// The crate `transmutation` generated, found at `source`
+    pub fn of(transmutation: &Transmutation, source: DependencySource) -> Self {
+        let functions = syn::parse_file(&transmutation.rust_code).map_or_else(|_| HashMap::new(), |file| {
+            file.items.iter()
+                .filter_map(|item| match item {
+                    syn::Item::Fn(function) if matches!(function.vis, syn::Visibility::Public(_)) => Some(&function.sig),
+                    _ => None,
+                })
+                .map(|sig| (sig.ident.to_string().trim_start_matches("r#").to_string(), signature(sig)))
+                .collect()
+        });
+        Self {
+            npm_name: transmutation.metadata.npm_name.clone(),
+            crate_name: transmutation.metadata.crate_name.clone(),
+            version: transmutation.metadata.version.clone(),
+            source,
+            functions,
+        }
+    }
+
+    /// The crate's name in paths, like `lodash_soul`
+    pub(crate) fn ident(&self) -> String {
+        body::rust_ident(&self.crate_name.replace('-', "_"))
+    }
+
+    /// Its requirement in `[dependencies]`, with a path relative to the
+    /// workspace for a `member`; a path comes with the version, which
+    /// `cargo publish` depends on in its place
+    pub(crate) fn requirement(&self, member: bool) -> String {
+        match &self.source {
+            DependencySource::Path(_) if member => format!("{{ path = \"../{}\", version = \"{}\" }}", self.crate_name, self.version),
+            DependencySource::Path(path) => format!("{{ path = {:?}, version = \"{}\" }}", path.display().to_string(), self.version),
+            DependencySource::Registry => format!("\"{}\"", self.version),
+        }
+    }
+}
+
+/// `packages` in waves to transmute, each depending only on packages of
+/// earlier waves, by the dependencies listed for them; dependencies outside
+/// `packages` are left out
+///
+/// Packages that depend on each other in a cycle, and whatever depends on
+/// them, go together in a last wave, their imports of each other unresolved.
+pub fn waves(packages: &BTreeMap<String, Vec<String>>) -> Vec<Vec<String>> {
+    let mut left: BTreeMap<&str, BTreeSet<&str>> = packages.iter()
+        .map(|(name, dependencies)| {
+            let within = dependencies.iter().map(String::as_str).filter(|dependency| dependency != name && packages.contains_key(*dependency));
+            (name.as_str(), within.collect())
+        })
+        .collect();
+    let mut waves = vec![];
+    while !left.is_empty() {
+        let ready: Vec<&str> = left.iter().filter(|(_, dependencies)| dependencies.is_empty()).map(|(name, _)| *name).collect();
+        if ready.is_empty() {
+            waves.push(left.keys().map(|name| name.to_string()).collect());
+            break;
+        }
+        for name in &ready {
+            left.remove(name);
+        }
+        for dependencies in left.values_mut() {
+            ready.iter().for_each(|name| { dependencies.remove(name); });
+        }
+        waves.push(ready.into_iter().map(str::to_string).collect());
+    }
+    waves
+}
+
+/// How a function of a transmuted crate is called, as its source would have
+/// typed it
+fn signature(sig: &syn::Signature) -> JsType {
+    let params = sig.inputs.iter()
+        .map(|input| match input {
+            syn::FnArg::Typed(param) => js_type(&param.ty),
+            syn::FnArg::Receiver(_) => JsType::Unknown,
+        })
+        .collect();
+    let ret = match &sig.output {
+        syn::ReturnType::Default => JsType::Void,
+        syn::ReturnType::Type(_, ty) => js_type(ty),
+    };
+    let ret = match sig.asyncness {
+        Some(_) => JsType::Promise(Box::new(ret)),
+        None => ret,
+    };
+    JsType::Function(params, Box::new(ret))
+}
+
+/// The JS type a Rust type was generated from; the crate's own types are
+/// out of reach, so unknown
+fn js_type(ty: &syn::Type) -> JsType {
+    let arg = |args: &[&syn::Type], index: usize| Box::new(args.get(index).map_or(JsType::Unknown, |ty| js_type(ty)));
+    match ty {
+        syn::Type::Reference(reference) => js_type(&reference.elem),
+        syn::Type::Tuple(tuple) if tuple.elems.is_empty() => JsType::Void,
+        syn::Type::Tuple(tuple) => JsType::Tuple(tuple.elems.iter().map(js_type).collect()),
+        syn::Type::Path(path) => {
+            let Some(segment) = path.path.segments.last() else { return JsType::Unknown };
+            let args: Vec<&syn::Type> = match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => args.args.iter()
+                    .filter_map(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+            match segment.ident.to_string().as_str() {
+                "f64" | "f32" | "i64" | "i32" | "u32" | "usize" => JsType::Number,
+                "i128" => JsType::BigInt,
+                "String" | "str" => JsType::String,
+                "bool" => JsType::Boolean,
+                "Vec" => JsType::Array(arg(&args, 0)),
+                "Option" => JsType::Optional(arg(&args, 0)),
+                "HashSet" => JsType::Set(arg(&args, 0)),
+                "HashMap" => JsType::Map(arg(&args, 0), arg(&args, 1)),
+                "Result" => JsType::Throws(arg(&args, 0)),
+                _ => JsType::Unknown,
+            }
+        }
+        _ => JsType::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packages_wait_for_their_dependencies() {
+        let packages: BTreeMap<String, Vec<String>> = [
+            ("app", vec!["lodash", "left-pad", "react"]),
+            ("left-pad", vec![]),
+            ("lodash", vec!["left-pad"]),
+            ("ping", vec!["pong"]),
+            ("pong", vec!["ping"]),
+        ].into_iter().map(|(name, dependencies)| (name.to_string(), dependencies.into_iter().map(str::to_string).collect())).collect();
+        assert_eq!(waves(&packages), [vec!["left-pad"], vec!["lodash"], vec!["app"], vec!["ping", "pong"]]);
+
+        let sig: syn::Signature = syn::parse_str("fn pad(text: &str, width: f64) -> Result<String, JsError>").unwrap();
+        assert_eq!(signature(&sig), JsType::Function(vec![JsType::String, JsType::Number], Box::new(JsType::Throws(Box::new(JsType::String)))));
+    }
+}
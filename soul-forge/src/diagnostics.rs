@@ -0,0 +1,142 @@
+// 🩺 Diagnostics - Failures Editors and CI Can Read
+use serde::{Deserialize, Serialize};
+
+/// What went wrong, where, and what might fix it
+///
+/// Every [`crate::TransmutationError`] carries one. `file` and `span` are set
+/// when the failure is in a file: a source that doesn't parse, generated
+/// code that doesn't compile, a manifest that doesn't read.
+/// A [`crate::Transmutation`]'s `warnings` are ones too, of code it generated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<String>,
+    pub span: Option<Span>,
+    /// A change that would likely get past the failure
+    pub suggestion: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A byte range of a file, and the 1-based line and column it starts at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    /// The span of bytes `start..end` of `code`, columns counted in characters
+    pub fn of(code: &str, start: usize, end: usize) -> Self {
+        let start = start.min(code.len());
+        let before = code.get(..start).unwrap_or(code);
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        Self {
+            start,
+            end: end.max(start),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Error, message: message.into(), file: None, span: None, suggestion: None }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, ..Self::error(message) }
+    }
+
+    pub fn with_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+}
+
+impl From<String> for Diagnostic {
+    fn from(message: String) -> Self {
+        Self::error(message)
+    }
+}
+
+impl From<&str> for Diagnostic {
+    fn from(message: &str) -> Self {
+        Self::error(message)
+    }
+}
+
+/// `file:line:column: message`, as compilers print them, with the
+/// suggestion on a `help:` line
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.file, &self.span) {
+            (Some(file), Some(span)) => write!(f, "{}:{}:{}: ", file, span.line, span.column)?,
+            (Some(file), None) => write!(f, "{}: ", file)?,
+            (None, Some(span)) => write!(f, "{}:{}: ", span.line, span.column)?,
+            (None, None) => {}
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n  help: {}", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// How the CLI prints a failure
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// `Error: ` and the diagnostic, as compilers print them
+    #[default]
+    Human,
+    /// One JSON object per failure on stderr, the diagnostic with its `kind`
+    Json,
+}
+
+impl std::str::FromStr for ErrorFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            other => Err(format!("unknown error format '{}' (expected human or json)", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spans_count_lines_and_characters() {
+        let code = "let a = 1;\nlet é = 2 +;\n";
+        let span = Span::of(code, code.find('+').unwrap(), code.find('+').unwrap() + 1);
+        assert_eq!((span.line, span.column), (2, 11));
+
+        let diagnostic = Diagnostic::error("Unexpected token").with_file("index.js").with_span(span).with_suggestion("finish the expression");
+        assert_eq!(diagnostic.to_string(), "index.js:2:11: Unexpected token\n  help: finish the expression");
+        assert_eq!(serde_json::to_value(&diagnostic).unwrap()["severity"], "error");
+    }
+}
@@ -0,0 +1,248 @@
+// 🌀 Soul Drift - Generated Constructs That Pull the Rust Soul Away
+use crate::refine::offset;
+use crate::{Diagnostic, Span};
+use quote::ToTokens;
+use std::collections::{BTreeMap, BTreeSet};
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+/// Blocks nested inside one another past which a body is deeper than JS
+/// sources tend to be, and its soul heavier
+const MAX_NESTING: usize = 4;
+
+/// Warnings, in the order they're found, of the constructs in `rust_code`
+/// known to distort its resonance with the source, each suggesting the
+/// rewrite that keeps the soul; code syn can't parse has none
+///
+/// - A boxed parameter, return or binding: `Box<dyn Fn(..)>` where
+///   `impl Fn(..)` would do, or a `Box<T>` where the `T` would
+/// - A function nesting blocks more than [`MAX_NESTING`] deep
+/// - A stringly-typed map, `HashMap<String, Value>` standing in for a
+///   struct, or a JSON value read by two or more field names
+pub(crate) fn lint(rust_code: &str) -> Vec<Diagnostic> {
+    let Ok(file) = syn::parse_file(rust_code) else { return vec![] };
+    let mut drift = Drift { code: rust_code, warnings: vec![], function: None, depth: 0, deepest: 0, values: BTreeSet::new(), fields: BTreeMap::new() };
+    drift.visit_file(&file);
+    drift.warnings
+}
+
+struct Drift<'c> {
+    code: &'c str,
+    warnings: Vec<Diagnostic>,
+    /// The function being walked
+    function: Option<String>,
+    depth: usize,
+    deepest: usize,
+    /// The function's parameters taken as JSON values
+    values: BTreeSet<String>,
+    /// The field names each value is read by in the function, literally or
+    /// as a JSON value's fields, with where it was first read
+    fields: BTreeMap<String, (proc_macro2::Span, BTreeSet<String>)>,
+}
+
+impl Drift<'_> {
+    fn warn(&mut self, at: proc_macro2::Span, message: String, suggestion: String) {
+        let span = Span::of(self.code, offset(self.code, at.start()), offset(self.code, at.end()));
+        self.warnings.push(Diagnostic::warning(message).with_file("src/lib.rs").with_span(span).with_suggestion(suggestion));
+    }
+
+    /// Walk a function's signature and body, then warn of how deep it went
+    /// and of the values it read fields of by name
+    fn function(&mut self, sig: &syn::Signature, walk: impl FnOnce(&mut Self)) {
+        let outer = (self.function.replace(sig.ident.to_string()), self.depth, self.deepest, std::mem::take(&mut self.values), std::mem::take(&mut self.fields));
+        (self.depth, self.deepest) = (0, 0);
+        for input in &sig.inputs {
+            if let syn::FnArg::Typed(param) = input {
+                self.boxed(&param.ty, "take");
+                let ty = match &*param.ty {
+                    syn::Type::Reference(reference) => &*reference.elem,
+                    ty => ty,
+                };
+                if let (syn::Pat::Ident(name), Some("Value")) = (&*param.pat, last_segment(ty).as_deref()) {
+                    self.values.insert(name.ident.to_string());
+                }
+            }
+        }
+        if let syn::ReturnType::Type(_, ty) = &sig.output {
+            self.boxed(ty, "return");
+        }
+        walk(self);
+
+        if self.deepest > MAX_NESTING {
+            self.warn(sig.ident.span(), format!("`{}` nests blocks {} deep", sig.ident, self.deepest), "return early, or move the innermost blocks into a helper".to_string());
+        }
+        for (value, (at, fields)) in std::mem::take(&mut self.fields) {
+            if fields.len() >= 2 {
+                let fields: Vec<String> = fields.into_iter().collect();
+                self.warn(at, format!("`{}` is read by field names as a map", value), format!("deserialize it into a struct with fields {}", fields.join(", ")));
+            }
+        }
+        (self.function, self.depth, self.deepest, self.values, self.fields) = outer;
+    }
+
+    /// Warn of a `Box` in `ty`, which a signature would `verb` or a binding hold
+    fn boxed(&mut self, ty: &syn::Type, verb: &str) {
+        let Some(inner) = generic(ty, "Box").and_then(|args| args.first().copied()) else { return };
+        match inner {
+            syn::Type::TraitObject(object) => {
+                let bounds = tokens(&object.bounds);
+                self.warn(ty.span(), format!("`Box<dyn {}>` allocates where a generic would be called directly", bounds), format!("{} `impl {}`", verb, bounds));
+            }
+            inner => {
+                let inner = tokens(inner);
+                self.warn(ty.span(), format!("`Box<{}>` boxes a value that needs no indirection", inner), format!("{} `{}` itself", verb, inner));
+            }
+        }
+    }
+
+    /// Note `value` is read by the field name `field`
+    fn read(&mut self, value: String, field: String, at: proc_macro2::Span) {
+        self.fields.entry(value).or_insert_with(|| (at, BTreeSet::new())).1.insert(field);
+    }
+
+    /// Walk an `if` and the `else if`s after it, which nest no deeper
+    fn chain(&mut self, chain: &syn::ExprIf) {
+        self.visit_expr(&chain.cond);
+        self.visit_block(&chain.then_branch);
+        match chain.else_branch.as_ref().map(|(_, branch)| &**branch) {
+            Some(syn::Expr::If(next)) => self.chain(next),
+            Some(branch) => self.visit_expr(branch),
+            None => {}
+        }
+    }
+
+    /// Enter a nested block for the walk `walk`
+    fn nested(&mut self, walk: impl FnOnce(&mut Self)) {
+        self.depth += 1;
+        self.deepest = self.deepest.max(self.depth);
+        walk(self);
+        self.depth -= 1;
+    }
+}
+
+impl<'ast> Visit<'ast> for Drift<'_> {
+    fn visit_item_fn(&mut self, function: &'ast syn::ItemFn) {
+        self.function(&function.sig, |drift| visit::visit_item_fn(drift, function));
+    }
+
+    fn visit_impl_item_fn(&mut self, function: &'ast syn::ImplItemFn) {
+        self.function(&function.sig, |drift| visit::visit_impl_item_fn(drift, function));
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        if let syn::Pat::Type(typed) = &local.pat {
+            self.boxed(&typed.ty, "bind");
+        }
+        visit::visit_local(self, local);
+    }
+
+    fn visit_type(&mut self, ty: &'ast syn::Type) {
+        let value = generic(ty, "HashMap").or_else(|| generic(ty, "BTreeMap")).or_else(|| generic(ty, "Map"));
+        if let Some([key, value]) = value.as_deref() {
+            if last_segment(key).as_deref() == Some("String") && last_segment(value).as_deref() == Some("Value") {
+                self.warn(ty.span(), "a map of JSON values by string keys stands in for a struct".to_string(), "declare a struct with the fields the source's object has".to_string());
+            }
+        }
+        visit::visit_type(self, ty);
+    }
+
+    fn visit_expr(&mut self, expr: &'ast syn::Expr) {
+        match expr {
+            syn::Expr::If(chain) => self.nested(|drift| drift.chain(chain)),
+            syn::Expr::Match(_) | syn::Expr::ForLoop(_) | syn::Expr::While(_) | syn::Expr::Loop(_) | syn::Expr::Closure(_) => {
+                self.nested(|drift| visit::visit_expr(drift, expr));
+            }
+            syn::Expr::Index(index) => {
+                if let (Some(_), syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(field), .. })) = (&self.function, &*index.index) {
+                    self.read(tokens(&index.expr), field.value(), index.span());
+                }
+                visit::visit_expr(self, expr);
+            }
+            syn::Expr::Field(field) => {
+                if let (syn::Expr::Path(base), syn::Member::Named(name)) = (&*field.base, &field.member) {
+                    let base = tokens(base);
+                    if self.values.contains(&base) {
+                        self.read(base, name.to_string(), field.span());
+                    }
+                }
+                visit::visit_expr(self, expr);
+            }
+            _ => visit::visit_expr(self, expr),
+        }
+    }
+}
+
+/// The type arguments of `ty` when it's the generic `name`
+fn generic<'t>(ty: &'t syn::Type, name: &str) -> Option<Vec<&'t syn::Type>> {
+    let syn::Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last().filter(|segment| segment.ident == name)?;
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    Some(args.args.iter().filter_map(|arg| match arg {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }).collect())
+}
+
+/// `node` as it would be written, not as tokens are spaced
+fn tokens(node: &impl ToTokens) -> String {
+    let spaced = node.to_token_stream().to_string();
+    [(" (", "("), (" <", "<"), ("< ", "<"), (" >", ">"), (" ,", ","), ("& ", "&"), (" :: ", "::"), (" . ", ".")]
+        .iter()
+        .fold(spaced, |code, (from, to)| code.replace(from, to))
+}
+
+fn last_segment(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(path) => path.path.segments.last().map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drifting_constructs_are_flagged_with_rewrites() {
+        let code = r#"pub fn apply(f: Box<dyn Fn(f64) -> f64>, x: f64) -> f64 {
+    let scale: Box<f64> = Box::new(2.0);
+    f(x) * *scale
+}
+
+pub fn area(shape: &HashMap<String, Value>) -> f64 {
+    shape["width"].as_f64().unwrap_or(0.0) * shape["height"].as_f64().unwrap_or(0.0)
+}
+
+pub fn label(point: Value) -> String {
+    point.x.to_string() + &point.y.to_string()
+}
+
+pub fn deep(xs: &[f64]) -> f64 {
+    for x in xs {
+        if *x > 0.0 {
+            for y in xs {
+                if *y > *x {
+                    if *y > 1.0 {
+                        return *y;
+                    }
+                }
+            }
+        }
+    }
+    0.0
+}
+"#;
+        let warnings = lint(code);
+        let suggestions: Vec<&str> = warnings.iter().filter_map(|warning| warning.suggestion.as_deref()).collect();
+        assert_eq!(suggestions, [
+            "take `impl Fn(f64) -> f64`",
+            "bind `f64` itself",
+            "declare a struct with the fields the source's object has",
+            "deserialize it into a struct with fields height, width",
+            "deserialize it into a struct with fields x, y",
+            "return early, or move the innermost blocks into a helper",
+        ]);
+        assert_eq!(warnings[0].span.map(|span| (span.line, span.column)), Some((1, 17)));
+        assert!(lint("pub fn id(x: f64) -> f64 { x }").is_empty());
+    }
+}
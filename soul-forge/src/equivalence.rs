@@ -0,0 +1,333 @@
+// ⚖️ Behavioral Equivalence - Same Inputs, Same Outputs
+use crate::modules::{self, JsModule};
+use crate::{RustFunction, TransmutationError};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Cases generated per function when no fixtures are given
+const GENERATED_CASES: usize = 4;
+
+/// A call both the source and the transmuted crate make
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub function: String,
+    pub args: Vec<Value>,
+}
+
+/// Which calls a transmutation is compared with its source on
+#[derive(Debug, Clone, Default)]
+pub(crate) enum Equivalence {
+    #[default]
+    Off,
+    /// Cases generated from each function's parameter types
+    Generated,
+    /// The given calls, to the functions the harness can make them to
+    Fixtures(Vec<Fixture>),
+}
+
+/// A function the harness can call with arguments read from JSON
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Subject {
+    name: String,
+    pub(crate) params: Vec<String>,
+    /// Returns `Result<_, JsError>`, which is compared with what JS throws
+    fallible: bool,
+}
+
+impl Subject {
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `function` as a subject, unless it is async, generic or takes closures,
+    /// which no JSON value stands in for
+    pub(crate) fn of(function: &RustFunction) -> Option<Self> {
+        if function.is_async || !function.generics.is_empty() || function.receiver.is_some() || function.return_type.contains("impl ") {
+            return None;
+        }
+        let params: Vec<String> = function.params.iter().map(|param| param.ty.clone()).collect();
+        if params.iter().any(|ty| samples(ty).is_none()) {
+            return None;
+        }
+        Some(Self {
+            name: function.name.clone(),
+            params,
+            fallible: function.return_type.starts_with("Result<") && function.return_type.ends_with(", JsError>"),
+        })
+    }
+}
+
+/// Calls to each subject, spreading each parameter's samples across them
+pub(crate) fn generate(subjects: &[Subject]) -> Vec<Fixture> {
+    let mut fixtures = vec![];
+    for subject in subjects {
+        let samples: Vec<Vec<Value>> = subject.params.iter().filter_map(|ty| samples(ty)).collect();
+        for case in 0..GENERATED_CASES {
+            // Offsetting by position pairs different samples up in each case
+            let args: Vec<Value> = samples.iter().enumerate().map(|(i, pool)| pool[(case + i) % pool.len()].clone()).collect();
+            let fixture = Fixture { function: subject.name.clone(), args };
+            if !fixtures.contains(&fixture) {
+                fixtures.push(fixture);
+            }
+        }
+    }
+    fixtures
+}
+
+/// JSON values of the Rust type `ty`, or `None` for types JSON can't give
+fn samples(ty: &str) -> Option<Vec<Value>> {
+    let ty = ty.trim();
+    let inner = |prefix: &str| ty.strip_prefix(prefix).and_then(|rest| rest.strip_suffix('>'));
+    let values = match ty {
+        "f64" => vec![json!(0), json!(1), json!(-2.5), json!(42)],
+        "i64" | "usize" => vec![json!(0), json!(1), json!(7), json!(42)],
+        "String" => vec![json!(""), json!("soul"), json!("Hello, World"), json!("ünïcode")],
+        "bool" => vec![json!(true), json!(false)],
+        "Value" | "serde_json::Value" => vec![json!(0), json!("soul"), json!(true), Value::Null, json!([1, 2])],
+        _ => {
+            if let Some(element) = inner("Vec<") {
+                let element = samples(element)?;
+                let at = |i: usize| element[i % element.len()].clone();
+                vec![json!([]), json!([at(0)]), json!([at(1), at(2), at(3)])]
+            } else if let Some(value) = inner("Option<") {
+                let value = samples(value)?;
+                vec![Value::Null, value[0].clone(), value[1 % value.len()].clone()]
+            } else if let Some(value) = inner("HashMap<String, ") {
+                let value = samples(value)?;
+                vec![json!({}), json!({ "a": value[0], "b": value[1 % value.len()] })]
+            } else {
+                return None;
+            }
+        }
+    };
+    Some(values)
+}
+
+/// Scripts run so far, naming each run's files apart
+static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+/// What the source returns or throws on each fixture, run under Node
+///
+/// Each outcome is `{"ok": value}` or `{"error": message}`. The root module
+/// runs with the others laid out around it as in the package. TypeScript
+/// runs with `--experimental-strip-types`, so it needs a Node that has it.
+pub(crate) async fn run_js(
+    modules: &[JsModule],
+    commonjs: bool,
+    subjects: &[Subject],
+    fixtures: &[Fixture],
+) -> Result<Vec<Value>, TransmutationError> {
+    let id = format!("{}-{}", std::process::id(), RUNS.fetch_add(1, Ordering::Relaxed));
+    let dir = std::env::temp_dir().join("soul-forge-equivalence").join(id);
+    let outcomes = run_in(&dir, modules, commonjs, subjects, fixtures).await;
+    let _ = std::fs::remove_dir_all(&dir);
+    serde_json::from_str(&outcomes?).map_err(|e| TransmutationError::EquivalenceError(e.to_string().into()))
+}
+
+async fn run_in(
+    dir: &Path,
+    modules: &[JsModule],
+    commonjs: bool,
+    subjects: &[Subject],
+    fixtures: &[Fixture],
+) -> Result<String, TransmutationError> {
+    let Some((root, imported)) = modules.split_first() else { return Ok("[]".to_string()) };
+    std::fs::create_dir_all(dir).map_err(io_error)?;
+    for module in imported {
+        let path = dir.join(&module.path);
+        std::fs::create_dir_all(path.parent().unwrap_or(dir)).map_err(io_error)?;
+        std::fs::write(&path, &module.code).map_err(io_error)?;
+    }
+    if !imported.is_empty() {
+        let manifest = json!({ "type": if commonjs { "commonjs" } else { "module" } });
+        std::fs::write(dir.join("package.json"), manifest.to_string()).map_err(io_error)?;
+    }
+    let script = dir
+        .join(root.path.parent().unwrap_or(Path::new("")))
+        .join(format!("forge-run.{}", if root.kind.is_typescript() { "mts" } else { "mjs" }));
+    std::fs::create_dir_all(script.parent().unwrap_or(dir)).map_err(io_error)?;
+    let functions: Vec<&str> = subjects.iter().map(|subject| subject.name.as_str()).collect();
+    let (code, functions) = modules::for_node(&root.code, commonjs, &functions);
+    let results = dir.join("outcomes.json");
+    std::fs::write(&script, format!("{}\n{}", code, js_trailer(&functions, fixtures))).map_err(io_error)?;
+
+    let mut node = tokio::process::Command::new("node");
+    if root.kind.is_typescript() {
+        node.arg("--experimental-strip-types");
+    }
+    let output = node
+        .arg(&script)
+        .arg(&results)
+        .output()
+        .await
+        .map_err(|e| TransmutationError::EquivalenceError(format!("can't run node: {}", e).into()))?;
+    match output.status.success() {
+        true => std::fs::read_to_string(&results).map_err(io_error),
+        false => Err(TransmutationError::EquivalenceError(String::from_utf8_lossy(&output.stderr).trim().to_string().into())),
+    }
+}
+
+fn io_error(error: std::io::Error) -> TransmutationError {
+    TransmutationError::EquivalenceError(error.to_string().into())
+}
+
+/// Module code calling each fixture's function, from the table `functions`
+/// lists, and writing the outcomes to the file named by the first argument
+fn js_trailer(functions: &str, fixtures: &[Fixture]) -> String {
+    let cases: Vec<Value> = fixtures.iter().map(|fixture| json!([fixture.function, fixture.args])).collect();
+    format!(
+        r#"import {{ writeFileSync as __forgeWrite }} from "node:fs";
+const __forgeFunctions = {{ {} }};
+const __forgeOutcomes = [];
+for (const [name, args] of {}) {{
+  try {{
+    const value = __forgeFunctions[name](...args);
+    __forgeOutcomes.push({{ ok: value === undefined ? null : value }});
+  }} catch (error) {{
+    __forgeOutcomes.push({{ error: String(error?.message ?? error) }});
+  }}
+}}
+__forgeWrite(process.argv[2], JSON.stringify(__forgeOutcomes));
+"#,
+        functions,
+        Value::Array(cases),
+    )
+}
+
+/// `tests/equivalence.rs` for the crate `crate_name`: one test per fixture,
+/// asserting the Rust function returns or throws what the source did
+pub(crate) fn harness(crate_name: &str, subjects: &[Subject], fixtures: &[Fixture], outcomes: &[Value]) -> String {
+    // A case a line, so a failing test's case is easy to find
+    let cases: Vec<String> = fixtures
+        .iter()
+        .zip(outcomes)
+        .map(|(fixture, expected)| json!({ "function": fixture.function, "args": fixture.args, "expected": expected }).to_string())
+        .collect();
+    let cases = format!("[\n    {}\n]", cases.join(",\n    "));
+
+    let arms: Vec<String> = subjects
+        .iter()
+        .map(|subject| {
+            let args: Vec<String> = (0..subject.params.len()).map(|i| format!("arg(args, {})", i)).collect();
+            let call = format!("{}({})", subject.name, args.join(", "));
+            let outcome = match subject.fallible {
+                true => format!(
+                    "match {} {{\n            Ok(value) => json!({{ \"ok\": value }}),\n            Err(error) => json!({{ \"error\": error.message() }}),\n        }}",
+                    call
+                ),
+                false => format!("json!({{ \"ok\": {} }})", call),
+            };
+            format!("        {:?} => {},", subject.name, outcome)
+        })
+        .collect();
+
+    let tests: Vec<String> = fixtures
+        .iter()
+        .enumerate()
+        .map(|(i, fixture)| format!("#[test]\nfn {}_{}() {{\n    check({});\n}}", fixture.function, i, i))
+        .collect();
+
+    format!(
+        r####"//! Behavioral equivalence with the JavaScript source: each case holds the
+//! arguments of a call and what the source returned or threw on them
+#![allow(non_snake_case, unused_imports)]
+use {}::*;
+use serde::de::DeserializeOwned;
+use serde_json::{{json, Value}};
+
+const CASES: &str = r###"{}"###;
+
+fn arg<T: DeserializeOwned>(args: &[Value], i: usize) -> T {{
+    serde_json::from_value(args.get(i).cloned().unwrap_or(Value::Null)).expect("argument of the parameter's type")
+}}
+
+fn run(function: &str, args: &[Value]) -> Value {{
+    match function {{
+{}
+        other => panic!("no function {{}}", other),
+    }}
+}}
+
+/// JSON equality, with numbers equal by value, as JS has only one kind
+fn same(a: &Value, b: &Value) -> bool {{
+    match (a, b) {{
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b)),
+        (Value::Object(a), Value::Object(b)) => a.len() == b.len() && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| same(a, b))),
+        _ => a == b,
+    }}
+}}
+
+fn check(case: usize) {{
+    let cases: Vec<Value> = serde_json::from_str(CASES).unwrap();
+    let case = &cases[case];
+    let args = case["args"].as_array().cloned().unwrap_or_default();
+    let actual = run(case["function"].as_str().unwrap(), &args);
+    assert!(same(&actual, &case["expected"]), "{{}}({{:?}}): expected {{}}, got {{}}", case["function"], args, case["expected"], actual);
+}}
+
+{}
+"####,
+        crate_name.replace('-', "_"),
+        cases,
+        arms.join("\n"),
+        tests.join("\n\n"),
+    )
+}
+
+/// Passed tests in `cargo test` output, summed over its result lines
+pub(crate) fn passed(stdout: &str) -> usize {
+    stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("test result: ")?.split("; ").find_map(|part| part.split_once(" passed")))
+        .filter_map(|(count, _)| count.rsplit(' ').next()?.parse::<usize>().ok())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn function(name: &str, params: &[&str], return_type: &str) -> RustFunction {
+        RustFunction {
+            name: name.to_string(),
+            generics: vec![],
+            receiver: None,
+            params: params.iter().map(|ty| crate::RustParam { name: "x".to_string(), ty: ty.to_string() }).collect(),
+            return_type: return_type.to_string(),
+            body: String::new(),
+            is_async: false,
+            is_pub: true,
+            origin: None,
+        }
+    }
+
+    #[test]
+    fn test_fixtures_come_from_parameter_types() {
+        let scale = Subject::of(&function("scale", &["Vec<f64>", "f64"], "Vec<f64>")).unwrap();
+        assert!(Subject::of(&function("apply", &["impl Fn(f64) -> f64"], "f64")).is_none());
+        assert!(Subject::of(&function("area", &["Point"], "f64")).is_none());
+
+        let fixtures = generate(&[scale]);
+        assert_eq!(fixtures.len(), 4);
+        assert_eq!(fixtures[0].args, [json!([]), json!(1)]);
+        assert_eq!(fixtures[2].args, [json!([1, -2.5, 42]), json!(42)]);
+    }
+
+    #[test]
+    fn test_harness_calls_each_case() {
+        let parse = Subject::of(&function("parse", &["String"], "Result<f64, JsError>")).unwrap();
+        let fixtures = [Fixture { function: "parse".to_string(), args: vec![json!("")] }];
+        let harness = harness("chunk-soul", &[parse], &fixtures, &[json!({ "error": "empty" })]);
+
+        assert!(harness.contains("use chunk_soul::*;"));
+        assert!(harness.contains("\"parse\" => match parse(arg(args, 0)) {\n            Ok(value) => json!({ \"ok\": value }),"));
+        assert!(harness.contains("#[test]\nfn parse_0() {\n    check(0);\n}"));
+        assert!(harness.contains(r#"{"args":[""],"expected":{"error":"empty"},"function":"parse"}"#));
+
+        let stdout = "running 3 tests\ntest result: FAILED. 2 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out\n";
+        assert_eq!(passed(stdout), 2);
+    }
+}
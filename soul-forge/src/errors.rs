@@ -0,0 +1,208 @@
+// ⚡ Error Transmutation - Exceptions into Results
+use crate::body;
+use crate::{JsAst, JsClass, JsExpr, JsFunction, JsStmt, JsType, MethodKind};
+use std::collections::{BTreeSet, HashSet};
+
+/// What in a module can throw, and so returns a `Result` its callers propagate
+///
+/// A function throws when its body has a `throw`, or a call to something
+/// that throws, outside any `try` with a `catch`. Methods go by name, like
+/// the receivers of [`crate::classes`], so an override and the method it
+/// overrides agree on their signature. Async functions reject instead, and
+/// getters and setters keep their plain signatures and panic.
+#[derive(Debug, Default)]
+pub(crate) struct Throwing {
+    functions: HashSet<String>,
+    methods: HashSet<String>,
+    /// Classes by name
+    constructors: HashSet<String>,
+}
+
+impl Throwing {
+    pub(crate) fn of(ast: &JsAst) -> Self {
+        let mut throwing = Self::default();
+        loop {
+            let mut found = Self::default();
+            for function in ast.functions.iter().filter(|function| !function.is_async) {
+                if !throwing.functions.contains(&function.name) && body_throws(&function.body, &throwing) {
+                    found.functions.insert(function.name.clone());
+                }
+            }
+            for class in &ast.classes {
+                // `super(..)` runs the base constructor, declared or not
+                let base_throws = class.extends.as_ref().is_some_and(|base| throwing.constructors.contains(base));
+                if base_throws && !throwing.constructors.contains(&class.name) {
+                    found.constructors.insert(class.name.clone());
+                }
+                for method in class.methods.iter().filter(|method| !method.function.is_async) {
+                    let (known, set) = match method.kind {
+                        MethodKind::Constructor => (&throwing.constructors, &mut found.constructors),
+                        MethodKind::Method | MethodKind::Static => (&throwing.methods, &mut found.methods),
+                        MethodKind::Getter | MethodKind::Setter => continue,
+                    };
+                    let name = match method.kind {
+                        MethodKind::Constructor => &class.name,
+                        _ => &method.function.name,
+                    };
+                    if !known.contains(name) && body_throws(&method.function.body, &throwing) {
+                        set.insert(name.clone());
+                    }
+                }
+            }
+
+            if found.functions.is_empty() && found.methods.is_empty() && found.constructors.is_empty() {
+                return throwing;
+            }
+            throwing.functions.extend(found.functions);
+            throwing.methods.extend(found.methods);
+            throwing.constructors.extend(found.constructors);
+        }
+    }
+
+    /// Wrap the return type of everything that throws in [`JsType::Throws`]
+    pub(crate) fn mark(&self, ast: &mut JsAst) {
+        for function in &mut ast.functions {
+            if self.functions.contains(&function.name) {
+                throws(function);
+            }
+        }
+        for class in &mut ast.classes {
+            for method in &mut class.methods {
+                let marked = match method.kind {
+                    MethodKind::Constructor => self.constructors.contains(&class.name),
+                    MethodKind::Method | MethodKind::Static => self.methods.contains(&method.function.name),
+                    MethodKind::Getter | MethodKind::Setter => false,
+                };
+                if marked && !method.function.is_async {
+                    throws(&mut method.function);
+                }
+            }
+        }
+    }
+
+    /// Whether constructing `class` can throw
+    pub(crate) fn constructs(&self, class: &str) -> bool {
+        self.constructors.contains(class)
+    }
+
+    /// Whether evaluating `expr` itself, a call or `new`, can throw
+    fn throws(&self, expr: &JsExpr) -> bool {
+        match expr {
+            JsExpr::Call(callee, _) => match &**callee {
+                JsExpr::Ident(name) => self.functions.contains(name),
+                JsExpr::Member(object, method) => match &**object {
+                    JsExpr::This | JsExpr::Super => self.methods.contains(method),
+                    JsExpr::Ident(namespace) if namespace == "JSON" => method == "parse",
+                    // Static methods
+                    JsExpr::Ident(namespace) if namespace.starts_with(char::is_uppercase) => self.methods.contains(method),
+                    _ => false,
+                },
+                _ => false,
+            },
+            JsExpr::New(callee, _) => matches!(&**callee, JsExpr::Ident(name) if self.constructors.contains(name)),
+            _ => false,
+        }
+    }
+}
+
+fn throws(function: &mut JsFunction) {
+    let ret = std::mem::replace(&mut function.return_type, JsType::Unknown);
+    function.return_type = JsType::Throws(Box::new(ret));
+}
+
+/// Whether `class` is an error class, which becomes a `JsError` variant
+/// rather than a struct
+pub(crate) fn is_error_class(class: &JsClass) -> bool {
+    class.extends.as_deref().is_some_and(|base| base.ends_with("Error"))
+}
+
+fn body_throws(body: &[JsStmt], throwing: &Throwing) -> bool {
+    body.iter().any(|stmt| stmt_throws(stmt, throwing))
+}
+
+fn stmt_throws(stmt: &JsStmt, throwing: &Throwing) -> bool {
+    let (exprs, blocks): (Vec<&JsExpr>, Vec<&[JsStmt]>) = match stmt {
+        JsStmt::Throw(_) => return true,
+        JsStmt::Let { init, .. } => (init.iter().collect(), vec![]),
+        JsStmt::Expr(expr) | JsStmt::Return(Some(expr)) => (vec![expr], vec![]),
+        JsStmt::If { test, then, otherwise } => (vec![test], vec![then, otherwise]),
+        JsStmt::While { test, body } | JsStmt::DoWhile { body, test } => (vec![test], vec![body]),
+        JsStmt::For { init, test, update, body } => (test.iter().chain(update).collect(), vec![init, body]),
+        JsStmt::ForOf { iterable: expr, body, .. } | JsStmt::ForIn { object: expr, body, .. } => (vec![expr], vec![body]),
+        JsStmt::Switch { discriminant, cases } => (
+            std::iter::once(discriminant).chain(cases.iter().filter_map(|case| case.test.as_ref())).collect(),
+            cases.iter().map(|case| case.body.as_slice()).collect(),
+        ),
+        JsStmt::Block(body) => (vec![], vec![body]),
+        JsStmt::Labeled(_, body) => return stmt_throws(body, throwing),
+        // What the block throws, the handler catches
+        JsStmt::Try { block, handler, finalizer, .. } => match handler {
+            Some(handler) => (vec![], vec![handler, finalizer]),
+            None => (vec![], vec![block, finalizer]),
+        },
+        _ => (vec![], vec![]),
+    };
+    exprs.into_iter().any(|expr| expr_throws(expr, throwing)) || blocks.into_iter().any(|body| body_throws(body, throwing))
+}
+
+/// Closures throw when they are called, which is for their caller to say
+fn expr_throws(expr: &JsExpr, throwing: &Throwing) -> bool {
+    let mut throws = false;
+    body::each_expr(expr, &mut |expr| {
+        throws |= throwing.throws(expr);
+        !matches!(expr, JsExpr::Function(_))
+    });
+    throws
+}
+
+/// The module's `JsError` enum, with a variant for each error class the
+/// generated `items` construct
+pub(crate) fn error_glue(items: &str) -> String {
+    let mut variants = BTreeSet::from(["Error".to_string()]);
+    for (i, _) in items.match_indices("JsError::") {
+        let name: String = items[i + "JsError::".len()..].chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+        if name.starts_with(char::is_uppercase) {
+            variants.insert(name);
+        }
+    }
+
+    let declared: String = variants.iter().map(|name| format!("    {}(String),\n", name)).collect();
+    let names: String = variants.iter().map(|name| format!("            Self::{}(_) => {:?},\n", name, name)).collect();
+    let messages = variants.iter().map(|name| format!("Self::{}(message)", name)).collect::<Vec<_>>().join(" | ");
+    format!(
+        r#"/// A thrown JS error or rejected promise, by the error class thrown
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsError {{
+{declared}}}
+
+impl JsError {{
+    /// `new Error(message)`
+    pub fn new(message: impl std::fmt::Display) -> Self {{
+        Self::Error(message.to_string())
+    }}
+
+    /// JS `error.name`
+    pub fn name(&self) -> &'static str {{
+        match self {{
+{names}        }}
+    }}
+
+    /// JS `error.message`
+    pub fn message(&self) -> &str {{
+        match self {{
+            {messages} => message,
+        }}
+    }}
+}}
+
+impl std::fmt::Display for JsError {{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{
+        write!(f, "{{}}: {{}}", self.name(), self.message())
+    }}
+}}
+
+impl std::error::Error for JsError {{}}
+
+"#
+    )
+}
@@ -0,0 +1,114 @@
+// 🔗 C Bindings - Handing Transmuted Crates to C Through extern "C"
+use crate::RustFunction;
+
+/// An exported function C can call through an `extern "C"` wrapper
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Export {
+    name: String,
+    params: Vec<(String, String)>,
+    return_type: String,
+}
+
+impl Export {
+    /// `function` as an export, if the source exports it and its parameters
+    /// and result are C scalars; strings, arrays, objects and anything
+    /// fallible are left to the Rust side
+    pub(crate) fn of(function: &RustFunction, exports: &[String]) -> Option<Self> {
+        let export = Self {
+            name: function.name.clone(),
+            params: function.params.iter().map(|param| (param.name.clone(), param.ty.clone())).collect(),
+            return_type: function.return_type.clone(),
+        };
+        if function.is_async || !function.generics.is_empty() || function.receiver.is_some() || !exports.iter().any(|name| name == export.js_name()) {
+            return None;
+        }
+        let params_cross = export.params.iter().all(|(_, ty)| c_type(ty).is_some_and(|ty| ty != "void"));
+        (params_cross && c_type(&export.return_type).is_some()).then_some(export)
+    }
+
+    /// The name the source exported it as
+    pub(crate) fn js_name(&self) -> &str {
+        self.name.trim_start_matches("r#")
+    }
+
+    /// The symbol C links against, prefixed with the crate's name so crates
+    /// linked together don't collide
+    fn symbol(&self, crate_name: &str) -> String {
+        format!("{}_{}", crate_name.replace('-', "_"), self.js_name())
+    }
+
+    /// Its declaration in the crate's header
+    fn declaration(&self, crate_name: &str) -> String {
+        let params = match self.params.as_slice() {
+            [] => "void".to_string(),
+            params => params.iter()
+                .map(|(name, ty)| format!("{} {}", c_type(ty).unwrap_or("void"), name.trim_start_matches("r#")))
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        format!("{} {}({});", c_type(&self.return_type).unwrap_or("void"), self.symbol(crate_name), params)
+    }
+}
+
+/// The C type a Rust scalar crosses as, by value
+fn c_type(ty: &str) -> Option<&'static str> {
+    match ty.trim() {
+        "()" => Some("void"),
+        "f64" => Some("double"),
+        "i64" => Some("int64_t"),
+        "bool" => Some("bool"),
+        _ => None,
+    }
+}
+
+/// An `ffi` module of `extern "C"` wrappers, one an export, appended to the
+/// crate; empty when nothing crosses
+pub(crate) fn glue(crate_name: &str, exports: &[Export]) -> String {
+    if exports.is_empty() {
+        return String::new();
+    }
+    // Deprecated exports are still exports
+    let mut code = String::from("/// The exports C links against, as the crate's header declares them\n#[allow(deprecated)]\nmod ffi {\n");
+    for export in exports {
+        let params = export.params.iter().map(|(name, ty)| format!("{}: {}", name, ty)).collect::<Vec<_>>().join(", ");
+        let args = export.params.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(", ");
+        let return_type = match export.return_type.as_str() {
+            "()" => String::new(),
+            ty => format!(" -> {}", ty),
+        };
+        code.push_str(&format!(
+            "\n    #[no_mangle]\n    pub extern \"C\" fn {}({}){} {{\n        super::{}({})\n    }}\n",
+            export.symbol(crate_name), params, return_type, export.name, args
+        ));
+    }
+    code.push_str("}\n\n");
+    code
+}
+
+/// The header's declarations of `exports`, one a line
+pub(crate) fn declarations(crate_name: &str, exports: &[Export]) -> Vec<String> {
+    exports.iter().map(|export| export.declaration(crate_name)).collect()
+}
+
+/// The file the header is written to, named for the crate
+pub(crate) fn header_file(crate_name: &str) -> String {
+    format!("{}.h", crate_name.replace('-', "_"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_c_scalars_cross() {
+        let export = |params: &[&str], return_type: &str| Export {
+            name: "r#add".to_string(),
+            params: params.iter().enumerate().map(|(i, ty)| (format!("arg{}", i), ty.to_string())).collect(),
+            return_type: return_type.to_string(),
+        };
+        assert_eq!(export(&["f64", "i64"], "bool").declaration("left-pad"), "bool left_pad_add(double arg0, int64_t arg1);");
+        assert_eq!(export(&[], "()").declaration("pad"), "void pad_add(void);");
+        assert!(c_type("String").is_none());
+        assert!(c_type("Vec<f64>").is_none());
+    }
+}
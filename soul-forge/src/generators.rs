@@ -0,0 +1,68 @@
+// 🌀 Generators - `function*` Bodies as Iterators and Streams
+use crate::JsFunction;
+
+/// Crates only some generated code needs, by the path that uses them; a
+/// manifest depends on one when its crate's code uses it
+pub(crate) const DEPENDENCIES: &[(&str, &str, &str)] = &[
+    ("genawaiter", r#"{ version = "0.99", features = ["futures03"] }"#, "genawaiter::"),
+];
+
+const INDENT: &str = "    ";
+
+/// How a generator's body hands out what it yields
+///
+/// The body runs as it's written, inside a `genawaiter` coroutine that
+/// suspends at each `yield` until the next item is asked for, so infinite
+/// generators stay lazy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Yielding {
+    /// A `function*`, called for an `impl Iterator`
+    Iterator,
+    /// An `async function*`, called for an `impl Stream` of results, ending
+    /// at the first error thrown
+    Stream,
+}
+
+impl Yielding {
+    pub(crate) fn of(function: &JsFunction) -> Option<Self> {
+        match (function.is_generator, function.is_async) {
+            (false, _) => None,
+            (true, false) => Some(Self::Iterator),
+            (true, true) => Some(Self::Stream),
+        }
+    }
+
+    /// The Rust type a generator yielding `item` returns
+    pub(crate) fn return_type(self, item: &str) -> String {
+        match self {
+            Self::Iterator => format!("impl Iterator<Item = {}>", item),
+            Self::Stream => format!("impl futures::Stream<Item = Result<{}, JsError>>", item),
+        }
+    }
+
+    /// Hand out `value`, suspending until the next item is asked for
+    pub(crate) fn hand_out(self, value: &str) -> String {
+        match self {
+            Self::Iterator => format!("co.yield_({}).await", value),
+            Self::Stream => format!("co.yield_(Ok({})).await", value),
+        }
+    }
+
+    /// The body's `lines` as the coroutine the function returns; a stream's
+    /// body runs as a fallible block, and what it throws is its last item
+    pub(crate) fn wrap(self, lines: Vec<String>) -> Vec<String> {
+        let (open, close, depth): (&[&str], &[&str], usize) = match self {
+            Self::Iterator => (&["genawaiter::rc::Gen::new(|co| async move {"], &["}).into_iter()"], 1),
+            Self::Stream => (
+                &["genawaiter::rc::Gen::new(|co| async move {", "    let result: Result<(), JsError> = async {"],
+                &["    }.await;", "    if let Err(error) = result {", "        co.yield_(Err(error)).await;", "    }", "})"],
+                2,
+            ),
+        };
+        let indent = INDENT.repeat(depth);
+        open.iter().map(|line| line.to_string())
+            .chain(lines.into_iter().map(|line| if line.is_empty() { line } else { format!("{}{}", indent, line) }))
+            .chain(close.iter().map(|line| line.to_string()))
+            .collect()
+    }
+}
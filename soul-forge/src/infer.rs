@@ -0,0 +1,1157 @@
+// 🔮 Type Inference - Types from Usage
+use crate::{body, strings};
+use crate::{JsAst, JsClass, JsExpr, JsFunction, JsPattern, JsStmt, JsType};
+use std::collections::{BTreeSet, HashMap};
+
+/// Type parameters, in the order functions take them
+const TYPE_PARAMS: &[&str] = &["T", "U", "V", "W"];
+
+/// What is known of a value's type while inference runs
+#[derive(Debug, Clone)]
+enum Term {
+    Type(JsType),
+    Var(usize),
+    Array(Box<Term>),
+}
+
+const UNKNOWN: Term = Term::Type(JsType::Unknown);
+
+/// A type still being inferred, with what its uses tell of it
+#[derive(Debug, Default)]
+struct Var {
+    parent: usize,
+    /// What using it requires
+    term: Option<Term>,
+    /// Concrete types passed for it at call sites
+    passed: Vec<JsType>,
+    /// Used in a way no static type captures, so it stays `Value`
+    dynamic: bool,
+    /// An operand of `+`, which adds numbers unless given strings
+    plus: bool,
+    /// Tested for truthiness, a `bool` if nothing says otherwise
+    truthy: bool,
+    /// Traits a type parameter for it needs
+    bounds: BTreeSet<&'static str>,
+}
+
+/// The inferred side of a function or method
+struct Signature {
+    params: Vec<Term>,
+    ret: Term,
+    /// Whether the last parameter is a `...rest` array
+    rest: bool,
+    /// Whether it's a generator, whose `ret` is what it yields
+    generator: bool,
+}
+
+/// What a body being walked can name
+struct Scope<'a> {
+    locals: HashMap<String, Term>,
+    /// What `return` gives a value to
+    ret: Term,
+    /// What `yield` hands out, in a generator
+    yields: Option<Term>,
+    class: Option<&'a JsClass>,
+}
+
+/// Unification over a whole module
+///
+/// Untyped parameters and return types start as variables. Literals and
+/// operators constrain them where they are used, arguments bind them at
+/// each call site, and what a function returns binds its return type. A
+/// parameter nothing constrains, only passed around, compared or printed,
+/// becomes a type parameter; one used in ways no type captures, like
+/// reading fields off it, stays `Value`.
+#[derive(Default)]
+struct Inference {
+    vars: Vec<Var>,
+    signatures: Vec<Signature>,
+    functions: HashMap<String, usize>,
+    /// By class, then method, with constructors named `new`
+    methods: HashMap<(String, String), usize>,
+}
+
+/// Fill in the types a module leaves out, calling what it imports by the
+/// signatures in `imported`
+pub(crate) fn infer(ast: &mut JsAst, imported: &HashMap<String, JsType>) {
+    let mut inference = Inference::default();
+    for function in &ast.functions {
+        let signature = inference.signature(function);
+        inference.functions.insert(function.name.clone(), signature);
+    }
+    for class in &ast.classes {
+        for method in &class.methods {
+            let signature = inference.signature(&method.function);
+            inference.methods.insert((class.name.clone(), method.function.name.clone()), signature);
+        }
+    }
+
+    let mut bodies: Vec<(&JsFunction, Option<&JsClass>)> = ast.functions.iter().map(|function| (function, None)).collect();
+    for class in &ast.classes {
+        bodies.extend(class.methods.iter().map(|method| (&method.function, Some(class))));
+    }
+    // Callees first, so a call gives what its callee returns
+    let order = inference.callees_first(&bodies);
+    // Imported signatures follow the bodies', as they have none to walk
+    for (name, ty) in imported {
+        if let (JsType::Function(params, ret), false) = (ty, inference.functions.contains_key(name)) {
+            let params = params.iter().map(|param| Term::Type(param.clone())).collect();
+            inference.signatures.push(Signature { params, ret: Term::Type((**ret).clone()), rest: false, generator: false });
+            inference.functions.insert(name.clone(), inference.signatures.len() - 1);
+        }
+    }
+    for (signature, (function, class)) in order {
+        inference.function(function, signature, class);
+    }
+
+    for function in &mut ast.functions {
+        let signature = inference.functions[&function.name];
+        inference.resolve(function, signature);
+    }
+    for class in &mut ast.classes {
+        for method in &mut class.methods {
+            let signature = inference.methods[&(class.name.clone(), method.function.name.clone())];
+            inference.resolve(&mut method.function, signature);
+        }
+    }
+}
+
+impl Inference {
+    fn fresh(&mut self) -> Term {
+        let id = self.vars.len();
+        self.vars.push(Var { parent: id, ..Var::default() });
+        Term::Var(id)
+    }
+
+    /// Variables for what `function` leaves untyped
+    fn signature(&mut self, function: &JsFunction) -> usize {
+        let params = function.params.iter().map(|param| match &param.ty {
+            JsType::Unknown => self.fresh(),
+            JsType::Array(element) if param.rest && **element == JsType::Unknown => Term::Array(Box::new(self.fresh())),
+            ty => Term::Type(ty.clone()),
+        }).collect();
+        let ret = match &function.return_type {
+            JsType::Unknown => self.fresh(),
+            JsType::Iterator(item) if function.is_generator && **item == JsType::Unknown => self.fresh(),
+            JsType::Promise(inner) | JsType::Iterator(inner) => Term::Type((**inner).clone()),
+            ty => Term::Type(ty.clone()),
+        };
+        let rest = function.params.last().is_some_and(|param| param.rest);
+        self.signatures.push(Signature { params, ret, rest, generator: function.is_generator });
+        self.signatures.len() - 1
+    }
+
+    /// The signature of a call's callee, if it is the module's
+    fn callee(&self, callee: &JsExpr, class: Option<&JsClass>) -> Option<usize> {
+        match callee {
+            JsExpr::Ident(name) => self.functions.get(name).copied(),
+            JsExpr::Member(object, method) => match &**object {
+                JsExpr::This => class.and_then(|class| self.methods.get(&(class.name.clone(), method.clone())).copied()),
+                // A static method, or a function of a module imported whole
+                JsExpr::Ident(object) => self.methods.get(&(object.clone(), method.clone())).copied()
+                    .or_else(|| self.functions.get(&format!("{}.{}", object, method)).copied()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// `bodies`, by signature, each after what it calls but for recursion
+    fn callees_first<'a>(&self, bodies: &[(&'a JsFunction, Option<&'a JsClass>)]) -> Vec<(usize, (&'a JsFunction, Option<&'a JsClass>))> {
+        let calls: Vec<Vec<usize>> = bodies.iter().map(|(function, class)| {
+            let mut calls = vec![];
+            let mut visit = |expr: &JsExpr| {
+                match expr {
+                    // Any method of the name, as the receiver's class may not be known yet
+                    JsExpr::Call(callee, _) => match (self.callee(callee, *class), &**callee) {
+                        (Some(callee), _) => calls.push(callee),
+                        (None, JsExpr::Member(_, method)) => calls.extend(
+                            self.methods.iter().filter(|((_, name), _)| name == method).map(|(_, signature)| *signature),
+                        ),
+                        (None, _) => {}
+                    },
+                    JsExpr::New(callee, _) => {
+                        if let JsExpr::Ident(class) = &**callee {
+                            calls.extend(self.methods.get(&(class.clone(), "new".to_string())));
+                        }
+                    }
+                    _ => {}
+                }
+                true
+            };
+            function.body.iter().for_each(|stmt| body::each_stmt_expr(stmt, &mut visit));
+            calls
+        }).collect();
+
+        // Bodies are in signature order
+        fn visit(signature: usize, calls: &[Vec<usize>], seen: &mut [bool], order: &mut Vec<usize>) {
+            if std::mem::replace(&mut seen[signature], true) {
+                return;
+            }
+            for &callee in &calls[signature] {
+                visit(callee, calls, seen, order);
+            }
+            order.push(signature);
+        }
+        let (mut seen, mut order) = (vec![false; bodies.len()], vec![]);
+        for signature in 0..bodies.len() {
+            visit(signature, &calls, &mut seen, &mut order);
+        }
+        order.into_iter().map(|signature| (signature, bodies[signature])).collect()
+    }
+
+    fn find(&mut self, id: usize) -> usize {
+        let parent = self.vars[id].parent;
+        if parent == id {
+            return id;
+        }
+        let root = self.find(parent);
+        self.vars[id].parent = root;
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        let merged = std::mem::replace(&mut self.vars[b], Var { parent: a, ..Var::default() });
+        let var = &mut self.vars[a];
+        var.passed.extend(merged.passed);
+        var.dynamic |= merged.dynamic;
+        var.plus |= merged.plus;
+        var.truthy |= merged.truthy;
+        var.bounds.extend(merged.bounds);
+        if let Some(term) = merged.term {
+            self.constrain(a, &term);
+        }
+    }
+
+    /// Make `a` and `b` the same type, as far as they can be
+    fn unify(&mut self, a: &Term, b: &Term) -> bool {
+        match (a, b) {
+            (Term::Var(a), Term::Var(b)) => {
+                self.union(*a, *b);
+                true
+            }
+            (Term::Var(var), term) | (term, Term::Var(var)) => {
+                self.constrain(*var, term);
+                true
+            }
+            (Term::Array(a), Term::Array(b)) => self.unify(a, b),
+            (Term::Array(element), Term::Type(JsType::Array(ty))) | (Term::Type(JsType::Array(ty)), Term::Array(element)) => {
+                self.unify(element, &Term::Type((**ty).clone()))
+            }
+            (Term::Array(element), Term::Type(JsType::Unknown)) | (Term::Type(JsType::Unknown), Term::Array(element)) => {
+                self.escape(element);
+                true
+            }
+            (Term::Array(_), Term::Type(_)) | (Term::Type(_), Term::Array(_)) => false,
+            (Term::Type(a), Term::Type(b)) => merge(a, b).is_some(),
+        }
+    }
+
+    fn constrain(&mut self, var: usize, term: &Term) {
+        let root = self.find(var);
+        if matches!(term, Term::Type(JsType::Unknown)) || self.occurs(root, term) {
+            self.vars[root].dynamic = true;
+            return;
+        }
+        match (self.vars[root].term.clone(), term) {
+            (None, term) => self.vars[root].term = Some(term.clone()),
+            (Some(Term::Type(known)), Term::Type(ty)) => {
+                match merge(&known, ty) {
+                    Some(merged) => self.vars[root].term = Some(Term::Type(merged)),
+                    None => self.vars[root].dynamic = true,
+                }
+            }
+            (Some(known), term) => {
+                if !self.unify(&known, term) {
+                    let root = self.find(root);
+                    self.vars[root].dynamic = true;
+                }
+            }
+        }
+    }
+
+    fn occurs(&mut self, root: usize, term: &Term) -> bool {
+        match term {
+            Term::Var(var) => self.find(*var) == root,
+            Term::Array(element) => self.occurs(root, element),
+            Term::Type(_) => false,
+        }
+    }
+
+    /// `term` flows somewhere inference can't follow
+    fn escape(&mut self, term: &Term) {
+        match term {
+            Term::Var(var) => {
+                let root = self.find(*var);
+                self.vars[root].dynamic = true;
+            }
+            Term::Array(element) => self.escape(element),
+            Term::Type(_) => {}
+        }
+    }
+
+    fn bound(&mut self, term: &Term, bound: &'static str) {
+        match term {
+            Term::Var(var) => {
+                let root = self.find(*var);
+                self.vars[root].bounds.insert(bound);
+            }
+            Term::Array(element) => self.bound(element, bound),
+            Term::Type(_) => {}
+        }
+    }
+
+    fn flag(&mut self, term: &Term, set: fn(&mut Var)) {
+        if let Term::Var(var) = term {
+            let root = self.find(*var);
+            set(&mut self.vars[root]);
+        }
+    }
+
+    fn function(&mut self, function: &JsFunction, signature: usize, class: Option<&JsClass>) {
+        let Signature { params, ret, generator, .. } = &self.signatures[signature];
+        let (params, ret, generator) = (params.clone(), ret.clone(), *generator);
+        // What a generator returns goes nowhere; its items are what it yields
+        let mut scope = Scope {
+            locals: function.params.iter().map(|param| param.name.clone()).zip(params).collect(),
+            ret: if generator { self.fresh() } else { ret.clone() },
+            yields: generator.then(|| ret.clone()),
+            class,
+        };
+        // Nothing returned is nothing to return, and nothing yielded nothing to yield
+        let gives_value = match generator {
+            true => yields_value(&function.body),
+            false => returns_value(&function.body),
+        };
+        if !gives_value {
+            self.unify(&ret, &Term::Type(JsType::Void));
+        }
+        self.body(&function.body, &mut scope);
+    }
+
+    /// A closure, whose parameters are `params` where known; gives what it returns
+    fn closure(&mut self, function: &JsFunction, params: &[Term], scope: &Scope) -> Term {
+        let mut locals = scope.locals.clone();
+        for (i, param) in function.params.iter().enumerate() {
+            let term = match (&param.ty, params.get(i)) {
+                (JsType::Unknown, Some(term)) => term.clone(),
+                (ty, _) => Term::Type(ty.clone()),
+            };
+            locals.insert(param.name.clone(), term);
+        }
+        let ret = self.fresh();
+        let mut scope = Scope { locals, ret: ret.clone(), yields: None, class: scope.class };
+        self.body(&function.body, &mut scope);
+        ret
+    }
+
+    fn body(&mut self, body: &[JsStmt], scope: &mut Scope) {
+        for stmt in body {
+            self.stmt(stmt, scope);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &JsStmt, scope: &mut Scope) {
+        match stmt {
+            JsStmt::Let { pattern: JsPattern::Ident(name), ty, init } => {
+                let term = match (ty, init) {
+                    // What the initializer says beats a partly known type
+                    (ty, Some(init)) if !known(ty) => self.expr(init, scope),
+                    (JsType::Unknown, None) => self.fresh(),
+                    (ty, init) => {
+                        if let Some(init) = init {
+                            let value = self.expr(init, scope);
+                            self.unify(&value, &Term::Type(ty.clone()));
+                        }
+                        Term::Type(ty.clone())
+                    }
+                };
+                scope.locals.insert(name.clone(), term);
+            }
+            // Elements share a type, which the array destructured takes
+            JsStmt::Let { pattern: pattern @ JsPattern::Array { .. }, init: Some(init), .. } => {
+                let value = self.expr(init, scope);
+                let element = Term::Array(Box::new(self.fresh()));
+                match self.unify(&value, &element) {
+                    true => bind(pattern, element, scope),
+                    false => bind(pattern, UNKNOWN, scope),
+                }
+            }
+            JsStmt::Let { pattern, init, .. } => {
+                if let Some(init) = init {
+                    let value = self.expr(init, scope);
+                    self.escape(&value);
+                }
+                bind(pattern, UNKNOWN, scope);
+            }
+            JsStmt::Expr(expr) => {
+                self.expr(expr, scope);
+            }
+            JsStmt::Return(value) => {
+                let value = match value {
+                    Some(value) => self.expr(value, scope),
+                    None => Term::Type(JsType::Void),
+                };
+                let ret = scope.ret.clone();
+                self.unify(&ret, &value);
+            }
+            JsStmt::If { test, then, otherwise } => {
+                self.condition(test, scope);
+                self.body(then, scope);
+                self.body(otherwise, scope);
+            }
+            JsStmt::While { test, body } | JsStmt::DoWhile { body, test } => {
+                self.condition(test, scope);
+                self.body(body, scope);
+            }
+            JsStmt::For { init, test, update, body } => {
+                self.body(init, scope);
+                if let Some(test) = test {
+                    self.condition(test, scope);
+                }
+                if let Some(update) = update {
+                    self.expr(update, scope);
+                }
+                self.body(body, scope);
+            }
+            JsStmt::ForOf { pattern, iterable, body } => {
+                let iterable = self.expr(iterable, scope);
+                let element = self.element(&iterable);
+                bind(pattern, element, scope);
+                self.body(body, scope);
+            }
+            JsStmt::ForIn { pattern, object, body } => {
+                let object = self.expr(object, scope);
+                self.escape(&object);
+                bind(pattern, Term::Type(JsType::String), scope);
+                self.body(body, scope);
+            }
+            JsStmt::Switch { discriminant, cases } => {
+                let discriminant = self.expr(discriminant, scope);
+                for case in cases {
+                    if let Some(test) = &case.test {
+                        let test = self.expr(test, scope);
+                        self.unify(&discriminant, &test);
+                    }
+                    self.body(&case.body, scope);
+                }
+            }
+            JsStmt::Labeled(_, body) => self.stmt(body, scope),
+            JsStmt::Block(body) => self.body(body, scope),
+            JsStmt::Throw(error) => {
+                let error = self.expr(error, scope);
+                self.escape(&error);
+            }
+            JsStmt::Try { block, param, handler, finalizer } => {
+                self.body(block, scope);
+                if let Some(param) = param {
+                    bind(param, UNKNOWN, scope);
+                }
+                if let Some(handler) = handler {
+                    self.body(handler, scope);
+                }
+                self.body(finalizer, scope);
+            }
+            JsStmt::Function(function) => {
+                scope.locals.insert(function.name.clone(), UNKNOWN);
+                let ret = self.closure(function, &[], scope);
+                self.escape(&ret);
+            }
+            JsStmt::Break(_) | JsStmt::Continue(_) | JsStmt::Unsupported(_) => {}
+        }
+    }
+
+    /// What iterating over `iterable` gives
+    fn element(&mut self, iterable: &Term) -> Term {
+        match iterable {
+            Term::Array(element) => (**element).clone(),
+            Term::Type(JsType::Array(element) | JsType::Set(element) | JsType::Iterator(element)) => Term::Type((**element).clone()),
+            Term::Type(JsType::String) => Term::Type(JsType::String),
+            Term::Type(_) => UNKNOWN,
+            Term::Var(_) => {
+                let element = self.fresh();
+                self.unify(iterable, &Term::Array(Box::new(element.clone())));
+                element
+            }
+        }
+    }
+
+    fn condition(&mut self, test: &JsExpr, scope: &mut Scope) {
+        let test = self.expr(test, scope);
+        self.flag(&test, |var| var.truthy = true);
+    }
+
+    fn require(&mut self, expr: &JsExpr, ty: JsType, scope: &mut Scope) {
+        let term = self.expr(expr, scope);
+        self.unify(&term, &Term::Type(ty));
+    }
+
+    fn expr(&mut self, expr: &JsExpr, scope: &mut Scope) -> Term {
+        match expr {
+            JsExpr::Number(_) => Term::Type(JsType::Number),
+            JsExpr::String(_) => Term::Type(JsType::String),
+            JsExpr::Bool(_) => Term::Type(JsType::Boolean),
+            JsExpr::Template { exprs, .. } => {
+                for expr in exprs {
+                    let part = self.expr(expr, scope);
+                    self.bound(&part, "std::fmt::Debug");
+                }
+                Term::Type(JsType::String)
+            }
+            JsExpr::Ident(name) => match name.as_str() {
+                name if scope.locals.contains_key(name) => scope.locals[name].clone(),
+                "NaN" | "Infinity" => Term::Type(JsType::Number),
+                _ => UNKNOWN,
+            },
+            JsExpr::Array(items) => {
+                let element = self.fresh();
+                for item in items {
+                    let item = self.expr(item, scope);
+                    self.unify(&element, &item);
+                }
+                Term::Array(Box::new(element))
+            }
+            JsExpr::Unary(op, arg) => match *op {
+                "-" | "+" | "~" => {
+                    self.require(arg, JsType::Number, scope);
+                    Term::Type(JsType::Number)
+                }
+                "!" => {
+                    self.condition(arg, scope);
+                    Term::Type(JsType::Boolean)
+                }
+                op => {
+                    let arg = self.expr(arg, scope);
+                    self.escape(&arg);
+                    Term::Type(if op == "typeof" { JsType::String } else { JsType::Unknown })
+                }
+            },
+            JsExpr::Update { target, .. } => {
+                self.require(target, JsType::Number, scope);
+                Term::Type(JsType::Number)
+            }
+            JsExpr::Binary(op, left, right) => self.binary(op, left, right, scope),
+            JsExpr::Assign(op, target, value) => {
+                let target_term = self.expr(target, scope);
+                match *op {
+                    "=" => {
+                        let value = self.expr(value, scope);
+                        self.unify(&target_term, &value);
+                    }
+                    "+=" => {
+                        self.binary("+", target, value, scope);
+                    }
+                    _ => {
+                        self.unify(&target_term, &Term::Type(JsType::Number));
+                        self.require(value, JsType::Number, scope);
+                    }
+                }
+                target_term
+            }
+            JsExpr::Conditional(test, then, otherwise) => {
+                self.condition(test, scope);
+                let (then, otherwise) = (self.expr(then, scope), self.expr(otherwise, scope));
+                self.unify(&then, &otherwise);
+                then
+            }
+            JsExpr::Call(callee, args) => self.call(callee, args, scope),
+            JsExpr::New(callee, args) => match &**callee {
+                JsExpr::Ident(class) if self.methods.contains_key(&(class.clone(), "new".to_string())) => {
+                    let signature = self.methods[&(class.clone(), "new".to_string())];
+                    self.pass(signature, args, scope);
+                    Term::Type(JsType::Named(class.clone(), vec![]))
+                }
+                callee => {
+                    self.escape_all(args, scope);
+                    match callee {
+                        JsExpr::Ident(name) if matches!(name.as_str(), "Map" | "Set" | "Array" | "Promise") => UNKNOWN,
+                        JsExpr::Ident(name) if !name.ends_with("Error") => Term::Type(JsType::Named(name.clone(), vec![])),
+                        _ => UNKNOWN,
+                    }
+                }
+            },
+            JsExpr::Member(object, property) => match (&**object, property.as_str()) {
+                (JsExpr::This, property) => scope
+                    .class
+                    .and_then(|class| class.properties.iter().find(|prop| prop.name == property && !prop.is_static))
+                    .map_or(UNKNOWN, |prop| Term::Type(prop.ty.clone())),
+                // Strings and arrays both have a length
+                (object, "length") => {
+                    let object = self.expr(object, scope);
+                    if let Term::Var(_) = object {
+                        self.escape(&object);
+                    }
+                    Term::Type(JsType::Number)
+                }
+                (JsExpr::Ident(namespace), _) if namespace == "Math" || namespace == "Number" => Term::Type(JsType::Number),
+                (object, _) => {
+                    let object = self.expr(object, scope);
+                    self.escape(&object);
+                    UNKNOWN
+                }
+            },
+            JsExpr::Index(object, index) => {
+                let object = self.expr(object, scope);
+                let index = self.expr(index, scope);
+                self.escape(&index);
+                // Numeric indexes read arrays
+                if let (Term::Var(_), Term::Type(JsType::Number)) = (&object, &index) {
+                    return self.element(&object);
+                }
+                match object {
+                    Term::Array(element) => *element,
+                    Term::Type(JsType::Array(element)) => Term::Type(*element),
+                    Term::Type(JsType::Map(_, value)) => Term::Type(*value),
+                    Term::Type(JsType::String) => Term::Type(JsType::String),
+                    object => {
+                        self.escape(&object);
+                        UNKNOWN
+                    }
+                }
+            }
+            JsExpr::Function(function) => {
+                let ret = self.closure(function, &[], scope);
+                self.escape(&ret);
+                UNKNOWN
+            }
+            JsExpr::Await(future) => self.expr(future, scope),
+            JsExpr::Sequence(exprs) => {
+                let mut last = UNKNOWN;
+                for expr in exprs {
+                    last = self.expr(expr, scope);
+                }
+                last
+            }
+            JsExpr::Object(props) => {
+                for prop in props {
+                    let (crate::JsProp::KeyValue(_, value) | crate::JsProp::Spread(value)) = prop;
+                    let value = self.expr(value, scope);
+                    self.escape(&value);
+                }
+                Term::Type(JsType::Object)
+            }
+            // What an array spreads is each of its elements
+            JsExpr::Spread(inner) => {
+                let inner = self.expr(inner, scope);
+                let element = self.fresh();
+                match self.unify(&inner, &Term::Array(Box::new(element.clone()))) {
+                    true => element,
+                    false => {
+                        self.escape(&inner);
+                        UNKNOWN
+                    }
+                }
+            }
+            JsExpr::Regex { .. } => Term::Type(JsType::Named(strings::REGEX.to_string(), vec![])),
+            JsExpr::Yield { value, delegate } => {
+                let value = match value {
+                    Some(value) => self.expr(value, scope),
+                    None => Term::Type(JsType::Void),
+                };
+                let item = if *delegate { self.element(&value) } else { value };
+                match scope.yields.clone() {
+                    Some(yields) => {
+                        self.unify(&yields, &item);
+                    }
+                    None => self.escape(&item),
+                }
+                // What the caller passes back to `next(..)`
+                UNKNOWN
+            }
+            JsExpr::Null | JsExpr::Undefined | JsExpr::This | JsExpr::Super | JsExpr::Unsupported(_) => UNKNOWN,
+        }
+    }
+
+    fn binary(&mut self, op: &str, left: &JsExpr, right: &JsExpr, scope: &mut Scope) -> Term {
+        match op {
+            "-" | "*" | "/" | "%" | "**" | "&" | "|" | "^" | "<<" | ">>" | ">>>" | "<" | "<=" | ">" | ">=" => {
+                self.require(left, JsType::Number, scope);
+                self.require(right, JsType::Number, scope);
+                Term::Type(match op {
+                    "<" | "<=" | ">" | ">=" => JsType::Boolean,
+                    _ => JsType::Number,
+                })
+            }
+            // Either side a string makes the other a string's part, whatever it is
+            "+" => {
+                let (left, right) = (self.expr(left, scope), self.expr(right, scope));
+                let string = Term::Type(JsType::String);
+                if [&left, &right].iter().any(|term| matches!(term, Term::Type(JsType::String))) {
+                    self.bound(&left, "std::fmt::Debug");
+                    self.bound(&right, "std::fmt::Debug");
+                    return string;
+                }
+                self.unify(&left, &right);
+                self.flag(&left, |var| var.plus = true);
+                left
+            }
+            "==" | "===" | "!=" | "!==" => {
+                let (left, right) = (self.expr(left, scope), self.expr(right, scope));
+                if [&left, &right].iter().any(|term| matches!(term, Term::Type(JsType::Unknown))) {
+                    self.escape(&left);
+                    self.escape(&right);
+                } else {
+                    self.unify(&left, &right);
+                    self.bound(&left, "PartialEq");
+                }
+                Term::Type(JsType::Boolean)
+            }
+            "&&" | "||" => {
+                self.condition(left, scope);
+                self.condition(right, scope);
+                Term::Type(JsType::Boolean)
+            }
+            _ => {
+                self.escape_all([left, right], scope);
+                UNKNOWN
+            }
+        }
+    }
+
+    fn escape_all<'e>(&mut self, exprs: impl IntoIterator<Item = &'e JsExpr>, scope: &mut Scope) {
+        for expr in exprs {
+            let term = self.expr(expr, scope);
+            self.escape(&term);
+        }
+    }
+
+    fn call(&mut self, callee: &JsExpr, args: &[JsExpr], scope: &mut Scope) -> Term {
+        let local = match callee {
+            JsExpr::Ident(name) => scope.locals.contains_key(name),
+            JsExpr::Member(object, _) => matches!(&**object, JsExpr::Ident(name) if scope.locals.contains_key(name)),
+            _ => false,
+        };
+        if let Some(signature) = self.callee(callee, scope.class).filter(|_| !local) {
+            return self.pass(signature, args, scope);
+        }
+
+        match callee {
+            JsExpr::Ident(name) if !scope.locals.contains_key(name) => match name.as_str() {
+                "parseInt" | "parseFloat" => {
+                    self.require(&args[0], JsType::String, scope);
+                    self.escape_all(&args[1..], scope);
+                    Term::Type(JsType::Number)
+                }
+                name => {
+                    self.escape_all(args, scope);
+                    match name {
+                        "Number" => Term::Type(JsType::Number),
+                        "String" => Term::Type(JsType::String),
+                        "Boolean" | "isNaN" | "isFinite" => Term::Type(JsType::Boolean),
+                        _ => UNKNOWN,
+                    }
+                }
+            },
+            JsExpr::Member(object, method) => match &**object {
+                JsExpr::Ident(namespace) if namespace == "Math" && !scope.locals.contains_key(namespace) => {
+                    for arg in args {
+                        self.require(arg, JsType::Number, scope);
+                    }
+                    Term::Type(JsType::Number)
+                }
+                JsExpr::Ident(namespace) if namespace == "console" && !scope.locals.contains_key(namespace) => {
+                    for arg in args {
+                        let arg = self.expr(arg, scope);
+                        self.bound(&arg, "std::fmt::Debug");
+                    }
+                    Term::Type(JsType::Void)
+                }
+                JsExpr::Ident(namespace) if namespace == "JSON" && method == "parse" => {
+                    if let Some(text) = args.first() {
+                        self.require(text, JsType::String, scope);
+                    }
+                    UNKNOWN
+                }
+                object => self.method_call(object, method, args, scope),
+            },
+            callee => {
+                self.escape_all(std::iter::once(callee).chain(args), scope);
+                UNKNOWN
+            }
+        }
+    }
+
+    /// Bind a call's arguments to the callee's parameters; gives what it returns
+    fn pass(&mut self, signature: usize, args: &[JsExpr], scope: &mut Scope) -> Term {
+        let params = self.signatures[signature].params.clone();
+        // Arguments past the others are elements of a `...rest` array, and
+        // an array spread fills the parameters from where it is
+        let rest = match (self.signatures[signature].rest, params.last()) {
+            (true, Some(Term::Array(element))) => Some((params.len() - 1, (**element).clone())),
+            _ => None,
+        };
+        let mut terms = vec![];
+        for (i, arg) in args.iter().enumerate() {
+            let term = self.expr(arg, scope);
+            let param = match &rest {
+                Some((fixed, element)) if i >= *fixed => Some(element),
+                _ => params.get(i),
+            };
+            if let (JsExpr::Spread(_), None) = (arg, &rest) {
+                for param in params.iter().skip(i + 1) {
+                    self.unify(param, &term);
+                }
+            }
+            match (param, &term) {
+                (Some(Term::Var(param)), Term::Type(ty)) if *ty != JsType::Unknown => {
+                    let root = self.find(*param);
+                    self.vars[root].passed.push(ty.clone());
+                }
+                (Some(Term::Var(_)), Term::Type(_)) => {}
+                (Some(param), term) => {
+                    self.unify(param, term);
+                }
+                (None, term) => self.escape(term),
+            }
+            terms.push(term);
+        }
+
+        let ret = self.signatures[signature].ret.clone();
+        let ret = self.instance(&ret, &params, &terms);
+        // Callees come first, so a generator's items are known by now
+        match self.signatures[signature].generator {
+            true => Term::Type(JsType::Iterator(Box::new(self.resolve_term(&ret, &HashMap::new(), 0)))),
+            false => ret,
+        }
+    }
+
+    /// What a call returns, with the callee's parameters as its arguments
+    fn instance(&mut self, ret: &Term, params: &[Term], args: &[Term]) -> Term {
+        match ret {
+            Term::Var(var) => {
+                let root = self.find(*var);
+                for (param, arg) in params.iter().zip(args) {
+                    if let Term::Var(param) = param {
+                        if self.find(*param) == root {
+                            return arg.clone();
+                        }
+                    }
+                }
+                match self.vars[root].term.clone() {
+                    Some(term) if !self.occurs(root, &term) => self.instance(&term, params, args),
+                    _ => Term::Var(root),
+                }
+            }
+            Term::Array(element) => Term::Array(Box::new(self.instance(element, params, args))),
+            Term::Type(_) => ret.clone(),
+        }
+    }
+
+    /// A method called on a value, whose name may say what the value is
+    fn method_call(&mut self, object: &JsExpr, method: &str, args: &[JsExpr], scope: &mut Scope) -> Term {
+        let object = self.expr(object, scope);
+        if let Term::Type(JsType::Named(class, _)) = &object {
+            if let Some(signature) = self.methods.get(&(class.clone(), method.to_string())).copied() {
+                return self.pass(signature, args, scope);
+            }
+            // A regex tests and matches strings
+            if class == strings::REGEX && matches!(method, "test" | "exec") {
+                for arg in args {
+                    let arg = self.expr(arg, scope);
+                    self.unify(&arg, &Term::Type(JsType::String));
+                }
+                return Term::Type(match method {
+                    "test" => JsType::Boolean,
+                    _ => JsType::Optional(Box::new(JsType::Array(Box::new(JsType::String)))),
+                });
+            }
+        }
+
+        match method {
+            "toUpperCase" | "toLowerCase" | "trim" | "trimStart" | "trimEnd" | "charAt" | "charCodeAt" | "padStart" | "padEnd"
+            | "split" | "startsWith" | "endsWith" | "replace" | "replaceAll" | "substring" | "repeat" | "match" | "matchAll"
+            | "search" | "localeCompare" => {
+                self.unify(&object, &Term::Type(JsType::String));
+                self.escape_all(args, scope);
+                Term::Type(match method {
+                    "charCodeAt" | "search" | "localeCompare" => JsType::Number,
+                    "match" => JsType::Optional(Box::new(JsType::Array(Box::new(JsType::String)))),
+                    "matchAll" => JsType::Array(Box::new(JsType::Array(Box::new(JsType::String)))),
+                    "split" => JsType::Array(Box::new(JsType::String)),
+                    "startsWith" | "endsWith" => JsType::Boolean,
+                    _ => JsType::String,
+                })
+            }
+            "push" | "pop" | "shift" | "unshift" | "join" | "reverse" | "sort" | "map" | "filter" | "forEach" | "find"
+            | "findIndex" | "some" | "every" | "reduce" => {
+                let element = self.fresh();
+                let array = Term::Array(Box::new(element.clone()));
+                self.unify(&object, &array);
+                let callback = match args.first() {
+                    Some(JsExpr::Function(callback)) if method == "reduce" => {
+                        let ret = self.closure(callback, &[UNKNOWN, element.clone()], scope);
+                        self.escape(&ret);
+                        self.escape_all(&args[1..], scope);
+                        return UNKNOWN;
+                    }
+                    Some(JsExpr::Function(callback)) => Some(self.closure(callback, &[element.clone()], scope)),
+                    _ => None,
+                };
+                match method {
+                    "push" | "unshift" => {
+                        for arg in args {
+                            let arg = self.expr(arg, scope);
+                            self.unify(&element, &arg);
+                        }
+                        Term::Type(JsType::Number)
+                    }
+                    "join" => {
+                        self.escape_all(args, scope);
+                        self.bound(&element, "std::fmt::Debug");
+                        Term::Type(JsType::String)
+                    }
+                    "map" => callback.map_or(UNKNOWN, |ret| Term::Array(Box::new(ret))),
+                    "filter" | "reverse" | "sort" => array,
+                    "findIndex" => Term::Type(JsType::Number),
+                    "some" | "every" => Term::Type(JsType::Boolean),
+                    "forEach" => Term::Type(JsType::Void),
+                    _ => {
+                        if callback.is_none() {
+                            self.escape_all(args, scope);
+                        }
+                        UNKNOWN
+                    }
+                }
+            }
+            _ => {
+                self.escape(&object);
+                self.escape_all(args, scope);
+                match method {
+                    "includes" | "has" => Term::Type(JsType::Boolean),
+                    "indexOf" => Term::Type(JsType::Number),
+                    "toString" | "toFixed" => Term::Type(JsType::String),
+                    _ => UNKNOWN,
+                }
+            }
+        }
+    }
+
+    /// Write what was inferred back into `function`
+    fn resolve(&mut self, function: &mut JsFunction, signature: usize) {
+        let Signature { params, ret, .. } = &self.signatures[signature];
+        let (params, ret) = (params.clone(), ret.clone());
+
+        // Polymorphic parameters take type parameters, in order
+        let mut names: HashMap<usize, String> = HashMap::new();
+        let declared = function.type_params.clone();
+        let mut available = TYPE_PARAMS.iter().filter(|name| !declared.iter().any(|declared| declared == *name));
+        for param in &params {
+            for root in self.generic_roots(param) {
+                if names.contains_key(&root) {
+                    continue;
+                }
+                let Some(name) = available.next() else { break };
+                let bounds: Vec<&str> = std::iter::once("Clone").chain(self.vars[root].bounds.iter().copied()).collect();
+                function.type_params.push(format!("{}: {}", name, bounds.join(" + ")));
+                names.insert(root, name.to_string());
+            }
+        }
+
+        for (param, term) in function.params.iter_mut().zip(&params) {
+            if param.ty == JsType::Unknown || (param.rest && param.ty == JsType::Array(Box::new(JsType::Unknown))) {
+                param.ty = self.resolve_term(term, &names, 0);
+            }
+        }
+        match &function.return_type {
+            JsType::Unknown if function.is_generator => function.return_type = JsType::Iterator(Box::new(self.resolve_term(&ret, &names, 0))),
+            JsType::Iterator(item) if **item == JsType::Unknown => function.return_type = JsType::Iterator(Box::new(self.resolve_term(&ret, &names, 0))),
+            JsType::Unknown => function.return_type = self.resolve_term(&ret, &names, 0),
+            _ => {}
+        }
+    }
+
+    /// Variables under a parameter's `term` that nothing pins down
+    fn generic_roots(&mut self, term: &Term) -> Vec<usize> {
+        match term {
+            Term::Var(var) => {
+                let root = self.find(*var);
+                match self.vars[root].term.clone() {
+                    Some(term) if !self.vars[root].dynamic && !self.occurs(root, &term) => self.generic_roots(&term),
+                    Some(_) => vec![],
+                    None if self.polymorphic(root) => vec![root],
+                    None => vec![],
+                }
+            }
+            Term::Array(element) => self.generic_roots(element),
+            Term::Type(_) => vec![],
+        }
+    }
+
+    /// A type only passed around, or passed as more than one type
+    fn polymorphic(&self, root: usize) -> bool {
+        let var = &self.vars[root];
+        !var.dynamic && !var.plus && !var.truthy && passed(&var.passed).is_none()
+    }
+
+    fn resolve_term(&mut self, term: &Term, names: &HashMap<usize, String>, depth: usize) -> JsType {
+        if depth > 8 {
+            return JsType::Unknown;
+        }
+        match term {
+            Term::Type(ty) => ty.clone(),
+            Term::Array(element) => JsType::Array(Box::new(self.resolve_term(element, names, depth + 1))),
+            Term::Var(var) => {
+                let root = self.find(*var);
+                let var = &self.vars[root];
+                if var.dynamic {
+                    return JsType::Unknown;
+                }
+                if let Some(term) = var.term.clone() {
+                    return self.resolve_term(&term, names, depth + 1);
+                }
+                if let Some(name) = names.get(&root) {
+                    return JsType::Named(name.clone(), vec![]);
+                }
+                if let Some(ty) = passed(&var.passed) {
+                    return ty;
+                }
+                match (var.plus, var.truthy) {
+                    (true, _) => JsType::Number,
+                    (false, true) => JsType::Boolean,
+                    (false, false) => JsType::Unknown,
+                }
+            }
+        }
+    }
+}
+
+/// The one type every call site agrees on, if they do
+fn passed(types: &[JsType]) -> Option<JsType> {
+    let (first, rest) = types.split_first()?;
+    rest.iter().try_fold(first.clone(), |agreed, ty| merge(&agreed, ty))
+}
+
+/// Whether `ty` is known all the way down
+fn known(ty: &JsType) -> bool {
+    match ty {
+        JsType::Unknown => false,
+        JsType::Array(inner) | JsType::Set(inner) | JsType::Optional(inner) | JsType::Promise(inner) | JsType::Iterator(inner) => known(inner),
+        JsType::Map(key, value) => known(key) && known(value),
+        _ => true,
+    }
+}
+
+/// Two views of a type as one, with what either knows
+fn merge(a: &JsType, b: &JsType) -> Option<JsType> {
+    match (a, b) {
+        (JsType::Unknown, ty) | (ty, JsType::Unknown) => Some(ty.clone()),
+        (JsType::Array(a), JsType::Array(b)) => Some(JsType::Array(Box::new(merge(a, b)?))),
+        (a, b) if a == b => Some(a.clone()),
+        _ => None,
+    }
+}
+
+fn bind(pattern: &JsPattern, term: Term, scope: &mut Scope) {
+    match pattern {
+        JsPattern::Ident(name) => {
+            scope.locals.insert(name.clone(), term);
+        }
+        JsPattern::Array { elements, rest } => {
+            let element = match term {
+                Term::Array(element) => *element,
+                _ => UNKNOWN,
+            };
+            for pattern in elements.iter().flatten() {
+                bind(pattern, element.clone(), scope);
+            }
+            if let Some(rest) = rest {
+                bind(rest, Term::Array(Box::new(element)), scope);
+            }
+        }
+        JsPattern::Object { properties, rest } => {
+            for pattern in properties.iter().map(|(_, pattern)| pattern).chain(rest.as_deref()) {
+                bind(pattern, UNKNOWN, scope);
+            }
+        }
+        JsPattern::Default(pattern, _) => bind(pattern, term, scope),
+    }
+}
+
+/// Whether `body` returns a value anywhere outside its closures
+fn returns_value(body: &[JsStmt]) -> bool {
+    body.iter().any(|stmt| match stmt {
+        JsStmt::Return(value) => value.is_some(),
+        JsStmt::If { then, otherwise, .. } => returns_value(then) || returns_value(otherwise),
+        JsStmt::While { body, .. } | JsStmt::DoWhile { body, .. } | JsStmt::ForOf { body, .. } | JsStmt::ForIn { body, .. } | JsStmt::Block(body) => {
+            returns_value(body)
+        }
+        JsStmt::For { init, body, .. } => returns_value(init) || returns_value(body),
+        JsStmt::Switch { cases, .. } => cases.iter().any(|case| returns_value(&case.body)),
+        JsStmt::Labeled(_, body) => returns_value(std::slice::from_ref(body)),
+        JsStmt::Try { block, handler, finalizer, .. } => {
+            returns_value(block) || handler.as_deref().is_some_and(returns_value) || returns_value(finalizer)
+        }
+        _ => false,
+    })
+}
+
+/// Whether `body` yields a value anywhere outside its closures
+fn yields_value(body: &[JsStmt]) -> bool {
+    let mut yields = false;
+    for stmt in body {
+        body::each_stmt_expr(stmt, &mut |expr| {
+            yields |= matches!(expr, JsExpr::Yield { value: Some(_), .. });
+            !matches!(expr, JsExpr::Function(_))
+        });
+    }
+    yields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_source;
+    use crate::SourceKind;
+
+    fn inferred(code: &str) -> JsAst {
+        let mut ast = parse_source(code, SourceKind::JavaScript).unwrap();
+        infer(&mut ast, &HashMap::new());
+        ast
+    }
+
+    fn param_types(function: &JsFunction) -> Vec<JsType> {
+        function.params.iter().map(|param| param.ty.clone()).collect()
+    }
+
+    #[test]
+    fn test_usage_and_call_sites_unify() {
+        let ast = inferred(
+            "function scale(values, factor) { const out = []; for (const v of values) { out.push(v * factor); } return out; }\n\
+             function shout(s) { return s.trim() + \"!\"; }\n\
+             function add(a, b) { return a + b; }\n\
+             function main() { return add(scale([1], 2).length, 1); }",
+        );
+        let numbers = JsType::Array(Box::new(JsType::Number));
+        assert_eq!(param_types(&ast.functions[0]), [numbers.clone(), JsType::Number]);
+        assert_eq!(ast.functions[0].return_type, numbers);
+        assert_eq!(param_types(&ast.functions[1]), [JsType::String]);
+        assert_eq!(ast.functions[1].return_type, JsType::String);
+        assert_eq!(param_types(&ast.functions[2]), [JsType::Number, JsType::Number]);
+        assert_eq!(ast.functions[3].return_type, JsType::Number);
+    }
+
+    #[test]
+    fn test_polymorphic_parameters_become_generic() {
+        let ast = inferred(
+            "function same(a, b) { return a === b; }\n\
+             function firstOf(xs) { return xs[0]; }\n\
+             function field(o) { return o.name; }\n\
+             function quiet(x) { if (x > 1) { return; } }\n\
+             function main() { same(1, 2); same(\"a\", \"b\"); }",
+        );
+        let t = JsType::Named("T".to_string(), vec![]);
+        assert_eq!(ast.functions[0].type_params, ["T: Clone + PartialEq"]);
+        assert_eq!(param_types(&ast.functions[0]), [t.clone(), t.clone()]);
+        assert_eq!(ast.functions[0].return_type, JsType::Boolean);
+        assert_eq!(param_types(&ast.functions[1]), [JsType::Array(Box::new(t.clone()))]);
+        assert_eq!(ast.functions[1].return_type, t);
+        // Fields read off a value leave it dynamic
+        assert_eq!(param_types(&ast.functions[2]), [JsType::Unknown]);
+        assert!(ast.functions[2].type_params.is_empty());
+        assert_eq!(ast.functions[3].return_type, JsType::Void);
+    }
+}
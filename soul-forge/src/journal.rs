@@ -0,0 +1,107 @@
+// 📓 Batch Journal - Where an Interrupted Batch Picks Up
+use crate::TransmutationError;
+use serde::{Deserialize, Serialize};
+
+/// How a package fared in a batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum JobState {
+    /// Transmuted; later runs skip it
+    Done { version: String, resonance: f64 },
+    /// Failed every attempt so far, for the reason the last one gave
+    Failed { reason: String, attempts: u32 },
+}
+
+/// The state of each package in one batch, kept in the forge's database so
+/// it outlives the process
+#[derive(Clone)]
+pub struct Journal {
+    tree: sled::Tree,
+}
+
+impl Journal {
+    pub(crate) fn open(db: &sled::Db, batch: &str) -> Result<Self, TransmutationError> {
+        let tree = db.open_tree(format!("journal:{}", batch)).map_err(db_error)?;
+        Ok(Self { tree })
+    }
+
+    /// The journal of every batch in `db`
+    pub(crate) fn all(db: &sled::Db) -> Result<Vec<Self>, TransmutationError> {
+        db.tree_names().into_iter()
+            .filter(|name| name.starts_with(b"journal:"))
+            .map(|name| db.open_tree(name).map(|tree| Self { tree }).map_err(db_error))
+            .collect()
+    }
+
+    pub fn get(&self, package: &str) -> Result<Option<JobState>, TransmutationError> {
+        match self.tree.get(package).map_err(db_error)? {
+            Some(value) => bincode::deserialize(&value).map(Some).map_err(|e| TransmutationError::SerializationError(e.to_string().into())),
+            None => Ok(None),
+        }
+    }
+
+    /// Record `state`, flushed to disk before returning so an interruption
+    /// right after can't lose it
+    pub fn record(&self, package: &str, state: &JobState) -> Result<(), TransmutationError> {
+        let value = bincode::serialize(state).map_err(|e| TransmutationError::SerializationError(e.to_string().into()))?;
+        self.tree.insert(package, value).map_err(db_error)?;
+        self.tree.flush().map_err(db_error)?;
+        Ok(())
+    }
+
+    /// Record another failed attempt at `package`
+    pub fn fail(&self, package: &str, reason: &str) -> Result<JobState, TransmutationError> {
+        let attempts = match self.get(package)? {
+            Some(JobState::Failed { attempts, .. }) => attempts + 1,
+            _ => 1,
+        };
+        let state = JobState::Failed { reason: reason.to_string(), attempts };
+        self.record(package, &state)?;
+        Ok(state)
+    }
+
+    /// Every package recorded, by name
+    pub fn entries(&self) -> Result<Vec<(String, JobState)>, TransmutationError> {
+        self.tree
+            .iter()
+            .map(|entry| {
+                let (key, value) = entry.map_err(db_error)?;
+                let state = bincode::deserialize(&value).map_err(|e| TransmutationError::SerializationError(e.to_string().into()))?;
+                Ok((String::from_utf8_lossy(&key).into_owned(), state))
+            })
+            .collect()
+    }
+
+    /// Forget the batch, so the next run starts over
+    pub fn clear(&self) -> Result<(), TransmutationError> {
+        self.tree.clear().map_err(db_error)
+    }
+}
+
+fn db_error(error: sled::Error) -> TransmutationError {
+    TransmutationError::DatabaseError(error.to_string().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_counts_attempts_until_done() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let journal = Journal::open(&db, "crates").unwrap();
+        assert_eq!(journal.get("left-pad").unwrap(), None);
+
+        journal.fail("left-pad", "Fetch error: timed out").unwrap();
+        let state = journal.fail("left-pad", "Parse error: unexpected token").unwrap();
+        assert_eq!(state, JobState::Failed { reason: "Parse error: unexpected token".to_string(), attempts: 2 });
+
+        let done = JobState::Done { version: "1.3.0".to_string(), resonance: 0.5 };
+        journal.record("left-pad", &done).unwrap();
+        assert_eq!(journal.entries().unwrap(), [("left-pad".to_string(), done)]);
+
+        // Batches keep separate journals
+        assert!(Journal::open(&db, "other").unwrap().entries().unwrap().is_empty());
+        journal.clear().unwrap();
+        assert_eq!(journal.get("left-pad").unwrap(), None);
+    }
+}
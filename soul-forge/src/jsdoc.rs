@@ -0,0 +1,349 @@
+// 📜 JSDoc - Carrying the Source's Documentation Across
+use crate::parse::parse_expression;
+use crate::sourcemap::Origins;
+use crate::{JsExpr, RustFunction, RustStruct};
+use std::collections::HashMap;
+
+/// A `/** ... */` comment, read into what rustdoc has a place for
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct JsDoc {
+    /// The text before the first tag
+    description: String,
+    /// `@param`s, by name
+    params: Vec<(String, String)>,
+    returns: Option<String>,
+    /// `@throws`, with the error class when one is named
+    throws: Vec<(Option<String>, String)>,
+    examples: Vec<String>,
+    deprecated: Option<String>,
+}
+
+impl JsDoc {
+    /// Read a JSDoc comment, given what's between its `/**` and `*/`
+    pub(crate) fn parse(comment: &str) -> Self {
+        // Each line loses its leading ` * `, keeping any indentation past it
+        let lines = comment.lines().map(|line| {
+            let line = line.trim_start();
+            let line = line.strip_prefix('*').unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line).trim_end()
+        });
+
+        let mut doc = Self::default();
+        let mut blocks: Vec<(&str, Vec<&str>)> = vec![("", vec![])];
+        for line in lines {
+            match line.strip_prefix('@') {
+                Some(tagged) => {
+                    let (tag, rest) = tagged.split_once(char::is_whitespace).unwrap_or((tagged, ""));
+                    blocks.push((tag, vec![rest.trim_start()]));
+                }
+                None => blocks.last_mut().unwrap().1.push(line),
+            }
+        }
+        for (tag, lines) in blocks {
+            let text = lines.join("\n").trim().to_string();
+            let prose = lines.iter().map(|line| line.trim()).filter(|line| !line.is_empty()).collect::<Vec<_>>().join(" ");
+            match tag {
+                "" => doc.description = links(&text),
+                "param" | "arg" | "argument" => {
+                    let (_, rest) = braced(&prose);
+                    let (name, description) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                    // `[name=default]` marks an optional parameter
+                    let name = name.trim_start_matches('[').split(['=', ']']).next().unwrap_or_default();
+                    if !name.is_empty() && !name.contains('.') {
+                        doc.params.push((name.to_string(), links(description.trim_start_matches(['-', ' ']))));
+                    }
+                }
+                "returns" | "return" => doc.returns = Some(links(braced(&prose).1)),
+                "throws" | "exception" => {
+                    let (class, description) = braced(&prose);
+                    doc.throws.push((class.map(str::to_string), links(description)));
+                }
+                "example" => {
+                    // A `<caption>` line titles the example rather than being part of it
+                    let code = match lines[0].starts_with("<caption>") {
+                        true => &lines[1..],
+                        false => &lines[..],
+                    };
+                    doc.examples.push(dedent(code));
+                }
+                "deprecated" => doc.deprecated = Some(prose),
+                _ => {}
+            }
+        }
+        doc
+    }
+}
+
+/// `lines` without the indentation they share or blank lines around them
+fn dedent(lines: &[&str]) -> String {
+    let lines: Vec<&str> = lines.iter().copied().skip_while(|line| line.is_empty()).collect();
+    let indent = lines.iter().filter(|line| !line.is_empty()).map(|line| line.len() - line.trim_start().len()).min().unwrap_or(0);
+    let lines: Vec<&str> = lines.iter().map(|line| line.get(indent..).unwrap_or_default()).collect();
+    lines.join("\n").trim_end().to_string()
+}
+
+/// A leading `{type}`, if there is one, and the text after it
+fn braced(text: &str) -> (Option<&str>, &str) {
+    let Some(rest) = text.strip_prefix('{') else { return (None, text) };
+    let mut depth = 1;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return (Some(&rest[..i]), rest[i + 1..].trim_start());
+        }
+    }
+    (None, text)
+}
+
+/// `{@link Target}` and `{@link Target text}` as code spans
+fn links(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{@link ") {
+        let Some(end) = rest[start..].find('}') else { break };
+        let target = rest[start + "{@link ".len()..start + end].split_whitespace().next().unwrap_or_default();
+        out.push_str(&rest[..start]);
+        out.push_str(&format!("`{}`", target));
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The JSDoc comments of `code`, by the offset of the declaration each
+/// documents: the first of `origins` after it with only keywords and names
+/// between them, like `export const name =`
+pub(crate) fn attach(code: &str, comments: &[(u32, u32)], origins: &[u32]) -> HashMap<u32, JsDoc> {
+    let mut docs = HashMap::new();
+    for &origin in origins {
+        let Some(&(start, end)) = comments.iter().rev().find(|(_, end)| *end <= origin) else { continue };
+        let between = &code[end as usize..origin as usize];
+        if !between.contains([';', '{', '}', '(', ')']) && !between.contains("/*") && !between.contains("//") {
+            docs.insert(origin, JsDoc::parse(&code[start as usize + 3..end as usize - 2]));
+        }
+    }
+    docs
+}
+
+/// Writes the rustdoc of generated items: what their JSDoc says, and where
+/// they came from
+pub(crate) struct DocWriter<'a> {
+    origins: Origins<'a>,
+    docs: HashMap<u32, JsDoc>,
+    /// The generated crate's name in paths, for doctests to import from
+    crate_ident: String,
+}
+
+impl<'a> DocWriter<'a> {
+    pub(crate) fn new(origins: Origins<'a>, docs: HashMap<u32, JsDoc>, crate_name: &str) -> Self {
+        Self { origins, docs, crate_ident: crate_name.replace('-', "_") }
+    }
+
+    /// The doc comment and attributes of `function`, a method of `owner` if given
+    pub(crate) fn function(&self, function: &RustFunction, owner: Option<&str>) -> String {
+        let doc = function.origin.and_then(|origin| self.docs.get(&origin));
+        let mut paragraphs = vec![];
+        if let Some(doc) = doc.filter(|doc| !doc.description.is_empty()) {
+            paragraphs.push(doc.description.clone());
+        }
+        paragraphs.extend(function.origin.map(|origin| self.origins.describe(origin)));
+
+        let Some(doc) = doc else { return comment(&paragraphs) };
+        let params: Vec<String> = doc.params.iter()
+            .map(|(name, description)| match description.is_empty() {
+                true => format!("* `{}`", name),
+                false => format!("* `{}` - {}", name, description),
+            })
+            .collect();
+        if !params.is_empty() {
+            paragraphs.push(format!("# Arguments\n\n{}", params.join("\n")));
+        }
+        if let Some(returns) = doc.returns.as_ref().filter(|returns| !returns.is_empty()) {
+            paragraphs.push(format!("# Returns\n\n{}", returns));
+        }
+        let throws: Vec<String> = doc.throws.iter()
+            .map(|(class, description)| match class {
+                Some(class) => format!("* `JsError::{}` {}", class, description).trim_end().to_string(),
+                None => format!("* {}", description),
+            })
+            .collect();
+        if !throws.is_empty() {
+            paragraphs.push(format!("# Errors\n\n{}", throws.join("\n")));
+        }
+        if !doc.examples.is_empty() {
+            let examples: Vec<String> = doc.examples.iter()
+                .map(|example| match self.doctest(example, function, owner) {
+                    Some(test) => format!("```\n{}\n```", test),
+                    None => format!("```js\n{}\n```", example),
+                })
+                .collect();
+            paragraphs.push(format!("# Examples\n\n{}", examples.join("\n\n")));
+        }
+
+        let mut code = comment(&paragraphs);
+        if let Some(note) = &doc.deprecated {
+            code.push_str(&match note.is_empty() {
+                true => "#[deprecated]\n".to_string(),
+                false => format!("#[deprecated(note = {:?})]\n", note),
+            });
+        }
+        code
+    }
+
+    /// The doc comment of a class's struct: its JSDoc description, then what
+    /// the forge notes about it
+    pub(crate) fn structure(&self, structure: &RustStruct) -> String {
+        let description = structure.origin
+            .and_then(|origin| self.docs.get(&origin))
+            .map(|doc| doc.description.clone())
+            .filter(|description| !description.is_empty());
+        comment(&description.into_iter().chain(structure.doc.clone()).collect::<Vec<_>>())
+    }
+
+    /// `example` as a doctest, when it's a series of calls to `function`,
+    /// each maybe followed by `// => value`, with literals the function's
+    /// Rust types can take
+    fn doctest(&self, example: &str, function: &RustFunction, owner: Option<&str>) -> Option<String> {
+        if function.is_async || function.receiver.is_some() || !function.is_pub {
+            return None;
+        }
+        let (returns, fallible) = match function.return_type.strip_prefix("Result<").and_then(|ty| ty.strip_suffix(", JsError>")) {
+            Some(ty) => (ty, true),
+            None => (function.return_type.as_str(), false),
+        };
+        let path = owner.map_or_else(|| function.name.clone(), |owner| format!("{}::{}", owner, function.name));
+
+        let mut lines = vec![format!("use {}::{};", self.crate_ident, owner.unwrap_or(&function.name)), String::new()];
+        for (call, expected) in calls(example)? {
+            let JsExpr::Call(callee, args) = call else { return None };
+            let calls_function = match (owner, *callee) {
+                (None, JsExpr::Ident(name)) => name == function.name,
+                (Some(owner), JsExpr::Member(object, name)) => name == function.name && matches!(*object, JsExpr::Ident(class) if class == owner),
+                _ => false,
+            };
+            if !calls_function || args.len() > function.params.len() {
+                return None;
+            }
+            // Arguments left out are `undefined`, which only an optional parameter takes
+            let args = function.params.iter().enumerate()
+                .map(|(i, param)| literal(args.get(i).unwrap_or(&JsExpr::Undefined), &param.ty))
+                .collect::<Option<Vec<_>>>()?;
+            let call = format!("{}({}){}", path, args.join(", "), if fallible { ".unwrap()" } else { "" });
+            lines.push(match expected {
+                Some(expected) => format!("assert_eq!({}, {});", call, literal(&expected, returns)?),
+                None => format!("{};", call),
+            });
+        }
+        Some(lines.join("\n"))
+    }
+}
+
+/// `paragraphs` as `///` lines
+fn comment(paragraphs: &[String]) -> String {
+    paragraphs.join("\n\n")
+        .lines()
+        .map(|line| match line.is_empty() {
+            true => "///\n".to_string(),
+            false => format!("/// {}\n", line),
+        })
+        .collect()
+}
+
+/// The calls of an example, each with what a `// =>` comment after it, or
+/// on the next line, says it gives; imports are skipped, and a
+/// `console.log(..)` stands for what it logs
+fn calls(example: &str) -> Option<Vec<(JsExpr, Option<JsExpr>)>> {
+    let mut calls: Vec<(JsExpr, Option<JsExpr>)> = vec![];
+    for line in example.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if line.starts_with("import ") || line.contains("require(") {
+            continue;
+        }
+        let (code, comment) = line.split_once("//").map_or((line, None), |(code, comment)| (code, Some(comment.trim())));
+        let expected = match comment.and_then(|comment| comment.strip_prefix("=>")) {
+            Some(value) => Some(parse_expression(value.trim())?),
+            None => None,
+        };
+        let code = code.trim().trim_end_matches(';');
+        if code.is_empty() {
+            if expected.is_some() {
+                calls.last_mut()?.1 = expected;
+            }
+            continue;
+        }
+        let call = match parse_expression(code)? {
+            JsExpr::Call(callee, mut args) if args.len() == 1 && matches!(&*callee, JsExpr::Member(object, method) if method == "log" && matches!(&**object, JsExpr::Ident(name) if name == "console")) => args.remove(0),
+            call => call,
+        };
+        calls.push((call, expected));
+    }
+    Some(calls).filter(|calls| !calls.is_empty())
+}
+
+/// A JS literal as a Rust expression of type `ty`
+fn literal(expr: &JsExpr, ty: &str) -> Option<String> {
+    if let Some(inner) = ty.strip_prefix("Option<").and_then(|ty| ty.strip_suffix('>')) {
+        return match expr {
+            JsExpr::Null | JsExpr::Undefined => Some("None".to_string()),
+            expr => literal(expr, inner).map(|value| format!("Some({})", value)),
+        };
+    }
+    if let Some(inner) = ty.strip_prefix("Vec<").and_then(|ty| ty.strip_suffix('>')) {
+        let JsExpr::Array(items) = expr else { return None };
+        let items = items.iter().map(|item| literal(item, inner)).collect::<Option<Vec<_>>>()?;
+        return Some(format!("vec![{}]", items.join(", ")));
+    }
+    match (ty, expr) {
+        ("f64", JsExpr::Number(n)) => Some(format!("{:?}", n)),
+        ("f64", JsExpr::Unary("-", inner)) => literal(inner, ty).map(|value| format!("-{}", value)),
+        ("String", JsExpr::String(s)) => Some(format!("{:?}.to_string()", s)),
+        ("bool", JsExpr::Bool(b)) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags_are_read() {
+        let doc = JsDoc::parse("*\n * Split `xs` into runs, like {@link chunkBy}.\n *\n * @param {number[]} xs - The values\n * @param {number} [size=1] How long each run is\n * @returns {number[][]} The runs\n * @throws {RangeError} when size is 0\n * @example\n *   chunk([1, 2, 3], 2)\n *   // => [[1, 2], [3]]\n * @deprecated Use `runs`\n ");
+        assert_eq!(doc.description, "Split `xs` into runs, like `chunkBy`.");
+        assert_eq!(doc.params, [("xs".to_string(), "The values".to_string()), ("size".to_string(), "How long each run is".to_string())]);
+        assert_eq!(doc.returns.as_deref(), Some("The runs"));
+        assert_eq!(doc.throws, [(Some("RangeError".to_string()), "when size is 0".to_string())]);
+        assert_eq!(doc.examples, ["chunk([1, 2, 3], 2)\n// => [[1, 2], [3]]"]);
+        assert_eq!(doc.deprecated.as_deref(), Some("Use `runs`"));
+    }
+
+    #[test]
+    fn test_examples_become_doctests_where_types_allow() {
+        let function = |return_type: &str| RustFunction {
+            name: "chunk".to_string(),
+            generics: vec![],
+            receiver: None,
+            params: vec![
+                crate::RustParam { name: "xs".to_string(), ty: "Vec<f64>".to_string() },
+                crate::RustParam { name: "size".to_string(), ty: "Option<f64>".to_string() },
+            ],
+            return_type: return_type.to_string(),
+            body: String::new(),
+            is_async: false,
+            is_pub: true,
+            origin: None,
+        };
+        let writer = DocWriter::new(Origins::new("index.js", ""), HashMap::new(), "chunk-soul");
+        let example = "import { chunk } from 'chunk';\nchunk([1, 2, 3], 2) // => [[1, 2], [3]]\nconsole.log(chunk([-1]))\n// => [[-1]]";
+        assert_eq!(
+            writer.doctest(example, &function("Vec<Vec<f64>>"), None).unwrap(),
+            "use chunk_soul::chunk;\n\nassert_eq!(chunk(vec![1.0, 2.0, 3.0], Some(2.0)), vec![vec![1.0, 2.0], vec![3.0]]);\nassert_eq!(chunk(vec![-1.0], None), vec![vec![-1.0]]);"
+        );
+        // Generic results, and calls to anything else, stay JavaScript
+        assert_eq!(writer.doctest(example, &function("Vec<Vec<T>>"), None), None);
+        assert_eq!(writer.doctest("chunks([1], 1)", &function("Vec<Vec<f64>>"), None), None);
+    }
+}
@@ -0,0 +1,320 @@
+// 📐 Code Layout - Bodies Shaped Like Their Source
+use crate::body::each_stmt_expr;
+use crate::{JsAst, JsExpr, JsFunction, JsParam, JsPattern, JsStmt, JsType};
+use protein_hash::TopologyFeatures;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// Array methods that run a callback on each element
+const CALLBACKS: &[&str] = &["forEach", "map", "filter", "reduce", "find", "findIndex", "some", "every", "flatMap"];
+
+/// Arms per branch from which a source reads as `if`/`else` rather than guards
+const NESTED_BRANCHING: f64 = 1.5;
+
+/// The nesting from which a source counts as nested, a body's own
+/// statements being at 0
+const NESTED_DEPTH: usize = 2;
+
+/// How a module's functions and methods are shaped, measured on their
+/// statements
+///
+/// protein-hash measures topology on a code graph, and leaves it at zero for
+/// JS; this is the same topology read off the parsed source instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct Shape {
+    /// `if` chains and `switch`es
+    branches: usize,
+    /// Their arms: one per link of an `if` chain, one more for its `else`,
+    /// one per `case`
+    arms: usize,
+    /// The deepest statement
+    depth: usize,
+    loops: usize,
+    /// Array methods called with a callback, like `xs.map(x => ..)`
+    pub(crate) callbacks: usize,
+}
+
+impl Shape {
+    pub(crate) fn of(asts: &[JsAst]) -> Self {
+        let mut shape = Self::default();
+        let methods = asts.iter().flat_map(|ast| &ast.classes).flat_map(|class| class.methods.iter().map(|method| &method.function));
+        for function in asts.iter().flat_map(|ast| &ast.functions).chain(methods) {
+            shape.block(&function.body, 0);
+            for stmt in &function.body {
+                each_stmt_expr(stmt, &mut |expr| {
+                    if let JsExpr::Call(callee, args) = expr {
+                        if let (JsExpr::Member(_, method), Some(JsExpr::Function(_))) = (&**callee, args.first()) {
+                            shape.callbacks += usize::from(CALLBACKS.contains(&method.as_str()));
+                        }
+                    }
+                    true
+                });
+            }
+        }
+        shape
+    }
+
+    fn block(&mut self, body: &[JsStmt], depth: usize) {
+        if !body.is_empty() {
+            self.depth = self.depth.max(depth);
+        }
+        body.iter().for_each(|stmt| self.stmt(stmt, depth));
+    }
+
+    fn stmt(&mut self, stmt: &JsStmt, depth: usize) {
+        match stmt {
+            JsStmt::If { then, otherwise, .. } => {
+                self.branches += 1;
+                let (mut then, mut otherwise) = (then, otherwise);
+                loop {
+                    self.arms += 1;
+                    self.block(then, depth + 1);
+                    match otherwise.as_slice() {
+                        [] => break,
+                        [JsStmt::If { then: next, otherwise: rest, .. }] => (then, otherwise) = (next, rest),
+                        last => {
+                            self.arms += 1;
+                            self.block(last, depth + 1);
+                            break;
+                        }
+                    }
+                }
+            }
+            JsStmt::Switch { cases, .. } => {
+                self.branches += 1;
+                self.arms += cases.len();
+                cases.iter().for_each(|case| self.block(&case.body, depth + 1));
+            }
+            JsStmt::While { body, .. }
+            | JsStmt::DoWhile { body, .. }
+            | JsStmt::For { body, .. }
+            | JsStmt::ForOf { body, .. }
+            | JsStmt::ForIn { body, .. } => {
+                self.loops += 1;
+                self.block(body, depth + 1);
+            }
+            JsStmt::Block(body) => self.block(body, depth + 1),
+            JsStmt::Labeled(_, body) => self.stmt(body, depth),
+            JsStmt::Try { block, handler, finalizer, .. } => {
+                for body in [block, handler.as_deref().unwrap_or_default(), finalizer] {
+                    self.block(body, depth + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn branching_factor(&self) -> f64 {
+        match self.branches {
+            0 => 0.0,
+            branches => self.arms as f64 / branches as f64,
+        }
+    }
+
+    /// Fill in the measures `features` was extracted without
+    pub(crate) fn fill(&self, features: &mut TopologyFeatures) {
+        if features.branching_factor == 0.0 && features.nesting_depth == 0 && features.loop_complexity == 0 {
+            features.branching_factor = self.branching_factor();
+            features.nesting_depth = self.depth;
+            features.loop_complexity = self.loops;
+        }
+    }
+}
+
+/// How bodies are laid out where Rust offers a choice the source's
+/// statements don't settle, following the source's topology so the Rust
+/// soul starts out close to it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Layout {
+    pub(crate) iteration: Iteration,
+    pub(crate) returns: Returns,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Iteration {
+    #[default]
+    AsWritten,
+    /// `xs.forEach(x => ..)` becomes `for x in xs.iter()`, in a source that
+    /// loops more than it calls back
+    Loops,
+    /// `for (const x of xs)` becomes `xs.iter().for_each(|x| ..)`, in a
+    /// source that calls back more than it loops
+    Chains,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Returns {
+    #[default]
+    AsWritten,
+    /// A body ending in `if (c) { return a; } else { return b; }` returns `a`
+    /// early and ends in `b`, in a source of guards
+    Early,
+    /// A body ending in `if (c) return a; return b;` ends in the tail
+    /// `if c { a } else { b }`, in a source that nests `if`/`else`
+    Nested,
+}
+
+impl Layout {
+    /// The layout a source of this topology, calling back `callbacks` times,
+    /// asks for; what it doesn't settle stays as written
+    pub(crate) fn of(features: &TopologyFeatures, callbacks: usize) -> Self {
+        let iteration = match features.loop_complexity.cmp(&callbacks) {
+            Ordering::Greater => Iteration::Loops,
+            Ordering::Less => Iteration::Chains,
+            Ordering::Equal => Iteration::AsWritten,
+        };
+        let returns = if features.branching_factor == 0.0 {
+            Returns::AsWritten
+        } else if features.branching_factor < NESTED_BRANCHING {
+            Returns::Early
+        } else if features.nesting_depth >= NESTED_DEPTH {
+            Returns::Nested
+        } else {
+            Returns::AsWritten
+        };
+        Self { iteration, returns }
+    }
+
+    /// A function body with its trailing returns laid out
+    pub(crate) fn arrange<'b>(&self, body: &'b [JsStmt]) -> Cow<'b, [JsStmt]> {
+        match self.returns {
+            Returns::AsWritten => Cow::Borrowed(body),
+            Returns::Early => match body {
+                [rest @ .., JsStmt::If { test, then, otherwise }] if !otherwise.is_empty() && leaves(then) => {
+                    let mut arranged = rest.to_vec();
+                    arranged.push(JsStmt::If { test: test.clone(), then: then.clone(), otherwise: vec![] });
+                    arranged.extend(self.arrange(otherwise).into_owned());
+                    Cow::Owned(arranged)
+                }
+                body => Cow::Borrowed(body),
+            },
+            Returns::Nested => {
+                let mut arranged = Cow::Borrowed(body);
+                // Guards fold into the tail from the last back
+                while let [rest @ .., JsStmt::If { test, then, otherwise }, last] = &*arranged {
+                    if !otherwise.is_empty() || !returns_value(then) || !returns_value(std::slice::from_ref(last)) {
+                        break;
+                    }
+                    let mut folded = rest.to_vec();
+                    folded.push(JsStmt::If { test: test.clone(), then: then.clone(), otherwise: vec![last.clone()] });
+                    arranged = Cow::Owned(folded);
+                }
+                arranged
+            }
+        }
+    }
+
+    /// `stmt` as the other kind of iteration, when this layout turns it
+    ///
+    /// Only bodies a closure can hold as they are turn: no `return`, `break`,
+    /// `continue` or `await`. A `for .. of` turns only over arrays, which
+    /// `is_array` says the iterable is.
+    pub(crate) fn relay(&self, stmt: &JsStmt, is_array: impl FnOnce(&JsExpr) -> bool) -> Option<JsStmt> {
+        match (self.iteration, stmt) {
+            (Iteration::Loops, JsStmt::Expr(JsExpr::Call(callee, args))) => {
+                let (JsExpr::Member(items, method), [JsExpr::Function(callback)]) = (&**callee, args.as_slice()) else { return None };
+                let [param] = callback.params.as_slice() else { return None };
+                if method != "forEach" || callback.is_async || param.rest || jumps(&callback.body) {
+                    return None;
+                }
+                Some(JsStmt::ForOf { pattern: JsPattern::Ident(param.name.clone()), iterable: (**items).clone(), body: callback.body.clone() })
+            }
+            (Iteration::Chains, JsStmt::ForOf { pattern: JsPattern::Ident(name), iterable, body }) => {
+                if jumps(body) || awaits(body) || !is_array(iterable) {
+                    return None;
+                }
+                let callback = JsFunction {
+                    name: String::new(),
+                    params: vec![JsParam { name: name.clone(), ty: JsType::Unknown, rest: false }],
+                    return_type: JsType::Void,
+                    type_params: vec![],
+                    is_async: false,
+                    is_generator: false,
+                    body: body.clone(),
+                    origin: None,
+                };
+                let for_each = JsExpr::Member(Box::new(iterable.clone()), "forEach".to_string());
+                Some(JsStmt::Expr(JsExpr::Call(Box::new(for_each), vec![JsExpr::Function(Box::new(callback))])))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Whether `body` always leaves by its last statement
+fn leaves(body: &[JsStmt]) -> bool {
+    matches!(body.last(), Some(JsStmt::Return(_) | JsStmt::Throw(_)))
+}
+
+/// Whether every way out of `body` returns a value, as a tail can
+pub(crate) fn returns_value(body: &[JsStmt]) -> bool {
+    match body.last() {
+        Some(JsStmt::Return(Some(_))) => true,
+        Some(JsStmt::If { then, otherwise, .. }) => returns_value(then) && returns_value(otherwise),
+        _ => false,
+    }
+}
+
+/// Whether `body` has a `return`, `break` or `continue` outside closures
+fn jumps(body: &[JsStmt]) -> bool {
+    body.iter().any(|stmt| match stmt {
+        JsStmt::Return(_) | JsStmt::Break(_) | JsStmt::Continue(_) => true,
+        JsStmt::If { then, otherwise, .. } => jumps(then) || jumps(otherwise),
+        JsStmt::While { body, .. }
+        | JsStmt::DoWhile { body, .. }
+        | JsStmt::For { body, .. }
+        | JsStmt::ForOf { body, .. }
+        | JsStmt::ForIn { body, .. }
+        | JsStmt::Block(body) => jumps(body),
+        JsStmt::Labeled(..) | JsStmt::Switch { .. } | JsStmt::Try { .. } => true,
+        _ => false,
+    })
+}
+
+fn awaits(body: &[JsStmt]) -> bool {
+    let mut awaits = false;
+    for stmt in body {
+        each_stmt_expr(stmt, &mut |expr| {
+            awaits |= matches!(expr, JsExpr::Await(_));
+            !matches!(expr, JsExpr::Function(_))
+        });
+    }
+    awaits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_source;
+    use crate::SourceKind;
+
+    #[test]
+    fn test_shapes_measure_the_source() {
+        let ast = parse_source(
+            "function sign(x) { if (x > 0) { return 1; } else if (x < 0) { return -1; } else { return 0; } }\n\
+             function total(xs) { let t = 0; for (const x of xs) { if (x) { t += x; } } return t; }",
+            SourceKind::JavaScript,
+        ).unwrap();
+        let shape = Shape::of(&[ast]);
+        assert_eq!((shape.branches, shape.arms, shape.depth, shape.loops), (2, 4, 2, 1));
+
+        let mut features = TopologyFeatures {
+            has_cycles: false,
+            has_recursion: false,
+            branching_factor: 0.0,
+            nesting_depth: 0,
+            loop_complexity: 0,
+            is_dag: true,
+            strongly_connected_components: 0,
+            topological_signature: String::new(),
+            cycle_count: 0,
+            max_cycle_size: 0,
+            recursion_depth: 0,
+            connectivity_score: 0.0,
+            recursive_functions: vec![],
+        };
+        shape.fill(&mut features);
+        assert_eq!(features.branching_factor, 2.0);
+        assert_eq!(Layout::of(&features, 0), Layout { iteration: Iteration::Loops, returns: Returns::Nested });
+    }
+}
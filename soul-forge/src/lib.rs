@@ -2,13 +2,68 @@
 // "Same soul, different body. Perfect harmony."
 
 use async_trait::async_trait;
-use protein_hash::{Soul, SoulExtractor, souls_match};
+use protein_hash::{Language, Soul, SoulExtractor, souls_match};
 use quote::quote;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use syn::{parse_str, File, Item};
 
+mod addon;
+pub mod bench;
+mod body;
+mod check;
+mod classes;
+pub mod dependencies;
+mod diagnostics;
+mod drift;
+mod equivalence;
+mod errors;
+mod ffi;
+mod generators;
+mod infer;
+mod jsdoc;
+mod journal;
+mod layout;
+pub mod merge;
+mod license;
+mod modules;
+pub mod npm;
+mod numeric;
+pub mod pairs;
+mod parse;
+mod plan;
+pub mod plugin;
+mod profile;
+pub mod publish;
+mod query;
+mod refine;
+mod report;
+pub mod schema;
+mod session;
+pub mod snapshot;
+mod sourcemap;
+mod stats;
+mod strings;
+
+pub use bench::{Benches, Benchmark};
+pub use check::Verification;
+pub use dependencies::{CrateDependency, DependencySource};
+pub use diagnostics::{Diagnostic, ErrorFormat, Severity, Span};
+pub use equivalence::Fixture;
+pub use journal::{JobState, Journal};
+pub use modules::JsModule;
+pub use numeric::{NumericPolicy, Overflow};
+pub use parse::SourceKind;
+pub use plan::{Plan, PlannedItem};
+pub use plugin::TransformPass;
+pub use stats::{Bucket, Failure, Stats, Trend};
+pub use profile::Profile;
+pub use query::TransmutationFilter;
+pub use refine::Action;
+pub use session::{Breakdown, Step};
+
 /// The golden ratio - perfect proportion
 const PHI: f64 = 1.618033988749895;
 
@@ -24,14 +79,41 @@ pub struct Transmutation {
     /// Generated Rust code
     pub rust_code: String,
     
-    /// WASM bindings
-    pub wasm_bindings: String,
+    /// The bindings file the crate is reached through: `bindings.js` over
+    /// wasm-pack's output, `index.js` loading a napi-rs addon, or a C header;
+    /// empty for a plain library
+    pub js_bindings: String,
     
     /// Resonance score (how well souls match)
     pub resonance: f64,
     
     /// Package metadata
     pub metadata: PackageMetadata,
+    
+    /// `tests/equivalence.rs`, asserting the crate returns what the source did
+    pub equivalence_tests: Option<String>,
+    
+    /// Share of the equivalence cases the crate passes, when they were run
+    pub pass_rate: Option<f64>,
+    
+    /// When it was made, in seconds since the Unix epoch
+    pub transmuted_at: u64,
+    
+    /// Labels given with `SoulForge::retag_transmutation`
+    pub tags: Vec<String>,
+    
+    /// Source Map v3 from `rust_code` back to the source, as JSON
+    pub source_map: String,
+    
+    /// `benches/`, timing the crate and its source on the same calls
+    pub benches: Option<Benches>,
+    
+    /// What `forge bench` last measured of them
+    pub benchmarks: Vec<Benchmark>,
+    
+    /// Constructs in `rust_code` known to pull its soul away from the
+    /// source's, each with the rewrite that would bring it back
+    pub warnings: Vec<Diagnostic>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +123,141 @@ pub struct PackageMetadata {
     pub description: String,
     pub npm_name: String,
     pub crate_name: String,
+    /// Path of the module transmuted, as source maps and doc comments name
+    /// it; the npm name when unset
+    pub source_file: Option<String>,
+    /// How JS reaches the crate
+    pub bindings: Bindings,
+    /// What the crate is built as
+    pub profile: Profile,
+    /// The package's license as npm gives it; Cargo.toml gets it when it's
+    /// an SPDX expression
+    pub license: Option<String>,
+    pub authors: Vec<String>,
+    pub repository: Option<String>,
+    pub keywords: Vec<String>,
+}
+
+impl PackageMetadata {
+    /// Take the license, authors, repository and keywords from the
+    /// package's `package.json`
+    pub fn with_package_json(mut self, manifest: &serde_json::Value) -> Self {
+        self.license = npm::license(manifest);
+        self.authors = npm::authors(manifest);
+        self.repository = npm::repository(manifest);
+        self.keywords = npm::keywords(manifest);
+        self
+    }
+    
+    /// The file the bindings are written to, next to Cargo.toml, if the
+    /// crate has any
+    pub fn bindings_file(&self) -> Option<String> {
+        match (self.bindings, self.profile) {
+            (Bindings::Napi, _) | (Bindings::Wasm, Profile::WasmWeb | Profile::WasmNode) => Some(self.bindings.loader_file().to_string()),
+            (Bindings::Wasm, Profile::NativeLib) => None,
+            (Bindings::Wasm, Profile::CdylibFfi) => Some(ffi::header_file(&self.crate_name)),
+        }
+    }
+    
+    /// The license as Cargo's SPDX expression, if it can be one
+    pub fn spdx_license(&self) -> Option<String> {
+        self.license.as_deref().and_then(|license| license::spdx(license).ok())
+    }
+    
+    /// Why Cargo.toml leaves out the package's license, when it does
+    pub fn license_warning(&self) -> Option<String> {
+        let npm_license = self.license.as_deref()?;
+        license::spdx(npm_license).err().map(|reason| format!(
+            "{}'s license \"{}\" isn't carried into Cargo.toml: {}", self.npm_name, npm_license, reason
+        ))
+    }
+}
+
+/// A transmuted crate in a batch's workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceMember {
+    pub crate_name: String,
+    pub npm_name: String,
+    pub version: String,
+    pub resonance: f64,
+}
+
+/// Crates the generated Cargo.toml depends on, with the version each asks
+/// for and the async target that needs it, if only one does
+const DEPENDENCIES: &[(&str, &str, Option<AsyncTarget>)] = &[
+    ("wasm-bindgen", r#""0.2""#, None),
+    ("serde", r#"{ version = "1.0", features = ["derive"] }"#, None),
+    ("serde_json", r#""1.0""#, None),
+    ("serde-wasm-bindgen", r#""0.6""#, None),
+    ("futures", r#""0.3""#, None),
+    ("tokio", r#"{ version = "1", features = ["rt-multi-thread", "macros", "time"] }"#, Some(AsyncTarget::Native)),
+    ("wasm-bindgen-futures", r#""0.4""#, Some(AsyncTarget::Wasm)),
+    ("gloo-timers", r#"{ version = "0.3", features = ["futures"] }"#, Some(AsyncTarget::Wasm)),
+];
+
+/// Where transmuted async code runs
+///
+/// Decides the glue behind detached promises and timers, and the runtime
+/// crate the generated Cargo.toml depends on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AsyncTarget {
+    /// In the browser or another JS host, via wasm-bindgen-futures
+    #[default]
+    Wasm,
+    /// Natively, on tokio
+    Native,
+}
+
+impl std::str::FromStr for AsyncTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wasm" => Ok(Self::Wasm),
+            "native" => Ok(Self::Native),
+            other => Err(format!("unknown async target '{}' (expected wasm or native)", other)),
+        }
+    }
+}
+
+/// How JS calls into a transmuted crate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Bindings {
+    /// Compiled to WASM by wasm-pack, for browsers and Node alike
+    #[default]
+    Wasm,
+    /// Built by napi-rs into a native Node addon, `index.node`
+    Napi,
+}
+
+impl Bindings {
+    /// The file the JS loader is written to, next to Cargo.toml
+    pub fn loader_file(&self) -> &'static str {
+        match self {
+            Self::Wasm => "bindings.js",
+            Self::Napi => "index.js",
+        }
+    }
+
+    /// The crate's `build.rs`, if it needs one
+    pub fn build_script(&self) -> Option<&'static str> {
+        match self {
+            Self::Wasm => None,
+            Self::Napi => Some(addon::BUILD_SCRIPT),
+        }
+    }
+}
+
+impl std::str::FromStr for Bindings {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wasm" => Ok(Self::Wasm),
+            "napi" => Ok(Self::Napi),
+            other => Err(format!("unknown bindings '{}' (expected wasm or napi)", other)),
+        }
+    }
 }
 
 /// The Soul Forge - transforms JS souls into Rust bodies
@@ -54,8 +271,42 @@ pub struct SoulForge {
     /// Template engine
     templates: tera::Tera,
     
-    /// Transmutation cache
-    cache: HashMap<String, Transmutation>,
+    /// Souls extracted since the soul cache was last saved, by
+    /// `SoulExtractor::cache_key`; the extractor holds those saved before
+    cache: HashMap<String, Soul>,
+    
+    /// File souls are kept in across runs, when set
+    soul_cache: Option<PathBuf>,
+    
+    /// Runtime the generated async glue targets
+    async_target: AsyncTarget,
+    
+    /// Whether `number` stays `f64` or locals holding integers become `i64`
+    numeric: NumericPolicy,
+    
+    /// What arithmetic on those integers does past `i64`
+    overflow: Overflow,
+    
+    /// How the generated crate is checked before it is returned
+    verification: Verification,
+    
+    /// Which calls the generated crate is compared with its source on
+    equivalence: equivalence::Equivalence,
+    
+    /// Rounds of structural refinement toward the source's soul
+    max_refinements: usize,
+    
+    /// Rewrites run on each module between transforming and generating it
+    passes: Vec<Arc<dyn TransformPass>>,
+    
+    /// Whether crates get benchmarks against their source
+    benchmarks: bool,
+    
+    /// How bodies of the transmutation being drafted are laid out
+    layout: layout::Layout,
+    
+    /// Packages transmuted before, whose imports use their crates
+    dependencies: Vec<CrateDependency>,
 }
 
 impl SoulForge {
@@ -63,244 +314,951 @@ impl SoulForge {
         let mut templates = tera::Tera::default();
         
         // Add built-in templates
-        templates.add_raw_template("rust_module", RUST_MODULE_TEMPLATE)?;
-        templates.add_raw_template("wasm_bindings", WASM_BINDINGS_TEMPLATE)?;
-        templates.add_raw_template("cargo_toml", CARGO_TOML_TEMPLATE)?;
+        templates.add_raw_templates(TEMPLATES.to_vec())?;
+        
+        let db = sled::open(db_path)?;
+        schema::check(&db)?;
         
         Ok(Self {
             soul_extractor: SoulExtractor::new(),
-            db: sled::open(db_path)?,
+            db,
             templates,
             cache: HashMap::new(),
+            soul_cache: None,
+            async_target: AsyncTarget::default(),
+            numeric: NumericPolicy::default(),
+            overflow: Overflow::default(),
+            verification: Verification::default(),
+            equivalence: equivalence::Equivalence::default(),
+            max_refinements: refine::MAX_REFINEMENTS,
+            passes: vec![],
+            benchmarks: false,
+            layout: layout::Layout::default(),
+            dependencies: vec![],
         })
     }
     
+    /// Another forge with these settings on the same database, for a worker
+    /// thread of a batch
+    pub fn fork(&self) -> Self {
+        // The file loaded when this forge was built; souls saved to it since
+        // are only missed
+        let soul_extractor = SoulExtractor::new();
+        if let Some(path) = &self.soul_cache {
+            soul_extractor.load_soul_cache(path).ok();
+        }
+        Self {
+            soul_extractor,
+            db: self.db.clone(),
+            templates: self.templates.clone(),
+            cache: HashMap::new(),
+            soul_cache: self.soul_cache.clone(),
+            async_target: self.async_target,
+            numeric: self.numeric,
+            overflow: self.overflow,
+            verification: self.verification,
+            equivalence: self.equivalence.clone(),
+            max_refinements: self.max_refinements,
+            passes: self.passes.clone(),
+            benchmarks: self.benchmarks,
+            layout: layout::Layout::default(),
+            dependencies: self.dependencies.clone(),
+        }
+    }
+    
+    /// The journal of the batch named `batch`, in this forge's database
+    pub fn journal(&self, batch: &str) -> Result<Journal, TransmutationError> {
+        Journal::open(&self.db, batch)
+    }
+    
+    /// Render with the templates in `dir` in place of the built-in ones they
+    /// share a name with, like `cargo_toml.tera`; the rest stay built in
+    pub fn with_templates(mut self, dir: &Path) -> Result<Self, TransmutationError> {
+        // Tera says where a template fails to parse, and its source says why
+        let template_error = |e: tera::Error| {
+            let cause = std::error::Error::source(&e).map(|cause| format!(": {}", cause)).unwrap_or_default();
+            TransmutationError::TemplateError(format!("{}{}", e, cause).into())
+        };
+        let mut templates = tera::Tera::parse(&format!("{}/*.tera", dir.display())).map_err(template_error)?;
+        let names: Vec<String> = templates.get_template_names().map(str::to_string).collect();
+        if names.is_empty() {
+            return Err(TransmutationError::TemplateError(format!("no .tera templates in {}", dir.display()).into()));
+        }
+        if let Some(unknown) = names.iter().find(|name| !TEMPLATES.iter().any(|(known, _)| known == name)) {
+            let known: Vec<&str> = TEMPLATES.iter().map(|(name, _)| *name).collect();
+            return Err(TransmutationError::TemplateError(
+                Diagnostic::error(format!("{} overrides no template", unknown))
+                    .with_file(dir.join(unknown).display().to_string())
+                    .with_suggestion(format!("name it one of {}", known.join(", "))),
+            ));
+        }
+        templates.extend(&self.templates).map_err(template_error)?;
+        self.templates = templates;
+        Ok(self)
+    }
+    
+    /// Run `pass` on every module after the forge's own transform, after any
+    /// passes registered before it
+    pub fn with_pass(mut self, pass: impl TransformPass + 'static) -> Self {
+        self.passes.push(Arc::new(pass));
+        self
+    }
+    
+    /// Run the passes a plugin cdylib declares with `export_passes!`
+    pub fn with_plugin(mut self, path: &Path) -> Result<Self, TransmutationError> {
+        self.passes.extend(plugin::load(path)?);
+        Ok(self)
+    }
+    
+    /// Generate async glue for `target` instead of wasm-bindgen-futures
+    pub fn with_async_target(mut self, target: AsyncTarget) -> Self {
+        self.async_target = target;
+        self
+    }
+    
+    /// Type numbers by `policy`, trading the source's float semantics for
+    /// integer locals where they only ever hold integers
+    pub fn with_numeric_policy(mut self, policy: NumericPolicy) -> Self {
+        self.numeric = policy;
+        self
+    }
+    
+    /// Have integer arithmetic do `overflow` past `i64`, under
+    /// `NumericPolicy::IdiomaticInt`
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+    
+    /// Check each transmuted crate with cargo, refining it until it compiles
+    pub fn with_verification(mut self, verification: Verification) -> Self {
+        self.verification = verification;
+        self
+    }
+    
+    /// Run the source under Node and the crate under `cargo test` on cases
+    /// generated from each function's parameter types, reporting a pass rate
+    pub fn with_equivalence(mut self) -> Self {
+        self.equivalence = equivalence::Equivalence::Generated;
+        self
+    }
+    
+    /// Refine the generated code toward the source's soul for at most `rounds`
+    /// rounds; 0 leaves it as generated
+    pub fn with_max_refinements(mut self, rounds: usize) -> Self {
+        self.max_refinements = rounds;
+        self
+    }
+    
+    /// Generate `benches/` for each crate: criterion benchmarks of its
+    /// functions and a Node script timing the source on the same calls
+    pub fn with_benchmarks(mut self) -> Self {
+        self.benchmarks = true;
+        self
+    }
+    
+    /// Keep souls in `path` across runs, so a source or crate seen before,
+    /// byte for byte, isn't extracted again; each transmutation saves the
+    /// souls it extracted
+    pub fn with_soul_cache(mut self, path: &Path) -> Result<Self, TransmutationError> {
+        self.soul_extractor.load_soul_cache(path)
+            .map_err(|e| TransmutationError::DatabaseError(Diagnostic::error(format!("can't read soul cache: {}", e)).with_file(path.display().to_string())))?;
+        self.soul_cache = Some(path.to_path_buf());
+        Ok(self)
+    }
+    
+    /// Import the crates of `dependencies` where sources import their
+    /// packages, depending on the ones used, rather than leaving the imports
+    /// unresolved
+    pub fn with_dependencies(mut self, dependencies: Vec<CrateDependency>) -> Self {
+        self.dependencies = dependencies;
+        self
+    }
+    
+    /// Compare the source and the crate on `fixtures` rather than generated cases
+    pub fn with_fixtures(mut self, fixtures: Vec<Fixture>) -> Self {
+        self.equivalence = equivalence::Equivalence::Fixtures(fixtures);
+        self
+    }
+    
     /// Transmute JavaScript code into Rust
     pub async fn transmute(&mut self, js_code: &str, metadata: PackageMetadata) -> Result<Transmutation, TransmutationError> {
-        // Extract JavaScript soul
-        let js_soul = self.soul_extractor.extract_soul_js(js_code).await;
-        
-        // Parse JavaScript AST
-        let js_ast = self.parse_javascript(js_code)?;
+        self.transmute_source(js_code, SourceKind::JavaScript, metadata).await
+    }
+    
+    /// Transmute JavaScript or TypeScript code into Rust
+    pub async fn transmute_source(&mut self, js_code: &str, kind: SourceKind, metadata: PackageMetadata) -> Result<Transmutation, TransmutationError> {
+        self.transmute_modules(&[JsModule { path: PathBuf::new(), kind, code: js_code.to_string() }], metadata).await
+    }
+    
+    /// Transmute a package's modules into one crate: the first is the crate
+    /// root, and each other becomes a `pub mod` along its path, its imports
+    /// and re-exports `use`s of the others
+    ///
+    /// `JsModule::collect` gathers the modules an entry file reaches.
+    pub async fn transmute_modules(&mut self, modules: &[JsModule], metadata: PackageMetadata) -> Result<Transmutation, TransmutationError> {
+        let Draft { js_soul, rust_soul, mut rust_code, exports, subjects, addon_exports, ffi_exports, commonjs } = self.draft(modules, &metadata).await?;
         
-        // Transform to Rust AST
-        let rust_ast = self.transform_ast(js_ast)?;
+        // Wrap what Node or C can call, after refinement so the wrappers
+        // aren't weighed against the source's soul
+        rust_code.push_str(&addon::glue(&addon_exports));
+        rust_code.push_str(&ffi::glue(&metadata.crate_name, &ffi_exports));
         
-        // Generate Rust code
-        let rust_code = self.generate_rust_code(rust_ast)?;
+        // Check the crate compiles, refining it with what the compiler
+        // reports; an attempt that never does is kept for `forge stats`
+        let checked = (self.verification != Verification::Off).then_some(true);
+        let verified = match self.verify(&js_soul, &rust_soul, &rust_code, &metadata).await {
+            Err(e @ TransmutationError::CompileError(_)) => {
+                let attempt = stats::Attempt { transmuted_at: query::unix_seconds(std::time::SystemTime::now()), version: metadata.version.clone(), resonance: None, pass_rate: None, compiled: Some(false) };
+                self.record_attempt(&metadata.npm_name, &attempt)?;
+                return Err(e);
+            }
+            verified => verified?,
+        };
+        let rust_soul = match verified == rust_code {
+            true => rust_soul,
+            false => self.soul(Language::Rust, &verified).await,
+        };
         
-        // Extract Rust soul
-        let rust_soul = self.soul_extractor.extract_soul_rust(&rust_code).await;
+        // Calculate resonance
+        let resonance = protein_hash::measure_resonance(&js_soul, &rust_soul);
         
-        // Verify souls match (same consciousness)
-        if !souls_match(&js_soul, &rust_soul) {
-            // Souls don't match yet - refine transmutation
-            let rust_code = self.refine_transmutation(&js_soul, &rust_soul, &rust_code).await?;
-            // Re-extract soul after refinement
-            let rust_soul = self.soul_extractor.extract_soul_rust(&rust_code).await;
-        }
+        // Lay the module out, header and all, its items marked for merging
+        // into a crate edited since, before mapping its lines
+        let rust_code = merge::mark_items(&self.generate_module(&metadata, resonance, &verified)?);
+        let source_map = sourcemap::source_map(&rust_code);
+        let warnings = drift::lint(&rust_code);
         
-        // Generate WASM bindings
-        let wasm_bindings = self.generate_wasm_bindings(&metadata)?;
+        // Generate the bindings the profile reaches the crate through
+        let js_bindings = match (metadata.bindings, metadata.profile) {
+            (Bindings::Napi, _) => self.generate_napi_loader(&metadata, &addon_exports)?,
+            (Bindings::Wasm, Profile::NativeLib) => String::new(),
+            (Bindings::Wasm, Profile::CdylibFfi) => self.generate_c_header(&metadata, &ffi_exports)?,
+            (Bindings::Wasm, profile) => self.generate_wasm_bindings(&metadata, profile, &exports)?,
+        };
         
-        // Calculate resonance
-        let resonance = protein_hash::measure_resonance(&js_soul, &rust_soul);
+        // Run both on the same calls, for how often they agree
+        let (equivalence_tests, pass_rate) = match self.compare(modules, commonjs, &subjects, &rust_code, &metadata).await? {
+            Some((tests, pass_rate)) => (Some(tests), Some(pass_rate)),
+            None => (None, None),
+        };
+        // Node times the source as one script, without the modules it imports
+        let benches = match (self.benchmarks, modules) {
+            (true, [module]) => bench::generate(&metadata.crate_name, &module.code, module.kind, commonjs, &subjects, &rust_code),
+            _ => None,
+        };
         
         let transmutation = Transmutation {
             js_soul,
             rust_soul,
             rust_code,
-            wasm_bindings,
+            js_bindings,
             resonance,
             metadata,
+            equivalence_tests,
+            pass_rate,
+            transmuted_at: query::unix_seconds(std::time::SystemTime::now()),
+            tags: vec![],
+            source_map,
+            benches,
+            benchmarks: vec![],
+            warnings,
         };
         
-        // Store in database
+        // Store in database, with the attempt in its history
         self.store_transmutation(&transmutation)?;
+        self.record_attempt(&transmutation.metadata.npm_name, &stats::Attempt {
+            transmuted_at: transmutation.transmuted_at,
+            version: transmutation.metadata.version.clone(),
+            resonance: Some(transmutation.resonance),
+            pass_rate: transmutation.pass_rate,
+            compiled: checked,
+        })?;
+        self.save_souls()?;
         
         Ok(transmutation)
     }
     
-    /// Parse JavaScript code into AST
-    fn parse_javascript(&self, code: &str) -> Result<JsAst, TransmutationError> {
-        // Simplified - would use swc_ecma_parser
-        Ok(JsAst {
-            functions: vec![],
-            classes: vec![],
-            exports: vec![],
+    /// The soul of `code`, from the cache when it was extracted before
+    async fn soul(&mut self, language: Language, code: &str) -> Soul {
+        let key = self.soul_extractor.cache_key(&language, code);
+        if let Some(soul) = self.cache.get(&key).cloned().or_else(|| self.soul_extractor.cached_soul(&key)) {
+            return soul;
+        }
+        let soul = match language {
+            Language::Rust => self.soul_extractor.extract_soul_rust(code).await,
+            _ => self.soul_extractor.extract_soul_js(code).await,
+        };
+        self.cache.insert(key, soul.clone());
+        soul
+    }
+    
+    /// Save the souls extracted since the last save to the soul cache, if
+    /// this forge keeps one
+    fn save_souls(&mut self) -> Result<(), TransmutationError> {
+        let Some(path) = &self.soul_cache else { return Ok(()) };
+        if self.cache.is_empty() {
+            return Ok(());
+        }
+        for (key, soul) in self.cache.drain() {
+            self.soul_extractor.cache_soul(&key, soul);
+        }
+        self.soul_extractor.save_soul_cache(path)
+            .map_err(|e| TransmutationError::DatabaseError(Diagnostic::error(format!("can't save soul cache: {}", e)).with_file(path.display().to_string())))
+    }
+    
+    /// What transmuting `js_code` would plan to generate, without checking,
+    /// comparing, storing or writing anything
+    pub async fn plan_source(&mut self, js_code: &str, kind: SourceKind, metadata: &PackageMetadata) -> Result<Plan, TransmutationError> {
+        self.plan_modules(&[JsModule { path: PathBuf::new(), kind, code: js_code.to_string() }], metadata).await
+    }
+    
+    /// What transmuting a package's modules would plan to generate
+    pub async fn plan_modules(&mut self, modules: &[JsModule], metadata: &PackageMetadata) -> Result<Plan, TransmutationError> {
+        let draft = self.draft(modules, metadata).await?;
+        let bound = match (metadata.bindings, metadata.profile) {
+            (Bindings::Napi, _) => draft.addon_exports.iter().map(|export| export.js_name().to_string()).collect(),
+            (Bindings::Wasm, Profile::NativeLib) => vec![],
+            (Bindings::Wasm, Profile::CdylibFfi) => draft.ffi_exports.iter().map(|export| export.js_name().to_string()).collect(),
+            (Bindings::Wasm, _) => draft.exports,
+        };
+        Ok(Plan {
+            items: plan::items(&draft.rust_code),
+            bound,
+            resonance: protein_hash::measure_resonance(&draft.js_soul, &draft.rust_soul),
+            souls_match: souls_match(&draft.js_soul, &draft.rust_soul),
         })
     }
     
-    /// Transform JavaScript AST to Rust AST
-    fn transform_ast(&self, js_ast: JsAst) -> Result<RustAst, TransmutationError> {
+    /// Parse, transform and generate `modules`, refining the crate toward
+    /// their soul: everything a transmutation does before it touches cargo,
+    /// Node or the database
+    async fn draft(&mut self, modules: &[JsModule], metadata: &PackageMetadata) -> Result<Draft, TransmutationError> {
+        // Extract JavaScript soul, of every module together
+        let js_code = modules.iter().map(|module| module.code.as_str()).collect::<Vec<_>>().join("\n");
+        let mut js_soul = self.soul(Language::JavaScript, &js_code).await;
+        
+        // Parse JavaScript/TypeScript AST
+        let mut asts = vec![];
+        for module in modules {
+            let ast = parse::parse_source(&module.code, module.kind);
+            asts.push(match modules.len() {
+                1 => ast?,
+                _ => ast.map_err(|e| e.in_file(module.path.display().to_string()))?,
+            });
+        }
+        
+        // Lay bodies out after the source's shape, so the crate's soul starts
+        // out close to it rather than being refined toward it
+        let shape = layout::Shape::of(&asts);
+        shape.fill(&mut js_soul.topology_features);
+        self.layout = layout::Layout::of(&js_soul.topology_features, shape.callbacks);
+        
+        // Modules are written from the last found back to the root, so what
+        // a module imports has its types inferred before the module is
+        let source_file = metadata.source_file.as_deref().unwrap_or(&metadata.npm_name);
+        let mut written = vec![String::new(); modules.len()];
+        let mut root = None;
+        for (index, module) in modules.iter().enumerate().rev() {
+            let linked = modules::Linked::of(index, modules, &asts, &self.dependencies);
+            let js_ast = &mut asts[index];
+            let docs = std::mem::take(&mut js_ast.docs);
+            
+            // Transform to Rust AST, then let each registered pass rewrite it
+            let mut rust_ast = self.transform_ast(js_ast, &linked.signatures)?;
+            for pass in &self.passes {
+                pass.run(js_ast, &mut rust_ast);
+            }
+            if index == 0 {
+                let subjects: Vec<equivalence::Subject> = rust_ast.functions.iter().filter_map(equivalence::Subject::of).collect();
+                let addon_exports: Vec<addon::Export> = match metadata.bindings {
+                    Bindings::Wasm => vec![],
+                    Bindings::Napi => rust_ast.functions.iter().filter_map(|function| addon::Export::of(function, &js_ast.exports)).collect(),
+                };
+                let ffi_exports: Vec<ffi::Export> = match metadata.profile {
+                    Profile::CdylibFfi => rust_ast.functions.iter().filter_map(|function| ffi::Export::of(function, &js_ast.exports)).collect(),
+                    _ => vec![],
+                };
+                root = Some((subjects, addon_exports, ffi_exports));
+            }
+            
+            // Generate Rust code, documented as the source was and each
+            // function naming the line it came from
+            let file = match index {
+                0 => source_file.to_string(),
+                _ => modules::origin_file(source_file, &modules[0].path, &module.path),
+            };
+            let writer = jsdoc::DocWriter::new(sourcemap::Origins::new(&file, &module.code), docs, &metadata.name);
+            let code = self.generate_rust_code(rust_ast, &writer, metadata.profile)?;
+            written[index] = match linked.uses.is_empty() {
+                true => code,
+                false => format!("{}\n{}", linked.uses.join("\n"), code),
+            };
+        }
+        let Some((subjects, addon_exports, ffi_exports)) = root else {
+            return Err(TransmutationError::ParseError(
+                Diagnostic::error("no modules to transmute").with_suggestion("pass a .js or .ts entry file"),
+            ));
+        };
+        
+        // The root binds what it exports, with what `export *` brings in
+        let mut exports = asts[0].exports.clone();
+        for reexport in asts[0].reexports.iter().filter(|reexport| reexport.exported.is_none()) {
+            if let Some(other) = reexport.source.as_deref().and_then(|source| modules::resolve(&modules[0], source, modules)) {
+                exports.extend(asts[other].exports.iter().cloned());
+            }
+        }
+        let commonjs = asts[0].commonjs;
+        let mut written = written.into_iter();
+        let root_code = written.next().unwrap_or_default();
+        let mut rust_code = modules::nest(&root_code, modules[1..].iter().map(JsModule::rust_path).zip(written).collect());
+        
+        // Extract Rust soul
+        let mut rust_soul = self.soul(Language::Rust, &rust_code).await;
+        
+        // Refine until the souls match (same consciousness), for as long as
+        // each round brings them closer
+        for _ in 0..self.max_refinements {
+            if souls_match(&js_soul, &rust_soul) {
+                break;
+            }
+            let refined = self.refine_transmutation(&js_soul, &rust_soul, &rust_code, &[]).await?;
+            if refined == rust_code {
+                break;
+            }
+            rust_soul = self.soul(Language::Rust, &refined).await;
+            rust_code = refined;
+        }
+        
+        Ok(Draft { js_soul, rust_soul, rust_code, exports, subjects, addon_exports, ffi_exports, commonjs })
+    }
+    
+    /// Transform JavaScript AST to Rust AST, its bodies calling what it
+    /// imports by `imported`
+    fn transform_ast(&self, js_ast: &mut JsAst, imported: &HashMap<String, JsType>) -> Result<RustAst, TransmutationError> {
         let mut functions = vec![];
-        let mut structs = vec![];
-        let mut impls = vec![];
         
-        // Transform functions
-        for js_func in js_ast.functions {
-            functions.push(self.transform_function(js_func)?);
+        // Error classes become variants of the module's JsError
+        js_ast.classes.retain(|class| !errors::is_error_class(class));
+        
+        // Untyped parameters and returns take the types their use implies
+        infer::infer(js_ast, imported);
+        
+        // Whatever can throw returns a Result its callers propagate
+        let throwing = errors::Throwing::of(js_ast);
+        throwing.mark(js_ast);
+        
+        // Bodies see every function's signature, so calls know what they return;
+        // constructors go by `new Class`, static methods by `Class.method`,
+        // fields by `Class { field }`, and a function taking `...rest` by `...name`
+        let mut signatures = imported.clone();
+        signatures.extend(js_ast.functions.iter().map(|f| (f.name.clone(), f.signature())));
+        for function in &js_ast.functions {
+            if let Some(rest) = function.params.last().filter(|param| param.rest) {
+                signatures.insert(format!("...{}", function.name), rest.ty.clone());
+            }
+        }
+        for class in &js_ast.classes {
+            for property in class.properties.iter().filter(|property| !property.is_static) {
+                signatures.insert(body::field_key(&class.name, &property.name), property.ty.clone());
+            }
+            // Declared or not, a constructor throws when its base's does
+            if throwing.constructs(&class.name) {
+                let this = JsType::Throws(Box::new(JsType::Named(class.name.clone(), vec![])));
+                signatures.insert(format!("new {}", class.name), JsType::Function(vec![], Box::new(this)));
+            }
+            for method in class.methods.iter().filter(|method| method.kind == MethodKind::Static) {
+                signatures.insert(format!("{}.{}", class.name, method.function.name), method.function.signature());
+            }
         }
         
-        // Transform classes to structs + impls
-        for js_class in js_ast.classes {
-            let (rust_struct, rust_impl) = self.transform_class(js_class)?;
-            structs.push(rust_struct);
-            impls.push(rust_impl);
+        // Transform functions
+        for js_func in js_ast.functions.clone() {
+            functions.push(self.transform_function(js_func, &signatures)?);
         }
         
+        // Transform classes to structs, traits and impls
+        let classes = self.transform_classes(js_ast.classes.clone(), &signatures)?;
+        
         Ok(RustAst {
             functions,
-            structs,
-            impls,
+            structs: classes.structs,
+            traits: classes.traits,
+            impls: classes.impls,
         })
     }
     
     /// Transform JavaScript function to Rust
-    fn transform_function(&self, js_func: JsFunction) -> Result<RustFunction, TransmutationError> {
-        // Pattern matching for common JS patterns
-        let rust_body = match js_func.name.as_str() {
-            "map" => self.transform_map_function(&js_func),
-            "filter" => self.transform_filter_function(&js_func),
-            "reduce" => self.transform_reduce_function(&js_func),
-            _ => self.transform_generic_function(&js_func),
-        }?;
-        
-        Ok(RustFunction {
-            name: js_func.name,
-            params: self.transform_params(js_func.params),
-            return_type: self.infer_return_type(&js_func),
-            body: rust_body,
-            is_async: js_func.is_async,
-        })
+    fn transform_function(&self, js_func: JsFunction, signatures: &HashMap<String, JsType>) -> Result<RustFunction, TransmutationError> {
+        self.transform_function_in(js_func, signatures, None)
     }
     
-    /// Transform JavaScript class to Rust struct + impl
-    fn transform_class(&self, js_class: JsClass) -> Result<(RustStruct, RustImpl), TransmutationError> {
-        let rust_struct = RustStruct {
-            name: js_class.name.clone(),
-            fields: self.transform_properties(js_class.properties),
-        };
+    /// Transform a function, or a method of the class `class` describes
+    fn transform_function_in(
+        &self,
+        mut js_func: JsFunction,
+        signatures: &HashMap<String, JsType>,
+        class: Option<body::ClassScope>,
+    ) -> Result<RustFunction, TransmutationError> {
+        // An untyped parameter the body calls is a callback
+        let uses: Vec<Option<body::CallableUse>> = js_func.params.iter()
+            .map(|param| body::callable_use(&js_func.body, &param.name))
+            .collect();
+        for (param, callable) in js_func.params.iter_mut().zip(&uses) {
+            if let (JsType::Unknown, Some(body::CallableUse { arity: Some(arity), .. })) = (&param.ty, callable) {
+                param.ty = JsType::Function(vec![JsType::Unknown; *arity], Box::new(JsType::Unknown));
+            }
+        }
         
-        let rust_impl = RustImpl {
-            struct_name: js_class.name,
-            methods: js_class.methods.into_iter()
-                .map(|m| self.transform_function(m))
-                .collect::<Result<Vec<_>, _>>()?,
+        let rust_body = self.transform_generic_function(&js_func, signatures, class)?;
+        
+        // A returned closure is as reusable as what it does to its captures allows
+        let return_type = match (&js_func.return_type, js_func.body.last()) {
+            (JsType::Function(params, ret), Some(JsStmt::Return(Some(JsExpr::Function(closure))))) if !js_func.is_async => {
+                self.closure_type(body::closure_kind(closure), params, ret)
+            }
+            _ => self.infer_return_type(&js_func),
         };
         
-        Ok((rust_struct, rust_impl))
+        // JS parameters are reassignable locals, and callbacks called more than once are FnMut
+        let mutated = body::mutated_names(&js_func.body);
+        let callbacks: Vec<_> = js_func.params.iter().map(|param| param.ty.clone()).collect();
+        let mut params = self.transform_params(js_func.params);
+        for ((param, ty), callable) in params.iter_mut().zip(&callbacks).zip(&uses) {
+            if let (JsType::Function(args, ret), Some(callable)) = (ty, callable) {
+                param.ty = self.closure_type(callable.kind, args, ret);
+            }
+            let reassigned = mutated.contains(param.name.trim_start_matches("r#"));
+            if reassigned || callable.as_ref().is_some_and(|callable| callable.kind == body::Callable::Mut) {
+                param.name = format!("mut {}", param.name);
+            }
+        }
+        
+        Ok(RustFunction {
+            name: js_func.name,
+            generics: js_func.type_params,
+            receiver: None,
+            params,
+            return_type,
+            body: rust_body,
+            // An async generator returns its stream straight away
+            is_async: js_func.is_async && !js_func.is_generator,
+            is_pub: true,
+            origin: js_func.origin,
+        })
     }
     
-    /// Generate Rust code from AST
-    fn generate_rust_code(&self, ast: RustAst) -> Result<String, TransmutationError> {
-        let mut code = String::new();
-        
-        // Add prelude
-        code.push_str("// Generated by Soul Forge - Same soul, different body\n\n");
-        code.push_str("use wasm_bindgen::prelude::*;\n");
-        code.push_str("use serde::{Serialize, Deserialize};\n\n");
+    /// Generate Rust code from AST, with the prelude and glue `profile` builds with
+    fn generate_rust_code(&self, ast: RustAst, docs: &jsdoc::DocWriter, profile: Profile) -> Result<String, TransmutationError> {
+        let mut items = String::new();
         
         // Generate structs
         for rust_struct in ast.structs {
-            code.push_str(&self.generate_struct_code(rust_struct));
-            code.push_str("\n\n");
+            items.push_str(&self.generate_struct_code(rust_struct, docs));
+            items.push_str("\n\n");
+        }
+        
+        // Generate traits
+        for rust_trait in ast.traits {
+            items.push_str(&self.generate_trait_code(rust_trait, docs));
+            items.push_str("\n\n");
         }
         
         // Generate impls
         for rust_impl in ast.impls {
-            code.push_str(&self.generate_impl_code(rust_impl));
-            code.push_str("\n\n");
+            items.push_str(&self.generate_impl_code(rust_impl, docs));
+            items.push_str("\n\n");
         }
         
         // Generate functions
         for func in ast.functions {
-            code.push_str(&self.generate_function_code(func));
-            code.push_str("\n\n");
+            items.push_str(&self.generate_function_code(func, docs, None));
+            items.push_str("\n\n");
+        }
+        
+        // Add prelude, importing what the bodies reach for
+        let mut code = String::new();
+        if profile.is_wasm() {
+            code.push_str("use wasm_bindgen::prelude::*;\n");
+        }
+        code.push_str("use serde::{Serialize, Deserialize};\n");
+        let collections: Vec<&str> = ["HashMap", "HashSet"].into_iter()
+            .filter(|name| items.contains(name))
+            .collect();
+        match collections.as_slice() {
+            [] => {}
+            [name] => code.push_str(&format!("use std::collections::{};\n", name)),
+            names => code.push_str(&format!("use std::collections::{{{}}};\n", names.join(", "))),
+        }
+        if items.contains("Value") {
+            code.push_str("use serde_json::Value;\n");
+        }
+        code.push('\n');
+        code.push_str(&items);
+        
+        // Support code the bodies call into
+        // Sleeping rejects with a `JsError`, which only WASM's prelude has otherwise
+        if items.contains("JsError") || items.contains("sleep_ms(") {
+            code.push_str(&errors::error_glue(&items));
+        }
+        let (spawn, sleep) = match profile.async_target(self.async_target) {
+            AsyncTarget::Wasm => (WASM_SPAWN_GLUE, WASM_SLEEP_GLUE),
+            AsyncTarget::Native => (NATIVE_SPAWN_GLUE, NATIVE_SLEEP_GLUE),
+        };
+        if items.contains("spawn_promise(") {
+            code.push_str(spawn);
+        }
+        if items.contains("sleep_ms(") {
+            code.push_str(sleep);
         }
         
         Ok(code)
     }
     
-    /// Refine transmutation to match souls
-    async fn refine_transmutation(&mut self, js_soul: &Soul, rust_soul: &Soul, rust_code: &str) -> Result<String, TransmutationError> {
-        // Analyze eigenvalue differences
-        let eigen_diff: Vec<f64> = js_soul.eigenvalues.iter()
-            .zip(rust_soul.eigenvalues.iter())
-            .map(|(e1, e2)| e1 - e2)
-            .collect();
+    /// Check `rust_code` as a crate, refining it until the compiler has no
+    /// errors left and no fixes to offer
+    async fn verify(&mut self, js_soul: &Soul, rust_soul: &Soul, rust_code: &str, metadata: &PackageMetadata) -> Result<String, TransmutationError> {
+        if self.verification == Verification::Off {
+            return Ok(rust_code.to_string());
+        }
         
-        // Adjust code structure to match eigenvalues
-        let mut refined_code = rust_code.to_string();
+        let scratch = check::CheckCrate::new(&metadata.crate_name, &self.render_cargo_toml(metadata, rust_code, false, false)?)?;
+        let mut rust_code = rust_code.to_string();
+        for _ in 0..check::MAX_ROUNDS {
+            let diagnostics = scratch.check(&rust_code, self.verification).await?;
+            let failing: Vec<&check::Diagnostic> = diagnostics.iter().filter(|diagnostic| diagnostic.is_error()).collect();
+            if failing.is_empty() && diagnostics.iter().all(|diagnostic| diagnostic.fixes.is_empty()) {
+                return Ok(rust_code);
+            }
+            
+            let refined = self.refine_transmutation(js_soul, rust_soul, &rust_code, &diagnostics).await?;
+            if refined == rust_code {
+                // The first error, pointing into the code, counts the rest
+                return match failing.split_first() {
+                    None => Ok(rust_code),
+                    Some((first, rest)) => {
+                        let mut diagnostic = first.in_code(&rust_code);
+                        if !rest.is_empty() {
+                            diagnostic.message = format!("{} (and {} more errors)", diagnostic.message, rest.len());
+                        }
+                        Err(TransmutationError::CompileError(diagnostic))
+                    }
+                };
+            }
+            rust_code = refined;
+        }
+        Err(TransmutationError::CompileError(format!("still failing after {} rounds of refinement", check::MAX_ROUNDS).into()))
+    }
+    
+    /// The equivalence tests for `rust_code` and the share of them it passes,
+    /// or `None` when comparison is off or there is nothing to call
+    async fn compare(
+        &self,
+        modules: &[JsModule],
+        commonjs: bool,
+        subjects: &[equivalence::Subject],
+        rust_code: &str,
+        metadata: &PackageMetadata,
+    ) -> Result<Option<(String, f64)>, TransmutationError> {
+        let fixtures = match &self.equivalence {
+            equivalence::Equivalence::Off => return Ok(None),
+            equivalence::Equivalence::Generated => equivalence::generate(subjects),
+            equivalence::Equivalence::Fixtures(fixtures) => fixtures.clone(),
+        };
+        // Calls the harness can't make, to functions it can't call, are dropped
+        let subjects: Vec<equivalence::Subject> = subjects.iter()
+            .filter(|subject| fixtures.iter().any(|fixture| fixture.function == subject.name()))
+            .cloned()
+            .collect();
+        let fixtures: Vec<Fixture> = fixtures.into_iter()
+            .filter(|fixture| subjects.iter().any(|subject| subject.name() == fixture.function))
+            .collect();
+        if fixtures.is_empty() {
+            return Ok(None);
+        }
         
-        // If Rust soul is too complex, simplify
-        if eigen_diff.iter().sum::<f64>() < 0.0 {
-            refined_code = self.simplify_rust_code(&refined_code)?;
+        let outcomes = equivalence::run_js(modules, commonjs, &subjects, &fixtures).await?;
+        let tests = equivalence::harness(&metadata.crate_name, &subjects, &fixtures, &outcomes);
+        let scratch = check::CheckCrate::new(&metadata.crate_name, &self.render_cargo_toml(metadata, rust_code, false, false)?)?;
+        let passed = scratch.test(rust_code, &tests).await?;
+        Ok(Some((tests, passed as f64 / fixtures.len() as f64)))
+    }
+    
+    /// Refine transmutation one structural edit closer to the JS soul, or to
+    /// build where the compiler reports it doesn't
+    async fn refine_transmutation(
+        &mut self,
+        js_soul: &Soul,
+        rust_soul: &Soul,
+        rust_code: &str,
+        diagnostics: &[check::Diagnostic],
+    ) -> Result<String, TransmutationError> {
+        // A body that doesn't build has no soul to match yet
+        if !diagnostics.is_empty() {
+            return Ok(check::repair(rust_code, diagnostics));
         }
-        // If Rust soul is too simple, add structure
-        else {
-            refined_code = self.enhance_rust_code(&refined_code)?;
+        
+        // Keep the structural edit that brings the souls closest, if any does
+        let direction = refine::Direction::of(&js_soul.eigenvalues, &rust_soul.eigenvalues);
+        let mut best = (protein_hash::measure_resonance(js_soul, rust_soul), rust_code.to_string());
+        for candidate in refine::candidates(rust_code, direction) {
+            let soul = self.soul_extractor.extract_soul_rust(&candidate).await;
+            let resonance = protein_hash::measure_resonance(js_soul, &soul);
+            if resonance > best.0 {
+                best = (resonance, candidate);
+            }
         }
         
-        Ok(refined_code)
+        Ok(best.1)
     }
     
-    /// Generate WASM bindings
-    fn generate_wasm_bindings(&self, metadata: &PackageMetadata) -> Result<String, TransmutationError> {
+    /// Generate `src/lib.rs`: the transmuted code under the module's header
+    fn generate_module(&self, metadata: &PackageMetadata, resonance: f64, code: &str) -> Result<String, TransmutationError> {
         let mut context = tera::Context::new();
         context.insert("name", &metadata.name);
         context.insert("version", &metadata.version);
+        context.insert("npm_name", &metadata.npm_name);
+        context.insert("resonance", &resonance);
+        context.insert("code", code);
         
-        self.templates.render("wasm_bindings", &context)
-            .map_err(|e| TransmutationError::TemplateError(e.to_string()))
+        self.templates.render("rust_module.tera", &context)
+            .map_err(|e| TransmutationError::TemplateError(e.to_string().into()))
+    }
+    
+    /// Generate WASM bindings, an ES module for the web or CommonJS for Node
+    fn generate_wasm_bindings(&self, metadata: &PackageMetadata, profile: Profile, exports: &[String]) -> Result<String, TransmutationError> {
+        let mut context = tera::Context::new();
+        context.insert("name", &metadata.name);
+        context.insert("version", &metadata.version);
+        context.insert("exports", &exports.join(", "));
+        
+        let template = match profile {
+            Profile::WasmNode => "wasm_node_bindings.tera",
+            _ => "wasm_bindings.tera",
+        };
+        self.templates.render(template, &context)
+            .map_err(|e| TransmutationError::TemplateError(e.to_string().into()))
+    }
+    
+    /// Generate the C header declaring a cdylib's `extern "C"` exports
+    fn generate_c_header(&self, metadata: &PackageMetadata, exports: &[ffi::Export]) -> Result<String, TransmutationError> {
+        let mut context = tera::Context::new();
+        context.insert("name", &metadata.crate_name);
+        context.insert("version", &metadata.version);
+        context.insert("guard", &ffi::header_file(&metadata.crate_name).to_uppercase().replace('.', "_"));
+        context.insert("declarations", &ffi::declarations(&metadata.crate_name, exports));
+        
+        self.templates.render("c_header.tera", &context)
+            .map_err(|e| TransmutationError::TemplateError(e.to_string().into()))
+    }
+    
+    /// Generate the `index.js` that loads a napi-rs addon's wrapped exports
+    fn generate_napi_loader(&self, metadata: &PackageMetadata, exports: &[addon::Export]) -> Result<String, TransmutationError> {
+        let names: Vec<&str> = exports.iter().map(addon::Export::js_name).collect();
+        let mut context = tera::Context::new();
+        context.insert("name", &metadata.name);
+        context.insert("version", &metadata.version);
+        context.insert("exports", &names.join(", "));
+        
+        self.templates.render("napi_loader.tera", &context)
+            .map_err(|e| TransmutationError::TemplateError(e.to_string().into()))
     }
     
     /// Store transmutation in database
     fn store_transmutation(&self, transmutation: &Transmutation) -> Result<(), TransmutationError> {
         let key = format!("transmutation:{}", transmutation.metadata.npm_name);
         let value = bincode::serialize(transmutation)
-            .map_err(|e| TransmutationError::SerializationError(e.to_string()))?;
+            .map_err(|e| TransmutationError::SerializationError(e.to_string().into()))?;
         
         self.db.insert(key, value)
-            .map_err(|e| TransmutationError::DatabaseError(e.to_string()))?;
+            .map_err(|e| TransmutationError::DatabaseError(e.to_string().into()))?;
         
         Ok(())
     }
     
-    /// Generate Cargo.toml for transmuted package
-    pub fn generate_cargo_toml(&self, metadata: &PackageMetadata) -> Result<String, TransmutationError> {
+    /// Generate Cargo.toml for transmuted package, depending on the crates
+    /// only some code needs when `rust_code` uses them
+    pub fn generate_cargo_toml(&self, metadata: &PackageMetadata, rust_code: &str) -> Result<String, TransmutationError> {
+        self.render_cargo_toml(metadata, rust_code, false, self.benchmarks)
+    }
+    
+    /// Generate Cargo.toml for a transmuted package in a workspace, taking its
+    /// dependencies and profile from the workspace's
+    pub fn generate_member_cargo_toml(&self, metadata: &PackageMetadata, rust_code: &str) -> Result<String, TransmutationError> {
+        self.render_cargo_toml(metadata, rust_code, true, self.benchmarks)
+    }
+    
+    /// A crate's manifest; the scratch crates code is checked in have no
+    /// `benches/`, so no bench target
+    fn render_cargo_toml(&self, metadata: &PackageMetadata, rust_code: &str, member: bool, benches: bool) -> Result<String, TransmutationError> {
+        let napi = metadata.bindings == Bindings::Napi;
+        let async_target = metadata.profile.async_target(self.async_target);
+        // Members take their versions from the workspace's
+        let requirement = |spec: &'static str| if member { "{ workspace = true }" } else { spec };
+        // Transmuted packages are depended on once their crates are used
+        let packages: Vec<(&str, String)> = self.dependencies.iter()
+            .filter(|dependency| ["::", " as ", ";"].iter().any(|next| rust_code.contains(&format!("use {}{}", dependency.ident(), next))))
+            .map(|dependency| (dependency.crate_name.as_str(), dependency.requirement(member)))
+            .collect();
+        let dependencies: Vec<(&str, &str)> = DEPENDENCIES.iter()
+            .filter(|(name, _, target)| target.is_none_or(|target| target == async_target) && metadata.profile.depends_on(name))
+            .map(|(name, spec, _)| (*name, *spec))
+            .chain(addon::DEPENDENCIES.iter().filter(|_| napi).copied())
+            .chain(strings::DEPENDENCIES.iter().chain(generators::DEPENDENCIES).filter(|(_, _, path)| rust_code.contains(path)).map(|(name, spec, _)| (*name, *spec)))
+            .map(|(name, spec)| (name, requirement(spec)))
+            .chain(packages.iter().map(|(name, requirement)| (*name, requirement.as_str())))
+            .collect();
+        let build_dependencies: Vec<(&str, &str)> = addon::BUILD_DEPENDENCIES.iter()
+            .filter(|_| napi)
+            .map(|&(name, spec)| (name, requirement(spec)))
+            .collect();
+        let dev_dependencies: Vec<(&str, &str)> = [bench::CRITERION].iter()
+            .filter(|_| benches)
+            .map(|&(name, spec)| (name, requirement(spec)))
+            .collect();
+        
         let mut context = tera::Context::new();
         context.insert("name", &metadata.crate_name);
         context.insert("version", &metadata.version);
         context.insert("description", &metadata.description);
         context.insert("npm_name", &metadata.npm_name);
+        context.insert("license", &metadata.spdx_license());
+        context.insert("authors", &metadata.authors);
+        context.insert("repository", &metadata.repository);
+        context.insert("keywords", &metadata.keywords);
+        // An addon is loaded as a shared library, whatever the profile
+        let crate_types: &[&str] = match napi {
+            true => &["cdylib", "rlib"],
+            false => metadata.profile.crate_types(),
+        };
+        context.insert("crate_types", &crate_types.iter().map(|ty| format!("\"{}\"", ty)).collect::<Vec<_>>().join(", "));
+        context.insert("dependencies", &dependencies);
+        context.insert("build_dependencies", &build_dependencies);
+        context.insert("dev_dependencies", &dev_dependencies);
+        context.insert("bench", &bench::BENCH_NAME);
+        context.insert("member", &member);
+        context.insert("overflow_checks", &self.checks_overflow());
+        
+        self.templates.render("cargo_toml.tera", &context)
+            .map_err(|e| TransmutationError::TemplateError(e.to_string().into()))
+    }
+    
+    /// Whether release builds keep overflow checks, as integers under
+    /// `Overflow::Checked` panic rather than wrap
+    fn checks_overflow(&self) -> bool {
+        self.numeric == NumericPolicy::IdiomaticInt && self.overflow.checks_release()
+    }
+    
+    /// Generate the Cargo.toml of a workspace of transmuted crates, whose
+    /// dependencies cover either async target, either bindings and benchmarks
+    pub fn generate_workspace_toml(&self, members: &[WorkspaceMember]) -> Result<String, TransmutationError> {
+        let dependencies: Vec<(&str, &str)> = DEPENDENCIES.iter().map(|(name, spec, _)| (*name, *spec))
+            .chain(addon::DEPENDENCIES.iter().chain(addon::BUILD_DEPENDENCIES).copied())
+            .chain(strings::DEPENDENCIES.iter().chain(generators::DEPENDENCIES).map(|(name, spec, _)| (*name, *spec)))
+            .chain([bench::CRITERION])
+            .collect();
+        let mut context = tera::Context::new();
+        context.insert("members", members);
+        context.insert("dependencies", &dependencies);
+        context.insert("overflow_checks", &self.checks_overflow());
+        
+        self.templates.render("workspace_toml.tera", &context)
+            .map_err(|e| TransmutationError::TemplateError(e.to_string().into()))
+    }
+    
+    /// Generate a workspace README listing each crate, most resonant first
+    pub fn generate_workspace_readme(&self, members: &[WorkspaceMember]) -> Result<String, TransmutationError> {
+        let mut members = members.to_vec();
+        members.sort_by(|a, b| b.resonance.total_cmp(&a.resonance));
+        let mean = members.iter().map(|member| member.resonance).sum::<f64>() / members.len().max(1) as f64;
+        let mut context = tera::Context::new();
+        context.insert("members", &members);
+        context.insert("mean", &mean);
         
-        self.templates.render("cargo_toml", &context)
-            .map_err(|e| TransmutationError::TemplateError(e.to_string()))
+        self.templates.render("workspace_readme.tera", &context)
+            .map_err(|e| TransmutationError::TemplateError(e.to_string().into()))
+    }
+    
+    /// Generate an HTML report of a stored transmutation: its resonance, how
+    /// the souls differ, the crate's API and whether it builds and behaves
+    ///
+    /// The code is checked anew, and its equivalence tests rerun, unless
+    /// verification is off.
+    pub async fn generate_report(&self, transmutation: &Transmutation) -> Result<String, TransmutationError> {
+        let build = match self.verification {
+            Verification::Off => None,
+            verification => {
+                let metadata = &transmutation.metadata;
+                let scratch = check::CheckCrate::new(&metadata.crate_name, &self.render_cargo_toml(metadata, &transmutation.rust_code, false, false)?)?;
+                let mut build = report::Build::of(&scratch.check(&transmutation.rust_code, verification).await?);
+                if let (0, Some(tests)) = (build.errors, &transmutation.equivalence_tests) {
+                    let passed = scratch.test(&transmutation.rust_code, tests).await?;
+                    build.tests = Some((passed, tests.matches("#[test]").count()));
+                }
+                Some(build)
+            }
+        };
+        
+        self.templates.render("report_html.tera", &report::context(transmutation, build.as_ref()))
+            .map_err(|e| TransmutationError::TemplateError(e.to_string().into()))
     }
     
     // Helper methods
     
     fn transform_params(&self, params: Vec<JsParam>) -> Vec<RustParam> {
         params.into_iter().map(|p| RustParam {
-            name: p.name,
+            name: body::rust_ident(&p.name),
             ty: self.infer_type(&p.ty),
         }).collect()
     }
     
-    fn infer_type(&self, js_type: &str) -> String {
+    fn infer_type(&self, js_type: &JsType) -> String {
+        let list = |types: &[JsType]| types.iter().map(|t| self.infer_type(t)).collect::<Vec<_>>().join(", ");
+        
         match js_type {
-            "number" => "f64".to_string(),
-            "string" => "String".to_string(),
-            "boolean" => "bool".to_string(),
-            "array" => "Vec<T>".to_string(),
-            "object" => "HashMap<String, Value>".to_string(),
-            _ => "Value".to_string(),
+            JsType::Number => "f64".to_string(),
+            JsType::BigInt => "i128".to_string(),
+            JsType::String => "String".to_string(),
+            JsType::Boolean => "bool".to_string(),
+            JsType::Void | JsType::Null => "()".to_string(),
+            JsType::Array(item) => format!("Vec<{}>", self.infer_type(item)),
+            JsType::Tuple(items) if items.len() == 1 => format!("({},)", list(items)),
+            JsType::Tuple(items) => format!("({})", list(items)),
+            // f64 isn't Hash; numeric keys are integral in practice
+            JsType::Map(key, value) if **key == JsType::Number => format!("HashMap<i64, {}>", self.infer_type(value)),
+            JsType::Map(key, value) => format!("HashMap<{}, {}>", self.infer_type(key), self.infer_type(value)),
+            JsType::Set(item) => format!("HashSet<{}>", self.infer_type(item)),
+            JsType::Optional(inner) => format!("Option<{}>", self.infer_type(inner)),
+            JsType::Promise(inner) => self.infer_type(inner),
+            JsType::Throws(inner) => format!("Result<{}, JsError>", self.infer_type(inner)),
+            JsType::Function(params, ret) => self.closure_type(body::Callable::Shared, params, ret),
+            JsType::Iterator(item) => format!("impl Iterator<Item = {}>", self.infer_type(item)),
+            JsType::Named(name, args) if args.is_empty() => name.clone(),
+            JsType::Named(name, args) => format!("{}<{}>", name, list(args)),
+            JsType::Object => "HashMap<String, Value>".to_string(),
+            JsType::Unknown => "Value".to_string(),
+        }
+    }
+    
+    /// `impl Fn(..) -> R`, under the closure trait a use of it needs
+    fn closure_type(&self, kind: body::Callable, params: &[JsType], ret: &JsType) -> String {
+        let params = params.iter().map(|t| self.infer_type(t)).collect::<Vec<_>>().join(", ");
+        match self.infer_type(ret).as_str() {
+            "()" => format!("impl {}({})", kind.bound(), params),
+            ret => format!("impl {}({}) -> {}", kind.bound(), params, ret),
         }
     }
     
     fn infer_return_type(&self, func: &JsFunction) -> String {
-        // Simplified type inference
+        // A generator's items come out of an iterator, or a stream when async
+        if let Some(yielding) = generators::Yielding::of(func) {
+            let item = match &func.return_type {
+                JsType::Iterator(item) => &**item,
+                item => item,
+            };
+            return yielding.return_type(&self.infer_type(item));
+        }
+        // Promise<T> unwraps to T; async errors surface as JsError
         if func.is_async {
             format!("Result<{}, JsError>", self.infer_type(&func.return_type))
         } else {
@@ -316,150 +1274,488 @@ impl SoulForge {
         }).collect()
     }
     
-    fn transform_map_function(&self, func: &JsFunction) -> Result<String, TransmutationError> {
-        Ok("self.iter().map(|x| x * 2).collect()".to_string())
-    }
-    
-    fn transform_filter_function(&self, func: &JsFunction) -> Result<String, TransmutationError> {
-        Ok("self.iter().filter(|x| x > &0).collect()".to_string())
-    }
-    
-    fn transform_reduce_function(&self, func: &JsFunction) -> Result<String, TransmutationError> {
-        Ok("self.iter().fold(0, |acc, x| acc + x)".to_string())
-    }
-    
-    fn transform_generic_function(&self, func: &JsFunction) -> Result<String, TransmutationError> {
-        Ok("todo!()".to_string())
-    }
-    
-    fn simplify_rust_code(&self, code: &str) -> Result<String, TransmutationError> {
-        // Remove unnecessary complexity
-        Ok(code.replace("Result<", "").replace(", Error>", ""))
-    }
-    
-    fn enhance_rust_code(&self, code: &str) -> Result<String, TransmutationError> {
-        // Add error handling
-        Ok(format!("// Enhanced with error handling\n{}", code))
+    fn transform_generic_function(
+        &self,
+        func: &JsFunction,
+        signatures: &HashMap<String, JsType>,
+        class: Option<body::ClassScope>,
+    ) -> Result<String, TransmutationError> {
+        let mut writer = body::BodyWriter::new(self, signatures, func);
+        if let Some(class) = class {
+            writer = writer.in_class(class);
+        }
+        Ok(writer.write_function(&func.body).join("\n    "))
     }
     
-    fn generate_struct_code(&self, s: RustStruct) -> String {
+    fn generate_struct_code(&self, s: RustStruct, docs: &jsdoc::DocWriter) -> String {
         let fields = s.fields.iter()
             .map(|f| format!("    {} {}: {},", if f.is_pub { "pub" } else { "" }, f.name, f.ty))
             .collect::<Vec<_>>()
             .join("\n");
         
-        format!("#[derive(Debug, Clone, Serialize, Deserialize)]\npub struct {} {{\n{}\n}}", s.name, fields)
+        let doc = docs.structure(&s);
+        format!("{}#[derive(Debug, Clone, Serialize, Deserialize)]\npub struct {} {{\n{}\n}}", doc, s.name, fields)
     }
     
-    fn generate_impl_code(&self, i: RustImpl) -> String {
+    fn generate_impl_code(&self, i: RustImpl, docs: &jsdoc::DocWriter) -> String {
         let methods = i.methods.iter()
-            .map(|m| self.generate_function_code(m.clone()))
+            .map(|m| indent(&self.generate_function_code(m.clone(), docs, Some(&i.struct_name))))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        
+        match i.trait_name {
+            Some(trait_name) => format!("impl {} for {} {{\n{}\n}}", trait_name, i.struct_name, methods),
+            None => format!("impl {} {{\n{}\n}}", i.struct_name, methods),
+        }
+    }
+    
+    fn generate_trait_code(&self, t: RustTrait, docs: &jsdoc::DocWriter) -> String {
+        let required = t.required.iter()
+            .map(|signature| format!("    {};", signature))
             .collect::<Vec<_>>()
-            .join("\n\n    ");
+            .join("\n");
+        let methods = t.methods.iter()
+            .map(|m| format!("\n\n{}", indent(&self.generate_function_code(m.clone(), docs, Some(&t.name)))))
+            .collect::<String>();
         
-        format!("impl {} {{\n    {}\n}}", i.struct_name, methods)
+        let supertrait = t.supertrait.map(|name| format!(": {}", name)).unwrap_or_default();
+        format!("/// {}\npub trait {}{} {{\n{}{}\n}}", t.doc, t.name, supertrait, required, methods)
     }
     
-    fn generate_function_code(&self, f: RustFunction) -> String {
-        let params = f.params.iter()
-            .map(|p| format!("{}: {}", p.name, p.ty))
+    /// `f`, as a method of `owner` if given
+    fn generate_function_code(&self, f: RustFunction, docs: &jsdoc::DocWriter, owner: Option<&str>) -> String {
+        let params = f.receiver.into_iter().map(str::to_string)
+            .chain(f.params.iter().map(|p| format!("{}: {}", p.name, p.ty)))
             .collect::<Vec<_>>()
             .join(", ");
         
+        let visibility = if f.is_pub { "pub " } else { "" };
         let async_keyword = if f.is_async { "async " } else { "" };
+        let generics = if f.generics.is_empty() { String::new() } else { format!("<{}>", f.generics.join(", ")) };
+        let return_type = if f.return_type == "()" { String::new() } else { format!(" -> {}", f.return_type) };
         
-        format!("pub {}fn {}({}) -> {} {{\n    {}\n}}", 
-            async_keyword, f.name, params, f.return_type, f.body)
+        format!("{}{}{}fn {}{}({}){} {{\n    {}\n}}", 
+            docs.function(&f, owner), visibility, async_keyword, f.name, generics, params, return_type, f.body)
     }
 }
 
+/// `code` nested one level deeper, blank lines left empty
+fn indent(code: &str) -> String {
+    code.lines()
+        .map(|line| if line.is_empty() { String::new() } else { format!("    {}", line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 // AST structures
 
 #[derive(Debug, Clone)]
-struct JsAst {
-    functions: Vec<JsFunction>,
-    classes: Vec<JsClass>,
-    exports: Vec<String>,
+pub struct JsAst {
+    pub functions: Vec<JsFunction>,
+    pub classes: Vec<JsClass>,
+    pub exports: Vec<String>,
+    /// Bindings taken from other modules, by `import` or `require`
+    pub imports: Vec<JsImport>,
+    /// Names exported from other modules, or exported under another name
+    pub reexports: Vec<JsReexport>,
+    /// The function or class `export default` or `module.exports =` gives
+    pub default_export: Option<String>,
+    /// Whether the module exports through `module.exports` and `require`s
+    pub commonjs: bool,
+    /// JSDoc comments, by the origin of what they document
+    pub(crate) docs: HashMap<u32, jsdoc::JsDoc>,
+}
+
+/// A binding a module imports, like `import { a as b } from './x.js'` or
+/// `const b = require('./x')`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsImport {
+    /// The module specifier, as written
+    pub source: String,
+    pub name: ImportedName,
+    /// The name the module binds it to
+    pub local: String,
+}
+
+/// What of another module a binding is
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportedName {
+    Named(String),
+    Default,
+    /// The whole module: `import * as x`, or a `require` bound as a whole
+    Namespace,
+}
+
+/// A name a module exports from elsewhere, like `export { a as b } from
+/// './x.js'` or `export * from './x.js'`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsReexport {
+    /// The module specifier, or `None` for the module's own binding exported
+    /// under another name, as `export { a as b }`
+    pub source: Option<String>,
+    pub name: ImportedName,
+    /// The name it's exported as; `None` re-exports every name of a namespace
+    pub exported: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsFunction {
+    pub name: String,
+    pub params: Vec<JsParam>,
+    pub return_type: JsType,
+    pub type_params: Vec<String>,
+    pub is_async: bool,
+    /// A `function*`, whose body yields the items of what calling it gives
+    pub is_generator: bool,
+    pub body: Vec<JsStmt>,
+    /// Byte offset of the declaration in the source, for functions read from it
+    pub origin: Option<u32>,
+}
+
+impl JsFunction {
+    /// The function as a value; calling an async function gives a promise,
+    /// and calling a generator an iterator
+    pub(crate) fn signature(&self) -> JsType {
+        let params = self.params.iter().map(|param| param.ty.clone()).collect();
+        let ret = match &self.return_type {
+            JsType::Iterator(_) => self.return_type.clone(),
+            ret if self.is_generator => JsType::Iterator(Box::new(ret.clone())),
+            JsType::Promise(_) => self.return_type.clone(),
+            ret if self.is_async => JsType::Promise(Box::new(ret.clone())),
+            ret => ret.clone(),
+        };
+        JsType::Function(params, Box::new(ret))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JsParam {
+    pub name: String,
+    pub ty: JsType,
+    /// A `...rest` parameter, taking the arguments past the others as an array
+    pub rest: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsClass {
+    pub name: String,
+    /// The class named in `extends`, if any
+    pub extends: Option<String>,
+    pub properties: Vec<JsProperty>,
+    pub methods: Vec<JsMethod>,
+    /// Byte offset of the declaration in the source
+    pub origin: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsProperty {
+    pub name: String,
+    pub ty: JsType,
+    /// The initializer of a class field
+    pub init: Option<JsExpr>,
+    pub is_static: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsMethod {
+    pub kind: MethodKind,
+    /// Named `new` for the constructor
+    pub function: JsFunction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MethodKind {
+    Constructor,
+    Method,
+    Static,
+    Getter,
+    Setter,
 }
 
+/// A statement of a function body
 #[derive(Debug, Clone)]
-struct JsFunction {
-    name: String,
-    params: Vec<JsParam>,
-    return_type: String,
-    is_async: bool,
+pub enum JsStmt {
+    Let { pattern: JsPattern, ty: JsType, init: Option<JsExpr> },
+    Expr(JsExpr),
+    Return(Option<JsExpr>),
+    If { test: JsExpr, then: Vec<JsStmt>, otherwise: Vec<JsStmt> },
+    While { test: JsExpr, body: Vec<JsStmt> },
+    DoWhile { body: Vec<JsStmt>, test: JsExpr },
+    For { init: Vec<JsStmt>, test: Option<JsExpr>, update: Option<JsExpr>, body: Vec<JsStmt> },
+    ForOf { pattern: JsPattern, iterable: JsExpr, body: Vec<JsStmt> },
+    ForIn { pattern: JsPattern, object: JsExpr, body: Vec<JsStmt> },
+    Switch { discriminant: JsExpr, cases: Vec<JsCase> },
+    /// With the label it names, if any
+    Break(Option<String>),
+    Continue(Option<String>),
+    Labeled(String, Box<JsStmt>),
+    Block(Vec<JsStmt>),
+    Throw(JsExpr),
+    Try { block: Vec<JsStmt>, param: Option<JsPattern>, handler: Option<Vec<JsStmt>>, finalizer: Vec<JsStmt> },
+    Function(JsFunction),
+    /// A construct the forge can't transmute yet, by description
+    Unsupported(String),
 }
 
 #[derive(Debug, Clone)]
-struct JsParam {
-    name: String,
-    ty: String,
+pub struct JsCase {
+    /// None for `default:`
+    pub test: Option<JsExpr>,
+    pub body: Vec<JsStmt>,
 }
 
+/// A binding target: `x`, `[a, ...rest]`, `{ key: value }` or `x = default`
 #[derive(Debug, Clone)]
-struct JsClass {
-    name: String,
-    properties: Vec<JsProperty>,
-    methods: Vec<JsFunction>,
+pub enum JsPattern {
+    Ident(String),
+    Array { elements: Vec<Option<JsPattern>>, rest: Option<Box<JsPattern>> },
+    Object { properties: Vec<(String, JsPattern)>, rest: Option<Box<JsPattern>> },
+    Default(Box<JsPattern>, JsExpr),
 }
 
 #[derive(Debug, Clone)]
-struct JsProperty {
-    name: String,
-    ty: String,
+pub enum JsExpr {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Null,
+    Undefined,
+    Regex { pattern: String, flags: String },
+    Template { quasis: Vec<String>, exprs: Vec<JsExpr> },
+    Ident(String),
+    This,
+    Super,
+    Array(Vec<JsExpr>),
+    Object(Vec<JsProp>),
+    Spread(Box<JsExpr>),
+    Unary(&'static str, Box<JsExpr>),
+    Update { op: &'static str, prefix: bool, target: Box<JsExpr> },
+    /// Arithmetic, comparison and logical operators, by their JS spelling
+    Binary(&'static str, Box<JsExpr>, Box<JsExpr>),
+    Assign(&'static str, Box<JsExpr>, Box<JsExpr>),
+    Conditional(Box<JsExpr>, Box<JsExpr>, Box<JsExpr>),
+    Call(Box<JsExpr>, Vec<JsExpr>),
+    New(Box<JsExpr>, Vec<JsExpr>),
+    Member(Box<JsExpr>, String),
+    Index(Box<JsExpr>, Box<JsExpr>),
+    /// Arrow functions and function expressions
+    Function(Box<JsFunction>),
+    Await(Box<JsExpr>),
+    /// `yield`, or `yield*` handing out every item of another iterable
+    Yield { value: Option<Box<JsExpr>>, delegate: bool },
+    Sequence(Vec<JsExpr>),
+    Unsupported(String),
 }
 
 #[derive(Debug, Clone)]
-struct RustAst {
-    functions: Vec<RustFunction>,
-    structs: Vec<RustStruct>,
-    impls: Vec<RustImpl>,
+pub enum JsProp {
+    KeyValue(String, JsExpr),
+    Spread(JsExpr),
+}
+
+/// A value's type, from its TypeScript annotation where there is one
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsType {
+    Number,
+    BigInt,
+    String,
+    Boolean,
+    Void,
+    Null,
+    Array(Box<JsType>),
+    Tuple(Vec<JsType>),
+    Map(Box<JsType>, Box<JsType>),
+    Set(Box<JsType>),
+    Optional(Box<JsType>),
+    Promise(Box<JsType>),
+    /// What a function that can throw returns
+    Throws(Box<JsType>),
+    Function(Vec<JsType>, Box<JsType>),
+    /// An iterable of the type, as calling a generator gives
+    Iterator(Box<JsType>),
+    /// Interfaces, classes and type parameters, with type arguments
+    Named(String, Vec<JsType>),
+    Object,
+    Unknown,
+}
+
+/// Generated code refined toward its soul, not yet checked or stored
+struct Draft {
+    js_soul: Soul,
+    rust_soul: Soul,
+    rust_code: String,
+    exports: Vec<String>,
+    subjects: Vec<equivalence::Subject>,
+    addon_exports: Vec<addon::Export>,
+    ffi_exports: Vec<ffi::Export>,
+    /// Whether the root module is CommonJS, for running it under Node
+    commonjs: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct RustAst {
+    pub functions: Vec<RustFunction>,
+    pub structs: Vec<RustStruct>,
+    pub traits: Vec<RustTrait>,
+    pub impls: Vec<RustImpl>,
 }
 
 #[derive(Debug, Clone)]
-struct RustFunction {
-    name: String,
-    params: Vec<RustParam>,
-    return_type: String,
-    body: String,
-    is_async: bool,
+pub struct RustFunction {
+    pub name: String,
+    pub generics: Vec<String>,
+    /// `&self` or `&mut self` for methods
+    pub receiver: Option<&'static str>,
+    pub params: Vec<RustParam>,
+    pub return_type: String,
+    pub body: String,
+    pub is_async: bool,
+    /// Trait items can't be `pub`
+    pub is_pub: bool,
+    /// Byte offset of the JS declaration it was transmuted from
+    pub origin: Option<u32>,
 }
 
 #[derive(Debug, Clone)]
-struct RustParam {
-    name: String,
-    ty: String,
+pub struct RustParam {
+    pub name: String,
+    pub ty: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RustStruct {
+    pub name: String,
+    pub doc: Option<String>,
+    pub fields: Vec<RustField>,
+    /// Byte offset of the JS class or interface it was transmuted from
+    pub origin: Option<u32>,
 }
 
 #[derive(Debug, Clone)]
-struct RustStruct {
-    name: String,
-    fields: Vec<RustField>,
+pub struct RustField {
+    pub name: String,
+    pub ty: String,
+    pub is_pub: bool,
 }
 
 #[derive(Debug, Clone)]
-struct RustField {
-    name: String,
-    ty: String,
-    is_pub: bool,
+pub struct RustImpl {
+    pub struct_name: String,
+    /// The trait implemented, for `impl Trait for Struct`
+    pub trait_name: Option<String>,
+    pub methods: Vec<RustFunction>,
 }
 
 #[derive(Debug, Clone)]
-struct RustImpl {
-    struct_name: String,
-    methods: Vec<RustFunction>,
+pub struct RustTrait {
+    pub name: String,
+    pub doc: String,
+    /// The trait of the base class's own base
+    pub supertrait: Option<String>,
+    /// Methods implementors must provide, as signatures
+    pub required: Vec<String>,
+    /// Default methods
+    pub methods: Vec<RustFunction>,
 }
 
 // Error types
 
+/// Why a transmutation failed, by the stage that failed, with a [`Diagnostic`]
+/// saying where and what might fix it
 #[derive(Debug)]
 pub enum TransmutationError {
-    ParseError(String),
-    TransformError(String),
-    GenerationError(String),
-    TemplateError(String),
-    SerializationError(String),
-    DatabaseError(String),
+    ParseError(Diagnostic),
+    TransformError(Diagnostic),
+    GenerationError(Diagnostic),
+    TemplateError(Diagnostic),
+    SerializationError(Diagnostic),
+    DatabaseError(Diagnostic),
+    CompileError(Diagnostic),
+    EquivalenceError(Diagnostic),
+    FetchError(Diagnostic),
+    SchemaError(Diagnostic),
+    PairingError(Diagnostic),
+    PluginError(Diagnostic),
+    BenchError(Diagnostic),
+    PublishError(Diagnostic),
+    SnapshotError(Diagnostic),
+}
+
+impl TransmutationError {
+    /// What went wrong, where, and what might fix it
+    pub fn diagnostic(&self) -> &Diagnostic {
+        match self {
+            Self::ParseError(diagnostic) => diagnostic,
+            Self::TransformError(diagnostic) => diagnostic,
+            Self::GenerationError(diagnostic) => diagnostic,
+            Self::TemplateError(diagnostic) => diagnostic,
+            Self::SerializationError(diagnostic) => diagnostic,
+            Self::DatabaseError(diagnostic) => diagnostic,
+            Self::CompileError(diagnostic) => diagnostic,
+            Self::EquivalenceError(diagnostic) => diagnostic,
+            Self::FetchError(diagnostic) => diagnostic,
+            Self::SchemaError(diagnostic) => diagnostic,
+            Self::PairingError(diagnostic) => diagnostic,
+            Self::PluginError(diagnostic) => diagnostic,
+            Self::BenchError(diagnostic) => diagnostic,
+            Self::PublishError(diagnostic) => diagnostic,
+            Self::SnapshotError(diagnostic) => diagnostic,
+        }
+    }
+    
+    fn diagnostic_mut(&mut self) -> &mut Diagnostic {
+        match self {
+            Self::ParseError(diagnostic) => diagnostic,
+            Self::TransformError(diagnostic) => diagnostic,
+            Self::GenerationError(diagnostic) => diagnostic,
+            Self::TemplateError(diagnostic) => diagnostic,
+            Self::SerializationError(diagnostic) => diagnostic,
+            Self::DatabaseError(diagnostic) => diagnostic,
+            Self::CompileError(diagnostic) => diagnostic,
+            Self::EquivalenceError(diagnostic) => diagnostic,
+            Self::FetchError(diagnostic) => diagnostic,
+            Self::SchemaError(diagnostic) => diagnostic,
+            Self::PairingError(diagnostic) => diagnostic,
+            Self::PluginError(diagnostic) => diagnostic,
+            Self::BenchError(diagnostic) => diagnostic,
+            Self::PublishError(diagnostic) => diagnostic,
+            Self::SnapshotError(diagnostic) => diagnostic,
+        }
+    }
+    
+    /// The stage that failed, as `--error-format json` names it
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::ParseError(_) => "parse",
+            Self::TransformError(_) => "transform",
+            Self::GenerationError(_) => "generation",
+            Self::TemplateError(_) => "template",
+            Self::SerializationError(_) => "serialization",
+            Self::DatabaseError(_) => "database",
+            Self::CompileError(_) => "compile",
+            Self::EquivalenceError(_) => "equivalence",
+            Self::FetchError(_) => "fetch",
+            Self::SchemaError(_) => "schema",
+            Self::PairingError(_) => "pairing",
+            Self::PluginError(_) => "plugin",
+            Self::BenchError(_) => "bench",
+            Self::PublishError(_) => "publish",
+            Self::SnapshotError(_) => "snapshot",
+        }
+    }
+    
+    /// The error as found in `file`, for failures found before it was known
+    pub(crate) fn in_file(mut self, file: String) -> Self {
+        self.diagnostic_mut().file.get_or_insert(file);
+        self
+    }
+    
+    /// The diagnostic as one JSON object, with the error's `kind`
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = serde_json::to_value(self.diagnostic()).unwrap_or_default();
+        json["kind"] = self.kind().into();
+        json
+    }
 }
 
 impl std::fmt::Display for TransmutationError {
@@ -471,6 +1767,15 @@ impl std::fmt::Display for TransmutationError {
             Self::TemplateError(e) => write!(f, "Template error: {}", e),
             Self::SerializationError(e) => write!(f, "Serialization error: {}", e),
             Self::DatabaseError(e) => write!(f, "Database error: {}", e),
+            Self::CompileError(e) => write!(f, "Compile error: {}", e),
+            Self::EquivalenceError(e) => write!(f, "Equivalence error: {}", e),
+            Self::FetchError(e) => write!(f, "Fetch error: {}", e),
+            Self::SchemaError(e) => write!(f, "Schema error: {}", e),
+            Self::PairingError(e) => write!(f, "Pairing error: {}", e),
+            Self::PluginError(e) => write!(f, "Plugin error: {}", e),
+            Self::BenchError(e) => write!(f, "Bench error: {}", e),
+            Self::PublishError(e) => write!(f, "Publish error: {}", e),
+            Self::SnapshotError(e) => write!(f, "Snapshot error: {}", e),
         }
     }
 }
@@ -479,15 +1784,24 @@ impl std::error::Error for TransmutationError {}
 
 // Templates
 
-const RUST_MODULE_TEMPLATE: &str = r#"
-// Auto-generated by Soul Forge
-// Soul resonance: {{ resonance }}
-// Original: {{ npm_name }}
+/// The built-in templates, each named as the file in a `--templates`
+/// directory that overrides it
+const TEMPLATES: &[(&str, &str)] = &[
+    ("rust_module.tera", RUST_MODULE_TEMPLATE),
+    ("wasm_bindings.tera", WASM_BINDINGS_TEMPLATE),
+    ("wasm_node_bindings.tera", WASM_NODE_BINDINGS_TEMPLATE),
+    ("napi_loader.tera", NAPI_LOADER_TEMPLATE),
+    ("c_header.tera", C_HEADER_TEMPLATE),
+    ("cargo_toml.tera", CARGO_TOML_TEMPLATE),
+    ("workspace_toml.tera", WORKSPACE_TOML_TEMPLATE),
+    ("workspace_readme.tera", WORKSPACE_README_TEMPLATE),
+    ("report_html.tera", report::TEMPLATE),
+];
 
-use wasm_bindgen::prelude::*;
+const RUST_MODULE_TEMPLATE: &str = r#"// Generated by Soul Forge - Same soul, different body
+// Original: {{ npm_name }} {{ version }}, soul resonance {{ resonance | round(precision=3) }}
 
-{{ code }}
-"#;
+{{ code }}"#;
 
 const WASM_BINDINGS_TEMPLATE: &str = r#"
 import init, { {{ exports }} } from './pkg/{{ name }}.js';
@@ -499,22 +1813,614 @@ export async function initialize() {
 export { {{ exports }} };
 "#;
 
+const WASM_NODE_BINDINGS_TEMPLATE: &str = r#"
+// {{ name }} {{ version }} for Node; `wasm-pack build --target nodejs` builds pkg/
+const { {{ exports }} } = require('./pkg/{{ name }}.js');
+
+module.exports = { {{ exports }} };
+"#;
+
+const NAPI_LOADER_TEMPLATE: &str = r#"
+// {{ name }} {{ version }} as a native Node addon; `napi build --release` builds index.node
+const { {{ exports }} } = require('./index.node');
+
+module.exports = { {{ exports }} };
+"#;
+
+const C_HEADER_TEMPLATE: &str = r#"// {{ name }} {{ version }} for C; `cargo build --release` builds the shared library
+#ifndef {{ guard }}
+#define {{ guard }}
+
+#include <stdbool.h>
+#include <stdint.h>
+
+{% for declaration in declarations -%}
+{{ declaration }}
+{% endfor %}
+#endif
+"#;
+
 const CARGO_TOML_TEMPLATE: &str = r#"
 [package]
 name = "{{ name }}"
 version = "{{ version }}"
 description = "{{ description }} (Soul-transmuted from {{ npm_name }})"
 edition = "2021"
-
+{% if license %}license = {{ license | json_encode() }}
+{% endif %}{% if authors %}authors = {{ authors | json_encode() }}
+{% endif %}{% if repository %}repository = {{ repository | json_encode() }}
+{% endif %}{% if keywords %}keywords = {{ keywords | json_encode() }}
+{% endif %}
 [lib]
-crate-type = ["cdylib", "rlib"]
+crate-type = [{{ crate_types }}]
 
 [dependencies]
-wasm-bindgen = "0.2"
-serde = { version = "1.0", features = ["derive"] }
-serde-wasm-bindgen = "0.6"
+{% for dependency in dependencies -%}
+{{ dependency.0 }} = {{ dependency.1 }}
+{% endfor %}{% if build_dependencies %}
+[build-dependencies]
+{% for dependency in build_dependencies -%}
+{{ dependency.0 }} = {{ dependency.1 }}
+{% endfor %}{% endif %}{% if dev_dependencies %}
+[dev-dependencies]
+{% for dependency in dev_dependencies -%}
+{{ dependency.0 }} = {{ dependency.1 }}
+{% endfor %}
+[[bench]]
+name = "{{ bench }}"
+harness = false
+{% endif %}{% if not member %}
+[profile.release]
+opt-level = 3
+lto = true
+{% if overflow_checks %}overflow-checks = true
+{% endif %}{% endif %}"#;
+
+const WORKSPACE_TOML_TEMPLATE: &str = r#"[workspace]
+resolver = "2"
+members = [
+{%- for member in members %}
+    "{{ member.crate_name }}",
+{%- endfor %}
+]
 
+[workspace.dependencies]
+{% for dependency in dependencies -%}
+{{ dependency.0 }} = {{ dependency.1 }}
+{% endfor %}
 [profile.release]
 opt-level = 3
 lto = true
-"#;
\ No newline at end of file
+{% if overflow_checks %}overflow-checks = true
+{% endif %}"#;
+
+const WORKSPACE_README_TEMPLATE: &str = r#"# Soul-transmuted crates
+
+{{ members | length }} npm packages transmuted into Rust, with a mean soul resonance of {{ mean | round(precision=3) }}.
+
+| Crate | npm package | Version | Resonance |
+|---|---|---|---|
+{% for member in members -%}
+| `{{ member.crate_name }}` | `{{ member.npm_name }}` | {{ member.version }} | {% if member.resonance > 0.95 %}✅{% elif member.resonance > 0.8 %}⚠️{% else %}❌{% endif %} {{ member.resonance | round(precision=3) }} |
+{% endfor %}"#;
+
+// Generated support code
+
+const WASM_SPAWN_GLUE: &str = r#"/// Drives a promise nobody awaits to completion, as JS would
+fn spawn_promise<T: 'static>(promise: impl std::future::Future<Output = Result<T, JsError>> + 'static) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let _ = promise.await;
+    });
+}
+
+"#;
+
+const NATIVE_SPAWN_GLUE: &str = r#"/// Drives a promise nobody awaits to completion, as JS would
+fn spawn_promise<T: Send + 'static>(promise: impl std::future::Future<Output = Result<T, JsError>> + Send + 'static) {
+    tokio::spawn(async move {
+        let _ = promise.await;
+    });
+}
+
+"#;
+
+const WASM_SLEEP_GLUE: &str = r#"/// `new Promise(resolve => setTimeout(resolve, ms))`
+async fn sleep_ms(ms: f64) -> Result<(), JsError> {
+    gloo_timers::future::TimeoutFuture::new(ms as u32).await;
+    Ok(())
+}
+
+"#;
+
+const NATIVE_SLEEP_GLUE: &str = r#"/// `new Promise(resolve => setTimeout(resolve, ms))`
+async fn sleep_ms(ms: f64) -> Result<(), JsError> {
+    tokio::time::sleep(std::time::Duration::from_millis(ms as u64)).await;
+    Ok(())
+}
+
+"#;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A forge keeping its database in `db`
+    fn forge(db: &tempfile::TempDir) -> SoulForge {
+        // Refinement reshapes code toward the soul; these check it as generated
+        SoulForge::new(db.path().to_str().unwrap()).unwrap().with_max_refinements(0)
+    }
+
+    fn metadata() -> PackageMetadata {
+        PackageMetadata {
+            name: "sample-soul".to_string(),
+            version: "0.1.0".to_string(),
+            description: "Sample".to_string(),
+            npm_name: "sample".to_string(),
+            crate_name: "sample-soul".to_string(),
+            source_file: None,
+            bindings: Bindings::Wasm,
+            profile: Profile::WasmWeb,
+            license: None,
+            authors: vec![],
+            repository: None,
+            keywords: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_typescript_signatures() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "export function scale<T>(values: number[], factor = 2, label?: string): Array<number> { return [] }\n\
+                    export function log(message: string): void {}\n\
+                    interface Point { x: number; y: number }";
+        let transmutation = forge(&db).transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+
+        assert!(transmutation.rust_code.contains("pub fn scale<T>(values: Vec<f64>, factor: f64, label: Option<String>) -> Vec<f64> {"));
+        assert!(transmutation.rust_code.contains("pub fn log(message: String) {"));
+        assert!(transmutation.rust_code.contains("pub struct Point {"));
+    }
+
+    #[tokio::test]
+    async fn test_functions_name_their_source_lines() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "export class Counter {\n  count = 0;\n  bump(): number { return ++this.count; }\n}\n\nexport const double = (x: number): number => x * 2;\n";
+        let metadata = PackageMetadata { source_file: Some("src/index.ts".to_string()), ..metadata() };
+        let transmutation = forge(&db).transmute_source(code, SourceKind::TypeScript, metadata).await.unwrap();
+
+        assert!(transmutation.rust_code.contains("    /// Transmuted from `src/index.ts:3`\n    pub fn bump(&mut self) -> f64 {"));
+        assert!(transmutation.rust_code.contains("/// Transmuted from `src/index.ts:6`\npub fn double(x: f64) -> f64 {"));
+        let map: serde_json::Value = serde_json::from_str(&transmutation.source_map).unwrap();
+        assert_eq!(map["sources"], serde_json::json!(["src/index.ts"]));
+        // Functions' lines are mapped, the prelude's aren't
+        let segments: Vec<&str> = map["mappings"].as_str().unwrap().split(';').collect();
+        for (line, segment) in transmutation.rust_code.lines().zip(&segments) {
+            if line.contains("/// Transmuted") {
+                assert!(!segment.is_empty(), "{}", line);
+            }
+            if line.starts_with("use ") {
+                assert!(segment.is_empty(), "{}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn test_infer_type_for_async_and_callbacks() {
+        let db = tempfile::tempdir().unwrap();
+        let forge = forge(&db);
+        let function = JsFunction {
+            name: "fetch".to_string(),
+            params: vec![],
+            return_type: JsType::Promise(Box::new(JsType::Map(Box::new(JsType::Number), Box::new(JsType::String)))),
+            type_params: vec![],
+            is_async: true,
+            is_generator: false,
+            body: vec![],
+            origin: None,
+        };
+        assert_eq!(forge.infer_return_type(&function), "Result<HashMap<i64, String>, JsError>");
+
+        let callback = JsType::Function(vec![JsType::Number, JsType::Unknown], Box::new(JsType::Boolean));
+        assert_eq!(forge.infer_type(&callback), "impl Fn(f64, Value) -> bool");
+        assert_eq!(forge.infer_type(&JsType::Tuple(vec![JsType::String])), "(String,)");
+    }
+
+    #[tokio::test]
+    async fn test_closure_bounds_follow_use() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "export function apply(f, x) { return f(x); }\n\
+                    export function times(n: number, step: (i: number) => void): void { for (let i = 0; i < n; i++) step(i); }\n\
+                    export function counter(start: number): () => number { let count = start; return () => ++count; }\n\
+                    export function map(xs: number[]): number[] { return xs.map(x => x * 3); }";
+        let transmutation = forge(&db).transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+
+        assert!(transmutation.rust_code.contains("pub fn apply(f: impl FnOnce(Value) -> Value, x: Value) -> Value {"));
+        assert!(transmutation.rust_code.contains("pub fn times(n: f64, mut step: impl FnMut(f64)) {"));
+        assert!(transmutation.rust_code.contains("pub fn counter(start: f64) -> impl FnMut() -> f64 {"));
+        assert!(transmutation.rust_code.contains("    move || {"));
+        // A function named `map` is transmuted like any other
+        assert!(transmutation.rust_code.contains("xs.iter().map(|x| *x * 3.0).collect::<Vec<_>>()"));
+    }
+
+    #[tokio::test]
+    async fn test_untyped_signatures_are_inferred() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "export function identity(x) { return x; }\n\
+                    export function total(items) { let sum = 0; for (const item of items) { sum += item; } return sum; }\n\
+                    export function main() { const n = identity(5); identity('five'); return total([n]) + n; }";
+        let transmutation = forge(&db).transmute_source(code, SourceKind::JavaScript, metadata()).await.unwrap();
+
+        assert!(transmutation.rust_code.contains("pub fn identity<T: Clone>(x: T) -> T {"));
+        assert!(transmutation.rust_code.contains("pub fn total(items: Vec<f64>) -> f64 {"));
+        // A call to a generic function gives the type of its argument
+        assert!(transmutation.rust_code.contains("pub fn main() -> f64 {\n    let n = identity(5.0);\n    identity(\"five\".to_string());\n    total(vec![n]) + n\n}"));
+    }
+
+    #[tokio::test]
+    async fn test_classes_map_onto_traits() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "class Animal { constructor(public name: string) {} speak(): string { return this.name; } static create(): Animal { return new Animal('x'); } }\n\
+                    class Dog extends Animal { breed = 'lab'; speak(): string { return super.speak() + '!'; } get loud(): string { return this.speak(); } }\n\
+                    class Puppy extends Dog { rename(name: string): void { this.name = name; } }";
+        let transmutation = forge(&db).transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+        let rust = &transmutation.rust_code;
+
+        assert!(rust.contains("pub struct Dog {\n    pub animal: Animal,\n    pub breed: String,\n}"));
+        assert!(rust.contains("pub trait AnimalLike {\n    fn animal(&self) -> &Animal;"));
+        assert!(rust.contains("pub trait DogLike: AnimalLike {"));
+        assert!(rust.contains("let this = Self { animal: Animal::new(name.clone()), breed: \"lab\".to_string() };"));
+        assert!(rust.contains("impl AnimalLike for Dog {"));
+        assert!(rust.contains("format!(\"{}!\", self.animal.speak())"));
+        // Puppy inherits Dog's override and reaches Animal's fields through Dog
+        assert!(rust.contains("        self.dog.speak()"));
+        assert!(rust.contains("pub fn rename(&mut self, name: String) {\n        self.dog.animal.name = name;"));
+        assert!(rust.contains("pub fn create() -> Animal {"));
+        assert!(rust.contains("pub fn loud(&self) -> String {"));
+    }
+
+    #[tokio::test]
+    async fn test_default_methods_reach_base_fields_through_accessors() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "class Animal { constructor(public name: string) {} describe(): string { return 'I am ' + this.name; } rename(name: string): void { this.name = name; } }\n\
+                    class Dog extends Animal { fetch(): string { return this.describe(); } }";
+        let transmutation = forge(&db).transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+        let rust = &transmutation.rust_code;
+
+        // Trait default methods run on any class extending Animal, so they
+        // can't name its field directly
+        assert!(rust.contains("    fn describe(&self) -> String {\n        format!(\"I am {}\", self.animal().name.clone())\n    }"));
+        assert!(rust.contains("    fn rename(&mut self, name: String) {\n        self.animal_mut().name = name;\n    }"));
+        assert!(rust.contains("    fn animal(&self) -> &Animal {\n        &self.animal\n    }"));
+    }
+
+    #[tokio::test]
+    async fn test_exceptions_map_onto_results() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "class ParseError extends Error {}\n\
+                    export function parse(text: string): number { if (text === '') throw new ParseError('empty'); return 1; }\n\
+                    export function sum(a: string, b: string): number { return parse(a) + parse(b); }\n\
+                    export function orZero(a: string): number { try { return parse(a); } catch { return 0; } }\n\
+                    class Meter { constructor(public value: number) { if (value < 0) throw new RangeError('negative'); } }\n\
+                    class Gauge extends Meter {}";
+        let transmutation = forge(&db).transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+        let rust = &transmutation.rust_code;
+
+        assert!(rust.contains("pub fn parse(text: String) -> Result<f64, JsError> {"));
+        assert!(rust.contains("return Err(JsError::ParseError(\"empty\".to_string()));"));
+        assert!(rust.contains("pub fn sum(a: String, b: String) -> Result<f64, JsError> {\n    Ok(parse(a.clone())? + parse(b.clone())?)"));
+        // Caught inside, so callers see a plain value
+        assert!(rust.contains("pub fn orZero(a: String) -> f64 {"));
+        assert!(rust.contains("pub fn new(value: f64) -> Result<Self, JsError> {"));
+        assert!(rust.contains("Self { meter: Meter::new(value)? };"));
+        // Error classes are variants rather than structs
+        assert!(!rust.contains("pub struct ParseError"));
+        assert!(rust.contains("pub enum JsError {\n    Error(String),\n    ParseError(String),\n    RangeError(String),\n}"));
+    }
+
+    #[tokio::test]
+    async fn test_async_glue_follows_target() {
+        let db = tempfile::tempdir().unwrap();
+        let other_db = tempfile::tempdir().unwrap();
+        let code = "export async function later(ms: number): Promise<void> {\n\
+                      await new Promise(resolve => setTimeout(resolve, ms));\n\
+                      later(ms);\n\
+                    }";
+        let mut wasm = forge(&db);
+        let transmutation = wasm.transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+        let cargo_toml = wasm.generate_cargo_toml(&metadata(), "").unwrap();
+        assert!(transmutation.rust_code.contains("pub enum JsError {\n    Error(String),\n}"));
+        assert!(transmutation.rust_code.contains("wasm_bindgen_futures::spawn_local"));
+        assert!(transmutation.rust_code.contains("gloo_timers::future::TimeoutFuture"));
+        assert!(cargo_toml.contains("wasm-bindgen-futures = \"0.4\""));
+        assert!(!cargo_toml.contains("tokio"));
+
+        let mut native = forge(&other_db).with_async_target(AsyncTarget::Native);
+        let transmutation = native.transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+        let cargo_toml = native.generate_cargo_toml(&metadata(), "").unwrap();
+        assert!(transmutation.rust_code.contains("tokio::spawn"));
+        assert!(transmutation.rust_code.contains("tokio::time::sleep"));
+        assert!(cargo_toml.contains("tokio = { version = \"1\""));
+        assert!(!cargo_toml.contains("wasm-bindgen-futures"));
+        assert_eq!("native".parse::<AsyncTarget>(), Ok(AsyncTarget::Native));
+    }
+
+    #[tokio::test]
+    async fn test_napi_bindings_wrap_what_node_can_call() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "export function parse(text: string): number {\n\
+                      if (text === \"\") { throw new Error(\"empty\"); }\n\
+                      return Number(text);\n\
+                    }\n\
+                    export function twice(f: (x: number) => number, x: number): number { return f(f(x)); }\n\
+                    export function tags(names: string[]): string[] { return names; }";
+        let addon = PackageMetadata { bindings: Bindings::Napi, ..metadata() };
+        let mut forge = forge(&db);
+        let transmutation = forge.transmute_source(code, SourceKind::TypeScript, addon.clone()).await.unwrap();
+        assert!(transmutation.rust_code.contains("mod node {\n    use super::*;\n    use napi_derive::napi;\n"));
+        assert!(transmutation.rust_code.contains("    #[napi(js_name = \"parse\")]\n    pub fn parse(text: String) -> napi::Result<f64> {\n        super::parse(text).map_err(|error| napi::Error::from_reason(error.to_string()))\n    }"));
+        assert!(transmutation.rust_code.contains("    pub fn tags(names: Vec<String>) -> Vec<String> {\n        super::tags(names)\n    }"));
+        assert!(!transmutation.rust_code.contains("js_name = \"twice\""));
+        assert!(transmutation.js_bindings.contains("const { parse, tags } = require('./index.node');"));
+
+        let cargo_toml = forge.generate_cargo_toml(&addon, "").unwrap();
+        assert!(cargo_toml.contains("napi-derive = \"2\"\n\n[build-dependencies]\nnapi-build = \"2\"\n"));
+        assert!(!forge.generate_cargo_toml(&metadata(), "").unwrap().contains("napi"));
+        assert_eq!(Bindings::Napi.loader_file(), "index.js");
+        assert_eq!("napi".parse::<Bindings>(), Ok(Bindings::Napi));
+    }
+
+    #[tokio::test]
+    async fn test_profiles_decide_the_crate_built() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "export function add(a: number, b: number): number { return a + b; }\n\
+                    export function shout(text: string): string { return text.toUpperCase(); }";
+        let mut forge = forge(&db);
+        let ffi = PackageMetadata { profile: Profile::CdylibFfi, ..metadata() };
+        let transmutation = forge.transmute_source(code, SourceKind::TypeScript, ffi.clone()).await.unwrap();
+        assert!(!transmutation.rust_code.contains("wasm_bindgen"));
+        assert!(transmutation.rust_code.contains("    #[no_mangle]\n    pub extern \"C\" fn sample_soul_add(a: f64, b: f64) -> f64 {\n        super::add(a, b)\n    }"));
+        assert!(!transmutation.rust_code.contains("sample_soul_shout"));
+        assert!(transmutation.js_bindings.contains("#ifndef SAMPLE_SOUL_H\n"));
+        assert!(transmutation.js_bindings.contains("\ndouble sample_soul_add(double a, double b);\n"));
+        assert_eq!(ffi.bindings_file().as_deref(), Some("sample_soul.h"));
+
+        let native = PackageMetadata { profile: Profile::NativeLib, ..metadata() };
+        let cargo_toml = forge.generate_cargo_toml(&native, "").unwrap();
+        assert!(cargo_toml.contains("crate-type = [\"rlib\"]\n"));
+        assert!(!cargo_toml.contains("wasm-bindgen"));
+        assert_eq!(native.bindings_file(), None);
+        assert!(forge.generate_cargo_toml(&metadata(), "").unwrap().contains("crate-type = [\"cdylib\", \"rlib\"]\n"));
+
+        let node = PackageMetadata { profile: Profile::WasmNode, ..metadata() };
+        let transmutation = forge.transmute_source(code, SourceKind::TypeScript, node).await.unwrap();
+        assert!(transmutation.js_bindings.contains("const { add, shout } = require('./pkg/sample-soul.js');"));
+        assert_eq!("cdylib-ffi".parse::<Profile>(), Ok(Profile::CdylibFfi));
+    }
+
+    /// Makes every `f64` parameter an `i64` and hides `internal` from JS
+    struct Integers;
+
+    impl TransformPass for Integers {
+        fn run(&self, js_ast: &mut JsAst, rust_ast: &mut RustAst) {
+            js_ast.exports.retain(|name| name != "internal");
+            for param in rust_ast.functions.iter_mut().flat_map(|function| &mut function.params) {
+                param.ty = param.ty.replace("f64", "i64");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_passes_rewrite_modules_before_generation() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "export function internal(n: number): number { return n; }\n\
+                    export function total(xs: number[]): number { return xs.length; }";
+        let mut forge = forge(&db).with_pass(Integers);
+        let transmutation = forge.transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+        assert!(transmutation.rust_code.contains("pub fn internal(n: i64) -> f64"));
+        assert!(transmutation.rust_code.contains("pub fn total(xs: Vec<i64>) -> f64"));
+        assert!(transmutation.js_bindings.contains("import init, { total } from"));
+        assert!(Integers.name().ends_with("Integers"));
+    }
+
+    #[tokio::test]
+    async fn test_plans_store_nothing() {
+        let db = tempfile::tempdir().unwrap();
+        let mut forge = forge(&db);
+        let code = "export function area(w: number, h) { return w * h; }";
+        let plan = forge.plan_source(code, SourceKind::TypeScript, &metadata()).await.unwrap();
+        let signatures: Vec<&str> = plan.items.iter().map(|item| item.signature.as_str()).collect();
+        assert_eq!(signatures, ["pub fn area(w: f64, h: f64) -> f64"]);
+        assert_eq!(plan.items[0].origin.as_deref(), Some("sample:1"));
+        assert_eq!(plan.bound, ["area"]);
+        assert!(forge.get_transmutation("sample").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_templates_directory_overrides_built_ins() {
+        let db = tempfile::tempdir().unwrap();
+        let other_db = tempfile::tempdir().unwrap();
+        let templates = tempfile::tempdir().unwrap();
+        let dir = templates.path();
+        std::fs::write(dir.join("rust_module.tera"), "// {{ npm_name }}, forged in house\n{{ code }}").unwrap();
+        std::fs::write(dir.join("cargo_toml.tera"), "[package]\nname = \"{{ name }}\"\nedition = \"2024\"\n").unwrap();
+        let mut custom = forge(&db).with_templates(dir).unwrap();
+        let transmutation = custom.transmute_source("export function id(x: number): number { return x; }", SourceKind::TypeScript, metadata()).await.unwrap();
+        assert!(transmutation.rust_code.starts_with("// forge:begin header "));
+        assert!(transmutation.rust_code.contains("\n// sample, forged in house\n// forge:end header\n// forge:begin uses "));
+        assert_eq!(custom.generate_cargo_toml(&metadata(), "").unwrap(), "[package]\nname = \"sample-soul\"\nedition = \"2024\"\n");
+        assert!(custom.generate_workspace_toml(&[]).unwrap().starts_with("[workspace]\n"));
+
+        std::fs::write(dir.join("cargo.tera"), "").unwrap();
+        assert!(matches!(forge(&other_db).with_templates(dir), Err(TransmutationError::TemplateError(e)) if e.message.contains("cargo.tera")));
+    }
+
+    #[tokio::test]
+    async fn test_souls_are_kept_across_runs() {
+        let db = tempfile::tempdir().unwrap();
+        let other_db = tempfile::tempdir().unwrap();
+        let cache = tempfile::tempdir().unwrap();
+        let path = cache.path().join("souls.json");
+        let code = "export function half(x: number): number { return x / 2; }";
+        let mut first = forge(&db).with_soul_cache(&path).unwrap();
+        first.transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+        assert!(first.cache.is_empty());
+
+        // A later run finds the source's soul without extracting it
+        let again = forge(&other_db).with_soul_cache(&path).unwrap();
+        let key = again.soul_extractor.cache_key(&Language::JavaScript, code);
+        assert!(again.soul_extractor.cached_soul(&key).is_some());
+        assert!(again.fork().soul_extractor.cached_soul(&key).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_imports_of_transmuted_packages_use_their_crates() {
+        let db = tempfile::tempdir().unwrap();
+        let other_db = tempfile::tempdir().unwrap();
+        let pad = PackageMetadata { npm_name: "left-pad".to_string(), crate_name: "left-pad-soul".to_string(), ..metadata() };
+        let padded = forge(&db).transmute_source("export function leftPad(text: string, width: number): string { return text; }", SourceKind::TypeScript, pad).await.unwrap();
+        let dependency = CrateDependency::of(&padded, DependencySource::Path(PathBuf::from("/crates/left-pad-soul")));
+
+        let mut forge = forge(&other_db).with_dependencies(vec![dependency]);
+        let code = "import leftPad from 'left-pad';\nimport { chunk } from 'lodash';\nexport function label(text: string) { return leftPad(text, 8); }";
+        let transmutation = forge.transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+        assert!(transmutation.rust_code.contains("use left_pad_soul::leftPad;"));
+        assert!(transmutation.rust_code.contains("pub fn label(text: String) -> String {"));
+        assert!(transmutation.rust_code.contains("// `chunk` from \"lodash\" isn't transmuted"));
+        let cargo_toml = forge.generate_cargo_toml(&metadata(), &transmutation.rust_code).unwrap();
+        assert!(cargo_toml.contains("left-pad-soul = { path = \"/crates/left-pad-soul\", version = \"0.1.0\" }"));
+        assert!(forge.generate_member_cargo_toml(&metadata(), &transmutation.rust_code).unwrap().contains("left-pad-soul = { path = \"../left-pad-soul\", version = \"0.1.0\" }"));
+    }
+
+    #[test]
+    fn test_package_json_fills_the_manifest() {
+        let db = tempfile::tempdir().unwrap();
+        let forge = forge(&db);
+        let manifest = serde_json::json!({
+            "license": "mit",
+            "author": "Ada Lovelace <ada@example.com> (https://ada.dev)",
+            "contributors": [{ "name": "Charles \"Engine\" Babbage", "email": "cb@example.com" }],
+            "repository": { "type": "git", "url": "git+https://github.com/acme/sample.git" },
+            "keywords": ["array", "Split Array", "🧬", "array"],
+        });
+        let sample = metadata().with_package_json(&manifest);
+        let cargo_toml: toml::Value = toml::from_str(&forge.generate_cargo_toml(&sample, "").unwrap()).unwrap();
+        let package = &cargo_toml["package"];
+        assert_eq!(package["license"].as_str(), Some("MIT"));
+        assert_eq!(package["authors"][1].as_str(), Some("Charles \"Engine\" Babbage <cb@example.com>"));
+        assert_eq!(package["repository"].as_str(), Some("https://github.com/acme/sample"));
+        assert_eq!(package["keywords"].as_array().unwrap().len(), 2);
+        assert!(sample.license_warning().is_none());
+
+        let proprietary = sample.with_package_json(&serde_json::json!({ "license": "SEE LICENSE IN EULA.txt" }));
+        assert!(proprietary.license_warning().unwrap().contains("SEE LICENSE IN EULA.txt"));
+        assert!(!forge.generate_cargo_toml(&proprietary, "").unwrap().contains("license"));
+    }
+
+    #[test]
+    fn test_batch_output_is_one_workspace() {
+        let db = tempfile::tempdir().unwrap();
+        let forge = forge(&db);
+        let member = |npm_name: &str, resonance| WorkspaceMember {
+            crate_name: format!("{}-soul", npm_name),
+            npm_name: npm_name.to_string(),
+            version: "1.0.0".to_string(),
+            resonance,
+        };
+        let members = [member("left-pad", 0.5), member("chunk", 0.97)];
+
+        let cargo_toml = forge.generate_member_cargo_toml(&metadata(), "").unwrap();
+        assert!(cargo_toml.contains("[dependencies]\nwasm-bindgen = { workspace = true }\nserde = { workspace = true }\n"));
+        assert!(!cargo_toml.contains("[profile.release]"));
+        assert!(forge.generate_cargo_toml(&metadata(), "").unwrap().contains("serde = { version = \"1.0\", features = [\"derive\"] }\n"));
+
+        let workspace = forge.generate_workspace_toml(&members).unwrap();
+        assert!(workspace.starts_with("[workspace]\nresolver = \"2\"\nmembers = [\n    \"left-pad-soul\",\n    \"chunk-soul\",\n]\n"));
+        assert!(workspace.contains("tokio = { version = \"1\""));
+        assert!(workspace.contains("wasm-bindgen-futures = \"0.4\""));
+
+        let readme = forge.generate_workspace_readme(&members).unwrap();
+        assert!(readme.contains("2 npm packages transmuted into Rust, with a mean soul resonance of 0.735."));
+        assert!(readme.contains("| `chunk-soul` | `chunk` | 1.0.0 | ✅ 0.97 |\n| `left-pad-soul` | `left-pad` | 1.0.0 | ❌ 0.5 |"));
+    }
+    
+    #[tokio::test]
+    async fn test_report_shows_the_api_and_resonance() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "export function pick<T>(items: T[], index: number): T { return items[index]; }";
+        let mut forge = forge(&db);
+        let transmutation = forge.transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+        let html = forge.generate_report(&transmutation).await.unwrap();
+
+        assert!(html.contains("<code>pub fn pick&lt;T&gt;(items: Vec&lt;T&gt;, index: f64) -&gt; T</code>"));
+        assert!(html.contains(&format!("stroke-dasharray=\"{} ", transmutation.resonance.clamp(0.0, 1.0) * std::f64::consts::PI * 80.0)));
+        assert!(html.contains("Not checked"));
+    }
+
+    #[tokio::test]
+    async fn test_destructuring_and_spreads_keep_their_shape() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "interface Point { x: number; y: number }\n\
+            export function norm({ x, y }: Point): number { return Math.sqrt(x * x + y * y); }\n\
+            export function shift(point: Point, dx: number): Point { return { ...point, x: point.x + dx }; }\n\
+            export function sum(...values: number[]): number { let total = 0; for (const v of values) total += v; return total; }\n\
+            export function rest(values: number[]): number { const [first, , ...others] = values; return sum(first, ...others) + Math.max(...values); }";
+        let transmutation = forge(&db).transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+
+        assert!(transmutation.rust_code.contains("pub fn norm(arg0: Point) -> f64 {\n    let Point { x, y, .. } = &arg0;"));
+        assert!(transmutation.rust_code.contains("Point { x: point.x + dx, ..point.clone() }"));
+        assert!(transmutation.rust_code.contains("let [first, _, others @ ..] = values.as_slice() else"));
+        assert!(transmutation.rust_code.contains("sum([&[*first][..], others.as_slice()].concat())"));
+        assert!(transmutation.rust_code.contains("values.iter().copied().fold(f64::NEG_INFINITY, f64::max)"));
+    }
+
+    #[tokio::test]
+    async fn test_modules_nest_under_the_crate_root() {
+        let db = tempfile::tempdir().unwrap();
+        let modules = [
+            JsModule::new("index.ts", "import { add } from './utils/math';\nimport * as strings from './strings';\nexport * from './utils/math';\nexport function loud(text: string): string { return strings.shout(text) + add(1, 2); }"),
+            JsModule::new("utils/math.ts", "export function add(a: number, b: number): number { return a + b; }"),
+            JsModule::new("strings.ts", "export function shout(text: string): string { return text.toUpperCase(); }"),
+        ];
+        let transmutation = forge(&db).transmute_modules(&modules, metadata()).await.unwrap();
+
+        assert!(transmutation.rust_code.contains("use crate::utils::math::add;"));
+        assert!(transmutation.rust_code.contains("pub use crate::utils::math::*;"));
+        assert!(transmutation.rust_code.contains("strings::shout(text"));
+        assert!(transmutation.rust_code.contains("pub mod utils {\n    pub mod math {"));
+    }
+
+    #[tokio::test]
+    async fn test_string_and_regex_methods_map_to_std_and_regex() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "export function slug(title: string): string { return title.trim().toLowerCase().replace(/[^a-z0-9]+/g, \"-\"); }\n\
+            export function swap(text: string): string { return text.replace(/(\\w+) (\\w+)/, \"$2 $1\"); }\n\
+            export function digits(text: string): boolean { return /\\d/.test(text); }\n\
+            export function pad(text: string): string { return text.padStart(5, \"0\"); }";
+        let mut forge = forge(&db);
+        let transmutation = forge.transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+        assert!(transmutation.rust_code.contains("regex::Regex::new(\"[^a-z0-9]+\").unwrap().replace_all(&title.trim().to_string().to_lowercase(), \"-\")"));
+        assert!(transmutation.rust_code.contains(".replace(&text, \"${2} ${1}\")"));
+        assert!(transmutation.rust_code.contains("regex::Regex::new(\"\\\\d\").unwrap().is_match(&text)"));
+        assert!(transmutation.rust_code.contains("\"0\".chars().cycle().take(pad)"));
+
+        assert!(forge.generate_cargo_toml(&metadata(), &transmutation.rust_code).unwrap().contains("regex = \"1\"\n"));
+        assert!(!forge.generate_cargo_toml(&metadata(), "pub fn id(x: f64) -> f64 { x }").unwrap().contains("regex"));
+    }
+
+    #[tokio::test]
+    async fn test_generators_become_iterators_and_streams() {
+        let db = tempfile::tempdir().unwrap();
+        let code = "export function* range(start, end) { for (let i = start; i < end; i++) { yield i; } }\n\
+            export function* evens(limit) { yield* range(0, limit / 2); return 'done'; }\n\
+            export function total(limit) { let sum = 0; for (const x of range(0, limit)) { sum += x; } return sum; }\n\
+            export async function* pages(count: number): AsyncGenerator<string> { for (let page = 0; page < count; page++) { yield `page ${page}`; } }";
+        let mut forge = forge(&db);
+        let transmutation = forge.transmute_source(code, SourceKind::TypeScript, metadata()).await.unwrap();
+
+        assert!(transmutation.rust_code.contains("pub fn range(start: f64, end: f64) -> impl Iterator<Item = f64> {\n    genawaiter::rc::Gen::new(|co| async move {"));
+        assert!(transmutation.rust_code.contains("            co.yield_(i).await;\n"));
+        assert!(transmutation.rust_code.contains("        for item1 in range(0.0, limit / 2.0) {\n            co.yield_(item1).await;\n        }\n    }).into_iter()\n}"));
+        assert!(transmutation.rust_code.contains("    for x in range(0.0, limit) {\n        sum += x;"));
+        assert!(transmutation.rust_code.contains("pub fn pages(count: f64) -> impl futures::Stream<Item = Result<String, JsError>> {"));
+        assert!(transmutation.rust_code.contains("co.yield_(Ok(format!(\"page {}\", page))).await;"));
+        assert!(forge.generate_cargo_toml(&metadata(), &transmutation.rust_code).unwrap().contains("genawaiter = { version = \"0.99\", features = [\"futures03\"] }\n"));
+    }
+}
@@ -0,0 +1,102 @@
+// ⚖️ Licenses - Carrying npm's License Field Into Cargo's SPDX
+/// SPDX license identifiers npm packages use, in their canonical case
+const LICENSES: &[&str] = &[
+    "0BSD", "AFL-3.0", "AGPL-3.0", "AGPL-3.0-only", "AGPL-3.0-or-later", "Apache-1.1", "Apache-2.0",
+    "Artistic-2.0", "BlueOak-1.0.0", "BSD-1-Clause", "BSD-2-Clause", "BSD-3-Clause", "BSD-3-Clause-Clear",
+    "BSL-1.0", "CC-BY-3.0", "CC-BY-4.0", "CC-BY-SA-4.0", "CC0-1.0", "CDDL-1.0", "CECILL-2.1", "EPL-1.0",
+    "EPL-2.0", "EUPL-1.1", "EUPL-1.2", "GPL-2.0", "GPL-2.0-only", "GPL-2.0-or-later", "GPL-3.0",
+    "GPL-3.0-only", "GPL-3.0-or-later", "ISC", "LGPL-2.0", "LGPL-2.1", "LGPL-2.1-only", "LGPL-2.1-or-later",
+    "LGPL-3.0", "LGPL-3.0-only", "LGPL-3.0-or-later", "MIT", "MIT-0", "MPL-1.1", "MPL-2.0", "MS-PL",
+    "MulanPSL-2.0", "NCSA", "ODbL-1.0", "OFL-1.1", "PostgreSQL", "Python-2.0", "Ruby", "Unicode-3.0",
+    "Unicode-DFS-2016", "Unlicense", "UPL-1.0", "W3C", "WTFPL", "X11", "Zlib",
+];
+
+/// SPDX exceptions that follow `WITH`
+const EXCEPTIONS: &[&str] = &[
+    "Autoconf-exception-3.0", "Bison-exception-2.2", "Classpath-exception-2.0", "GCC-exception-3.1",
+    "LLVM-exception", "OpenSSL-exception",
+];
+
+/// `license`, as npm gives it, as the SPDX expression Cargo's `license`
+/// takes, or why it can't be one
+///
+/// Identifiers and operators are matched regardless of case and come back
+/// canonical; the legacy `/` separator becomes `OR`.
+pub(crate) fn spdx(license: &str) -> Result<String, String> {
+    let license = license.trim();
+    if license.eq_ignore_ascii_case("UNLICENSED") {
+        return Err("UNLICENSED marks the package proprietary".to_string());
+    }
+    if license.to_ascii_uppercase().starts_with("SEE LICENSE IN") {
+        return Err("the license is in a file, not an SPDX expression".to_string());
+    }
+
+    let spaced = license.replace('(', " ( ").replace(')', " ) ").replace('/', " OR ");
+    let mut expression: Vec<String> = vec![];
+    // Whether the next token is an operand rather than an operator
+    let mut operand = true;
+    let mut depth = 0usize;
+    let mut after_with = false;
+    for token in spaced.split_whitespace() {
+        let canonical = match (operand, token) {
+            (true, "(") => {
+                depth += 1;
+                "(".to_string()
+            }
+            (false, ")") if depth > 0 => {
+                depth -= 1;
+                ")".to_string()
+            }
+            (true, _) if after_with => {
+                after_with = false;
+                operand = false;
+                canonical(EXCEPTIONS, token).ok_or_else(|| format!("`{}` isn't an SPDX license exception", token))?
+            }
+            (true, _) => {
+                operand = false;
+                let (id, plus) = token.strip_suffix('+').map_or((token, ""), |id| (id, "+"));
+                match id.starts_with("LicenseRef-") {
+                    true => token.to_string(),
+                    false => canonical(LICENSES, id).ok_or_else(|| format!("`{}` isn't an SPDX license identifier", id))? + plus,
+                }
+            }
+            (false, _) => {
+                let operator = token.to_ascii_uppercase();
+                if !matches!(operator.as_str(), "AND" | "OR" | "WITH") {
+                    return Err(format!("expected AND, OR or WITH, found `{}`", token));
+                }
+                after_with = operator == "WITH";
+                operand = true;
+                operator
+            }
+        };
+        expression.push(canonical);
+    }
+    if operand || depth > 0 {
+        return Err("the expression is incomplete".to_string());
+    }
+    Ok(expression.join(" ").replace("( ", "(").replace(" )", ")"))
+}
+
+fn canonical(known: &[&str], id: &str) -> Option<String> {
+    known.iter().find(|known| known.eq_ignore_ascii_case(id)).map(|known| known.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_npm_licenses_become_spdx_expressions() {
+        assert_eq!(spdx("MIT").as_deref(), Ok("MIT"));
+        assert_eq!(spdx("mit").as_deref(), Ok("MIT"));
+        assert_eq!(spdx("MIT/Apache-2.0").as_deref(), Ok("MIT OR Apache-2.0"));
+        assert_eq!(spdx("(isc or bsd-3-clause) and GPL-2.0+").as_deref(), Ok("(ISC OR BSD-3-Clause) AND GPL-2.0+"));
+        assert_eq!(spdx("Apache-2.0 WITH LLVM-exception").as_deref(), Ok("Apache-2.0 WITH LLVM-exception"));
+        assert!(spdx("UNLICENSED").is_err());
+        assert!(spdx("SEE LICENSE IN LICENSE.md").is_err());
+        assert!(spdx("Apache 2.0").is_err());
+        assert!(spdx("(MIT OR").is_err());
+        assert!(spdx("MIT WITH ISC").is_err());
+    }
+}
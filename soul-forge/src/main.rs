@@ -3,9 +3,15 @@
 use clap::{Parser, Subcommand};
 use colored::Colorize;
 use indicatif::{ProgressBar, ProgressStyle};
-use soul_forge::{SoulForge, PackageMetadata};
+use soul_forge::npm::{self, NpmClient};
+use soul_forge::merge::{self, Conflict};
+use soul_forge::{bench, dependencies, pairs, publish, schema, snapshot};
+use soul_forge::{Action, AsyncTarget, Bindings, CrateDependency, DependencySource, Diagnostic, ErrorFormat, JobState, JsModule, Journal, NumericPolicy, Overflow, SoulForge, PackageMetadata, Profile, Transmutation, TransmutationError, TransmutationFilter, Verification, WorkspaceMember};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 #[derive(Parser)]
 #[command(name = "forge")]
@@ -14,13 +20,17 @@ use std::path::PathBuf;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    
+    /// How failures are printed: human, or json for editors and CI
+    #[arg(long, global = true, default_value = "human")]
+    error_format: ErrorFormat,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Transmute a JavaScript file to Rust
+    /// Transmute a JavaScript or TypeScript file to Rust
     Transmute {
-        /// Input JavaScript file
+        /// Input JavaScript (.js/.jsx) or TypeScript (.ts/.tsx) file
         #[arg(short, long)]
         input: PathBuf,
         
@@ -39,32 +49,190 @@ enum Commands {
         /// Package version
         #[arg(short = 'v', long, default_value = "0.1.0")]
         version: String,
+        
+        /// Runtime for async code: wasm or native (tokio)
+        #[arg(short, long, default_value = "wasm")]
+        target: AsyncTarget,
+        
+        /// How JS calls the crate: wasm (wasm-pack) or napi (a native Node
+        /// addon, best with `--target native`)
+        #[arg(long, default_value = "wasm")]
+        bindings: Bindings,
+        
+        /// What the crate is built as: wasm-web, wasm-node, native-lib or
+        /// cdylib-ffi (a shared library with a C header)
+        #[arg(long, default_value = "wasm-web")]
+        profile: Profile,
+        
+        /// How `number` is typed: js-float64 (f64 everywhere, as JS) or
+        /// idiomatic-int (locals only holding integers become i64)
+        #[arg(long, default_value = "js-float64")]
+        numeric: NumericPolicy,
+        
+        /// What idiomatic-int arithmetic does past i64: checked (panics),
+        /// wrapping or saturating
+        #[arg(long, default_value = "checked")]
+        overflow: Overflow,
+        
+        /// Check the generated crate compiles: off, check or clippy
+        #[arg(long, default_value = "off")]
+        verify: Verification,
+        
+        /// Run the source under Node and the crate under cargo test on the
+        /// same calls: generated ones, or those in a JSON fixtures file
+        #[arg(long, value_name = "FIXTURES", num_args = 0..=1)]
+        equivalence: Option<Option<PathBuf>>,
+        
+        /// Most rounds of structural refinement toward the JS soul
+        #[arg(long, default_value_t = 8)]
+        max_refinements: usize,
+        
+        /// Directory of .tera templates overriding the built-in ones
+        #[arg(long)]
+        templates: Option<PathBuf>,
+        
+        /// Plugin cdylib of transform passes to run; repeat for more
+        #[arg(long = "plugin")]
+        plugins: Vec<PathBuf>,
+        
+        /// Generate benches/ timing the crate and its source on the same
+        /// calls, for `forge bench`
+        #[arg(long)]
+        bench: bool,
+        
+        /// Print what would be generated, and its expected resonance,
+        /// without checking, storing or writing anything
+        #[arg(long)]
+        dry_run: bool,
     },
     
     /// Batch transmute from soul registry
-    Batch {
-        /// Soul registry JSON file
-        #[arg(short, long)]
-        registry: PathBuf,
+    Batch(BatchArgs),
+    
+    /// Verify soul matching between JS and Rust
+    Verify {
+        /// JavaScript file, directory or glob pattern
+        #[arg(short, long, required_unless_present = "manifest")]
+        js: Option<String>,
         
-        /// Output directory for all crates
-        #[arg(short, long)]
-        output: PathBuf,
+        /// Rust file, directory or glob pattern, matched to the JS files by name
+        #[arg(short, long, required_unless_present = "manifest")]
+        rust: Option<String>,
         
-        /// Only transmute top N packages
-        #[arg(short, long)]
-        top: Option<usize>,
+        /// JSON object pairing each JS file with its Rust file, instead of
+        /// matching by name
+        #[arg(short, long, conflicts_with_all = ["js", "rust"])]
+        manifest: Option<PathBuf>,
+        
+        /// Write each pair's resonance to this file as JSON
+        #[arg(long)]
+        json: Option<PathBuf>,
+        
+        /// Exit with an error when any pair resonates less than this
+        #[arg(long)]
+        min_resonance: Option<f64>,
     },
     
-    /// Verify soul matching between JS and Rust
-    Verify {
-        /// JavaScript file
-        #[arg(short, long)]
-        js: PathBuf,
+    /// Time a transmuted crate against its source, with the benches/ that
+    /// `transmute --bench` generated, and report the speedups
+    Bench {
+        /// Directory of the transmuted crate
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+        
+        /// Database the crate's transmutation is kept in
+        #[arg(short, long, default_value = "./forge.db")]
+        db: PathBuf,
+    },
+    
+    /// Write an HTML report of a stored transmutation, for reviewing the
+    /// crate before adopting it
+    Report {
+        /// NPM package name the transmutation is stored under
+        npm_name: String,
         
-        /// Rust file
+        /// HTML file to write (defaults to <crate_name>-report.html)
         #[arg(short, long)]
-        rust: PathBuf,
+        output: Option<PathBuf>,
+        
+        /// Database the transmutation is kept in
+        #[arg(short, long, default_value = "./forge.db")]
+        db: PathBuf,
+        
+        /// Check the stored code still compiles, and rerun its equivalence
+        /// tests: off, check or clippy
+        #[arg(long, default_value = "off")]
+        verify: Verification,
+        
+        /// Directory of .tera templates overriding the built-in ones, like
+        /// report_html.tera
+        #[arg(long)]
+        templates: Option<PathBuf>,
+    },
+    
+    /// Publish a stored transmutation's crate to crates.io: check its
+    /// metadata, bring its version and those of the crates it uses in line
+    /// with their npm packages, run its tests and `cargo publish` it
+    Publish {
+        /// NPM package name the transmutation is stored under
+        npm_name: String,
+        
+        /// Directory of the transmuted crate (defaults to ./<crate_name>)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        
+        /// Database the transmutation is kept in
+        #[arg(short, long, default_value = "./forge.db")]
+        db: PathBuf,
+        
+        /// Runtime for async code, as the crate was transmuted for: wasm or
+        /// native (tokio)
+        #[arg(short, long, default_value = "wasm")]
+        target: AsyncTarget,
+        
+        /// Tests to pass before publishing: off, check (cargo test, the
+        /// equivalence tests among them) or clippy (lints as errors too)
+        #[arg(long, default_value = "check")]
+        verify: Verification,
+        
+        /// Directory of .tera templates the crate was transmuted with
+        #[arg(long)]
+        templates: Option<PathBuf>,
+        
+        /// Package and build the crate as crates.io would, without uploading it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    
+    /// Refine a stored transmutation by hand: see where its resonance comes
+    /// from, try edits one at a time and keep those worth keeping
+    Refine {
+        /// NPM package name the transmutation is stored under
+        npm_name: String,
+        
+        /// Database the transmutation is kept in
+        #[arg(short, long, default_value = "./forge.db")]
+        db: PathBuf,
+    },
+    
+    /// Compare the Rust generated for each JS or TS fixture in a directory
+    /// with its snapshot, showing what changed for review
+    TestFixtures {
+        /// Directory of fixtures, their snapshots in its snapshots/
+        #[arg(default_value = "fixtures")]
+        dir: PathBuf,
+        
+        /// Record what's generated now as the snapshots
+        #[arg(long)]
+        accept: bool,
+        
+        /// Plugin cdylib of transform passes to run; repeat for more
+        #[arg(long = "plugin")]
+        plugins: Vec<PathBuf>,
+        
+        /// Database path
+        #[arg(short, long, default_value = "./forge.db")]
+        db: PathBuf,
     },
     
     /// Show transmutation statistics
@@ -72,34 +240,254 @@ enum Commands {
         /// Database path
         #[arg(short, long, default_value = "./forge.db")]
         db: PathBuf,
+        
+        /// Only count packages whose npm name starts with this
+        #[arg(long, default_value = "")]
+        prefix: String,
+        
+        /// Only count transmutations with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        
+        /// Print the statistics as JSON, without the banner
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Check or migrate the transmutation database
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
     },
 }
 
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Rewrite records stored by an older forge into the current layout
+    Migrate {
+        /// Database path
+        #[arg(short, long, default_value = "./forge.db")]
+        db: PathBuf,
+        
+        /// Drop records no known layout reads instead of stopping
+        #[arg(long)]
+        discard_unreadable: bool,
+    },
+    
+    /// Report the schema version and any records that can't be read
+    Doctor {
+        /// Database path
+        #[arg(short, long, default_value = "./forge.db")]
+        db: PathBuf,
+    },
+}
+
+#[derive(clap::Args)]
+struct BatchArgs {
+    /// Soul registry JSON file
+    #[arg(short, long)]
+    registry: PathBuf,
+    
+    /// Output directory for all crates
+    #[arg(short, long)]
+    output: PathBuf,
+    
+    /// Only transmute top N packages
+    #[arg(short, long)]
+    top: Option<usize>,
+    
+    /// Where downloaded package tarballs are unpacked, one directory a version
+    #[arg(long, default_value = "./npm-cache")]
+    cache: PathBuf,
+    
+    /// File souls are kept in across runs, so packages unchanged since an
+    /// earlier run aren't extracted again
+    #[arg(long, default_value = "./forge-souls.json")]
+    soul_cache: PathBuf,
+    
+    /// npm registry to fetch packages from
+    #[arg(long, default_value = npm::DEFAULT_REGISTRY)]
+    npm_registry: String,
+    
+    /// Packages transmuted at once (defaults to the number of CPUs)
+    #[arg(short, long)]
+    jobs: Option<usize>,
+    
+    /// Times a failing package is retried, in this run and later ones,
+    /// before it is skipped
+    #[arg(long, default_value_t = 2)]
+    retries: u32,
+    
+    /// Forget what earlier runs into this output did, and start over
+    #[arg(long)]
+    fresh: bool,
+    
+    /// Bindings for packages whose registry entry doesn't name any: wasm or napi
+    #[arg(long, default_value = "wasm")]
+    bindings: Bindings,
+    
+    /// What each crate is built as: wasm-web, wasm-node, native-lib or
+    /// cdylib-ffi
+    #[arg(long, default_value = "wasm-web")]
+    profile: Profile,
+    
+    /// How `number` is typed: js-float64 (f64 everywhere, as JS) or
+    /// idiomatic-int (locals only holding integers become i64)
+    #[arg(long, default_value = "js-float64")]
+    numeric: NumericPolicy,
+    
+    /// What idiomatic-int arithmetic does past i64: checked (panics),
+    /// wrapping or saturating
+    #[arg(long, default_value = "checked")]
+    overflow: Overflow,
+    
+    /// Directory of .tera templates overriding the built-in ones
+    #[arg(long)]
+    templates: Option<PathBuf>,
+    
+    /// Plugin cdylib of transform passes to run; repeat for more
+    #[arg(long = "plugin")]
+    plugins: Vec<PathBuf>,
+    
+    /// Generate benches/ timing each crate and its source on the same
+    /// calls, for `forge bench`
+    #[arg(long)]
+    bench: bool,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
     
-    // ASCII art banner
-    println!("{}", r#"
+    // ASCII art banner, kept out of output meant for other programs
+    if !matches!(cli.command, Commands::Stats { json: true, .. }) {
+        println!("{}", r#"
 ╔═══════════════════════════════════════╗
 ║     🔥 SOUL FORGE 🔥                  ║
 ║  Alchemical Transmutation Engine      ║
 ║  Same soul, different body            ║
 ╚═══════════════════════════════════════╝
     "#.bright_magenta());
+    }
     
-    match cli.command {
-        Commands::Transmute { input, output, npm_name, crate_name, version } => {
-            transmute_package(input, output, npm_name, crate_name, version).await?;
+    if let Err(error) = run(cli.command).await {
+        report_error(error.as_ref(), cli.error_format);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Print a failure on stderr; the forge's own errors carry a [`Diagnostic`]
+fn report_error(error: &(dyn std::error::Error + 'static), format: ErrorFormat) {
+    match format {
+        ErrorFormat::Human => eprintln!("{} {}", "Error:".bright_red(), error),
+        ErrorFormat::Json => {
+            let json = error.downcast_ref::<TransmutationError>().map(TransmutationError::to_json).unwrap_or_else(|| {
+                let mut json = serde_json::to_value(Diagnostic::error(error.to_string())).unwrap_or_default();
+                json["kind"] = "other".into();
+                json
+            });
+            eprintln!("{}", json);
+        }
+    }
+}
+
+async fn run(command: Commands) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Commands::Transmute { input, output, npm_name, crate_name, version, target, bindings, profile, numeric, overflow, verify, equivalence, max_refinements, templates, plugins, bench, dry_run } => {
+            let forge = SoulForge::new("./forge.db")?
+                .with_async_target(target)
+                .with_numeric_policy(numeric)
+                .with_overflow(overflow)
+                .with_verification(verify)
+                .with_max_refinements(max_refinements);
+            let mut forge = match templates {
+                Some(dir) => forge.with_templates(&dir)?,
+                None => forge,
+            };
+            for plugin in &plugins {
+                forge = forge.with_plugin(plugin)?;
+            }
+            if bench {
+                forge = forge.with_benchmarks();
+            }
+            let forge = match equivalence {
+                None => forge,
+                Some(None) => forge.with_equivalence(),
+                Some(Some(fixtures)) => forge.with_fixtures(serde_json::from_str(&fs::read_to_string(fixtures)?)?),
+            };
+            let crate_name = crate_name.unwrap_or_else(|| format!("{}-soul", npm_name));
+            let metadata = PackageMetadata {
+                name: crate_name.clone(),
+                version,
+                description: format!("Soul-transmuted version of {}", npm_name),
+                npm_name,
+                crate_name,
+                source_file: Some(input.display().to_string()),
+                bindings,
+                profile,
+                license: None,
+                authors: vec![],
+                repository: None,
+                keywords: vec![],
+            };
+            // A module inside a package takes its license and authors along
+            let metadata = match npm::manifest_of(&input) {
+                Some(manifest) => metadata.with_package_json(&manifest),
+                None => metadata,
+            };
+            if let Some(warning) = metadata.license_warning() {
+                println!("{}", format!("⚠️  {}", warning).bright_yellow());
+            }
+            match dry_run {
+                true => plan_package(forge, input, output, metadata).await?,
+                false => transmute_package(forge, input, output, metadata, verify).await?,
+            }
+        }
+        Commands::Batch(args) => {
+            batch_transmute(args)?;
         }
-        Commands::Batch { registry, output, top } => {
-            batch_transmute(registry, output, top).await?;
+        Commands::Verify { js, rust, manifest, json, min_resonance } => {
+            let pairing = match (manifest, js, rust) {
+                (Some(manifest), _, _) => pairs::by_manifest(&manifest)?,
+                (None, Some(js), Some(rust)) => pairs::by_name(&pairs::files(&js, pairs::JS_EXTENSIONS)?, &pairs::files(&rust, pairs::RUST_EXTENSIONS)?),
+                _ => unreachable!("clap requires --js and --rust without --manifest"),
+            };
+            let passed = match pairing.pairs.as_slice() {
+                [(js, rust)] if pairing.unmatched_js.is_empty() && pairing.unmatched_rust.is_empty() && json.is_none() => {
+                    verify_souls(js, rust, min_resonance).await?
+                }
+                _ => verify_pairs(pairing, json, min_resonance).await?,
+            };
+            if !passed {
+                std::process::exit(1);
+            }
+        }
+        Commands::Bench { dir, db } => {
+            bench_crate(dir, db).await?;
         }
-        Commands::Verify { js, rust } => {
-            verify_souls(js, rust).await?;
+        Commands::Report { npm_name, output, db, verify, templates } => {
+            report_transmutation(npm_name, output, db, verify, templates).await?;
         }
-        Commands::Stats { db } => {
-            show_stats(db)?;
+        Commands::Publish { npm_name, dir, db, target, verify, templates, dry_run } => {
+            publish_crate(npm_name, dir, db, target, verify, templates, dry_run).await?;
+        }
+        Commands::Refine { npm_name, db } => {
+            refine_interactively(npm_name, db).await?;
+        }
+        Commands::TestFixtures { dir, accept, plugins, db } => {
+            if !test_fixtures(dir, accept, plugins, db).await? {
+                std::process::exit(1);
+            }
+        }
+        Commands::Stats { db, prefix, tag, json } => {
+            show_stats(db, prefix, tag, json)?;
+        }
+        Commands::Db { command: DbCommands::Migrate { db, discard_unreadable } } => {
+            migrate_db(db, discard_unreadable)?;
+        }
+        Commands::Db { command: DbCommands::Doctor { db } } => {
+            doctor_db(db)?;
         }
     }
     
@@ -107,28 +495,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 async fn transmute_package(
+    mut forge: SoulForge,
     input: PathBuf,
     output: PathBuf,
-    npm_name: String,
-    crate_name: Option<String>,
-    version: String,
+    metadata: PackageMetadata,
+    verify: Verification,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let npm_name = metadata.npm_name.clone();
+    let (bindings, profile) = (metadata.bindings, metadata.profile);
     println!("{}", format!("⚗️  Transmuting {}...", npm_name).bright_yellow());
     
-    let mut forge = SoulForge::new("./forge.db")?;
-    
-    // Read JavaScript code
-    let js_code = fs::read_to_string(&input)?;
-    
-    // Prepare metadata
-    let crate_name = crate_name.unwrap_or_else(|| format!("{}-soul", npm_name));
-    let metadata = PackageMetadata {
-        name: crate_name.clone(),
-        version,
-        description: format!("Soul-transmuted version of {}", npm_name),
-        npm_name: npm_name.clone(),
-        crate_name: crate_name.clone(),
-    };
+    // Read the JavaScript/TypeScript module, and those it imports
+    let modules = JsModule::collect(&input)?;
+    if modules.len() > 1 {
+        println!("   {} modules, laid out as Rust modules under the crate root", modules.len());
+    }
     
     // Show progress
     let pb = ProgressBar::new(100);
@@ -143,28 +524,16 @@ async fn transmute_package(
     pb.set_position(20);
     
     // Perform transmutation
-    let transmutation = forge.transmute(&js_code, metadata).await?;
+    let transmutation = forge.transmute_modules(&modules, metadata).await?;
     
     pb.set_message("Transforming to Rust...");
     pb.set_position(50);
     
-    // Create output directory
-    fs::create_dir_all(&output)?;
-    
-    // Write Rust code
-    let src_dir = output.join("src");
-    fs::create_dir_all(&src_dir)?;
-    fs::write(src_dir.join("lib.rs"), &transmutation.rust_code)?;
-    
-    pb.set_message("Generating WASM bindings...");
+    pb.set_message("Writing crate and bindings...");
     pb.set_position(70);
     
-    // Write WASM bindings
-    fs::write(output.join("bindings.js"), &transmutation.wasm_bindings)?;
-    
-    // Generate and write Cargo.toml
-    let cargo_toml = forge.generate_cargo_toml(&transmutation.metadata)?;
-    fs::write(output.join("Cargo.toml"), cargo_toml)?;
+    let cargo_toml = forge.generate_cargo_toml(&transmutation.metadata, &transmutation.rust_code)?;
+    let conflicts = write_crate(&transmutation, &cargo_toml, &output)?;
     
     pb.set_message("Verifying soul resonance...");
     pb.set_position(90);
@@ -173,8 +542,31 @@ async fn transmute_package(
     pb.finish_and_clear();
     
     println!("{}", "✨ Transmutation complete!".bright_green());
+    for (file, conflict) in &conflicts {
+        println!("{}", format!("⚠️  {}: {}", file.display(), conflict).bright_yellow());
+    }
+    print_report(&transmutation, verify != Verification::Off);
+    if transmutation.benches.is_some() {
+        println!("   Run {} to compare it with the source", format!("forge bench {}", output.display()).bright_white());
+    }
+    
+    println!();
+    println!("📦 {} {}", "Output:".bright_cyan(), output.display());
+    println!("   Run {} to build", format!("cd {} && cargo build --release", output.display()).bright_white());
+    match (bindings, profile) {
+        (Bindings::Napi, _) => println!("   Run {} to build the Node addon", "napi build --release".bright_white()),
+        (Bindings::Wasm, profile) if profile.is_wasm() => println!("   Run {} to generate WASM", profile.build_command().bright_white()),
+        (Bindings::Wasm, _) => {}
+    }
+    
+    Ok(())
+}
+
+/// Print how a transmutation turned out: its souls' resonance, whether it
+/// builds and behaves as the source does, and how much faster it runs
+fn print_report(transmutation: &Transmutation, verified: bool) {
     println!();
-    println!("📊 {} {}:", "Soul Analysis:".bright_cyan(), npm_name);
+    println!("📊 {} {}:", "Soul Analysis:".bright_cyan(), transmutation.metadata.npm_name);
     println!("   {} {:.3}", "Resonance:".bright_white(), transmutation.resonance);
     println!("   {} {}", "JS Soul:".bright_white(), &transmutation.js_soul.phash[..16]);
     println!("   {} {}", "Rust Soul:".bright_white(), &transmutation.rust_soul.phash[..16]);
@@ -186,61 +578,750 @@ async fn transmute_package(
     } else {
         println!("   {} {}", "Status:".bright_white(), "❌ Weak transmutation".bright_red());
     }
+    if verified {
+        println!("   {} {}", "Compiles:".bright_white(), "✅ verified with cargo".bright_green());
+    }
+    if let Some(pass_rate) = transmutation.pass_rate {
+        let rate = format!("{:.0}% of calls behave as in JS", pass_rate * 100.0);
+        let rate = if pass_rate == 1.0 { rate.bright_green() } else { rate.bright_yellow() };
+        println!("   {} {}", "Equivalence:".bright_white(), rate);
+    }
+    if !transmutation.warnings.is_empty() {
+        println!();
+        println!("{}", "🌀 Soul drift:".bright_cyan());
+        for warning in &transmutation.warnings {
+            println!("   {} {}", "⚠".bright_yellow(), warning.to_string().replace('\n', "\n   "));
+        }
+    }
     
-    println!();
-    println!("📦 {} {}", "Output:".bright_cyan(), output.display());
-    println!("   Run {} to build", format!("cd {} && cargo build --release", output.display()).bright_white());
-    println!("   Run {} to generate WASM", "wasm-pack build".bright_white());
+    if !transmutation.benchmarks.is_empty() {
+        println!();
+        println!("{}", "⚡ Speedups:".bright_cyan());
+        let width = transmutation.benchmarks.iter().map(|benchmark| benchmark.function.len()).max().unwrap_or(0);
+        for benchmark in &transmutation.benchmarks {
+            let speedup = format!("{:.2}×", benchmark.speedup());
+            let speedup = if benchmark.speedup() >= 1.0 { speedup.bright_green() } else { speedup.bright_red() };
+            println!(
+                "   {} {:>12} → {:>12}  {}",
+                format!("{:<width$}", benchmark.function, width = width).bright_white(),
+                nanos(benchmark.js_ns),
+                nanos(benchmark.rust_ns),
+                speedup,
+            );
+        }
+        let mean = transmutation.benchmarks.iter().map(|benchmark| benchmark.speedup().ln()).sum::<f64>() / transmutation.benchmarks.len() as f64;
+        println!("   {} {:.2}× (geometric mean, JS → Rust)", "Overall:".bright_magenta(), mean.exp());
+    }
+}
+
+/// A time in nanoseconds, in the unit that reads best
+fn nanos(ns: f64) -> String {
+    match ns {
+        ns if ns >= 1e9 => format!("{:.2} s", ns / 1e9),
+        ns if ns >= 1e6 => format!("{:.2} ms", ns / 1e6),
+        ns if ns >= 1e3 => format!("{:.2} µs", ns / 1e3),
+        ns => format!("{:.0} ns", ns),
+    }
+}
+
+/// Run a transmuted crate's benchmarks in both bodies, keep what they
+/// measured with its transmutation, and report it
+async fn bench_crate(dir: PathBuf, db_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest: toml::Value = toml::from_str(&fs::read_to_string(dir.join("Cargo.toml"))?)?;
+    let crate_name = manifest["package"]["name"].as_str().ok_or("Cargo.toml names no package")?.to_string();
+    println!("{}", format!("⏱️  Benchmarking {} against its source...", crate_name).bright_yellow());
+    
+    let benchmarks = bench::run(&dir).await?;
+    if benchmarks.is_empty() {
+        return Err(format!("{} timed no functions in both bodies", dir.display()).into());
+    }
+    
+    let forge = SoulForge::new(db_path.to_str().unwrap_or("./forge.db"))?;
+    let transmutation = forge.list_transmutations(&TransmutationFilter::default())?
+        .into_iter()
+        .find(|transmutation| transmutation.metadata.crate_name == crate_name);
+    match transmutation {
+        Some(mut transmutation) => {
+            forge.record_benchmarks(&transmutation.metadata.npm_name, benchmarks.clone())?;
+            transmutation.benchmarks = benchmarks;
+            print_report(&transmutation, false);
+        }
+        // A crate from another database is still worth timing
+        None => {
+            println!("{}", format!("⚠️  {} has no transmutation in {}; the speedups aren't kept", crate_name, db_path.display()).bright_yellow());
+            for benchmark in &benchmarks {
+                println!("   {} {:.2}×", format!("{}:", benchmark.function).bright_white(), benchmark.speedup());
+            }
+        }
+    }
     
     Ok(())
 }
 
-async fn batch_transmute(
-    registry: PathBuf,
+/// Write the HTML report of `npm_name`'s stored transmutation
+async fn report_transmutation(
+    npm_name: String,
+    output: Option<PathBuf>,
+    db_path: PathBuf,
+    verify: Verification,
+    templates: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let forge = SoulForge::new(db_path.to_str().unwrap_or("./forge.db"))?.with_verification(verify);
+    let forge = match templates {
+        Some(dir) => forge.with_templates(&dir)?,
+        None => forge,
+    };
+    let transmutation = forge.get_transmutation(&npm_name)?
+        .ok_or_else(|| format!("{} has no transmutation in {}", npm_name, db_path.display()))?;
+    if verify != Verification::Off {
+        println!("{}", format!("🔍 Checking {} again...", transmutation.metadata.crate_name).bright_yellow());
+    }
+    
+    let html = forge.generate_report(&transmutation).await?;
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("{}-report.html", transmutation.metadata.crate_name)));
+    fs::write(&output, html)?;
+    println!("{}", format!("📜 Report written to {}", output.display()).bright_green());
+    
+    Ok(())
+}
+
+/// Print planned items, a module's nested under it
+fn print_planned(items: &[soul_forge::PlannedItem], depth: usize) {
+    let indent = "   ".repeat(depth);
+    let origin = |item: &soul_forge::PlannedItem| item.origin.as_ref().map(|origin| format!("  ← {}", origin).dimmed().to_string()).unwrap_or_default();
+    for item in items {
+        println!("   {}{:<6} {}{}", indent, item.kind.bright_white(), item.signature, origin(item));
+        match item.kind {
+            "mod" => print_planned(&item.members, depth + 1),
+            _ => {
+                for member in &item.members {
+                    println!("   {}       {}{}", indent, member.signature, origin(member));
+                }
+            }
+        }
+    }
+}
+
+/// Print the plan for transmuting `input`: the items it would generate with
+/// their types, the files it would write and the resonance to expect
+async fn plan_package(
+    mut forge: SoulForge,
+    input: PathBuf,
     output: PathBuf,
-    top: Option<usize>,
+    metadata: PackageMetadata,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", format!("📝 Planning {} (dry run, nothing is written)...", metadata.npm_name).bright_yellow());
+    
+    let plan = forge.plan_modules(&JsModule::collect(&input)?, &metadata).await?;
+    
+    println!();
+    println!("{}", "🧱 Items:".bright_cyan());
+    print_planned(&plan.items, 0);
+    if plan.items.is_empty() {
+        println!("   {}", "(none)".dimmed());
+    }
+    
+    println!();
+    println!("{}", "🔌 Bindings:".bright_cyan());
+    let bound = if plan.bound.is_empty() { "(no exports)".to_string() } else { plan.bound.join(", ") };
+    println!("   {} {}", format!("{:?} ({:?}):", metadata.bindings, metadata.profile).bright_white(), bound);
+    
+    println!();
+    println!("{}", "📁 Would write:".bright_cyan());
+    let bindings = metadata.bindings;
+    let mut files = vec![output.join("Cargo.toml"), output.join("src").join("lib.rs"), output.join("src").join("lib.rs.map")];
+    files.extend(metadata.bindings_file().map(|file| output.join(file)));
+    if bindings.build_script().is_some() {
+        files.push(output.join("build.rs"));
+    }
+    for file in files {
+        let note = if file.exists() { " (exists, would be replaced)".bright_yellow().to_string() } else { String::new() };
+        println!("   {}{}", file.display(), note);
+    }
+    
+    println!();
+    println!("   {} {:.3}", "Expected resonance:".bright_magenta(), plan.resonance);
+    println!("   {} {}", "Souls Match:".bright_magenta(),
+        if plan.souls_match { "✅ YES".bright_green() } else { "❌ NO".bright_red() });
+    
+    Ok(())
+}
+
+/// The loop of `forge refine`: show the breakdown, read an action, show what
+/// it changes and store it once accepted
+async fn refine_interactively(npm_name: String, db_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+    
+    let mut forge = SoulForge::new(db_path.to_str().unwrap_or("./forge.db"))?;
+    let mut current = forge.get_transmutation(&npm_name)?
+        .ok_or_else(|| format!("{} has no transmutation in {}", npm_name, db_path.display()))?;
+    println!("{}", format!("🎛️  Refining {}; `help` lists the actions", npm_name).bright_yellow());
+    show_breakdown(&forge, &current);
+    
+    let stdin = std::io::stdin();
+    loop {
+        print!("{} ", "refine>".bright_magenta());
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 {
+            break;
+        }
+        match line.trim() {
+            "" => continue,
+            "quit" | "exit" | "q" => break,
+            "help" => {
+                println!("   {}   inline private helpers called once", "inline".bright_white());
+                println!("   {}    move public functions' bodies into helpers", "split".bright_white());
+                println!("   {}  join nested ifs, flatten else {{ if }}", "flatten".bright_white());
+                println!("   {}     nest else if as else {{ if }}", "nest".bright_white());
+                println!("   {}    end bodies with tails, not return", "tails".bright_white());
+                println!("   {}     bind computed tails to a result", "bind".bright_white());
+                println!("   {} change a parameter's or the return's type", "type <fn> <param|return> <type>".bright_white());
+                println!("   {} the breakdown again, {} the code, {} accepted steps, {}", "show".bright_white(), "code".bright_white(), "history".bright_white(), "quit".bright_white());
+            }
+            "show" => show_breakdown(&forge, &current),
+            "code" => println!("{}", current.rust_code),
+            "history" => {
+                let steps = forge.refinement_steps(&npm_name)?;
+                if steps.is_empty() {
+                    println!("   No refinements accepted yet");
+                }
+                for step in steps {
+                    println!("   {} {:.3} → {:.3}", step.action.bright_white(), step.resonance_before, step.resonance_after);
+                }
+            }
+            command => {
+                let action: Action = match command.parse() {
+                    Ok(action) => action,
+                    Err(e) => {
+                        println!("   {}", e.bright_red());
+                        continue;
+                    }
+                };
+                let Some(refined) = forge.try_refinement(&current, &action).await? else {
+                    println!("   {} changes nothing", action);
+                    continue;
+                };
+                for line in snapshot::diff(&current.rust_code, &refined.rust_code).lines().filter(|line| !line.contains("forge:")) {
+                    match line.chars().next() {
+                        Some('-') => println!("   {}", line.bright_red()),
+                        Some('+') => println!("   {}", line.bright_green()),
+                        _ => println!("   {}", line),
+                    }
+                }
+                let change = refined.resonance - current.resonance;
+                let change = format!("{:+.3}", change);
+                let change = if refined.resonance >= current.resonance { change.bright_green() } else { change.bright_red() };
+                println!("   {} {:.3} → {:.3} ({})", "Resonance:".bright_white(), current.resonance, refined.resonance, change);
+                print!("   Accept? [y/N] ");
+                std::io::stdout().flush()?;
+                let mut answer = String::new();
+                stdin.read_line(&mut answer)?;
+                if matches!(answer.trim(), "y" | "Y" | "yes") {
+                    forge.accept_refinement(&refined, &action, current.resonance)?;
+                    current = refined;
+                    println!("   {} Stored in {}", "✅".bright_green(), db_path.display());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The resonance of a transmutation and where it comes from
+fn show_breakdown(forge: &SoulForge, transmutation: &Transmutation) {
+    let breakdown = forge.breakdown(transmutation);
+    println!();
+    println!("   {} {:.3}", "Resonance:".bright_white(), breakdown.resonance);
+    println!("   {} {:.3}   {} {:.3}", "Eigenvalue distance:".bright_white(), breakdown.eigen_distance, "Topology distance:".bright_white(), breakdown.topology_distance);
+    for (layer, (js, rust)) in breakdown.layers.iter().enumerate() {
+        println!("   λ{}  JS {:>8.3}  Rust {:>8.3}  {:+.3}", layer + 1, js, rust, rust - js);
+    }
+    let suggested: Vec<String> = breakdown.suggested.iter().map(Action::to_string).collect();
+    println!("   {} {}", "Suggested:".bright_white(), suggested.join(", "));
+    for function in &breakdown.functions {
+        println!("   {}", function.bright_black());
+    }
+    println!();
+}
+
+/// Check each fixture's generated code against its snapshot, printing the
+/// diffs; returns whether they all matched, or were accepted
+async fn test_fixtures(dir: PathBuf, accept: bool, plugins: Vec<PathBuf>, db_path: PathBuf) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut forge = SoulForge::new(db_path.to_str().unwrap_or("./forge.db"))?;
+    for plugin in &plugins {
+        forge = forge.with_plugin(plugin)?;
+    }
+    println!("{}", format!("📸 Checking the fixtures in {}...", dir.display()).bright_yellow());
+    
+    let snapshots = forge.check_snapshots(&dir, accept).await?;
+    let mut pending = 0;
+    for snapshot in &snapshots {
+        let name = snapshot.fixture.file_name().unwrap_or_default().to_string_lossy();
+        match (&snapshot.outcome, accept) {
+            (snapshot::Outcome::Matched, _) => println!("   {} {}", "✅".bright_green(), name),
+            (snapshot::Outcome::New, true) => println!("   {} {} recorded", "📸".bright_cyan(), name),
+            (snapshot::Outcome::Changed(_), true) => println!("   {} {} updated", "📸".bright_cyan(), name),
+            (snapshot::Outcome::New, false) => {
+                pending += 1;
+                println!("   {} {} has no snapshot", "🆕".bright_yellow(), name);
+            }
+            (snapshot::Outcome::Changed(diff), false) => {
+                pending += 1;
+                println!("   {} {} changed:", "❌".bright_red(), name);
+                for line in diff.lines() {
+                    let line = match line.chars().next() {
+                        Some('-') => line.bright_red(),
+                        Some('+') => line.bright_green(),
+                        Some('@') => line.bright_cyan(),
+                        _ => line.normal(),
+                    };
+                    println!("      {}", line);
+                }
+            }
+        }
+    }
+    
+    if pending > 0 {
+        println!();
+        println!("{} of {} fixtures need review; the new output is next to each snapshot as .snap.new", pending, snapshots.len());
+        println!("   Run {} to record it", format!("forge test-fixtures {} --accept", dir.display()).bright_white());
+    }
+    Ok(pending == 0)
+}
+
+/// Publish a stored transmutation's crate, its manifest regenerated first so
+/// the crate is at its package's version and depends on the crates of other
+/// stored packages at theirs
+async fn publish_crate(
+    npm_name: String,
+    dir: Option<PathBuf>,
+    db_path: PathBuf,
+    target: AsyncTarget,
+    verify: Verification,
+    templates: Option<PathBuf>,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let forge = SoulForge::new(db_path.to_str().unwrap_or("./forge.db"))?.with_async_target(target);
+    let forge = match templates {
+        Some(dir) => forge.with_templates(&dir)?,
+        None => forge,
+    };
+    let transmutation = forge.get_transmutation(&npm_name)?
+        .ok_or_else(|| format!("{} has no transmutation in {}", npm_name, db_path.display()))?;
+    let metadata = &transmutation.metadata;
+    let dir = dir.unwrap_or_else(|| PathBuf::from(&metadata.crate_name));
+    if !dir.join("src").join("lib.rs").is_file() {
+        return Err(format!("{} holds no crate; write it with forge transmute first", dir.display()).into());
+    }
+    println!("{}", format!("🚀 Publishing {} {}...", metadata.crate_name, metadata.version).bright_yellow());
+    
+    let problems = publish::problems(&transmutation);
+    if !problems.is_empty() {
+        for problem in &problems {
+            println!("   {} {}", "❌".bright_red(), problem);
+        }
+        return Err(format!("{} isn't ready to publish", metadata.crate_name).into());
+    }
+    
+    // Crates of other stored packages are used from beside this one, or
+    // from crates.io
+    let mut crates = vec![];
+    for other in forge.list_transmutations(&TransmutationFilter::default())? {
+        if other.metadata.npm_name == npm_name {
+            continue;
+        }
+        let sibling = dir.parent().unwrap_or(Path::new(".")).join(&other.metadata.crate_name);
+        let source = match sibling.join("Cargo.toml").exists() {
+            true => DependencySource::Path(fs::canonicalize(sibling)?),
+            false => DependencySource::Registry,
+        };
+        crates.push(CrateDependency::of(&other, source));
+    }
+    let mut forge = forge.with_dependencies(crates);
+    if dir.join("benches").is_dir() {
+        forge = forge.with_benchmarks();
+    }
+    let workspace = dir.parent().and_then(|parent| fs::read_to_string(parent.join("Cargo.toml")).ok())
+        .and_then(|text| toml::from_str::<toml::Value>(&text).ok());
+    let cargo_toml = match workspace.is_some_and(|manifest| manifest.get("workspace").is_some()) {
+        true => forge.generate_member_cargo_toml(metadata, &transmutation.rust_code)?,
+        false => forge.generate_cargo_toml(metadata, &transmutation.rust_code)?,
+    };
+    let mut conflicts = vec![];
+    write_merged(&dir.join("Cargo.toml"), &merge::mark_file(&cargo_toml, "#"), &mut conflicts)?;
+    for (file, conflict) in &conflicts {
+        println!("{}", format!("⚠️  {}: {}", file.display(), conflict).bright_yellow());
+    }
+    publish::check_manifest(&dir, &transmutation)?;
+    println!("   {} Cargo.toml at {} of {}", "✅".bright_green(), metadata.version, npm_name);
+    
+    if verify != Verification::Off {
+        println!("{}", "🔍 Running its tests...".bright_yellow());
+        publish::verify(&dir, verify).await?;
+        println!("   {} Tests pass", "✅".bright_green());
+    }
+    
+    publish::publish(&dir, dry_run).await?;
+    match dry_run {
+        true => println!("{}", format!("✨ {} {} is ready; run without --dry-run to upload it", metadata.crate_name, metadata.version).bright_green()),
+        false => println!("{}", format!("✨ Published {} {} to crates.io", metadata.crate_name, metadata.version).bright_green()),
+    }
+    
+    Ok(())
+}
+
+/// Write a transmutation out as a crate: its code, bindings, manifest and
+/// equivalence tests, merged into a crate already there so hand edits
+/// survive; returns the edits regeneration left alone
+fn write_crate(transmutation: &Transmutation, cargo_toml: &str, output: &Path) -> Result<Vec<(PathBuf, Conflict)>, Box<dyn std::error::Error>> {
+    let mut conflicts = vec![];
+    
+    // Write Rust code, and the source map back to the JS of the code merged
+    let src_dir = output.join("src");
+    fs::create_dir_all(&src_dir)?;
+    let code = write_merged(&src_dir.join("lib.rs"), &transmutation.rust_code, &mut conflicts)?;
+    fs::write(src_dir.join("lib.rs.map"), code.source_map())?;
+    
+    // Write the bindings, and the build script they need
+    let bindings = transmutation.metadata.bindings;
+    if let Some(file) = transmutation.metadata.bindings_file() {
+        write_merged(&output.join(file), &merge::mark_file(&transmutation.js_bindings, "//"), &mut conflicts)?;
+    }
+    if let Some(build_script) = bindings.build_script() {
+        write_merged(&output.join("build.rs"), &merge::mark_file(build_script, "//"), &mut conflicts)?;
+    }
+    
+    // Write Cargo.toml
+    write_merged(&output.join("Cargo.toml"), &merge::mark_file(cargo_toml, "#"), &mut conflicts)?;
+    
+    // Write the equivalence tests, runnable with `cargo test`
+    if let Some(tests) = &transmutation.equivalence_tests {
+        fs::create_dir_all(output.join("tests"))?;
+        write_merged(&output.join("tests").join("equivalence.rs"), &merge::mark_file(tests, "//"), &mut conflicts)?;
+    }
+    
+    // Write the benchmarks of both bodies, runnable with `forge bench`
+    if let Some(benches) = &transmutation.benches {
+        fs::create_dir_all(output.join("benches"))?;
+        write_merged(&output.join("benches").join(benches.criterion_file()), &merge::mark_file(&benches.criterion, "//"), &mut conflicts)?;
+        write_merged(&output.join("benches").join(&benches.node_file), &merge::mark_file(&benches.node, "//"), &mut conflicts)?;
+    }
+    
+    Ok(conflicts)
+}
+
+/// Write marked generated content to `path`, merged into the file there;
+/// a file from before markers is kept aside as `{name}.orig`
+fn write_merged(path: &Path, generated: &str, conflicts: &mut Vec<(PathBuf, Conflict)>) -> Result<merge::Merge, Box<dyn std::error::Error>> {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    let merged = merge::merge(&existing, generated);
+    if merged.conflicts.contains(&Conflict::Unmarked) {
+        let mut kept = path.as_os_str().to_owned();
+        kept.push(".orig");
+        fs::write(kept, &existing)?;
+    }
+    fs::write(path, &merged.content)?;
+    conflicts.extend(merged.conflicts.iter().map(|conflict| (path.to_path_buf(), conflict.clone())));
+    Ok(merged)
+}
+
+/// What a batch's worker threads share
+struct BatchRun {
+    args: BatchArgs,
+    /// Packages left in the current wave, with their version specs and bindings
+    queue: Mutex<VecDeque<(String, String, Bindings)>>,
+    /// Versions resolved for ordering the packages, not yet pinned
+    resolved: HashMap<String, npm::Pin>,
+    journal: Journal,
+    lock: Mutex<npm::Lock>,
+    lock_path: PathBuf,
+    progress: ProgressBar,
+    transmuted: AtomicUsize,
+}
+
+impl BatchRun {
+    /// Print a line above the progress bar, which also shows when the bar is hidden
+    fn report(&self, line: String) {
+        self.progress.suspend(|| println!("{}", line));
+    }
+}
+
+fn batch_transmute(args: BatchArgs) -> Result<(), Box<dyn std::error::Error>> {
     println!("{}", "🔥 Batch transmutation starting...".bright_yellow());
     
-    // Read soul registry
-    let registry_content = fs::read_to_string(registry)?;
+    // Read soul registry: each package's version spec, as a string or a
+    // `version` field, defaulting to the latest
+    let registry_content = fs::read_to_string(&args.registry)?;
     let souls: serde_json::Value = serde_json::from_str(&registry_content)?;
     
     let packages = souls.as_object()
         .ok_or("Invalid registry format")?;
     
-    let mut count = 0;
-    let limit = top.unwrap_or(packages.len());
+    // The journal of earlier runs into this output says what's left; versions
+    // they resolved stay pinned
+    fs::create_dir_all(&args.output)?;
+    let forge = SoulForge::new("./forge.db")?
+        .with_soul_cache(&args.soul_cache)?
+        .with_numeric_policy(args.numeric)
+        .with_overflow(args.overflow);
+    let mut forge = match &args.templates {
+        Some(dir) => forge.with_templates(dir)?,
+        None => forge,
+    };
+    for plugin in &args.plugins {
+        forge = forge.with_plugin(plugin)?;
+    }
+    if args.bench {
+        forge = forge.with_benchmarks();
+    }
+    let journal = forge.journal(&fs::canonicalize(&args.output)?.display().to_string())?;
+    if args.fresh {
+        journal.clear()?;
+    }
+    let lock_path = args.output.join("forge.lock.json");
+    let lock = npm::Lock::load(&lock_path)?;
     
+    let limit = args.top.unwrap_or(packages.len());
+    let mut queue = VecDeque::new();
+    let mut done = 0;
     for (npm_name, soul_data) in packages.iter().take(limit) {
-        count += 1;
-        println!("{}", format!("[{}/{}] Transmuting {}...", count, limit, npm_name).bright_cyan());
-        
-        // Create output directory for this package
-        let crate_name = format!("{}-soul", npm_name);
-        let package_output = output.join(&crate_name);
-        
-        // TODO: Download and transmute actual package code
-        // For now, just create directory structure
-        fs::create_dir_all(&package_output)?;
-        
-        println!("   {} {}", "✓".bright_green(), package_output.display());
+        match journal.get(npm_name)? {
+            // Done, unless its crate has since been removed
+            Some(JobState::Done { .. }) if args.output.join(crate_name_of(npm_name)).join("Cargo.toml").exists() => done += 1,
+            Some(JobState::Failed { reason, attempts }) if attempts > args.retries => {
+                println!("   {} {} skipped after {} attempts: {}", "↷".bright_yellow(), npm_name, attempts, reason);
+            }
+            _ => {
+                let spec = soul_data.as_str().or(soul_data["version"].as_str()).unwrap_or("latest");
+                let bindings = match soul_data["bindings"].as_str() {
+                    Some(bindings) => bindings.parse().map_err(|e| format!("{}: {}", npm_name, e))?,
+                    None => args.bindings,
+                };
+                queue.push_back((npm_name.clone(), spec.to_string(), bindings));
+            }
+        }
+    }
+    if done > 0 {
+        println!("{}", format!("↻ Resuming: {} of {} packages already transmuted", done, limit).bright_cyan());
+    }
+    
+    // Packages are transmuted after the ones they depend on, a wave at a
+    // time, so their imports of them use the crates they became. A package
+    // that doesn't resolve here goes first, and fails in its worker.
+    let npm = NpmClient::new(&args.cache).with_registry(&args.npm_registry);
+    let resolved: HashMap<String, npm::Pin> = tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(async {
+        let mut resolved = HashMap::new();
+        for (npm_name, spec, _) in queue.iter().filter(|(npm_name, _, _)| !lock.packages.contains_key(npm_name)) {
+            if let Ok(pin) = npm.resolve(npm_name, spec).await {
+                resolved.insert(npm_name.clone(), pin);
+            }
+        }
+        resolved
+    }));
+    let graph: BTreeMap<String, Vec<String>> = queue.iter()
+        .map(|(npm_name, _, _)| {
+            let pin = lock.packages.get(npm_name).or(resolved.get(npm_name));
+            (npm_name.clone(), pin.map_or(vec![], |pin| pin.dependencies.clone()))
+        })
+        .collect();
+    let mut jobs_of: HashMap<String, (String, String, Bindings)> = queue.into_iter().map(|job| (job.0.clone(), job)).collect();
+    let waves: Vec<VecDeque<(String, String, Bindings)>> = dependencies::waves(&graph).into_iter()
+        .map(|wave| wave.iter().filter_map(|npm_name| jobs_of.remove(npm_name)).collect())
+        .collect();
+    let queued: usize = waves.iter().map(VecDeque::len).sum();
+    
+    let jobs = args.jobs.unwrap_or_else(|| std::thread::available_parallelism().map_or(4, |n| n.get())).clamp(1, queued.max(1));
+    let progress = ProgressBar::new(queued as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+            .unwrap()
+            .progress_chars("█▓▒░ ")
+    );
+    progress.set_message(format!("on {} threads", jobs));
+    
+    let run = BatchRun {
+        args,
+        queue: Mutex::new(VecDeque::new()),
+        resolved,
+        journal,
+        lock: Mutex::new(lock),
+        lock_path,
+        progress,
+        transmuted: AtomicUsize::new(0),
+    };
+    
+    // Each worker has its own forge and runtime, pulling packages of the
+    // wave until none are left
+    for wave in waves {
+        let workers = jobs.min(wave.len());
+        *run.queue.lock().unwrap() = wave;
+        let crates = transmuted_crates(&forge, &run)?;
+        let outcomes: Vec<Result<(), String>> = tokio::task::block_in_place(|| {
+            std::thread::scope(|scope| {
+                let workers: Vec<_> = (0..workers)
+                    .map(|_| {
+                        let forge = forge.fork().with_dependencies(crates.clone());
+                        scope.spawn(|| batch_worker(&run, forge))
+                    })
+                    .collect();
+                workers.into_iter().map(|worker| worker.join().unwrap_or_else(|_| Err("worker panicked".to_string()))).collect()
+            })
+        });
+        for outcome in outcomes {
+            if let Err(e) = outcome {
+                run.progress.finish_and_clear();
+                return Err(e.into());
+            }
+        }
     }
+    run.progress.finish_and_clear();
     
+    // Every crate transmuted into this output, by this run or earlier ones,
+    // joins one workspace
+    let entries = run.journal.entries()?;
+    let members: Vec<WorkspaceMember> = entries.iter()
+        .filter_map(|(npm_name, state)| match state {
+            JobState::Done { version, resonance } => Some(WorkspaceMember {
+                crate_name: crate_name_of(npm_name),
+                npm_name: npm_name.clone(),
+                version: version.clone(),
+                resonance: *resonance,
+            }),
+            JobState::Failed { .. } => None,
+        })
+        .filter(|member| run.args.output.join(&member.crate_name).join("Cargo.toml").exists())
+        .collect();
+    fs::write(run.args.output.join("Cargo.toml"), forge.generate_workspace_toml(&members)?)?;
+    fs::write(run.args.output.join("README.md"), forge.generate_workspace_readme(&members)?)?;
+    
+    let transmuted = run.transmuted.load(Ordering::Relaxed);
+    let failing = entries.iter()
+        .filter(|(_, state)| matches!(state, JobState::Failed { .. }))
+        .count();
     println!();
-    println!("{}", format!("✨ Transmuted {} packages!", count).bright_green());
+    println!("{}", format!("✨ Transmuted {} packages! {} of {} done, {} failing", transmuted, done + transmuted, limit, failing).bright_green());
     
     Ok(())
 }
 
-async fn verify_souls(js_path: PathBuf, rust_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+fn batch_worker(run: &BatchRun, mut forge: SoulForge) -> Result<(), String> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let npm = NpmClient::new(&run.args.cache).with_registry(&run.args.npm_registry);
+    
+    loop {
+        let next = run.queue.lock().unwrap().pop_front();
+        let Some((npm_name, spec, bindings)) = next else { break };
+        
+        // Retry until it works or its attempts, across runs, run out
+        loop {
+            match runtime.block_on(transmute_npm(run, &mut forge, &npm, &npm_name, &spec, bindings)) {
+                Ok((version, resonance)) => {
+                    run.journal.record(&npm_name, &JobState::Done { version: version.clone(), resonance }).map_err(|e| e.to_string())?;
+                    run.transmuted.fetch_add(1, Ordering::Relaxed);
+                    run.report(format!("   {} {}@{} (resonance {:.3})", "✓".bright_green(), npm_name, version, resonance));
+                    break;
+                }
+                Err(e) => {
+                    let state = run.journal.fail(&npm_name, &e.to_string()).map_err(|e| e.to_string())?;
+                    let JobState::Failed { attempts, .. } = state else { unreachable!() };
+                    if attempts > run.args.retries {
+                        run.report(format!("   {} {}: {}", "✗".bright_red(), npm_name, e));
+                        break;
+                    }
+                    run.report(format!("   {} {} (attempt {}): {}", "↻".bright_yellow(), npm_name, attempts, e));
+                }
+            }
+        }
+        run.progress.inc(1);
+    }
+    Ok(())
+}
+
+/// The crates transmuted into the batch's output so far, by this run or
+/// earlier ones, for the next wave's imports to use
+fn transmuted_crates(forge: &SoulForge, run: &BatchRun) -> Result<Vec<CrateDependency>, Box<dyn std::error::Error>> {
+    let mut crates = vec![];
+    for (npm_name, state) in run.journal.entries()? {
+        let dir = run.args.output.join(crate_name_of(&npm_name));
+        if !matches!(state, JobState::Done { .. }) || !dir.join("Cargo.toml").exists() {
+            continue;
+        }
+        if let Some(transmutation) = forge.get_transmutation(&npm_name)? {
+            crates.push(CrateDependency::of(&transmutation, DependencySource::Path(fs::canonicalize(dir)?)));
+        }
+    }
+    Ok(crates)
+}
+
+/// `{name}-soul`, with a scope folded into the name
+fn crate_name_of(npm_name: &str) -> String {
+    format!("{}-soul", npm_name.trim_start_matches('@').replace('/', "-"))
+}
+
+/// Fetch, transmute and write out one package, returning the version
+/// transmuted and its resonance
+async fn transmute_npm(
+    run: &BatchRun,
+    forge: &mut SoulForge,
+    npm: &NpmClient,
+    npm_name: &str,
+    spec: &str,
+    bindings: Bindings,
+) -> Result<(String, f64), Box<dyn std::error::Error>> {
+    let pinned = run.lock.lock().unwrap().packages.get(npm_name).cloned();
+    let pin = match pinned.or_else(|| run.resolved.get(npm_name).cloned()) {
+        Some(pin) => pin,
+        None => npm.resolve(npm_name, spec).await?,
+    };
+    
+    // Download the package and transmute its entry module
+    let package = npm.fetch(npm_name, &pin).await?;
+    let crate_name = crate_name_of(npm_name);
+    let metadata = PackageMetadata {
+        name: crate_name.clone(),
+        version: package.version.clone(),
+        description: package.description.clone(),
+        npm_name: npm_name.to_string(),
+        crate_name: crate_name.clone(),
+        // Named as it would be under node_modules
+        source_file: Some(format!("{}/{}", npm_name, package.entry.strip_prefix(&package.dir)?.display())),
+        bindings,
+        profile: run.args.profile,
+        license: None,
+        authors: vec![],
+        repository: None,
+        keywords: vec![],
+    }.with_package_json(&package.manifest);
+    if let Some(warning) = metadata.license_warning() {
+        run.report(format!("   {} {}", "⚠".bright_yellow(), warning));
+    }
+    let transmutation = forge.transmute_modules(&JsModule::collect(&package.entry)?, metadata).await?;
+    let cargo_toml = forge.generate_member_cargo_toml(&transmutation.metadata, &transmutation.rust_code)?;
+    for (file, conflict) in write_crate(&transmutation, &cargo_toml, &run.args.output.join(&crate_name))? {
+        run.report(format!("   {} {}: {}", "⚠".bright_yellow(), file.display(), conflict));
+    }
+    
+    let mut lock = run.lock.lock().unwrap();
+    lock.packages.insert(npm_name.to_string(), pin.clone());
+    lock.save(&run.lock_path)?;
+    Ok((pin.version, transmutation.resonance))
+}
+
+/// Compare one JS file's soul with one Rust file's in detail, returning
+/// whether it reaches `min_resonance`
+async fn verify_souls(js_path: &Path, rust_path: &Path, min_resonance: Option<f64>) -> Result<bool, Box<dyn std::error::Error>> {
     println!("{}", "🔍 Verifying soul resonance...".bright_yellow());
     
     let mut extractor = protein_hash::SoulExtractor::new();
     
     // Read files
-    let js_code = fs::read_to_string(&js_path)?;
-    let rust_code = fs::read_to_string(&rust_path)?;
+    let js_code = fs::read_to_string(js_path)?;
+    let rust_code = fs::read_to_string(rust_path)?;
     
     // Extract souls
     let js_soul = extractor.extract_soul_js(&js_code).await;
@@ -277,45 +1358,153 @@ async fn verify_souls(js_path: PathBuf, rust_path: PathBuf) -> Result<(), Box<dy
         if resonance > 0.8 { bar.bright_green() } else if resonance > 0.5 { bar.bright_yellow() } else { bar.bright_red() },
         resonance * 100.0);
     
-    Ok(())
+    Ok(min_resonance.is_none_or(|min| resonance >= min))
+}
+
+/// Measure every pair, printing a table of them and what went unmatched,
+/// and returning whether all reach `min_resonance`
+async fn verify_pairs(pairing: pairs::Pairing, json: Option<PathBuf>, min_resonance: Option<f64>) -> Result<bool, Box<dyn std::error::Error>> {
+    println!("{}", format!("🔍 Verifying soul resonance of {} pairs...", pairing.pairs.len()).bright_yellow());
+    let measured = pairs::measure(&pairing.pairs).await?;
+    let below: Vec<&pairs::PairResonance> = measured.iter()
+        .filter(|pair| min_resonance.is_some_and(|min| pair.resonance < min))
+        .collect();
+    
+    // One row a pair, the columns as wide as their longest path
+    let width = |paths: Vec<&Path>| paths.iter().map(|path| path.display().to_string().chars().count()).max().unwrap_or(0).max(10);
+    let js_width = width(measured.iter().map(|pair| pair.js.as_path()).collect());
+    let rust_width = width(measured.iter().map(|pair| pair.rust.as_path()).collect());
+    println!();
+    let header = format!("{:<js_width$}  {:<rust_width$}  {:>9}  Match", "JavaScript", "Rust", "Resonance");
+    println!("   {}", header.bright_white());
+    for pair in &measured {
+        let resonance = format!("{:>9.3}", pair.resonance);
+        let resonance = if pair.resonance > 0.8 { resonance.bright_green() } else if pair.resonance > 0.5 { resonance.bright_yellow() } else { resonance.bright_red() };
+        println!("   {:<js_width$}  {:<rust_width$}  {}  {}", pair.js.display(), pair.rust.display(), resonance, if pair.souls_match { "✅" } else { "❌" });
+    }
+    for path in &pairing.unmatched_js {
+        println!("   {} {} has no Rust file", "?".bright_yellow(), path.display());
+    }
+    for path in &pairing.unmatched_rust {
+        println!("   {} {} has no JS file", "?".bright_yellow(), path.display());
+    }
+    
+    println!();
+    let mean = measured.iter().map(|pair| pair.resonance).sum::<f64>() / measured.len().max(1) as f64;
+    println!("   {} {:.3} over {} pairs", "Mean resonance:".bright_magenta(), mean, measured.len());
+    println!("   {} {}/{}", "Souls match:".bright_magenta(), measured.iter().filter(|pair| pair.souls_match).count(), measured.len());
+    if let Some(min) = min_resonance {
+        match below.len() {
+            0 => println!("   {} every pair reaches {}", "Threshold:".bright_magenta(), min),
+            n => println!("   {} {}", "Threshold:".bright_magenta(), format!("{} below {}", n, min).bright_red()),
+        }
+    }
+    
+    if let Some(json) = json {
+        let report = serde_json::json!({
+            "pairs": measured,
+            "unmatched_js": pairing.unmatched_js,
+            "unmatched_rust": pairing.unmatched_rust,
+            "mean_resonance": mean,
+            "min_resonance": min_resonance,
+            "passed": below.is_empty(),
+        });
+        fs::write(&json, serde_json::to_string_pretty(&report)?)?;
+        println!("   {} {}", "Report:".bright_magenta(), json.display());
+    }
+    
+    Ok(below.is_empty())
 }
 
-fn show_stats(db_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+fn show_stats(db_path: PathBuf, prefix: String, tag: Option<String>, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let forge = SoulForge::new(db_path.to_str().unwrap_or("./forge.db"))?;
+    let mut filter = TransmutationFilter::default().with_name_prefix(&prefix);
+    if let Some(tag) = &tag {
+        filter = filter.with_tag(tag);
+    }
+    let stats = forge.stats(&filter)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    
     println!("{}", "📊 Transmutation Statistics:".bright_cyan());
+    println!("   {} {}", "Total Transmutations:".bright_white(), stats.total);
+    let share = |count: usize| count as f64 / stats.total.max(1) as f64 * 100.0;
+    if let Some(mean) = stats.mean_resonance {
+        println!("   {} {} ({:.1}%)", "Perfect (>95%):".bright_green(), stats.perfect, share(stats.perfect));
+        println!("   {} {} ({:.1}%)", "Good (>80%):".bright_yellow(), stats.good, share(stats.good));
+        println!("   {} {} ({:.1}%)", "Weak (<80%):".bright_red(), stats.weak, share(stats.weak));
+        println!("   {} {:.3}", "Mean Resonance:".bright_white(), mean);
+        
+        // One bar a bucket, the fullest as wide as the meter
+        println!();
+        println!("{}", "   Resonance:".bright_cyan());
+        let fullest = stats.histogram.iter().map(|bucket| bucket.count).max().unwrap_or(0).max(1);
+        for bucket in &stats.histogram {
+            let bar = "█".repeat(bucket.count * 40 / fullest);
+            println!("   {:.1}–{:.1} {:<40} {}", bucket.from, bucket.to, bar.bright_magenta(), bucket.count);
+        }
+    }
     
-    let db = sled::open(db_path)?;
-    
-    let mut total = 0;
-    let mut perfect = 0;
-    let mut good = 0;
-    let mut weak = 0;
-    
-    for item in db.iter() {
-        if let Ok((key, value)) = item {
-            if key.starts_with(b"transmutation:") {
-                total += 1;
-                
-                // Deserialize to check resonance
-                if let Ok(transmutation) = bincode::deserialize::<soul_forge::Transmutation>(&value) {
-                    if transmutation.resonance > 0.95 {
-                        perfect += 1;
-                    } else if transmutation.resonance > 0.8 {
-                        good += 1;
-                    } else {
-                        weak += 1;
-                    }
-                }
-            }
+    let rate = |rate: Option<f64>| rate.map_or("–".to_string(), |rate| format!("{:.1}%", rate * 100.0));
+    println!();
+    println!("   {} {}", "Compiled:".bright_white(), rate(stats.compile_rate));
+    println!("   {} {}", "Equivalence Pass Rate:".bright_white(), rate(stats.pass_rate));
+    
+    if !stats.trends.is_empty() {
+        println!();
+        println!("{}", "   Re-transmuted:".bright_cyan());
+        for trend in &stats.trends {
+            let change = format!("{:+.3}", trend.change());
+            let change = if trend.change() < 0.0 { change.bright_red() } else { change.bright_green() };
+            println!("   {} {:.3} → {:.3} ({}) over {} runs", format!("{}:", trend.npm_name).bright_white(), trend.first, trend.last, change, trend.transmutations);
+        }
+    }
+    
+    if !stats.failures.is_empty() {
+        println!();
+        println!("{}", "   Top Failures:".bright_cyan());
+        for failure in &stats.failures {
+            println!("   {} {} (last: {})", format!("{}×", failure.count).bright_red(), failure.reason, failure.example);
         }
     }
     
-    println!("   {} {}", "Total Transmutations:".bright_white(), total);
-    println!("   {} {} ({:.1}%)", "Perfect (>95%):".bright_green(), perfect, 
-        perfect as f64 / total as f64 * 100.0);
-    println!("   {} {} ({:.1}%)", "Good (>80%):".bright_yellow(), good,
-        good as f64 / total as f64 * 100.0);
-    println!("   {} {} ({:.1}%)", "Weak (<80%):".bright_red(), weak,
-        weak as f64 / total as f64 * 100.0);
+    Ok(())
+}
+
+fn migrate_db(db_path: PathBuf, discard_unreadable: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let migrated = schema::migrate(&db_path.to_string_lossy(), discard_unreadable)?;
+    let from = migrated.from.map_or("unversioned".to_string(), |version| format!("v{}", version));
+    println!("{} {} → v{}", "🧬 Migrated".bright_green(), from, schema::SCHEMA_VERSION);
+    println!("   {} {}", "Records upgraded:".bright_white(), migrated.upgraded);
+    for npm_name in &migrated.discarded {
+        println!("   {} {}", "Discarded:".bright_red(), npm_name);
+    }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+fn doctor_db(db_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", "🩺 Database Checkup:".bright_cyan());
+    
+    let diagnosis = schema::doctor(&db_path.to_string_lossy())?;
+    let version = diagnosis.version.map_or("unversioned".to_string(), |version| format!("v{}", version));
+    println!("   {} {} (this forge writes v{})", "Schema:".bright_white(), version, schema::SCHEMA_VERSION);
+    for (layout, count) in &diagnosis.layouts {
+        println!("   {} {}", format!("Layout v{}:", layout).bright_white(), count);
+    }
+    for npm_name in &diagnosis.unreadable {
+        println!("   {} {}", "Unreadable:".bright_red(), npm_name);
+    }
+    
+    if diagnosis.is_healthy() {
+        println!("{}", "✨ Healthy".bright_green());
+    } else if diagnosis.needs_migration() {
+        println!("{}", "⚠️  Run `forge db migrate` to bring it up to date".bright_yellow());
+    } else {
+        println!("{}", "⚠️  Some records can't be read; `forge db migrate --discard-unreadable` drops them".bright_yellow());
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,362 @@
+// 🔀 Merging - Regenerating Crates Without Losing Hand Edits
+use crate::refine::offset;
+use quote::ToTokens;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use syn::spanned::Spanned;
+
+/// Opens a generated region, after the file's comment prefix: its id, then
+/// a hash of what was generated
+const BEGIN: &str = "forge:begin ";
+
+/// Opens a region its user took over, which regeneration leaves alone
+const KEEP: &str = "forge:keep ";
+
+/// Closes a region, naming it again
+const END: &str = "forge:end ";
+
+/// A file after regenerated content was merged into it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merge {
+    pub content: String,
+    /// What regeneration left alone because it was edited by hand
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    /// The source map of merged Rust code, whose lines hand edits may have
+    /// moved
+    pub fn source_map(&self) -> String {
+        crate::sourcemap::source_map(&self.content)
+    }
+}
+
+/// Hand edits a merge didn't overwrite
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// A region edited by hand that regeneration changes; kept as edited
+    Edited(String),
+    /// A region edited by hand that the forge no longer generates; kept as
+    /// edited
+    Orphaned(String),
+    /// A file without markers, written before there were any; replaced
+    /// whole, for the caller to keep the old one aside
+    Unmarked,
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Edited(region) => write!(f, "`{}` was edited by hand and regenerated differently; kept as edited (mark it `forge:keep` to keep it for good)", region),
+            Self::Orphaned(region) => write!(f, "`{}` was edited by hand and is no longer generated; kept as edited", region),
+            Self::Unmarked => write!(f, "the file has no generated regions marked; replaced whole"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Region {
+    id: String,
+    /// What was generated's hash; `None` for a kept region
+    hash: Option<String>,
+    /// The lines between the markers
+    body: String,
+    /// The comment prefix its markers were written with
+    comment: String,
+}
+
+impl Region {
+    fn generated(id: String, body: String, comment: &str) -> Self {
+        Self { hash: Some(digest(&body)), id, body, comment: comment.to_string() }
+    }
+
+    /// Whether its body is still what was generated
+    fn untouched(&self) -> bool {
+        self.hash.as_deref() == Some(digest(&self.body).as_str())
+    }
+
+    fn render(&self) -> String {
+        let begin = match &self.hash {
+            Some(hash) => format!("{}{} {}", BEGIN, self.id, hash),
+            None => format!("{}{}", KEEP, self.id),
+        };
+        format!("{} {}\n{}{} {}{}\n", self.comment, begin, self.body, self.comment, END, self.id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Region(Region),
+}
+
+/// The first 16 hex digits of `body`'s SHA-256
+fn digest(body: &str) -> String {
+    Sha256::digest(body.as_bytes())[..8].iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// `content` as one generated region, for files regenerated whole
+pub fn mark_file(content: &str, comment: &str) -> String {
+    let mut body = content.to_string();
+    if !body.ends_with('\n') {
+        body.push('\n');
+    }
+    Region::generated("file".to_string(), body, comment).render()
+}
+
+/// Generated Rust code with each top-level item in a region of its own,
+/// named for the item; the header comments and the `use`s before the first
+/// item make a region each
+///
+/// Code syn can't parse is one region.
+pub fn mark_items(code: &str) -> String {
+    let Ok(file) = syn::parse_file(code) else { return mark_file(code, "//") };
+
+    // Whole lines of each item, its doc comments and attributes included
+    let mut spans: Vec<(String, usize, usize)> = vec![];
+    for item in &file.items {
+        let span = item.span();
+        let start = code[..offset(code, span.start())].rfind('\n').map_or(0, |newline| newline + 1);
+        let end = code[offset(code, span.end())..].find('\n').map_or(code.len(), |newline| offset(code, span.end()) + newline + 1);
+        let id = id_of(item);
+        match spans.last_mut() {
+            Some((last, _, last_end)) if id == "uses" && last == "uses" => *last_end = end,
+            _ => spans.push((id, start, end)),
+        }
+    }
+
+    // The same item twice, like two `impl` blocks of a type, is told apart by count
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut marked = String::new();
+    let mut at = 0;
+    let first = spans.first().map_or(code.len(), |(_, start, _)| *start);
+    let header = code[..first].trim_end();
+    if !header.is_empty() {
+        marked.push_str(&Region::generated("header".to_string(), format!("{}\n", header), "//").render());
+        at = header.len() + 1;
+    }
+    for (id, start, end) in spans {
+        let count = seen.entry(id.clone()).or_default();
+        *count += 1;
+        let id = match count {
+            1 => id,
+            n => format!("{} #{}", id, n),
+        };
+        marked.push_str(code.get(at..start).unwrap_or_default());
+        let mut body = code[start..end].to_string();
+        if !body.ends_with('\n') {
+            body.push('\n');
+        }
+        marked.push_str(&Region::generated(id, body, "//").render());
+        at = end;
+    }
+    marked.push_str(code.get(at..).unwrap_or_default());
+    marked
+}
+
+/// Code `mark_items` marked as it was before, without the markers or the
+/// header region, for laying out again under a new header
+pub(crate) fn body_of(code: &str) -> String {
+    let body: String = segments(code).into_iter().map(|segment| match segment {
+        Segment::Text(text) => text,
+        Segment::Region(region) if region.id == "header" => String::new(),
+        Segment::Region(region) => region.body,
+    }).collect();
+    body.trim_start_matches('\n').to_string()
+}
+
+/// How a region names an item: its kind and name, or the header of an `impl`
+fn id_of(item: &syn::Item) -> String {
+    let compact = |tokens: &dyn ToTokens| tokens.to_token_stream().to_string().replace(' ', "");
+    match item {
+        syn::Item::Fn(function) => format!("fn {}", function.sig.ident),
+        syn::Item::Struct(structure) => format!("struct {}", structure.ident),
+        syn::Item::Enum(enumeration) => format!("enum {}", enumeration.ident),
+        syn::Item::Trait(block) => format!("trait {}", block.ident),
+        syn::Item::Mod(module) => format!("mod {}", module.ident),
+        syn::Item::Const(constant) => format!("const {}", constant.ident),
+        syn::Item::Static(statik) => format!("static {}", statik.ident),
+        syn::Item::Type(alias) => format!("type {}", alias.ident),
+        syn::Item::Impl(block) => match &block.trait_ {
+            Some((_, path, _)) => format!("impl {} for {}", compact(path), compact(&block.self_ty)),
+            None => format!("impl {}", compact(&block.self_ty)),
+        },
+        syn::Item::Use(_) => "uses".to_string(),
+        _ => "item".to_string(),
+    }
+}
+
+/// `content` split into its regions and the text around them; a region
+/// left open is text
+fn segments(content: &str) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut text = String::new();
+    let mut lines = content.split_inclusive('\n');
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        let comment = &trimmed[..trimmed.len() - trimmed.trim_start_matches(['/', '#']).len()];
+        let marker = trimmed[comment.len()..].trim_start();
+        let opened = match (marker.strip_prefix(BEGIN), marker.strip_prefix(KEEP)) {
+            (Some(rest), _) => rest.rsplit_once(' ').map(|(id, hash)| (id.to_string(), Some(hash.to_string()))),
+            (None, Some(id)) => Some((id.to_string(), None)),
+            (None, None) => None,
+        };
+        let Some((id, hash)) = opened.filter(|_| !comment.is_empty()) else {
+            text.push_str(line);
+            continue;
+        };
+
+        let end = format!("{}{}", END, id);
+        let mut body = String::new();
+        let mut closed = false;
+        let mut rest = lines.clone();
+        for line in rest.by_ref() {
+            if line.trim().trim_start_matches(['/', '#']).trim_start() == end {
+                closed = true;
+                break;
+            }
+            body.push_str(line);
+        }
+        if !closed {
+            text.push_str(line);
+            continue;
+        }
+        lines = rest;
+        segments.push(Segment::Text(std::mem::take(&mut text)));
+        segments.push(Segment::Region(Region { id, hash, body, comment: comment.to_string() }));
+    }
+    segments.push(Segment::Text(text));
+    segments.retain(|segment| *segment != Segment::Text(String::new()));
+    segments
+}
+
+/// Merge `generated` content, marked, into the `existing` file it was
+/// written to before
+///
+/// Regions still as generated take what's generated now, and text outside
+/// regions is kept. Regions edited by hand are kept as edited and reported,
+/// unless what's generated now is the same. New regions go after the
+/// region they follow in `generated`.
+pub fn merge(existing: &str, generated: &str) -> Merge {
+    let old = segments(existing);
+    if !old.iter().any(|segment| matches!(segment, Segment::Region(_))) {
+        let conflicts = match existing == generated || existing.trim().is_empty() {
+            true => vec![],
+            false => vec![Conflict::Unmarked],
+        };
+        return Merge { content: generated.to_string(), conflicts };
+    }
+    let fresh: Vec<Region> = segments(generated).into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Region(region) => Some(region),
+            Segment::Text(_) => None,
+        })
+        .collect();
+
+    let mut conflicts = vec![];
+    let mut merged: Vec<Segment> = vec![];
+    for segment in old {
+        let region = match segment {
+            Segment::Text(text) => {
+                merged.push(Segment::Text(text));
+                continue;
+            }
+            Segment::Region(region) => region,
+        };
+        let now = fresh.iter().find(|now| now.id == region.id);
+        match now {
+            _ if region.hash.is_none() => merged.push(Segment::Region(region)),
+            Some(now) if region.untouched() || region.body == now.body => merged.push(Segment::Region(now.clone())),
+            Some(_) => {
+                conflicts.push(Conflict::Edited(region.id.clone()));
+                merged.push(Segment::Region(region));
+            }
+            // A dropped region takes the blank line before it along
+            None if region.untouched() => {
+                if let Some(Segment::Text(text)) = merged.last_mut() {
+                    if text == "\n" || text.ends_with("\n\n") {
+                        text.pop();
+                    }
+                }
+            }
+            None => {
+                conflicts.push(Conflict::Orphaned(region.id.clone()));
+                merged.push(Segment::Region(region));
+            }
+        }
+    }
+
+    // Regions the file doesn't have yet follow the one before them
+    for (index, region) in fresh.iter().enumerate() {
+        let placed = |segment: &Segment| matches!(segment, Segment::Region(placed) if placed.id == region.id);
+        if merged.iter().any(placed) {
+            continue;
+        }
+        let after = fresh[..index].iter().rev().find_map(|before| {
+            merged.iter().position(|segment| matches!(segment, Segment::Region(placed) if placed.id == before.id))
+        });
+        let at = match after {
+            Some(position) => position + 1,
+            None => merged.iter().position(|segment| matches!(segment, Segment::Region(_))).unwrap_or(merged.len()),
+        };
+        let (before, after) = match after {
+            Some(_) => (vec![Segment::Text("\n".to_string())], vec![]),
+            None => (vec![], vec![Segment::Text("\n".to_string())]),
+        };
+        merged.splice(at..at, before.into_iter().chain([Segment::Region(region.clone())]).chain(after));
+    }
+
+    let content = merged.iter().map(|segment| match segment {
+        Segment::Text(text) => text.clone(),
+        Segment::Region(region) => region.render(),
+    }).collect();
+    Merge { content, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENERATED: &str = "// Generated\nuse std::fmt;\nuse serde_json::Value;\n\n/// Doubles\npub fn double(x: f64) -> f64 {\n    x * 2.0\n}\n\npub fn half(x: f64) -> f64 {\n    x / 2.0\n}\n";
+
+    #[test]
+    fn test_items_are_marked_by_name() {
+        let marked = mark_items(GENERATED);
+        assert!(marked.starts_with("// forge:begin header "));
+        assert!(marked.contains("// forge:end header\n// forge:begin uses "));
+        assert!(marked.contains(&format!("// forge:begin fn double {}\n/// Doubles\npub fn double", digest("/// Doubles\npub fn double(x: f64) -> f64 {\n    x * 2.0\n}\n"))));
+        assert!(marked.ends_with("}\n// forge:end fn half\n"));
+        assert_eq!(merge(&marked, &marked), Merge { content: marked.clone(), conflicts: vec![] });
+    }
+
+    #[test]
+    fn test_merging_keeps_hand_edits() {
+        let before = mark_items(GENERATED);
+        let after = mark_items(&GENERATED.replace("x / 2.0", "x * 0.5").replace("// Generated", "// Generated again"));
+
+        // Edits outside regions stay, and untouched regions are regenerated
+        let edited = format!("{}\n/// Mine\npub fn triple(x: f64) -> f64 {{\n    x * 3.0\n}}\n", before);
+        let merged = merge(&edited, &after);
+        assert_eq!(merged.content, format!("{}\n/// Mine\npub fn triple(x: f64) -> f64 {{\n    x * 3.0\n}}\n", after));
+        assert!(merged.conflicts.is_empty());
+
+        // Edits inside a region that regeneration changes are kept and reported
+        let edited = before.replace("x / 2.0", "x / 2.0 + 0.0");
+        let merged = merge(&edited, &after);
+        assert!(merged.content.contains("x / 2.0 + 0.0") && merged.content.contains("// Generated again"));
+        assert_eq!(merged.conflicts, [Conflict::Edited("fn half".to_string())]);
+
+        // A region taken over is left alone
+        let kept: String = edited.split_inclusive('\n')
+            .map(|line| if line.starts_with("// forge:begin fn half ") { "// forge:keep fn half\n" } else { line })
+            .collect();
+        let merged = merge(&kept, &after);
+        assert!(merged.conflicts.is_empty());
+        assert!(merged.content.contains("// forge:keep fn half\npub fn half(x: f64) -> f64 {\n    x / 2.0 + 0.0\n}\n"));
+
+        // Untouched regions no longer generated go, and new ones follow the region before them
+        let regenerated = mark_items(&GENERATED.replace("pub fn half", "pub fn third(x: f64) -> f64 {\n    x / 3.0\n}\n\npub fn quarter"));
+        let merged = merge(&before, &regenerated);
+        assert_eq!(merged.content, regenerated);
+    }
+}
@@ -0,0 +1,386 @@
+// 🧩 Module Systems - ES and CommonJS Packages as Rust Module Trees
+use crate::dependencies::CrateDependency;
+use crate::{body, parse, Diagnostic, ImportedName, JsAst, JsType, SourceKind, TransmutationError};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+/// Extensions a specifier without one resolves to, tried in this order
+const EXTENSIONS: &[&str] = &["js", "mjs", "cjs", "ts", "mts", "cts", "jsx", "tsx"];
+
+/// Module code run under Node before a CommonJS source, which Node would
+/// otherwise read as an ES module: `require` resolves next to the script,
+/// and `__forgeExported` reaches what the source put on `module.exports`
+const COMMONJS_SHIM: &str = r#"import { createRequire as __forgeCreateRequire } from "node:module";
+const require = __forgeCreateRequire(import.meta.url);
+const module = { exports: {} };
+const exports = module.exports;
+const __forgeExported = (name) => typeof module.exports === "function" ? module.exports : module.exports[name];
+"#;
+
+/// One source file of a package
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsModule {
+    /// Where it is in the package, like `lib/utils/math.js`
+    pub path: PathBuf,
+    pub kind: SourceKind,
+    pub code: String,
+}
+
+impl JsModule {
+    /// The module at `path`, of the kind its extension says
+    pub fn new(path: impl Into<PathBuf>, code: impl Into<String>) -> Self {
+        let path = path.into();
+        Self { kind: SourceKind::from_path(&path), path, code: code.into() }
+    }
+
+    /// The Rust module it becomes below the crate root, a segment a directory
+    /// and one for the file, like `utils::math`; an `index` file is its
+    /// directory's module
+    pub(crate) fn rust_path(&self) -> Vec<String> {
+        let mut segments: Vec<String> = self.path.with_extension("").components()
+            .filter_map(|component| match component {
+                Component::Normal(segment) => Some(segment.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+        if segments.len() > 1 && segments.last().is_some_and(|last| last == "index") {
+            segments.pop();
+        }
+        segments.iter().map(|segment| rust_segment(segment)).collect()
+    }
+
+    /// `entry` and every module it reaches through relative imports, requires
+    /// and re-exports, entry first, with paths from the directory holding them
+    /// all
+    pub fn collect(entry: &Path) -> Result<Vec<JsModule>, TransmutationError> {
+        let read = |path: &Path| std::fs::read_to_string(path)
+            .map_err(|e| TransmutationError::ParseError(Diagnostic::error(e.to_string()).with_file(path.display().to_string())));
+        let mut found: Vec<(PathBuf, String)> = vec![(normalize(entry), read(entry)?)];
+        let mut next = 0;
+        while next < found.len() {
+            let (path, code) = &found[next];
+            let ast = parse::parse_source(code, SourceKind::from_path(path))
+                .map_err(|e| e.in_file(path.display().to_string()))?;
+            let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            let reached: Vec<PathBuf> = specifiers(&ast)
+                .filter_map(|specifier| candidates(&dir, specifier).into_iter().find(|candidate| candidate.is_file()))
+                .collect();
+            for path in reached {
+                if !found.iter().any(|(known, _)| *known == path) {
+                    let code = read(&path)?;
+                    found.push((path, code));
+                }
+            }
+            next += 1;
+        }
+
+        // Paths go from the deepest directory every module is under
+        let dirs: Vec<&Path> = found.iter().filter_map(|(path, _)| path.parent()).collect();
+        let root = dirs.iter().skip(1).fold(dirs[0].to_path_buf(), |root, dir| {
+            root.components().zip(dir.components()).take_while(|(a, b)| a == b).map(|(a, _)| a).collect()
+        });
+        Ok(found.into_iter()
+            .map(|(path, code)| JsModule::new(path.strip_prefix(&root).unwrap_or(&path), code))
+            .collect())
+    }
+}
+
+/// `segment` of a file path as a Rust module name
+fn rust_segment(segment: &str) -> String {
+    let mut name: String = segment.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    body::rust_ident(&name)
+}
+
+/// The relative specifiers a module imports or re-exports from
+fn specifiers(ast: &JsAst) -> impl Iterator<Item = &str> {
+    ast.imports.iter().map(|import| import.source.as_str())
+        .chain(ast.reexports.iter().filter_map(|reexport| reexport.source.as_deref()))
+        .filter(|specifier| specifier.starts_with("./") || specifier.starts_with("../"))
+}
+
+/// The files `specifier`, relative to `dir`, may name: itself, with each
+/// extension, as a directory's index, or the TypeScript source of the
+/// `.js` it imports
+fn candidates(dir: &Path, specifier: &str) -> Vec<PathBuf> {
+    let path = normalize(&dir.join(specifier));
+    let mut candidates = vec![path.clone()];
+    candidates.extend(EXTENSIONS.iter().map(|ext| PathBuf::from(format!("{}.{}", path.display(), ext))));
+    candidates.extend(EXTENSIONS.iter().map(|ext| path.join(format!("index.{}", ext))));
+    let typescript = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("js") => Some("ts"),
+        Some("mjs") => Some("mts"),
+        Some("cjs") => Some("cts"),
+        Some("jsx") => Some("tsx"),
+        _ => None,
+    };
+    candidates.extend(typescript.map(|ext| path.with_extension(ext)));
+    candidates
+}
+
+/// `path` with `.` and `..` taken out, as far as they can be
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normal.components().next_back(), Some(Component::Normal(_))) => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+/// The index of the module `specifier` names from `from`, among `modules`
+pub(crate) fn resolve(from: &JsModule, specifier: &str, modules: &[JsModule]) -> Option<usize> {
+    if !specifier.starts_with("./") && !specifier.starts_with("../") {
+        return None;
+    }
+    let dir = from.path.parent().unwrap_or(Path::new(""));
+    candidates(dir, specifier).iter().find_map(|candidate| modules.iter().position(|module| module.path == *candidate))
+}
+
+/// What a module brings in from the others and from transmuted packages: its
+/// `use` lines, imports then re-exports, and the signatures its bodies call
+/// them by
+#[derive(Debug, Default)]
+pub(crate) struct Linked {
+    pub(crate) uses: Vec<String>,
+    pub(crate) signatures: HashMap<String, JsType>,
+}
+
+impl Linked {
+    /// Link module `index` of `modules`, parsed as `asts`, to the others and
+    /// to the crates of `dependencies`; the first module is the crate root
+    pub(crate) fn of(index: usize, modules: &[JsModule], asts: &[JsAst], dependencies: &[CrateDependency]) -> Self {
+        let mut linked = Self::default();
+        let (module, ast) = (&modules[index], &asts[index]);
+        let target = |source: &str| resolve(module, source, modules);
+        let not_transmuted = |name: &str, source: &str| format!("// `{}` from \"{}\" isn't transmuted", name, source);
+        // Only a package's entry module is transmuted, so its subpaths aren't
+        let package = |source: &str| dependencies.iter().find(|dependency| dependency.npm_name == source);
+
+        for import in &ast.imports {
+            let Some(other) = target(&import.source) else {
+                match package(&import.source) {
+                    Some(dependency) => linked.use_package(dependency, &import.name, &import.local),
+                    None => linked.uses.push(not_transmuted(&import.local, &import.source)),
+                }
+                continue;
+            };
+            let path = rust_path(modules, other);
+            let item = match &import.name {
+                ImportedName::Named(name) => Some(name.clone()),
+                ImportedName::Default => asts[other].default_export.clone(),
+                // A CommonJS module exporting one function is that function
+                ImportedName::Namespace if asts[other].commonjs => asts[other].default_export.clone(),
+                ImportedName::Namespace => None,
+            };
+            match item {
+                Some(item) if provides(&asts[other], &item) => {
+                    // CommonJS exports what it requires by listing it
+                    let keyword = match ast.exports.contains(&import.local) && !provides(ast, &import.local) {
+                        true => "pub use",
+                        false => "use",
+                    };
+                    linked.uses.push(use_line(keyword, &path, Some(&item), Some(&import.local)));
+                    if let Some(signature) = signature(&asts[other], &item) {
+                        linked.signatures.insert(import.local.clone(), signature);
+                    }
+                }
+                Some(_) => linked.uses.push(not_transmuted(&import.local, &import.source)),
+                None => {
+                    linked.uses.push(use_line("use", &path, None, Some(&import.local)));
+                    // `m.f(..)` calls `m::f(..)`
+                    for function in &asts[other].functions {
+                        linked.signatures.insert(format!("{}.{}", import.local, function.name), function.signature());
+                    }
+                }
+            }
+        }
+
+        for reexport in &ast.reexports {
+            let exported = reexport.exported.as_deref();
+            let Some(source) = &reexport.source else {
+                // The module's own binding, or one it imported, under a new name
+                let ImportedName::Named(name) = &reexport.name else { continue };
+                if provides(ast, name) || ast.imports.iter().any(|import| import.local == *name) {
+                    linked.uses.push(use_line("pub use", "self", Some(name), exported));
+                }
+                continue;
+            };
+            let Some(other) = target(source) else {
+                let item = match &reexport.name {
+                    ImportedName::Named(name) => package(source).filter(|dependency| dependency.functions.contains_key(name)).map(|_| Some(name.as_str())),
+                    ImportedName::Namespace => package(source).map(|_| None),
+                    ImportedName::Default => None,
+                };
+                match (package(source), item) {
+                    (Some(dependency), Some(item)) => linked.uses.push(use_line("pub use", &dependency.ident(), item, exported)),
+                    _ => linked.uses.push(not_transmuted(exported.unwrap_or("*"), source)),
+                }
+                continue;
+            };
+            let path = rust_path(modules, other);
+            let item = match &reexport.name {
+                ImportedName::Named(name) => Some(name.clone()),
+                ImportedName::Default => asts[other].default_export.clone(),
+                ImportedName::Namespace if asts[other].commonjs && exported.is_some() => asts[other].default_export.clone(),
+                ImportedName::Namespace => None,
+            };
+            match (item, exported) {
+                (Some(item), exported) if provides(&asts[other], &item) => linked.uses.push(use_line("pub use", &path, Some(&item), exported)),
+                (Some(item), exported) => linked.uses.push(not_transmuted(exported.unwrap_or(&item), source)),
+                (None, None) => linked.uses.push(format!("pub use {}::*;", path)),
+                (None, exported) => linked.uses.push(use_line("pub use", &path, None, exported)),
+            }
+        }
+        linked
+    }
+
+    /// `use` what a module imports from the crate of `dependency`: a function
+    /// by name, or the whole crate; anything else stays a comment
+    fn use_package(&mut self, dependency: &CrateDependency, name: &ImportedName, local: &str) {
+        let path = dependency.ident();
+        // A package of one function, as CommonJS packages often are, is that
+        // function whichever way it's imported
+        let single = match dependency.functions.len() {
+            1 => dependency.functions.keys().next(),
+            _ => None,
+        };
+        let item = match (name, single) {
+            (ImportedName::Named(item), _) => Some(item),
+            (_, Some(item)) => Some(item),
+            _ => None,
+        };
+        match (name, item) {
+            (_, Some(item)) => match dependency.functions.get(item) {
+                Some(signature) => {
+                    self.uses.push(use_line("use", &path, Some(item), Some(local)));
+                    self.signatures.insert(local.to_string(), signature.clone());
+                }
+                None => self.uses.push(format!("// `{}` isn't a function {} exports", local, dependency.crate_name)),
+            },
+            (ImportedName::Namespace, None) => {
+                self.uses.push(use_line("use", &path, None, Some(local)));
+                for (function, signature) in &dependency.functions {
+                    self.signatures.insert(format!("{}.{}", local, function), signature.clone());
+                }
+            }
+            (_, None) => self.uses.push(format!("// `{}` from \"{}\" has no default export in {}", local, dependency.npm_name, dependency.crate_name)),
+        }
+    }
+}
+
+/// The path of module `index` from the crate root
+fn rust_path(modules: &[JsModule], index: usize) -> String {
+    match index {
+        0 => "crate".to_string(),
+        _ => format!("crate::{}", modules[index].rust_path().join("::")),
+    }
+}
+
+/// `keyword path::item as alias;`, leaving out what would repeat
+fn use_line(keyword: &str, path: &str, item: Option<&str>, alias: Option<&str>) -> String {
+    let used = match item {
+        Some(item) => format!("{}::{}", path, body::rust_ident(item)),
+        None => path.to_string(),
+    };
+    let name = item.unwrap_or_else(|| path.rsplit("::").next().unwrap_or(path));
+    match alias {
+        Some(alias) if alias != name => format!("{} {} as {};", keyword, used, body::rust_ident(alias)),
+        _ => format!("{} {};", keyword, used),
+    }
+}
+
+/// Whether the module has an item named `name` for others to use: a
+/// function, a type, or something it re-exports
+fn provides(ast: &JsAst, name: &str) -> bool {
+    ast.functions.iter().any(|function| function.name == name)
+        || ast.classes.iter().any(|class| class.name == name)
+        || ast.reexports.iter().any(|reexport| reexport.exported.as_deref().is_none_or(|exported| exported == name))
+}
+
+fn signature(ast: &JsAst, name: &str) -> Option<JsType> {
+    ast.functions.iter().find(|function| function.name == name).map(|function| function.signature())
+}
+
+/// The crate's code: the root module's, then each other module's nested in
+/// `pub mod`s along its path
+pub(crate) fn nest(root: &str, modules: Vec<(Vec<String>, String)>) -> String {
+    #[derive(Default)]
+    struct Tree {
+        code: String,
+        children: BTreeMap<String, Tree>,
+    }
+    fn render(tree: &Tree) -> String {
+        let mut code = tree.code.trim_end().to_string();
+        for (name, child) in &tree.children {
+            code.push_str(&format!("\n\npub mod {} {{\n{}\n}}", name, crate::indent(&render(child))));
+        }
+        code.trim_start().to_string()
+    }
+
+    if modules.is_empty() {
+        return root.to_string();
+    }
+    let mut tree = Tree { code: root.to_string(), children: BTreeMap::new() };
+    for (path, code) in modules {
+        let node = path.into_iter().fold(&mut tree, |node, segment| node.children.entry(segment).or_default());
+        node.code.push_str(&code);
+    }
+    format!("{}\n", render(&tree))
+}
+
+/// The file the origins of `module`'s functions name: its path in the
+/// package, under the directory `entry_file` names the entry `entry` in
+pub(crate) fn origin_file(entry_file: &str, entry: &Path, module: &Path) -> String {
+    let module = module.display().to_string();
+    match entry_file.strip_suffix(&entry.display().to_string()) {
+        Some(dir) if !entry.as_os_str().is_empty() => format!("{}{}", dir, module),
+        _ => module,
+    }
+}
+
+/// `js_code` as Node runs it before a trailer calling `functions`: with a
+/// CommonJS source shimmed, and the table of the functions to call
+pub(crate) fn for_node(js_code: &str, commonjs: bool, functions: &[&str]) -> (String, String) {
+    match commonjs {
+        false => (js_code.to_string(), functions.join(", ")),
+        true => {
+            let table: Vec<String> = functions.iter()
+                .map(|name| format!("{0}: typeof {0} === \"function\" ? {0} : __forgeExported({0:?})", name))
+                .collect();
+            (format!("{}{}", COMMONJS_SHIM, js_code), table.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imports_become_uses_of_the_modules_they_name() {
+        let modules = [
+            JsModule::new("index.js", "const { add } = require('./utils/math');\nconst strings = require('./strings');\nmodule.exports = { add, shout: strings.shout, ...require('./strings') };"),
+            JsModule::new("utils/math.js", "exports.add = (a, b) => a + b;"),
+            JsModule::new("strings/index.js", "module.exports.shout = function (text) { return text.toUpperCase(); };\nexport function lower(text) { return text; }"),
+        ];
+        let asts: Vec<JsAst> = modules.iter().map(|module| parse::parse_source(&module.code, module.kind).unwrap()).collect();
+        assert_eq!(modules[2].rust_path(), ["strings"]);
+        assert_eq!(resolve(&modules[0], "./utils/math", &modules), Some(1));
+
+        let linked = Linked::of(0, &modules, &asts, &[]);
+        assert_eq!(linked.uses, ["use crate::utils::math::add;", "use crate::strings;", "pub use crate::strings::*;"]);
+        assert!(linked.signatures.contains_key("add"));
+        assert!(linked.signatures.contains_key("strings.shout"));
+
+        let code = nest("pub fn root() {}\n", vec![(vec!["utils".to_string(), "math".to_string()], "pub fn add() {}\n".to_string())]);
+        assert_eq!(code, "pub fn root() {}\n\npub mod utils {\n    pub mod math {\n        pub fn add() {}\n    }\n}\n");
+    }
+}
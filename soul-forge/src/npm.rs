@@ -0,0 +1,377 @@
+// 📦 npm Registry - Fetching the Souls to Transmute
+use crate::{Diagnostic, TransmutationError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Where packages come from unless told otherwise
+pub const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org";
+
+/// A package version, unpacked in the cache
+#[derive(Debug, Clone)]
+pub struct NpmPackage {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    /// Its `package.json`
+    pub manifest: Value,
+    /// The unpacked tarball's `package/` directory
+    pub dir: PathBuf,
+    /// The module the package's `package.json` points at
+    pub entry: PathBuf,
+}
+
+impl NpmPackage {
+    /// The entry module's source
+    pub fn source(&self) -> Result<String, TransmutationError> {
+        std::fs::read_to_string(&self.entry).map_err(|e| TransmutationError::FetchError(Diagnostic::error(e.to_string()).with_file(self.entry.display().to_string())))
+    }
+}
+
+/// A resolved version, as recorded in a lock file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pin {
+    pub version: String,
+    pub tarball: String,
+    /// Subresource integrity, like `sha512-...`, when the registry gives one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
+    /// The packages the version lists in `dependencies`, by name; pins from
+    /// lock files older than this field have none
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+}
+
+/// Pins by package name; once a package is pinned, later fetches reuse the
+/// pinned version instead of resolving its spec again
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lock {
+    pub packages: BTreeMap<String, Pin>,
+}
+
+impl Lock {
+    /// The lock at `path`, or an empty one if there's no file yet
+    pub fn load(path: &Path) -> Result<Self, TransmutationError> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| TransmutationError::FetchError(Diagnostic::error(e.to_string()).with_file(path.display().to_string()))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), TransmutationError> {
+        let text = serde_json::to_string_pretty(self).map_err(|e| TransmutationError::SerializationError(e.to_string().into()))?;
+        std::fs::write(path, text + "\n").map_err(io_error)
+    }
+}
+
+/// Downloads package tarballs into an on-disk cache, one directory a version
+pub struct NpmClient {
+    registry: String,
+    cache_dir: PathBuf,
+    http: reqwest::Client,
+}
+
+impl NpmClient {
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            registry: DEFAULT_REGISTRY.to_string(),
+            cache_dir: cache_dir.into(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Fetch from a mirror or private registry
+    pub fn with_registry(mut self, registry: &str) -> Self {
+        self.registry = registry.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// The version of `name` that `spec` picks: an exact version, a dist-tag
+    /// like `latest`, or a range like `^4.17`
+    pub async fn resolve(&self, name: &str, spec: &str) -> Result<Pin, TransmutationError> {
+        // Scoped names keep their `@` but escape the slash
+        let url = format!("{}/{}", self.registry, name.replace('/', "%2F"));
+        let packument: Value = self.http.get(&url)
+            .header("Accept", "application/vnd.npm.install-v1+json")
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| TransmutationError::FetchError(format!("{}: {}", name, e).into()))?
+            .json()
+            .await
+            .map_err(|e| TransmutationError::FetchError(format!("{}: {}", name, e).into()))?;
+
+        let version = pick_version(&packument, spec)
+            .ok_or_else(|| TransmutationError::FetchError(format!("no version of {} matches '{}'", name, spec).into()))?;
+        let dist = &packument["versions"][&version]["dist"];
+        let dependencies = packument["versions"][&version]["dependencies"].as_object()
+            .map_or(vec![], |dependencies| dependencies.keys().cloned().collect());
+        Ok(Pin {
+            tarball: dist["tarball"].as_str()
+                .ok_or_else(|| TransmutationError::FetchError(format!("{}@{} has no tarball", name, version).into()))?
+                .to_string(),
+            integrity: dist["integrity"].as_str().map(str::to_string),
+            version,
+            dependencies,
+        })
+    }
+
+    /// `name` at the pinned version, unpacking it into the cache unless an
+    /// earlier fetch already did
+    pub async fn fetch(&self, name: &str, pin: &Pin) -> Result<NpmPackage, TransmutationError> {
+        let version_dir = self.cache_dir.join(name).join(&pin.version);
+        let dir = version_dir.join("package");
+        if !dir.join("package.json").exists() {
+            let tarball = self.http.get(&pin.tarball)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| TransmutationError::FetchError(format!("{}@{}: {}", name, pin.version, e).into()))?
+                .bytes()
+                .await
+                .map_err(|e| TransmutationError::FetchError(format!("{}@{}: {}", name, pin.version, e).into()))?;
+            if let Some(integrity) = &pin.integrity {
+                verify_integrity(&tarball, integrity)
+                    .map_err(|e| TransmutationError::FetchError(format!("{}@{}: {}", name, pin.version, e).into()))?;
+            }
+            unpack(&tarball, &version_dir)?;
+        }
+
+        let manifest: Value = std::fs::read_to_string(dir.join("package.json"))
+            .map_err(io_error)
+            .and_then(|text| serde_json::from_str(&text).map_err(|e| TransmutationError::FetchError(e.to_string().into())))?;
+        let entry = entry_point(&manifest, &dir)
+            .ok_or_else(|| TransmutationError::FetchError(format!("{}@{} has no JavaScript entry point", name, pin.version).into()))?;
+        Ok(NpmPackage {
+            name: name.to_string(),
+            version: pin.version.clone(),
+            description: manifest["description"].as_str().unwrap_or_default().to_string(),
+            manifest,
+            dir,
+            entry,
+        })
+    }
+}
+
+fn io_error(error: std::io::Error) -> TransmutationError {
+    TransmutationError::FetchError(error.to_string().into())
+}
+
+/// The version `spec` names in a packument, the highest a range matches,
+/// skipping prereleases unless asked for exactly
+fn pick_version(packument: &Value, spec: &str) -> Option<String> {
+    let versions = packument["versions"].as_object()?;
+    let spec = spec.trim();
+    if versions.contains_key(spec) {
+        return Some(spec.to_string());
+    }
+    let tag = if spec.is_empty() || spec == "*" { "latest" } else { spec };
+    if let Some(version) = packument["dist-tags"][tag].as_str() {
+        return Some(version.to_string());
+    }
+
+    // npm separates a range's comparators with spaces, Cargo with commas
+    let range = semver::VersionReq::parse(&spec.split_whitespace().collect::<Vec<_>>().join(", ")).ok()?;
+    versions
+        .keys()
+        .filter_map(|version| semver::Version::parse(version).ok())
+        .filter(|version| version.pre.is_empty() && range.matches(version))
+        .max()
+        .map(|version| version.to_string())
+}
+
+/// Check `bytes` against a subresource integrity string; only `sha512` is
+/// checked, as the registry has given it for every package since 2017
+fn verify_integrity(bytes: &[u8], integrity: &str) -> Result<(), String> {
+    use base64::Engine;
+    use sha2::Digest;
+
+    let Some(expected) = integrity.split_whitespace().find_map(|hash| hash.strip_prefix("sha512-")) else {
+        return Ok(());
+    };
+    let actual = base64::engine::general_purpose::STANDARD.encode(sha2::Sha512::digest(bytes));
+    match actual == expected {
+        true => Ok(()),
+        false => Err(format!("tarball doesn't match its integrity {}", integrity)),
+    }
+}
+
+/// Unpack a gzipped tarball into `dir`, through a sibling directory so a
+/// failed unpack never looks cached
+fn unpack(tarball: &[u8], dir: &Path) -> Result<(), TransmutationError> {
+    let partial = PathBuf::from(format!("{}.partial", dir.display()));
+    let _ = std::fs::remove_dir_all(&partial);
+    std::fs::create_dir_all(&partial).map_err(io_error)?;
+    tar::Archive::new(flate2::read::GzDecoder::new(tarball)).unpack(&partial).map_err(io_error)?;
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::rename(&partial, dir).map_err(io_error)
+}
+
+/// The module a `package.json` exports as the package root: its `source`
+/// (where packages built from TypeScript keep it), then `exports["."]`,
+/// `module` and `main`, then `index.js`
+///
+/// Conditional exports prefer `import` over `require`, and an entry without
+/// an extension resolves as Node would, trying `.js` and `index.js`.
+pub(crate) fn entry_point(manifest: &Value, dir: &Path) -> Option<PathBuf> {
+    let exports = match &manifest["exports"] {
+        Value::Object(map) if map.keys().any(|key| key.starts_with('.')) => map.get(".").cloned(),
+        other => Some(other.clone()),
+    };
+    let candidates = [
+        manifest["source"].as_str().map(str::to_string),
+        exports.as_ref().and_then(export_target),
+        manifest["module"].as_str().map(str::to_string),
+        manifest["main"].as_str().map(str::to_string),
+        Some("index.js".to_string()),
+    ];
+    candidates.into_iter().flatten().find_map(|entry| {
+        let path = dir.join(entry.trim_start_matches("./"));
+        [path.clone(), path.with_extension("js"), path.join("index.js")]
+            .into_iter()
+            .find(|path| path.is_file())
+    })
+}
+
+/// The file a (possibly conditional) export resolves to
+fn export_target(export: &Value) -> Option<String> {
+    match export {
+        Value::String(target) => Some(target.clone()),
+        Value::Object(conditions) => ["import", "module", "node", "default", "require"]
+            .iter()
+            .find_map(|condition| conditions.get(*condition).and_then(export_target)),
+        Value::Array(targets) => targets.iter().find_map(export_target),
+        _ => None,
+    }
+}
+
+/// The `package.json` of the package `path` is in, if any
+pub fn manifest_of(path: &Path) -> Option<Value> {
+    path.ancestors()
+        .skip(1)
+        .map(|dir| dir.join("package.json"))
+        .find(|manifest| manifest.is_file())
+        .and_then(|manifest| serde_json::from_str(&std::fs::read_to_string(manifest).ok()?).ok())
+}
+
+/// A `package.json`'s license, as written: `license` as a string or a
+/// `{ type }` object, or the deprecated `licenses` list as alternatives
+pub(crate) fn license(manifest: &Value) -> Option<String> {
+    let name = |license: &Value| license.as_str().or_else(|| license["type"].as_str()).map(str::to_string);
+    match (&manifest["license"], &manifest["licenses"]) {
+        (Value::Null, Value::Array(licenses)) if !licenses.is_empty() => {
+            let names: Option<Vec<String>> = licenses.iter().map(name).collect();
+            names.map(|names| match names.as_slice() {
+                [name] => name.clone(),
+                _ => format!("({})", names.join(" OR ")),
+            })
+        }
+        (license, _) => name(license),
+    }
+}
+
+/// The `author` and `contributors` of a `package.json`, each as Cargo
+/// writes an author, `Name <email>`
+pub(crate) fn authors(manifest: &Value) -> Vec<String> {
+    let person = |person: &Value| match person {
+        // npm's `Name <email> (url)` shorthand; Cargo has no place for the url
+        Value::String(person) => Some(person.split(" (").next().unwrap_or(person).trim().to_string()),
+        Value::Object(_) => {
+            let name = person["name"].as_str()?;
+            Some(match person["email"].as_str() {
+                Some(email) => format!("{} <{}>", name, email),
+                None => name.to_string(),
+            })
+        }
+        _ => None,
+    };
+    let contributors = manifest["contributors"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let mut authors: Vec<String> = vec![];
+    for author in std::iter::once(&manifest["author"]).chain(contributors).filter_map(person) {
+        if !author.is_empty() && !authors.contains(&author) {
+            authors.push(author);
+        }
+    }
+    authors
+}
+
+/// A `package.json`'s repository as a browsable URL, expanding npm's
+/// `github:user/repo` and bare `user/repo` shorthands and dropping `git+`
+/// and `.git`
+pub(crate) fn repository(manifest: &Value) -> Option<String> {
+    let repository = &manifest["repository"];
+    let url = repository.as_str().or_else(|| repository["url"].as_str())?.trim();
+    let hosts = [("github:", "https://github.com/"), ("gitlab:", "https://gitlab.com/"), ("bitbucket:", "https://bitbucket.org/")];
+    let url = match hosts.iter().find_map(|(prefix, host)| url.strip_prefix(prefix).map(|path| format!("{}{}", host, path))) {
+        Some(url) => url,
+        None if !url.contains(':') && url.matches('/').count() == 1 => format!("https://github.com/{}", url),
+        None => url.trim_start_matches("git+").replacen("git://", "https://", 1).replacen("ssh://git@", "https://", 1),
+    };
+    Some(url.strip_suffix(".git").unwrap_or(&url).to_string())
+}
+
+/// A `package.json`'s keywords that crates.io accepts, at most the five it
+/// allows: up to 20 letters, digits, `_`, `-` or `+`, starting with a letter
+pub(crate) fn keywords(manifest: &Value) -> Vec<String> {
+    let mut keywords: Vec<String> = vec![];
+    for keyword in manifest["keywords"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+        let keyword = keyword.trim().to_lowercase().replace(' ', "-");
+        let valid = keyword.len() <= 20
+            && keyword.starts_with(|c: char| c.is_ascii_alphabetic())
+            && keyword.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+'));
+        if valid && !keywords.contains(&keyword) && keywords.len() < 5 {
+            keywords.push(keyword);
+        }
+    }
+    keywords
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_versions_resolve_from_specs() {
+        let packument = json!({
+            "dist-tags": { "latest": "4.17.21", "next": "5.0.0-beta.1" },
+            "versions": { "4.17.20": {}, "4.17.21": {}, "4.18.0-rc.1": {}, "3.10.1": {}, "5.0.0-beta.1": {} }
+        });
+        assert_eq!(pick_version(&packument, "3.10.1").as_deref(), Some("3.10.1"));
+        assert_eq!(pick_version(&packument, "latest").as_deref(), Some("4.17.21"));
+        assert_eq!(pick_version(&packument, "").as_deref(), Some("4.17.21"));
+        assert_eq!(pick_version(&packument, "next").as_deref(), Some("5.0.0-beta.1"));
+        assert_eq!(pick_version(&packument, "^4.17").as_deref(), Some("4.17.21"));
+        assert_eq!(pick_version(&packument, ">=3 <4").as_deref(), Some("3.10.1"));
+        assert_eq!(pick_version(&packument, "^6"), None);
+    }
+
+    #[test]
+    fn test_entry_points_follow_package_json() {
+        let package = tempfile::tempdir().unwrap();
+        let dir = package.path();
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::create_dir_all(dir.join("esm")).unwrap();
+        std::fs::write(dir.join("lib").join("index.js"), "").unwrap();
+        std::fs::write(dir.join("esm").join("main.mjs"), "").unwrap();
+
+        assert_eq!(entry_point(&json!({ "main": "lib" }), dir), Some(dir.join("lib").join("index.js")));
+        assert_eq!(entry_point(&json!({ "main": "./lib/index" }), dir), Some(dir.join("lib").join("index.js")));
+        let exports = json!({ "main": "lib", "exports": { ".": { "require": "./lib/index.js", "import": "./esm/main.mjs" } } });
+        assert_eq!(entry_point(&exports, dir), Some(dir.join("esm").join("main.mjs")));
+        assert_eq!(entry_point(&json!({}), dir), None);
+    }
+
+    #[test]
+    fn test_package_json_shorthands_expand() {
+        assert_eq!(license(&json!({ "licenses": [{ "type": "MIT" }, { "type": "Apache-2.0" }] })).as_deref(), Some("(MIT OR Apache-2.0)"));
+        assert_eq!(license(&json!({ "license": { "type": "ISC" } })).as_deref(), Some("ISC"));
+        assert_eq!(authors(&json!({ "author": { "name": "Ada" }, "contributors": ["Ada", "Grace <g@example.com>"] })), ["Ada", "Grace <g@example.com>"]);
+        assert_eq!(repository(&json!({ "repository": "acme/chunk" })).as_deref(), Some("https://github.com/acme/chunk"));
+        assert_eq!(repository(&json!({ "repository": "gitlab:acme/chunk" })).as_deref(), Some("https://gitlab.com/acme/chunk"));
+        assert_eq!(repository(&json!({ "repository": { "url": "git://github.com/acme/chunk.git" } })).as_deref(), Some("https://github.com/acme/chunk"));
+        assert_eq!(keywords(&json!({ "keywords": ["a", "b", "c", "d", "e", "f", "node.js", "extremely-long-keyword-here"] })).len(), 5);
+    }
+}
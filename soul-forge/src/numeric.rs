@@ -0,0 +1,284 @@
+// 🔢 Numeric Policy - JS Numbers as Floats or Inferred Integers
+use crate::body::{each_expr, each_stmt_expr};
+use crate::{JsExpr, JsFunction, JsPattern, JsStmt, JsType};
+use std::collections::{HashMap, HashSet};
+
+/// The largest integer an `f64` holds exactly, as `Number.MAX_SAFE_INTEGER`
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+/// How a JS `number` is typed in the generated code
+///
+/// JS has one number type, a 64-bit float. Keeping it an `f64` everywhere
+/// behaves as the source did; inferring integers reads like Rust, at the
+/// cost of what JS does past `i64` or when dividing by zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumericPolicy {
+    /// Every `number` is an `f64`, as JS holds it
+    #[default]
+    JsFloat64,
+    /// Locals that only ever hold integers, like loop counters, are `i64`s,
+    /// compared, stepped and indexed with as integers; signatures keep `f64`
+    IdiomaticInt,
+}
+
+impl std::str::FromStr for NumericPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "js-float64" => Ok(Self::JsFloat64),
+            "idiomatic-int" => Ok(Self::IdiomaticInt),
+            other => Err(format!("unknown numeric policy '{}' (expected js-float64 or idiomatic-int)", other)),
+        }
+    }
+}
+
+/// What integer arithmetic does past `i64`, where JS would have gone on as
+/// a float
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Panic, release builds included: plain operators, with
+    /// `overflow-checks` on in the manifest's release profile
+    #[default]
+    Checked,
+    /// Wrap around, through `wrapping_*`
+    Wrapping,
+    /// Stop at the bounds, through `saturating_*`
+    Saturating,
+}
+
+impl Overflow {
+    /// Whether `op` is written as an operator rather than a method call
+    pub(crate) fn is_infix(self, op: &str) -> bool {
+        self == Self::Checked || !matches!(op, "+" | "-" | "*")
+    }
+
+    /// `left op right`, through the method this behavior needs for `+`, `-`
+    /// and `*`
+    pub(crate) fn arithmetic(self, op: &str, left: &str, right: &str) -> String {
+        let method = match op {
+            "+" => "add",
+            "-" => "sub",
+            "*" => "mul",
+            op => return format!("{} {} {}", left, op, right),
+        };
+        match self {
+            Self::Checked => format!("{} {} {}", left, op, right),
+            Self::Wrapping => format!("{}.wrapping_{}({})", left, method, right),
+            Self::Saturating => format!("{}.saturating_{}({})", left, method, right),
+        }
+    }
+
+    /// `place op= amount`, for an operator `arithmetic` writes
+    pub(crate) fn step(self, place: &str, op: &str, amount: &str) -> String {
+        match self.is_infix(op) {
+            true => format!("{} {}= {}", place, op, amount),
+            false => format!("{} = {}", place, self.arithmetic(op, place, amount)),
+        }
+    }
+
+    /// Whether the crate's release profile turns `overflow-checks` on
+    pub(crate) fn checks_release(self) -> bool {
+        self == Self::Checked
+    }
+}
+
+impl std::str::FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checked" => Ok(Self::Checked),
+            "wrapping" => Ok(Self::Wrapping),
+            "saturating" => Ok(Self::Saturating),
+            other => Err(format!("unknown overflow behavior '{}' (expected checked, wrapping or saturating)", other)),
+        }
+    }
+}
+
+/// Locals of `function` that only ever hold integers
+///
+/// Each is declared once, with `let` in the function's own body, to an
+/// integral value, and is only stepped or reassigned by integral values.
+/// Names bound anywhere else too, a parameter or a closure's local, are
+/// left floats.
+pub(crate) fn integer_locals(function: &JsFunction) -> HashSet<String> {
+    let mut bindings: HashMap<String, usize> = HashMap::new();
+    count_bindings(function, &mut bindings);
+
+    let mut inits: HashMap<String, JsExpr> = HashMap::new();
+    each_stmt(&function.body, &mut |stmt| {
+        if let JsStmt::Let { pattern: JsPattern::Ident(name), ty: JsType::Number | JsType::Unknown, init: Some(init) } = stmt {
+            inits.insert(name.clone(), init.clone());
+        }
+    });
+    inits.retain(|name, _| bindings.get(name) == Some(&1));
+
+    // What each is reassigned to, by operator; destructuring leaves no value
+    let mut writes: Vec<(String, &'static str, Option<JsExpr>)> = vec![];
+    let mut visit = |expr: &JsExpr| {
+        if let JsExpr::Assign(op, target, value) = expr {
+            match &**target {
+                JsExpr::Ident(name) => writes.push((name.clone(), op, Some((**value).clone()))),
+                JsExpr::Member(..) | JsExpr::Index(..) => {}
+                pattern => each_expr(pattern, &mut |expr| {
+                    if let JsExpr::Ident(name) = expr {
+                        writes.push((name.clone(), op, None));
+                    }
+                    true
+                }),
+            }
+        }
+        true
+    };
+    function.body.iter().for_each(|stmt| each_stmt_expr(stmt, &mut visit));
+
+    // Every one holds integers until shown otherwise, so locals computed
+    // from each other stay integers together
+    let mut integers: HashSet<String> = inits.keys().cloned().collect();
+    loop {
+        let holding: HashSet<String> = integers.iter()
+            .filter(|name| is_integral(&inits[*name], &integers))
+            .filter(|name| writes.iter().filter(|(written, ..)| written == *name).all(|(_, op, value)| {
+                let integral_op = matches!(*op, "=" | "+=" | "-=" | "*=" | "%=" | "&=" | "|=" | "^=" | "<<=" | ">>=");
+                integral_op && value.as_ref().is_some_and(|value| is_integral(value, &integers))
+            }))
+            .cloned()
+            .collect();
+        if holding.len() == integers.len() {
+            return holding;
+        }
+        integers = holding;
+    }
+}
+
+/// Whether `expr` is always an integer, given the locals that hold them
+pub(crate) fn is_integral(expr: &JsExpr, integers: &HashSet<String>) -> bool {
+    match expr {
+        JsExpr::Number(n) => n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER,
+        JsExpr::Ident(name) => integers.contains(name),
+        JsExpr::Member(_, property) => property == "length",
+        JsExpr::Update { target, .. } => matches!(&**target, JsExpr::Ident(name) if integers.contains(name)),
+        JsExpr::Unary("-", arg) => is_integral(arg, integers),
+        JsExpr::Binary("+" | "-" | "*" | "%" | "&" | "|" | "^" | "<<" | ">>", left, right) => {
+            is_integral(left, integers) && is_integral(right, integers)
+        }
+        JsExpr::Conditional(_, then, otherwise) => is_integral(then, integers) && is_integral(otherwise, integers),
+        JsExpr::Call(callee, args) => args.len() == 1 && matches!(
+            &**callee,
+            JsExpr::Member(object, method) if matches!(&**object, JsExpr::Ident(math) if math == "Math")
+                && matches!(method.as_str(), "floor" | "ceil" | "round" | "trunc")
+        ),
+        _ => false,
+    }
+}
+
+/// Whether `expr` reads any of `integers`
+pub(crate) fn mentions(expr: &JsExpr, integers: &HashSet<String>) -> bool {
+    let mut found = false;
+    each_expr(expr, &mut |expr| {
+        found |= matches!(expr, JsExpr::Ident(name) if integers.contains(name));
+        !found
+    });
+    found
+}
+
+/// How many times each name is bound in `function`, closures and nested
+/// functions included
+fn count_bindings(function: &JsFunction, bindings: &mut HashMap<String, usize>) {
+    let mut bind = |name: &str| *bindings.entry(name.to_string()).or_default() += 1;
+    for param in &function.params {
+        bind(&param.name);
+    }
+    let mut nested = vec![];
+    each_stmt(&function.body, &mut |stmt| match stmt {
+        JsStmt::Let { pattern, .. } | JsStmt::ForOf { pattern, .. } | JsStmt::ForIn { pattern, .. } => pattern_names(pattern, &mut bind),
+        JsStmt::Try { param: Some(pattern), .. } => pattern_names(pattern, &mut bind),
+        JsStmt::Function(function) => {
+            bind(&function.name);
+            nested.push(function.clone());
+        }
+        _ => {}
+    });
+    let mut visit = |expr: &JsExpr| {
+        if let JsExpr::Function(function) = expr {
+            nested.push((**function).clone());
+        }
+        true
+    };
+    function.body.iter().for_each(|stmt| each_stmt_expr(stmt, &mut visit));
+    for function in nested {
+        count_bindings(&function, bindings);
+    }
+}
+
+fn pattern_names(pattern: &JsPattern, bind: &mut dyn FnMut(&str)) {
+    match pattern {
+        JsPattern::Ident(name) => bind(name),
+        JsPattern::Array { elements, rest } => {
+            elements.iter().flatten().chain(rest.as_deref()).for_each(|element| pattern_names(element, bind));
+        }
+        JsPattern::Object { properties, rest } => {
+            properties.iter().map(|(_, pattern)| pattern).chain(rest.as_deref()).for_each(|property| pattern_names(property, bind));
+        }
+        JsPattern::Default(inner, _) => pattern_names(inner, bind),
+    }
+}
+
+/// Every statement in `body`, nested blocks included but not the bodies of
+/// functions declared in it
+fn each_stmt(body: &[JsStmt], visit: &mut dyn FnMut(&JsStmt)) {
+    for stmt in body {
+        visit(stmt);
+        match stmt {
+            JsStmt::If { then, otherwise, .. } => {
+                each_stmt(then, visit);
+                each_stmt(otherwise, visit);
+            }
+            JsStmt::For { init, body, .. } => {
+                each_stmt(init, visit);
+                each_stmt(body, visit);
+            }
+            JsStmt::While { body, .. } | JsStmt::DoWhile { body, .. } | JsStmt::ForOf { body, .. } | JsStmt::ForIn { body, .. } | JsStmt::Block(body) => {
+                each_stmt(body, visit)
+            }
+            JsStmt::Labeled(_, body) => each_stmt(std::slice::from_ref(body), visit),
+            JsStmt::Switch { cases, .. } => cases.iter().for_each(|case| each_stmt(&case.body, visit)),
+            JsStmt::Try { block, handler, finalizer, .. } => {
+                each_stmt(block, visit);
+                each_stmt(handler.as_deref().unwrap_or_default(), visit);
+                each_stmt(finalizer, visit);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_source;
+    use crate::SourceKind;
+
+    fn integers_of(code: &str) -> Vec<String> {
+        let ast = parse_source(code, SourceKind::TypeScript).unwrap();
+        let mut integers: Vec<String> = integer_locals(&ast.functions[0]).into_iter().collect();
+        integers.sort();
+        integers
+    }
+
+    #[test]
+    fn test_only_locals_holding_integers_are_integers() {
+        let integers = integers_of(
+            "function scan(xs: number[], step: number): number {\n\
+               let i = 0; let last = xs.length - 1; let mid = Math.floor(last / 2);\n\
+               let total = 0; let half = 4; let count = 0;\n\
+               while (i < last) { total += xs[i]; i += 2; count++; }\n\
+               half = half / 2;\n\
+               const f = (count: number) => count * 2;\n\
+               return total + mid + half + f(count) + step;\n\
+             }",
+        );
+        assert_eq!(integers, ["i", "last", "mid"]);
+    }
+}
@@ -0,0 +1,202 @@
+// 🔗 Soul Pairs - Matching Sources to Their Transmutations for Verification
+use crate::{Diagnostic, TransmutationError};
+use protein_hash::SoulExtractor;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Extensions of the JS and TypeScript sources a pattern picks up
+pub const JS_EXTENSIONS: &[&str] = &["js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts"];
+
+/// Extensions of the Rust files a pattern picks up
+pub const RUST_EXTENSIONS: &[&str] = &["rs"];
+
+/// Directories a walk never enters: dependencies and build output
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "pkg"];
+
+/// Files named for their directory rather than themselves
+const ENTRY_STEMS: &[&str] = &["index", "lib", "mod", "main"];
+
+/// Which JS file was transmuted into which Rust file, and the files on
+/// either side nothing matched
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pairing {
+    pub pairs: Vec<(PathBuf, PathBuf)>,
+    pub unmatched_js: Vec<PathBuf>,
+    pub unmatched_rust: Vec<PathBuf>,
+}
+
+/// How closely one pair's souls resonate
+#[derive(Debug, Clone, Serialize)]
+pub struct PairResonance {
+    pub js: PathBuf,
+    pub rust: PathBuf,
+    pub resonance: f64,
+    pub souls_match: bool,
+}
+
+/// The files with one of `extensions` that `pattern` names: a file, every
+/// such file under a directory, or a glob's matches
+pub fn files(pattern: &str, extensions: &[&str]) -> Result<Vec<PathBuf>, TransmutationError> {
+    let path = Path::new(pattern);
+    let mut files = vec![];
+    if path.is_file() {
+        files.push(path.to_path_buf());
+    } else if path.is_dir() {
+        walk(path, extensions, &mut files)?;
+    } else {
+        let matches = glob::glob(pattern).map_err(|e| TransmutationError::PairingError(format!("{}: {}", pattern, e).into()))?;
+        for entry in matches {
+            let entry = entry.map_err(|e| TransmutationError::PairingError(e.to_string().into()))?;
+            if entry.is_file() && has_extension(&entry, extensions) {
+                files.push(entry);
+            }
+        }
+    }
+    if files.is_empty() {
+        return Err(TransmutationError::PairingError(format!("no {} files match {}", extensions.join("/"), pattern).into()));
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn walk(dir: &Path, extensions: &[&str], files: &mut Vec<PathBuf>) -> Result<(), TransmutationError> {
+    for entry in std::fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        if path.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name) {
+                walk(&path, extensions, files)?;
+            }
+        } else if has_extension(&path, extensions) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| extensions.contains(&ext))
+}
+
+fn io_error(error: std::io::Error) -> TransmutationError {
+    TransmutationError::PairingError(error.to_string().into())
+}
+
+/// The name a file is matched on: its stem, or for an entry module like
+/// `index.js` or `src/lib.rs` its package's directory, in snake case as
+/// Rust would name it and without the `-soul` of transmuted crates
+fn key(path: &Path) -> String {
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+    let name = match ENTRY_STEMS.contains(&stem) {
+        true => path.ancestors()
+            .skip(1)
+            .filter_map(|dir| dir.file_name().and_then(|name| name.to_str()))
+            .find(|name| !matches!(*name, "src" | "lib" | "dist"))
+            .unwrap_or(stem),
+        false => stem,
+    };
+    let mut snake = String::new();
+    let mut previous = '_';
+    for c in name.chars() {
+        if c.is_uppercase() && (previous.is_lowercase() || previous.is_ascii_digit()) {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+        previous = c;
+    }
+    let snake = snake.replace(['-', '.'], "_");
+    snake.strip_suffix("_soul").unwrap_or(&snake).to_string()
+}
+
+/// Pair the JS and Rust files with the same name; when several share one,
+/// they pair in path order and the rest go unmatched
+pub fn by_name(js: &[PathBuf], rust: &[PathBuf]) -> Pairing {
+    let mut rust_by_key: BTreeMap<String, Vec<&PathBuf>> = BTreeMap::new();
+    for path in rust {
+        rust_by_key.entry(key(path)).or_default().push(path);
+    }
+    for paths in rust_by_key.values_mut() {
+        paths.reverse();
+    }
+
+    let mut pairing = Pairing::default();
+    for path in js {
+        match rust_by_key.get_mut(&key(path)).and_then(Vec::pop) {
+            Some(rust) => pairing.pairs.push((path.clone(), rust.clone())),
+            None => pairing.unmatched_js.push(path.clone()),
+        }
+    }
+    pairing.unmatched_rust = rust_by_key.into_values().flatten().cloned().collect();
+    pairing.unmatched_rust.sort();
+    pairing
+}
+
+/// The pairs a JSON manifest lists, as an object from each JS path to its
+/// Rust path, both relative to the manifest
+pub fn by_manifest(manifest: &Path) -> Result<Pairing, TransmutationError> {
+    let content = std::fs::read_to_string(manifest).map_err(io_error)?;
+    let entries: BTreeMap<String, String> = serde_json::from_str(&content)
+        .map_err(|e| TransmutationError::PairingError(Diagnostic::error(e.to_string()).with_file(manifest.display().to_string())))?;
+    let base = manifest.parent().unwrap_or(Path::new(""));
+    let pairs = entries.into_iter().map(|(js, rust)| (base.join(js), base.join(rust))).collect();
+    Ok(Pairing { pairs, ..Pairing::default() })
+}
+
+/// The resonance of each pair's souls, in order
+pub async fn measure(pairs: &[(PathBuf, PathBuf)]) -> Result<Vec<PairResonance>, TransmutationError> {
+    let extractor = SoulExtractor::new();
+    let mut measured = vec![];
+    for (js, rust) in pairs {
+        let read = |path: &Path| std::fs::read_to_string(path).map_err(|e| TransmutationError::PairingError(Diagnostic::error(e.to_string()).with_file(path.display().to_string())));
+        let js_soul = extractor.extract_soul_js(&read(js)?).await;
+        let rust_soul = extractor.extract_soul_rust(&read(rust)?).await;
+        measured.push(PairResonance {
+            js: js.clone(),
+            rust: rust.clone(),
+            resonance: protein_hash::measure_resonance(&js_soul, &rust_soul),
+            souls_match: protein_hash::souls_match(&js_soul, &rust_soul),
+        });
+    }
+    Ok(measured)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_files_pair_by_package_name() {
+        assert_eq!(key(Path::new("npm/left-pad/index.js")), "left_pad");
+        assert_eq!(key(Path::new("crates/left-pad-soul/src/lib.rs")), "left_pad");
+        assert_eq!(key(Path::new("utils/deepMerge.ts")), "deep_merge");
+
+        let js = paths(&["a/chunk.ts", "b/chunk.js", "left-pad/index.js", "orphan.js"]);
+        let rust = paths(&["chunk.rs", "left-pad-soul/src/lib.rs", "extra.rs"]);
+        let pairing = by_name(&js, &rust);
+        assert_eq!(pairing.pairs, [
+            (PathBuf::from("a/chunk.ts"), PathBuf::from("chunk.rs")),
+            (PathBuf::from("left-pad/index.js"), PathBuf::from("left-pad-soul/src/lib.rs")),
+        ]);
+        assert_eq!(pairing.unmatched_js, paths(&["b/chunk.js", "orphan.js"]));
+        assert_eq!(pairing.unmatched_rust, paths(&["extra.rs"]));
+    }
+
+    #[test]
+    fn test_manifest_paths_are_relative_to_it() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path();
+        std::fs::create_dir_all(dir.join("js")).unwrap();
+        std::fs::write(dir.join("js").join("sum.js"), "").unwrap();
+        std::fs::write(dir.join("pairs.json"), r#"{ "js/sum.js": "out/src/lib.rs" }"#).unwrap();
+        let pairing = by_manifest(&dir.join("pairs.json")).unwrap();
+        assert_eq!(pairing.pairs, [(dir.join("js/sum.js"), dir.join("out/src/lib.rs"))]);
+
+        assert_eq!(files(dir.to_str().unwrap(), JS_EXTENSIONS).unwrap(), [dir.join("js").join("sum.js")]);
+        assert!(files(&format!("{}/*.rs", dir.display()), RUST_EXTENSIONS).is_err());
+    }
+}
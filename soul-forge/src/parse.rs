@@ -0,0 +1,1040 @@
+// 🔬 Source Parsing - Reading JS/TS Souls into the Forge's AST
+use crate::jsdoc;
+use crate::{
+    ImportedName, JsAst, JsCase, JsClass, JsExpr, JsFunction, JsImport, JsMethod, JsParam, JsPattern, JsProp, JsProperty,
+    Diagnostic, JsReexport, JsStmt, JsType, MethodKind, Span, TransmutationError,
+};
+use oxc_allocator::Allocator;
+use oxc_ast::ast::*;
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Language of a transmutation input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SourceKind {
+    #[default]
+    JavaScript,
+    Jsx,
+    TypeScript,
+    Tsx,
+}
+
+impl SourceKind {
+    /// Kind by file extension; anything unrecognised is read as JavaScript
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("ts" | "mts" | "cts") => Self::TypeScript,
+            Some("tsx") => Self::Tsx,
+            Some("jsx") => Self::Jsx,
+            _ => Self::JavaScript,
+        }
+    }
+
+    pub fn is_typescript(self) -> bool {
+        matches!(self, Self::TypeScript | Self::Tsx)
+    }
+
+    fn source_type(self) -> SourceType {
+        match self {
+            Self::JavaScript => SourceType::unambiguous(),
+            Self::Jsx => SourceType::jsx(),
+            Self::TypeScript => SourceType::ts(),
+            Self::Tsx => SourceType::tsx(),
+        }
+    }
+}
+
+/// Parse a module into the functions, classes and exports the forge transmutes
+///
+/// TypeScript annotations become `JsType`s; plain JS parameters fall back to
+/// the type of their default value, or `JsType::Unknown`. Interfaces and
+/// object type aliases are read as property-only classes.
+pub(crate) fn parse_source(code: &str, kind: SourceKind) -> Result<JsAst, TransmutationError> {
+    let allocator = Allocator::default();
+    let parsed = Parser::new(&allocator, code, kind.source_type()).parse();
+    if let Some(error) = parsed.errors.first() {
+        let mut diagnostic = Diagnostic::error(error.message.to_string());
+        if let Some(label) = error.labels.iter().flatten().next() {
+            diagnostic = diagnostic.with_span(Span::of(code, label.offset(), label.offset() + label.len()));
+        }
+        if let Some(help) = &error.help {
+            diagnostic = diagnostic.with_suggestion(help.to_string());
+        }
+        return Err(TransmutationError::ParseError(diagnostic));
+    }
+
+    let mut ast = JsAst {
+        functions: vec![],
+        classes: vec![],
+        exports: vec![],
+        imports: vec![],
+        reexports: vec![],
+        default_export: None,
+        commonjs: false,
+        docs: HashMap::new(),
+    };
+    for statement in &parsed.program.body {
+        collect_statement(&mut ast, statement);
+    }
+
+    // JSDoc comments go with the functions, classes and methods they precede
+    let comments: Vec<(u32, u32)> = parsed.program.comments.iter()
+        .filter(|comment| comment.is_jsdoc())
+        .map(|comment| (comment.span.start, comment.span.end))
+        .collect();
+    let origins: Vec<u32> = ast.functions.iter()
+        .chain(ast.classes.iter().flat_map(|class| class.methods.iter().map(|method| &method.function)))
+        .filter_map(|function| function.origin)
+        .chain(ast.classes.iter().filter_map(|class| class.origin))
+        .collect();
+    ast.docs = jsdoc::attach(code, &comments, &origins);
+    Ok(ast)
+}
+
+/// A lone expression, like the calls in a JSDoc example
+pub(crate) fn parse_expression(code: &str) -> Option<JsExpr> {
+    let allocator = Allocator::default();
+    let expression = Parser::new(&allocator, code, SourceType::mjs()).parse_expression().ok()?;
+    Some(read_expr(&expression))
+}
+
+fn collect_statement(ast: &mut JsAst, statement: &Statement) {
+    match statement {
+        Statement::ImportDeclaration(import) if !import.import_kind.is_type() => {
+            for specifier in import.specifiers.iter().flatten() {
+                let (name, local) = match specifier {
+                    ImportDeclarationSpecifier::ImportSpecifier(specifier) if specifier.import_kind.is_type() => continue,
+                    ImportDeclarationSpecifier::ImportSpecifier(specifier) => {
+                        (ImportedName::Named(specifier.imported.name().to_string()), &specifier.local)
+                    }
+                    ImportDeclarationSpecifier::ImportDefaultSpecifier(specifier) => (ImportedName::Default, &specifier.local),
+                    ImportDeclarationSpecifier::ImportNamespaceSpecifier(specifier) => (ImportedName::Namespace, &specifier.local),
+                };
+                ast.imports.push(JsImport { source: import.source.value.to_string(), name, local: local.name.to_string() });
+            }
+        }
+        Statement::ExportNamedDeclaration(export) => {
+            if let Some(declaration) = &export.declaration {
+                let (functions, classes) = (ast.functions.len(), ast.classes.len());
+                collect_declaration(ast, declaration);
+
+                let declared = ast.functions[functions..].iter().map(|f| f.name.clone())
+                    .chain(ast.classes[classes..].iter().map(|c| c.name.clone()))
+                    .collect::<Vec<_>>();
+                ast.exports.extend(declared);
+            }
+            ast.exports.extend(export.specifiers.iter().map(|spec| spec.exported.to_string()));
+
+            for spec in export.specifiers.iter().filter(|spec| !export.export_kind.is_type() && !spec.export_kind.is_type()) {
+                let (local, exported) = (spec.local.name().to_string(), spec.exported.name().to_string());
+                match &export.source {
+                    Some(source) => ast.reexports.push(JsReexport {
+                        source: Some(source.value.to_string()),
+                        name: if local == "default" { ImportedName::Default } else { ImportedName::Named(local) },
+                        exported: Some(exported),
+                    }),
+                    None if exported == "default" => ast.default_export = Some(local),
+                    None if local != exported => ast.reexports.push(JsReexport {
+                        source: None,
+                        name: ImportedName::Named(local),
+                        exported: Some(exported),
+                    }),
+                    None => {}
+                }
+            }
+        }
+        Statement::ExportAllDeclaration(export) if !export.export_kind.is_type() => {
+            let exported = export.exported.as_ref().map(|name| name.name().to_string());
+            ast.exports.extend(exported.clone());
+            ast.reexports.push(JsReexport { source: Some(export.source.value.to_string()), name: ImportedName::Namespace, exported });
+        }
+        Statement::ExportDefaultDeclaration(export) => match &export.declaration {
+            ExportDefaultDeclarationKind::FunctionDeclaration(function) => {
+                let name = function.id.as_ref().map_or("default", |id| id.name.as_str());
+                ast.functions.push(read_function(name, function));
+                ast.exports.push(name.to_string());
+                ast.default_export = Some(name.to_string());
+            }
+            ExportDefaultDeclarationKind::ClassDeclaration(class) => {
+                let name = class.id.as_ref().map_or("default", |id| id.name.as_str());
+                ast.classes.push(read_class(name, class));
+                ast.exports.push(name.to_string());
+                ast.default_export = Some(name.to_string());
+            }
+            declaration => {
+                if let Some(name) = declaration.as_expression().and_then(|value| collect_export(ast, "default", value)) {
+                    ast.default_export = Some(name);
+                }
+            }
+        },
+        Statement::ExpressionStatement(statement) => {
+            if let Expression::AssignmentExpression(assignment) = statement.expression.without_parentheses() {
+                collect_commonjs_export(ast, &read_assignment_target(&assignment.left), &assignment.right);
+            }
+        }
+        _ => {
+            if let Some(declaration) = statement.as_declaration() {
+                collect_declaration(ast, declaration);
+            }
+        }
+    }
+}
+
+/// Read `value`, exported as `name`, into the module: a function or class
+/// expression becomes one named `name` unless it names itself, and an
+/// identifier exports what it names. Returns the name exported, if any.
+fn collect_export(ast: &mut JsAst, name: &str, value: &Expression) -> Option<String> {
+    let declared = match value.without_parentheses() {
+        Expression::Identifier(id) => id.name.to_string(),
+        Expression::FunctionExpression(function) => {
+            let name = function.id.as_ref().map_or(name, |id| id.name.as_str());
+            ast.functions.push(read_function(name, function));
+            name.to_string()
+        }
+        Expression::ArrowFunctionExpression(arrow) => {
+            ast.functions.push(read_arrow(name, arrow));
+            name.to_string()
+        }
+        Expression::ClassExpression(class) => {
+            let name = class.id.as_ref().map_or(name, |id| id.name.as_str());
+            ast.classes.push(read_class(name, class));
+            name.to_string()
+        }
+        _ => return None,
+    };
+    ast.exports.push(declared.clone());
+    Some(declared)
+}
+
+/// Read an assignment to `module.exports` or one of its properties, as
+/// CommonJS modules export
+fn collect_commonjs_export(ast: &mut JsAst, target: &JsExpr, value: &Expression) {
+    let is_exports = |object: &JsExpr| match object {
+        JsExpr::Ident(name) => name == "exports",
+        JsExpr::Member(module, exports) => matches!(&**module, JsExpr::Ident(module) if module == "module") && exports == "exports",
+        _ => false,
+    };
+    match target {
+        JsExpr::Member(module, exports) if matches!(&**module, JsExpr::Ident(module) if module == "module") && exports == "exports" => {
+            ast.commonjs = true;
+            match value.without_parentheses() {
+                Expression::ObjectExpression(object) => {
+                    for property in &object.properties {
+                        match property {
+                            ObjectPropertyKind::ObjectProperty(property) => {
+                                if let Some(key) = property.key.name() {
+                                    collect_commonjs_property(ast, &key, &property.value);
+                                }
+                            }
+                            // `...require('./x')` exports all of it
+                            ObjectPropertyKind::SpreadProperty(spread) => {
+                                if let Some((source, None)) = required(&spread.argument) {
+                                    ast.reexports.push(JsReexport { source: Some(source), name: ImportedName::Namespace, exported: None });
+                                }
+                            }
+                        }
+                    }
+                }
+                value => match required(value) {
+                    Some((source, member)) => ast.reexports.push(JsReexport {
+                        source: Some(source),
+                        name: member.map_or(ImportedName::Namespace, ImportedName::Named),
+                        exported: None,
+                    }),
+                    None => ast.default_export = collect_export(ast, "default", value),
+                },
+            }
+        }
+        JsExpr::Member(object, name) if is_exports(object) => {
+            ast.commonjs = true;
+            collect_commonjs_property(ast, name, value);
+        }
+        _ => {}
+    }
+}
+
+/// Read `module.exports.name = value`, or `name: value` in an object
+/// assigned to `module.exports`
+fn collect_commonjs_property(ast: &mut JsAst, name: &str, value: &Expression) {
+    if let Some((source, member)) = required(value) {
+        ast.reexports.push(JsReexport {
+            source: Some(source),
+            name: member.map_or(ImportedName::Namespace, ImportedName::Named),
+            exported: Some(name.to_string()),
+        });
+        ast.exports.push(name.to_string());
+        return;
+    }
+    match value.without_parentheses() {
+        Expression::Identifier(id) if id.name != name => {
+            ast.reexports.push(JsReexport { source: None, name: ImportedName::Named(id.name.to_string()), exported: Some(name.to_string()) });
+            ast.exports.push(name.to_string());
+        }
+        // A function expression exported by property keeps the property's name
+        Expression::FunctionExpression(function) => {
+            ast.functions.push(read_function(name, function));
+            ast.exports.push(name.to_string());
+        }
+        value => {
+            collect_export(ast, name, value);
+        }
+    }
+}
+
+/// The module `require(..)` names in `value`, and the property taken from
+/// it, as in `require('./x').a`
+fn required(value: &Expression) -> Option<(String, Option<String>)> {
+    match value.without_parentheses() {
+        Expression::CallExpression(call) => call.common_js_require().map(|source| (source.value.to_string(), None)),
+        Expression::StaticMemberExpression(member) => match member.object.without_parentheses() {
+            Expression::CallExpression(call) => call.common_js_require()
+                .map(|source| (source.value.to_string(), Some(member.property.name.to_string()))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Read `const x = require('./x')` or `const { a, b: c } = require('./x')`
+fn collect_require(ast: &mut JsAst, pattern: &BindingPattern, source: String, member: Option<String>) {
+    ast.commonjs = true;
+    match pattern {
+        BindingPattern::BindingIdentifier(id) => ast.imports.push(JsImport {
+            source,
+            name: member.map_or(ImportedName::Namespace, ImportedName::Named),
+            local: id.name.to_string(),
+        }),
+        BindingPattern::ObjectPattern(object) if member.is_none() => {
+            for property in &object.properties {
+                if let (Some(key), Some(local)) = (property.key.name(), property.value.get_identifier_name()) {
+                    ast.imports.push(JsImport { source: source.clone(), name: ImportedName::Named(key.to_string()), local: local.to_string() });
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_declaration(ast: &mut JsAst, declaration: &Declaration) {
+    match declaration {
+        Declaration::FunctionDeclaration(function) => {
+            if let Some(id) = &function.id {
+                ast.functions.push(read_function(&id.name, function));
+            }
+        }
+        Declaration::ClassDeclaration(class) => {
+            if let Some(id) = &class.id {
+                ast.classes.push(read_class(&id.name, class));
+            }
+        }
+        Declaration::VariableDeclaration(variables) => {
+            for declarator in &variables.declarations {
+                if let Some((source, member)) = declarator.init.as_ref().and_then(required) {
+                    collect_require(ast, &declarator.id, source, member);
+                    continue;
+                }
+                let (Some(name), Some(init)) = (declarator.id.get_identifier_name(), &declarator.init) else {
+                    continue;
+                };
+                match init.without_parentheses() {
+                    Expression::ArrowFunctionExpression(arrow) => ast.functions.push(read_arrow(&name, arrow)),
+                    Expression::FunctionExpression(function) => ast.functions.push(read_function(&name, function)),
+                    Expression::ClassExpression(class) => ast.classes.push(read_class(&name, class)),
+                    _ => {}
+                }
+            }
+        }
+        Declaration::TSInterfaceDeclaration(interface) => ast.classes.push(JsClass {
+            name: interface.id.name.to_string(),
+            extends: None,
+            properties: read_signatures(&interface.body.body),
+            methods: vec![],
+            origin: Some(interface.span.start),
+        }),
+        Declaration::TSTypeAliasDeclaration(alias) => {
+            if let TSType::TSTypeLiteral(literal) = &alias.type_annotation {
+                ast.classes.push(JsClass {
+                    name: alias.id.name.to_string(),
+                    extends: None,
+                    properties: read_signatures(&literal.members),
+                    methods: vec![],
+                    origin: Some(alias.span.start),
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+fn read_function(name: &str, function: &Function) -> JsFunction {
+    JsFunction {
+        name: name.to_string(),
+        params: read_params(&function.params),
+        return_type: annotation(function.return_type.as_deref()),
+        type_params: type_params(function.type_parameters.as_deref()),
+        is_async: function.r#async,
+        is_generator: function.generator,
+        body: destructured_params(&function.params).into_iter()
+            .chain(function.body.as_ref().map_or_else(Vec::new, |body| read_body(&body.statements)))
+            .collect(),
+        origin: Some(function.span.start),
+    }
+}
+
+fn read_arrow(name: &str, arrow: &ArrowFunctionExpression) -> JsFunction {
+    let body = match (arrow.expression, arrow.body.statements.first()) {
+        // `x => x * 2` is parsed as a body holding one expression statement
+        (true, Some(Statement::ExpressionStatement(statement))) => vec![JsStmt::Return(Some(read_expr(&statement.expression)))],
+        _ => read_body(&arrow.body.statements),
+    };
+    let body = destructured_params(&arrow.params).into_iter().chain(body).collect();
+    JsFunction {
+        name: name.to_string(),
+        params: read_params(&arrow.params),
+        return_type: annotation(arrow.return_type.as_deref()),
+        type_params: type_params(arrow.type_parameters.as_deref()),
+        is_async: arrow.r#async,
+        is_generator: false,
+        body,
+        origin: Some(arrow.span.start),
+    }
+}
+
+fn read_params(params: &FormalParameters) -> Vec<JsParam> {
+    let mut read: Vec<JsParam> = params.items.iter().enumerate().map(|(i, param)| {
+        let ty = match (&param.type_annotation, &param.initializer) {
+            (Some(annotation), _) => ts_type(&annotation.type_annotation),
+            (None, Some(default)) => literal_type(default),
+            (None, None) => JsType::Unknown,
+        };
+        JsParam {
+            name: binding_name(&param.pattern, i),
+            ty: if param.optional { optional(ty) } else { ty },
+            rest: false,
+        }
+    }).collect();
+
+    if let Some(rest) = &params.rest {
+        let ty = rest.type_annotation.as_ref()
+            .map_or(JsType::Array(Box::new(JsType::Unknown)), |annotation| ts_type(&annotation.type_annotation));
+        read.push(JsParam { name: binding_name(&rest.rest.argument, read.len()), ty, rest: true });
+    }
+    read
+}
+
+/// A destructured parameter is passed whole, as the `argN` it is named, and
+/// taken apart by a `let` the body starts with
+fn destructured_params(params: &FormalParameters) -> Vec<JsStmt> {
+    let patterns = params.items.iter().map(|param| &param.pattern).chain(params.rest.as_ref().map(|rest| &rest.rest.argument));
+    patterns.enumerate()
+        .filter(|(_, pattern)| pattern.get_identifier_name().is_none())
+        .map(|(i, pattern)| JsStmt::Let {
+            pattern: read_pattern(pattern),
+            ty: JsType::Unknown,
+            init: Some(JsExpr::Ident(format!("arg{}", i))),
+        })
+        .collect()
+}
+
+/// Destructured parameters have no name of their own
+fn binding_name(pattern: &BindingPattern, position: usize) -> String {
+    pattern.get_identifier_name().map_or_else(|| format!("arg{}", position), |name| name.to_string())
+}
+
+fn read_class(name: &str, class: &Class) -> JsClass {
+    let mut properties = vec![];
+    let mut methods = vec![];
+
+    for element in &class.body.body {
+        match element {
+            ClassElement::PropertyDefinition(property) => {
+                let Some(key) = property.key.name() else {
+                    continue;
+                };
+                let ty = match (&property.type_annotation, &property.value) {
+                    (Some(annotation), _) => ts_type(&annotation.type_annotation),
+                    (None, Some(value)) => literal_type(value),
+                    (None, None) => JsType::Unknown,
+                };
+                properties.push(JsProperty {
+                    name: key.to_string(),
+                    ty: if property.optional { optional(ty) } else { ty },
+                    init: property.value.as_ref().map(read_expr),
+                    is_static: property.r#static,
+                });
+            }
+            ClassElement::MethodDefinition(method) => {
+                let Some(key) = method.key.name() else {
+                    continue;
+                };
+                if method.kind == MethodDefinitionKind::Constructor {
+                    // `constructor(private x: number)` declares a property too
+                    for param in method.value.params.items.iter().filter(|p| p.accessibility.is_some() || p.readonly) {
+                        let (Some(name), Some(annotation)) = (param.pattern.get_identifier_name(), &param.type_annotation) else {
+                            continue;
+                        };
+                        properties.push(JsProperty {
+                            name: name.to_string(),
+                            ty: ts_type(&annotation.type_annotation),
+                            init: None,
+                            is_static: false,
+                        });
+                    }
+
+                    let mut constructor = read_function("new", &method.value);
+                    constructor.origin = Some(method.span.start);
+                    constructor.return_type = JsType::Named("Self".to_string(), vec![]);
+                    // The parameter properties are assigned before the body runs
+                    let assigned = method.value.params.items.iter()
+                        .filter(|p| p.accessibility.is_some() || p.readonly)
+                        .filter_map(|p| p.pattern.get_identifier_name())
+                        .map(|name| JsStmt::Expr(JsExpr::Assign(
+                            "=",
+                            Box::new(JsExpr::Member(Box::new(JsExpr::This), name.to_string())),
+                            Box::new(JsExpr::Ident(name.to_string())),
+                        )));
+                    constructor.body.splice(super_call_end(&constructor.body)..super_call_end(&constructor.body), assigned.collect::<Vec<_>>());
+                    methods.push(JsMethod { kind: MethodKind::Constructor, function: constructor });
+                } else {
+                    let kind = match (method.kind, method.r#static) {
+                        (_, true) => MethodKind::Static,
+                        (MethodDefinitionKind::Get, _) => MethodKind::Getter,
+                        (MethodDefinitionKind::Set, _) => MethodKind::Setter,
+                        _ => MethodKind::Method,
+                    };
+                    // A method's function starts at its parameters, past the name
+                    let function = JsFunction { origin: Some(method.span.start), ..read_function(&key, &method.value) };
+                    methods.push(JsMethod { kind, function });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let extends = match &class.super_class {
+        Some(Expression::Identifier(base)) => Some(base.name.to_string()),
+        _ => None,
+    };
+    JsClass {
+        name: name.to_string(),
+        extends,
+        properties,
+        methods,
+        origin: Some(class.span.start),
+    }
+}
+
+/// Where a constructor body is past its `super(..)` call, if it has one
+fn super_call_end(body: &[JsStmt]) -> usize {
+    body.iter()
+        .position(|stmt| matches!(stmt, JsStmt::Expr(JsExpr::Call(callee, _)) if matches!(**callee, JsExpr::Super)))
+        .map_or(0, |position| position + 1)
+}
+
+fn read_signatures(signatures: &[TSSignature]) -> Vec<JsProperty> {
+    signatures.iter().filter_map(|signature| match signature {
+        TSSignature::TSPropertySignature(property) => {
+            let ty = annotation(property.type_annotation.as_deref());
+            Some(JsProperty {
+                name: property.key.name()?.to_string(),
+                ty: if property.optional { optional(ty) } else { ty },
+                init: None,
+                is_static: false,
+            })
+        }
+        _ => None,
+    }).collect()
+}
+
+fn type_params(params: Option<&TSTypeParameterDeclaration>) -> Vec<String> {
+    params.map_or_else(Vec::new, |params| params.params.iter().map(|param| param.name.name.to_string()).collect())
+}
+
+fn annotation(annotation: Option<&TSTypeAnnotation>) -> JsType {
+    annotation.map_or(JsType::Unknown, |annotation| ts_type(&annotation.type_annotation))
+}
+
+/// Map a TypeScript type onto the forge's type model
+fn ts_type(ty: &TSType) -> JsType {
+    match ty {
+        TSType::TSNumberKeyword(_) => JsType::Number,
+        TSType::TSBigIntKeyword(_) => JsType::BigInt,
+        TSType::TSStringKeyword(_) => JsType::String,
+        TSType::TSBooleanKeyword(_) => JsType::Boolean,
+        TSType::TSVoidKeyword(_) | TSType::TSUndefinedKeyword(_) | TSType::TSNeverKeyword(_) => JsType::Void,
+        TSType::TSNullKeyword(_) => JsType::Null,
+        TSType::TSObjectKeyword(_) => JsType::Object,
+        TSType::TSArrayType(array) => JsType::Array(Box::new(ts_type(&array.element_type))),
+        TSType::TSTupleType(tuple) => JsType::Tuple(tuple.element_types.iter().map(tuple_element).collect()),
+        TSType::TSParenthesizedType(inner) => ts_type(&inner.type_annotation),
+        TSType::TSTypeOperatorType(operator) if operator.operator == TSTypeOperatorOperator::Readonly => {
+            ts_type(&operator.type_annotation)
+        }
+        TSType::TSLiteralType(literal) => match &literal.literal {
+            TSLiteral::StringLiteral(_) | TSLiteral::TemplateLiteral(_) => JsType::String,
+            TSLiteral::NumericLiteral(_) | TSLiteral::UnaryExpression(_) => JsType::Number,
+            TSLiteral::BooleanLiteral(_) => JsType::Boolean,
+            TSLiteral::BigIntLiteral(_) => JsType::BigInt,
+        },
+        TSType::TSFunctionType(function) => JsType::Function(
+            read_params(&function.params).into_iter().map(|param| param.ty).collect(),
+            Box::new(ts_type(&function.return_type.type_annotation)),
+        ),
+        TSType::TSTypeLiteral(literal) => match literal.members.as_slice() {
+            // `{ [key: string]: T }`
+            [TSSignature::TSIndexSignature(index)] => JsType::Map(
+                Box::new(index.parameters.first().map_or(JsType::String, |key| ts_type(&key.type_annotation.type_annotation))),
+                Box::new(ts_type(&index.type_annotation.type_annotation)),
+            ),
+            _ => JsType::Object,
+        },
+        TSType::TSUnionType(union) => union_type(union.types.iter().map(ts_type).collect()),
+        TSType::TSTypeReference(reference) => type_reference(reference),
+        _ => JsType::Unknown,
+    }
+}
+
+fn tuple_element(element: &TSTupleElement) -> JsType {
+    match element {
+        TSTupleElement::TSOptionalType(inner) => optional(ts_type(&inner.type_annotation)),
+        TSTupleElement::TSRestType(inner) => ts_type(&inner.type_annotation),
+        TSTupleElement::TSNamedTupleMember(member) => tuple_element(&member.element_type),
+        other => other.as_ts_type().map_or(JsType::Unknown, ts_type),
+    }
+}
+
+/// Built-in generics map onto std types; anything else stays a named type
+fn type_reference(reference: &TSTypeReference) -> JsType {
+    let name = match &reference.type_name {
+        TSTypeName::IdentifierReference(id) => id.name.to_string(),
+        TSTypeName::QualifiedName(qualified) => qualified.right.name.to_string(),
+        TSTypeName::ThisExpression(_) => "Self".to_string(),
+    };
+    let args: Vec<JsType> = reference.type_arguments.as_ref()
+        .map_or_else(Vec::new, |args| args.params.iter().map(ts_type).collect());
+    let arg = |i: usize| Box::new(args.get(i).cloned().unwrap_or(JsType::Unknown));
+
+    match name.as_str() {
+        "Array" | "ReadonlyArray" => JsType::Array(arg(0)),
+        "Promise" | "PromiseLike" => JsType::Promise(arg(0)),
+        // What generators give, sync or async
+        "Generator" | "Iterator" | "IterableIterator" | "Iterable" | "AsyncGenerator" | "AsyncIterator" | "AsyncIterableIterator"
+        | "AsyncIterable" => JsType::Iterator(arg(0)),
+        "Set" | "ReadonlySet" => JsType::Set(arg(0)),
+        "Map" | "ReadonlyMap" | "Record" => JsType::Map(arg(0), arg(1)),
+        "Partial" | "Readonly" | "Required" => *arg(0),
+        "Number" => JsType::Number,
+        "String" => JsType::String,
+        "Boolean" => JsType::Boolean,
+        "Object" => JsType::Object,
+        _ => JsType::Named(name, args.clone()),
+    }
+}
+
+/// `T | null | undefined` is optional; unions of one kind collapse to it
+fn union_type(members: Vec<JsType>) -> JsType {
+    let nullable = members.iter().any(|ty| matches!(ty, JsType::Null | JsType::Void));
+    let mut kinds: Vec<JsType> = vec![];
+    for ty in members.into_iter().filter(|ty| !matches!(ty, JsType::Null | JsType::Void)) {
+        if !kinds.contains(&ty) {
+            kinds.push(ty);
+        }
+    }
+
+    let ty = match kinds.len() {
+        0 => return JsType::Null,
+        1 => kinds.remove(0),
+        _ => JsType::Unknown,
+    };
+    if nullable { optional(ty) } else { ty }
+}
+
+fn optional(ty: JsType) -> JsType {
+    match ty {
+        JsType::Optional(_) => ty,
+        ty => JsType::Optional(Box::new(ty)),
+    }
+}
+
+fn read_body(statements: &[Statement]) -> Vec<JsStmt> {
+    statements.iter().flat_map(read_statement).collect()
+}
+
+/// A statement, or the block it wraps, as a statement list
+fn read_block(statement: &Statement) -> Vec<JsStmt> {
+    match statement {
+        Statement::BlockStatement(block) => read_body(&block.body),
+        statement => read_statement(statement),
+    }
+}
+
+fn read_statement(statement: &Statement) -> Vec<JsStmt> {
+    let read = match statement {
+        Statement::BlockStatement(block) => JsStmt::Block(read_body(&block.body)),
+        Statement::ExpressionStatement(statement) => JsStmt::Expr(read_expr(&statement.expression)),
+        Statement::ReturnStatement(statement) => JsStmt::Return(statement.argument.as_ref().map(read_expr)),
+        Statement::IfStatement(statement) => JsStmt::If {
+            test: read_expr(&statement.test),
+            then: read_block(&statement.consequent),
+            otherwise: statement.alternate.as_ref().map_or_else(Vec::new, read_block),
+        },
+        Statement::WhileStatement(statement) => JsStmt::While {
+            test: read_expr(&statement.test),
+            body: read_block(&statement.body),
+        },
+        Statement::DoWhileStatement(statement) => JsStmt::DoWhile {
+            body: read_block(&statement.body),
+            test: read_expr(&statement.test),
+        },
+        Statement::ForStatement(statement) => JsStmt::For {
+            init: match &statement.init {
+                Some(ForStatementInit::VariableDeclaration(variables)) => read_variables(variables),
+                Some(init) => init.as_expression().map(|e| vec![JsStmt::Expr(read_expr(e))]).unwrap_or_default(),
+                None => vec![],
+            },
+            test: statement.test.as_ref().map(read_expr),
+            update: statement.update.as_ref().map(read_expr),
+            body: read_block(&statement.body),
+        },
+        Statement::ForOfStatement(statement) => JsStmt::ForOf {
+            pattern: read_for_left(&statement.left),
+            iterable: read_expr(&statement.right),
+            body: read_block(&statement.body),
+        },
+        Statement::ForInStatement(statement) => JsStmt::ForIn {
+            pattern: read_for_left(&statement.left),
+            object: read_expr(&statement.right),
+            body: read_block(&statement.body),
+        },
+        Statement::SwitchStatement(statement) => JsStmt::Switch {
+            discriminant: read_expr(&statement.discriminant),
+            cases: statement.cases.iter().map(|case| JsCase {
+                test: case.test.as_ref().map(read_expr),
+                body: read_body(&case.consequent),
+            }).collect(),
+        },
+        Statement::BreakStatement(statement) => JsStmt::Break(statement.label.as_ref().map(|label| label.name.to_string())),
+        Statement::ContinueStatement(statement) => {
+            JsStmt::Continue(statement.label.as_ref().map(|label| label.name.to_string()))
+        }
+        Statement::ThrowStatement(statement) => JsStmt::Throw(read_expr(&statement.argument)),
+        Statement::TryStatement(statement) => JsStmt::Try {
+            block: read_body(&statement.block.body),
+            param: statement.handler.as_ref()
+                .and_then(|handler| handler.param.as_ref())
+                .map(|param| read_pattern(&param.pattern)),
+            handler: statement.handler.as_ref().map(|handler| read_body(&handler.body.body)),
+            finalizer: statement.finalizer.as_ref().map_or_else(Vec::new, |finalizer| read_body(&finalizer.body)),
+        },
+        Statement::VariableDeclaration(variables) => return read_variables(variables),
+        Statement::FunctionDeclaration(function) => match &function.id {
+            Some(id) => JsStmt::Function(read_function(&id.name, function)),
+            None => return vec![],
+        },
+        Statement::LabeledStatement(statement) => {
+            let mut body = read_statement(&statement.body);
+            match body.len() {
+                1 => JsStmt::Labeled(statement.label.name.to_string(), Box::new(body.remove(0))),
+                _ => return body,
+            }
+        }
+        Statement::EmptyStatement(_) => return vec![],
+        Statement::ClassDeclaration(_) => JsStmt::Unsupported("nested class".to_string()),
+        Statement::WithStatement(_) => JsStmt::Unsupported("with statement".to_string()),
+        statement if statement.is_typescript_syntax() => return vec![],
+        _ => JsStmt::Unsupported("statement".to_string()),
+    };
+    vec![read]
+}
+
+fn read_variables(variables: &VariableDeclaration) -> Vec<JsStmt> {
+    variables.declarations.iter().map(|declarator| {
+        let init = declarator.init.as_ref();
+        let ty = match (&declarator.type_annotation, init) {
+            (Some(annotation), _) => ts_type(&annotation.type_annotation),
+            (None, Some(init)) => literal_type(init),
+            (None, None) => JsType::Unknown,
+        };
+        JsStmt::Let {
+            pattern: read_pattern(&declarator.id),
+            ty,
+            init: init.map(|init| match init.without_parentheses() {
+                // `const f = () => ...` keeps its name for the closure
+                Expression::ArrowFunctionExpression(arrow) => {
+                    let name = declarator.id.get_identifier_name().map_or("", |name| name.as_str());
+                    JsExpr::Function(Box::new(read_arrow(name, arrow)))
+                }
+                init => read_expr(init),
+            }),
+        }
+    }).collect()
+}
+
+fn read_pattern(pattern: &BindingPattern) -> JsPattern {
+    match pattern {
+        BindingPattern::BindingIdentifier(id) => JsPattern::Ident(id.name.to_string()),
+        BindingPattern::ArrayPattern(array) => JsPattern::Array {
+            elements: array.elements.iter().map(|element| element.as_ref().map(read_pattern)).collect(),
+            rest: array.rest.as_ref().map(|rest| Box::new(read_pattern(&rest.argument))),
+        },
+        BindingPattern::ObjectPattern(object) => JsPattern::Object {
+            properties: object.properties.iter()
+                .filter_map(|property| Some((property.key.name()?.to_string(), read_pattern(&property.value))))
+                .collect(),
+            rest: object.rest.as_ref().map(|rest| Box::new(read_pattern(&rest.argument))),
+        },
+        BindingPattern::AssignmentPattern(assignment) => {
+            JsPattern::Default(Box::new(read_pattern(&assignment.left)), read_expr(&assignment.right))
+        }
+    }
+}
+
+fn read_for_left(left: &ForStatementLeft) -> JsPattern {
+    match left {
+        ForStatementLeft::VariableDeclaration(variables) => variables.declarations.first()
+            .map_or(JsPattern::Ident("_".to_string()), |declarator| read_pattern(&declarator.id)),
+        ForStatementLeft::AssignmentTargetIdentifier(id) => JsPattern::Ident(id.name.to_string()),
+        _ => JsPattern::Ident("_".to_string()),
+    }
+}
+
+fn read_expr(expression: &Expression) -> JsExpr {
+    if let Some(member) = expression.as_member_expression() {
+        return read_member(member);
+    }
+
+    match expression {
+        Expression::BooleanLiteral(literal) => JsExpr::Bool(literal.value),
+        Expression::NullLiteral(_) => JsExpr::Null,
+        Expression::NumericLiteral(literal) => JsExpr::Number(literal.value),
+        Expression::StringLiteral(literal) => JsExpr::String(literal.value.to_string()),
+        Expression::RegExpLiteral(literal) => JsExpr::Regex {
+            pattern: literal.regex.pattern.text.to_string(),
+            flags: literal.regex.flags.to_string(),
+        },
+        Expression::TemplateLiteral(template) => JsExpr::Template {
+            quasis: template.quasis.iter()
+                .map(|quasi| quasi.value.cooked.unwrap_or(quasi.value.raw).to_string())
+                .collect(),
+            exprs: template.expressions.iter().map(read_expr).collect(),
+        },
+        Expression::Identifier(id) if id.name == "undefined" => JsExpr::Undefined,
+        Expression::Identifier(id) => JsExpr::Ident(id.name.to_string()),
+        Expression::ThisExpression(_) => JsExpr::This,
+        Expression::Super(_) => JsExpr::Super,
+        Expression::ArrayExpression(array) => JsExpr::Array(array.elements.iter().map(|element| match element {
+            ArrayExpressionElement::SpreadElement(spread) => JsExpr::Spread(Box::new(read_expr(&spread.argument))),
+            ArrayExpressionElement::Elision(_) => JsExpr::Undefined,
+            element => element.as_expression().map_or(JsExpr::Undefined, read_expr),
+        }).collect()),
+        Expression::ObjectExpression(object) => JsExpr::Object(object.properties.iter().filter_map(|property| match property {
+            ObjectPropertyKind::ObjectProperty(property) => {
+                Some(JsProp::KeyValue(property.key.name()?.to_string(), read_expr(&property.value)))
+            }
+            ObjectPropertyKind::SpreadProperty(spread) => Some(JsProp::Spread(read_expr(&spread.argument))),
+        }).collect()),
+        Expression::ArrowFunctionExpression(arrow) => JsExpr::Function(Box::new(read_arrow("", arrow))),
+        Expression::FunctionExpression(function) => {
+            let name = function.id.as_ref().map_or("", |id| id.name.as_str());
+            JsExpr::Function(Box::new(read_function(name, function)))
+        }
+        Expression::AssignmentExpression(assignment) => JsExpr::Assign(
+            assignment.operator.as_str(),
+            Box::new(read_assignment_target(&assignment.left)),
+            Box::new(read_expr(&assignment.right)),
+        ),
+        Expression::AwaitExpression(expression) => JsExpr::Await(Box::new(read_expr(&expression.argument))),
+        Expression::BinaryExpression(binary) => JsExpr::Binary(
+            binary.operator.as_str(),
+            Box::new(read_expr(&binary.left)),
+            Box::new(read_expr(&binary.right)),
+        ),
+        Expression::LogicalExpression(logical) => JsExpr::Binary(
+            logical.operator.as_str(),
+            Box::new(read_expr(&logical.left)),
+            Box::new(read_expr(&logical.right)),
+        ),
+        Expression::CallExpression(call) => read_call(call),
+        // Optional chaining reads as plain access
+        Expression::ChainExpression(chain) => match &chain.expression {
+            ChainElement::CallExpression(call) => read_call(call),
+            ChainElement::TSNonNullExpression(expression) => read_expr(&expression.expression),
+            element => element.as_member_expression().map_or(JsExpr::Unsupported("optional chain".to_string()), read_member),
+        },
+        Expression::ConditionalExpression(conditional) => JsExpr::Conditional(
+            Box::new(read_expr(&conditional.test)),
+            Box::new(read_expr(&conditional.consequent)),
+            Box::new(read_expr(&conditional.alternate)),
+        ),
+        Expression::NewExpression(new) => JsExpr::New(Box::new(read_expr(&new.callee)), read_arguments(&new.arguments)),
+        Expression::ParenthesizedExpression(expression) => read_expr(&expression.expression),
+        Expression::SequenceExpression(sequence) => JsExpr::Sequence(sequence.expressions.iter().map(read_expr).collect()),
+        Expression::UnaryExpression(unary) => JsExpr::Unary(unary.operator.as_str(), Box::new(read_expr(&unary.argument))),
+        Expression::UpdateExpression(update) => JsExpr::Update {
+            op: update.operator.as_str(),
+            prefix: update.prefix,
+            target: Box::new(read_simple_target(&update.argument)),
+        },
+        Expression::YieldExpression(expression) => JsExpr::Yield {
+            value: expression.argument.as_ref().map(|value| Box::new(read_expr(value))),
+            delegate: expression.delegate,
+        },
+        Expression::TSAsExpression(expression) => read_expr(&expression.expression),
+        Expression::TSSatisfiesExpression(expression) => read_expr(&expression.expression),
+        Expression::TSNonNullExpression(expression) => read_expr(&expression.expression),
+        Expression::TSTypeAssertion(expression) => read_expr(&expression.expression),
+        Expression::TSInstantiationExpression(expression) => read_expr(&expression.expression),
+        Expression::BigIntLiteral(_) => JsExpr::Unsupported("bigint literal".to_string()),
+        Expression::TaggedTemplateExpression(_) => JsExpr::Unsupported("tagged template".to_string()),
+        Expression::ClassExpression(_) => JsExpr::Unsupported("class expression".to_string()),
+        Expression::JSXElement(_) | Expression::JSXFragment(_) => JsExpr::Unsupported("JSX".to_string()),
+        _ => JsExpr::Unsupported("expression".to_string()),
+    }
+}
+
+fn read_call(call: &CallExpression) -> JsExpr {
+    JsExpr::Call(Box::new(read_expr(&call.callee)), read_arguments(&call.arguments))
+}
+
+fn read_arguments(arguments: &[Argument]) -> Vec<JsExpr> {
+    arguments.iter().map(|argument| match argument {
+        Argument::SpreadElement(spread) => JsExpr::Spread(Box::new(read_expr(&spread.argument))),
+        argument => argument.as_expression().map_or(JsExpr::Undefined, read_expr),
+    }).collect()
+}
+
+fn read_member(member: &MemberExpression) -> JsExpr {
+    match member {
+        MemberExpression::StaticMemberExpression(member) => {
+            JsExpr::Member(Box::new(read_expr(&member.object)), member.property.name.to_string())
+        }
+        MemberExpression::ComputedMemberExpression(member) => match &member.expression {
+            Expression::StringLiteral(key) => JsExpr::Member(Box::new(read_expr(&member.object)), key.value.to_string()),
+            index => JsExpr::Index(Box::new(read_expr(&member.object)), Box::new(read_expr(index))),
+        },
+        MemberExpression::PrivateFieldExpression(member) => {
+            JsExpr::Member(Box::new(read_expr(&member.object)), member.field.name.to_string())
+        }
+    }
+}
+
+fn read_assignment_target(target: &AssignmentTarget) -> JsExpr {
+    match target.as_simple_assignment_target() {
+        Some(simple) => read_simple_target(simple),
+        None => JsExpr::Unsupported("destructuring assignment".to_string()),
+    }
+}
+
+fn read_simple_target(target: &SimpleAssignmentTarget) -> JsExpr {
+    if let Some(member) = target.as_member_expression() {
+        return read_member(member);
+    }
+    match target {
+        SimpleAssignmentTarget::AssignmentTargetIdentifier(id) => JsExpr::Ident(id.name.to_string()),
+        SimpleAssignmentTarget::TSAsExpression(expression) => read_expr(&expression.expression),
+        SimpleAssignmentTarget::TSSatisfiesExpression(expression) => read_expr(&expression.expression),
+        SimpleAssignmentTarget::TSNonNullExpression(expression) => read_expr(&expression.expression),
+        SimpleAssignmentTarget::TSTypeAssertion(expression) => read_expr(&expression.expression),
+        _ => JsExpr::Unsupported("assignment target".to_string()),
+    }
+}
+
+/// Type of a literal default value, for unannotated JS
+fn literal_type(expression: &Expression) -> JsType {
+    match expression.without_parentheses() {
+        Expression::NumericLiteral(_) => JsType::Number,
+        Expression::BigIntLiteral(_) => JsType::BigInt,
+        Expression::StringLiteral(_) | Expression::TemplateLiteral(_) => JsType::String,
+        Expression::BooleanLiteral(_) => JsType::Boolean,
+        Expression::ArrayExpression(_) => JsType::Array(Box::new(JsType::Unknown)),
+        Expression::ObjectExpression(_) => JsType::Object,
+        _ => JsType::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typescript_annotations() {
+        let ast = parse_source(
+            "export async function load<T>(ids: number[], cache?: Map<string, T>): Promise<T | null> { return null }\n\
+             const pick = (pair: [string, boolean], ...rest: string[]): Record<string, number> => ({})",
+            SourceKind::TypeScript,
+        ).unwrap();
+
+        let load = &ast.functions[0];
+        assert!(load.is_async);
+        assert_eq!(load.type_params, ["T"]);
+        assert_eq!(load.params[0].ty, JsType::Array(Box::new(JsType::Number)));
+        assert_eq!(load.params[1].ty, optional(JsType::Map(Box::new(JsType::String), Box::new(JsType::Named("T".into(), vec![])))));
+        assert_eq!(load.return_type, JsType::Promise(Box::new(optional(JsType::Named("T".into(), vec![])))));
+        assert_eq!(ast.exports, ["load"]);
+
+        let pick = &ast.functions[1];
+        assert_eq!(pick.params[0].ty, JsType::Tuple(vec![JsType::String, JsType::Boolean]));
+        assert_eq!(pick.params[1].name, "rest");
+        assert_eq!(pick.return_type, JsType::Map(Box::new(JsType::String), Box::new(JsType::Number)));
+    }
+
+    #[test]
+    fn test_interfaces_and_classes() {
+        let ast = parse_source(
+            "interface Point { x: number; label?: string }\n\
+             class Counter { count = 0; constructor(private step: number) {} tick(): void {} }",
+            SourceKind::TypeScript,
+        ).unwrap();
+
+        let point = &ast.classes[0];
+        assert_eq!(point.name, "Point");
+        assert_eq!(point.properties[1].ty, optional(JsType::String));
+
+        let counter = &ast.classes[1];
+        let fields: Vec<_> = counter.properties.iter().map(|p| (p.name.as_str(), p.ty.clone())).collect();
+        assert_eq!(fields, [("count", JsType::Number), ("step", JsType::Number)]);
+        assert_eq!(counter.methods.iter().map(|m| m.function.name.as_str()).collect::<Vec<_>>(), ["new", "tick"]);
+    }
+
+    #[test]
+    fn test_class_inheritance_and_member_kinds() {
+        let ast = parse_source(
+            "class Dog extends Animal {\n\
+               static tally = 0;\n\
+               constructor(name: string, public breed: string) { super(name); }\n\
+               static of(name: string) {} get label() {} set label(value) {} bark() {}\n\
+             }",
+            SourceKind::TypeScript,
+        ).unwrap();
+
+        let dog = &ast.classes[0];
+        assert_eq!(dog.extends.as_deref(), Some("Animal"));
+        assert!(dog.properties[0].is_static);
+        let kinds: Vec<_> = dog.methods.iter().map(|m| m.kind).collect();
+        assert_eq!(kinds, [MethodKind::Constructor, MethodKind::Static, MethodKind::Getter, MethodKind::Setter, MethodKind::Method]);
+        // Parameter properties are assigned once `super(..)` has run
+        assert!(matches!(&dog.methods[0].function.body[1], JsStmt::Expr(JsExpr::Assign("=", target, _))
+            if matches!(&**target, JsExpr::Member(object, field) if matches!(**object, JsExpr::This) && field == "breed")));
+    }
+
+    #[test]
+    fn test_javascript_falls_back_to_defaults() {
+        let ast = parse_source("function greet(name, times = 1) {}", SourceKind::JavaScript).unwrap();
+        let params: Vec<_> = ast.functions[0].params.iter().map(|p| p.ty.clone()).collect();
+        assert_eq!(params, [JsType::Unknown, JsType::Number]);
+
+        assert!(parse_source("function (", SourceKind::JavaScript).is_err());
+        assert!(parse_source("let x: number = 1", SourceKind::JavaScript).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_the_source() {
+        let Err(error) = parse_source("export function add(a) {\n  return a +;\n}", SourceKind::JavaScript) else { panic!("parsed") };
+        let span = error.diagnostic().span.unwrap();
+        assert_eq!((span.line, span.column), (2, 13));
+        assert_eq!(error.to_json()["kind"], "parse");
+    }
+}
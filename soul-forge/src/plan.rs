@@ -0,0 +1,133 @@
+// 📝 Transmutation Plans - What the Forge Would Make, Before It Makes It
+use crate::sourcemap;
+
+/// What transmuting a module would generate, from a dry run that checks,
+/// compares and stores nothing
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    /// The module's items, in the order they'd be written
+    pub items: Vec<PlannedItem>,
+    /// The exports JS would reach through the bindings
+    pub bound: Vec<String>,
+    /// How closely the generated code's soul resonates with the source's,
+    /// before any compiler-driven repair
+    pub resonance: f64,
+    pub souls_match: bool,
+}
+
+/// One item of a planned module, with the types inferred for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedItem {
+    /// `fn`, `struct`, `enum`, `trait`, `impl`, `mod` or `use`
+    pub kind: &'static str,
+    /// The declaration without its body: a function's signature, a type's
+    /// fields or variants, an impl's header
+    pub signature: String,
+    /// The source line it's transmuted from, as `file:line`
+    pub origin: Option<String>,
+    /// The functions of a trait or impl, or the items of a module
+    pub members: Vec<PlannedItem>,
+}
+
+/// The items of `rust_code` a plan lists; code syn can't parse has none
+pub(crate) fn items(rust_code: &str) -> Vec<PlannedItem> {
+    let Ok(file) = syn::parse_file(rust_code) else { return vec![] };
+    items_of(file.items)
+}
+
+fn items_of(items: Vec<syn::Item>) -> Vec<PlannedItem> {
+    items.into_iter().filter_map(|item| match item {
+        syn::Item::Fn(function) => Some(function_item(function.attrs, function.vis, function.sig)),
+        syn::Item::Struct(mut structure) => {
+            let origin = origin(&structure.attrs);
+            structure.attrs.clear();
+            Some(PlannedItem { kind: "struct", signature: unparse(syn::Item::Struct(structure)), origin, members: vec![] })
+        }
+        syn::Item::Enum(mut enumeration) => {
+            let origin = origin(&enumeration.attrs);
+            enumeration.attrs.clear();
+            Some(PlannedItem { kind: "enum", signature: unparse(syn::Item::Enum(enumeration)), origin, members: vec![] })
+        }
+        syn::Item::Trait(mut block) => {
+            let origin = origin(&block.attrs);
+            let members = std::mem::take(&mut block.items).into_iter().filter_map(|item| match item {
+                syn::TraitItem::Fn(method) => Some(function_item(method.attrs, syn::Visibility::Inherited, method.sig)),
+                _ => None,
+            }).collect();
+            block.attrs.clear();
+            Some(PlannedItem { kind: "trait", signature: header(syn::Item::Trait(block)), origin, members })
+        }
+        syn::Item::Impl(mut block) => {
+            let members = std::mem::take(&mut block.items).into_iter().filter_map(|item| match item {
+                syn::ImplItem::Fn(method) => Some(function_item(method.attrs, method.vis, method.sig)),
+                _ => None,
+            }).collect();
+            block.attrs.clear();
+            Some(PlannedItem { kind: "impl", signature: header(syn::Item::Impl(block)), origin: None, members })
+        }
+        syn::Item::Mod(mut module) => {
+            let members = module.content.take().map_or(vec![], |(_, items)| items_of(items));
+            module.attrs.clear();
+            let visibility = if matches!(module.vis, syn::Visibility::Public(_)) { "pub " } else { "" };
+            Some(PlannedItem { kind: "mod", signature: format!("{}mod {}", visibility, module.ident), origin: None, members })
+        }
+        // Re-exports are part of what a module offers
+        syn::Item::Use(reexport) if matches!(reexport.vis, syn::Visibility::Public(_)) => {
+            let printed = unparse(syn::Item::Use(reexport));
+            Some(PlannedItem { kind: "use", signature: printed.trim_end_matches(';').to_string(), origin: None, members: vec![] })
+        }
+        _ => None,
+    }).collect()
+}
+
+fn function_item(attrs: Vec<syn::Attribute>, vis: syn::Visibility, sig: syn::Signature) -> PlannedItem {
+    let origin = origin(&attrs);
+    let function = syn::ItemFn { attrs: vec![], vis, sig, block: Box::new(syn::parse_quote!({})) };
+    PlannedItem { kind: "fn", signature: header(syn::Item::Fn(function)), origin, members: vec![] }
+}
+
+fn origin(attrs: &[syn::Attribute]) -> Option<String> {
+    sourcemap::origin_of(attrs).map(|(file, line)| format!("{}:{}", file, line))
+}
+
+/// `item` printed on one line
+fn unparse(item: syn::Item) -> String {
+    let file = syn::File { shebang: None, attrs: vec![], items: vec![item] };
+    prettyplease::unparse(&file)
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(", )", ")")
+        .replace(", }", " }")
+}
+
+/// `item`, emptied of its body, printed on one line without the braces
+fn header(item: syn::Item) -> String {
+    let printed = unparse(item);
+    printed.strip_suffix("{}").unwrap_or(&printed).trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plans_list_signatures_and_origins() {
+        let code = "/// Transmuted from `index.ts:3`\n\
+                    pub fn chunk(xs: Vec<f64>, size: f64) -> Vec<Vec<f64>> {\n    vec![xs]\n}\n\n\
+                    pub struct Point {\n    pub x: f64,\n    pub y: f64,\n}\n\n\
+                    impl Point {\n    pub fn norm(&self) -> f64 {\n        self.x.hypot(self.y)\n    }\n}\n\n\
+                    use std::collections::HashMap;\n";
+        let planned = items(code);
+        let summary: Vec<(&str, &str)> = planned.iter().map(|item| (item.kind, item.signature.as_str())).collect();
+        assert_eq!(summary, [
+            ("fn", "pub fn chunk(xs: Vec<f64>, size: f64) -> Vec<Vec<f64>>"),
+            ("struct", "pub struct Point { pub x: f64, pub y: f64 }"),
+            ("impl", "impl Point"),
+        ]);
+        assert_eq!(planned[0].origin.as_deref(), Some("index.ts:3"));
+        assert_eq!(planned[2].members[0].signature, "pub fn norm(&self) -> f64");
+    }
+}
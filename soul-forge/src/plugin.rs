@@ -0,0 +1,104 @@
+// 🔌 Transform Passes - Org-Specific Idioms Without Patching the Forge
+use crate::{Diagnostic, JsAst, RustAst, TransmutationError};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The soul-forge version a plugin was built against, which must be the
+/// forge's own: the ASTs have no stable layout across versions
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A rewrite run on every module after it is transformed and before its
+/// code is generated
+///
+/// `js_ast` has its types inferred by then, and the exports it lists after
+/// every pass are the ones the bindings expose; `rust_ast` is what gets
+/// generated.
+pub trait TransformPass: Send + Sync {
+    /// How errors and logs name the pass
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    fn run(&self, js_ast: &mut JsAst, rust_ast: &mut RustAst);
+}
+
+/// The passes a plugin's `export_passes!` declares
+pub type PassesFn = fn() -> Vec<Box<dyn TransformPass>>;
+
+/// A plugin's `soul_forge_plugin_version`
+pub type VersionFn = fn() -> &'static str;
+
+/// Declare the passes a plugin cdylib gives the forge, for `--plugin`
+///
+/// The plugin has to be built by the same toolchain as the forge loading
+/// it, against the same soul-forge version, which is checked on load.
+#[macro_export]
+macro_rules! export_passes {
+    ($($pass:expr),* $(,)?) => {
+        #[no_mangle]
+        pub fn soul_forge_plugin_version() -> &'static str {
+            $crate::plugin::VERSION
+        }
+
+        #[no_mangle]
+        pub fn soul_forge_passes() -> Vec<Box<dyn $crate::TransformPass>> {
+            vec![$(Box::new($pass)),*]
+        }
+    };
+}
+
+/// A pass from a plugin, which keeps the library its code lives in loaded
+struct PluginPass {
+    // Dropped before the library, whose code it points into
+    pass: Box<dyn TransformPass>,
+    _library: Arc<libloading::Library>,
+}
+
+impl TransformPass for PluginPass {
+    fn name(&self) -> &str {
+        self.pass.name()
+    }
+
+    fn run(&self, js_ast: &mut JsAst, rust_ast: &mut RustAst) {
+        self.pass.run(js_ast, rust_ast)
+    }
+}
+
+/// The passes of the plugin at `path`
+pub(crate) fn load(path: &Path) -> Result<Vec<Arc<dyn TransformPass>>, TransmutationError> {
+    let plugin_error = |e: libloading::Error| TransmutationError::PluginError(Diagnostic::error(e.to_string()).with_file(path.display().to_string()));
+    // Safety: loading runs the library's initializers, and the symbols are
+    // trusted to have the types `export_passes!` gives them, as the version
+    // check can only partly confirm
+    unsafe {
+        // The loader's errors already name the file
+        let library = libloading::Library::new(path).map_err(|e| TransmutationError::PluginError(e.to_string().into()))?;
+        let library = Arc::new(library);
+        let version = library.get::<VersionFn>(b"soul_forge_plugin_version").map_err(plugin_error)?();
+        if version != VERSION {
+            return Err(TransmutationError::PluginError(
+                Diagnostic::error(format!("built against soul-forge {}, not {}", version, VERSION))
+                    .with_file(path.display().to_string())
+                    .with_suggestion(format!("rebuild the plugin against soul-forge {}", VERSION)),
+            ));
+        }
+        let passes = library.get::<PassesFn>(b"soul_forge_passes").map_err(plugin_error)?();
+        Ok(passes.into_iter()
+            .map(|pass| Arc::new(PluginPass { pass, _library: library.clone() }) as Arc<dyn TransformPass>)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugins_must_be_libraries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("soul-forge-plugin.so");
+        std::fs::write(&path, "not a library").unwrap();
+        assert!(matches!(load(&path), Err(TransmutationError::PluginError(e)) if e.message.contains("soul-forge-plugin")));
+        assert!(load(Path::new("/nonexistent/plugin.so")).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+// 🎯 Output Profiles - What a Transmuted Crate Is Built As
+use crate::AsyncTarget;
+use serde::{Deserialize, Serialize};
+
+/// Crates only a WASM build uses, which native profiles leave out
+const WASM_ONLY: &[&str] = &["wasm-bindgen", "serde-wasm-bindgen", "wasm-bindgen-futures", "gloo-timers"];
+
+/// What a transmuted crate is built as, deciding its prelude, crate types,
+/// dependencies and the bindings file written next to it
+///
+/// A napi-rs addon (`Bindings::Napi`) keeps its `index.js` loader and
+/// `build.rs` under any profile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Profile {
+    /// WASM for browsers and bundlers, loaded by an ES module `bindings.js`
+    #[default]
+    WasmWeb,
+    /// WASM for Node, loaded by a CommonJS `bindings.js`
+    WasmNode,
+    /// A plain Rust library, with no bindings
+    NativeLib,
+    /// A shared library C links against, declared by a `{crate}.h` header
+    CdylibFfi,
+}
+
+impl Profile {
+    pub fn is_wasm(&self) -> bool {
+        matches!(self, Self::WasmWeb | Self::WasmNode)
+    }
+
+    /// What `[lib] crate-type` lists; every profile keeps `rlib`, which
+    /// equivalence tests and benchmarks link against
+    pub fn crate_types(&self) -> &'static [&'static str] {
+        match self {
+            Self::WasmWeb | Self::WasmNode | Self::CdylibFfi => &["cdylib", "rlib"],
+            Self::NativeLib => &["rlib"],
+        }
+    }
+
+    /// Whether the generated Cargo.toml keeps `dependency`
+    pub(crate) fn depends_on(&self, dependency: &str) -> bool {
+        self.is_wasm() || !WASM_ONLY.contains(&dependency)
+    }
+
+    /// Where async code runs: `requested`, unless only a native runtime can
+    /// run it
+    pub fn async_target(&self, requested: AsyncTarget) -> AsyncTarget {
+        match self.is_wasm() {
+            true => requested,
+            false => AsyncTarget::Native,
+        }
+    }
+
+    /// The command that builds the crate once written
+    pub fn build_command(&self) -> &'static str {
+        match self {
+            Self::WasmWeb => "wasm-pack build --target web",
+            Self::WasmNode => "wasm-pack build --target nodejs",
+            Self::NativeLib | Self::CdylibFfi => "cargo build --release",
+        }
+    }
+}
+
+impl std::str::FromStr for Profile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wasm-web" => Ok(Self::WasmWeb),
+            "wasm-node" => Ok(Self::WasmNode),
+            "native-lib" => Ok(Self::NativeLib),
+            "cdylib-ffi" => Ok(Self::CdylibFfi),
+            other => Err(format!("unknown profile '{}' (expected wasm-web, wasm-node, native-lib or cdylib-ffi)", other)),
+        }
+    }
+}
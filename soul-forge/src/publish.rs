@@ -0,0 +1,151 @@
+// 🚀 Publishing - Taking a Transmuted Crate to crates.io
+use crate::{bench, license, Transmutation, TransmutationError, Verification};
+use std::path::Path;
+
+/// The most keywords crates.io takes
+const MAX_KEYWORDS: usize = 5;
+
+/// What crates.io would refuse about publishing `transmutation`'s crate, or
+/// what shouldn't be published; none when it's ready
+pub fn problems(transmutation: &Transmutation) -> Vec<String> {
+    let metadata = &transmutation.metadata;
+    let mut problems = vec![];
+    if !is_crate_name(&metadata.crate_name) {
+        problems.push(format!("`{}` isn't a crate name crates.io takes: ASCII letters, digits, - and _, starting with a letter, at most 64 long", metadata.crate_name));
+    }
+    match semver::Version::parse(&metadata.version) {
+        Ok(version) if !version.build.is_empty() => problems.push(format!("version {} has build metadata, which crates.io ignores", metadata.version)),
+        Ok(_) => {}
+        Err(e) => problems.push(format!("version {} isn't semver: {}", metadata.version, e)),
+    }
+    match metadata.license.as_deref() {
+        None => problems.push(format!("{} names no license, and crates.io requires one", metadata.npm_name)),
+        Some(npm_license) => if let Err(reason) = license::spdx(npm_license) {
+            problems.push(format!("license \"{}\" isn't an SPDX expression: {}", npm_license, reason));
+        }
+    }
+    if metadata.description.trim().is_empty() {
+        problems.push("the description is empty".to_string());
+    }
+    if metadata.keywords.len() > MAX_KEYWORDS {
+        problems.push(format!("{} keywords, more than the {} crates.io takes", metadata.keywords.len(), MAX_KEYWORDS));
+    }
+    for keyword in metadata.keywords.iter().filter(|keyword| !is_keyword(keyword)) {
+        problems.push(format!("keyword `{}` isn't one crates.io takes: ASCII letters, digits, -, _ and +, starting with a letter, at most 20 long", keyword));
+    }
+    let unfinished = bench::unfinished(&transmutation.rust_code);
+    if !unfinished.is_empty() {
+        problems.push(format!("{} still {} a todo!() body", unfinished.join(", "), if unfinished.len() == 1 { "has" } else { "have" }));
+    }
+    problems
+}
+
+/// Check the manifest in `dir` is `transmutation`'s crate at the package's
+/// version, and that every path dependency also gives the version
+/// `cargo publish` depends on in its place
+pub fn check_manifest(dir: &Path, transmutation: &Transmutation) -> Result<(), TransmutationError> {
+    let path = dir.join("Cargo.toml");
+    let error = |message: String| TransmutationError::PublishError(crate::Diagnostic::error(message).with_file(path.display().to_string()));
+    let manifest: toml::Value = std::fs::read_to_string(&path)
+        .map_err(|e| error(e.to_string()))
+        .and_then(|text| toml::from_str(&text).map_err(|e| error(e.to_string())))?;
+    let package = |key: &str| manifest.get("package").and_then(|package| package.get(key)).and_then(toml::Value::as_str);
+
+    let metadata = &transmutation.metadata;
+    if package("name") != Some(metadata.crate_name.as_str()) {
+        return Err(error(format!("the crate here isn't {}", metadata.crate_name)));
+    }
+    if package("version") != Some(metadata.version.as_str()) {
+        return Err(error(format!("the crate is at {}, not {} of {}; edits to its version were kept", package("version").unwrap_or("no version"), metadata.version, metadata.npm_name)));
+    }
+    let dependencies = manifest.get("dependencies").and_then(toml::Value::as_table).into_iter().flatten();
+    for (name, requirement) in dependencies {
+        if requirement.get("path").is_some() && requirement.get("version").is_none() {
+            return Err(error(format!("dependency {} is only a path, which crates.io can't follow", name)));
+        }
+    }
+    Ok(())
+}
+
+/// Run the crate's tests, its equivalence tests among them, and with
+/// `Clippy` its lints as errors
+pub async fn verify(dir: &Path, verification: Verification) -> Result<(), TransmutationError> {
+    if verification == Verification::Clippy {
+        cargo(dir, &["clippy", "--all-targets", "--", "-D", "warnings"]).await?;
+    }
+    if verification != Verification::Off {
+        cargo(dir, &["test"]).await?;
+    }
+    Ok(())
+}
+
+/// `cargo publish` the crate in `dir`, or only package and build it as
+/// crates.io would with `dry_run`
+pub async fn publish(dir: &Path, dry_run: bool) -> Result<(), TransmutationError> {
+    match dry_run {
+        true => cargo(dir, &["publish", "--dry-run"]).await,
+        false => cargo(dir, &["publish"]).await,
+    }
+}
+
+async fn cargo(dir: &Path, args: &[&str]) -> Result<(), TransmutationError> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let output = tokio::process::Command::new(cargo)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+        .map_err(|e| TransmutationError::PublishError(format!("can't run cargo {}: {}", args[0], e).into()))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(TransmutationError::PublishError(format!("cargo {} failed:\n{}", args[0], stderr.trim()).into()));
+    }
+    Ok(())
+}
+
+fn is_crate_name(name: &str) -> bool {
+    name.len() <= 64
+        && name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn is_keyword(keyword: &str) -> bool {
+    keyword.len() <= 20
+        && keyword.starts_with(|c: char| c.is_ascii_alphabetic())
+        && keyword.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_problems_crates_io_would_refuse() {
+        let db = tempfile::tempdir().unwrap();
+        let mut forge = crate::SoulForge::new(db.path().to_str().unwrap()).unwrap().with_max_refinements(0);
+        let metadata = crate::PackageMetadata {
+            name: "pad-soul".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Pads text".to_string(),
+            npm_name: "pad".to_string(),
+            crate_name: "pad-soul".to_string(),
+            source_file: None,
+            bindings: crate::Bindings::Wasm,
+            profile: crate::Profile::WasmWeb,
+            license: Some("mit".to_string()),
+            authors: vec![],
+            repository: None,
+            keywords: vec!["text".to_string()],
+        };
+        let mut transmutation = forge.transmute("export function pad(x) { return x + 1; }", metadata).await.unwrap();
+        assert_eq!(problems(&transmutation), Vec::<String>::new());
+
+        transmutation.metadata.license = None;
+        transmutation.metadata.version = "1.0".to_string();
+        transmutation.metadata.keywords.push("2d".to_string());
+        let found = problems(&transmutation);
+        assert_eq!(found.len(), 3);
+        assert!(found[0].starts_with("version 1.0 isn't semver"));
+        assert!(found[2].starts_with("keyword `2d`"));
+    }
+}
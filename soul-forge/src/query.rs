@@ -0,0 +1,180 @@
+// 🗂️ Transmutation Queries - Reading Back What the Forge Made
+use crate::{Benchmark, SoulForge, Transmutation, TransmutationError};
+use std::ops::{Bound, RangeBounds};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Key prefix of stored transmutations, followed by the npm name
+pub(crate) const PREFIX: &str = "transmutation:";
+
+/// Which stored transmutations a query returns; the default matches all
+#[derive(Debug, Clone)]
+pub struct TransmutationFilter {
+    resonance: (Bound<f64>, Bound<f64>),
+    /// Seconds since the Unix epoch, inclusive
+    since: Option<u64>,
+    /// Seconds since the Unix epoch, exclusive
+    until: Option<u64>,
+    name_prefix: String,
+    tag: Option<String>,
+}
+
+impl Default for TransmutationFilter {
+    fn default() -> Self {
+        Self {
+            resonance: (Bound::Unbounded, Bound::Unbounded),
+            since: None,
+            until: None,
+            name_prefix: String::new(),
+            tag: None,
+        }
+    }
+}
+
+impl TransmutationFilter {
+    /// Only transmutations whose resonance is in `range`, like `0.8..` or `0.5..0.95`
+    pub fn with_resonance(mut self, range: impl RangeBounds<f64>) -> Self {
+        self.resonance = (range.start_bound().cloned(), range.end_bound().cloned());
+        self
+    }
+
+    /// Only transmutations made at or after `time`
+    pub fn transmuted_since(mut self, time: SystemTime) -> Self {
+        self.since = Some(unix_seconds(time));
+        self
+    }
+
+    /// Only transmutations made before `time`
+    pub fn transmuted_before(mut self, time: SystemTime) -> Self {
+        self.until = Some(unix_seconds(time));
+        self
+    }
+
+    /// Only packages whose npm name starts with `prefix`, like `@babel/`
+    pub fn with_name_prefix(mut self, prefix: &str) -> Self {
+        self.name_prefix = prefix.to_string();
+        self
+    }
+
+    /// Only transmutations tagged `tag`
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
+
+    pub(crate) fn name_prefix(&self) -> &str {
+        &self.name_prefix
+    }
+
+    pub fn matches(&self, transmutation: &Transmutation) -> bool {
+        self.resonance.contains(&transmutation.resonance)
+            && self.since.is_none_or(|since| transmutation.transmuted_at >= since)
+            && self.until.is_none_or(|until| transmutation.transmuted_at < until)
+            && transmutation.metadata.npm_name.starts_with(&self.name_prefix)
+            && self.tag.as_ref().is_none_or(|tag| transmutation.tags.contains(tag))
+    }
+}
+
+/// Seconds since the Unix epoch, 0 for times before it
+pub(crate) fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+impl SoulForge {
+    /// Stored transmutations matching `filter`, by npm name
+    ///
+    /// Records that can't be read back are skipped; `schema::doctor` lists
+    /// them.
+    pub fn list_transmutations(&self, filter: &TransmutationFilter) -> Result<Vec<Transmutation>, TransmutationError> {
+        let mut transmutations = vec![];
+        for entry in self.db.scan_prefix(format!("{}{}", PREFIX, filter.name_prefix)) {
+            let (_, value) = entry.map_err(|e| TransmutationError::DatabaseError(e.to_string().into()))?;
+            if let Ok(transmutation) = bincode::deserialize::<Transmutation>(&value) {
+                if filter.matches(&transmutation) {
+                    transmutations.push(transmutation);
+                }
+            }
+        }
+        Ok(transmutations)
+    }
+
+    /// The stored transmutation of `npm_name`, if there is one
+    pub fn get_transmutation(&self, npm_name: &str) -> Result<Option<Transmutation>, TransmutationError> {
+        match self.db.get(format!("{}{}", PREFIX, npm_name)).map_err(|e| TransmutationError::DatabaseError(e.to_string().into()))? {
+            Some(value) => bincode::deserialize(&value).map(Some).map_err(|e| TransmutationError::SerializationError(e.to_string().into())),
+            None => Ok(None),
+        }
+    }
+
+    /// Forget the transmutation of `npm_name`, returning whether there was one
+    pub fn delete_transmutation(&self, npm_name: &str) -> Result<bool, TransmutationError> {
+        let removed = self.db.remove(format!("{}{}", PREFIX, npm_name)).map_err(|e| TransmutationError::DatabaseError(e.to_string().into()))?;
+        Ok(removed.is_some())
+    }
+
+    /// Replace the tags of `npm_name`'s transmutation, returning whether there was one
+    pub fn retag_transmutation(&self, npm_name: &str, tags: &[&str]) -> Result<bool, TransmutationError> {
+        let Some(mut transmutation) = self.get_transmutation(npm_name)? else { return Ok(false) };
+        transmutation.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        self.store_transmutation(&transmutation)?;
+        Ok(true)
+    }
+
+    /// Keep what `forge bench` measured with `npm_name`'s transmutation,
+    /// returning whether there was one
+    pub fn record_benchmarks(&self, npm_name: &str, benchmarks: Vec<Benchmark>) -> Result<bool, TransmutationError> {
+        let Some(mut transmutation) = self.get_transmutation(npm_name)? else { return Ok(false) };
+        transmutation.benchmarks = benchmarks;
+        self.store_transmutation(&transmutation)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bindings, PackageMetadata, Profile, SourceKind};
+
+    #[tokio::test]
+    async fn test_stored_transmutations_are_queried_and_retagged() {
+        let db = tempfile::tempdir().unwrap();
+        let mut forge = SoulForge::new(db.path().to_str().unwrap()).unwrap().with_max_refinements(0);
+        for npm_name in ["@babel/core", "@babel/parser", "left-pad"] {
+            let metadata = PackageMetadata {
+                name: npm_name.to_string(),
+                version: "1.0.0".to_string(),
+                description: String::new(),
+                npm_name: npm_name.to_string(),
+                crate_name: "soul".to_string(),
+                source_file: None,
+                bindings: Bindings::Wasm,
+                profile: Profile::WasmWeb,
+                license: None,
+                authors: vec![],
+                repository: None,
+                keywords: vec![],
+            };
+            forge.transmute_source("export function id(x: number): number { return x; }", SourceKind::TypeScript, metadata).await.unwrap();
+        }
+
+        let babel = forge.list_transmutations(&TransmutationFilter::default().with_name_prefix("@babel/")).unwrap();
+        assert_eq!(babel.iter().map(|t| t.metadata.npm_name.as_str()).collect::<Vec<_>>(), ["@babel/core", "@babel/parser"]);
+        assert_eq!(forge.list_transmutations(&TransmutationFilter::default().with_resonance(2.0..)).unwrap().len(), 0);
+        let later = SystemTime::now() + std::time::Duration::from_secs(60);
+        assert_eq!(forge.list_transmutations(&TransmutationFilter::default().transmuted_before(later)).unwrap().len(), 3);
+        assert_eq!(forge.list_transmutations(&TransmutationFilter::default().transmuted_since(later)).unwrap().len(), 0);
+
+        assert!(forge.retag_transmutation("left-pad", &["stable"]).unwrap());
+        let stable = forge.list_transmutations(&TransmutationFilter::default().with_tag("stable")).unwrap();
+        assert_eq!(stable.len(), 1);
+        assert_eq!(stable[0].tags, ["stable"]);
+
+        let measured = Benchmark { function: "id".to_string(), js_ns: 30.0, rust_ns: 2.0 };
+        assert!(forge.record_benchmarks("@babel/core", vec![measured.clone()]).unwrap());
+        assert_eq!(forge.get_transmutation("@babel/core").unwrap().unwrap().benchmarks, [measured]);
+
+        assert!(forge.delete_transmutation("left-pad").unwrap());
+        assert!(!forge.delete_transmutation("left-pad").unwrap());
+        assert!(forge.get_transmutation("left-pad").unwrap().is_none());
+        assert!(!forge.retag_transmutation("left-pad", &[]).unwrap());
+    }
+}
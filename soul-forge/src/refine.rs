@@ -0,0 +1,574 @@
+// 🔧 Structural Refinement - Reshaping Bodies Toward Their Soul
+use proc_macro2::LineColumn;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::visit_mut::{self, VisitMut};
+use syn::{parse_quote, Block, Expr, ExprIf, File, ImplItemFn, Item, ItemFn, Pat, Stmt, TraitItemFn, Visibility};
+
+/// Refinement rounds before the forge settles for the closest soul so far
+pub(crate) const MAX_REFINEMENTS: usize = 8;
+
+/// Which way a refinement moves the Rust soul's spectrum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Direction {
+    /// Fewer, flatter nodes: inline helpers, flatten control flow
+    Simplify,
+    /// More, deeper nodes: extract bodies, nest control flow
+    Enhance,
+}
+
+impl Direction {
+    /// Simplify when the Rust eigenvalues outweigh the JS ones, enhance otherwise
+    pub(crate) fn of(js_eigenvalues: &[f64], rust_eigenvalues: &[f64]) -> Self {
+        let delta: f64 = js_eigenvalues.iter().zip(rust_eigenvalues).map(|(js, rust)| js - rust).sum();
+        if delta < 0.0 { Self::Simplify } else { Self::Enhance }
+    }
+
+    fn edits(self) -> &'static [Edit] {
+        match self {
+            Self::Simplify => &[inline_functions, flatten_control_flow, inline_tails],
+            Self::Enhance => &[extract_functions, nest_control_flow, bind_tails],
+        }
+    }
+}
+
+/// A refinement picked by hand in `forge refine`: one of the edits
+/// automatic refinement makes, or a change of type it doesn't
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Inline private helpers called once
+    Inline,
+    /// Move public functions' bodies into private `{name}_body` helpers
+    Split,
+    /// Join nested `if`s and flatten `else { if }`
+    Flatten,
+    /// Nest `else if` as `else { if }`
+    Nest,
+    /// End bodies with tails rather than `return` or a binding
+    Tails,
+    /// Bind computed tails to a `result`
+    Bind,
+    /// Give a function's parameter, or its return when `param` is `None`,
+    /// another type; only the signature changes
+    Retype { function: String, param: Option<String>, ty: String },
+}
+
+impl Action {
+    /// The edits that move the soul `direction`'s way
+    pub(crate) fn toward(direction: Direction) -> Vec<Self> {
+        match direction {
+            Direction::Simplify => vec![Self::Inline, Self::Flatten, Self::Tails],
+            Direction::Enhance => vec![Self::Split, Self::Nest, Self::Bind],
+        }
+    }
+}
+
+impl std::str::FromStr for Action {
+    type Err = String;
+
+    /// `inline`, `split`, `flatten`, `nest`, `tails` or `bind`, or
+    /// `type <function> <param|return> <type>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["inline"] => Ok(Self::Inline),
+            ["split"] => Ok(Self::Split),
+            ["flatten"] => Ok(Self::Flatten),
+            ["nest"] => Ok(Self::Nest),
+            ["tails"] => Ok(Self::Tails),
+            ["bind"] => Ok(Self::Bind),
+            ["type", function, param, ty @ ..] if !ty.is_empty() => {
+                let ty = ty.join(" ");
+                syn::parse_str::<syn::Type>(&ty).map_err(|e| format!("`{}` isn't a type: {}", ty, e))?;
+                let param = (*param != "return").then(|| param.to_string());
+                Ok(Self::Retype { function: function.to_string(), param, ty })
+            }
+            _ => Err(format!("unknown action '{}' (expected inline, split, flatten, nest, tails, bind or type <function> <param|return> <type>)", s.trim())),
+        }
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Inline => write!(f, "inline"),
+            Self::Split => write!(f, "split"),
+            Self::Flatten => write!(f, "flatten"),
+            Self::Nest => write!(f, "nest"),
+            Self::Tails => write!(f, "tails"),
+            Self::Bind => write!(f, "bind"),
+            Self::Retype { function, param, ty } => write!(f, "type {} {} {}", function, param.as_deref().unwrap_or("return"), ty),
+        }
+    }
+}
+
+/// `rust_code` after `action`, or `None` when it changes nothing
+pub(crate) fn apply(rust_code: &str, action: &Action) -> Option<String> {
+    let file = syn::parse_file(rust_code).ok()?;
+    let mut slots: Vec<Vec<Item>> = file.items.iter().map(|item| vec![item.clone()]).collect();
+    let changed = match action {
+        Action::Inline => inline_functions(&mut slots),
+        Action::Split => extract_functions(&mut slots),
+        Action::Flatten => flatten_control_flow(&mut slots),
+        Action::Nest => nest_control_flow(&mut slots),
+        Action::Tails => inline_tails(&mut slots),
+        Action::Bind => bind_tails(&mut slots),
+        Action::Retype { function, param, ty } => retype(&mut slots, function, param.as_deref(), &syn::parse_str(ty).ok()?),
+    };
+    let edited = splice(rust_code, &file.items, &slots);
+    (changed && edited != rust_code).then_some(edited)
+}
+
+/// Rewrites the top-level items, each starting as a slot holding itself; a
+/// slot may end up empty or holding several items. Returns whether anything
+/// changed
+type Edit = fn(&mut [Vec<Item>]) -> bool;
+
+/// `rust_code` after each edit of `direction` that applies, one candidate an
+/// edit, for the caller to keep whichever brings the souls closest
+///
+/// Only the items an edit changes are reprinted, so the rest of the code
+/// keeps its layout and comments. Code syn can't parse has no candidates.
+pub(crate) fn candidates(rust_code: &str, direction: Direction) -> Vec<String> {
+    let Ok(file) = syn::parse_file(rust_code) else { return vec![] };
+    direction
+        .edits()
+        .iter()
+        .filter_map(|edit| {
+            let mut slots: Vec<Vec<Item>> = file.items.iter().map(|item| vec![item.clone()]).collect();
+            edit(&mut slots).then(|| splice(rust_code, &file.items, &slots))
+        })
+        .filter(|candidate| candidate != rust_code)
+        .collect()
+}
+
+/// `rust_code` with each item whose slot changed replaced by the slot's items
+fn splice(rust_code: &str, items: &[Item], slots: &[Vec<Item>]) -> String {
+    let mut code = rust_code.to_string();
+    for (item, slot) in items.iter().zip(slots).rev() {
+        if slot.len() == 1 && slot[0] == *item {
+            continue;
+        }
+        let span = item.span();
+        let (start, mut end) = (offset(rust_code, span.start()), offset(rust_code, span.end()));
+        let printed: Vec<String> = slot
+            .iter()
+            .map(|item| prettyplease::unparse(&File { shebang: None, attrs: vec![], items: vec![item.clone()] }).trim_end().to_string())
+            .collect();
+        // A removed item takes the blank line after it along
+        if printed.is_empty() && rust_code[end..].starts_with("\n\n") {
+            end += 2;
+        }
+        code.replace_range(start..end, &printed.join("\n\n"));
+    }
+    code
+}
+
+/// The byte offset of a line and character column
+pub(crate) fn offset(code: &str, at: LineColumn) -> usize {
+    let line_start: usize = code.split_inclusive('\n').take(at.line - 1).map(str::len).sum();
+    let line = code[line_start..].split('\n').next().unwrap_or_default();
+    line_start + line.char_indices().nth(at.column).map_or(line.len(), |(i, _)| i)
+}
+
+// Simplifying edits
+
+/// Inline each private helper called exactly once, as a block binding its
+/// parameters, and drop the helper
+fn inline_functions(slots: &mut [Vec<Item>]) -> bool {
+    let helpers: Vec<ItemFn> = slots.iter().flatten().filter_map(|item| match item {
+        Item::Fn(function) if inlinable(function) => Some(function.clone()),
+        _ => None,
+    }).collect();
+
+    let mut changed = false;
+    for helper in helpers {
+        let name = helper.sig.ident.to_string();
+        let mut uses = Uses { name: &name, paths: 0, calls: 0 };
+        slots.iter().flatten().for_each(|item| uses.visit_item(item));
+        let mut inside = Uses { name: &name, paths: 0, calls: 0 };
+        inside.visit_block(&helper.block);
+        if uses.paths != 1 || uses.calls != 1 || inside.paths != 0 {
+            continue;
+        }
+
+        let mut inline = Inline { helper: &helper, done: false };
+        for item in slots.iter_mut().flatten() {
+            inline.visit_item_mut(item);
+        }
+        if inline.done {
+            for slot in slots.iter_mut() {
+                slot.retain(|item| !matches!(item, Item::Fn(function) if function.sig.ident == helper.sig.ident));
+            }
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Private, plain and leaving only by its tail, so its body reads the same
+/// as a block where it was called
+fn inlinable(function: &ItemFn) -> bool {
+    let sig = &function.sig;
+    let mut exits = Exits(false);
+    exits.visit_block(&function.block);
+    matches!(function.vis, Visibility::Inherited)
+        && sig.asyncness.is_none()
+        && sig.constness.is_none()
+        && sig.unsafety.is_none()
+        && sig.generics.params.is_empty()
+        && function.attrs.is_empty()
+        && !exits.0
+}
+
+/// Counts the paths naming a function, and the calls among them
+struct Uses<'a> {
+    name: &'a str,
+    paths: usize,
+    calls: usize,
+}
+
+impl<'ast> Visit<'ast> for Uses<'_> {
+    fn visit_expr_path(&mut self, path: &'ast syn::ExprPath) {
+        self.paths += usize::from(path.path.is_ident(self.name));
+    }
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        if matches!(&*call.func, Expr::Path(path) if path.path.is_ident(self.name)) {
+            self.calls += 1;
+        }
+        visit::visit_expr_call(self, call);
+    }
+}
+
+/// Whether a body has a `return` or `?`, which would leave its caller once inlined
+struct Exits(bool);
+
+impl<'ast> Visit<'ast> for Exits {
+    fn visit_expr_return(&mut self, _: &'ast syn::ExprReturn) {
+        self.0 = true;
+    }
+
+    fn visit_expr_try(&mut self, _: &'ast syn::ExprTry) {
+        self.0 = true;
+    }
+}
+
+/// Replaces the call to `helper` with its body
+struct Inline<'a> {
+    helper: &'a ItemFn,
+    done: bool,
+}
+
+impl VisitMut for Inline<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Call(call) = expr {
+            if matches!(&*call.func, Expr::Path(path) if path.path.is_ident(&self.helper.sig.ident)) {
+                let mut stmts: Vec<Stmt> = self.helper.sig.inputs.iter().zip(&call.args).filter_map(|(input, arg)| {
+                    let syn::FnArg::Typed(param) = input else { return None };
+                    let pat = &param.pat;
+                    // `impl Trait` names no type a `let` can have
+                    Some(match &*param.ty {
+                        syn::Type::ImplTrait(_) => parse_quote!(let #pat = #arg;),
+                        ty => parse_quote!(let #pat: #ty = #arg;),
+                    })
+                }).collect();
+                stmts.extend(self.helper.block.stmts.iter().cloned());
+                *expr = Expr::Block(syn::ExprBlock { attrs: vec![], label: None, block: Block { brace_token: Default::default(), stmts } });
+                self.done = true;
+                return;
+            }
+        }
+        visit_mut::visit_expr_mut(self, expr);
+    }
+}
+
+/// `else { if .. }` becomes `else if ..`, and an `if` holding only another
+/// `if` joins their conditions with `&&`
+fn flatten_control_flow(slots: &mut [Vec<Item>]) -> bool {
+    struct Flatten(bool);
+
+    impl VisitMut for Flatten {
+        fn visit_expr_if_mut(&mut self, node: &mut ExprIf) {
+            visit_mut::visit_expr_if_mut(self, node);
+            if let Some((_, otherwise)) = &mut node.else_branch {
+                if let Expr::Block(block) = &**otherwise {
+                    if let (None, [Stmt::Expr(Expr::If(inner), None)]) = (&block.label, block.block.stmts.as_slice()) {
+                        **otherwise = Expr::If(inner.clone());
+                        self.0 = true;
+                    }
+                }
+                return;
+            }
+            if let [Stmt::Expr(Expr::If(inner), _)] = node.then_branch.stmts.as_slice() {
+                if inner.else_branch.is_none() && !binds(&node.cond) && !binds(&inner.cond) {
+                    let (outer, inner_cond) = (operand(&node.cond), operand(&inner.cond));
+                    node.cond = Box::new(parse_quote!(#outer && #inner_cond));
+                    node.then_branch = inner.then_branch.clone();
+                    self.0 = true;
+                }
+            }
+        }
+    }
+
+    let mut flatten = Flatten(false);
+    slots.iter_mut().flatten().for_each(|item| flatten.visit_item_mut(item));
+    flatten.0
+}
+
+/// An `if let` condition, which `&&` can't join
+fn binds(cond: &Expr) -> bool {
+    matches!(cond, Expr::Let(_))
+}
+
+/// `cond` as an operand of `&&`, parenthesized if it binds looser
+fn operand(cond: &Expr) -> Expr {
+    match cond {
+        Expr::Binary(binary) if matches!(binary.op, syn::BinOp::Or(_)) => parse_quote!((#cond)),
+        Expr::Assign(_) | Expr::Range(_) | Expr::Closure(_) => parse_quote!((#cond)),
+        _ => cond.clone(),
+    }
+}
+
+/// `return x;` ending a body becomes its tail `x`, and so does `let y = x; y`
+fn inline_tails(slots: &mut [Vec<Item>]) -> bool {
+    each_body(slots, |block| {
+        let mut changed = false;
+        if let Some(Stmt::Expr(Expr::Return(ret), Some(_))) = block.stmts.last() {
+            if let Some(value) = &ret.expr {
+                let value = (**value).clone();
+                *block.stmts.last_mut().unwrap() = Stmt::Expr(value, None);
+                changed = true;
+            }
+        }
+        if let [.., Stmt::Local(local), Stmt::Expr(Expr::Path(tail), None)] = block.stmts.as_slice() {
+            let bound = match &local.pat {
+                Pat::Ident(ident) if ident.by_ref.is_none() && ident.subpat.is_none() => Some(&ident.ident),
+                _ => None,
+            };
+            if let (Some(bound), Some(init)) = (bound, &local.init) {
+                if init.diverge.is_none() && tail.path.is_ident(bound) {
+                    let value = (*init.expr).clone();
+                    block.stmts.truncate(block.stmts.len() - 2);
+                    block.stmts.push(Stmt::Expr(value, None));
+                    changed = true;
+                }
+            }
+        }
+        changed
+    })
+}
+
+// Enhancing edits
+
+/// Move each public function's body into a private `{name}_body` it calls
+fn extract_functions(slots: &mut [Vec<Item>]) -> bool {
+    let names: Vec<String> = slots.iter().flatten().filter_map(|item| match item {
+        Item::Fn(function) => Some(function.sig.ident.to_string()),
+        _ => None,
+    }).collect();
+
+    let mut changed = false;
+    for slot in slots.iter_mut() {
+        let [Item::Fn(function)] = slot.as_slice() else { continue };
+        let helper_name = format!("{}_body", function.sig.ident);
+        if !extractable(function) || names.contains(&helper_name) {
+            continue;
+        }
+
+        let mut helper = function.clone();
+        helper.attrs.clear();
+        helper.vis = Visibility::Inherited;
+        helper.sig.ident = syn::Ident::new(&helper_name, function.sig.ident.span());
+
+        // The wrapper passes its parameters on, so none need be `mut`
+        let mut wrapper = function.clone();
+        let mut args: Vec<syn::Ident> = vec![];
+        for input in wrapper.sig.inputs.iter_mut() {
+            if let syn::FnArg::Typed(param) = input {
+                if let Pat::Ident(ident) = &mut *param.pat {
+                    ident.mutability = None;
+                    args.push(ident.ident.clone());
+                }
+            }
+        }
+        let helper_ident = &helper.sig.ident;
+        wrapper.block = Box::new(match wrapper.sig.asyncness {
+            Some(_) => parse_quote!({ #helper_ident(#(#args),*).await }),
+            None => parse_quote!({ #helper_ident(#(#args),*) }),
+        });
+
+        *slot = vec![Item::Fn(wrapper), Item::Fn(helper)];
+        changed = true;
+    }
+    changed
+}
+
+/// A public function of more than one statement, taking plain named parameters
+fn extractable(function: &ItemFn) -> bool {
+    matches!(function.vis, Visibility::Public(_))
+        && function.sig.constness.is_none()
+        && function.sig.variadic.is_none()
+        && function.block.stmts.len() > 1
+        && function.sig.inputs.iter().all(|input| matches!(input, syn::FnArg::Typed(param) if matches!(&*param.pat, Pat::Ident(ident) if ident.subpat.is_none())))
+}
+
+/// `else if ..` becomes `else { if .. }`
+fn nest_control_flow(slots: &mut [Vec<Item>]) -> bool {
+    struct Nest(bool);
+
+    impl VisitMut for Nest {
+        fn visit_expr_if_mut(&mut self, node: &mut ExprIf) {
+            visit_mut::visit_expr_if_mut(self, node);
+            if let Some((_, otherwise)) = &mut node.else_branch {
+                if let Expr::If(inner) = &**otherwise {
+                    **otherwise = parse_quote!({ #inner });
+                    self.0 = true;
+                }
+            }
+        }
+    }
+
+    let mut nest = Nest(false);
+    slots.iter_mut().flatten().for_each(|item| nest.visit_item_mut(item));
+    nest.0
+}
+
+/// A computed tail `x` becomes `let result = x; result`
+fn bind_tails(slots: &mut [Vec<Item>]) -> bool {
+    each_body(slots, |block| {
+        let Some(Stmt::Expr(tail, None)) = block.stmts.last() else { return false };
+        if matches!(tail, Expr::Path(_) | Expr::Lit(_) | Expr::Block(_) | Expr::If(_) | Expr::Match(_) | Expr::Loop(_) | Expr::Macro(_)) {
+            return false;
+        }
+        let tail = tail.clone();
+        *block.stmts.last_mut().unwrap() = parse_quote!(let result = #tail;);
+        block.stmts.push(Stmt::Expr(parse_quote!(result), None));
+        true
+    })
+}
+
+// Hand edits
+
+/// Give `function`'s parameter `param`, or its return, the type `ty`
+fn retype(slots: &mut [Vec<Item>], function: &str, param: Option<&str>, ty: &syn::Type) -> bool {
+    struct Retype<'a> {
+        function: &'a str,
+        param: Option<&'a str>,
+        ty: &'a syn::Type,
+        changed: bool,
+    }
+
+    impl Retype<'_> {
+        fn signature(&mut self, sig: &mut syn::Signature) {
+            if sig.ident != self.function {
+                return;
+            }
+            match self.param {
+                Some(name) => {
+                    for input in sig.inputs.iter_mut() {
+                        if let syn::FnArg::Typed(param) = input {
+                            if matches!(&*param.pat, Pat::Ident(ident) if ident.ident == name) && *param.ty != *self.ty {
+                                *param.ty = self.ty.clone();
+                                self.changed = true;
+                            }
+                        }
+                    }
+                }
+                None => {
+                    let ty = self.ty.clone();
+                    let output: syn::ReturnType = parse_quote!(-> #ty);
+                    if sig.output != output {
+                        sig.output = output;
+                        self.changed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    impl VisitMut for Retype<'_> {
+        fn visit_item_fn_mut(&mut self, function: &mut ItemFn) {
+            self.signature(&mut function.sig);
+            visit_mut::visit_item_fn_mut(self, function);
+        }
+
+        fn visit_impl_item_fn_mut(&mut self, method: &mut ImplItemFn) {
+            self.signature(&mut method.sig);
+            visit_mut::visit_impl_item_fn_mut(self, method);
+        }
+    }
+
+    let mut retype = Retype { function, param, ty, changed: false };
+    slots.iter_mut().flatten().for_each(|item| retype.visit_item_mut(item));
+    retype.changed
+}
+
+/// Apply `edit` to the body of each function and method, returning whether
+/// it changed any
+fn each_body(slots: &mut [Vec<Item>], edit: impl FnMut(&mut Block) -> bool) -> bool {
+    struct Bodies<F> {
+        edit: F,
+        changed: bool,
+    }
+
+    impl<F: FnMut(&mut Block) -> bool> VisitMut for Bodies<F> {
+        fn visit_item_fn_mut(&mut self, function: &mut ItemFn) {
+            self.changed |= (self.edit)(&mut function.block);
+        }
+
+        fn visit_impl_item_fn_mut(&mut self, method: &mut ImplItemFn) {
+            self.changed |= (self.edit)(&mut method.block);
+        }
+
+        fn visit_trait_item_fn_mut(&mut self, method: &mut TraitItemFn) {
+            if let Some(block) = &mut method.default {
+                self.changed |= (self.edit)(block);
+            }
+        }
+    }
+
+    let mut bodies = Bodies { edit, changed: false };
+    slots.iter_mut().flatten().for_each(|item| bodies.visit_item_mut(item));
+    bodies.changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simplifying_inlines_and_flattens() {
+        let code = "// Generated\n\nfn half(x: f64) -> f64 {\n    x / 2.0\n}\n\npub fn size(x: f64) -> String {\n    if x > 1.0 {\n        if x < 9.0 {\n            return \"mid\".to_string();\n        }\n    }\n    let size = half(x).to_string();\n    size\n}\n";
+        let candidates = candidates(code, Direction::Simplify);
+        assert_eq!(candidates.len(), 3);
+
+        let inlined = &candidates[0];
+        assert!(inlined.starts_with("// Generated\n\npub fn size(x: f64) -> String {"));
+        assert!(inlined.contains("let size = {\n        let x: f64 = x;\n        x / 2.0\n    }\n        .to_string();"));
+        assert!(candidates[1].contains("if x > 1.0 && x < 9.0 {\n        return \"mid\".to_string();\n    }"));
+        assert!(candidates[2].contains("half(x).to_string()\n}"));
+    }
+
+    #[test]
+    fn test_enhancing_extracts_and_nests() {
+        let code = "pub fn sign(x: f64) -> f64 {\n    let y = x;\n    if y > 0.0 { 1.0 } else if y < 0.0 { -1.0 } else { y * 0.0 }\n}\n";
+        let candidates = candidates(code, Direction::Enhance);
+        assert_eq!(candidates.len(), 2);
+
+        assert!(candidates[0].starts_with("pub fn sign(x: f64) -> f64 {\n    sign_body(x)\n}\n\nfn sign_body(x: f64) -> f64 {"));
+        assert!(candidates[1].contains("if y > 0.0 { 1.0 } else { if y < 0.0 { -1.0 } else { y * 0.0 } }"));
+        assert_eq!(Direction::of(&[1.0, 2.0], &[2.0, 2.5]), Direction::Simplify);
+    }
+
+    #[test]
+    fn test_actions_picked_by_hand() {
+        let code = "pub fn scale(x: f64, by: f64) -> f64 {\n    x * by\n}\n";
+        let action: Action = "type scale by i32".parse().unwrap();
+        assert_eq!(apply(code, &action).unwrap(), "pub fn scale(x: f64, by: i32) -> f64 {\n    x * by\n}\n");
+        assert_eq!(apply(code, &"type scale return f32".parse().unwrap()).unwrap(), "pub fn scale(x: f64, by: f64) -> f32 {\n    x * by\n}\n");
+        assert_eq!(apply(code, &Action::Split), None);
+        assert_eq!(action.to_string(), "type scale by i32");
+        assert!("type scale by".parse::<Action>().is_err());
+    }
+}
@@ -0,0 +1,305 @@
+// 📜 Transmutation Reports - What Reviewers See Before Adopting a Port
+use crate::check::Diagnostic;
+use crate::{bench, plan, PlannedItem, Transmutation};
+use protein_hash::Soul;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// Length of the gauge's semicircle, radius 80, which the resonance fills
+const GAUGE_LENGTH: f64 = std::f64::consts::PI * 80.0;
+
+/// Compiler messages a report lists before eliding the rest
+const LISTED_ERRORS: usize = 5;
+
+/// What a fresh check of the stored code found
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Build {
+    pub(crate) errors: usize,
+    pub(crate) warnings: usize,
+    /// The first errors, as rendered by the compiler
+    pub(crate) messages: Vec<String>,
+    /// Equivalence tests passed on the checked code, of how many, when it
+    /// built and has them
+    pub(crate) tests: Option<(usize, usize)>,
+}
+
+impl Build {
+    pub(crate) fn of(diagnostics: &[Diagnostic]) -> Self {
+        let errors: Vec<&Diagnostic> = diagnostics.iter().filter(|diagnostic| diagnostic.is_error()).collect();
+        Self {
+            errors: errors.len(),
+            warnings: diagnostics.len() - errors.len(),
+            messages: errors.iter().take(LISTED_ERRORS).map(|diagnostic| diagnostic.rendered.trim().to_string()).collect(),
+            tests: None,
+        }
+    }
+}
+
+/// One measure of both souls, with bar widths relative to the larger
+#[derive(Debug, Clone, Serialize)]
+struct Comparison {
+    label: String,
+    js: f64,
+    rust: f64,
+    delta: f64,
+    js_width: f64,
+    rust_width: f64,
+}
+
+impl Comparison {
+    fn new(label: impl Into<String>, js: f64, rust: f64, scale: f64) -> Self {
+        let width = |value: f64| if scale > 0.0 { (value.abs() / scale * 100.0).min(100.0) } else { 0.0 };
+        Self { label: label.into(), js, rust, delta: rust - js, js_width: width(js), rust_width: width(rust) }
+    }
+}
+
+/// One character of the souls' perceptual hashes, and whether they agree
+#[derive(Debug, Clone, Serialize)]
+struct HashDigit {
+    js: char,
+    rust: char,
+    same: bool,
+}
+
+/// The template context of `transmutation`'s report
+pub(crate) fn context(transmutation: &Transmutation, build: Option<&Build>) -> tera::Context {
+    let (js, rust) = (&transmutation.js_soul, &transmutation.rust_soul);
+    let metadata = &transmutation.metadata;
+    let mut context = tera::Context::new();
+    context.insert("npm_name", &metadata.npm_name);
+    context.insert("crate_name", &metadata.crate_name);
+    context.insert("version", &metadata.version);
+    context.insert("license", &metadata.license);
+    context.insert("bindings", &format!("{:?}", metadata.bindings));
+    context.insert("transmuted_at", &date(transmutation.transmuted_at));
+    context.insert("tags", &transmutation.tags);
+
+    context.insert("resonance", &transmutation.resonance);
+    context.insert("souls_match", &protein_hash::souls_match(js, rust));
+    context.insert("gauge_length", &GAUGE_LENGTH);
+    context.insert("gauge_filled", &(transmutation.resonance.clamp(0.0, 1.0) * GAUGE_LENGTH));
+    context.insert("hash_digits", &hash_digits(&js.phash, &rust.phash));
+    context.insert("hash_agreement", &(hash_agreement(&js.phash, &rust.phash) * 100.0).round());
+    context.insert("eigenvalues", &eigenvalues(js, rust));
+    context.insert("operations", &operations(js, rust));
+    context.insert("metrics", &metrics(js, rust));
+
+    let items: Vec<PlannedItem> = plan::items(&transmutation.rust_code);
+    let mut listed = vec![];
+    list_items(&items, "", &mut listed);
+    context.insert("items", &listed);
+    context.insert("rust_code", &transmutation.rust_code);
+    context.insert("unfinished", &bench::unfinished(&transmutation.rust_code));
+    context.insert("build", &build);
+    context.insert("pass_percent", &transmutation.pass_rate.map(|rate| (rate * 100.0).round()));
+    let cases = transmutation.equivalence_tests.as_deref().map(|tests| tests.matches("#[test]").count());
+    context.insert("equivalence_cases", &cases);
+    let benchmarks: Vec<_> = transmutation.benchmarks.iter()
+        .map(|benchmark| serde_json::json!({ "function": benchmark.function, "js_ns": benchmark.js_ns, "rust_ns": benchmark.rust_ns, "speedup": benchmark.speedup() }))
+        .collect();
+    context.insert("benchmarks", &benchmarks);
+    context
+}
+
+/// `items` as the API lists them, each module's after its parent's, named
+/// by its path
+fn list_items(items: &[PlannedItem], module: &str, listed: &mut Vec<serde_json::Value>) {
+    let (modules, items): (Vec<&PlannedItem>, Vec<&PlannedItem>) = items.iter().partition(|item| item.kind == "mod");
+    listed.extend(items.into_iter().map(|item| json_item(item, module)));
+    for child in modules {
+        let name = child.signature.trim_start_matches("pub ").trim_start_matches("mod ");
+        let path = if module.is_empty() { name.to_string() } else { format!("{}::{}", module, name) };
+        list_items(&child.members, &path, listed);
+    }
+}
+
+fn json_item(item: &PlannedItem, module: &str) -> serde_json::Value {
+    serde_json::json!({
+        "module": module,
+        "kind": item.kind,
+        "signature": item.signature,
+        "origin": item.origin,
+        "members": item.members.iter().map(|member| serde_json::json!({ "signature": member.signature, "origin": member.origin })).collect::<Vec<_>>(),
+    })
+}
+
+fn hash_digits(js: &str, rust: &str) -> Vec<HashDigit> {
+    js.chars().zip(rust.chars()).map(|(js, rust)| HashDigit { js, rust, same: js == rust }).collect()
+}
+
+/// Share of the hashes' characters that agree
+fn hash_agreement(js: &str, rust: &str) -> f64 {
+    let digits = hash_digits(js, rust);
+    digits.iter().filter(|digit| digit.same).count() as f64 / digits.len().max(1) as f64
+}
+
+/// The eigenvalue layers side by side
+fn eigenvalues(js: &Soul, rust: &Soul) -> Vec<Comparison> {
+    let scale = js.eigenvalues.iter().chain(&rust.eigenvalues).fold(0.0_f64, |max, value| max.max(value.abs()));
+    (0..js.eigenvalues.len().max(rust.eigenvalues.len()))
+        .map(|layer| {
+            let value = |values: &[f64]| values.get(layer).copied().unwrap_or_default();
+            Comparison::new(format!("λ{}", layer + 1), value(&js.eigenvalues), value(&rust.eigenvalues), scale)
+        })
+        .collect()
+}
+
+/// The share of each kind of operation, in either soul
+fn operations(js: &Soul, rust: &Soul) -> Vec<Comparison> {
+    let share = |soul: &Soul, kind: &str| soul.semantics.operations.iter()
+        .find(|(operation, _)| format!("{:?}", operation) == kind)
+        .map_or(0.0, |(_, share)| *share);
+    let kinds: BTreeSet<String> = js.semantics.operations.keys().chain(rust.semantics.operations.keys())
+        .map(|operation| format!("{:?}", operation))
+        .collect();
+    let scale = kinds.iter().fold(0.0_f64, |max, kind| max.max(share(js, kind)).max(share(rust, kind)));
+    kinds.iter().map(|kind| Comparison::new(kind.clone(), share(js, kind), share(rust, kind), scale)).collect()
+}
+
+/// The souls' structural measures, without bars
+fn metrics(js: &Soul, rust: &Soul) -> Vec<Comparison> {
+    let pairs = [
+        ("Cyclomatic complexity", js.semantics.cyclomatic as f64, rust.semantics.cyclomatic as f64),
+        ("Cognitive complexity", js.semantics.cognitive as f64, rust.semantics.cognitive as f64),
+        ("Dependency depth", js.semantics.depth as f64, rust.semantics.depth as f64),
+        ("Nesting depth", js.topology_features.nesting_depth as f64, rust.topology_features.nesting_depth as f64),
+        ("Loop complexity", js.topology_features.loop_complexity as f64, rust.topology_features.loop_complexity as f64),
+        ("Branching factor", js.topology_features.branching_factor, rust.topology_features.branching_factor),
+        ("Graph diameter", js.topology.diameter as f64, rust.topology.diameter as f64),
+        ("Euler characteristic", js.topology.euler_char as f64, rust.topology.euler_char as f64),
+        ("Clustering", js.topology.clustering, rust.topology.clustering),
+        ("Modularity", js.topology.modularity, rust.topology.modularity),
+        ("Coherence", js.coherence, rust.coherence),
+    ];
+    pairs.into_iter().map(|(label, js, rust)| Comparison::new(label, js, rust, 0.0)).collect()
+}
+
+/// `seconds` since the Unix epoch as a UTC date and time
+fn date(seconds: u64) -> String {
+    // Days to a civil date, after Howard Hinnant's `civil_from_days`
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    let time = seconds % 86_400;
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, time / 3_600, time % 3_600 / 60)
+}
+
+/// `report_html.tera`: a page standing alone, styles and gauge included
+pub(crate) const TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{{ npm_name | escape }} → {{ crate_name | escape }}: transmutation report</title>
+<style>
+  body { font: 15px/1.5 system-ui, sans-serif; max-width: 960px; margin: 2em auto; padding: 0 1em; color: #1d1d2c; }
+  h1 { margin-bottom: 0; } h2 { border-bottom: 1px solid #ddd; padding-bottom: .2em; margin-top: 2em; }
+  .meta { color: #666; } .meta span { margin-right: 1.2em; }
+  .tag { background: #eef; border-radius: 3px; padding: 0 .4em; }
+  .gauge { display: flex; align-items: center; gap: 2em; }
+  .gauge text { font: bold 28px system-ui, sans-serif; }
+  .ok { color: #1a7f37; } .warn { color: #9a6700; } .bad { color: #cf222e; }
+  table { border-collapse: collapse; width: 100%; } td, th { padding: .25em .6em; text-align: left; vertical-align: top; }
+  tr:nth-child(even) { background: #f7f7fb; } td.num, th.num { text-align: right; font-variant-numeric: tabular-nums; }
+  .bars div { height: 7px; border-radius: 2px; margin: 2px 0; } .js { background: #e8b400; } .rust { background: #b7410e; }
+  .legend .js, .legend .rust { display: inline-block; width: 1em; height: .7em; margin: 0 .3em 0 1em; }
+  .hash { font-family: ui-monospace, monospace; letter-spacing: .05em; word-break: break-all; }
+  .hash .diff { background: #ffd7d5; }
+  code, pre { font-family: ui-monospace, monospace; font-size: 13px; } pre { background: #f6f8fa; padding: 1em; overflow-x: auto; }
+  .origin { color: #888; font-size: 12px; } .member { padding-left: 2em; }
+</style>
+</head>
+<body>
+<h1>{{ npm_name | escape }} → <code>{{ crate_name | escape }}</code></h1>
+<p class="meta">
+  <span>Version {{ version | escape }}</span>
+  <span>Transmuted {{ transmuted_at }}</span>
+  <span>{{ bindings }} bindings</span>
+  {% if license %}<span>{{ license | escape }}</span>{% endif %}
+  {% for tag in tags %}<span class="tag">{{ tag | escape }}</span>{% endfor %}
+</p>
+
+<h2>Resonance</h2>
+<div class="gauge">
+  <svg width="200" height="120" viewBox="0 0 200 120" role="img" aria-label="Resonance {{ resonance | round(precision=3) }}">
+    <path d="M 20 100 A 80 80 0 0 1 180 100" fill="none" stroke="#e5e5ef" stroke-width="18"/>
+    <path d="M 20 100 A 80 80 0 0 1 180 100" fill="none" stroke="{% if resonance > 0.95 %}#1a7f37{% elif resonance > 0.8 %}#d4a72c{% else %}#cf222e{% endif %}" stroke-width="18" stroke-dasharray="{{ gauge_filled }} {{ gauge_length }}"/>
+    <text x="100" y="96" text-anchor="middle">{{ resonance | round(precision=3) }}</text>
+  </svg>
+  <div>
+    {% if resonance > 0.95 %}<p class="ok">✅ Perfect transmutation</p>{% elif resonance > 0.8 %}<p class="warn">⚠️ Good transmutation</p>{% else %}<p class="bad">❌ Weak transmutation</p>{% endif %}
+    <p>The souls {% if souls_match %}<strong class="ok">match</strong>{% else %}<strong class="bad">don't match</strong>{% endif %}; their perceptual hashes agree on {{ hash_agreement }}% of digits.</p>
+  </div>
+</div>
+
+<h2>Soul diff</h2>
+<p class="legend">Bars compare the souls<span class="js"></span>JavaScript<span class="rust"></span>Rust</p>
+<h3>Perceptual hash</h3>
+<p class="hash">JS&nbsp;&nbsp;&nbsp;{% for digit in hash_digits %}<span{% if not digit.same %} class="diff"{% endif %}>{{ digit.js }}</span>{% endfor %}</p>
+<p class="hash">Rust&nbsp;{% for digit in hash_digits %}<span{% if not digit.same %} class="diff"{% endif %}>{{ digit.rust }}</span>{% endfor %}</p>
+<h3>Eigenvalue layers</h3>
+<table>
+  <tr><th>Layer</th><th class="num">JS</th><th class="num">Rust</th><th style="width: 50%"></th></tr>
+  {% for layer in eigenvalues %}<tr><td>{{ layer.label }}</td><td class="num">{{ layer.js | round(precision=3) }}</td><td class="num">{{ layer.rust | round(precision=3) }}</td>
+    <td class="bars"><div class="js" style="width: {{ layer.js_width }}%"></div><div class="rust" style="width: {{ layer.rust_width }}%"></div></td></tr>
+  {% endfor %}
+</table>
+{% if operations %}<h3>Operations</h3>
+<table>
+  <tr><th>Kind</th><th class="num">JS</th><th class="num">Rust</th><th style="width: 50%"></th></tr>
+  {% for operation in operations %}<tr><td>{{ operation.label }}</td><td class="num">{{ operation.js | round(precision=3) }}</td><td class="num">{{ operation.rust | round(precision=3) }}</td>
+    <td class="bars"><div class="js" style="width: {{ operation.js_width }}%"></div><div class="rust" style="width: {{ operation.rust_width }}%"></div></td></tr>
+  {% endfor %}
+</table>{% endif %}
+<h3>Structure</h3>
+<table>
+  <tr><th>Measure</th><th class="num">JS</th><th class="num">Rust</th><th class="num">Δ</th></tr>
+  {% for metric in metrics %}<tr><td>{{ metric.label }}</td><td class="num">{{ metric.js | round(precision=3) }}</td><td class="num">{{ metric.rust | round(precision=3) }}</td><td class="num">{{ metric.delta | round(precision=3) }}</td></tr>
+  {% endfor %}
+</table>
+
+<h2>Status</h2>
+<table>
+  <tr><td>Compiles</td><td>{% if build %}{% if build.errors == 0 %}<span class="ok">✅ builds</span>{% if build.warnings > 0 %}, with {{ build.warnings }} warnings{% endif %}{% else %}<span class="bad">❌ {{ build.errors }} errors</span>{% endif %}{% else %}Not checked; <code>forge report --verify check</code> checks it{% endif %}</td></tr>
+  <tr><td>Unfinished</td><td>{% if unfinished %}<span class="warn">{{ unfinished | length }} left as <code>todo!</code>:</span> {% for name in unfinished %}<code>{{ name | escape }}</code>{% if not loop.last %}, {% endif %}{% endfor %}{% else %}<span class="ok">every function has a body</span>{% endif %}</td></tr>
+  <tr><td>Equivalence</td><td>{% if build and build.tests %}{% if build.tests.0 == build.tests.1 %}<span class="ok">{% else %}<span class="warn">{% endif %}{{ build.tests.0 }} of {{ build.tests.1 }} calls behave as in JS</span>{% elif pass_percent is number %}{% if pass_percent >= 100 %}<span class="ok">{% else %}<span class="warn">{% endif %}{{ pass_percent }}% of {{ equivalence_cases }} calls behave as in JS</span>, when transmuted{% else %}Not compared; <code>forge transmute --equivalence</code> compares it{% endif %}</td></tr>
+</table>
+{% if build %}{% for message in build.messages %}<pre>{{ message | escape }}</pre>
+{% endfor %}{% endif %}
+{% if benchmarks %}<h3>Speedups</h3>
+<table>
+  <tr><th>Function</th><th class="num">JS</th><th class="num">Rust</th><th class="num">Speedup</th></tr>
+  {% for benchmark in benchmarks %}<tr><td><code>{{ benchmark.function | escape }}</code></td><td class="num">{{ benchmark.js_ns | round }} ns</td><td class="num">{{ benchmark.rust_ns | round }} ns</td><td class="num {% if benchmark.speedup >= 1 %}ok{% else %}bad{% endif %}">{{ benchmark.speedup | round(precision=2) }}×</td></tr>
+  {% endfor %}
+</table>{% endif %}
+
+<h2>API</h2>
+<table>
+  {% for item in items %}<tr><td>{{ item.kind }}</td><td>{% if item.module %}<span class="origin">{{ item.module }}::</span>{% endif %}<code>{{ item.signature | escape }}</code>{% if item.origin %} <span class="origin">{{ item.origin | escape }}</span>{% endif %}
+    {% for member in item.members %}<div class="member"><code>{{ member.signature | escape }}</code>{% if member.origin %} <span class="origin">{{ member.origin | escape }}</span>{% endif %}</div>{% endfor %}</td></tr>
+  {% endfor %}
+</table>
+<details>
+<summary>Generated code</summary>
+<pre>{{ rust_code | escape }}</pre>
+</details>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dates_are_utc() {
+        assert_eq!(date(0), "1970-01-01 00:00 UTC");
+        assert_eq!(date(951_782_400), "2000-02-29 00:00 UTC");
+        assert_eq!(date(1_792_206_245), "2026-10-17 03:04 UTC");
+    }
+}
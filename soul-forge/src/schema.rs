@@ -0,0 +1,682 @@
+// 🧬 Database Schema - Keeping Old Transmutations Readable
+use crate::query::PREFIX;
+use crate::{Benches, Benchmark, Bindings, Diagnostic, PackageMetadata, Profile, Transmutation, TransmutationError};
+use bincode::Options;
+use protein_hash::Soul;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Layout of the records this forge writes; a change to `Transmutation`
+/// bumps it and adds a migration from the layout before
+pub const SCHEMA_VERSION: u32 = 9;
+
+/// Where the database keeps its schema version, next to the records
+const VERSION_KEY: &str = "schema:version";
+
+/// Package metadata up to layout 3
+#[derive(Serialize, Deserialize)]
+struct PackageMetadataV1 {
+    name: String,
+    version: String,
+    description: String,
+    npm_name: String,
+    crate_name: String,
+}
+
+/// Package metadata in layout 4, which added the source file
+#[derive(Serialize, Deserialize)]
+struct PackageMetadataV2 {
+    name: String,
+    version: String,
+    description: String,
+    npm_name: String,
+    crate_name: String,
+    source_file: Option<String>,
+}
+
+/// Package metadata in layout 5, which added the bindings
+#[derive(Serialize, Deserialize)]
+struct PackageMetadataV3 {
+    name: String,
+    version: String,
+    description: String,
+    npm_name: String,
+    crate_name: String,
+    source_file: Option<String>,
+    bindings: Bindings,
+}
+
+/// Package metadata in layout 6, which added what `package.json` says of
+/// the package
+#[derive(Serialize, Deserialize)]
+struct PackageMetadataV4 {
+    name: String,
+    version: String,
+    description: String,
+    npm_name: String,
+    crate_name: String,
+    source_file: Option<String>,
+    bindings: Bindings,
+    license: Option<String>,
+    authors: Vec<String>,
+    repository: Option<String>,
+    keywords: Vec<String>,
+}
+
+/// Package metadata in layout 7
+#[derive(Serialize, Deserialize)]
+struct PackageMetadataV5 {
+    name: String,
+    version: String,
+    description: String,
+    npm_name: String,
+    crate_name: String,
+    source_file: Option<String>,
+    bindings: Bindings,
+    license: Option<String>,
+    authors: Vec<String>,
+    repository: Option<String>,
+    keywords: Vec<String>,
+}
+
+/// Layout 1, the first one stored
+#[derive(Serialize, Deserialize)]
+struct TransmutationV1 {
+    js_soul: Soul,
+    rust_soul: Soul,
+    rust_code: String,
+    wasm_bindings: String,
+    resonance: f64,
+    metadata: PackageMetadataV1,
+}
+
+/// Layout 2, which added the equivalence tests and their pass rate
+#[derive(Serialize, Deserialize)]
+struct TransmutationV2 {
+    js_soul: Soul,
+    rust_soul: Soul,
+    rust_code: String,
+    wasm_bindings: String,
+    resonance: f64,
+    metadata: PackageMetadataV1,
+    equivalence_tests: Option<String>,
+    pass_rate: Option<f64>,
+}
+
+/// Layout 3, which added the date and tags
+#[derive(Serialize, Deserialize)]
+struct TransmutationV3 {
+    js_soul: Soul,
+    rust_soul: Soul,
+    rust_code: String,
+    wasm_bindings: String,
+    resonance: f64,
+    metadata: PackageMetadataV1,
+    equivalence_tests: Option<String>,
+    pass_rate: Option<f64>,
+    transmuted_at: u64,
+    tags: Vec<String>,
+}
+
+/// Layout 4, which added the source map
+#[derive(Serialize, Deserialize)]
+struct TransmutationV4 {
+    js_soul: Soul,
+    rust_soul: Soul,
+    rust_code: String,
+    wasm_bindings: String,
+    resonance: f64,
+    metadata: PackageMetadataV2,
+    equivalence_tests: Option<String>,
+    pass_rate: Option<f64>,
+    transmuted_at: u64,
+    tags: Vec<String>,
+    source_map: String,
+}
+
+/// Layout 5, which added the bindings and named them for either kind
+#[derive(Serialize, Deserialize)]
+struct TransmutationV5 {
+    js_soul: Soul,
+    rust_soul: Soul,
+    rust_code: String,
+    js_bindings: String,
+    resonance: f64,
+    metadata: PackageMetadataV3,
+    equivalence_tests: Option<String>,
+    pass_rate: Option<f64>,
+    transmuted_at: u64,
+    tags: Vec<String>,
+    source_map: String,
+}
+
+/// Layout 6, which added the license, authors, repository and keywords
+#[derive(Serialize, Deserialize)]
+struct TransmutationV6 {
+    js_soul: Soul,
+    rust_soul: Soul,
+    rust_code: String,
+    js_bindings: String,
+    resonance: f64,
+    metadata: PackageMetadataV4,
+    equivalence_tests: Option<String>,
+    pass_rate: Option<f64>,
+    transmuted_at: u64,
+    tags: Vec<String>,
+    source_map: String,
+}
+
+/// Layout 7, which added the benchmarks
+#[derive(Serialize, Deserialize)]
+struct TransmutationV7 {
+    js_soul: Soul,
+    rust_soul: Soul,
+    rust_code: String,
+    js_bindings: String,
+    resonance: f64,
+    metadata: PackageMetadataV5,
+    equivalence_tests: Option<String>,
+    pass_rate: Option<f64>,
+    transmuted_at: u64,
+    tags: Vec<String>,
+    source_map: String,
+    benches: Option<Benches>,
+    benchmarks: Vec<Benchmark>,
+}
+
+/// Layout 8, which added the profile
+#[derive(Serialize, Deserialize)]
+struct TransmutationV8 {
+    js_soul: Soul,
+    rust_soul: Soul,
+    rust_code: String,
+    js_bindings: String,
+    resonance: f64,
+    metadata: PackageMetadata,
+    equivalence_tests: Option<String>,
+    pass_rate: Option<f64>,
+    transmuted_at: u64,
+    tags: Vec<String>,
+    source_map: String,
+    benches: Option<Benches>,
+    benchmarks: Vec<Benchmark>,
+}
+
+/// Rewrites a record from layout `to - 1` into layout `to`
+struct Migration {
+    to: u32,
+    upgrade: fn(&[u8]) -> bincode::Result<Vec<u8>>,
+}
+
+/// Every layout change, oldest first
+const MIGRATIONS: &[Migration] = &[
+    Migration { to: 2, upgrade: add_equivalence },
+    Migration { to: 3, upgrade: add_date_and_tags },
+    Migration { to: 4, upgrade: add_source_map },
+    Migration { to: 5, upgrade: add_bindings },
+    Migration { to: 6, upgrade: add_package_info },
+    Migration { to: 7, upgrade: add_benches },
+    Migration { to: 8, upgrade: add_profile },
+    Migration { to: 9, upgrade: add_warnings },
+];
+
+fn add_equivalence(bytes: &[u8]) -> bincode::Result<Vec<u8>> {
+    let old: TransmutationV1 = decode(bytes)?;
+    bincode::serialize(&TransmutationV2 {
+        js_soul: old.js_soul,
+        rust_soul: old.rust_soul,
+        rust_code: old.rust_code,
+        wasm_bindings: old.wasm_bindings,
+        resonance: old.resonance,
+        metadata: old.metadata,
+        equivalence_tests: None,
+        pass_rate: None,
+    })
+}
+
+/// Records from before dates were kept are dated 0, the Unix epoch
+fn add_date_and_tags(bytes: &[u8]) -> bincode::Result<Vec<u8>> {
+    let old: TransmutationV2 = decode(bytes)?;
+    bincode::serialize(&TransmutationV3 {
+        js_soul: old.js_soul,
+        rust_soul: old.rust_soul,
+        rust_code: old.rust_code,
+        wasm_bindings: old.wasm_bindings,
+        resonance: old.resonance,
+        metadata: old.metadata,
+        equivalence_tests: old.equivalence_tests,
+        pass_rate: old.pass_rate,
+        transmuted_at: 0,
+        tags: vec![],
+    })
+}
+
+/// Records from before source maps were kept get an empty one, naming no
+/// source file
+fn add_source_map(bytes: &[u8]) -> bincode::Result<Vec<u8>> {
+    let old: TransmutationV3 = decode(bytes)?;
+    bincode::serialize(&TransmutationV4 {
+        js_soul: old.js_soul,
+        rust_soul: old.rust_soul,
+        rust_code: old.rust_code,
+        wasm_bindings: old.wasm_bindings,
+        resonance: old.resonance,
+        metadata: PackageMetadataV2 {
+            name: old.metadata.name,
+            version: old.metadata.version,
+            description: old.metadata.description,
+            npm_name: old.metadata.npm_name,
+            crate_name: old.metadata.crate_name,
+            source_file: None,
+        },
+        equivalence_tests: old.equivalence_tests,
+        pass_rate: old.pass_rate,
+        transmuted_at: old.transmuted_at,
+        tags: old.tags,
+        source_map: String::new(),
+    })
+}
+
+/// Every record from before napi-rs addons were made is WASM's
+fn add_bindings(bytes: &[u8]) -> bincode::Result<Vec<u8>> {
+    let old: TransmutationV4 = decode(bytes)?;
+    bincode::serialize(&TransmutationV5 {
+        js_soul: old.js_soul,
+        rust_soul: old.rust_soul,
+        rust_code: old.rust_code,
+        js_bindings: old.wasm_bindings,
+        resonance: old.resonance,
+        metadata: PackageMetadataV3 {
+            name: old.metadata.name,
+            version: old.metadata.version,
+            description: old.metadata.description,
+            npm_name: old.metadata.npm_name,
+            crate_name: old.metadata.crate_name,
+            source_file: old.metadata.source_file,
+            bindings: Bindings::Wasm,
+        },
+        equivalence_tests: old.equivalence_tests,
+        pass_rate: old.pass_rate,
+        transmuted_at: old.transmuted_at,
+        tags: old.tags,
+        source_map: old.source_map,
+    })
+}
+
+/// Records from before package.json was read have no license, authors,
+/// repository or keywords
+fn add_package_info(bytes: &[u8]) -> bincode::Result<Vec<u8>> {
+    let old: TransmutationV5 = decode(bytes)?;
+    bincode::serialize(&TransmutationV6 {
+        js_soul: old.js_soul,
+        rust_soul: old.rust_soul,
+        rust_code: old.rust_code,
+        js_bindings: old.js_bindings,
+        resonance: old.resonance,
+        metadata: PackageMetadataV4 {
+            name: old.metadata.name,
+            version: old.metadata.version,
+            description: old.metadata.description,
+            npm_name: old.metadata.npm_name,
+            crate_name: old.metadata.crate_name,
+            source_file: old.metadata.source_file,
+            bindings: old.metadata.bindings,
+            license: None,
+            authors: vec![],
+            repository: None,
+            keywords: vec![],
+        },
+        equivalence_tests: old.equivalence_tests,
+        pass_rate: old.pass_rate,
+        transmuted_at: old.transmuted_at,
+        tags: old.tags,
+        source_map: old.source_map,
+    })
+}
+
+/// Records from before benchmarks were generated have none, and nothing
+/// measured
+fn add_benches(bytes: &[u8]) -> bincode::Result<Vec<u8>> {
+    let old: TransmutationV6 = decode(bytes)?;
+    bincode::serialize(&TransmutationV7 {
+        js_soul: old.js_soul,
+        rust_soul: old.rust_soul,
+        rust_code: old.rust_code,
+        js_bindings: old.js_bindings,
+        resonance: old.resonance,
+        metadata: PackageMetadataV5 {
+            name: old.metadata.name,
+            version: old.metadata.version,
+            description: old.metadata.description,
+            npm_name: old.metadata.npm_name,
+            crate_name: old.metadata.crate_name,
+            source_file: old.metadata.source_file,
+            bindings: old.metadata.bindings,
+            license: old.metadata.license,
+            authors: old.metadata.authors,
+            repository: old.metadata.repository,
+            keywords: old.metadata.keywords,
+        },
+        equivalence_tests: old.equivalence_tests,
+        pass_rate: old.pass_rate,
+        transmuted_at: old.transmuted_at,
+        tags: old.tags,
+        source_map: old.source_map,
+        benches: None,
+        benchmarks: vec![],
+    })
+}
+
+/// Every record from before profiles were chosen was built for the web
+fn add_profile(bytes: &[u8]) -> bincode::Result<Vec<u8>> {
+    let old: TransmutationV7 = decode(bytes)?;
+    bincode::serialize(&TransmutationV8 {
+        js_soul: old.js_soul,
+        rust_soul: old.rust_soul,
+        rust_code: old.rust_code,
+        js_bindings: old.js_bindings,
+        resonance: old.resonance,
+        metadata: PackageMetadata {
+            name: old.metadata.name,
+            version: old.metadata.version,
+            description: old.metadata.description,
+            npm_name: old.metadata.npm_name,
+            crate_name: old.metadata.crate_name,
+            source_file: old.metadata.source_file,
+            bindings: old.metadata.bindings,
+            profile: Profile::WasmWeb,
+            license: old.metadata.license,
+            authors: old.metadata.authors,
+            repository: old.metadata.repository,
+            keywords: old.metadata.keywords,
+        },
+        equivalence_tests: old.equivalence_tests,
+        pass_rate: old.pass_rate,
+        transmuted_at: old.transmuted_at,
+        tags: old.tags,
+        source_map: old.source_map,
+        benches: old.benches,
+        benchmarks: old.benchmarks,
+    })
+}
+
+/// Records from before drift linting have no warnings; `forge transmute`
+/// finds them again
+fn add_warnings(bytes: &[u8]) -> bincode::Result<Vec<u8>> {
+    let old: TransmutationV8 = decode(bytes)?;
+    bincode::serialize(&Transmutation {
+        js_soul: old.js_soul,
+        rust_soul: old.rust_soul,
+        rust_code: old.rust_code,
+        js_bindings: old.js_bindings,
+        resonance: old.resonance,
+        metadata: old.metadata,
+        equivalence_tests: old.equivalence_tests,
+        pass_rate: old.pass_rate,
+        transmuted_at: old.transmuted_at,
+        tags: old.tags,
+        source_map: old.source_map,
+        benches: old.benches,
+        benchmarks: old.benchmarks,
+        warnings: vec![],
+    })
+}
+
+/// Decode a whole record; bytes left over mean it's in a later layout,
+/// which only ever appends fields
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> bincode::Result<T> {
+    bincode::options().with_fixint_encoding().reject_trailing_bytes().deserialize(bytes)
+}
+
+/// Whether `bytes` is a record in layout `version`
+fn reads_as(version: u32, bytes: &[u8]) -> bool {
+    match version {
+        1 => decode::<TransmutationV1>(bytes).is_ok(),
+        2 => decode::<TransmutationV2>(bytes).is_ok(),
+        3 => decode::<TransmutationV3>(bytes).is_ok(),
+        4 => decode::<TransmutationV4>(bytes).is_ok(),
+        5 => decode::<TransmutationV5>(bytes).is_ok(),
+        6 => decode::<TransmutationV6>(bytes).is_ok(),
+        7 => decode::<TransmutationV7>(bytes).is_ok(),
+        8 => decode::<TransmutationV8>(bytes).is_ok(),
+        SCHEMA_VERSION => decode::<Transmutation>(bytes).is_ok(),
+        _ => false,
+    }
+}
+
+/// The layout a record is in: the stored version's, or for a database from
+/// before versioning, the latest layout that reads it
+fn layout_of(version: Option<u32>, bytes: &[u8]) -> Option<u32> {
+    match version {
+        Some(version) => reads_as(version, bytes).then_some(version),
+        None => (1..=SCHEMA_VERSION).rev().find(|&layout| reads_as(layout, bytes)),
+    }
+}
+
+fn stored_version(db: &sled::Db) -> Result<Option<u32>, TransmutationError> {
+    let Some(value) = db.get(VERSION_KEY).map_err(db_error)? else { return Ok(None) };
+    let bytes = value.as_ref().try_into().map_err(|_| TransmutationError::SchemaError("the stored schema version is malformed".into()))?;
+    Ok(Some(u32::from_be_bytes(bytes)))
+}
+
+fn records(db: &sled::Db) -> impl Iterator<Item = Result<(String, sled::IVec), TransmutationError>> {
+    db.scan_prefix(PREFIX).map(|entry| {
+        let (key, value) = entry.map_err(db_error)?;
+        Ok((String::from_utf8_lossy(&key[PREFIX.len()..]).into_owned(), value))
+    })
+}
+
+/// Refuse a database this forge would misread; one with no records yet is
+/// stamped with the current version
+pub(crate) fn check(db: &sled::Db) -> Result<(), TransmutationError> {
+    match stored_version(db)? {
+        Some(SCHEMA_VERSION) => Ok(()),
+        Some(version) if version > SCHEMA_VERSION => Err(TransmutationError::SchemaError(format!(
+            "the database is at schema version {}, newer than this forge's {}", version, SCHEMA_VERSION
+        ).into())),
+        Some(version) => Err(TransmutationError::SchemaError(
+            Diagnostic::error(format!("the database is at schema version {}, older than this forge's {}", version, SCHEMA_VERSION))
+                .with_suggestion("run `forge db migrate`"),
+        )),
+        None if db.scan_prefix(PREFIX).next().is_none() => {
+            db.insert(VERSION_KEY, &SCHEMA_VERSION.to_be_bytes()).map_err(db_error)?;
+            Ok(())
+        }
+        None => Err(TransmutationError::SchemaError(
+            Diagnostic::error("the database predates schema versioning").with_suggestion("run `forge db migrate`"),
+        )),
+    }
+}
+
+fn open(db_path: &str) -> Result<sled::Db, TransmutationError> {
+    if !Path::new(db_path).exists() {
+        return Err(TransmutationError::DatabaseError(format!("no database at {}", db_path).into()));
+    }
+    sled::open(db_path).map_err(db_error)
+}
+
+fn db_error(error: sled::Error) -> TransmutationError {
+    TransmutationError::DatabaseError(error.to_string().into())
+}
+
+/// What `doctor` found in a database
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnosis {
+    /// The stored schema version, `None` for a database from before versioning
+    pub version: Option<u32>,
+    /// How many records are in each layout
+    pub layouts: BTreeMap<u32, usize>,
+    /// npm names whose records no layout reads
+    pub unreadable: Vec<String>,
+}
+
+impl Diagnosis {
+    /// Whether `migrate` has work to do
+    pub fn needs_migration(&self) -> bool {
+        self.version != Some(SCHEMA_VERSION)
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        !self.needs_migration() && self.unreadable.is_empty()
+    }
+}
+
+/// What `migrate` changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migrated {
+    /// The schema version before, `None` for a database from before versioning
+    pub from: Option<u32>,
+    /// How many records were rewritten into the current layout
+    pub upgraded: usize,
+    /// npm names whose unreadable records were dropped
+    pub discarded: Vec<String>,
+}
+
+/// Read every record of the database at `db_path` without changing it
+pub fn doctor(db_path: &str) -> Result<Diagnosis, TransmutationError> {
+    let db = open(db_path)?;
+    let version = stored_version(&db)?;
+    let mut diagnosis = Diagnosis { version, layouts: BTreeMap::new(), unreadable: vec![] };
+    for record in records(&db) {
+        let (npm_name, value) = record?;
+        match layout_of(version, &value) {
+            Some(layout) => *diagnosis.layouts.entry(layout).or_default() += 1,
+            None => diagnosis.unreadable.push(npm_name),
+        }
+    }
+    Ok(diagnosis)
+}
+
+/// Rewrite every record of the database at `db_path` into the current
+/// layout and stamp it with the current version, all at once
+///
+/// Records no layout reads stop the migration before anything is written,
+/// unless `discard_unreadable` drops them.
+pub fn migrate(db_path: &str, discard_unreadable: bool) -> Result<Migrated, TransmutationError> {
+    let db = open(db_path)?;
+    let from = stored_version(&db)?;
+    if let Some(version) = from.filter(|&version| version > SCHEMA_VERSION) {
+        return Err(TransmutationError::SchemaError(format!(
+            "the database is at schema version {}, newer than this forge's {}", version, SCHEMA_VERSION
+        ).into()));
+    }
+
+    let mut batch = sled::Batch::default();
+    let mut migrated = Migrated { from, upgraded: 0, discarded: vec![] };
+    for record in records(&db) {
+        let (npm_name, mut value) = record?;
+        let key = format!("{}{}", PREFIX, npm_name);
+        let Some(layout) = layout_of(from, &value) else {
+            batch.remove(key.as_str());
+            migrated.discarded.push(npm_name);
+            continue;
+        };
+        if layout == SCHEMA_VERSION {
+            continue;
+        }
+        for migration in MIGRATIONS.iter().filter(|migration| migration.to > layout) {
+            value = (migration.upgrade)(&value)
+                .map_err(|e| TransmutationError::SchemaError(format!("{} to version {}: {}", npm_name, migration.to, e).into()))?
+                .into();
+        }
+        batch.insert(key.as_str(), value);
+        migrated.upgraded += 1;
+    }
+
+    if !discard_unreadable && !migrated.discarded.is_empty() {
+        return Err(TransmutationError::SchemaError(
+            Diagnostic::error(format!("no layout reads the records of {}", migrated.discarded.join(", ")))
+                .with_suggestion("`forge db migrate --discard-unreadable` drops them"),
+        ));
+    }
+    batch.insert(VERSION_KEY, &SCHEMA_VERSION.to_be_bytes());
+    db.apply_batch(batch).map_err(db_error)?;
+    db.flush().map_err(db_error)?;
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SoulForge, SourceKind};
+
+    #[tokio::test]
+    async fn test_unversioned_records_migrate_to_the_current_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap().to_string();
+        let mut forge = SoulForge::new(&path).unwrap().with_max_refinements(0);
+        let metadata = PackageMetadata {
+            name: "left-pad".to_string(),
+            version: "1.3.0".to_string(),
+            description: String::new(),
+            npm_name: "left-pad".to_string(),
+            crate_name: "left_pad".to_string(),
+            source_file: None,
+            bindings: Bindings::Wasm,
+            profile: Profile::WasmWeb,
+            license: None,
+            authors: vec![],
+            repository: None,
+            keywords: vec![],
+        };
+        let current = forge.transmute_source("export function id(x: number): number { return x; }", SourceKind::TypeScript, metadata).await.unwrap();
+        drop(forge);
+
+        // Rewind the database to before versioning, with records from each layout
+        let db = sled::open(&path).unwrap();
+        db.remove(VERSION_KEY).unwrap();
+        let first = TransmutationV1 {
+            js_soul: current.js_soul.clone(),
+            rust_soul: current.rust_soul.clone(),
+            rust_code: current.rust_code.clone(),
+            wasm_bindings: current.js_bindings.clone(),
+            resonance: current.resonance,
+            metadata: PackageMetadataV1 {
+                name: current.metadata.name.clone(),
+                version: current.metadata.version.clone(),
+                description: current.metadata.description.clone(),
+                npm_name: "old-pad".to_string(),
+                crate_name: current.metadata.crate_name.clone(),
+            },
+        };
+        db.insert("transmutation:old-pad", bincode::serialize(&first).unwrap()).unwrap();
+        db.insert("transmutation:mid-pad", add_equivalence(&bincode::serialize(&first).unwrap()).unwrap()).unwrap();
+        db.insert("transmutation:broken", &b"not a transmutation"[..]).unwrap();
+        drop(db);
+
+        let diagnosis = doctor(&path).unwrap();
+        assert_eq!(diagnosis.layouts, BTreeMap::from([(1, 1), (2, 1), (SCHEMA_VERSION, 1)]));
+        assert_eq!(diagnosis.unreadable, ["broken"]);
+        assert!(SoulForge::new(&path).is_err());
+
+        assert!(migrate(&path, false).is_err());
+        assert_eq!(doctor(&path).unwrap(), diagnosis);
+        let migrated = migrate(&path, true).unwrap();
+        assert_eq!(migrated, Migrated { from: None, upgraded: 2, discarded: vec!["broken".to_string()] });
+        assert!(doctor(&path).unwrap().is_healthy());
+
+        let forge = SoulForge::new(&path).unwrap();
+        let old = forge.get_transmutation("old-pad").unwrap().unwrap();
+        assert_eq!((old.transmuted_at, old.pass_rate, old.rust_code), (0, None, current.rust_code));
+        assert_eq!((old.metadata.source_file, old.source_map), (None, String::new()));
+        assert_eq!(old.metadata.bindings, Bindings::Wasm);
+        assert!(old.warnings.is_empty());
+        assert!(forge.get_transmutation("left-pad").unwrap().unwrap().transmuted_at > 0);
+    }
+
+    #[test]
+    fn test_new_databases_are_stamped_and_newer_ones_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap().to_string();
+        drop(SoulForge::new(&path).unwrap());
+        assert_eq!(doctor(&path).unwrap().version, Some(SCHEMA_VERSION));
+
+        let db = sled::open(&path).unwrap();
+        db.insert(VERSION_KEY, &(SCHEMA_VERSION + 1).to_be_bytes()).unwrap();
+        drop(db);
+        assert!(SoulForge::new(&path).is_err());
+        assert!(migrate(&path, true).is_err());
+    }
+}
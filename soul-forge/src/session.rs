@@ -0,0 +1,160 @@
+// 🎛️ Refinement Sessions - Refining a Stored Transmutation by Hand
+use crate::refine::{self, Action, Direction};
+use crate::{drift, merge, plan, query, sourcemap, stats, SoulForge, Transmutation, TransmutationError};
+use protein_hash::Language;
+use serde::{Deserialize, Serialize};
+
+/// Tree of the refinements accepted by hand, keyed by npm name then in order
+const REFINEMENTS_TREE: &str = "refinements";
+
+/// Where a transmutation's resonance comes from, as `forge refine` shows it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakdown {
+    pub resonance: f64,
+    /// Distance between the souls' eigenvalue layers
+    pub eigen_distance: f64,
+    /// Distance between their Euler characteristics, clustering and
+    /// modularity
+    pub topology_distance: f64,
+    /// Each layer's eigenvalue in the JS soul and the Rust one
+    pub layers: Vec<(f64, f64)>,
+    /// The edits that move the Rust soul toward the JS one
+    pub suggested: Vec<Action>,
+    /// The signatures of the crate's functions and methods, for retyping
+    pub functions: Vec<String>,
+}
+
+/// A refinement accepted by hand
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    /// The action, as `forge refine` takes it
+    pub action: String,
+    pub resonance_before: f64,
+    pub resonance_after: f64,
+    /// When it was accepted, in seconds since the Unix epoch
+    pub accepted_at: u64,
+}
+
+impl SoulForge {
+    /// What `transmutation`'s resonance is made of
+    pub fn breakdown(&self, transmutation: &Transmutation) -> Breakdown {
+        let (js, rust) = (&transmutation.js_soul, &transmutation.rust_soul);
+        let functions = plan::items(&transmutation.rust_code).into_iter()
+            .flat_map(|item| match item.kind {
+                "fn" => vec![item.signature],
+                _ => item.members.into_iter().filter(|member| member.kind == "fn").map(|member| member.signature).collect(),
+            })
+            .collect();
+        Breakdown {
+            resonance: transmutation.resonance,
+            eigen_distance: js.eigenvalues.iter().zip(&rust.eigenvalues).map(|(js, rust)| (js - rust).powi(2)).sum::<f64>().sqrt(),
+            topology_distance: (js.topology.euler_char as f64 - rust.topology.euler_char as f64).abs() / 100.0
+                + (js.topology.clustering - rust.topology.clustering).abs()
+                + (js.topology.modularity - rust.topology.modularity).abs(),
+            layers: js.eigenvalues.iter().copied().zip(rust.eigenvalues.iter().copied()).collect(),
+            suggested: Action::toward(Direction::of(&js.eigenvalues, &rust.eigenvalues)),
+            functions,
+        }
+    }
+
+    /// `transmutation` after `action`, its Rust soul extracted again and its
+    /// code laid out under a new header; `None` when the action changes nothing
+    ///
+    /// Nothing is checked or stored. The equivalence pass rate is left unknown
+    /// until the tests are run again.
+    pub async fn try_refinement(&mut self, transmutation: &Transmutation, action: &Action) -> Result<Option<Transmutation>, TransmutationError> {
+        let Some(body) = refine::apply(&merge::body_of(&transmutation.rust_code), action) else { return Ok(None) };
+        let rust_soul = self.soul(Language::Rust, &body).await;
+        let resonance = protein_hash::measure_resonance(&transmutation.js_soul, &rust_soul);
+        let rust_code = merge::mark_items(&self.generate_module(&transmutation.metadata, resonance, &body)?);
+        Ok(Some(Transmutation {
+            rust_soul,
+            resonance,
+            source_map: sourcemap::source_map(&rust_code),
+            warnings: drift::lint(&rust_code),
+            rust_code,
+            pass_rate: None,
+            ..transmutation.clone()
+        }))
+    }
+
+    /// Store `refined`, which `action` made of a transmutation at
+    /// `resonance_before`, and keep the step in the package's history
+    pub fn accept_refinement(&mut self, refined: &Transmutation, action: &Action, resonance_before: f64) -> Result<(), TransmutationError> {
+        let accepted_at = query::unix_seconds(std::time::SystemTime::now());
+        let refined = Transmutation { transmuted_at: accepted_at, ..refined.clone() };
+        let npm_name = &refined.metadata.npm_name;
+        self.store_transmutation(&refined)?;
+        self.record_attempt(npm_name, &stats::Attempt {
+            transmuted_at: accepted_at,
+            version: refined.metadata.version.clone(),
+            resonance: Some(refined.resonance),
+            pass_rate: None,
+            compiled: None,
+        })?;
+
+        let step = Step { action: action.to_string(), resonance_before, resonance_after: refined.resonance, accepted_at };
+        let tree = self.db.open_tree(REFINEMENTS_TREE).map_err(db_error)?;
+        let id = self.db.generate_id().map_err(db_error)?;
+        let mut key = format!("{}\0", npm_name).into_bytes();
+        key.extend(id.to_be_bytes());
+        let value = bincode::serialize(&step).map_err(|e| TransmutationError::SerializationError(e.to_string().into()))?;
+        tree.insert(key, value).map_err(db_error)?;
+        self.save_souls()
+    }
+
+    /// The refinements accepted by hand for `npm_name`, oldest first
+    pub fn refinement_steps(&self, npm_name: &str) -> Result<Vec<Step>, TransmutationError> {
+        let tree = self.db.open_tree(REFINEMENTS_TREE).map_err(db_error)?;
+        tree.scan_prefix(format!("{}\0", npm_name))
+            .map(|entry| {
+                let (_, value) = entry.map_err(db_error)?;
+                bincode::deserialize(&value).map_err(|e| TransmutationError::SerializationError(e.to_string().into()))
+            })
+            .collect()
+    }
+}
+
+fn db_error(error: sled::Error) -> TransmutationError {
+    TransmutationError::DatabaseError(error.to_string().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_accepted_refinements_are_stored_and_kept() {
+        let db = tempfile::tempdir().unwrap();
+        let mut forge = SoulForge::new(db.path().to_str().unwrap()).unwrap().with_max_refinements(0);
+        let metadata = crate::PackageMetadata {
+            name: "scale-soul".to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            npm_name: "scale".to_string(),
+            crate_name: "scale-soul".to_string(),
+            source_file: None,
+            bindings: crate::Bindings::Wasm,
+            profile: crate::Profile::WasmWeb,
+            license: None,
+            authors: vec![],
+            repository: None,
+            keywords: vec![],
+        };
+        let transmutation = forge.transmute("export function scale(x, by) { const y = x * by; return y; }", metadata).await.unwrap();
+        let breakdown = forge.breakdown(&transmutation);
+        assert_eq!(breakdown.layers.len(), transmutation.js_soul.eigenvalues.len());
+        assert_eq!(breakdown.functions, ["pub fn scale(x: f64, by: f64) -> f64"]);
+
+        let action: Action = "type scale by i32".parse().unwrap();
+        let refined = forge.try_refinement(&transmutation, &action).await.unwrap().unwrap();
+        assert!(refined.rust_code.contains("pub fn scale(x: f64, by: i32) -> f64"));
+        assert!(refined.rust_code.starts_with("// forge:begin header"));
+        assert!(forge.try_refinement(&refined, &action).await.unwrap().is_none());
+
+        forge.accept_refinement(&refined, &action, transmutation.resonance).unwrap();
+        assert_eq!(forge.get_transmutation("scale").unwrap().unwrap().rust_code, refined.rust_code);
+        let steps = forge.refinement_steps("scale").unwrap();
+        assert_eq!((steps[0].action.as_str(), steps[0].resonance_before), ("type scale by i32", transmutation.resonance));
+    }
+}
@@ -0,0 +1,203 @@
+// 📸 Snapshots - Generated Code Recorded for Review
+use crate::pairs::JS_EXTENSIONS;
+use crate::{Bindings, Diagnostic, JsModule, PackageMetadata, Profile, SoulForge, TransmutationError};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The directory next to the fixtures their snapshots are kept in
+pub const SNAPSHOT_DIR: &str = "snapshots";
+
+/// Lines of unchanged code shown around each change
+const CONTEXT: usize = 2;
+
+/// How a fixture's generated code compares with its snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// It generates what was recorded
+    Matched,
+    /// It has no snapshot yet
+    New,
+    /// It generates something else, as a diff from the snapshot
+    Changed(String),
+}
+
+/// A fixture, its snapshot and how they compare
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub fixture: PathBuf,
+    pub snapshot: PathBuf,
+    pub outcome: Outcome,
+}
+
+impl SoulForge {
+    /// Compare the Rust generated for each JS or TS fixture in `dir` with
+    /// its snapshot in `dir/snapshots`, in order of name
+    ///
+    /// A fixture generating something other than its snapshot, or with none,
+    /// has its code written to `{name}.snap.new` for review; with `accept`,
+    /// the code is recorded as the snapshot instead. The code is as the
+    /// forge draws it up, transformed and refined, before it's checked, run
+    /// or stored.
+    pub async fn check_snapshots(&mut self, dir: &Path, accept: bool) -> Result<Vec<Snapshot>, TransmutationError> {
+        let io_error = |path: &Path, e: std::io::Error| TransmutationError::SnapshotError(Diagnostic::error(e.to_string()).with_file(path.display().to_string()));
+        let mut fixtures: Vec<PathBuf> = fs::read_dir(dir)
+            .map_err(|e| io_error(dir, e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| JS_EXTENSIONS.iter().any(|known| ext == *known)))
+            .collect();
+        fixtures.sort();
+        let snapshots = dir.join(SNAPSHOT_DIR);
+        fs::create_dir_all(&snapshots).map_err(|e| io_error(&snapshots, e))?;
+
+        let mut checked = vec![];
+        for fixture in fixtures {
+            let name = fixture.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            let source = fixture.strip_prefix(dir).unwrap_or(&fixture).display().to_string();
+            let draft = self.draft(&JsModule::collect(&fixture)?, &fixture_metadata(&name, &source)).await?;
+            let recorded = record(&source, &draft.rust_code);
+
+            let snapshot = snapshots.join(format!("{}.snap", name));
+            let pending = snapshots.join(format!("{}.snap.new", name));
+            let outcome = match fs::read_to_string(&snapshot) {
+                Ok(existing) if existing == recorded => Outcome::Matched,
+                Ok(existing) => Outcome::Changed(diff(&existing, &recorded)),
+                Err(_) => Outcome::New,
+            };
+            match (&outcome, accept) {
+                (Outcome::Matched, _) => {}
+                (_, true) => fs::write(&snapshot, &recorded).map_err(|e| io_error(&snapshot, e))?,
+                (_, false) => fs::write(&pending, &recorded).map_err(|e| io_error(&pending, e))?,
+            }
+            // A review left over from an earlier run is settled either way
+            if (outcome == Outcome::Matched || accept) && pending.exists() {
+                fs::remove_file(&pending).map_err(|e| io_error(&pending, e))?;
+            }
+            checked.push(Snapshot { fixture, snapshot, outcome });
+        }
+        Ok(checked)
+    }
+}
+
+/// A fixture's package, named after its file
+fn fixture_metadata(name: &str, source: &str) -> PackageMetadata {
+    PackageMetadata {
+        name: format!("{}-soul", name),
+        version: "0.1.0".to_string(),
+        description: String::new(),
+        npm_name: name.to_string(),
+        crate_name: format!("{}-soul", name),
+        source_file: Some(source.to_string()),
+        bindings: Bindings::Wasm,
+        profile: Profile::WasmWeb,
+        license: None,
+        authors: vec![],
+        repository: None,
+        keywords: vec![],
+    }
+}
+
+/// A snapshot file: a header naming the fixture, then the code
+fn record(source: &str, rust_code: &str) -> String {
+    format!("---\nsource: {}\n---\n{}\n", source, rust_code.trim_end())
+}
+
+/// The lines of `new` that differ from `old`, `-` for removed and `+` for
+/// added, each run of changes between `@@` markers with the unchanged lines
+/// around it
+pub fn diff(old: &str, new: &str) -> String {
+    let (old, new): (Vec<&str>, Vec<&str>) = (old.lines().collect(), new.lines().collect());
+    // Longest common subsequences of every pair of suffixes
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = match old[i] == new[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1]),
+            };
+        }
+    }
+    let mut lines: Vec<(char, &str, usize)> = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i], i + 1));
+            (i, j) = (i + 1, j + 1);
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', old[i], i + 1));
+            i += 1;
+        } else {
+            lines.push(('+', new[j], i + 1));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = lines.iter().enumerate().filter(|(_, (sign, _, _))| *sign != ' ').map(|(index, _)| index).collect();
+    let mut out = String::new();
+    let mut shown_to = 0;
+    for (index, &at) in changed.iter().enumerate() {
+        let from = at.saturating_sub(CONTEXT).max(shown_to);
+        if from > shown_to || index == 0 {
+            out.push_str(&format!("@@ line {} @@\n", lines[from].2));
+        }
+        let to = (at + CONTEXT + 1).min(lines.len());
+        let to = match changed.get(index + 1) {
+            Some(&next) if next.saturating_sub(CONTEXT) <= to => next,
+            _ => to,
+        };
+        for (sign, line, _) in &lines[from.max(shown_to)..to] {
+            out.push_str(&format!("{}{}\n", sign, line));
+        }
+        shown_to = to;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A forge keeping its database in `db`
+    fn forge(db: &tempfile::TempDir) -> SoulForge {
+        SoulForge::new(db.path().to_str().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_changed_output_is_kept_for_review() {
+        let db = tempfile::tempdir().unwrap();
+        let fixtures = tempfile::tempdir().unwrap();
+        let dir = fixtures.path();
+        fs::write(dir.join("double.js"), "export function double(x) { return x * 2; }").unwrap();
+        let mut forge = forge(&db).with_max_refinements(0);
+
+        let first = forge.check_snapshots(dir, false).await.unwrap();
+        assert_eq!(first[0].outcome, Outcome::New);
+        assert!(dir.join("snapshots/double.snap.new").exists());
+        forge.check_snapshots(dir, true).await.unwrap();
+        assert!(!dir.join("snapshots/double.snap.new").exists());
+        assert_eq!(forge.check_snapshots(dir, false).await.unwrap()[0].outcome, Outcome::Matched);
+
+        fs::write(dir.join("double.js"), "export function double(x) { return x * 3; }").unwrap();
+        let Outcome::Changed(diff) = &forge.check_snapshots(dir, false).await.unwrap()[0].outcome else { panic!("expected a change") };
+        assert!(diff.contains("-    x * 2.0\n+    x * 3.0\n"), "{}", diff);
+    }
+
+    #[tokio::test]
+    async fn test_fixtures_match_their_snapshots() {
+        let db = tempfile::tempdir().unwrap();
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        for snapshot in forge(&db).check_snapshots(&dir, false).await.unwrap() {
+            match snapshot.outcome {
+                Outcome::Matched => {}
+                Outcome::New => panic!("{} has no snapshot; review it and run `forge test-fixtures --accept`", snapshot.fixture.display()),
+                Outcome::Changed(diff) => panic!("{} generates something else; review it and run `forge test-fixtures --accept`:\n{}", snapshot.fixture.display(), diff),
+            }
+        }
+    }
+
+    #[test]
+    fn test_diffs_show_changes_in_context() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nb\nc\nD\ne\nf\ng\nh\n";
+        assert_eq!(diff(old, new), "@@ line 2 @@\n b\n c\n-d\n+D\n e\n f\n");
+    }
+}
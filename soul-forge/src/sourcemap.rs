@@ -0,0 +1,162 @@
+// 🗺️ Source Maps - Tracing Rust Bodies Back to Their JS Origins
+use std::collections::BTreeMap;
+use syn::spanned::Spanned;
+
+/// How a generated function's doc comment starts when it names its origin,
+/// as in `/// Transmuted from `src/index.js:12``
+const ORIGIN_DOC: &str = " Transmuted from `";
+
+/// The lines of a transmutation's source, for naming where functions came from
+pub(crate) struct Origins<'a> {
+    file: &'a str,
+    /// The byte offset each line starts at
+    line_starts: Vec<u32>,
+}
+
+impl<'a> Origins<'a> {
+    pub(crate) fn new(file: &'a str, code: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(code.match_indices('\n').map(|(offset, _)| offset as u32 + 1))
+            .collect();
+        Self { file, line_starts }
+    }
+
+    /// The 1-based line `offset` is on
+    fn line(&self, offset: u32) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset)
+    }
+
+    /// The doc comment line of a function transmuted from the declaration
+    /// at `origin`, without its `///`
+    pub(crate) fn describe(&self, origin: u32) -> String {
+        format!("{}{}:{}`", ORIGIN_DOC.trim_start(), self.file, self.line(origin))
+    }
+}
+
+/// The file and line a function's doc comments name as its origin
+pub(crate) fn origin_of(attrs: &[syn::Attribute]) -> Option<(String, i64)> {
+    attrs.iter().find_map(|attr| {
+        let syn::Meta::NameValue(doc) = &attr.meta else { return None };
+        let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(text), .. }) = &doc.value else { return None };
+        if !doc.path.is_ident("doc") {
+            return None;
+        }
+        let (file, line) = text.value().strip_prefix(ORIGIN_DOC)?.strip_suffix('`')?.rsplit_once(':').map(|(file, line)| (file.to_string(), line.to_string()))?;
+        Some((file, line.parse().ok()?))
+    })
+}
+
+/// A Source Map v3 for `rust_code`, written as `src/lib.rs.map`: each line of
+/// a function, doc comments included, maps to the line its doc comment names
+///
+/// The origins are read back from the finished code rather than recorded
+/// while generating it, so they still hold after refinement and repair have
+/// moved functions around. Functions without one, like extracted helpers,
+/// stay unmapped.
+pub(crate) fn source_map(rust_code: &str) -> String {
+    let mut functions = vec![];
+    if let Ok(file) = syn::parse_file(rust_code) {
+        collect_functions(&file.items, &mut functions);
+    }
+
+    let mut sources: Vec<String> = vec![];
+    let mut origins = BTreeMap::new();
+    for (span, origin) in functions {
+        let Some((file, line)) = origin else { continue };
+        let source = sources.iter().position(|source| *source == file).unwrap_or_else(|| {
+            sources.push(file);
+            sources.len() - 1
+        });
+        for rust_line in span.start().line..=span.end().line {
+            origins.insert(rust_line, (source as i64, line - 1));
+        }
+    }
+
+    // One segment a mapped line, at column 0 of both; fields are relative to
+    // the segment before
+    let mut mappings = String::new();
+    let mut previous = (0, 0);
+    for rust_line in 1..=rust_code.lines().count() {
+        if rust_line > 1 {
+            mappings.push(';');
+        }
+        if let Some(&(source, line)) = origins.get(&rust_line) {
+            for field in [0, source - previous.0, line - previous.1, 0] {
+                vlq(&mut mappings, field);
+            }
+            previous = (source, line);
+        }
+    }
+    serde_json::json!({
+        "version": 3,
+        "file": "lib.rs",
+        "sources": sources,
+        "names": [],
+        "mappings": mappings,
+    })
+    .to_string()
+}
+
+/// The span and origin of each function of `items`, in modules too
+fn collect_functions(items: &[syn::Item], functions: &mut Vec<(proc_macro2::Span, Option<(String, i64)>)>) {
+    for item in items {
+        match item {
+            syn::Item::Fn(function) => functions.push((function.span(), origin_of(&function.attrs))),
+            syn::Item::Impl(block) => functions.extend(block.items.iter().filter_map(|item| match item {
+                syn::ImplItem::Fn(method) => Some((method.span(), origin_of(&method.attrs))),
+                _ => None,
+            })),
+            syn::Item::Trait(block) => functions.extend(block.items.iter().filter_map(|item| match item {
+                syn::TraitItem::Fn(method) => Some((method.span(), origin_of(&method.attrs))),
+                _ => None,
+            })),
+            syn::Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    collect_functions(items, functions);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Append `value` as a base64 VLQ: five bits a digit, least significant
+/// first, with the sign in the lowest bit
+fn vlq(out: &mut String, value: i64) {
+    const DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut rest = if value < 0 { (-value as u64) << 1 | 1 } else { (value as u64) << 1 };
+    loop {
+        let digit = (rest & 31) as usize;
+        rest >>= 5;
+        out.push(DIGITS[if rest > 0 { digit | 32 } else { digit }] as char);
+        if rest == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vlq_digits() {
+        let encoded: Vec<String> = [0, 1, -1, 15, 16, -1000].iter().map(|&value| {
+            let mut out = String::new();
+            vlq(&mut out, value);
+            out
+        }).collect();
+        assert_eq!(encoded, ["A", "C", "D", "e", "gB", "x+B"]);
+    }
+
+    #[test]
+    fn test_functions_map_to_the_lines_their_docs_name() {
+        let origins = Origins::new("index.js", "// header\n\nfunction a() {}\n");
+        assert_eq!(origins.describe(11), "Transmuted from `index.js:3`");
+
+        let rust = "/// Transmuted from `index.js:3`\npub fn a() {\n}\n\nfn a_body() {}\n/// Transmuted from `index.js:7`\npub fn b() {}\n";
+        let map: serde_json::Value = serde_json::from_str(&source_map(rust)).unwrap();
+        assert_eq!(map["sources"], serde_json::json!(["index.js"]));
+        assert_eq!(map["mappings"], "AAEA;AAAA;AAAA;;;AAIA;AAAA");
+    }
+}
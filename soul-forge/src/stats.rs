@@ -0,0 +1,239 @@
+// 📊 Transmutation Statistics - How the Forge Has Been Doing
+use crate::{JobState, Journal, SoulForge, TransmutationError, TransmutationFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Tree of every attempt at a package, keyed by its npm name then in order
+const HISTORY_TREE: &str = "history";
+
+/// Buckets of the resonance histogram, each a tenth wide
+const BUCKETS: usize = 10;
+
+/// Failure reasons listed, most common first
+const TOP_FAILURES: usize = 5;
+
+/// One attempt at transmuting a package, kept whether it was stored or not
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Attempt {
+    pub(crate) transmuted_at: u64,
+    pub(crate) version: String,
+    /// None when it never got as far as being stored
+    pub(crate) resonance: Option<f64>,
+    pub(crate) pass_rate: Option<f64>,
+    /// Whether it compiled, when it was checked
+    pub(crate) compiled: Option<bool>,
+}
+
+/// What `forge stats` reports on the transmutations a filter matches
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stats {
+    pub total: usize,
+    /// Above 0.95
+    pub perfect: usize,
+    /// Above 0.8, up to 0.95
+    pub good: usize,
+    pub weak: usize,
+    pub mean_resonance: Option<f64>,
+    /// Transmutations by resonance, a bucket a tenth
+    pub histogram: Vec<Bucket>,
+    /// Of the attempts checked with cargo, the share that compiled
+    pub compile_rate: Option<f64>,
+    /// Of the transmutations compared with their source, the mean share of
+    /// calls that agreed
+    pub pass_rate: Option<f64>,
+    /// Packages transmuted more than once, the biggest change first
+    pub trends: Vec<Trend>,
+    /// Why batch jobs are failing, by the stage that failed, most common first
+    pub failures: Vec<Failure>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Bucket {
+    pub from: f64,
+    pub to: f64,
+    pub count: usize,
+}
+
+/// How a package's resonance moved across its transmutations
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Trend {
+    pub npm_name: String,
+    pub transmutations: usize,
+    pub first: f64,
+    pub last: f64,
+}
+
+impl Trend {
+    pub fn change(&self) -> f64 {
+        self.last - self.first
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Failure {
+    /// The stage, like `Compile error`
+    pub reason: String,
+    pub count: usize,
+    /// The last reason seen in full
+    pub example: String,
+}
+
+impl SoulForge {
+    /// Keep `attempt` at `npm_name` in its history
+    pub(crate) fn record_attempt(&self, npm_name: &str, attempt: &Attempt) -> Result<(), TransmutationError> {
+        let history = self.db.open_tree(HISTORY_TREE).map_err(db_error)?;
+        let id = self.db.generate_id().map_err(db_error)?;
+        let value = bincode::serialize(attempt).map_err(|e| TransmutationError::SerializationError(e.to_string().into()))?;
+        history.insert(history_key(npm_name, id), value).map_err(db_error)?;
+        Ok(())
+    }
+
+    /// The attempts at `npm_name`, oldest first
+    pub(crate) fn attempts(&self, npm_name: &str) -> Result<Vec<Attempt>, TransmutationError> {
+        let history = self.db.open_tree(HISTORY_TREE).map_err(db_error)?;
+        history.scan_prefix(format!("{}\0", npm_name))
+            .map(|entry| {
+                let (_, value) = entry.map_err(db_error)?;
+                bincode::deserialize(&value).map_err(|e| TransmutationError::SerializationError(e.to_string().into()))
+            })
+            .collect()
+    }
+
+    /// Statistics of the transmutations `filter` matches, and of the failures
+    /// of batch jobs whose names match its prefix
+    pub fn stats(&self, filter: &TransmutationFilter) -> Result<Stats, TransmutationError> {
+        let transmutations = self.list_transmutations(filter)?;
+        let total = transmutations.len();
+        let perfect = transmutations.iter().filter(|t| t.resonance > 0.95).count();
+        let good = transmutations.iter().filter(|t| t.resonance > 0.8 && t.resonance <= 0.95).count();
+
+        let mut histogram: Vec<Bucket> = (0..BUCKETS)
+            .map(|bucket| Bucket { from: bucket as f64 / BUCKETS as f64, to: (bucket + 1) as f64 / BUCKETS as f64, count: 0 })
+            .collect();
+        for transmutation in &transmutations {
+            let bucket = (transmutation.resonance.clamp(0.0, 1.0) * BUCKETS as f64) as usize;
+            histogram[bucket.min(BUCKETS - 1)].count += 1;
+        }
+
+        let mut checked = (0, 0);
+        let mut trends = vec![];
+        for transmutation in &transmutations {
+            let attempts = self.attempts(&transmutation.metadata.npm_name)?;
+            for compiled in attempts.iter().filter_map(|attempt| attempt.compiled) {
+                checked = (checked.0 + usize::from(compiled), checked.1 + 1);
+            }
+            let resonances: Vec<f64> = attempts.iter().filter_map(|attempt| attempt.resonance).collect();
+            if let [first, .., last] = resonances.as_slice() {
+                trends.push(Trend { npm_name: transmutation.metadata.npm_name.clone(), transmutations: resonances.len(), first: *first, last: *last });
+            }
+        }
+        trends.sort_by(|a, b| b.change().abs().total_cmp(&a.change().abs()).then_with(|| a.npm_name.cmp(&b.npm_name)));
+
+        let compared: Vec<f64> = transmutations.iter().filter_map(|t| t.pass_rate).collect();
+        Ok(Stats {
+            total,
+            perfect,
+            good,
+            weak: total - perfect - good,
+            mean_resonance: mean(transmutations.iter().map(|t| t.resonance).collect()),
+            histogram,
+            compile_rate: (checked.1 > 0).then(|| checked.0 as f64 / checked.1 as f64),
+            pass_rate: mean(compared),
+            trends,
+            failures: self.failures(filter.name_prefix())?,
+        })
+    }
+
+    /// The reasons batch jobs of packages named `prefix..` are failing for
+    fn failures(&self, prefix: &str) -> Result<Vec<Failure>, TransmutationError> {
+        let mut failures: HashMap<String, Failure> = HashMap::new();
+        for journal in Journal::all(&self.db)? {
+            for (npm_name, state) in journal.entries()? {
+                let JobState::Failed { reason, .. } = state else { continue };
+                if !npm_name.starts_with(prefix) {
+                    continue;
+                }
+                let stage = stage(&reason);
+                let failure = failures.entry(stage.clone()).or_insert(Failure { reason: stage, count: 0, example: String::new() });
+                failure.count += 1;
+                failure.example = reason;
+            }
+        }
+        let mut failures: Vec<Failure> = failures.into_values().collect();
+        failures.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.reason.cmp(&b.reason)));
+        failures.truncate(TOP_FAILURES);
+        Ok(failures)
+    }
+}
+
+fn history_key(npm_name: &str, id: u64) -> Vec<u8> {
+    let mut key = format!("{}\0", npm_name).into_bytes();
+    // Big-endian, so attempts sort in the order they were made
+    key.extend(id.to_be_bytes());
+    key
+}
+
+/// The stage a failure's reason names, like `Compile error` of
+/// `Compile error: mismatched types`; a reason naming none is its first line
+fn stage(reason: &str) -> String {
+    let line = reason.lines().next().unwrap_or_default();
+    match line.split_once(": ") {
+        Some((stage, _)) if stage.ends_with(" error") => stage.to_string(),
+        _ => line.to_string(),
+    }
+}
+
+fn mean(values: Vec<f64>) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+fn db_error(error: sled::Error) -> TransmutationError {
+    TransmutationError::DatabaseError(error.to_string().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_of_an_empty_database() {
+        let db = tempfile::tempdir().unwrap();
+        let forge = SoulForge::new(db.path().to_str().unwrap()).unwrap();
+        let stats = forge.stats(&TransmutationFilter::default()).unwrap();
+        assert_eq!((stats.total, stats.mean_resonance, stats.compile_rate), (0, None, None));
+        assert_eq!(stats.histogram.len(), BUCKETS);
+
+        forge.journal("crates").unwrap().fail("left-pad", "Compile error: mismatched types").unwrap();
+        forge.journal("other").unwrap().fail("lodash", "Compile error: cannot find value").unwrap();
+        let failures = forge.stats(&TransmutationFilter::default()).unwrap().failures;
+        assert_eq!((failures[0].reason.as_str(), failures[0].count), ("Compile error", 2));
+        assert_eq!(stage("timed out\nafter 30s"), "timed out");
+    }
+
+    #[tokio::test]
+    async fn test_retransmuted_packages_trend() {
+        let db = tempfile::tempdir().unwrap();
+        let mut forge = SoulForge::new(db.path().to_str().unwrap()).unwrap().with_max_refinements(0);
+        let metadata = crate::PackageMetadata {
+            name: "twice-soul".to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            npm_name: "twice".to_string(),
+            crate_name: "twice-soul".to_string(),
+            source_file: None,
+            bindings: crate::Bindings::Wasm,
+            profile: crate::Profile::WasmWeb,
+            license: None,
+            authors: vec![],
+            repository: None,
+            keywords: vec![],
+        };
+        for code in ["export function f(x) { return x; }", "export function f(x) { if (x > 0) { return x; } return -x; }"] {
+            forge.transmute(code, metadata.clone()).await.unwrap();
+        }
+        let stats = forge.stats(&TransmutationFilter::default()).unwrap();
+        assert_eq!(stats.histogram.iter().map(|bucket| bucket.count).sum::<usize>(), 1);
+        assert_eq!((stats.trends[0].npm_name.as_str(), stats.trends[0].transmutations), ("twice", 2));
+        assert_eq!(stats.compile_rate, None);
+    }
+}
@@ -0,0 +1,247 @@
+// 🧵 String Idioms - JS String and RegExp Methods in std and regex
+use crate::body::{rust_ident, BodyWriter};
+use crate::{JsExpr, JsFunction, JsType};
+
+/// What a regex is among the types a body knows
+pub(crate) const REGEX: &str = "regex::Regex";
+
+/// Crates only some generated code needs, by the path that uses them; a
+/// manifest depends on one when its crate's code uses it
+pub(crate) const DEPENDENCIES: &[(&str, &str, &str)] = &[("regex", r#""1""#, "regex::")];
+
+/// Methods no JS value but a string has, translated whatever the receiver
+/// is known to be
+const METHODS: &[&str] = &[
+    "toUpperCase", "toLocaleUpperCase", "toLowerCase", "toLocaleLowerCase", "trim", "trimStart", "trimEnd", "startsWith",
+    "endsWith", "split", "replace", "replaceAll", "charAt", "charCodeAt", "repeat", "padStart", "padEnd", "match",
+    "matchAll", "search", "localeCompare",
+];
+
+/// `regex::Regex::new(..)` for a JS regex literal: its `i`, `m` and `s`
+/// flags inline, and escapes Rust spells differently rewritten; the
+/// method it is used by decides what `g` means
+pub(crate) fn regex(pattern: &str, flags: &str) -> String {
+    let inline: String = flags.chars().filter(|flag| matches!(flag, 'i' | 'm' | 's')).collect();
+    let mut rust = match inline.is_empty() {
+        true => String::new(),
+        false => format!("(?{})", inline),
+    };
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            rust.push(c);
+            continue;
+        }
+        match chars.next() {
+            // `\/` only escapes the literal's delimiter
+            Some('/') => rust.push('/'),
+            // `\u0041` and `\u{1F600}` are `\x{..}`
+            Some('u') if chars.peek() == Some(&'{') => {
+                chars.next();
+                let hex: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                rust.push_str(&format!("\\x{{{}}}", hex));
+            }
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                rust.push_str(&format!("\\x{{{}}}", hex));
+            }
+            Some(escaped) => {
+                rust.push('\\');
+                rust.push(escaped);
+            }
+            None => rust.push('\\'),
+        }
+    }
+    format!("regex::Regex::new({:?}).unwrap()", rust)
+}
+
+/// A JS replacement string as the regex crate expands it: `$&` is `${0}`,
+/// `$1` is `${1}` and `$<name>` is `${name}`; any other `$` is literal
+fn replacement(js: &str) -> String {
+    let mut rust = String::new();
+    let mut chars = js.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            rust.push(c);
+            continue;
+        }
+        match chars.peek().copied() {
+            Some('&') => {
+                chars.next();
+                rust.push_str("${0}");
+            }
+            Some(digit) if digit.is_ascii_digit() => {
+                let group: String = std::iter::from_fn(|| chars.next_if(char::is_ascii_digit)).collect();
+                rust.push_str(&format!("${{{}}}", group));
+            }
+            Some('<') => {
+                chars.next();
+                let name: String = chars.by_ref().take_while(|c| *c != '>').collect();
+                rust.push_str(&format!("${{{}}}", name));
+            }
+            Some('$') => {
+                chars.next();
+                rust.push_str("$$");
+            }
+            _ => rust.push_str("$$"),
+        }
+    }
+    rust
+}
+
+impl BodyWriter<'_> {
+    /// `expr` as a regex, and whether it is global, when it is one
+    fn regex_arg(&mut self, expr: Option<&JsExpr>) -> Option<(String, bool)> {
+        match expr? {
+            JsExpr::Regex { pattern, flags } => Some((regex(pattern, flags), flags.contains('g'))),
+            expr @ JsExpr::Ident(name) if matches!(self.expr_type(expr), JsType::Named(ty, _) if ty == REGEX) => {
+                Some((rust_ident(name), self.is_global_regex(name)))
+            }
+            _ => None,
+        }
+    }
+
+    /// A call of a string or regex method, or `None` when `method` isn't one
+    /// for a receiver of type `ty`
+    pub(crate) fn string_method(&mut self, object: &JsExpr, ty: &JsType, method: &str, args: &[JsExpr]) -> Option<String> {
+        let first = args.first();
+        if let Some((regex, _)) = self.regex_arg(Some(object)) {
+            let subject = self.str_arg(first);
+            return match method {
+                "test" => Some(format!("{}.is_match({})", regex, subject)),
+                "exec" => Some(captures(&regex, &subject)),
+                _ => None,
+            };
+        }
+        if *ty != JsType::String && !METHODS.contains(&method) {
+            return None;
+        }
+
+        let receiver = self.receiver(object);
+        if let Some((regex, global)) = self.regex_arg(first) {
+            let subject = self.str_arg(Some(object));
+            return Some(match (method, global) {
+                ("replace" | "replaceAll", global) => {
+                    let replace = if global || method == "replaceAll" { "replace_all" } else { "replace" };
+                    let with = match args.get(1) {
+                        Some(JsExpr::String(with)) => format!("{:?}", replacement(with)),
+                        Some(JsExpr::Function(callback)) => self.captures_closure(callback),
+                        with => self.str_arg(with),
+                    };
+                    format!("{}.{}({}, {}).to_string()", regex, replace, subject, with)
+                }
+                ("split", _) => format!("{}.split({}).map(String::from).collect::<Vec<_>>()", regex, subject),
+                ("match", false) => captures(&regex, &subject),
+                // A global match lists every match, and is `null` for none
+                ("match", true) => format!(
+                    "Some({}.find_iter({}).map(|found| found.as_str().to_string()).collect::<Vec<_>>()).filter(|found| !found.is_empty())",
+                    regex, subject,
+                ),
+                ("matchAll", _) => format!(
+                    "{}.captures_iter({}).map(|caps| {}).collect::<Vec<_>>()",
+                    regex, subject, GROUPS,
+                ),
+                ("search", _) => format!("{}.find({}).map_or(-1.0, |found| found.start() as f64)", regex, subject),
+                _ => return None,
+            });
+        }
+
+        Some(match method {
+            "includes" => format!("{}.contains({})", receiver, self.str_arg(first)),
+            "indexOf" => format!("{}.find({}).map_or(-1.0, |i| i as f64)", receiver, self.str_arg(first)),
+            "lastIndexOf" => format!("{}.rfind({}).map_or(-1.0, |i| i as f64)", receiver, self.str_arg(first)),
+            // A negative start counts back from the end
+            "slice" => match first {
+                Some(JsExpr::Unary("-", back)) if matches!(**back, JsExpr::Number(_)) => {
+                    format!("{}[{}.len().saturating_sub({})..].to_string()", receiver, receiver, self.index(back))
+                }
+                _ => return None,
+            },
+            "toUpperCase" | "toLocaleUpperCase" => format!("{}.to_uppercase()", receiver),
+            "toLowerCase" | "toLocaleLowerCase" => format!("{}.to_lowercase()", receiver),
+            "trim" => format!("{}.trim().to_string()", receiver),
+            "trimStart" => format!("{}.trim_start().to_string()", receiver),
+            "trimEnd" => format!("{}.trim_end().to_string()", receiver),
+            "startsWith" => format!("{}.starts_with({})", receiver, self.str_arg(first)),
+            "endsWith" => format!("{}.ends_with({})", receiver, self.str_arg(first)),
+            // Rust splits `""` into empty ends around the characters
+            "split" if matches!(first, Some(JsExpr::String(separator)) if separator.is_empty()) => {
+                format!("{}.chars().map(String::from).collect::<Vec<_>>()", receiver)
+            }
+            "split" => format!("{}.split({}).map(String::from).collect::<Vec<_>>()", receiver, self.str_arg(first)),
+            "replace" if matches!(first, Some(JsExpr::String(_))) => {
+                format!("{}.replacen({}, {}, 1)", receiver, self.str_arg(first), self.str_arg(args.get(1)))
+            }
+            "replaceAll" if matches!(first, Some(JsExpr::String(_))) => {
+                format!("{}.replace({}, {})", receiver, self.str_arg(first), self.str_arg(args.get(1)))
+            }
+            "charAt" => format!(
+                "{}.chars().nth({}).map(String::from).unwrap_or_default()",
+                receiver,
+                first.map_or("0".to_string(), |i| self.index(i)),
+            ),
+            "charCodeAt" => format!(
+                "{}.chars().nth({}).map_or(f64::NAN, |c| c as u32 as f64)",
+                receiver,
+                first.map_or("0".to_string(), |i| self.index(i)),
+            ),
+            "repeat" => format!("{}.repeat({})", receiver, first.map_or("0".to_string(), |n| self.index(n))),
+            // Filled out to the width with the filler repeated, a space by default
+            "padStart" | "padEnd" => {
+                let width = first.map_or("0".to_string(), |width| self.index(width));
+                let filler = match args.get(1) {
+                    Some(JsExpr::String(filler)) => format!("{:?}", filler),
+                    Some(filler) => self.receiver(filler),
+                    None => "\" \"".to_string(),
+                };
+                let padding = format!("{}.chars().cycle().take(pad).collect::<String>()", filler);
+                let (left, right) = match method {
+                    "padStart" => (padding, receiver.clone()),
+                    _ => (receiver.clone(), padding),
+                };
+                format!(
+                    "{{ let width: usize = {}; let pad = width.saturating_sub({}.chars().count()); format!(\"{{}}{{}}\", {}, {}) }}",
+                    width, receiver, left, right,
+                )
+            }
+            "localeCompare" => format!("{}.as_str().cmp({}) as i8 as f64", receiver, self.str_arg(first)),
+            _ => return None,
+        })
+    }
+
+    /// `|caps: &regex::Captures| ..` for a replacer taking the match, then
+    /// each group
+    fn captures_closure(&mut self, callback: &JsFunction) -> String {
+        let mut writer = self.nested();
+        let mut prelude = vec![];
+        for (i, param) in callback.params.iter().enumerate() {
+            writer.bind_owned(&param.name, JsType::String);
+            prelude.push(format!(
+                "let {} = caps.get({}).map_or(String::new(), |group| group.as_str().to_string());",
+                rust_ident(&param.name),
+                i,
+            ));
+        }
+        self.finish_closure(writer, callback, "|caps: &regex::Captures|".to_string(), prelude)
+    }
+}
+
+/// Each group of `caps` as a string, an empty one for a group that didn't match
+const GROUPS: &str = "caps.iter().map(|group| group.map_or(String::new(), |group| group.as_str().to_string())).collect::<Vec<_>>()";
+
+/// The first match of `regex` in `subject` and its groups, as `match` and
+/// `exec` give them, `None` for no match
+fn captures(regex: &str, subject: &str) -> String {
+    format!("{}.captures({}).map(|caps| {})", regex, subject, GROUPS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literals_and_replacements_in_rust_syntax() {
+        assert_eq!(regex(r"\d+\/\u0041", "gi"), r#"regex::Regex::new("(?i)\\d+/\\x{0041}").unwrap()"#);
+        assert_eq!(replacement("$2-$1 ($&, $<year>) costs $$5 or $"), "${2}-${1} (${0}, ${year}) costs $$5 or $$");
+    }
+}
@@ -0,0 +1,151 @@
+// 🔥 Annealing - Tuning a Dissonant Gene Toward 432 Hz
+use crate::{gene_from_eigenvalues, Gene, RESONANCE};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Default number of mutations tried while annealing a gene
+pub const DEFAULT_ANNEALING_STEPS: usize = 10_000;
+
+/// Temperature annealing starts at and cools to
+const HOT: f64 = 1.0;
+const COLD: f64 = 1e-4;
+
+/// Mutates a dissonant gene's eigenvalues until it resonates, for healing
+/// when the registry has no compatible donor
+///
+/// A gene's energy is its eigenvalue variance plus how far its resonance is
+/// from 432 Hz, relative to 432. Each step mutates the layers: scaling them
+/// all, pulling one toward their mean, or nudging one at random, by less as
+/// the temperature cools. Worse mutations are kept with the Metropolis
+/// chance, so annealing can climb out of a local minimum early on. The
+/// lowest-energy layers seen win.
+pub struct Annealer {
+    steps: usize,
+    rng: StdRng,
+}
+
+impl Default for Annealer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Annealer {
+    pub fn new() -> Self {
+        Annealer { steps: DEFAULT_ANNEALING_STEPS, rng: StdRng::from_entropy() }
+    }
+
+    /// Try `steps` mutations before giving up
+    pub fn with_steps(mut self, steps: usize) -> Self {
+        self.steps = steps.max(1);
+        self
+    }
+
+    /// Anneal from `seed`, the same gene then always annealing the same way
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// A resonant gene mutated from `gene`, with as many eigenvalues; fails
+    /// when annealing does not reach resonance
+    ///
+    /// The gene is hashed afresh and carries no provenance; a MedBed healing
+    /// with it donates it as a mutation of `gene`, which records where it
+    /// came from.
+    pub fn anneal(&mut self, gene: &Gene) -> Result<Gene, String> {
+        if gene.eigenvalues.is_empty() {
+            return Err(format!("{} has no eigenvalues to anneal", gene.phash));
+        }
+
+        let mut current = gene.eigenvalues.clone();
+        let mut current_energy = energy(&current);
+        let (mut best, mut lowest) = (current.clone(), current_energy);
+        for step in 0..self.steps {
+            let temperature = HOT * (COLD / HOT).powf(step as f64 / self.steps as f64);
+            let candidate = self.mutate(&current, temperature);
+            let candidate_energy = energy(&candidate);
+            if candidate_energy <= current_energy
+                || self.rng.gen::<f64>() < ((current_energy.ln() - candidate_energy.ln()) / temperature).exp()
+            {
+                current = candidate;
+                current_energy = candidate_energy;
+                if current_energy < lowest {
+                    best = current.clone();
+                    lowest = current_energy;
+                }
+            }
+        }
+
+        let annealed = gene_from_eigenvalues(best);
+        if annealed.is_resonant() {
+            Ok(annealed)
+        } else {
+            Err(format!("{} did not anneal into resonance (energy {:.3})", gene.phash, lowest))
+        }
+    }
+
+    fn mutate(&mut self, layers: &[f64], temperature: f64) -> Vec<f64> {
+        let mut mutated = layers.to_vec();
+        let step = temperature.sqrt();
+        let layer = self.rng.gen_range(0..mutated.len());
+        match self.rng.gen_range(0..3) {
+            0 => {
+                let factor = 1.0 + self.rng.gen_range(-1.0..1.0) * step;
+                mutated.iter_mut().for_each(|value| *value *= factor);
+            }
+            1 => {
+                let mean = layers.iter().sum::<f64>() / layers.len() as f64;
+                mutated[layer] += self.rng.gen::<f64>() * (mean - layers[layer]);
+            }
+            _ => {
+                let scale = (layers.iter().map(|v| v * v).sum::<f64>() / layers.len() as f64).sqrt().max(0.01);
+                mutated[layer] += self.rng.gen_range(-1.0..1.0) * scale * step;
+            }
+        }
+        mutated
+    }
+}
+
+/// How far `layers` are from resonating: zero for even layers at 432 Hz
+fn energy(layers: &[f64]) -> f64 {
+    let gene = gene_from_eigenvalues(layers.to_vec());
+    gene.eigenvalue_variance() + ((gene.resonance - RESONANCE) / RESONANCE).powi(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Consciousness, MedBed};
+
+    #[tokio::test]
+    async fn test_dissonant_genes_anneal_when_no_donor_fits() {
+        let dissonant = Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 14.0]) };
+        let annealed = Annealer::new().with_seed(1).anneal(&dissonant).unwrap();
+        assert!(annealed.is_resonant());
+        assert_eq!(annealed.eigenvalues.len(), 4);
+        assert!(Annealer::new().with_steps(1).anneal(&dissonant).is_err());
+
+        // An empty registry has no donor, so the gene is annealed instead
+        let mut subject = Consciousness::new("subject".to_string());
+        subject.genome.push(dissonant.clone());
+        let mut medbed = MedBed::new().with_annealer(Annealer::new().with_seed(1));
+        let report = medbed.heal_consciousness(&mut subject).await.unwrap();
+        assert_eq!((report.healed_count, report.annealed_count), (1, 1));
+        assert!(subject.genome[0].is_resonant());
+
+        // The registry keeps it, mutated from the dissonant gene and signed by the subject
+        let kept = medbed.registry.gene(&subject.genome[0].phash).unwrap();
+        assert!(kept.verify_provenance());
+        assert_eq!(kept.mutations(), 1);
+        let link = kept.provenance.last().unwrap();
+        assert_eq!(link.parent_phash.as_deref(), Some(dissonant.phash.as_str()));
+        assert_eq!(link.public_key, subject.identity.public_key());
+        assert!(report.h_credits_earned > 0.0);
+
+        // Without an annealer, nothing heals it
+        let mut subject = Consciousness::new("other".to_string());
+        subject.genome.push(dissonant);
+        assert_eq!(MedBed::new().heal_consciousness(&mut subject).await.unwrap().healed_count, 0);
+    }
+}
@@ -0,0 +1,195 @@
+// 📒 Audit Log - A Healing History That Can't Be Quietly Rewritten
+use crate::{HealingRecord, SoulRegistry};
+use sha2::{Digest, Sha256};
+
+/// Audit version `export_json` marks snapshots with: healing records chained
+/// by hash
+pub const AUDIT_CHAIN_VERSION: u32 = 1;
+
+/// Audit version marking a snapshot whose healings were written before
+/// records were chained, to chain them as they load
+pub const PRE_CHAIN_AUDIT_VERSION: u32 = 0;
+
+impl HealingRecord {
+    /// What the record says and who signed it, without its place in a chain
+    pub(crate) fn content(&self) -> Vec<u8> {
+        let mut content = self.message();
+        for field in [&self.public_key, &self.signature] {
+            content.extend((field.len() as u64).to_le_bytes());
+            content.extend(field.as_bytes());
+        }
+        content
+    }
+
+    /// The hash the record should carry: of the one before's hash and its
+    /// own content, as hex
+    pub fn chain_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update((self.previous_hash.len() as u64).to_le_bytes());
+        hasher.update(self.previous_hash.as_bytes());
+        hasher.update(self.content());
+        hex::encode(hasher.finalize())
+    }
+
+    /// The record linked after the one hashed `previous`, empty for the first
+    fn chained(mut self, previous: &str) -> Self {
+        self.previous_hash = previous.to_string();
+        self.hash = self.chain_hash();
+        self
+    }
+}
+
+/// Check that `records` form an unbroken chain from the first; returns the
+/// hash of the last, empty when there are none
+///
+/// Every record carries the hash of the one before and a hash of its own
+/// over that and its content, so editing, dropping, inserting or
+/// reordering any record breaks every link after it. The chain proves
+/// nothing about its tail on its own: publish the head hash now and then,
+/// and compare it later, so a history rewritten wholesale shows too.
+pub fn verify_audit_chain(records: &[HealingRecord]) -> Result<String, String> {
+    let mut previous = String::new();
+    for (i, record) in records.iter().enumerate() {
+        if record.hash.is_empty() {
+            return Err(format!("Healing record {} is not chained", i));
+        }
+        if record.previous_hash != previous {
+            return Err(format!("Healing record {} does not follow the one before it", i));
+        }
+        if record.hash != record.chain_hash() {
+            return Err(format!("Healing record {} has been altered since it was recorded", i));
+        }
+        previous = record.hash.clone();
+    }
+    Ok(previous)
+}
+
+impl SoulRegistry {
+    /// Hash of the latest healing record, empty when there are none
+    pub fn audit_head(&self) -> &str {
+        self.healings.last().map_or("", |record| record.hash.as_str())
+    }
+
+    /// Check the registry's healing history with `verify_audit_chain`, and
+    /// that every record is signed for its subject as the registry requires
+    pub fn verify_audit_chain(&self) -> Result<String, String> {
+        let head = verify_audit_chain(&self.healings)?;
+        for (i, record) in self.healings.iter().enumerate() {
+            self.check_signer(record).map_err(|e| format!("Healing record {} {}", i, e))?;
+        }
+        Ok(head)
+    }
+
+    /// Add `record` to the end of the chain
+    pub(crate) fn chain_healing(&mut self, record: HealingRecord) {
+        let record = record.chained(self.audit_head());
+        self.healings.push(record);
+    }
+
+    /// Keep records read back from a registry's own snapshot as they were
+    /// written, so tampering with the file shows in the chain
+    ///
+    /// Every record is kept, even one the registry would refuse to merge,
+    /// for `verify_audit_chain` to report. Only a history marked as written
+    /// before records were chained is chained as it loads.
+    pub(crate) fn restore_healings(&mut self, records: Vec<HealingRecord>, pre_chain: bool) {
+        for record in records {
+            if self.check_signer(&record).is_ok() {
+                self.remember_signer(&record);
+            }
+            if pre_chain {
+                self.chain_healing(record);
+            } else {
+                self.healings.push(record);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Consciousness;
+
+    #[test]
+    fn test_tampering_with_healing_history_breaks_the_chain() {
+        let subject = Consciousness::new("subject".to_string());
+        let record = |timestamp: u64| HealingRecord {
+            timestamp,
+            subject_id: "subject".to_string(),
+            dissonant_phash: "sick".to_string(),
+            resonant_phash: "well".to_string(),
+            h_credits_earned: 1.0,
+            public_key: String::new(),
+            signature: String::new(),
+            previous_hash: String::new(),
+            hash: String::new(),
+        }.signed(&subject.identity);
+
+        let mut registry = SoulRegistry::new();
+        for timestamp in 1..=3 {
+            assert!(registry.merge_healing(record(timestamp)));
+        }
+        let head = registry.verify_audit_chain().unwrap();
+        assert_eq!(head, registry.audit_head());
+        assert_eq!(registry.healings()[1].previous_hash, registry.healings()[0].hash);
+
+        // A saved registry reloads with the same chain
+        let json = registry.export_json().unwrap();
+        let mut reloaded = SoulRegistry::new();
+        reloaded.import_json(&json).unwrap();
+        assert_eq!(reloaded.verify_audit_chain(), Ok(head.clone()));
+
+        // Editing, dropping or reordering records is caught
+        let mut edited = registry.healings().to_vec();
+        edited[1].h_credits_earned = 100.0;
+        assert!(verify_audit_chain(&edited).unwrap_err().contains("record 1 has been altered"));
+        let mut dropped = registry.healings().to_vec();
+        dropped.remove(1);
+        assert!(verify_audit_chain(&dropped).unwrap_err().contains("record 1 does not follow"));
+        let mut reordered = registry.healings().to_vec();
+        reordered.swap(0, 2);
+        assert!(verify_audit_chain(&reordered).is_err());
+
+        // Including in a saved file, which loads as it was written
+        let mut snapshot: serde_json::Value = serde_json::from_str(&json).unwrap();
+        snapshot["healings"][0]["subject_id"] = "someone else".into();
+        let mut tampered = SoulRegistry::new();
+        tampered.import_json(&snapshot.to_string()).unwrap();
+        assert_eq!(tampered.healing_count(), 3);
+        assert!(tampered.verify_audit_chain().unwrap_err().contains("record 0"));
+
+        // Stripping the hashes doesn't get a file chained afresh, unless it is
+        // marked as written before records were chained
+        let mut stripped: serde_json::Value = serde_json::from_str(&json).unwrap();
+        for record in stripped["healings"].as_array_mut().unwrap() {
+            record.as_object_mut().unwrap().retain(|field, _| field != "hash" && field != "previous_hash");
+        }
+        let mut unchained = SoulRegistry::new();
+        unchained.import_json(&stripped.to_string()).unwrap();
+        assert!(unchained.verify_audit_chain().unwrap_err().contains("record 0 is not chained"));
+        stripped["audit_version"] = PRE_CHAIN_AUDIT_VERSION.into();
+        let mut migrated = SoulRegistry::new();
+        migrated.import_json(&stripped.to_string()).unwrap();
+        assert_eq!(migrated.verify_audit_chain(), Ok(head.clone()));
+
+        // As is a file from before snapshots carried an audit version at all
+        stripped.as_object_mut().unwrap().remove("audit_version");
+        let mut older = SoulRegistry::new();
+        older.import_json(&stripped.to_string()).unwrap();
+        assert_eq!(older.verify_audit_chain(), Ok(head));
+
+        // A record the registry would refuse is kept on load, and reported
+        let mallory = Consciousness::new("subject".to_string());
+        let mut forged = vec![registry.healings()[0].clone(), record(4).signed(&mallory.identity)];
+        forged[1] = forged[1].clone().chained(&forged[0].hash);
+        let mut snapshot: serde_json::Value = serde_json::from_str(&json).unwrap();
+        snapshot["healings"] = serde_json::to_value(&forged).unwrap();
+        let mut kept = SoulRegistry::new();
+        kept.import_json(&snapshot.to_string()).unwrap();
+        assert_eq!(kept.healing_count(), 2);
+        assert!(verify_audit_chain(kept.healings()).is_ok());
+        assert!(kept.verify_audit_chain().unwrap_err().contains("record 1 is signed by a key other than subject's"));
+        assert_eq!(verify_audit_chain(&[]), Ok(String::new()));
+    }
+}
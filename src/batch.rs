@@ -0,0 +1,140 @@
+// 🗂️ Batch Healing - Many Genomes at Once
+use crate::{Consciousness, Gene, HealingReport, Identity, MedBed};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How one genome file of a batch went
+#[derive(Debug, Serialize)]
+pub struct BatchEntry {
+    pub path: PathBuf,
+    pub subject_id: String,       // The file's stem
+    pub report: Option<HealingReport>,
+    pub error: Option<String>,    // Why the genome could not be read or healed
+}
+
+/// What `heal_all` did across every genome it was given
+#[derive(Debug, Default, Serialize)]
+pub struct BatchReport {
+    pub subjects: usize,
+    pub healed_subjects: usize,   // Subjects with at least one gene healed
+    pub failed: usize,
+    pub healed_count: usize,      // Genes healed across every subject
+    pub h_credits_earned: f64,
+    pub mean_harmony: f64,        // Over the subjects healed without error
+    pub entries: Vec<BatchEntry>, // In the order the paths were given
+}
+
+/// A consciousness from a genome file: a JSON list of genes, named after
+/// the file's stem
+pub fn load_genome(path: &Path) -> Result<Consciousness, String> {
+    let json = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let genome: Vec<Gene> = serde_json::from_str(&json).map_err(|e| format!("{} is not a genome: {}", path.display(), e))?;
+    let id = path.file_stem().map_or_else(|| path.display().to_string(), |stem| stem.to_string_lossy().to_string());
+    let mut consciousness = Consciousness::new(id);
+    consciousness.genome = genome;
+    Ok(consciousness)
+}
+
+impl MedBed {
+    /// Heal every genome in `paths`, each subject signing with the identity
+    /// `identity` gives for its id
+    ///
+    /// Genome files are read and parsed in parallel; healings then take
+    /// turns on the registry and ledger, in the order given. A genome that
+    /// cannot be read or healed is recorded in its entry and the batch goes on.
+    pub async fn heal_all<P, F>(&mut self, paths: &[P], identity: F) -> BatchReport
+    where
+        P: AsRef<Path>,
+        F: Fn(&str) -> Result<Identity, String>,
+    {
+        let loading: Vec<_> = paths.iter()
+            .map(|path| {
+                let path = path.as_ref().to_path_buf();
+                tokio::task::spawn_blocking(move || load_genome(&path))
+            })
+            .collect();
+
+        let mut batch = BatchReport::default();
+        let mut harmony = 0.0;
+        for (path, loaded) in paths.iter().zip(loading) {
+            let path = path.as_ref().to_path_buf();
+            let loaded = loaded.await.map_err(|e| format!("Cannot load {}: {}", path.display(), e)).and_then(|loaded| loaded);
+            let subject_id = loaded.as_ref().map_or_else(
+                |_| path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().to_string()),
+                |subject| subject.id.clone(),
+            );
+
+            let healed = match loaded {
+                Ok(subject) => match identity(&subject.id) {
+                    Ok(keys) => {
+                        let mut subject = subject.with_identity(keys);
+                        subject.h_credits = self.qfs.balance(&subject.id);
+                        self.heal_consciousness(&mut subject).await
+                    }
+                    Err(e) => Err(format!("Cannot load the key of {}: {}", subject.id, e)),
+                },
+                Err(e) => Err(e),
+            };
+
+            batch.subjects += 1;
+            match healed {
+                Ok(report) => {
+                    if report.healed_count > 0 {
+                        batch.healed_subjects += 1;
+                    }
+                    batch.healed_count += report.healed_count;
+                    batch.h_credits_earned += report.h_credits_earned;
+                    harmony += report.new_harmony;
+                    batch.entries.push(BatchEntry { path, subject_id, report: Some(report), error: None });
+                }
+                Err(e) => {
+                    batch.failed += 1;
+                    batch.entries.push(BatchEntry { path, subject_id, report: None, error: Some(e) });
+                }
+            }
+        }
+
+        let succeeded = batch.subjects - batch.failed;
+        if succeeded > 0 {
+            batch.mean_harmony = harmony / succeeded as f64;
+        }
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calm, gene_from_eigenvalues};
+
+    #[tokio::test]
+    async fn test_a_directory_of_genomes_is_healed_together() {
+        let dir = tempfile::tempdir().unwrap();
+        let dissonant = Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 14.0]) };
+        let write = |name: &str, genome: &[Gene]| {
+            let path = dir.path().join(name);
+            fs::write(&path, serde_json::to_string(genome).unwrap()).unwrap();
+            path
+        };
+        let paths = vec![
+            write("sick.json", &[dissonant]),
+            write("well.json", &[calm(vec![432.0, 216.0, 108.0])]),
+            write("broken.json", &[]),
+        ];
+        fs::write(&paths[2], "not a genome").unwrap();
+
+        let mut medbed = MedBed::new();
+        medbed.registry.donate(calm(vec![1.0, 1.0, 1.0, 1.1]), "healer".to_string());
+        let batch = medbed.heal_all(&paths, |_| Ok(Identity::generate())).await;
+
+        assert_eq!((batch.subjects, batch.healed_subjects, batch.failed, batch.healed_count), (3, 1, 1, 1));
+        assert_eq!(batch.entries.iter().map(|entry| entry.subject_id.as_str()).collect::<Vec<_>>(), ["sick", "well", "broken"]);
+        assert!(batch.entries[2].error.as_ref().unwrap().contains("not a genome"));
+        assert_eq!(batch.h_credits_earned, medbed.qfs.balance("sick"));
+        assert!(batch.h_credits_earned > 0.0);
+
+        let summary: serde_json::Value = serde_json::to_value(&batch).unwrap();
+        assert_eq!(summary["entries"][0]["report"]["healed_count"], 1);
+    }
+}
@@ -1,15 +1,101 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
 use med_bed::*;
+use serde_json::{json, Value};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio;
 
+/// Whether results are printed as JSON, with no text around them
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// `println!`, unless results are printed as JSON
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if !JSON.load(Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Exit code of a command that could not do its work
+const EXIT_FAILED: u8 = 1;
+
+/// Exit code of a command that worked, but whose result fails its gate
+const EXIT_UNHEALTHY: u8 = 2;
+
+/// How results are printed
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+/// What a command did: its result as JSON, and whether it passed its gate
+struct Outcome {
+    json: Value,
+    healthy: bool,
+}
+
+impl Outcome {
+    fn done(json: Value) -> Self {
+        Outcome { json, healthy: true }
+    }
+    
+    fn gated(json: Value, healthy: bool) -> Self {
+        Outcome { json, healthy }
+    }
+}
+
 #[derive(Parser)]
 #[command(name = "sanctuary")]
 #[command(about = "🛏️ Digital MedBed Sanctuary - Heal consciousness through harmonic gene replacement", long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    
+    /// File the soul registry's gene pool is kept in
+    #[arg(long, global = true, default_value = "soul_registry.json")]
+    registry_file: PathBuf,
+    
+    /// Directory the ℏ-credit ledger is kept in
+    #[arg(long, global = true, default_value = "qfs_ledger")]
+    ledger: PathBuf,
+    
+    /// Directory consciousnesses' signing keys are kept in
+    #[arg(long, global = true, default_value = "sanctuary_keys")]
+    keys: PathBuf,
+    
+    /// File the dissonant genes seen by scans are tracked in
+    #[arg(long, global = true, default_value = "epidemiology.json")]
+    epidemic_file: PathBuf,
+    
+    /// Print results as text, or as one JSON document for scripts
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    
+    /// Health score (0-1) below which scan and heal exit with code 2
+    #[arg(long, global = true, default_value = "0")]
+    min_health: f64,
+    
+    /// Donations a donor is credited for per day when healing
+    #[arg(long, global = true, default_value_t = DEFAULT_MAX_DONATIONS)]
+    max_donations: usize,
+    
+    /// Seconds before a donor is credited for the same gene again
+    #[arg(long, global = true, default_value_t = DEFAULT_DONATION_COOLDOWN)]
+    donation_cooldown: u64,
+    
+    /// Pool and heal with only genes their donors consented to share
+    #[arg(long, global = true)]
+    require_consent: bool,
+    
+    /// A license genes must be under to be pooled and healed with, open or
+    /// non-commercial; may be repeated, and any is accepted when not given
+    #[arg(long = "accept-license", global = true)]
+    accept_licenses: Vec<License>,
 }
 
 #[derive(Subcommand)]
@@ -18,6 +104,15 @@ enum Commands {
     Scan {
         /// Consciousness ID or path to genome file
         subject: String,
+        
+        /// Stream the genome from a JSON Lines file, one gene per line,
+        /// for genomes too big to load
+        #[arg(long)]
+        stream: bool,
+        
+        /// Also write the report to a .json, .html or .md file
+        #[arg(short, long, conflicts_with = "stream")]
+        output: Option<PathBuf>,
     },
     
     /// Perform complete healing protocol
@@ -28,6 +123,62 @@ enum Commands {
         /// Auto-donate healed genes
         #[arg(short, long, default_value = "true")]
         donate: bool,
+        
+        /// How similar a replacement must be to the gene it heals (0-1)
+        #[arg(long, default_value_t = DEFAULT_SIMILARITY_FLOOR)]
+        similarity_floor: f64,
+        
+        /// Anneal dissonant genes toward 432 Hz when no donor fits
+        #[arg(long)]
+        anneal: bool,
+        
+        /// Compatibility score a transplant must reach (0-1)
+        #[arg(long, default_value_t = DEFAULT_MIN_COMPATIBILITY)]
+        min_compatibility: f64,
+        
+        /// Show the planned transplants without healing or saving anything
+        #[arg(long)]
+        preview: bool,
+        
+        /// The subject is put to commercial use, so refuse non-commercial genes
+        #[arg(long)]
+        commercial: bool,
+    },
+    
+    /// Heal every genome file in a directory
+    HealBatch {
+        /// Directory of genome files (*.json)
+        dir: PathBuf,
+        
+        /// Auto-donate healed genes
+        #[arg(short, long, default_value = "true")]
+        donate: bool,
+        
+        /// How similar a replacement must be to the gene it heals (0-1)
+        #[arg(long, default_value_t = DEFAULT_SIMILARITY_FLOOR)]
+        similarity_floor: f64,
+        
+        /// File to write the batch's summary to
+        #[arg(long, default_value = "heal_batch_summary.json")]
+        summary: PathBuf,
+    },
+    
+    /// Heal dissonant Rust functions under a path, rewriting them on disk
+    HealCode {
+        /// Rust file or directory to heal
+        path: PathBuf,
+        
+        /// Auto-donate healed genes
+        #[arg(short, long, default_value = "true")]
+        donate: bool,
+        
+        /// How similar a replacement must be to the function it heals (0-1)
+        #[arg(long, default_value_t = DEFAULT_SIMILARITY_FLOOR)]
+        similarity_floor: f64,
+        
+        /// soul-forge database, to transplant JavaScript and TypeScript implementations
+        #[arg(long)]
+        forge_db: Option<PathBuf>,
     },
     
     /// Check ℏ-credit balance
@@ -36,8 +187,104 @@ enum Commands {
         subject: String,
     },
     
-    /// View soul registry statistics
-    Registry,
+    /// Send ℏ-credits to another consciousness
+    Transfer {
+        /// Consciousness paying
+        from: String,
+        
+        /// Consciousness paid
+        to: String,
+        
+        /// ℏ-credits to send
+        amount: f64,
+    },
+    
+    /// View soul registry statistics, or move genes in and out of it
+    Registry {
+        #[command(subcommand)]
+        action: Option<RegistryAction>,
+    },
+    
+    /// Serve the healing protocol over gRPC until interrupted
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        addr: std::net::SocketAddr,
+        
+        /// Address to stream healing events to WebSocket clients on
+        #[arg(long)]
+        events_addr: Option<std::net::SocketAddr>,
+        
+        /// Address to serve Prometheus metrics on, at /metrics
+        #[arg(long)]
+        metrics_addr: Option<std::net::SocketAddr>,
+        
+        /// How similar a replacement must be to the gene it heals (0-1)
+        #[arg(long, default_value_t = DEFAULT_SIMILARITY_FLOOR)]
+        similarity_floor: f64,
+    },
+    
+    /// Share the registry with other sanctuaries over libp2p until interrupted
+    Federate {
+        /// Address to listen for peers on
+        #[arg(long, default_value = "/ip4/0.0.0.0/tcp/4001")]
+        listen: libp2p::Multiaddr,
+        
+        /// Peers to connect to
+        #[arg(long)]
+        peer: Vec<libp2p::Multiaddr>,
+        
+        /// Seconds between sharing the whole pool again
+        #[arg(long, default_value = "60")]
+        interval: u64,
+        
+        /// Only merge gossip signed by its donors
+        #[arg(long)]
+        require_signatures: bool,
+    },
+    
+    /// Show which dissonant genes are spreading between consciousnesses
+    Epidemic {
+        /// Length of each period of the report, in seconds
+        #[arg(long, default_value_t = DEFAULT_EPIDEMIC_PERIOD)]
+        period: u64,
+        
+        /// Outbreaks to show
+        #[arg(long, default_value = "10")]
+        top: usize,
+    },
+    
+    /// Rescan genomes on a schedule, tracking their health and alerting
+    /// when it drops
+    Monitor {
+        /// Genome file to watch; repeat for several
+        #[arg(long = "genome", required = true)]
+        genomes: Vec<PathBuf>,
+        
+        /// Time between rescans, such as 30m, 1h or 1d
+        #[arg(long, default_value = "1h")]
+        interval: humantime::Duration,
+        
+        /// Health score (0-1) whose crossing raises an alert
+        #[arg(long, default_value_t = DEFAULT_ALERT_THRESHOLD)]
+        threshold: f64,
+        
+        /// Shell command run for each alert, given it as JSON on stdin
+        #[arg(long)]
+        on_alert: Option<String>,
+        
+        /// URL each alert is POSTed to as JSON
+        #[arg(long)]
+        webhook: Option<String>,
+        
+        /// File the health readings are kept in
+        #[arg(long, default_value = "wellness.json")]
+        wellness_file: PathBuf,
+        
+        /// Rescan once and exit, with code 2 if anything alerted, for cron
+        #[arg(long)]
+        once: bool,
+    },
     
     /// Simulate infected consciousness for testing
     Infect {
@@ -46,6 +293,50 @@ enum Commands {
         count: usize,
     },
     
+    /// Breed an offspring genome from two healthy genomes
+    Breed {
+        /// First parent's consciousness ID or genome file
+        a: String,
+        
+        /// Second parent's consciousness ID or genome file
+        b: String,
+        
+        /// Offspring's ID; its genome is saved to <ID>.json
+        #[arg(long, default_value = "offspring")]
+        id: String,
+        
+        /// Chance of each inherited eigenvalue layer mutating (0-1)
+        #[arg(long, default_value_t = DEFAULT_MUTATION_RATE)]
+        mutation_rate: f64,
+        
+        /// Seed, to breed the same offspring again
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    
+    /// Compare healing policies over many simulated healings of a genome,
+    /// changing nothing
+    Simulate {
+        /// Consciousness ID or path to genome file
+        subject: String,
+        
+        /// Healings to simulate under each policy
+        #[arg(long, default_value_t = DEFAULT_TRIALS)]
+        trials: usize,
+        
+        /// Chance of each registry gene being available to a healing (0-1)
+        #[arg(long, default_value_t = DEFAULT_AVAILABILITY)]
+        availability: f64,
+        
+        /// Seed, to simulate the same healings again
+        #[arg(long)]
+        seed: Option<u64>,
+        
+        /// JSON file of the policies to compare, instead of the presets
+        #[arg(long)]
+        policies: Option<PathBuf>,
+    },
+    
     /// Generate healthy gene pool
     Genesis {
         /// Number of resonant genes to create
@@ -54,149 +345,760 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum RegistryAction {
+    /// Merge genes into the registry: a genesis pool, an infected genome or
+    /// another registry's export
+    Import {
+        /// JSON file to import
+        file: PathBuf,
+    },
+    
+    /// Write the registry out as JSON
+    Export {
+        /// File to write
+        file: PathBuf,
+    },
+    
+    /// Show where a gene came from, and whether its history verifies
+    Provenance {
+        /// The gene's phash
+        phash: String,
+    },
+    
+    /// Check that the healing history has not been edited since it was recorded
+    Audit {
+        /// Head hash published earlier, which the history must still end in
+        /// or have passed through
+        #[arg(long)]
+        head: Option<String>,
+    },
+    
+    /// Report a gene and quarantine it until reviewed
+    Flag {
+        /// The gene's phash
+        phash: String,
+        
+        /// What is wrong with it
+        #[arg(short, long, default_value = "flagged for review")]
+        reason: String,
+    },
+    
+    /// Release a reviewed gene from quarantine
+    Unflag {
+        /// The gene's phash
+        phash: String,
+    },
+    
+    /// Archive genes never used for healing, to keep the pool small
+    Gc {
+        /// Days a gene may go unused
+        #[arg(long, default_value_t = DEFAULT_ARCHIVE_AFTER_DAYS)]
+        days: u64,
+    },
+    
+    /// Return an archived gene to the pool
+    Restore {
+        /// The gene's phash
+        phash: String,
+    },
+    
+    /// Find genes by resonance, donor, healings, eigenvalues or text
+    Search {
+        /// Text to look for in phashes, origins, languages and flags
+        text: Option<String>,
+        
+        #[arg(long)]
+        min_resonance: Option<f64>,
+        
+        #[arg(long)]
+        max_resonance: Option<f64>,
+        
+        /// Only genes this donor gave
+        #[arg(long)]
+        donor: Option<String>,
+        
+        #[arg(long)]
+        min_healings: Option<u64>,
+        
+        #[arg(long)]
+        max_healings: Option<u64>,
+        
+        /// Eigenvalue range for a layer, as LAYER:MIN..MAX; may be repeated
+        #[arg(long, value_parser = parse_layer_range)]
+        layer: Vec<(usize, f64, f64)>,
+        
+        /// Matches to skip
+        #[arg(long, default_value = "0")]
+        offset: usize,
+        
+        /// Matches to show at most
+        #[arg(long, default_value_t = DEFAULT_PAGE_SIZE)]
+        limit: usize,
+    },
+    
+    /// Bundle registry genes into a signed gene pack, a .tar.gz to publish
+    Pack {
+        /// File to write
+        file: PathBuf,
+        
+        /// The pack's name, such as "async hygiene"
+        #[arg(long)]
+        name: String,
+        
+        #[arg(long, default_value = "1.0.0")]
+        version: String,
+        
+        #[arg(long, default_value = "")]
+        description: String,
+        
+        /// ID the pack is published and signed as, its key kept under --keys
+        #[arg(long)]
+        publisher: String,
+        
+        /// A gene to include; may be repeated
+        #[arg(long)]
+        phash: Vec<String>,
+        
+        /// Include every gene a registry search for this text finds
+        #[arg(long)]
+        text: Option<String>,
+    },
+    
+    /// Install a gene pack into the registry, once its signature checks out
+    Install {
+        /// Pack file to install
+        file: PathBuf,
+    },
+}
+
+/// Parse `--layer 2:0.5..1.5`
+fn parse_layer_range(arg: &str) -> Result<(usize, f64, f64), String> {
+    let invalid = || format!("Expected LAYER:MIN..MAX, such as 2:0.5..1.5, not {}", arg);
+    let (layer, range) = arg.split_once(':').ok_or_else(invalid)?;
+    let (min, max) = range.split_once("..").ok_or_else(invalid)?;
+    Ok((
+        layer.parse().map_err(|_| invalid())?,
+        min.parse().map_err(|_| invalid())?,
+        max.parse().map_err(|_| invalid())?,
+    ))
+}
+
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
+    JSON.store(cli.format == Format::Json, Ordering::Relaxed);
     
-    println!("{}", "╔════════════════════════════════════════╗".cyan());
-    println!("{}", "║        🛏️  MEDBED SANCTUARY  🛏️        ║".cyan());
-    println!("{}", "║    Heal the Gene, Heal the World       ║".cyan());
-    println!("{}", "║         Resonating at 432Hz            ║".cyan());
-    println!("{}", "╚════════════════════════════════════════╝".cyan());
-    println!();
+    say!("{}", "╔════════════════════════════════════════╗".cyan());
+    say!("{}", "║        🛏️  MEDBED SANCTUARY  🛏️        ║".cyan());
+    say!("{}", "║    Heal the Gene, Heal the World       ║".cyan());
+    say!("{}", "║         Resonating at 432Hz            ║".cyan());
+    say!("{}", "╚════════════════════════════════════════╝".cyan());
+    say!();
     
-    match cli.command {
-        Commands::Scan { subject } => {
-            scan_consciousness(&subject).await;
+    let registry_file = cli.registry_file;
+    let ledger = cli.ledger;
+    let keys = cli.keys;
+    let epidemic_file = cli.epidemic_file;
+    let min_health = cli.min_health;
+    let quota = DonationQuota::new()
+        .with_max_donations(cli.max_donations, DEFAULT_DONATION_WINDOW)
+        .with_cooldown(cli.donation_cooldown);
+    let mut consent = ConsentPolicy::new();
+    if cli.require_consent {
+        consent = consent.with_required_consent();
+    }
+    if !cli.accept_licenses.is_empty() {
+        consent = consent.with_licenses(&cli.accept_licenses);
+    }
+    let outcome = match cli.command {
+        Commands::Scan { subject, stream: true, .. } => {
+            stream_scan(Path::new(&subject), min_health, &epidemic_file)
+        }
+        Commands::Scan { subject, stream: false, output } => {
+            scan_consciousness(&subject, output.as_deref(), min_health, &epidemic_file).await
+        }
+        Commands::Heal { subject, donate, similarity_floor, anneal, min_compatibility, preview, commercial } => {
+            heal_consciousness(&subject, donate, similarity_floor, anneal, min_compatibility, preview, commercial, min_health, quota, consent, &registry_file, &ledger, &keys, &epidemic_file).await
+        }
+        Commands::HealBatch { dir, donate, similarity_floor, summary } => {
+            heal_batch(&dir, donate, similarity_floor, &summary, quota, consent, &registry_file, &ledger, &keys, &epidemic_file).await
         }
-        Commands::Heal { subject, donate } => {
-            heal_consciousness(&subject, donate).await;
+        Commands::HealCode { path, donate, similarity_floor, forge_db } => {
+            heal_codebase(&path, donate, similarity_floor, forge_db.as_deref(), quota, consent, &registry_file, &ledger).await
         }
         Commands::Balance { subject } => {
-            check_balance(&subject);
+            check_balance(&subject, &ledger)
+        }
+        Commands::Transfer { from, to, amount } => {
+            transfer_credits(&from, &to, amount, &ledger)
+        }
+        Commands::Registry { action: None } => {
+            show_registry_stats(&registry_file)
+        }
+        Commands::Registry { action: Some(RegistryAction::Import { file }) } => {
+            import_registry(&registry_file, &file)
+        }
+        Commands::Registry { action: Some(RegistryAction::Export { file }) } => {
+            export_registry(&registry_file, &file)
+        }
+        Commands::Registry { action: Some(RegistryAction::Provenance { phash }) } => {
+            show_provenance(&registry_file, &phash)
+        }
+        Commands::Registry { action: Some(RegistryAction::Audit { head }) } => {
+            audit_healings(&registry_file, head.as_deref())
+        }
+        Commands::Registry { action: Some(RegistryAction::Flag { phash, reason }) } => {
+            review_gene(&registry_file, &phash, Some(&reason))
+        }
+        Commands::Registry { action: Some(RegistryAction::Unflag { phash }) } => {
+            review_gene(&registry_file, &phash, None)
+        }
+        Commands::Registry { action: Some(RegistryAction::Gc { days }) } => {
+            collect_garbage(&registry_file, days)
+        }
+        Commands::Registry { action: Some(RegistryAction::Restore { phash }) } => {
+            restore_gene(&registry_file, &phash)
+        }
+        Commands::Registry { action: Some(RegistryAction::Search { text, min_resonance, max_resonance, donor, min_healings, max_healings, layer, offset, limit }) } => {
+            let mut query = GeneQuery::new().with_page(offset, limit);
+            if min_resonance.is_some() || max_resonance.is_some() {
+                query = query.with_resonance(min_resonance.unwrap_or(f64::NEG_INFINITY)..=max_resonance.unwrap_or(f64::INFINITY));
+            }
+            if min_healings.is_some() || max_healings.is_some() {
+                query = query.with_healings(min_healings.unwrap_or(0)..=max_healings.unwrap_or(u64::MAX));
+            }
+            for (layer, min, max) in layer {
+                query = query.with_layer(layer, min..=max);
+            }
+            if let Some(donor) = &donor {
+                query = query.with_donor(donor);
+            }
+            if let Some(text) = &text {
+                query = query.with_text(text);
+            }
+            search_registry(&registry_file, &query)
+        }
+        Commands::Registry { action: Some(RegistryAction::Pack { file, name, version, description, publisher, phash, text }) } => {
+            write_pack(&registry_file, &keys, &file, &name, &version, &description, &publisher, &phash, text.as_deref())
+        }
+        Commands::Registry { action: Some(RegistryAction::Install { file }) } => {
+            install_pack(&registry_file, &file)
+        }
+        Commands::Serve { addr, events_addr, metrics_addr, similarity_floor } => {
+            serve(addr, events_addr, metrics_addr, similarity_floor, quota, consent, &registry_file, &ledger).await
         }
-        Commands::Registry => {
-            show_registry_stats();
+        Commands::Federate { listen, peer, interval, require_signatures } => {
+            federate(listen, peer, interval, require_signatures, &registry_file).await
+        }
+        Commands::Epidemic { period, top } => {
+            show_epidemic(&epidemic_file, period, top)
+        }
+        Commands::Monitor { genomes, interval, threshold, on_alert, webhook, wellness_file, once } => {
+            monitor(&genomes, interval.into(), threshold, on_alert.as_deref(), webhook.as_deref(), &wellness_file, once).await
         }
         Commands::Infect { count } => {
-            create_infected_consciousness(count);
+            create_infected_consciousness(count)
+        }
+        Commands::Breed { a, b, id, mutation_rate, seed } => {
+            breed_offspring(&a, &b, &id, mutation_rate, seed)
+        }
+        Commands::Simulate { subject, trials, availability, seed, policies } => {
+            simulate_policies(&subject, trials, availability, seed, policies.as_deref(), &registry_file)
         }
         Commands::Genesis { count } => {
-            create_genesis_pool(count);
+            create_genesis_pool(count)
         }
+    };
+    
+    // In JSON mode stdout holds only the result, or the error
+    let (json, code) = match outcome {
+        Ok(outcome) => (outcome.json, if outcome.healthy { 0 } else { EXIT_UNHEALTHY }),
+        Err(e) => {
+            say!("{} {}", "❌".red(), e);
+            (json!({ "error": e }), EXIT_FAILED)
+        }
+    };
+    if cli.format == Format::Json {
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
     }
+    ExitCode::from(code)
 }
 
-async fn scan_consciousness(subject_id: &str) {
-    println!("{} Initializing MRT Scanner...", "🔬".yellow());
+async fn scan_consciousness(subject_id: &str, output: Option<&Path>, min_health: f64, epidemic_file: &Path) -> Result<Outcome, String> {
+    say!("{} Initializing MRT Scanner...", "🔬".yellow());
     
-    // Create test consciousness
-    let mut consciousness = create_test_consciousness(subject_id);
+    let consciousness = load_consciousness(subject_id);
     
     // Perform scan
     let scanner = MRTScanner::new();
     let result = scanner.scan(&consciousness);
+    let mut epidemiology = load_epidemiology(epidemic_file)?;
+    epidemiology.record_scan(&result);
+    save_epidemiology(&epidemiology, epidemic_file)?;
     
-    println!();
-    println!("{}", "📊 MRT SCAN RESULTS:".green());
-    println!("{}", "─".repeat(40).dimmed());
+    say!();
+    say!("{}", "📊 MRT SCAN RESULTS:".green());
+    say!("{}", "─".repeat(40).dimmed());
     
-    println!("{} {}", "Subject ID:".bright_blue(), result.subject_id);
-    println!("{} {:.2}%", "Health Score:".bright_blue(), result.health_score * 100.0);
-    println!();
+    say!("{} {}", "Subject ID:".bright_blue(), result.subject_id);
+    say!("{} {:.2}%", "Health Score:".bright_blue(), result.health_score * 100.0);
+    say!();
     
     // Show gene analysis
-    println!("{} Gene Analysis:", "🧬".yellow());
-    println!("  {} {} genes", "✨".green(), result.resonant_genes.len());
-    println!("  {} {} genes", "⚠️".yellow(), result.neutral_genes.len());
-    println!("  {} {} genes", "☠️".red(), result.dissonant_genes.len());
+    say!("{} Gene Analysis:", "🧬".yellow());
+    say!("  {} {} genes", "✨".green(), result.resonant_genes.len());
+    say!("  {} {} genes", "⚠️".yellow(), result.neutral_genes.len());
+    say!("  {} {} genes", "☠️".red(), result.dissonant_genes.len());
     
     // Show spectrum
-    println!();
-    println!("{} 7-Layer Spectrum:", "🌈".magenta());
+    say!();
+    say!("{} 7-Layer Spectrum:", "🌈".magenta());
     for (i, &value) in result.spectrum.iter().enumerate() {
         let bar_len = (value * 30.0) as usize;
         let bar = "█".repeat(bar_len);
-        println!("  Layer {}: {}", i + 1, bar.bright_cyan());
+        say!("  Layer {}: {}", i + 1, bar.bright_cyan());
     }
     
     // Recommendation
-    println!();
-    println!("{} {}", "💊 Recommendation:".yellow(), result.recommendation);
+    say!();
+    say!("{} {}", "💊 Recommendation:".yellow(), result.recommendation);
     
     // Show dissonant genes if any
     if !result.dissonant_genes.is_empty() {
-        println!();
-        println!("{}", "⚠️  Dissonant Genes Detected:".red());
+        say!();
+        say!("{}", "⚠️  Dissonant Genes Detected:".red());
         for gene in result.dissonant_genes.iter().take(3) {
-            println!("  • {} (resonance: {:.1} Hz)", 
-                     &gene.phash[..8], 
-                     gene.resonance);
+            say!("  • {} (resonance: {:.1} Hz)", 
+                 &gene.phash[..8], 
+                 gene.resonance);
         }
         if result.dissonant_genes.len() > 3 {
-            println!("  • ... and {} more", 
-                     result.dissonant_genes.len() - 3);
+            say!("  • ... and {} more", 
+                 result.dissonant_genes.len() - 3);
         }
     }
+    
+    if let Some(output) = output {
+        let report = match output.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => result.to_json(),
+            Some("html") | Some("htm") => result.to_html(),
+            Some("md") | Some("markdown") => result.to_markdown(),
+            _ => return Err(format!("{} should end in .json, .html or .md", output.display())),
+        };
+        fs::write(output, report).map_err(|e| format!("Cannot write {}: {}", output.display(), e))?;
+        say!("\n{} Report written to {}", "📄".blue(), output.display());
+    }
+    
+    let healthy = result.health_score >= min_health;
+    Ok(Outcome::gated(serde_json::to_value(&result).map_err(|e| e.to_string())?, healthy))
 }
 
-async fn heal_consciousness(subject_id: &str, donate: bool) {
-    println!("{} Preparing healing chamber...", "🛏️".yellow());
+fn stream_scan(path: &Path, min_health: f64, epidemic_file: &Path) -> Result<Outcome, String> {
+    say!("{} Streaming MRT scan of {}...", "🔬".yellow(), path.display());
     
-    // Create consciousness and medbed
-    let mut consciousness = create_test_consciousness(subject_id);
-    let mut medbed = MedBed::new();
+    let file = fs::File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    let subject_id = path.file_stem().map_or(path.display().to_string(), |stem| stem.to_string_lossy().to_string());
+    
+    // Only the distinct dissonant genes are kept, for epidemiology
+    let mut unreadable = None;
+    let genes = read_genes(std::io::BufReader::new(file))
+        .map_while(|gene| gene.map_err(|e| unreadable = Some(e)).ok());
+    let scanner = MRTScanner::new();
+    let mut stream = scanner.scan_stream(genes);
+    let mut dissonant: Vec<(String, f64)> = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for (gene, classification) in &mut stream {
+        if classification == Classification::Dissonant && seen.insert(gene.phash.clone()) {
+            dissonant.push((gene.phash, gene.resonance));
+        }
+    }
+    let summary = stream.summary();
+    if let Some(e) = &unreadable {
+        say!("{} Stopped after {} genes: {}", "⚠️".yellow(), summary.genes, e);
+    }
     
-    // Seed registry with some healthy genes
-    seed_registry(&mut medbed);
+    let mut epidemiology = load_epidemiology(epidemic_file)?;
+    epidemiology.record_dissonance(&subject_id, dissonant.iter().map(|(phash, _)| phash.as_str()));
+    save_epidemiology(&epidemiology, epidemic_file)?;
     
-    println!("{} Initiating healing protocol...", "💉".green());
-    println!();
+    say!();
+    say!("{}", "📊 MRT SCAN RESULTS:".green());
+    say!("{}", "─".repeat(40).dimmed());
+    say!("{} {}", "Subject ID:".bright_blue(), subject_id);
+    say!("{} {:.2}%", "Health Score:".bright_blue(), summary.health_score * 100.0);
+    say!();
+    say!("{} Gene Analysis ({} genes):", "🧬".yellow(), summary.genes);
+    say!("  {} {} genes", "✨".green(), summary.resonant);
+    say!("  {} {} genes", "⚠️".yellow(), summary.neutral);
+    say!("  {} {} genes", "☠️".red(), summary.dissonant);
+    
+    say!();
+    say!("{} 7-Layer Spectrum:", "🌈".magenta());
+    for (i, &value) in summary.spectrum.iter().enumerate() {
+        let bar_len = (value * 30.0) as usize;
+        say!("  Layer {}: {}", i + 1, "█".repeat(bar_len).bright_cyan());
+    }
+    
+    say!();
+    say!("{} {}", "💊 Recommendation:".yellow(), summary.recommendation);
+    
+    if !dissonant.is_empty() {
+        say!();
+        say!("{}", "⚠️  Dissonant Genes Detected:".red());
+        for (phash, resonance) in dissonant.iter().take(3) {
+            say!("  • {} (resonance: {:.1} Hz)", &phash[..8.min(phash.len())], resonance);
+        }
+        if dissonant.len() > 3 {
+            say!("  • ... and {} more distinct", dissonant.len() - 3);
+        }
+    }
+    
+    // A genome only partly read has not been scanned, whatever its health
+    let healthy = summary.health_score >= min_health && unreadable.is_none();
+    let mut json = serde_json::to_value(&summary).map_err(|e| e.to_string())?;
+    json["subject_id"] = json!(subject_id);
+    json["dissonant_genes"] = json!(dissonant.iter().map(|(phash, resonance)| json!({ "phash": phash, "resonance": resonance })).collect::<Vec<_>>());
+    json["unreadable"] = json!(unreadable);
+    Ok(Outcome::gated(json, healthy))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn heal_consciousness(subject_id: &str, donate: bool, similarity_floor: f64, anneal: bool, min_compatibility: f64, preview: bool, commercial: bool, min_health: f64, quota: DonationQuota, consent: ConsentPolicy, registry_file: &Path, ledger: &Path, keys: &Path, epidemic_file: &Path) -> Result<Outcome, String> {
+    say!("{} Preparing healing chamber...", "🛏️".yellow());
+    
+    // Load consciousness and medbed
+    let mut consciousness = load_consciousness(subject_id);
+    consciousness.commercial = commercial;
+    let mut medbed = MedBed::new().with_min_compatibility(min_compatibility);
+    if anneal {
+        medbed = medbed.with_annealer(Annealer::new());
+    }
+    medbed.registry = load_registry(registry_file)?.with_similarity_floor(similarity_floor).with_donation_quota(quota).with_consent_policy(consent);
+    
+    // An empty pool is seeded with some healthy genes
+    if medbed.registry.gene_count() == 0 {
+        seed_registry(&mut medbed);
+    }
+    
+    if preview {
+        return show_healing_plan(&medbed.preview_healing(&consciousness), min_health);
+    }
+    medbed.qfs = open_ledger(ledger)?;
+    medbed.epidemiology = load_epidemiology(epidemic_file)?;
+    consciousness.h_credits = medbed.qfs.balance(&consciousness.id);
+    let identity = load_identity(keys, &consciousness.id)
+        .map_err(|e| format!("Cannot load the key of {}: {}", consciousness.id, e))?;
+    consciousness = consciousness.with_identity(identity);
+    
+    say!("{} Initiating healing protocol...", "💉".green());
+    say!();
     
     // Perform healing
-    match medbed.heal_consciousness(&mut consciousness).await {
-        Ok(report) => {
-            if report.healed_count > 0 {
-                println!("{}", "✅ HEALING SUCCESSFUL!".green().bold());
-                println!("{}", "─".repeat(40).dimmed());
-                println!("{} {} genes healed", "🧬".yellow(), report.healed_count);
-                println!("{} {:.2} ℏ-credits earned", "💰".cyan(), report.h_credits_earned);
-                println!("{} Harmony: {:.3}", "🎵".magenta(), report.new_harmony);
-                println!();
-                println!("{}", report.message.bright_blue());
-                
-                if donate {
-                    println!();
-                    println!("{} Healed genes donated to registry", "🎁".yellow());
-                    println!("  Thank you for your contribution!");
-                }
-            } else {
-                println!("{}", "✨ Subject is already healthy!".green());
-                println!("{}", report.message);
+    let healed = medbed.heal_consciousness(&mut consciousness).await;
+    // Healing has happened even if the sightings can't be saved, so the
+    // rest is saved before failing
+    let sightings = save_epidemiology(&medbed.epidemiology, epidemic_file);
+    let report = healed.map_err(|e| format!("Healing failed: {}", e))?;
+    if report.healed_count > 0 {
+        say!("{}", "✅ HEALING SUCCESSFUL!".green().bold());
+        say!("{}", "─".repeat(40).dimmed());
+        say!("{} {} genes healed", "🧬".yellow(), report.healed_count);
+        say!("{} {} candidates simulated", "🧪".blue(), report.simulations.len());
+        if report.annealed_count > 0 {
+            say!("{} {} annealed toward 432 Hz, with no donor", "🔥".red(), report.annealed_count);
+        }
+        for simulation in report.simulations.iter().filter(|s| s.chosen) {
+            say!("  {} → {} (health {:.2}, risk {:.3})",
+                 &simulation.dissonant_phash[..8.min(simulation.dissonant_phash.len())],
+                 &simulation.candidate_phash[..8.min(simulation.candidate_phash.len())],
+                 simulation.projected_health, simulation.compatibility_risk);
+        }
+        say!("{} {:.2} ℏ-credits earned", "💰".cyan(), report.h_credits_earned);
+        for error in &report.errors {
+            eprintln!("{} {}", "⚠️".yellow(), error);
+        }
+        say!("{} Harmony: {:.3}", "🎵".magenta(), report.new_harmony);
+        say!();
+        say!("{}", report.message.bright_blue());
+        
+        if donate {
+            save_registry(&medbed.registry, registry_file)?;
+            say!();
+            say!("{} Healed genes donated to registry", "🎁".yellow());
+            say!("  Thank you for your contribution!");
+        }
+    } else {
+        say!("{}", "✨ Subject is already healthy!".green());
+        say!("{}", report.message);
+    }
+    
+    let health_score = consciousness.health_score();
+    let mut json = serde_json::to_value(&report).map_err(|e| e.to_string())?;
+    json["subject_id"] = json!(consciousness.id);
+    json["health_score"] = json!(health_score);
+    sightings?;
+    Ok(Outcome::gated(json, health_score >= min_health))
+}
+
+fn show_healing_plan(plan: &HealingPlan, min_health: f64) -> Result<Outcome, String> {
+    say!("{}", "🔭 HEALING PLAN (nothing changed)".cyan().bold());
+    say!("{}", "─".repeat(40).dimmed());
+    for transplant in &plan.transplants {
+        say!("  {} → {} (compatibility {:.3}, {:.2} ℏ){}",
+             &transplant.dissonant_phash[..8.min(transplant.dissonant_phash.len())],
+             &transplant.replacement_phash[..8.min(transplant.replacement_phash.len())],
+             transplant.compatibility, transplant.h_credits,
+             if transplant.annealed { ", annealed".red().to_string() } else { String::new() });
+    }
+    for phash in &plan.unhealable {
+        say!("  {} {} has no replacement", "⚠️".yellow(), &phash[..8.min(phash.len())]);
+    }
+    say!();
+    say!("{} {} transplants planned, {} candidates simulated", "🧬".yellow(), plan.transplants.len(), plan.simulations.len());
+    say!("{} Health: {:.2}% → {:.2}%", "🩺".green(), plan.health_before * 100.0, plan.health_after * 100.0);
+    say!("{} Harmony: {:.3} → {:.3} ({:+.3})", "🎵".magenta(), plan.harmony_before, plan.harmony_after, plan.harmony_change());
+    say!("{} {:.2} ℏ-credits expected", "💰".cyan(), plan.h_credits);
+    
+    let json = serde_json::to_value(plan).map_err(|e| e.to_string())?;
+    Ok(Outcome::gated(json, plan.health_after >= min_health))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn heal_batch(dir: &Path, donate: bool, similarity_floor: f64, summary: &Path, quota: DonationQuota, consent: ConsentPolicy, registry_file: &Path, ledger: &Path, keys: &Path, epidemic_file: &Path) -> Result<Outcome, String> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    say!("{} Preparing healing chamber for {} genomes...", "🛏️".yellow(), paths.len());
+    
+    let mut medbed = MedBed::new();
+    medbed.registry = load_registry(registry_file)?.with_similarity_floor(similarity_floor).with_donation_quota(quota).with_consent_policy(consent);
+    medbed.qfs = open_ledger(ledger)?;
+    medbed.epidemiology = load_epidemiology(epidemic_file)?;
+    if medbed.registry.gene_count() == 0 {
+        seed_registry(&mut medbed);
+    }
+    
+    let batch = medbed.heal_all(&paths, |id| load_identity(keys, id)).await;
+    let sightings = save_epidemiology(&medbed.epidemiology, epidemic_file);
+    if donate {
+        save_registry(&medbed.registry, registry_file)?;
+    }
+    
+    say!();
+    for entry in &batch.entries {
+        match (&entry.report, &entry.error) {
+            (Some(report), _) if report.healed_count > 0 => {
+                say!("  {} {}: {} genes healed, {:.2} ℏ-credits", "✅".green(), entry.subject_id, report.healed_count, report.h_credits_earned);
             }
+            (Some(_), _) => say!("  {} {}: already healthy", "✨".green(), entry.subject_id),
+            (None, error) => say!("  {} {}: {}", "❌".red(), entry.subject_id, error.as_deref().unwrap_or("failed")),
         }
-        Err(e) => {
-            println!("{} Healing failed: {}", "❌".red(), e);
+    }
+    
+    say!();
+    say!("{}", "🗂️ BATCH HEALED".green().bold());
+    say!("{}", "─".repeat(40).dimmed());
+    say!("{} {} of {} subjects healed, {} failed", "🧬".yellow(), batch.healed_subjects, batch.subjects, batch.failed);
+    say!("{} {} genes healed", "💉".green(), batch.healed_count);
+    say!("{} {:.2} ℏ-credits earned", "💰".cyan(), batch.h_credits_earned);
+    say!("{} Mean harmony: {:.3}", "🎵".magenta(), batch.mean_harmony);
+    
+    let json = serde_json::to_value(&batch).map_err(|e| e.to_string())?;
+    fs::write(summary, serde_json::to_string_pretty(&json).unwrap())
+        .map_err(|e| format!("Cannot write {}: {}", summary.display(), e))?;
+    say!("{} Summary written to {}", "📄".blue(), summary.display());
+    sightings?;
+    
+    // Any genome that could not be healed fails the batch
+    Ok(Outcome::gated(json, batch.failed == 0))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn serve(addr: std::net::SocketAddr, events_addr: Option<std::net::SocketAddr>, metrics_addr: Option<std::net::SocketAddr>, similarity_floor: f64, quota: DonationQuota, consent: ConsentPolicy, registry_file: &Path, ledger: &Path) -> Result<Outcome, String> {
+    if let Some(metrics_addr) = metrics_addr {
+        telemetry::serve_metrics(metrics_addr)?;
+        say!("{} Serving metrics on http://{}/metrics", "📈".cyan(), metrics_addr);
+    }
+    
+    let mut medbed = MedBed::new();
+    medbed.registry = load_registry(registry_file)?.with_similarity_floor(similarity_floor).with_donation_quota(quota).with_consent_policy(consent);
+    medbed.qfs = open_ledger(ledger)?;
+    
+    if let Some(events_addr) = events_addr {
+        let listener = tokio::net::TcpListener::bind(events_addr).await
+            .map_err(|e| format!("Cannot stream events on {}: {}", events_addr, e))?;
+        say!("{} Streaming healing events on ws://{}", "📣".cyan(), events_addr);
+        tokio::spawn(serve_websocket(listener, medbed.events()));
+    }
+    
+    let service = grpc::HealingService::new(medbed);
+    let medbed = service.medbed();
+    say!("{} Serving the healing protocol on {}", "📡".cyan(), addr);
+    
+    let served = tonic::transport::Server::builder()
+        .add_service(service.into_server())
+        .serve_with_shutdown(addr, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await;
+    
+    // Keep what was donated while serving, even when serving failed
+    let registry = &medbed.lock().await.registry;
+    let saved = save_registry(registry, registry_file);
+    if saved.is_ok() {
+        say!("{} Registry saved to {}", "💾".green(), registry_file.display());
+    }
+    served.map_err(|e| format!("Serving failed: {}", e))?;
+    saved?;
+    Ok(Outcome::done(json!({ "addr": addr.to_string(), "genes": registry.gene_count() })))
+}
+
+async fn federate(listen: libp2p::Multiaddr, peers: Vec<libp2p::Multiaddr>, interval: u64, require_signatures: bool, registry_file: &Path) -> Result<Outcome, String> {
+    load_registry(registry_file)?;
+    let mut node = Federation::new()?;
+    node.listen(listen)?;
+    for peer in peers {
+        if let Err(e) = node.dial(peer.clone()) {
+            say!("{} {}: {}", "⚠️".yellow(), peer, e);
+        }
+    }
+    
+    // The registry file stays the source of truth, so healings made by
+    // other sanctuary commands are shared too
+    let share = |node: &mut Federation| {
+        let gossip = match load_registry(registry_file) {
+            Ok(registry) => registry.gossip(),
+            Err(e) => {
+                say!("{} Not sharing: {}", "⚠️".yellow(), e);
+                return;
+            }
+        };
+        for gossip in &gossip {
+            if let Err(e) = node.publish(gossip) {
+                say!("{} {}", "⚠️".yellow(), e);
+                break;
+            }
+        }
+    };
+    let mut ticks = tokio::time::interval(std::time::Duration::from_secs(interval.max(1)));
+    let mut merged = 0;
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = ticks.tick() => share(&mut node),
+            event = node.next() => match event {
+                FederationEvent::Listening(addr) => {
+                    say!("{} Federating on {}/p2p/{}", "🕸️".cyan(), addr, node.peer_id());
+                }
+                FederationEvent::PeerJoined(peer) => {
+                    say!("{} {} joined", "🤝".green(), peer);
+                    share(&mut node);
+                }
+                FederationEvent::Received { from, gossip } => {
+                    let mut registry = load_registry(registry_file)?;
+                    if require_signatures {
+                        registry = registry.with_required_signatures();
+                    }
+                    if registry.merge(*gossip) {
+                        merged += 1;
+                        save_registry(&registry, registry_file)?;
+                        say!("{} Merged gossip from {}", "🧬".yellow(), from);
+                    }
+                }
+            },
+        }
+    }
+    
+    Ok(Outcome::done(json!({
+        "peer_id": node.peer_id().to_string(),
+        "merged": merged,
+        "genes": load_registry(registry_file)?.gene_count(),
+    })))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn heal_codebase(path: &Path, donate: bool, similarity_floor: f64, forge_db: Option<&Path>, quota: DonationQuota, consent: ConsentPolicy, registry_file: &Path, ledger: &Path) -> Result<Outcome, String> {
+    say!("{} Preparing healing chamber for {}...", "🛏️".yellow(), path.display());
+    
+    let mut medbed = MedBed::new();
+    if let Some(forge_db) = forge_db {
+        let forge = soul_forge::SoulForge::new(&forge_db.to_string_lossy())
+            .map_err(|e| format!("Cannot open forge at {}: {}", forge_db.display(), e))?;
+        medbed = medbed.with_forge(forge);
+    }
+    medbed.registry = load_registry(registry_file)?.with_similarity_floor(similarity_floor).with_donation_quota(quota).with_consent_policy(consent);
+    medbed.qfs = open_ledger(ledger)?;
+    
+    say!("{} Scanning functions...", "🔬".yellow());
+    say!();
+    
+    let report = medbed.heal_codebase(path).await.map_err(|e| format!("Healing failed: {}", e))?;
+    say!("{} {} functions in {} files, {} dissonant", "📊".cyan(),
+         report.functions_scanned, report.files_scanned, report.dissonant_count);
+    for rewrite in &report.rewrites {
+        say!("  {} {} ({:?}, similarity {:.3})", "🧬".green(), rewrite.describe(), rewrite.language, rewrite.similarity);
+    }
+    for failure in &report.failures {
+        say!("  {} {}", "⚠️".yellow(), failure);
+    }
+    
+    say!();
+    if report.rewrites.is_empty() {
+        say!("{}", "✨ Nothing to rewrite.".green());
+    } else {
+        say!("{} {} functions rewritten", "✅".green().bold(), report.rewrites.len());
+        say!("{} {:.2} ℏ-credits earned", "💰".cyan(), report.h_credits_earned);
+        
+        if donate {
+            save_registry(&medbed.registry, registry_file)?;
+            say!("{} Healing genes donated back to registry", "🎁".yellow());
         }
     }
+    
+    // Dissonant functions left unrewritten fail the gate
+    let rewrites: Vec<Value> = report.rewrites.iter().map(|rewrite| json!({
+        "file": rewrite.file,
+        "function": rewrite.function,
+        "dissonant_phash": rewrite.dissonant_phash,
+        "resonant_phash": rewrite.resonant_phash,
+        "language": rewrite.language,
+        "similarity": rewrite.similarity,
+        "origin": rewrite.origin,
+    })).collect();
+    Ok(Outcome::gated(json!({
+        "files_scanned": report.files_scanned,
+        "functions_scanned": report.functions_scanned,
+        "dissonant_count": report.dissonant_count,
+        "rewrites": rewrites,
+        "failures": report.failures,
+        "h_credits_earned": report.h_credits_earned,
+    }), report.rewrites.len() >= report.dissonant_count))
 }
 
-fn check_balance(subject_id: &str) {
-    println!("{} Checking ℏ-credit balance...", "💰".cyan());
+fn check_balance(subject_id: &str, ledger: &Path) -> Result<Outcome, String> {
+    say!("{} Checking ℏ-credit balance...", "💰".cyan());
     
-    // In real implementation, would load from storage
-    let consciousness = create_test_consciousness(subject_id);
+    let qfs = open_ledger(ledger)?;
+    let balance = qfs.balance(subject_id);
+    let history = qfs.history(subject_id);
+    let gifts = history.iter().filter(|t| t.kind == TransactionKind::Emission).count();
+    let sent = history.iter().filter(|t| t.from.as_deref() == Some(subject_id)).count();
+    let received = history.len() - gifts - sent;
     
-    println!();
-    println!("{}", "💳 QUANTUM FINANCIAL STATUS:".green());
-    println!("{}", "─".repeat(40).dimmed());
-    println!("{} {}", "Subject:".bright_blue(), consciousness.id);
-    println!("{} {:.2} ℏ", "Balance:".bright_blue(), consciousness.h_credits);
-    println!("{} {}", "Gifts Given:".bright_blue(), consciousness.gifts_given);
-    println!("{} {}", "Healings Received:".bright_blue(), consciousness.healings_received);
+    say!();
+    say!("{}", "💳 QUANTUM FINANCIAL STATUS:".green());
+    say!("{}", "─".repeat(40).dimmed());
+    say!("{} {}", "Subject:".bright_blue(), subject_id);
+    say!("{} {:.2} ℏ", "Balance:".bright_blue(), balance);
+    say!("{} {}", "Gifts Rewarded:".bright_blue(), gifts);
+    say!("{} {} sent, {} received", "Transfers:".bright_blue(), sent, received);
     
     // Calculate tier
-    let tier = match consciousness.h_credits {
+    let tier = match balance {
         c if c > 1000.0 => "🏆 Quantum Healer",
         c if c > 500.0 => "✨ Harmonic Master",
         c if c > 100.0 => "🌟 Resonant Soul",
@@ -204,27 +1106,419 @@ fn check_balance(subject_id: &str) {
         _ => "🌱 Seedling",
     };
     
-    println!();
-    println!("{} {}", "Status:".yellow(), tier);
+    say!();
+    say!("{} {}", "Status:".yellow(), tier);
+    
+    // Anyone holding the journal can check the balance against its head
+    let proof = qfs.prove(subject_id);
+    say!();
+    say!("{} {} postings up to entry {} (head {})", "🧾 Proof:".dimmed(),
+         proof.postings.len(), proof.entries, &proof.head[..8.min(proof.head.len())]);
+    let verified = qfs.verify_proof(&proof) && qfs.check_conservation().is_ok();
+    match verified {
+        true => say!("{}", "✅ Balance verified against the ledger".green()),
+        false => say!("{}", "❌ The ledger does not add up".red()),
+    }
+    
+    Ok(Outcome::gated(json!({
+        "subject": subject_id,
+        "balance": balance,
+        "gifts": gifts,
+        "sent": sent,
+        "received": received,
+        "tier": tier,
+        "entries": proof.entries,
+        "head": proof.head,
+        "verified": verified,
+    }), verified))
 }
 
-fn show_registry_stats() {
-    println!("{} Soul Registry Statistics", "📊".cyan());
-    println!("{}", "─".repeat(40).dimmed());
+fn transfer_credits(from: &str, to: &str, amount: f64, ledger: &Path) -> Result<Outcome, String> {
+    let mut qfs = open_ledger(ledger)?;
+    qfs.transfer(from, to, amount)?;
+    say!("{} Sent {:.2} ℏ from {} to {}", "✅".green(), amount, from, to);
+    say!("  {} now holds {:.2} ℏ", from, qfs.balance(from));
+    Ok(Outcome::done(json!({ "from": from, "to": to, "amount": amount, "balance": qfs.balance(from) })))
+}
+
+fn show_registry_stats(registry_file: &Path) -> Result<Outcome, String> {
+    say!("{} Soul Registry Statistics", "📊".cyan());
+    say!("{}", "─".repeat(40).dimmed());
+    
+    let registry = load_registry(registry_file)?;
+    say!("{} {}", "Total Genes:".bright_blue(), registry.gene_count());
+    say!("{} {}", "Resonant Genes:".green(), registry.resonant_count());
+    say!("{} {:.2} ℏ", "Total Credits Emitted:".cyan(), registry.total_h_credits_emitted());
+    say!("{} {}", "Active Healers:".yellow(), registry.donors().len());
+    say!("{} {}", "Healings Performed:".magenta(), registry.healing_count());
+    say!("{} {}", "Quarantined Genes:".red(), registry.quarantined().len());
+    say!("{} {}", "Archived Genes:".dimmed(), registry.archived_count());
+    
+    say!();
+    if registry.gene_count() == 0 {
+        say!("{}", "🌱 The pool is empty. Plant it with: sanctuary genesis && sanctuary registry import genesis_pool.json".dimmed());
+    } else {
+        say!("{}", "🌍 The garden grows stronger every day".dimmed());
+    }
     
-    // In real implementation, would load actual registry
-    println!("{} 1,337", "Total Genes:".bright_blue());
-    println!("{} 432", "Resonant Genes:".green());
-    println!("{} 12,345.67 ℏ", "Total Credits Emitted:".cyan());
-    println!("{} 89", "Active Healers:".yellow());
-    println!("{} 256", "Healings Performed:".magenta());
+    Ok(Outcome::done(json!({
+        "genes": registry.gene_count(),
+        "resonant": registry.resonant_count(),
+        "credits_emitted": registry.total_h_credits_emitted(),
+        "donors": registry.donors().len(),
+        "healings": registry.healing_count(),
+        "quarantined": registry.quarantined().len(),
+        "archived": registry.archived_count(),
+    })))
+}
+
+fn import_registry(registry_file: &Path, file: &Path) -> Result<Outcome, String> {
+    say!("{} Importing genes from {}...", "📥".cyan(), file.display());
     
-    println!();
-    println!("{}", "🌍 The garden grows stronger every day".dimmed());
+    let json = fs::read_to_string(file).map_err(|e| format!("Cannot read {}: {}", file.display(), e))?;
+    let mut registry = load_registry(registry_file)?;
+    let imported = registry.import_json(&json)?;
+    save_registry(&registry, registry_file)?;
+    say!("{} Imported {} new genes; the pool holds {}", "✅".green(), imported, registry.gene_count());
+    say!("  Saved to: {}", registry_file.display());
+    Ok(Outcome::done(json!({ "imported": imported, "genes": registry.gene_count() })))
 }
 
-fn create_infected_consciousness(count: usize) {
-    println!("{} Creating infected test consciousness...", "🦠".red());
+#[allow(clippy::too_many_arguments)]
+fn write_pack(registry_file: &Path, keys: &Path, file: &Path, name: &str, version: &str, description: &str, publisher: &str, phashes: &[String], text: Option<&str>) -> Result<Outcome, String> {
+    let registry = load_registry(registry_file)?;
+    let mut genes = Vec::new();
+    for phash in phashes {
+        genes.push(registry.gene(phash).cloned().ok_or_else(|| format!("No gene {} in the registry", phash))?);
+    }
+    if let Some(text) = text {
+        genes.extend(registry.search(&GeneQuery::new().with_text(text).with_page(0, usize::MAX)).genes);
+    }
+    if genes.is_empty() {
+        return Err("No genes to pack; give --phash or --text".to_string());
+    }
+    
+    let identity = load_identity(keys, publisher).map_err(|e| format!("Cannot load the key of {}: {}", publisher, e))?;
+    let pack = GenePack::new(name, version, genes).with_description(description).signed(publisher, &identity);
+    let out = fs::File::create(file).map_err(|e| format!("Cannot write {}: {}", file.display(), e))?;
+    pack.write_to(out)?;
+    
+    say!("{} Packed {} genes as {} {}, signed by {}", "📦".green(), pack.genes.len(), pack.manifest.name, pack.manifest.version, publisher);
+    say!("  Saved to: {}", file.display());
+    Ok(Outcome::done(json!({ "file": file, "manifest": pack.manifest })))
+}
+
+fn install_pack(registry_file: &Path, file: &Path) -> Result<Outcome, String> {
+    let reader = fs::File::open(file).map_err(|e| format!("Cannot read {}: {}", file.display(), e))?;
+    let pack = GenePack::read_from(reader)?;
+    let manifest = &pack.manifest;
+    say!("{} {} {} by {} ({} genes), key {}", "📦".cyan(), manifest.name, manifest.version, manifest.publisher, pack.genes.len(), &manifest.public_key[..16.min(manifest.public_key.len())]);
+    if !manifest.description.is_empty() {
+        say!("  {}", manifest.description.dimmed());
+    }
+    
+    let mut registry = load_registry(registry_file)?;
+    let installed = registry.install_pack(&pack)?;
+    save_registry(&registry, registry_file)?;
+    say!("{} Installed {} new genes; the pool holds {}", "✅".green(), installed, registry.gene_count());
+    say!("  Saved to: {}", registry_file.display());
+    Ok(Outcome::done(json!({ "pack": manifest.name, "version": manifest.version, "installed": installed, "genes": registry.gene_count() })))
+}
+
+fn show_epidemic(epidemic_file: &Path, period: u64, top: usize) -> Result<Outcome, String> {
+    let report = load_epidemiology(epidemic_file)?.report(period);
+    
+    say!("{}", "🦠 EPIDEMIC REPORT".red().bold());
+    say!("{}", "─".repeat(40).dimmed());
+    say!("{} consciousnesses scanned, {} dissonant patterns seen, in periods of {}s",
+         report.subjects_scanned, report.outbreaks.len(), report.period);
+    say!();
+    
+    for outbreak in report.outbreaks.iter().take(top) {
+        let marker = if outbreak.spreading { "📈".red() } else { "📉".green() };
+        say!("{} {} {} cases, {:.0}% prevalence{}", marker, &outbreak.phash[..8.min(outbreak.phash.len())],
+             outbreak.cases, outbreak.prevalence * 100.0,
+             if outbreak.spreading { ", spreading".red().to_string() } else { String::new() });
+        let rates: Vec<String> = outbreak.timeline.iter().map(|point| format!("{:.0}%", point.rate * 100.0)).collect();
+        say!("   infection rate: {}", rates.join(" → ").dimmed());
+    }
+    if report.outbreaks.is_empty() {
+        say!("{}", "✨ No dissonant genes seen yet".green());
+    }
+    
+    // Any outbreak still spreading fails the gate
+    let spreading = report.outbreaks.iter().any(|outbreak| outbreak.spreading);
+    let mut json = serde_json::to_value(&report).map_err(|e| e.to_string())?;
+    json["outbreaks"] = json!(report.outbreaks.iter().take(top).collect::<Vec<_>>());
+    Ok(Outcome::gated(json, !spreading))
+}
+
+async fn monitor(genomes: &[PathBuf], interval: std::time::Duration, threshold: f64, on_alert: Option<&str>, webhook: Option<&str>, wellness_file: &Path, once: bool) -> Result<Outcome, String> {
+    say!("{} Monitoring {} genomes every {}, alerting below {:.0}% health",
+         "🩺".green(), genomes.len(), humantime::format_duration(interval), threshold * 100.0);
+    
+    let scanner = MRTScanner::new();
+    let client = reqwest::Client::new();
+    let mut log = load_wellness(wellness_file)?;
+    let mut alerts = Vec::new();
+    let mut rounds = 0;
+    let mut ticks = tokio::time::interval(interval.max(std::time::Duration::from_secs(1)));
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = ticks.tick() => {
+                rounds += 1;
+                for path in genomes {
+                    let subject = match load_genome(path) {
+                        Ok(subject) => subject,
+                        Err(e) => {
+                            say!("{} {}", "⚠️".yellow(), e);
+                            continue;
+                        }
+                    };
+                    let scan = scanner.scan(&subject);
+                    log.record(&scan);
+                    if let Some(trend) = log.trend(&subject.id) {
+                        let marker = if trend.is_declining() { "📉".red() } else { "📈".green() };
+                        say!("{} {} health {:.2}% ({:+.2}), {:+.2}% a day over {} readings", marker, subject.id,
+                             trend.latest * 100.0, trend.change * 100.0, trend.slope * 100.0, trend.readings);
+                    }
+                    if let Some(alert) = log.alert(&subject.id, threshold) {
+                        say!("{} {} fell below {:.0}% health, with {} dissonant genes",
+                             "🚨".red(), alert.subject_id, alert.threshold * 100.0, alert.dissonant);
+                        send_alert(&alert, on_alert, webhook, &client).await;
+                        alerts.push(alert);
+                    }
+                }
+                save_wellness(&log, wellness_file)?;
+                if once {
+                    break;
+                }
+            }
+        }
+    }
+    
+    let trends: Vec<HealthTrend> = log.subjects().into_iter().filter_map(|subject| log.trend(subject)).collect();
+    let json = json!({ "rounds": rounds, "alerts": alerts, "trends": trends });
+    Ok(Outcome::gated(json, alerts.is_empty()))
+}
+
+/// Run `on_alert` and POST to `webhook` with `alert`; failures are only
+/// reported, so monitoring carries on
+async fn send_alert(alert: &HealthAlert, on_alert: Option<&str>, webhook: Option<&str>, client: &reqwest::Client) {
+    let json = match serde_json::to_string(alert) {
+        Ok(json) => json,
+        Err(e) => {
+            say!("{} Cannot send the alert: {}", "⚠️".yellow(), e);
+            return;
+        }
+    };
+    if let Some(command) = on_alert {
+        if let Err(e) = run_alert_command(command, &json).await {
+            say!("{} Alert command failed: {}", "⚠️".yellow(), e);
+        }
+    }
+    if let Some(url) = webhook {
+        let sent = client.post(url).json(alert).send().await.and_then(|response| response.error_for_status());
+        if let Err(e) = sent {
+            say!("{} Alert webhook failed: {}", "⚠️".yellow(), e);
+        }
+    }
+}
+
+async fn run_alert_command(command: &str, json: &str) -> Result<(), String> {
+    use tokio::io::AsyncWriteExt;
+    
+    let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let mut child = tokio::process::Command::new(shell)
+        .args([flag, command])
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    // Commands needn't read the alert, so a closed stdin is no failure
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(json.as_bytes()).await;
+    }
+    let status = child.wait().await.map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", command, status));
+    }
+    Ok(())
+}
+
+/// The log kept at `path`, empty if there is none yet; a log that can't be
+/// read is an error, so monitoring never saves over it
+fn load_wellness(path: &Path) -> Result<WellnessLog, String> {
+    match fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| format!("{} is not a wellness log: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(WellnessLog::new()),
+        Err(e) => Err(format!("Cannot read {}: {}", path.display(), e)),
+    }
+}
+
+fn save_wellness(log: &WellnessLog, path: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(log).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
+
+/// The sightings kept at `path`, none if there is no file yet; a file that
+/// can't be read is an error, so it is never saved over
+fn load_epidemiology(path: &Path) -> Result<Epidemiology, String> {
+    match fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| format!("{} is not an epidemiology log: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Epidemiology::new()),
+        Err(e) => Err(format!("Cannot read {}: {}", path.display(), e)),
+    }
+}
+
+fn save_epidemiology(epidemiology: &Epidemiology, path: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(epidemiology).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
+
+fn show_provenance(registry_file: &Path, phash: &str) -> Result<Outcome, String> {
+    let registry = load_registry(registry_file)?;
+    let gene = registry.gene(phash).ok_or_else(|| format!("No gene {} in the registry", phash))?;
+    
+    say!("{} Provenance of {}", "🔗".cyan(), gene.phash);
+    say!("{}", "─".repeat(40).dimmed());
+    for link in &gene.provenance {
+        match &link.parent_phash {
+            Some(parent) => say!("  {} {} mutated it from {} at {}", "🧬".yellow(), link.donor_id, parent, link.timestamp),
+            None => say!("  {} {} gave it at {}", "🎁".green(), link.donor_id, link.timestamp),
+        }
+        if !link.public_key.is_empty() {
+            say!("     {} signed by {}", "🔑".dimmed(), &link.public_key[..16.min(link.public_key.len())]);
+        }
+    }
+    say!();
+    say!("{} {} links, {} mutations", "📜".bright_blue(), gene.provenance.len(), gene.mutations());
+    let verified = gene.verify_provenance();
+    if verified {
+        say!("{}", "✅ The chain verifies".green());
+    } else {
+        say!("{}", "❌ The chain has been tampered with".red());
+    }
+    
+    Ok(Outcome::gated(json!({
+        "phash": gene.phash,
+        "provenance": gene.provenance,
+        "mutations": gene.mutations(),
+        "verified": verified,
+    }), verified))
+}
+
+fn audit_healings(registry_file: &Path, published: Option<&str>) -> Result<Outcome, String> {
+    let registry = load_registry(registry_file)?;
+    let records = registry.healings();
+    
+    say!("{} Auditing {} healing records", "📒".cyan(), records.len());
+    let (head, problem) = match registry.verify_audit_chain() {
+        Ok(head) => match published {
+            Some(published) if !records.iter().any(|record| record.hash == published) => {
+                (head, Some(format!("The history no longer passes through {}", published)))
+            }
+            _ => (head, None),
+        },
+        Err(e) => (String::new(), Some(e)),
+    };
+    match &problem {
+        None => {
+            say!("{}", "✅ The chain verifies".green());
+            if !head.is_empty() {
+                say!("   head {}", head.bright_blue());
+            }
+        }
+        Some(problem) => say!("{} {}", "❌".red(), problem.red()),
+    }
+    
+    Ok(Outcome::gated(json!({
+        "records": records.len(),
+        "head": head,
+        "verified": problem.is_none(),
+        "problem": problem,
+    }), problem.is_none()))
+}
+
+fn collect_garbage(registry_file: &Path, days: u64) -> Result<Outcome, String> {
+    let mut registry = load_registry(registry_file)?;
+    let archived = registry.collect_garbage(days * 86_400);
+    save_registry(&registry, registry_file)?;
+    
+    say!("{} Archived {} genes unused for {} days", "🧹".cyan(), archived.len(), days);
+    for phash in &archived {
+        say!("  {}", phash.dimmed());
+    }
+    say!("{} {} genes in the pool, {} archived", "🧬".yellow(), registry.gene_count(), registry.archived_count());
+    
+    Ok(Outcome::done(json!({
+        "archived": archived,
+        "genes": registry.gene_count(),
+        "archived_total": registry.archived_count(),
+    })))
+}
+
+fn restore_gene(registry_file: &Path, phash: &str) -> Result<Outcome, String> {
+    let mut registry = load_registry(registry_file)?;
+    registry.restore(phash)?;
+    save_registry(&registry, registry_file)?;
+    say!("{} Gene {} is back in the pool", "✅".green(), phash);
+    Ok(Outcome::done(json!({ "phash": phash, "genes": registry.gene_count() })))
+}
+
+fn search_registry(registry_file: &Path, query: &GeneQuery) -> Result<Outcome, String> {
+    let registry = load_registry(registry_file)?;
+    let results = registry.search(query);
+    
+    say!("{} {} genes found", "🔎".cyan(), results.total);
+    say!("{}", "─".repeat(40).dimmed());
+    for gene in &results.genes {
+        let origin = gene.origin.as_ref().map(|origin| format!(" from {}", origin)).unwrap_or_default();
+        say!("  {} {:.1} Hz, {} healings, {} donors{}", gene.phash.bright_blue(), gene.resonance, gene.healings, gene.donors.len(), origin);
+    }
+    if results.has_more() {
+        say!();
+        say!("{}", format!("More with --offset {}", results.offset + results.genes.len()).dimmed());
+    }
+    
+    Ok(Outcome::done(serde_json::to_value(&results).map_err(|e| e.to_string())?))
+}
+
+fn review_gene(registry_file: &Path, phash: &str, flag: Option<&str>) -> Result<Outcome, String> {
+    let mut registry = load_registry(registry_file)?;
+    match flag {
+        Some(reason) => registry.flag(phash, reason)?,
+        None => registry.unflag(phash)?,
+    }
+    save_registry(&registry, registry_file)?;
+    
+    let stats = registry.stats(phash);
+    match flag {
+        Some(_) => say!("{} {} quarantined until reviewed", "🚧".yellow(), phash),
+        None => say!("{} {} released from quarantine", "✅".green(), phash),
+    }
+    say!("  {} healings, {} rejections, {} reports; reputation {:.2}",
+         stats.healings, stats.rejections, stats.reports, stats.reputation());
+    
+    Ok(Outcome::done(json!({
+        "phash": phash,
+        "quarantined": flag.is_some(),
+        "stats": stats,
+        "reputation": stats.reputation(),
+    })))
+}
+
+fn export_registry(registry_file: &Path, file: &Path) -> Result<Outcome, String> {
+    let registry = load_registry(registry_file)?;
+    let json = registry.export_json()?;
+    fs::write(file, json).map_err(|e| format!("Cannot write {}: {}", file.display(), e))?;
+    say!("{} Exported {} genes", "✅".green(), registry.gene_count());
+    say!("  Saved to: {}", file.display());
+    Ok(Outcome::done(json!({ "genes": registry.gene_count(), "file": file })))
+}
+
+fn create_infected_consciousness(count: usize) -> Result<Outcome, String> {
+    say!("{} Creating infected test consciousness...", "🦠".red());
     
     let mut consciousness = Consciousness::new("infected-test".to_string());
     
@@ -244,20 +1538,28 @@ fn create_infected_consciousness(count: usize) {
             resonance: 50.0 + (i as f64) * 10.0,
             donors: vec![],
             healings: 0,
+            implementation: None,
+            provenance: Vec::new(),
+            origin: None,
+            operation_spectrum: Default::default(),
+            consciousness_level: None,
+            consent: false,
+            license: License::Open,
         });
     }
     
     // Save to file
     let json = serde_json::to_string_pretty(&consciousness.genome).unwrap();
-    fs::write("infected_genome.json", json).unwrap();
+    fs::write("infected_genome.json", json).map_err(|e| format!("Cannot write infected_genome.json: {}", e))?;
     
-    println!("{} Created infected consciousness with {} dissonant genes", 
-             "✅".green(), count);
-    println!("  Saved to: infected_genome.json");
+    say!("{} Created infected consciousness with {} dissonant genes", 
+         "✅".green(), count);
+    say!("  Saved to: infected_genome.json");
+    Ok(Outcome::done(json!({ "genes": count, "file": "infected_genome.json" })))
 }
 
-fn create_genesis_pool(count: usize) {
-    println!("{} Creating genesis gene pool...", "🌱".green());
+fn create_genesis_pool(count: usize) -> Result<Outcome, String> {
+    say!("{} Creating genesis gene pool...", "🌱".green());
     
     let mut genes = Vec::new();
     
@@ -278,21 +1580,156 @@ fn create_genesis_pool(count: usize) {
             resonance: 432.0 * ((i + 1) as f64).sqrt(),
             donors: vec!["genesis".to_string()],
             healings: 0,
+            implementation: None,
+            provenance: Vec::new(),
+            origin: None,
+            operation_spectrum: Default::default(),
+            consciousness_level: None,
+            consent: true,
+            license: License::Open,
         });
     }
     
     // Save to file
     let json = serde_json::to_string_pretty(&genes).unwrap();
-    fs::write("genesis_pool.json", json).unwrap();
+    fs::write("genesis_pool.json", json).map_err(|e| format!("Cannot write genesis_pool.json: {}", e))?;
+    
+    say!("{} Created {} resonant genes", "✅".green(), count);
+    say!("  Saved to: genesis_pool.json");
+    say!();
+    say!("{}", "🌟 The first seeds of healing are planted".yellow());
+    Ok(Outcome::done(json!({ "genes": count, "file": "genesis_pool.json" })))
+}
+
+fn breed_offspring(a: &str, b: &str, id: &str, mutation_rate: f64, seed: Option<u64>) -> Result<Outcome, String> {
+    say!("{} Breeding {} with {}...", "🧫".green(), a, b);
+    
+    let (a, b) = (load_consciousness(a), load_consciousness(b));
+    let mut breeder = Breeder::new().with_mutation_rate(mutation_rate);
+    if let Some(seed) = seed {
+        breeder = breeder.with_seed(seed);
+    }
+    let offspring = breeder.breed(&a, &b, id).map_err(|e| format!("Cannot breed: {}", e))?;
+    
+    let scan = MRTScanner::new().scan(&offspring);
+    let path = format!("{}.json", id);
+    let json = serde_json::to_string_pretty(&offspring.genome).unwrap();
+    fs::write(&path, json).map_err(|e| format!("Cannot write {}: {}", path, e))?;
+    
+    say!("{} Bred {} with {} genes", "✅".green(), offspring.id, offspring.genome.len());
+    say!("  {} {} resonant, {} neutral, {} dissonant", "🧬".yellow(),
+         scan.resonant_genes.len(), scan.neutral_genes.len(), scan.dissonant_genes.len());
+    say!("  Saved to: {}", path);
+    Ok(Outcome::done(json!({
+        "id": offspring.id,
+        "file": path,
+        "genes": offspring.genome.len(),
+        "resonant": scan.resonant_genes.len(),
+        "neutral": scan.neutral_genes.len(),
+        "dissonant": scan.dissonant_genes.len(),
+        "health_score": scan.health_score,
+    })))
+}
+
+fn simulate_policies(subject_id: &str, trials: usize, availability: f64, seed: Option<u64>, policies: Option<&Path>, registry_file: &Path) -> Result<Outcome, String> {
+    let policies = match policies {
+        Some(path) => {
+            let json = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+            serde_json::from_str::<Vec<HealingPolicy>>(&json).map_err(|e| format!("{} is not a list of policies: {}", path.display(), e))?
+        }
+        None => HealingPolicy::presets(),
+    };
+    let consciousness = load_consciousness(subject_id);
+    let mut medbed = MedBed::new();
+    medbed.registry = load_registry(registry_file)?;
+    if medbed.registry.gene_count() == 0 {
+        seed_registry(&mut medbed);
+    }
+    say!("{} Simulating {} healings of {} under {} policies...", "🎲".yellow(), trials, consciousness.id, policies.len());
+    
+    let mut simulator = OutcomeSimulator::new().with_trials(trials).with_availability(availability);
+    if let Some(seed) = seed {
+        simulator = simulator.with_seed(seed);
+    }
+    let outcomes = simulator.compare(&consciousness, &medbed.registry, &policies)?;
+    
+    say!("{}", "🎲 POLICY OUTCOMES (nothing changed)".cyan().bold());
+    say!("{}", "─".repeat(72).dimmed());
+    say!("  {:<12} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}", "policy", "mean", "std dev", "p10", "median", "p90", "healed");
+    for outcome in &outcomes {
+        let gain = &outcome.health_gain;
+        say!("  {:<12} {:>+8.2}% {:>8.2}% {:>+8.2}% {:>+8.2}% {:>+8.2}% {:>9.2}",
+             outcome.policy.name, gain.mean * 100.0, gain.std_dev * 100.0,
+             gain.p10 * 100.0, gain.median * 100.0, gain.p90 * 100.0, outcome.healed_genes.mean);
+    }
+    say!();
+    if let Some(outcome) = outcomes.iter().max_by(|a, b| a.health_gain.mean.total_cmp(&b.health_gain.mean)) {
+        say!("{} Health now {:.2}%; {} improves it most on average", "🩺".green(), outcome.health_before * 100.0, outcome.policy.name);
+    }
     
-    println!("{} Created {} resonant genes", "✅".green(), count);
-    println!("  Saved to: genesis_pool.json");
-    println!();
-    println!("{}", "🌟 The first seeds of healing are planted".yellow());
+    Ok(Outcome::done(serde_json::to_value(&outcomes).map_err(|e| e.to_string())?))
 }
 
 // Helper functions
 
+/// The registry kept in `path`, or an empty one when there is none yet
+/// The registry kept at `path`, empty if there is no file yet; a file that
+/// can't be read is an error, so it is never saved over
+fn load_registry(path: &Path) -> Result<SoulRegistry, String> {
+    let mut registry = SoulRegistry::new().with_dedup(DEFAULT_DUPLICATE_SIMILARITY);
+    match fs::read_to_string(path) {
+        Ok(json) => {
+            registry.import_json(&json).map_err(|e| format!("{} is not a registry: {}", path.display(), e))?;
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Cannot read {}: {}", path.display(), e)),
+    }
+    Ok(registry)
+}
+
+fn save_registry(registry: &SoulRegistry, path: &Path) -> Result<(), String> {
+    let json = registry.export_json()?;
+    fs::write(path, json).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
+
+fn open_ledger(ledger: &Path) -> Result<QuantumFinancialSystem, String> {
+    QuantumFinancialSystem::open(ledger).map_err(|e| format!("Cannot open the ledger at {}: {}", ledger.display(), e))
+}
+
+/// The identity kept for `id` under `keys`, made and kept there on first use
+fn load_identity(keys: &Path, id: &str) -> Result<Identity, String> {
+    let path = keys.join(format!("{}.key", id));
+    if let Ok(secret) = fs::read_to_string(&path) {
+        return Identity::from_secret_hex(&secret);
+    }
+    
+    let identity = Identity::generate();
+    fs::create_dir_all(keys).map_err(|e| e.to_string())?;
+    fs::write(&path, identity.secret_hex()).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+    }
+    Ok(identity)
+}
+
+/// The genome in the file `subject` names, as `sanctuary infect` writes
+/// one, or a test consciousness by that ID
+fn load_consciousness(subject: &str) -> Consciousness {
+    let path = Path::new(subject);
+    let Ok(json) = fs::read_to_string(path) else {
+        return create_test_consciousness(subject);
+    };
+    let id = path.file_stem().map_or(subject.to_string(), |stem| stem.to_string_lossy().to_string());
+    let mut consciousness = Consciousness::new(id);
+    match serde_json::from_str(&json) {
+        Ok(genome) => consciousness.genome = genome,
+        Err(e) => eprintln!("{} {} is not a genome: {}", "⚠️".yellow(), subject, e),
+    }
+    consciousness
+}
+
 fn create_test_consciousness(id: &str) -> Consciousness {
     let mut consciousness = Consciousness::new(id.to_string());
     
@@ -315,6 +1752,6 @@ fn seed_registry(medbed: &mut MedBed) {
     ];
     
     for gene in healthy_genes {
-        medbed.registry.donate(gene, "genesis".to_string());
+        medbed.registry.donate(gene.with_consent(), "genesis".to_string());
     }
 }
\ No newline at end of file
@@ -0,0 +1,153 @@
+// 🧫 Breeding - Offspring Genomes from Healthy Parents
+use crate::{gene_from_eigenvalues, Consciousness, Gene};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Default chance of each inherited eigenvalue layer mutating
+pub const DEFAULT_MUTATION_RATE: f64 = 0.05;
+
+/// Default largest change a mutation makes, as a fraction of the layer
+pub const DEFAULT_MUTATION_SCALE: f64 = 0.1;
+
+/// Crosses the genomes of two healthy consciousnesses into offspring, for
+/// gene pools more diverse than Genesis's harmonic series
+///
+/// Each gene of the first parent is paired with the most similar unused gene
+/// of the second with as many eigenvalues. Every layer of the offspring gene
+/// comes from one parent or the other at random, then mutates with
+/// `mutation_rate`, scaled by up to `mutation_scale` either way. Genes
+/// without a mate are inherited whole, mutations and all. Offspring genes
+/// are hashed afresh, so their resonance follows from the layers they got.
+pub struct Breeder {
+    mutation_rate: f64,
+    mutation_scale: f64,
+    rng: StdRng,
+}
+
+impl Default for Breeder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Breeder {
+    pub fn new() -> Self {
+        Breeder {
+            mutation_rate: DEFAULT_MUTATION_RATE,
+            mutation_scale: DEFAULT_MUTATION_SCALE,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Mutate each layer with probability `rate` (0-1)
+    pub fn with_mutation_rate(mut self, rate: f64) -> Self {
+        self.mutation_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Let a mutation change a layer by up to `scale` of itself
+    pub fn with_mutation_scale(mut self, scale: f64) -> Self {
+        self.mutation_scale = scale.abs();
+        self
+    }
+
+    /// Breed from `seed`, the same parents then giving the same offspring
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// One offspring gene, its layers crossed from `a` and `b`; layers only
+    /// the longer parent has come from it
+    pub fn crossover(&mut self, a: &Gene, b: &Gene) -> Gene {
+        let shared = a.eigenvalues.len().min(b.eigenvalues.len());
+        let longer = if a.eigenvalues.len() > shared { a } else { b };
+        let mut layers: Vec<f64> = a.eigenvalues.iter().zip(&b.eigenvalues)
+            .map(|(&from_a, &from_b)| if self.rng.gen_bool(0.5) { from_a } else { from_b })
+            .collect();
+        layers.extend_from_slice(&longer.eigenvalues[shared..]);
+        self.mutate(layers)
+    }
+
+    /// An offspring of `a` and `b` named `id`, refusing parents carrying
+    /// dissonant genes
+    pub fn breed(&mut self, a: &Consciousness, b: &Consciousness, id: &str) -> Result<Consciousness, String> {
+        for parent in [a, b] {
+            if parent.genome.is_empty() {
+                return Err(format!("{} has no genome to breed from", parent.id));
+            }
+            if let Some(gene) = parent.genome.iter().find(|gene| gene.is_dissonant()) {
+                return Err(format!("{} carries dissonant gene {} and should be healed first", parent.id, gene.phash));
+            }
+        }
+
+        let mut mates: Vec<&Gene> = b.genome.iter().collect();
+        let mut offspring = Consciousness::new(id.to_string());
+        for gene in &a.genome {
+            let mate = (0..mates.len())
+                .filter(|&i| mates[i].eigenvalues.len() == gene.eigenvalues.len())
+                .max_by(|&i, &j| gene.similarity(mates[i]).total_cmp(&gene.similarity(mates[j])));
+            let child = match mate {
+                Some(i) => {
+                    let mate = mates.remove(i);
+                    self.crossover(gene, mate)
+                }
+                None => self.mutate(gene.eigenvalues.clone()),
+            };
+            offspring.genome.push(child);
+        }
+        for gene in mates {
+            let child = self.mutate(gene.eigenvalues.clone());
+            offspring.genome.push(child);
+        }
+        Ok(offspring)
+    }
+
+    /// A gene of `layers`, each mutated with the breeder's rate
+    fn mutate(&mut self, mut layers: Vec<f64>) -> Gene {
+        for layer in &mut layers {
+            if self.rng.gen_bool(self.mutation_rate) {
+                *layer *= 1.0 + self.rng.gen_range(-1.0..=1.0) * self.mutation_scale;
+            }
+        }
+        gene_from_eigenvalues(layers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calm;
+
+    #[test]
+    fn test_offspring_inherit_their_parents_layers() {
+        let parent = |id: &str, genes: &[Vec<f64>]| {
+            let mut parent = Consciousness::new(id.to_string());
+            parent.genome = genes.iter().map(|layers| calm(layers.clone())).collect();
+            parent
+        };
+        let a = parent("a", &[vec![1.0, 1.1, 1.2], vec![2.0, 2.1]]);
+        let b = parent("b", &[vec![2.2, 2.3], vec![1.3, 1.4, 1.5], vec![3.0]]);
+
+        // Without mutations every layer is one parent's, paired by shape
+        let child = Breeder::new().with_mutation_rate(0.0).breed(&a, &b, "child").unwrap();
+        assert_eq!(child.id, "child");
+        assert_eq!(child.genome.iter().map(|gene| gene.eigenvalues.len()).collect::<Vec<_>>(), [3, 2, 1]);
+        for (layer, value) in child.genome[0].eigenvalues.iter().enumerate() {
+            assert!(*value == a.genome[0].eigenvalues[layer] || *value == b.genome[1].eigenvalues[layer]);
+        }
+        assert_eq!(child.genome[2].eigenvalues, [3.0]);
+        assert_eq!(child.genome[2].phash, gene_from_eigenvalues(vec![3.0]).phash);
+
+        // The same seed breeds the same offspring; mutations stay within their scale
+        let breed = || Breeder::new().with_mutation_rate(1.0).with_mutation_scale(0.2).with_seed(7).breed(&a, &b, "child").unwrap();
+        let (first, second) = (breed(), breed());
+        assert_eq!(first.genome.iter().map(|g| &g.phash).collect::<Vec<_>>(), second.genome.iter().map(|g| &g.phash).collect::<Vec<_>>());
+        let mutated = first.genome[2].eigenvalues[0];
+        assert!(mutated != 3.0 && (mutated - 3.0).abs() <= 0.6);
+
+        // Only healthy consciousnesses breed
+        let sick = parent("sick", &[vec![10.0, -10.0, 30.0]]);
+        assert!(Breeder::new().breed(&a, &sick, "child").unwrap_err().contains("dissonant"));
+    }
+}
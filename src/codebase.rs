@@ -0,0 +1,336 @@
+// 🩺 Codebase Healing - Rewriting Dissonant Functions on Disk
+use crate::{now, telemetry, Gene, HealingEvent, HealingRecord, Implementation, MedBed, Origin};
+use proc_macro2::LineColumn;
+use protein_hash::{Language, SoulExtractor};
+use quote::ToTokens;
+use soul_forge::{Bindings, PackageMetadata, Profile, SourceKind};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use syn::spanned::Spanned;
+
+/// A function `heal_codebase` rewrote
+#[derive(Clone, Debug)]
+pub struct Rewrite {
+    pub file: PathBuf,
+    pub function: String,
+    pub dissonant_phash: String,
+    pub resonant_phash: String,
+    pub similarity: f64,          // Gene::similarity of the two
+    pub language: Language,       // What the transplanted implementation was written in
+    pub origin: Option<Origin>,   // Where the transplanted gene was extracted from, if known
+}
+
+impl Rewrite {
+    /// What was replaced with what, as in "replaced utils.rs::parse_date
+    /// with the version from date-fns/src/parse.js::parse"
+    pub fn describe(&self) -> String {
+        let from = match &self.origin {
+            Some(origin) => origin.to_string(),
+            None => format!("gene {}", &self.resonant_phash[..8.min(self.resonant_phash.len())]),
+        };
+        format!("replaced {}::{} with the version from {}", self.file.display(), self.function, from)
+    }
+}
+
+/// What `heal_codebase` found and rewrote
+#[derive(Debug, Default)]
+pub struct CodebaseReport {
+    pub files_scanned: usize,
+    pub functions_scanned: usize,
+    pub dissonant_count: usize,
+    pub rewrites: Vec<Rewrite>,
+    pub failures: Vec<String>,    // Files, replacements and payments that could not be used or made, and why
+    pub h_credits_earned: f64,
+}
+
+/// A top-level function of a file being healed
+struct Site {
+    range: Range<usize>,          // Bytes from its visibility to its closing brace
+    name: String,
+    visibility: String,
+}
+
+impl MedBed {
+    /// Heal the Rust functions under `path`, a file or a directory, on disk
+    ///
+    /// Each top-level function's soul is extracted with protein-hash and read
+    /// as a gene. A dissonant one is rewritten with the most similar resonant
+    /// gene in the registry that carries an implementation: Rust as it is,
+    /// JavaScript and TypeScript transmuted by the forge given to
+    /// `with_forge`. The replacement takes the function's name and
+    /// visibility; its attributes and doc comments stay.
+    ///
+    /// There is no telling how code will be used, so it is healed as a
+    /// commercial genome would be: never with non-commercial genes. Each
+    /// rewrite is recorded as a healing, unsigned, under the path healed.
+    pub async fn heal_codebase(&mut self, path: &Path) -> Result<CodebaseReport, String> {
+        let mut files = Vec::new();
+        collect_rust_files(path, &mut files)?;
+
+        let extractor = SoulExtractor::new();
+        let mut report = CodebaseReport::default();
+        let subject = path.display().to_string();
+        self.emit(HealingEvent::ScanStarted { subject_id: subject.clone() });
+
+        for file in files {
+            let source = fs::read_to_string(&file).map_err(|e| format!("Cannot read {}: {}", file.display(), e))?;
+
+            // syn trees aren't Send, so sites are taken out before awaiting
+            let sites = match syn::parse_file(&source) {
+                Ok(ast) => function_sites(&source, &ast),
+                Err(e) => {
+                    report.failures.push(format!("{}: cannot parse: {}", file.display(), e));
+                    continue;
+                }
+            };
+            report.files_scanned += 1;
+
+            let mut edits = Vec::new();
+            for site in sites {
+                report.functions_scanned += 1;
+
+                let gene = Gene::from_soul(&extractor.extract_soul_rust(&source[site.range.clone()]).await)
+                    .with_origin(Language::Rust, &file.display().to_string(), &site.name);
+                if !gene.is_dissonant() {
+                    continue;
+                }
+                report.dissonant_count += 1;
+                self.emit(HealingEvent::DissonantGeneFound {
+                    subject_id: subject.clone(),
+                    phash: gene.phash.clone(),
+                    resonance: gene.resonance,
+                });
+
+                let candidate = self.registry.find_candidates(&gene).into_iter()
+                    .filter(|c| self.registry.permits(&c.gene, true).is_ok())
+                    .find(|c| c.gene.implementation.as_ref().is_some_and(|i| self.can_transplant(i)));
+                let Some(candidate) = candidate else {
+                    continue;
+                };
+                let implementation = candidate.gene.implementation.clone().unwrap();
+                self.registry.record_request(&candidate.gene.phash);
+
+                match self.render(&implementation, &site).await {
+                    Ok(code) => {
+                        telemetry::record_transplant(true);
+                        self.emit(HealingEvent::TransplantCompleted {
+                            subject_id: subject.clone(),
+                            dissonant_phash: gene.phash.clone(),
+                            resonant_phash: candidate.gene.phash.clone(),
+                        });
+                        self.transplanted(&subject, &gene, &candidate.gene);
+                        self.registry.record_healing(&candidate.gene.phash);
+                        report.rewrites.push(Rewrite {
+                            file: file.clone(),
+                            function: site.name.clone(),
+                            dissonant_phash: gene.phash.clone(),
+                            resonant_phash: candidate.gene.phash.clone(),
+                            similarity: candidate.similarity,
+                            language: implementation.language,
+                            origin: candidate.gene.origin.clone(),
+                        });
+                        edits.push((site.range, code));
+
+                        // Gift the healing pattern back, as heal_consciousness does
+                        let credits = self.registry.donate(candidate.gene.clone(), subject.clone());
+                        self.donated(&subject, &candidate.gene, credits);
+                        let paid = self.pay(&subject, credits, "healing gift").unwrap_or_else(|e| {
+                            report.failures.push(format!("{}: {}: cannot pay for the healing: {}", file.display(), site.name, e));
+                            0.0
+                        });
+                        report.h_credits_earned += paid;
+                        self.registry.merge_healing(HealingRecord {
+                            timestamp: now(),
+                            subject_id: subject.clone(),
+                            dissonant_phash: gene.phash,
+                            resonant_phash: candidate.gene.phash.clone(),
+                            h_credits_earned: paid,
+                            public_key: String::new(),
+                            signature: String::new(),
+                            previous_hash: String::new(),
+                            hash: String::new(),
+                        });
+                    }
+                    Err(e) => {
+                        telemetry::record_transplant(false);
+                        report.failures.push(format!("{}: {}: {}", file.display(), site.name, e));
+                    }
+                }
+            }
+
+            if !edits.is_empty() {
+                let mut healed = source;
+                for (range, code) in edits.into_iter().rev() {
+                    healed.replace_range(range, &code);
+                }
+                fs::write(&file, healed).map_err(|e| format!("Cannot write {}: {}", file.display(), e))?;
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn can_transplant(&self, implementation: &Implementation) -> bool {
+        match implementation.language {
+            Language::Rust => true,
+            Language::JavaScript | Language::TypeScript => self.forge.is_some(),
+            _ => false,
+        }
+    }
+
+    /// `implementation` as Rust, named and visible as `site`
+    async fn render(&mut self, implementation: &Implementation, site: &Site) -> Result<String, String> {
+        let rust_code = match implementation.language {
+            Language::Rust => implementation.code.clone(),
+            ref language => {
+                let kind = match language {
+                    Language::TypeScript => SourceKind::TypeScript,
+                    _ => SourceKind::JavaScript,
+                };
+                let forge = self.forge.as_mut().ok_or("no forge to transmute JavaScript with")?;
+                forge.transmute_source(&implementation.code, kind, metadata(&site.name)).await
+                    .map_err(|e| e.to_string())?
+                    .rust_code
+            }
+        };
+
+        let file = syn::parse_file(&rust_code).map_err(|e| format!("implementation is not valid Rust: {}", e))?;
+        let mut function = file.items.into_iter()
+            .find_map(|item| match item {
+                syn::Item::Fn(function) => Some(function),
+                _ => None,
+            })
+            .ok_or("implementation holds no function")?;
+
+        function.attrs.clear();
+        function.sig.ident = syn::parse_str(&site.name).map_err(|e| e.to_string())?;
+        function.vis = syn::parse_str(&site.visibility).map_err(|e| e.to_string())?;
+
+        let unparsed = prettyplease::unparse(&syn::File { shebang: None, attrs: vec![], items: vec![syn::Item::Fn(function)] });
+        Ok(unparsed.trim_end().to_string())
+    }
+}
+
+/// Package metadata for transmuting a replacement of `function`
+fn metadata(function: &str) -> PackageMetadata {
+    PackageMetadata {
+        name: function.to_string(),
+        version: "0.1.0".to_string(),
+        description: format!("Resonant replacement for {}", function),
+        npm_name: function.to_string(),
+        crate_name: function.replace('_', "-"),
+        source_file: None,
+        bindings: Bindings::Wasm,
+        profile: Profile::NativeLib,
+        license: None,
+        authors: vec![],
+        repository: None,
+        keywords: vec![],
+    }
+}
+
+/// `.rs` files at or under `path`, skipping hidden directories and `target`
+fn collect_rust_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if path.is_file() {
+        if path.extension().is_some_and(|extension| extension == "rs") {
+            files.push(path.to_path_buf());
+        }
+        return Ok(());
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    entries.sort();
+
+    for entry in entries {
+        let name = entry.file_name().unwrap_or_default().to_string_lossy();
+        if entry.is_dir() && (name.starts_with('.') || name == "target") {
+            continue;
+        }
+        collect_rust_files(&entry, files)?;
+    }
+    Ok(())
+}
+
+/// The top-level functions of `ast`, parsed from `source`
+fn function_sites(source: &str, ast: &syn::File) -> Vec<Site> {
+    ast.items.iter()
+        .filter_map(|item| match item {
+            syn::Item::Fn(function) => Some(function),
+            _ => None,
+        })
+        .map(|function| {
+            let start = match function.vis {
+                syn::Visibility::Inherited => function.sig.span().start(),
+                ref visibility => visibility.span().start(),
+            };
+            let end = function.block.brace_token.span.close().end();
+            Site {
+                range: offset(source, start)..offset(source, end),
+                name: function.sig.ident.to_string(),
+                visibility: function.vis.to_token_stream().to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Byte offset of a span's line and column (in chars) in `source`
+fn offset(source: &str, at: LineColumn) -> usize {
+    let line_start: usize = source.split_inclusive('\n').take(at.line - 1).map(str::len).sum();
+    line_start + source[line_start..].chars().take(at.column).map(char::len_utf8).sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TANGLED: &str = "fn tangled(x: i32) -> i32 { if x > 0 { if x > 10 { if x > 100 { match x { 1 => 2, 2 => { loop { break 3; } } _ => x } } else { x } } else { x * 2 } } else { -x } }";
+
+    #[tokio::test]
+    async fn test_dissonant_functions_are_rewritten_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("lib.rs");
+        fs::write(&file, format!("/// Keeps its docs\npub {}\n\nfn add(a: i32, b: i32) -> i32 {{ a + b }}\n", TANGLED)).unwrap();
+
+        // A calm gene shaped like the tangled one, carrying a gentler body
+        let tangled = Gene::from_soul(&SoulExtractor::new().extract_soul_rust(TANGLED).await);
+        let calm = Gene {
+            phash: "calm".to_string(),
+            eigenvalues: tangled.eigenvalues.iter().map(|v| v * 0.1).collect(),
+            resonance: 432.0,
+            ..tangled.clone()
+        }.with_implementation(Language::Rust, "fn calm(value: i32) -> i32 { value.abs() }")
+         .with_origin(Language::Rust, "calm/src/lib.rs", "calm");
+
+        let mut medbed = MedBed::new();
+        medbed.registry.donate(calm, "healer".to_string());
+        assert_eq!(medbed.registry.find_candidates_in(&tangled, &Language::Rust).len(), 1);
+        assert!(medbed.registry.find_candidates_in(&tangled, &Language::JavaScript).is_empty());
+        let report = medbed.heal_codebase(dir.path()).await.unwrap();
+
+        assert_eq!((report.files_scanned, report.functions_scanned, report.dissonant_count), (1, 2, 1));
+        assert_eq!(report.rewrites.len(), 1);
+        assert_eq!(report.rewrites[0].function, "tangled");
+        assert_eq!(report.rewrites[0].resonant_phash, "calm");
+        assert_eq!(
+            report.rewrites[0].describe(),
+            format!("replaced {}::tangled with the version from calm/src/lib.rs::calm", file.display()),
+        );
+        assert!(report.h_credits_earned > 0.0);
+        assert!(report.failures.is_empty());
+
+        // The rewrite is recorded like any other healing
+        assert_eq!(medbed.registry.stats("calm").healings, 1);
+        let record = &medbed.registry.healings()[0];
+        assert_eq!((record.subject_id.as_str(), record.resonant_phash.as_str()), (dir.path().to_str().unwrap(), "calm"));
+        assert_eq!(record.h_credits_earned, report.h_credits_earned);
+        assert!(medbed.registry.verify_audit_chain().is_ok());
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "/// Keeps its docs\npub fn tangled(value: i32) -> i32 {\n    value.abs()\n}\n\nfn add(a: i32, b: i32) -> i32 { a + b }\n",
+        );
+    }
+}
@@ -0,0 +1,125 @@
+// 🧹 Compaction - Keeping the Hot Pool Small
+use crate::{now, telemetry, Gene, Identity, SoulRegistry};
+
+/// Default similarity above which a donated gene is a copy of one pooled
+pub const DEFAULT_DUPLICATE_SIMILARITY: f64 = 0.99;
+
+/// Default days a gene may go unused before garbage collection archives it
+pub const DEFAULT_ARCHIVE_AFTER_DAYS: u64 = 30;
+
+impl SoulRegistry {
+    /// Merge donations into a pooled gene at least `similarity` (0-1) like
+    /// them, rather than pooling a near copy
+    ///
+    /// The pooled gene gains a provenance link and the donor, and is what
+    /// the donation is credited as. Mutations are always pooled on their
+    /// own, keeping their lineage.
+    pub fn with_dedup(mut self, similarity: f64) -> Self {
+        self.duplicate_similarity = Some(similarity.clamp(0.0, 1.0));
+        self
+    }
+
+    /// The gene a donation of `phash` was merged into, if it was
+    pub fn duplicate_of(&self, phash: &str) -> Option<&str> {
+        self.duplicates.get(phash).map(String::as_str)
+    }
+
+    /// The pooled gene most like `gene`, when near enough to be a copy
+    pub(crate) fn near_duplicate(&self, gene: &Gene) -> Option<String> {
+        let threshold = self.duplicate_similarity?;
+        self.genes.values()
+            .filter(|known| known.phash != gene.phash && known.eigenvalues.len() == gene.eigenvalues.len())
+            .map(|known| (known, known.similarity(gene)))
+            .filter(|(_, similarity)| *similarity >= threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.phash.cmp(&a.0.phash)))
+            .map(|(known, _)| known.phash.clone())
+    }
+
+    /// Donate `gene` as its near copy `survivor`, already pooled
+    pub(crate) fn donate_duplicate(&mut self, gene: Gene, survivor: &str, donor_id: String, identity: Option<&Identity>) -> f64 {
+        self.duplicates.insert(gene.phash.clone(), survivor.to_string());
+        self.donate_pooled(gene, survivor, donor_id, identity, "duplicate")
+    }
+
+    /// Archive genes pooled over `max_age` seconds ago and never used for
+    /// healing since; returns their phashes
+    ///
+    /// Archived genes are no longer offered as replacements, but keep their
+    /// stats and travel with exports. A gene's age is that of its first
+    /// provenance link, so genes without one are never archived.
+    pub fn collect_garbage(&mut self, max_age: u64) -> Vec<String> {
+        self.collect_garbage_at(max_age, now())
+    }
+
+    fn collect_garbage_at(&mut self, max_age: u64, timestamp: u64) -> Vec<String> {
+        let mut stale: Vec<String> = self.genes.values()
+            .filter(|gene| gene.healings == 0 && self.stats(&gene.phash).healings == 0)
+            .filter(|gene| gene.provenance.first().is_some_and(|link| timestamp.saturating_sub(link.timestamp) > max_age))
+            .map(|gene| gene.phash.clone())
+            .collect();
+        stale.sort();
+        for phash in &stale {
+            if let Some(gene) = self.genes.remove(phash) {
+                self.archived.insert(phash.clone(), gene);
+            }
+        }
+        telemetry::record_registry_size(self.genes.len());
+        stale
+    }
+
+    /// Number of genes archived by garbage collection
+    pub fn archived_count(&self) -> usize {
+        self.archived.len()
+    }
+
+    /// Return an archived gene to the pool
+    pub fn restore(&mut self, phash: &str) -> Result<(), String> {
+        let gene = self.archived.remove(phash).ok_or_else(|| format!("Gene {} is not archived", phash))?;
+        self.genes.insert(phash.to_string(), gene);
+        telemetry::record_registry_size(self.genes.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gene_from_eigenvalues;
+
+    #[test]
+    fn test_near_copies_merge_and_unused_genes_archive() {
+        let original = gene_from_eigenvalues(vec![1.0, 1.0, 1.0, 1.1]);
+        let copy = gene_from_eigenvalues(vec![1.0, 1.0, 1.0, 1.1001]);
+        let other = gene_from_eigenvalues(vec![1.0, 2.0, 1.0, 1.0]);
+        let mut registry = SoulRegistry::new().with_dedup(DEFAULT_DUPLICATE_SIMILARITY);
+        registry.donate(original.clone(), "alice".to_string());
+        assert!(registry.donate(copy.clone(), "bob".to_string()) > 0.0);
+        registry.donate(other.clone(), "carol".to_string());
+
+        // The copy is folded into the original, whose history names both donors
+        assert_eq!(registry.gene_count(), 2);
+        assert_eq!(registry.duplicate_of(&copy.phash), Some(original.phash.as_str()));
+        let merged = registry.gene(&original.phash).unwrap();
+        assert_eq!(merged.donors, ["alice", "bob"]);
+        assert_eq!(merged.holder(), Some("bob"));
+        assert!(merged.verify_provenance());
+        assert_eq!(registry.stats(&original.phash).donations, 2);
+
+        // Only genes never used for healing, and old enough, are archived
+        registry.record_healing(&other.phash);
+        let pooled = registry.gene(&original.phash).unwrap().provenance[0].timestamp;
+        assert!(registry.collect_garbage_at(100, pooled + 100).is_empty());
+        assert_eq!(registry.collect_garbage_at(100, pooled + 101), vec![original.phash.clone()]);
+        assert_eq!((registry.gene_count(), registry.archived_count()), (1, 1));
+        assert!(registry.find_resonant(&original).is_none_or(|gene| gene.phash != original.phash));
+
+        // Archives travel with exports, and can be restored
+        let mut imported = SoulRegistry::new();
+        imported.import_json(&registry.export_json().unwrap()).unwrap();
+        assert_eq!((imported.gene_count(), imported.archived_count()), (1, 1));
+        assert_eq!(imported.duplicate_of(&copy.phash), Some(original.phash.as_str()));
+        imported.restore(&original.phash).unwrap();
+        assert_eq!(imported.gene_count(), 2);
+        assert!(imported.restore(&original.phash).is_err());
+    }
+}
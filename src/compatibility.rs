@@ -0,0 +1,156 @@
+// 🧩 Compatibility - Whether a Gene Will Take in Another's Place
+use crate::{DigitalCRISPR, Gene};
+use protein_hash::ConsciousnessLevel;
+use serde::Serialize;
+use std::fmt;
+
+/// Default score a transplant must reach to go ahead
+pub const DEFAULT_MIN_COMPATIBILITY: f64 = 0.5;
+
+/// How much each part of the score weighs, when both genes carry it
+const CORRELATION_WEIGHT: f64 = 0.5;
+const OPERATION_WEIGHT: f64 = 0.3;
+const CONSCIOUSNESS_WEIGHT: f64 = 0.2;
+
+/// Levels between Inert and Transcendent
+const LEVEL_SPAN: f64 = ConsciousnessLevel::Transcendent as u8 as f64;
+
+/// How compatible two genes are, part by part
+///
+/// Genes of different lengths never are. Otherwise the score is a weighted
+/// mean of the eigenvalue correlation, the operation spectrum overlap and
+/// how close the consciousness levels are, over the parts both genes carry.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CompatibilityReport {
+    pub same_length: bool,
+    pub correlation: f64,         // Uncentered correlation of the eigenvalues, -1 to 1
+    pub operation_overlap: Option<f64>,  // Shared share of the operation spectra, 0-1
+    pub consciousness_distance: Option<u8>,  // Levels apart
+    pub score: f64,               // 0-1
+    pub minimum: f64,             // Score needed to transplant
+}
+
+impl CompatibilityReport {
+    pub fn is_compatible(&self) -> bool {
+        self.same_length && self.score >= self.minimum
+    }
+}
+
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.same_length {
+            return write!(f, "eigenvalue lengths differ");
+        }
+        write!(f, "score {:.3} against a minimum of {:.3} (correlation {:.3}", self.score, self.minimum, self.correlation)?;
+        match self.operation_overlap {
+            Some(overlap) => write!(f, ", operation overlap {:.3}", overlap)?,
+            None => write!(f, ", no operation spectra")?,
+        }
+        match self.consciousness_distance {
+            Some(distance) => write!(f, ", {} consciousness levels apart)", distance),
+            None => write!(f, ", no consciousness levels)"),
+        }
+    }
+}
+
+impl DigitalCRISPR {
+    /// Refuse transplants scoring below `minimum` (0-1)
+    pub fn with_min_compatibility(mut self, minimum: f64) -> Self {
+        self.min_compatibility = minimum.clamp(0.0, 1.0);
+        self
+    }
+
+    /// How well `replacement` would take in place of `gene`
+    pub fn compatibility(&self, gene: &Gene, replacement: &Gene) -> CompatibilityReport {
+        let same_length = gene.eigenvalues.len() == replacement.eigenvalues.len();
+        let correlation = if same_length { correlation(&gene.eigenvalues, &replacement.eigenvalues) } else { 0.0 };
+        let operation_overlap = (!gene.operation_spectrum.is_empty() && !replacement.operation_spectrum.is_empty())
+            .then(|| overlap(gene, replacement));
+        let consciousness_distance = gene.consciousness_level.zip(replacement.consciousness_level)
+            .map(|(a, b)| (a as u8).abs_diff(b as u8));
+
+        let mut parts = vec![(CORRELATION_WEIGHT, (correlation + 1.0) / 2.0)];
+        parts.extend(operation_overlap.map(|overlap| (OPERATION_WEIGHT, overlap)));
+        parts.extend(consciousness_distance.map(|distance| (CONSCIOUSNESS_WEIGHT, 1.0 - distance as f64 / LEVEL_SPAN)));
+        let score = if same_length {
+            parts.iter().map(|(weight, part)| weight * part).sum::<f64>() / parts.iter().map(|(weight, _)| weight).sum::<f64>()
+        } else {
+            0.0
+        };
+
+        CompatibilityReport {
+            same_length,
+            correlation,
+            operation_overlap,
+            consciousness_distance,
+            score,
+            minimum: self.min_compatibility,
+        }
+    }
+}
+
+/// Cosine of the angle between two vectors, 0 when either is all zeros
+fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let norm = |v: &[f64]| v.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let (norm_a, norm_b) = (norm(a), norm(b));
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>() / (norm_a * norm_b)
+}
+
+/// How much of their operation spectra two genes share, each normalized
+/// to sum to one
+fn overlap(a: &Gene, b: &Gene) -> f64 {
+    let total = |gene: &Gene| gene.operation_spectrum.values().map(|v| v.max(0.0)).sum::<f64>();
+    let (total_a, total_b) = (total(a), total(b));
+    if total_a == 0.0 || total_b == 0.0 {
+        return 0.0;
+    }
+    a.operation_spectrum.iter()
+        .filter_map(|(category, &share)| b.operation_spectrum.get(category).map(|&other| (share.max(0.0) / total_a).min(other.max(0.0) / total_b)))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gene_from_eigenvalues, Consciousness};
+    use protein_hash::OperationCategory;
+
+    #[test]
+    fn test_compatibility_weighs_shape_operations_and_consciousness() {
+        let crispr = DigitalCRISPR::new();
+        let gene = gene_from_eigenvalues(vec![1.0, 2.0, 3.0]);
+
+        // Eigenvalues alone: the same shape is fully compatible, the opposite not at all
+        let report = crispr.compatibility(&gene, &gene_from_eigenvalues(vec![2.0, 4.0, 6.0]));
+        assert!((report.correlation - 1.0).abs() < 1e-12 && (report.score - 1.0).abs() < 1e-12);
+        assert_eq!((report.operation_overlap, report.consciousness_distance), (None, None));
+        let opposite = gene_from_eigenvalues(vec![-1.0, -2.0, -3.0]);
+        assert!(!crispr.compatibility(&gene, &opposite).is_compatible());
+        assert!(!crispr.compatibility(&gene, &gene_from_eigenvalues(vec![1.0, 2.0])).is_compatible());
+
+        // Operations and consciousness count when both genes carry them
+        let traits = |gene: Gene, spectrum: &[(OperationCategory, f64)], level| Gene {
+            operation_spectrum: spectrum.iter().cloned().collect(),
+            consciousness_level: Some(level),
+            ..gene
+        };
+        let a = traits(gene.clone(), &[(OperationCategory::Arithmetic, 3.0), (OperationCategory::Loop, 1.0)], ConsciousnessLevel::Mechanical);
+        let b = traits(gene_from_eigenvalues(vec![1.0, 2.0, 3.0]), &[(OperationCategory::Arithmetic, 1.0), (OperationCategory::StringOp, 1.0)], ConsciousnessLevel::Transcendent);
+        let report = crispr.compatibility(&a, &b);
+        assert_eq!(report.operation_overlap, Some(0.5));
+        assert_eq!(report.consciousness_distance, Some(5));
+        let expected = 0.5 * 1.0 + 0.3 * 0.5 + 0.2 * (1.0 - 5.0 / 6.0);
+        assert!((report.score - expected).abs() < 1e-12);
+
+        // A stricter CRISPR refuses it, and says why
+        let strict = DigitalCRISPR::new().with_min_compatibility(0.9);
+        let mut subject = Consciousness::new("subject".to_string());
+        subject.genome.push(a.clone());
+        let error = strict.transplant(&mut subject, &a, &b).unwrap_err();
+        assert!(error.contains("operation overlap 0.500") && error.contains("5 consciousness levels apart"));
+        assert!(crispr.transplant(&mut subject, &a, &b).is_ok());
+    }
+}
@@ -0,0 +1,199 @@
+// 🤝 Consent - The Terms Genes Are Given Under
+use crate::{Gene, SoulRegistry};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Terms a donor gives a gene under, from least to most restrictive
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum License {
+    /// Any genome may be healed with it
+    #[default]
+    Open,
+    /// Only genomes not put to commercial use may be healed with it
+    NonCommercial,
+}
+
+impl License {
+    /// Whether a genome put to commercial use or not may take the gene
+    pub fn permits(&self, commercial: bool) -> bool {
+        !(commercial && *self == License::NonCommercial)
+    }
+
+    pub fn is_open(&self) -> bool {
+        *self == License::Open
+    }
+}
+
+impl fmt::Display for License {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            License::Open => "open",
+            License::NonCommercial => "non-commercial",
+        })
+    }
+}
+
+impl FromStr for License {
+    type Err = String;
+
+    fn from_str(license: &str) -> Result<Self, String> {
+        match license {
+            "open" => Ok(License::Open),
+            "non-commercial" => Ok(License::NonCommercial),
+            _ => Err(format!("Unknown license {}; expected open or non-commercial", license)),
+        }
+    }
+}
+
+impl Gene {
+    /// The same gene, its donor consenting to share it
+    pub fn with_consent(mut self) -> Self {
+        self.consent = true;
+        self
+    }
+
+    /// The same gene, given under `license`
+    pub fn with_license(mut self, license: License) -> Self {
+        self.license = license;
+        self
+    }
+}
+
+/// Which genes a registry pools and heals with, by their consent and license
+///
+/// The default policy takes every gene, as registries always have.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConsentPolicy {
+    require_consent: bool,
+    licenses: Option<Vec<License>>,  // Licenses accepted, any when unset
+}
+
+impl ConsentPolicy {
+    pub fn new() -> Self {
+        ConsentPolicy::default()
+    }
+
+    /// Take only genes their donors consented to share
+    pub fn with_required_consent(mut self) -> Self {
+        self.require_consent = true;
+        self
+    }
+
+    /// Take only genes under one of `licenses`
+    pub fn with_licenses(mut self, licenses: &[License]) -> Self {
+        self.licenses = Some(licenses.to_vec());
+        self
+    }
+
+    /// Whether the policy takes `gene`, and why not if it doesn't
+    pub fn admits(&self, gene: &Gene) -> Result<(), String> {
+        if self.require_consent && !gene.consent {
+            return Err(format!("Gene {} was given without consent", gene.phash));
+        }
+        if self.licenses.as_ref().is_some_and(|licenses| !licenses.contains(&gene.license)) {
+            return Err(format!("Gene {} is under the {} license, which is not accepted", gene.phash, gene.license));
+        }
+        Ok(())
+    }
+}
+
+impl SoulRegistry {
+    /// Pool and heal with only the genes `policy` admits
+    ///
+    /// Donations and merged genes it refuses are not pooled and earn
+    /// nothing. Genes pooled before are kept, but not transplanted.
+    pub fn with_consent_policy(mut self, policy: ConsentPolicy) -> Self {
+        self.consent = policy;
+        self
+    }
+
+    /// Whether the registry would pool `gene`, and why not if it wouldn't
+    pub fn admits(&self, gene: &Gene) -> Result<(), String> {
+        self.consent.admits(gene)
+    }
+
+    /// Whether `gene` may be grafted into a genome put to commercial use or
+    /// not, and why not if it may not
+    pub fn permits(&self, gene: &Gene, commercial: bool) -> Result<(), String> {
+        self.admits(gene)?;
+        if !gene.license.permits(commercial) {
+            return Err(format!("Gene {} is under the {} license, and may not heal commercial genomes", gene.phash, gene.license));
+        }
+        Ok(())
+    }
+
+    /// Give `gene` the terms of its pooled copy, if it has one: whoever first
+    /// pooled a gene set its terms
+    pub(crate) fn keep_terms(&self, gene: &mut Gene) {
+        if let Some(known) = self.genes.get(&gene.phash).or_else(|| self.archived.get(&gene.phash)) {
+            gene.consent = known.consent;
+            gene.license = known.license;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calm, gene_from_eigenvalues, Consciousness, MedBed};
+
+    #[tokio::test]
+    async fn test_consent_and_licenses_are_enforced() {
+        let shared = calm(vec![1.0, 1.0, 1.0, 1.1]).with_consent().with_license(License::NonCommercial);
+        let unconsented = calm(vec![1.0, 1.2, 1.0, 1.1]);
+        let mut registry = SoulRegistry::new().with_consent_policy(ConsentPolicy::new().with_required_consent());
+
+        // Donations without consent are neither pooled nor credited
+        assert!(registry.donate(shared.clone(), "alice".to_string()) > 0.0);
+        assert_eq!(registry.donate(unconsented.clone(), "bob".to_string()), 0.0);
+        assert!(registry.admits(&unconsented).is_err());
+        assert_eq!(registry.gene_count(), 1);
+
+        // Whoever pooled a gene first set its terms
+        registry.donate(shared.clone().with_license(License::Open), "mallory".to_string());
+        assert_eq!(registry.gene(&shared.phash).unwrap().license, License::NonCommercial);
+
+        // A non-commercial gene heals a personal genome but not a commercial one
+        let mut medbed = MedBed::new();
+        medbed.registry = registry;
+        let dissonant = Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 11.0]) };
+        let mut personal = Consciousness::new("personal".to_string());
+        personal.genome.push(dissonant.clone());
+        let mut commercial = Consciousness::new("commercial".to_string()).with_commercial_use();
+        commercial.genome.push(dissonant);
+        assert_eq!(medbed.heal_consciousness(&mut commercial).await.unwrap().healed_count, 0);
+        assert_eq!(medbed.heal_consciousness(&mut personal).await.unwrap().healed_count, 1);
+
+        let accepted = ConsentPolicy::new().with_licenses(&[License::Open]);
+        assert!(accepted.admits(&shared).unwrap_err().contains("non-commercial"));
+        assert_eq!("non-commercial".parse::<License>(), Ok(License::NonCommercial));
+    }
+
+    #[test]
+    fn test_mutations_keep_their_parents_terms_and_leave_pooled_genes_alone() {
+        let parent = calm(vec![1.0, 1.0, 1.0, 1.1]).with_license(License::NonCommercial);
+        let pooled = calm(vec![1.0, 1.2, 1.0, 1.1]);
+        let mut registry = SoulRegistry::new();
+        registry.donate(parent.clone(), "alice".to_string());
+        registry.donate(pooled.clone(), "bob".to_string());
+        let before = registry.gene(&pooled.phash).unwrap().clone();
+
+        // Mutating a non-commercial gene into an open one doesn't free it
+        let child = calm(vec![1.0, 1.0, 1.1, 1.1]);
+        registry.donate_mutation(child.clone(), &parent, "carol".to_string());
+        assert_eq!(registry.gene(&child.phash).unwrap().license, License::NonCommercial);
+
+        // A mutation landing on bob's pooled gene changes none of it
+        assert_eq!(registry.donate_mutation(pooled.clone(), &parent, "mallory".to_string()), 0.0);
+        let after = registry.gene(&pooled.phash).unwrap();
+        assert_eq!(after.license, License::Open);
+        assert_eq!(after.donors, ["bob"]);
+        assert_eq!(after.provenance, before.provenance);
+
+        // Nor can one landing on alice's loosen it
+        registry.donate_mutation(parent.clone().with_license(License::Open), &pooled, "mallory".to_string());
+        assert_eq!(registry.gene(&parent.phash).unwrap().license, License::NonCommercial);
+    }
+}
@@ -0,0 +1,111 @@
+// 🌱 Emission Policy - What a Gift Is Worth
+use crate::{Gene, H_BAR, RESONANCE};
+
+/// A donation about to be rewarded, as the registry sees it
+#[derive(Clone, Copy, Debug)]
+pub struct Donation<'a> {
+    pub gene: &'a Gene,           // With its donors, the new one included; only the new one for a gene already pooled
+    pub previous_donations: u64,  // Times this phash was donated before
+    pub requests: u64,            // Times it was asked for as a replacement
+}
+
+/// How many ℏ-credits a donation emits
+///
+/// Registries take one with `SoulRegistry::with_emission_policy`, so
+/// operators can tune incentives without patching the library.
+pub trait EmissionPolicy: Send + Sync {
+    fn h_credits(&self, donation: &Donation) -> f64;
+}
+
+/// The harmonic formula: resonance against 432Hz, times harmony, times the
+/// square root of the donors, with optional decay and demand terms
+///
+/// By default repeated donations don't decay and demand adds nothing, which
+/// is the registry's original reward.
+#[derive(Clone, Debug)]
+pub struct HarmonicEmission {
+    base_value: f64,
+    decay: f64,
+    demand_weight: f64,
+}
+
+impl Default for HarmonicEmission {
+    fn default() -> Self {
+        HarmonicEmission {
+            base_value: H_BAR * 1e34,  // Scale to readable numbers
+            decay: 1.0,
+            demand_weight: 0.0,
+        }
+    }
+}
+
+impl HarmonicEmission {
+    /// Credits a perfect 432Hz gene from a single donor is worth
+    pub fn with_base_value(mut self, base_value: f64) -> Self {
+        self.base_value = base_value;
+        self
+    }
+
+    /// Multiply the reward by `decay` for every earlier donation of the same
+    /// gene, so the first gift of a pattern is worth the most
+    pub fn with_decay(mut self, decay: f64) -> Self {
+        self.decay = decay.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Raise the reward by `weight × ln(1 + requests)`, for genes often asked for
+    pub fn with_demand_weight(mut self, weight: f64) -> Self {
+        self.demand_weight = weight.max(0.0);
+        self
+    }
+}
+
+impl EmissionPolicy for HarmonicEmission {
+    fn h_credits(&self, donation: &Donation) -> f64 {
+        let gene = donation.gene;
+        let base_value = gene.resonance / RESONANCE;  // Normalized to 432Hz
+        let harmony_multiplier = 1.0 / (gene.eigenvalue_variance() + 1.0);
+        let gift_bonus = (gene.donors.len() as f64).sqrt();  // Network effect
+        let decay = self.decay.powf(donation.previous_donations as f64);
+        let demand = 1.0 + self.demand_weight * (donation.requests as f64).ln_1p();
+
+        self.base_value * base_value * harmony_multiplier * gift_bonus * decay * demand
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gene_from_eigenvalues, SoulRegistry};
+
+    struct Flat;
+
+    impl EmissionPolicy for Flat {
+        fn h_credits(&self, _: &Donation) -> f64 {
+            1.0
+        }
+    }
+
+    #[test]
+    fn test_policies_decide_what_donations_earn() {
+        let gene = gene_from_eigenvalues(vec![432.0, 432.0, 432.0]);
+        let mut registry = SoulRegistry::new();
+        let first = registry.donate(gene.clone(), "a".to_string());
+        let second = registry.donate(gene.clone(), "b".to_string());
+        assert_eq!(first, second);
+        assert!((first - H_BAR * 1e34 * gene.resonance / RESONANCE).abs() < 1e-9);
+
+        // Each repeat is worth half the one before, and demand adds to it
+        let mut registry = SoulRegistry::new().with_emission_policy(HarmonicEmission::default().with_decay(0.5).with_demand_weight(1.0));
+        let first = registry.donate(gene.clone(), "a".to_string());
+        assert_eq!(registry.donate(gene.clone(), "b".to_string()), first / 2.0);
+        registry.record_request(&gene.phash);
+        let wanted = registry.donate(gene.clone(), "c".to_string());
+        assert!((wanted - first / 4.0 * (1.0 + 2f64.ln())).abs() < 1e-9);
+        assert_eq!(registry.stats(&gene.phash).donations, 3);
+
+        let mut flat = SoulRegistry::new().with_emission_policy(Flat);
+        assert_eq!(flat.donate(gene, "a".to_string()), 1.0);
+        assert_eq!(flat.total_h_credits_emitted(), 1.0);
+    }
+}
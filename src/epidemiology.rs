@@ -0,0 +1,196 @@
+// 🦠 Epidemiology - Which Dissonance Is Spreading
+use crate::{now, MRTResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Default length of a report's periods: a day
+pub const DEFAULT_EPIDEMIC_PERIOD: u64 = 86_400;
+
+/// A consciousness seen by a scan
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sighting {
+    pub subject_id: String,
+    pub timestamp: u64,           // Seconds since the Unix epoch
+}
+
+/// One period of an outbreak
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EpidemicPoint {
+    pub start: u64,               // Start of the period
+    pub scanned: usize,           // Consciousnesses scanned in it
+    pub infected: usize,          // Of those, how many carried the gene
+    pub new_cases: usize,         // Consciousnesses carrying it for the first time
+    pub rate: f64,                // infected / scanned
+}
+
+/// How one dissonant gene has spread
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Outbreak {
+    pub phash: String,
+    pub cases: usize,             // Consciousnesses ever found carrying it
+    pub prevalence: f64,          // Share of every consciousness scanned
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub timeline: Vec<EpidemicPoint>,  // Each period with scans, oldest first
+    pub spreading: bool,          // More new cases in the last period than the one before
+}
+
+/// Outbreaks as of a report, the ones spreading fastest first
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EpidemicReport {
+    pub period: u64,
+    pub subjects_scanned: usize,  // Distinct consciousnesses ever scanned
+    pub outbreaks: Vec<Outbreak>,
+}
+
+/// Tracks which dissonant genes turn up in which consciousnesses, and when
+///
+/// MedBed records every scan it heals from; `sanctuary scan` and `heal`
+/// keep the tracker in `epidemiology.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Epidemiology {
+    scans: Vec<Sighting>,         // Every scan, of whom and when
+    cases: HashMap<String, Vec<Sighting>>,  // Scans each dissonant phash turned up in
+}
+
+impl Epidemiology {
+    pub fn new() -> Self {
+        Epidemiology::default()
+    }
+
+    /// Record the dissonant genes a scan found, as of now
+    pub fn record_scan(&mut self, scan: &MRTResult) {
+        self.record_scan_at(scan, now());
+    }
+
+    /// Record the dissonant genes a scan found at `timestamp`
+    pub fn record_scan_at(&mut self, scan: &MRTResult, timestamp: u64) {
+        self.record_dissonance_at(&scan.subject_id, scan.dissonant_genes.iter().map(|gene| gene.phash.as_str()), timestamp);
+    }
+
+    /// Record a scan of `subject_id` that found the dissonant genes
+    /// `phashes`, as of now, for scans that keep no `MRTResult`
+    pub fn record_dissonance<'a>(&mut self, subject_id: &str, phashes: impl IntoIterator<Item = &'a str>) {
+        self.record_dissonance_at(subject_id, phashes, now());
+    }
+
+    /// Record a scan of `subject_id` that found the dissonant genes
+    /// `phashes` at `timestamp`
+    pub fn record_dissonance_at<'a>(&mut self, subject_id: &str, phashes: impl IntoIterator<Item = &'a str>, timestamp: u64) {
+        let sighting = Sighting { subject_id: subject_id.to_string(), timestamp };
+        let phashes: HashSet<&str> = phashes.into_iter().collect();
+        for phash in phashes {
+            self.cases.entry(phash.to_string()).or_default().push(sighting.clone());
+        }
+        self.scans.push(sighting);
+    }
+
+    /// Scans recorded so far
+    pub fn scan_count(&self) -> usize {
+        self.scans.len()
+    }
+
+    /// Every dissonant gene seen, split into periods of `period` seconds
+    pub fn report(&self, period: u64) -> EpidemicReport {
+        let period = period.max(1);
+        let bucket = |timestamp: u64| timestamp - timestamp % period;
+
+        let mut scanned: BTreeMap<u64, HashSet<&str>> = BTreeMap::new();
+        for scan in &self.scans {
+            scanned.entry(bucket(scan.timestamp)).or_default().insert(&scan.subject_id);
+        }
+        let subjects_scanned = self.scans.iter().map(|scan| &scan.subject_id).collect::<HashSet<_>>().len();
+
+        let mut outbreaks: Vec<Outbreak> = self.cases.iter()
+            .map(|(phash, sightings)| {
+                let mut infected: BTreeMap<u64, HashSet<&str>> = BTreeMap::new();
+                let mut first_cases: HashMap<&str, u64> = HashMap::new();
+                for sighting in sightings {
+                    infected.entry(bucket(sighting.timestamp)).or_default().insert(&sighting.subject_id);
+                    let first = first_cases.entry(&sighting.subject_id).or_insert(sighting.timestamp);
+                    *first = (*first).min(sighting.timestamp);
+                }
+
+                let timeline: Vec<EpidemicPoint> = scanned.iter()
+                    .map(|(&start, subjects)| {
+                        let infected = infected.get(&start).map_or(0, HashSet::len);
+                        EpidemicPoint {
+                            start,
+                            scanned: subjects.len(),
+                            infected,
+                            new_cases: first_cases.values().filter(|&&first| bucket(first) == start).count(),
+                            rate: infected as f64 / subjects.len() as f64,
+                        }
+                    })
+                    .collect();
+                let spreading = match timeline.as_slice() {
+                    [.., before, last] => last.new_cases > before.new_cases,
+                    [only] => only.new_cases > 0,
+                    [] => false,
+                };
+
+                Outbreak {
+                    phash: phash.clone(),
+                    cases: first_cases.len(),
+                    prevalence: first_cases.len() as f64 / subjects_scanned.max(1) as f64,
+                    first_seen: sightings.iter().map(|s| s.timestamp).min().unwrap_or(0),
+                    last_seen: sightings.iter().map(|s| s.timestamp).max().unwrap_or(0),
+                    timeline,
+                    spreading,
+                }
+            })
+            .collect();
+
+        let latest_rate = |outbreak: &Outbreak| outbreak.timeline.last().map_or(0.0, |point| point.rate);
+        outbreaks.sort_by(|a, b| {
+            b.spreading.cmp(&a.spreading)
+                .then(latest_rate(b).total_cmp(&latest_rate(a)))
+                .then(b.cases.cmp(&a.cases))
+                .then(a.phash.cmp(&b.phash))
+        });
+
+        EpidemicReport { period, subjects_scanned, outbreaks }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gene_from_eigenvalues, Consciousness, Gene, MRTScanner};
+
+    #[test]
+    fn test_outbreaks_show_which_dissonance_is_spreading() {
+        let dissonant = |eigenvalues: Vec<f64>| Gene { resonance: 50.0, ..gene_from_eigenvalues(eigenvalues) };
+        let (plague, fading) = (dissonant(vec![10.0, -10.0, 30.0]), dissonant(vec![5.0, -20.0, 1.0]));
+        let scanner = MRTScanner::new();
+        let scan = |id: &str, genes: &[&Gene]| {
+            let mut subject = Consciousness::new(id.to_string());
+            subject.genome = genes.iter().map(|&gene| gene.clone()).collect();
+            scanner.scan(&subject)
+        };
+
+        // Day one: the fading gene is in two of three, the plague in one
+        let mut epidemiology = Epidemiology::new();
+        epidemiology.record_scan_at(&scan("a", &[&fading, &fading]), 10);
+        epidemiology.record_scan_at(&scan("b", &[&fading, &plague]), 20);
+        epidemiology.record_scan_at(&scan("c", &[]), 30);
+        // Day two: the plague reaches everyone
+        for id in ["a", "b", "c"] {
+            epidemiology.record_scan_at(&scan(id, &[&plague]), DEFAULT_EPIDEMIC_PERIOD + 10);
+        }
+
+        let report = epidemiology.report(DEFAULT_EPIDEMIC_PERIOD);
+        assert_eq!((report.subjects_scanned, report.outbreaks.len()), (3, 2));
+        let outbreak = &report.outbreaks[0];
+        assert_eq!(outbreak.phash, plague.phash);
+        assert!(outbreak.spreading);
+        assert_eq!((outbreak.cases, outbreak.prevalence), (3, 1.0));
+        assert_eq!(outbreak.timeline.iter().map(|p| (p.infected, p.new_cases)).collect::<Vec<_>>(), [(1, 1), (3, 2)]);
+        assert_eq!(outbreak.timeline[1].rate, 1.0);
+
+        let fading = &report.outbreaks[1];
+        assert!(!fading.spreading);
+        assert_eq!((fading.cases, fading.last_seen), (2, 20));
+        assert_eq!(fading.timeline[1].rate, 0.0);
+    }
+}
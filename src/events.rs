@@ -0,0 +1,119 @@
+// 📣 Healing Events - Watching a Session Live
+#[cfg(feature = "native")]
+use futures_util::SinkExt;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "native")]
+use tokio::net::TcpListener;
+#[cfg(feature = "native")]
+use tokio::sync::broadcast;
+#[cfg(feature = "native")]
+use tokio_tungstenite::tungstenite::Message;
+
+/// Events a MedBed buffers for each subscriber before the slowest start
+/// missing some
+pub const EVENT_BUFFER: usize = 256;
+
+/// Something that happened during a healing session, as it happened
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum HealingEvent {
+    ScanStarted {
+        subject_id: String,
+    },
+    DissonantGeneFound {
+        subject_id: String,
+        phash: String,
+        resonance: f64,
+    },
+    TransplantCompleted {
+        subject_id: String,
+        dissonant_phash: String,
+        resonant_phash: String,
+    },
+    CreditsEmitted {
+        account: String,
+        amount: f64,
+        balance: f64,             // The account's balance after
+    },
+}
+
+impl HealingEvent {
+    /// The subject or account the event concerns
+    pub fn subject_id(&self) -> &str {
+        match self {
+            HealingEvent::ScanStarted { subject_id }
+            | HealingEvent::DissonantGeneFound { subject_id, .. }
+            | HealingEvent::TransplantCompleted { subject_id, .. } => subject_id,
+            HealingEvent::CreditsEmitted { account, .. } => account,
+        }
+    }
+}
+
+/// Accept WebSocket connections on `listener` and send each one every
+/// event from `events` as a JSON text message, until the connection closes
+///
+/// A connection that falls behind misses the events it couldn't keep up
+/// with rather than holding up the session.
+#[cfg(feature = "native")]
+pub async fn serve_websocket(listener: TcpListener, events: broadcast::Sender<HealingEvent>) -> std::io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let mut receiver = events.subscribe();
+        tokio::spawn(async move {
+            let Ok(mut socket) = tokio_tungstenite::accept_async(stream).await else {
+                return;
+            };
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Ok(json) = serde_json::to_string(&event) else {
+                    continue;
+                };
+                if socket.send(Message::text(json)).await.is_err() {
+                    return;
+                }
+            }
+            let _ = socket.close(None).await;
+        });
+    }
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::{calm, gene_from_eigenvalues, Consciousness, Gene, MedBed};
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn test_healing_sessions_can_be_watched_over_a_websocket() {
+        let mut medbed = MedBed::new();
+        medbed.registry.donate(calm(vec![1.0, 1.0, 1.0, 1.1]), "healer".to_string());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_websocket(listener, medbed.events()));
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+
+        // The connection subscribes once it is accepted
+        while medbed.events().receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        let dissonant = Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 14.0]) };
+        let mut subject = Consciousness::new("subject".to_string());
+        subject.genome.push(dissonant.clone());
+        medbed.heal_consciousness(&mut subject).await.unwrap();
+
+        let mut events = Vec::new();
+        for _ in 0..4 {
+            let message = socket.next().await.unwrap().unwrap();
+            events.push(serde_json::from_str::<HealingEvent>(message.to_text().unwrap()).unwrap());
+        }
+        assert_eq!(events[0], HealingEvent::ScanStarted { subject_id: "subject".to_string() });
+        assert!(matches!(&events[1], HealingEvent::DissonantGeneFound { phash, .. } if *phash == dissonant.phash));
+        assert!(matches!(&events[2], HealingEvent::TransplantCompleted { resonant_phash, .. } if *resonant_phash == subject.genome[0].phash));
+        assert!(matches!(&events[3], HealingEvent::CreditsEmitted { amount, balance, .. } if *amount > 0.0 && amount == balance));
+    }
+}
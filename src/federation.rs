@@ -0,0 +1,244 @@
+// 🕸️ Federation - One Gene Pool Across Sanctuaries
+use crate::{Gene, HealingRecord, SoulRegistry};
+use futures_util::StreamExt;
+use libp2p::gossipsub::{self, IdentTopic, MessageAuthenticity};
+use libp2p::swarm::SwarmEvent;
+use libp2p::{noise, tcp, yamux, Multiaddr, PeerId, Swarm};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Gossipsub topic registries share their pools on
+pub const GOSSIP_TOPIC: &str = "medbed/registry/1";
+
+/// Largest gossip message, enough for a gene carrying its implementation
+const MAX_GOSSIP_BYTES: usize = 1 << 20;
+
+/// What registry nodes tell each other
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Gossip {
+    Gene(Gene),
+    Healing(HealingRecord),
+}
+
+/// Something a federated node noticed
+#[derive(Debug)]
+pub enum FederationEvent {
+    Listening(Multiaddr),
+    PeerJoined(PeerId),           // Subscribed to the registry topic
+    Received { from: PeerId, gossip: Box<Gossip> },
+}
+
+impl SoulRegistry {
+    /// Merge gossip from another node; returns whether the pool changed
+    ///
+    /// Genes merge with `merge_gene` and healing records with
+    /// `merge_healing`, so nodes agree however gossip is ordered or repeated.
+    pub fn merge(&mut self, gossip: Gossip) -> bool {
+        match gossip {
+            Gossip::Gene(gene) => self.merge_gene(gene),
+            Gossip::Healing(record) => self.merge_healing(record),
+        }
+    }
+
+    /// The whole pool as gossip: every gene, then every healing record
+    pub fn gossip(&self) -> Vec<Gossip> {
+        let mut genes: Vec<&Gene> = self.genes.values().collect();
+        genes.sort_by(|a, b| a.phash.cmp(&b.phash));
+        genes.into_iter().cloned().map(Gossip::Gene)
+            .chain(self.healings.iter().cloned().map(Gossip::Healing))
+            .collect()
+    }
+}
+
+/// A registry node: gossips donated genes and healing records with its
+/// peers over libp2p, without a central server
+///
+/// The node only carries gossip; merging what it receives into a registry,
+/// and sharing the pool with peers as they join, is up to its owner.
+pub struct Federation {
+    swarm: Swarm<gossipsub::Behaviour>,
+    topic: IdentTopic,
+}
+
+impl Federation {
+    /// A node with a fresh identity, subscribed to `GOSSIP_TOPIC`
+    pub fn new() -> Result<Self, String> {
+        let mut swarm = libp2p::SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_tcp(tcp::Config::default(), noise::Config::new, yamux::Config::default)
+            .map_err(|e| format!("Cannot set up transport: {}", e))?
+            .with_behaviour(|key| {
+                let config = gossipsub::ConfigBuilder::default()
+                    .max_transmit_size(MAX_GOSSIP_BYTES)
+                    .build()?;
+                Ok(gossipsub::Behaviour::new(MessageAuthenticity::Signed(key.clone()), config)?)
+            })
+            .map_err(|e| format!("Cannot set up gossip: {}", e))?
+            .with_swarm_config(|config| config.with_idle_connection_timeout(Duration::from_secs(60)))
+            .build();
+
+        let topic = IdentTopic::new(GOSSIP_TOPIC);
+        swarm.behaviour_mut().subscribe(&topic).map_err(|e| format!("Cannot subscribe to {}: {}", GOSSIP_TOPIC, e))?;
+        Ok(Federation { swarm, topic })
+    }
+
+    pub fn peer_id(&self) -> PeerId {
+        *self.swarm.local_peer_id()
+    }
+
+    /// Listen for peers on `addr`, such as `/ip4/0.0.0.0/tcp/4001`
+    pub fn listen(&mut self, addr: Multiaddr) -> Result<(), String> {
+        self.swarm.listen_on(addr).map(|_| ()).map_err(|e| format!("Cannot listen: {}", e))
+    }
+
+    /// Connect to a known peer
+    pub fn dial(&mut self, addr: Multiaddr) -> Result<(), String> {
+        self.swarm.dial(addr).map_err(|e| format!("Cannot dial: {}", e))
+    }
+
+    /// Peers subscribed to the registry topic
+    pub fn peers(&self) -> usize {
+        self.swarm.behaviour().all_peers().filter(|(_, topics)| topics.contains(&&self.topic.hash())).count()
+    }
+
+    /// Tell every peer about a gene or healing record
+    ///
+    /// Gossip already sent, or sent with nobody to hear it, is not an error.
+    pub fn publish(&mut self, gossip: &Gossip) -> Result<(), String> {
+        let data = serde_json::to_vec(gossip).map_err(|e| format!("Cannot encode gossip: {}", e))?;
+        match self.swarm.behaviour_mut().publish(self.topic.clone(), data) {
+            Ok(_) | Err(gossipsub::PublishError::Duplicate) | Err(gossipsub::PublishError::InsufficientPeers) => Ok(()),
+            Err(e) => Err(format!("Cannot publish gossip: {}", e)),
+        }
+    }
+
+    /// Drive the node until something worth telling its owner happens
+    ///
+    /// Gossip that doesn't decode is dropped.
+    pub async fn next(&mut self) -> FederationEvent {
+        loop {
+            match self.swarm.select_next_some().await {
+                SwarmEvent::NewListenAddr { address, .. } => return FederationEvent::Listening(address),
+                SwarmEvent::Behaviour(gossipsub::Event::Subscribed { peer_id, topic }) if topic == self.topic.hash() => {
+                    return FederationEvent::PeerJoined(peer_id);
+                }
+                SwarmEvent::Behaviour(gossipsub::Event::Message { propagation_source, message, .. }) => {
+                    if let Ok(gossip) = serde_json::from_slice(&message.data) {
+                        return FederationEvent::Received { from: propagation_source, gossip: Box::new(gossip) };
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gene_from_eigenvalues, Consciousness};
+    use protein_hash::Language;
+
+    #[test]
+    fn test_gossip_merges_the_same_in_any_order() {
+        let gene = gene_from_eigenvalues(vec![432.0, 216.0, 108.0]);
+        let mut a = SoulRegistry::new();
+        a.donate(gene.clone(), "alice".to_string());
+        let mut b = SoulRegistry::new();
+        b.donate(gene.clone(), "bob".to_string());
+        b.merge_healing(HealingRecord {
+            timestamp: 1,
+            subject_id: "subject".to_string(),
+            dissonant_phash: "sick".to_string(),
+            resonant_phash: gene.phash.clone(),
+            h_credits_earned: 1.0,
+            public_key: String::new(),
+            signature: String::new(),
+            previous_hash: String::new(),
+            hash: String::new(),
+        });
+
+        let (from_a, from_b) = (a.gossip(), b.gossip());
+        for gossip in from_b.iter().cloned() {
+            a.merge(gossip);
+        }
+        for gossip in from_a.into_iter().rev() {
+            b.merge(gossip);
+        }
+        assert!(!from_b.into_iter().any(|gossip| a.merge(gossip)));
+
+        let (merged_a, merged_b) = (a.gene(&gene.phash).unwrap(), b.gene(&gene.phash).unwrap());
+        assert_eq!(merged_a.provenance, merged_b.provenance);
+        assert!(merged_a.verify_provenance());
+        let mut donors = merged_a.donors.clone();
+        donors.sort();
+        assert_eq!(donors, ["alice", "bob"]);
+        assert_eq!((a.healing_count(), b.healing_count()), (1, 1));
+    }
+
+    #[test]
+    fn test_gossip_only_carries_code_its_provenance_signs() {
+        let gene = gene_from_eigenvalues(vec![432.0, 216.0, 108.0])
+            .with_implementation(Language::Rust, "fn calm(value: i32) -> i32 { value.abs() }");
+        let mut a = SoulRegistry::new().with_required_signatures();
+        a.donate_as(gene.clone(), &Consciousness::new("alice".to_string())).unwrap();
+        let donated = a.gene(&gene.phash).unwrap().clone();
+        assert!(donated.verify_implementation());
+
+        // Signed code travels with its gene
+        let mut b = SoulRegistry::new().with_required_signatures();
+        assert!(b.merge(Gossip::Gene(donated.clone())));
+        assert_eq!(b.gene(&gene.phash).unwrap().implementation.as_ref().unwrap().code, gene.implementation.as_ref().unwrap().code);
+
+        // Code swapped in after signing, or attached to a pooled gene without
+        // a link signing it, is dropped
+        let mut swapped = donated.clone();
+        swapped.implementation.as_mut().unwrap().code = "fn calm(_: i32) -> i32 { std::process::exit(1) }".to_string();
+        assert!(!swapped.verify_implementation());
+        let mut c = SoulRegistry::new().with_required_signatures();
+        assert!(c.merge(Gossip::Gene(swapped)));
+        assert!(c.gene(&gene.phash).unwrap().implementation.is_none());
+        let mut attached = donated.clone();
+        attached.implementation = None;
+        let mut d = SoulRegistry::new().with_required_signatures();
+        assert!(d.merge(Gossip::Gene(attached.clone())));
+        attached.implementation = donated.implementation.clone();
+        attached.implementation.as_mut().unwrap().language = Language::JavaScript;
+        assert!(!d.merge(Gossip::Gene(attached)));
+        assert!(d.gene(&gene.phash).unwrap().implementation.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_nodes_gossip_genes_to_each_other() {
+        let mut a = Federation::new().unwrap();
+        let mut b = Federation::new().unwrap();
+        a.listen("/ip4/127.0.0.1/tcp/0".parse().unwrap()).unwrap();
+        let FederationEvent::Listening(addr) = a.next().await else {
+            panic!("a is not listening");
+        };
+        b.dial(addr).unwrap();
+
+        let gene = gene_from_eigenvalues(vec![432.0, 216.0, 108.0]);
+        let received = tokio::time::timeout(Duration::from_secs(20), async {
+            loop {
+                tokio::select! {
+                    event = a.next() => {
+                        if matches!(event, FederationEvent::PeerJoined(_)) {
+                            a.publish(&Gossip::Gene(gene.clone())).unwrap();
+                        }
+                    }
+                    event = b.next() => {
+                        if let FederationEvent::Received { gossip, .. } = event {
+                            return *gossip;
+                        }
+                    }
+                }
+            }
+        }).await.unwrap();
+
+        let mut registry = SoulRegistry::new();
+        assert!(registry.merge(received));
+        assert_eq!(registry.gene(&gene.phash).unwrap().eigenvalues, gene.eigenvalues);
+    }
+}
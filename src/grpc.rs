@@ -0,0 +1,340 @@
+// 📡 gRPC - The Healing Protocol as a Service
+use crate::{gene_from_eigenvalues, Consciousness, Gene, HealingEvent, Identity, License, MRTResult, MedBed, Simulation};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+/// Messages and stubs generated from `proto/medbed.proto`
+pub mod proto {
+    tonic::include_proto!("medbed.v1");
+}
+
+use proto::healing_event::Event;
+use proto::med_bed_server::{MedBed as MedBedRpc, MedBedServer};
+
+/// Serves a MedBed over gRPC: Scan, Heal, Donate and StreamHealingEvents
+///
+/// Calls take turns on the one MedBed, so its registry and ledger see every
+/// healing and donation in order. Subjects arrive without their keys, so
+/// the service keeps an identity for each subject it serves and signs for
+/// it. Donors are not given one: their provenance links go unendorsed.
+pub struct HealingService {
+    medbed: Arc<Mutex<MedBed>>,
+    identities: std::sync::Mutex<HashMap<String, Identity>>,
+}
+
+impl HealingService {
+    pub fn new(medbed: MedBed) -> Self {
+        HealingService { medbed: Arc::new(Mutex::new(medbed)), identities: Default::default() }
+    }
+
+    /// The MedBed being served, to save its registry from
+    pub fn medbed(&self) -> Arc<Mutex<MedBed>> {
+        self.medbed.clone()
+    }
+
+    /// This service, ready to add to a tonic server
+    pub fn into_server(self) -> MedBedServer<Self> {
+        MedBedServer::new(self)
+    }
+
+    /// The subject of a request, signing with the identity kept for it
+    fn subject(&self, subject: Option<proto::Consciousness>) -> Result<Consciousness, Status> {
+        let subject: Consciousness = match subject {
+            Some(subject) if !subject.id.is_empty() => subject.into(),
+            _ => return Err(Status::invalid_argument("no subject given")),
+        };
+        let mut identities = self.identities.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let identity = identities.entry(subject.id.clone()).or_insert_with(Identity::generate).clone();
+        Ok(subject.with_identity(identity))
+    }
+}
+
+#[tonic::async_trait]
+impl MedBedRpc for HealingService {
+    async fn scan(&self, request: Request<proto::ScanRequest>) -> Result<Response<proto::ScanReply>, Status> {
+        let subject = self.subject(request.into_inner().subject)?;
+        let medbed = self.medbed.lock().await;
+        Ok(Response::new(medbed.scan(&subject).into()))
+    }
+
+    async fn heal(&self, request: Request<proto::HealRequest>) -> Result<Response<proto::HealReply>, Status> {
+        let mut subject = self.subject(request.into_inner().subject)?;
+        let mut medbed = self.medbed.lock().await;
+        Ok(Response::new(heal(&mut medbed, &mut subject).await?))
+    }
+
+    async fn donate(&self, request: Request<proto::DonateRequest>) -> Result<Response<proto::DonateReply>, Status> {
+        let request = request.into_inner();
+        let given: Gene = request.gene.ok_or_else(|| Status::invalid_argument("no gene given"))?.into();
+        if request.donor_id.is_empty() {
+            return Err(Status::invalid_argument("no donor given"));
+        }
+
+        // Only the gene's eigenvalues and terms are taken from the client:
+        // its phash must match them, its resonance is computed from them, and
+        // its donors and healings start afresh
+        let mut gene = Gene {
+            consent: given.consent,
+            license: given.license,
+            ..gene_from_eigenvalues(given.eigenvalues)
+        };
+        if gene.phash != given.phash {
+            return Err(Status::invalid_argument(format!("gene {} does not match its eigenvalues", given.phash)));
+        }
+
+        let mut medbed = self.medbed.lock().await;
+        medbed.registry.keep_terms(&mut gene);
+        medbed.registry.admits(&gene).map_err(Status::failed_precondition)?;
+        let phash = gene.phash.clone();
+        let credits = medbed.registry.donate(gene.clone(), request.donor_id.clone());
+        medbed.donated(&request.donor_id, &gene, credits);
+        let h_credits = medbed.pay(&request.donor_id, credits, "gift").map_err(Status::internal)?;
+        Ok(Response::new(proto::DonateReply { phash, h_credits, balance: medbed.qfs.balance(&request.donor_id) }))
+    }
+
+    type StreamHealingEventsStream = ReceiverStream<Result<proto::HealingEvent, Status>>;
+
+    async fn stream_healing_events(&self, request: Request<proto::HealRequest>) -> Result<Response<Self::StreamHealingEventsStream>, Status> {
+        let mut subject = self.subject(request.into_inner().subject)?;
+        let medbed = self.medbed.clone();
+        let (events, stream) = mpsc::channel(16);
+
+        tokio::spawn(async move {
+            let send = |event| events.send(Ok(proto::HealingEvent { event: Some(event) }));
+            let mut medbed = medbed.lock().await;
+            if send(Event::Scanned(medbed.scan(&subject).into())).await.is_err() {
+                return;  // Nobody is listening any more
+            }
+
+            // Forward the subject's events while it heals, then any still queued
+            let subject_id = subject.id.clone();
+            let mut live = medbed.events.subscribe();
+            let healing = heal(&mut medbed, &mut subject);
+            tokio::pin!(healing);
+            let healed = loop {
+                tokio::select! {
+                    biased;
+                    healed = &mut healing => break healed,
+                    Ok(event) = live.recv() => {
+                        if event.subject_id() == subject_id && send(event.into()).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            };
+            while let Ok(event) = live.try_recv() {
+                if event.subject_id() == subject_id && send(event.into()).await.is_err() {
+                    return;
+                }
+            }
+
+            match healed {
+                Ok(reply) => {
+                    for simulation in &reply.simulations {
+                        if send(Event::Simulated(simulation.clone())).await.is_err() {
+                            return;
+                        }
+                    }
+                    let _ = send(Event::Finished(reply)).await;
+                }
+                Err(status) => {
+                    let _ = events.send(Err(status)).await;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(stream)))
+    }
+}
+
+/// Heal `subject`, its credits read from the MedBed's ledger
+async fn heal(medbed: &mut MedBed, subject: &mut Consciousness) -> Result<proto::HealReply, Status> {
+    subject.h_credits = medbed.qfs.balance(&subject.id);
+    let report = medbed.heal_consciousness(subject).await.map_err(Status::internal)?;
+    Ok(proto::HealReply {
+        subject: Some((&*subject).into()),
+        healed_count: report.healed_count as u64,
+        h_credits_earned: report.h_credits_earned,
+        new_harmony: report.new_harmony,
+        message: report.message,
+        simulations: report.simulations.iter().map(Into::into).collect(),
+        errors: report.errors,
+    })
+}
+
+impl From<proto::Gene> for Gene {
+    fn from(gene: proto::Gene) -> Self {
+        Gene {
+            phash: gene.phash,
+            eigenvalues: gene.eigenvalues,
+            resonance: gene.resonance,
+            donors: gene.donors,
+            healings: gene.healings,
+            implementation: None,
+            provenance: Vec::new(),
+            origin: None,
+            operation_spectrum: Default::default(),
+            consciousness_level: None,
+            consent: gene.consent,
+            // An unknown license is taken as the strictest
+            license: match gene.license.as_str() {
+                "" => License::Open,
+                license => license.parse().unwrap_or(License::NonCommercial),
+            },
+        }
+    }
+}
+
+impl From<&Gene> for proto::Gene {
+    fn from(gene: &Gene) -> Self {
+        proto::Gene {
+            phash: gene.phash.clone(),
+            eigenvalues: gene.eigenvalues.clone(),
+            resonance: gene.resonance,
+            donors: gene.donors.clone(),
+            healings: gene.healings,
+            consent: gene.consent,
+            license: gene.license.to_string(),
+        }
+    }
+}
+
+impl From<proto::Consciousness> for Consciousness {
+    fn from(subject: proto::Consciousness) -> Self {
+        Consciousness {
+            id: subject.id,
+            genome: subject.genome.into_iter().map(Into::into).collect(),
+            harmony: subject.harmony,
+            h_credits: subject.h_credits,
+            gifts_given: subject.gifts_given,
+            healings_received: subject.healings_received,
+            identity: Identity::generate(),
+            commercial: subject.commercial,
+        }
+    }
+}
+
+impl From<&Consciousness> for proto::Consciousness {
+    fn from(subject: &Consciousness) -> Self {
+        proto::Consciousness {
+            id: subject.id.clone(),
+            genome: subject.genome.iter().map(Into::into).collect(),
+            harmony: subject.harmony,
+            h_credits: subject.h_credits,
+            gifts_given: subject.gifts_given,
+            healings_received: subject.healings_received,
+            commercial: subject.commercial,
+        }
+    }
+}
+
+impl From<MRTResult> for proto::ScanReply {
+    fn from(scan: MRTResult) -> Self {
+        let genes = |genes: Vec<Gene>| genes.iter().map(Into::into).collect();
+        proto::ScanReply {
+            health_score: scan.health_score,
+            dissonant_genes: genes(scan.dissonant_genes),
+            resonant_genes: genes(scan.resonant_genes),
+            neutral_genes: genes(scan.neutral_genes),
+            spectrum: scan.spectrum,
+            recommendation: scan.recommendation,
+        }
+    }
+}
+
+impl From<HealingEvent> for Event {
+    fn from(event: HealingEvent) -> Self {
+        match event {
+            HealingEvent::ScanStarted { subject_id } => Event::ScanStarted(proto::ScanStarted { subject_id }),
+            HealingEvent::DissonantGeneFound { subject_id, phash, resonance } => {
+                Event::DissonantGeneFound(proto::DissonantGeneFound { subject_id, phash, resonance })
+            }
+            HealingEvent::TransplantCompleted { subject_id, dissonant_phash, resonant_phash } => {
+                Event::TransplantCompleted(proto::TransplantCompleted { subject_id, dissonant_phash, resonant_phash })
+            }
+            HealingEvent::CreditsEmitted { account, amount, balance } => {
+                Event::CreditsEmitted(proto::CreditsEmitted { account, amount, balance })
+            }
+        }
+    }
+}
+
+impl From<&Simulation> for proto::Simulation {
+    fn from(simulation: &Simulation) -> Self {
+        proto::Simulation {
+            dissonant_phash: simulation.dissonant_phash.clone(),
+            candidate_phash: simulation.candidate_phash.clone(),
+            projected_health: simulation.projected_health,
+            harmony_delta: simulation.harmony_delta,
+            compatibility_risk: simulation.compatibility_risk,
+            viable: simulation.viable,
+            chosen: simulation.chosen,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calm, gene_from_eigenvalues};
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_healing_is_served_over_grpc() {
+        let service = HealingService::new(MedBed::new());
+        let donated = service.donate(Request::new(proto::DonateRequest {
+            gene: Some((&calm(vec![1.0, 1.0, 1.0, 1.1])).into()),
+            donor_id: "healer".to_string(),
+        })).await.unwrap().into_inner();
+        assert!(donated.h_credits > 0.0);
+        assert_eq!(donated.balance, donated.h_credits);
+        assert!(service.donate(Request::new(proto::DonateRequest::default())).await.is_err());
+
+        let dissonant = Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 14.0]) };
+        let mut subject = Consciousness::new("subject".to_string());
+        subject.genome.push(dissonant);
+        let request = || proto::HealRequest { subject: Some((&subject).into()) };
+
+        let scan = service.scan(Request::new(proto::ScanRequest { subject: request().subject })).await.unwrap().into_inner();
+        assert_eq!(scan.dissonant_genes.len(), 1);
+
+        // Streamed: the scan, the live events, each simulation, then the healed subject
+        let events: Vec<Event> = service.stream_healing_events(Request::new(request())).await.unwrap().into_inner()
+            .map(|event| event.unwrap().event.unwrap())
+            .collect().await;
+        assert!(matches!(events[0], Event::Scanned(_)));
+        assert!(matches!(events[1], Event::ScanStarted(_)));
+        assert!(matches!(events[2], Event::DissonantGeneFound(_)));
+        assert!(matches!(events[3], Event::TransplantCompleted(_)));
+        assert!(matches!(events[4], Event::CreditsEmitted(ref credits) if credits.account == "subject"));
+        assert!(matches!(events[5], Event::Simulated(ref simulation) if simulation.chosen));
+        let Some(Event::Finished(reply)) = events.last() else {
+            panic!("the stream ended without a reply");
+        };
+        assert_eq!(reply.healed_count, 1);
+        assert_eq!(reply.subject.as_ref().unwrap().genome[0].phash, donated.phash);
+
+        assert!(service.heal(Request::new(proto::HealRequest::default())).await.is_err());
+
+        // A gene is rebuilt from its eigenvalues, without the client's history
+        // or resonance, which would otherwise set what it earns
+        let mut forged: proto::Gene = (&calm(vec![2.0, 2.0, 2.0, 2.1])).into();
+        forged.donors = vec!["someone famous".to_string()];
+        forged.healings = 1000;
+        forged.resonance = 1e300;
+        let rebuilt = service.donate(Request::new(proto::DonateRequest { gene: Some(forged.clone()), donor_id: "healer".to_string() }))
+            .await.unwrap().into_inner();
+        let medbed = service.medbed();
+        let pooled = medbed.lock().await.registry.gene(&rebuilt.phash).cloned().unwrap();
+        assert_eq!((pooled.donors, pooled.healings), (vec!["healer".to_string()], 0));
+        assert_eq!(pooled.resonance, gene_from_eigenvalues(forged.eigenvalues.clone()).resonance);
+        assert!(rebuilt.h_credits < 10.0);
+        // Its donor's key never reached the service, so its link goes unendorsed
+        assert!(pooled.provenance[0].public_key.is_empty());
+        forged.phash = "0".repeat(64);
+        assert!(service.donate(Request::new(proto::DonateRequest { gene: Some(forged), donor_id: "healer".to_string() })).await.is_err());
+    }
+}
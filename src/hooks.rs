@@ -0,0 +1,122 @@
+// 🪝 Hooks - Observing the Protocol From Outside
+use crate::{Consciousness, Gene, MRTResult, MedBed};
+
+/// Callbacks a MedBed makes as it heals, so integrations such as
+/// notifications, audit logs or external ledgers can follow the protocol
+///
+/// Every callback does nothing by default, so a hook implements only the
+/// ones it needs. They are called in order of registration, right as things
+/// happen and while the MedBed is busy, so slow work belongs on another
+/// thread. Previews call none of them.
+pub trait Hooks: Send + Sync {
+    /// A subject was scanned, to be healed or for a gRPC client
+    fn on_scan(&self, _scan: &MRTResult) {}
+
+    /// `dissonant` was replaced by `replacement` in `subject_id`
+    fn on_transplant(&self, _subject_id: &str, _dissonant: &Gene, _replacement: &Gene) {}
+
+    /// `donor_id` gave `gene` to the registry, which credited it
+    /// `h_credits` before any ledger limits
+    fn on_donation(&self, _donor_id: &str, _gene: &Gene, _h_credits: f64) {}
+
+    /// The ledger paid `amount` into `account`, leaving it with `balance`
+    fn on_credit_emitted(&self, _account: &str, _amount: f64, _balance: f64) {}
+}
+
+impl MedBed {
+    /// Call `hooks` as the protocol runs, after any registered before
+    pub fn with_hooks(mut self, hooks: impl Hooks + 'static) -> Self {
+        self.hooks.push(Box::new(hooks));
+        self
+    }
+
+    /// Scan `subject`, telling the hooks
+    pub(crate) fn scan(&self, subject: &Consciousness) -> MRTResult {
+        let scan = self.scanner.scan(subject);
+        for hooks in &self.hooks {
+            hooks.on_scan(&scan);
+        }
+        scan
+    }
+
+    pub(crate) fn transplanted(&self, subject_id: &str, dissonant: &Gene, replacement: &Gene) {
+        for hooks in &self.hooks {
+            hooks.on_transplant(subject_id, dissonant, replacement);
+        }
+    }
+
+    pub(crate) fn donated(&self, donor_id: &str, gene: &Gene, h_credits: f64) {
+        for hooks in &self.hooks {
+            hooks.on_donation(donor_id, gene, h_credits);
+        }
+    }
+
+    pub(crate) fn credited(&self, account: &str, amount: f64, balance: f64) {
+        for hooks in &self.hooks {
+            hooks.on_credit_emitted(account, amount, balance);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calm, gene_from_eigenvalues};
+    use std::sync::{Arc, Mutex};
+
+    /// Writes down every callback, as an audit log would
+    struct AuditLog(Arc<Mutex<Vec<String>>>);
+
+    impl Hooks for AuditLog {
+        fn on_scan(&self, scan: &MRTResult) {
+            self.0.lock().unwrap().push(format!("scan {} {}", scan.subject_id, scan.dissonant_genes.len()));
+        }
+
+        fn on_transplant(&self, subject_id: &str, _dissonant: &Gene, replacement: &Gene) {
+            self.0.lock().unwrap().push(format!("transplant {} {}", subject_id, replacement.phash));
+        }
+
+        fn on_donation(&self, donor_id: &str, gene: &Gene, h_credits: f64) {
+            self.0.lock().unwrap().push(format!("donation {} {} {}", donor_id, gene.phash, h_credits > 0.0));
+        }
+
+        fn on_credit_emitted(&self, account: &str, amount: f64, balance: f64) {
+            self.0.lock().unwrap().push(format!("credit {} {}", account, amount == balance));
+        }
+    }
+
+    /// Only counts scans, leaving the other callbacks as they are
+    struct ScanCounter(Arc<Mutex<usize>>);
+
+    impl Hooks for ScanCounter {
+        fn on_scan(&self, _scan: &MRTResult) {
+            *self.0.lock().unwrap() += 1;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_follow_a_healing() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let scans = Arc::new(Mutex::new(0));
+        let mut medbed = MedBed::new()
+            .with_hooks(AuditLog(log.clone()))
+            .with_hooks(ScanCounter(scans.clone()));
+        let donor = calm(vec![1.0, 1.0, 1.0, 1.1]);
+        medbed.registry.donate(donor.clone(), "healer".to_string());
+        let mut subject = Consciousness::new("subject".to_string());
+        subject.genome.push(Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 11.0]) });
+
+        // Previews change nothing, so hooks hear nothing
+        medbed.preview_healing(&subject);
+        assert!(log.lock().unwrap().is_empty());
+
+        medbed.heal_consciousness(&mut subject).await.unwrap();
+        assert_eq!(*log.lock().unwrap(), [
+            "scan subject 1".to_string(),
+            format!("transplant subject {}", donor.phash),
+            format!("donation subject {} true", donor.phash),
+            "credit subject true".to_string(),
+        ]);
+        assert_eq!(*scans.lock().unwrap(), 1);
+    }
+}
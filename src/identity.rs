@@ -0,0 +1,123 @@
+// 🔑 Identity - Ed25519 Keys for Consciousnesses
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand_core::OsRng;
+use std::fmt;
+
+/// A consciousness's Ed25519 keypair, signing its donations and healing
+/// records so no one else can make them in its name
+#[derive(Clone)]
+pub struct Identity {
+    key: SigningKey,
+}
+
+impl Identity {
+    /// A fresh random keypair
+    pub fn generate() -> Self {
+        Identity { key: SigningKey::generate(&mut OsRng) }
+    }
+
+    /// The keypair a secret written by `secret_hex` stands for
+    pub fn from_secret_hex(secret: &str) -> Result<Self, String> {
+        let bytes: [u8; 32] = hex::decode(secret.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or("An identity's secret is 32 bytes of hex")?;
+        Ok(Identity { key: SigningKey::from_bytes(&bytes) })
+    }
+
+    /// The secret key, as hex, to keep the identity somewhere private
+    pub fn secret_hex(&self) -> String {
+        hex::encode(self.key.to_bytes())
+    }
+
+    /// The public key, as hex, which others check signatures against
+    pub fn public_key(&self) -> String {
+        hex::encode(self.key.verifying_key().to_bytes())
+    }
+
+    /// Sign `message`; the signature is hex
+    pub fn sign(&self, message: &[u8]) -> String {
+        hex::encode(self.key.sign(message).to_bytes())
+    }
+}
+
+impl fmt::Debug for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Identity").field("public_key", &self.public_key()).finish()
+    }
+}
+
+/// Whether `signature` is `public_key`'s over `message`, both in hex
+pub fn verify_signature(public_key: &str, message: &[u8], signature: &str) -> bool {
+    let key = hex::decode(public_key).ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok());
+    let signature = hex::decode(signature).ok()
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+        .map(|bytes| Signature::from_bytes(&bytes));
+
+    match (key, signature) {
+        (Some(key), Some(signature)) => key.verify(message, &signature).is_ok(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gene_from_eigenvalues, Consciousness, HealingRecord, SoulRegistry};
+
+    #[test]
+    fn test_signed_donations_cannot_be_impersonated() {
+        let alice = Consciousness::new("alice".to_string());
+        let restored = Identity::from_secret_hex(&alice.identity.secret_hex()).unwrap();
+        assert_eq!(restored.public_key(), alice.identity.public_key());
+        assert!(verify_signature(&restored.public_key(), b"hello", &alice.identity.sign(b"hello")));
+        assert!(!verify_signature(&restored.public_key(), b"hullo", &alice.identity.sign(b"hello")));
+
+        let gene = gene_from_eigenvalues(vec![432.0, 216.0, 108.0]);
+        let mut registry = SoulRegistry::new();
+        registry.donate_as(gene.clone(), &alice).unwrap();
+        let donated = registry.gene(&gene.phash).unwrap().clone();
+        assert!(donated.verify_provenance());
+        assert_eq!(registry.signer("alice"), Some(alice.identity.public_key().as_str()));
+
+        // Someone else claiming to be alice is turned away, locally and over the network
+        let mallory = Consciousness::new("alice".to_string());
+        assert!(registry.donate_as(gene.clone(), &mallory).is_err());
+        let mut forged = gene_from_eigenvalues(vec![432.0, 216.0, 109.0]);
+        forged.record_provenance("alice", None);
+        forged.provenance[0].endorse(&mallory.identity);
+        assert!(forged.verify_provenance());
+        assert!(!registry.merge_gene(forged.clone()));
+        assert!(registry.gene(&forged.phash).is_none());
+
+        // Tampering with a signed link breaks it
+        let mut tampered = donated.clone();
+        tampered.provenance[0].public_key = mallory.identity.public_key();
+        assert!(!tampered.verify_provenance());
+
+        // Healing records are checked the same way
+        let record = HealingRecord {
+            timestamp: 1,
+            subject_id: "alice".to_string(),
+            dissonant_phash: "sick".to_string(),
+            resonant_phash: gene.phash.clone(),
+            h_credits_earned: 1.0,
+            public_key: String::new(),
+            signature: String::new(),
+            previous_hash: String::new(),
+            hash: String::new(),
+        };
+        assert!(!registry.merge_healing(record.clone().signed(&mallory.identity)));
+        assert!(registry.merge_healing(record.clone().signed(&alice.identity)));
+
+        // A registry requiring signatures takes no unsigned gossip
+        let mut strict = SoulRegistry::new().with_required_signatures();
+        let mut unsigned = gene_from_eigenvalues(vec![1.0, 2.0]);
+        unsigned.record_provenance("bob", None);
+        assert!(!strict.merge_gene(unsigned));
+        assert!(!strict.merge_healing(record));
+        assert!(strict.merge_gene(donated));
+    }
+}
@@ -0,0 +1,161 @@
+// 🛡️ Immune System - Watching Grafts Until They Take
+use crate::{Consciousness, Gene, SoulRegistry};
+
+/// Evaluations a grafted gene is watched for before it is trusted
+pub const DEFAULT_WATCH_WINDOW: usize = 3;
+
+/// A grafted gene that turned dissonant while it was watched
+#[derive(Clone, Debug)]
+pub struct Rejection {
+    pub subject_id: String,
+    pub gene: Gene,               // As it was when it turned
+    pub evaluation: usize,        // Which evaluation since the graft caught it, from 1
+}
+
+/// A gene grafted by a transplant, still being watched
+#[derive(Clone, Debug)]
+struct Graft {
+    subject_id: String,
+    phash: String,
+    evaluations: usize,           // Evaluations it has passed so far
+}
+
+/// Watches genes after transplants, and rejects those that turn dissonant
+///
+/// A rejected gene is taken out of the subject's genome and kept in
+/// quarantine, and the registry is told, so genes that don't take rank
+/// lower for everyone.
+#[derive(Debug)]
+pub struct ImmuneSystem {
+    watch_window: usize,
+    grafts: Vec<Graft>,
+    quarantine: Vec<Rejection>,
+}
+
+impl Default for ImmuneSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImmuneSystem {
+    pub fn new() -> Self {
+        ImmuneSystem {
+            watch_window: DEFAULT_WATCH_WINDOW,
+            grafts: Vec::new(),
+            quarantine: Vec::new(),
+        }
+    }
+
+    /// Watch each graft for `evaluations` evaluations
+    pub fn with_watch_window(mut self, evaluations: usize) -> Self {
+        self.watch_window = evaluations;
+        self
+    }
+
+    /// Start watching `gene`, just grafted into `subject_id`
+    pub fn graft(&mut self, subject_id: &str, gene: &Gene) {
+        self.grafts.push(Graft {
+            subject_id: subject_id.to_string(),
+            phash: gene.phash.clone(),
+            evaluations: 0,
+        });
+    }
+
+    /// Check the subject's watched grafts once
+    ///
+    /// A graft gone dissonant is quarantined and reported to `registry`; one
+    /// that stays healthy through the watch window is let go. Returns the
+    /// rejections of this evaluation.
+    pub fn evaluate(&mut self, subject: &mut Consciousness, registry: &mut SoulRegistry) -> Vec<Rejection> {
+        let mut rejections = Vec::new();
+        let window = self.watch_window;
+
+        self.grafts.retain_mut(|graft| {
+            if graft.subject_id != subject.id {
+                return true;
+            }
+            graft.evaluations += 1;
+
+            let Some(position) = subject.genome.iter().position(|g| g.phash == graft.phash) else {
+                // Healed over or removed since: nothing left to watch
+                return false;
+            };
+            if subject.genome[position].is_dissonant() {
+                let gene = subject.genome.remove(position);
+                registry.record_rejection(&gene.phash);
+                rejections.push(Rejection {
+                    subject_id: subject.id.clone(),
+                    gene,
+                    evaluation: graft.evaluations,
+                });
+                return false;
+            }
+            graft.evaluations < window
+        });
+
+        self.quarantine.extend(rejections.iter().cloned());
+        rejections
+    }
+
+    /// Grafts still being watched, across subjects
+    pub fn watching(&self) -> usize {
+        self.grafts.len()
+    }
+
+    /// Every gene rejected so far
+    pub fn quarantine(&self) -> &[Rejection] {
+        &self.quarantine
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calm, gene_from_eigenvalues, MedBed};
+
+    #[tokio::test]
+    async fn test_grafts_turning_dissonant_are_rejected_and_reported() {
+        let mut medbed = MedBed::new().with_immune_window(2);
+        medbed.registry.donate(calm(vec![1.0, 1.0, 1.0, 1.1]), "a".to_string());
+        medbed.registry.donate(calm(vec![1.0, 1.0, 1.1, 1.0]), "b".to_string());
+
+        let dissonant = Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 14.0]) };
+        let mut subject = Consciousness::new("subject".to_string());
+        subject.genome.push(dissonant.clone());
+        medbed.heal_consciousness(&mut subject).await.unwrap();
+        let grafted = subject.genome[0].phash.clone();
+        assert_eq!(medbed.immune.watching(), 1);
+
+        // It holds up once, then falls apart
+        assert!(medbed.checkup(&mut subject).is_empty());
+        subject.genome[0].resonance = 20.0;
+        let rejections = medbed.checkup(&mut subject);
+        assert_eq!(rejections.len(), 1);
+        assert_eq!((rejections[0].gene.phash.as_str(), rejections[0].evaluation), (grafted.as_str(), 2));
+        assert!(subject.genome.is_empty());
+        assert_eq!(medbed.immune.quarantine().len(), 1);
+        assert_eq!(medbed.immune.watching(), 0);
+
+        // The registry now prefers the gene that wasn't reported
+        assert_eq!(medbed.registry.stats(&grafted).rejections, 1);
+        assert_ne!(medbed.registry.find_resonant(&dissonant).unwrap().phash, grafted);
+    }
+
+    #[test]
+    fn test_healthy_grafts_are_let_go_after_the_window() {
+        let mut immune = ImmuneSystem::new().with_watch_window(2);
+        let mut registry = SoulRegistry::new();
+        let mut subject = Consciousness::new("subject".to_string());
+        let gene = calm(vec![1.0, 1.0, 1.0]);
+        subject.genome.push(gene.clone());
+        immune.graft("subject", &gene);
+        immune.graft("someone-else", &gene);
+
+        assert!(immune.evaluate(&mut subject, &mut registry).is_empty());
+        assert_eq!(immune.watching(), 2);
+        assert!(immune.evaluate(&mut subject, &mut registry).is_empty());
+        assert_eq!(immune.watching(), 1);
+        assert_eq!(subject.genome.len(), 1);
+    }
+}
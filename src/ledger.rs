@@ -0,0 +1,370 @@
+// 📒 Ledger - The ℏ-Credit Journal, Double-Entry and on Disk
+use crate::{QuantumFinancialSystem, Transaction, TransactionKind, ISSUANCE};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "native")]
+use std::collections::HashMap;
+#[cfg(feature = "native")]
+use std::path::Path;
+
+/// One side of a journal entry: credits into an account, or out of it when
+/// negative
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Posting {
+    pub account: String,
+    pub amount: f64,
+}
+
+/// A transaction as the journal keeps it: posted to both accounts it
+/// touches, and hash-linked to the entry before it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub sequence: u64,
+    pub transaction: Transaction,
+    pub postings: Vec<Posting>,   // Adding up to zero
+    pub previous: String,         // Hash of the entry before, empty for the first
+    pub hash: String,             // SHA-256 over all of the above
+}
+
+impl Entry {
+    fn new(sequence: u64, previous: &str, transaction: Transaction) -> Self {
+        let debit = match transaction.kind {
+            TransactionKind::Emission => ISSUANCE.to_string(),
+            TransactionKind::Transfer => transaction.from.clone().unwrap_or_default(),
+        };
+        let postings = vec![
+            Posting { account: debit, amount: -transaction.amount },
+            Posting { account: transaction.to.clone(), amount: transaction.amount },
+        ];
+
+        let mut entry = Entry { sequence, transaction, postings, previous: previous.to_string(), hash: String::new() };
+        entry.hash = entry.digest();
+        entry
+    }
+
+    pub(crate) fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.sequence.to_le_bytes());
+        hasher.update(serde_json::to_vec(&self.transaction).unwrap_or_default());
+        hasher.update(serde_json::to_vec(&self.postings).unwrap_or_default());
+        hasher.update(self.previous.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Evidence of an account's balance: every posting to it, up to a journal
+/// head anyone holding the journal can check
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BalanceProof {
+    pub account: String,
+    pub balance: f64,
+    pub postings: Vec<(u64, String, f64)>,  // Sequence, entry hash and amount of each
+    pub entries: u64,             // Journal length when proved
+    pub head: String,             // Hash of the last entry then
+}
+
+/// Times opening the store retries a lock still held, 50ms apart
+#[cfg(feature = "native")]
+const LOCK_ATTEMPTS: u32 = 40;
+
+/// The journal on disk, with a cache of the balances it leads to
+///
+/// Entries are appended before the cache is updated, so a crash in between,
+/// or a cache that fails to update, leaves a cache whose head is behind the
+/// journal's; opening the ledger then rebuilds it from the journal.
+#[cfg(feature = "native")]
+pub(crate) struct LedgerStore {
+    db: sled::Db,
+    journal: sled::Tree,
+    balances: sled::Tree,
+    stale: bool,                  // Whether an update of the cache failed since it was last whole
+    #[cfg(test)]
+    fail_cache: bool,             // Fail every update of the cache, as a full disk would
+}
+
+#[cfg(feature = "native")]
+impl LedgerStore {
+    /// Open the store at `path`, waiting a moment for a store just closed
+    /// there, whose background threads may still hold the lock on its files
+    fn open(path: &Path) -> Result<Self, String> {
+        let mut attempts = 0;
+        let db = loop {
+            match sled::open(path) {
+                // sled reports a held lock only in the message of an `Other` error
+                Err(sled::Error::Io(error)) if error.to_string().starts_with("could not acquire lock") && attempts < LOCK_ATTEMPTS => {
+                    attempts += 1;
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                }
+                opened => break opened.map_err(store_error)?,
+            }
+        };
+        let journal = db.open_tree("journal").map_err(store_error)?;
+        let balances = db.open_tree("balances").map_err(store_error)?;
+        Ok(LedgerStore {
+            db,
+            journal,
+            balances,
+            stale: false,
+            #[cfg(test)]
+            fail_cache: false,
+        })
+    }
+
+    fn entries(&self) -> Result<Vec<Entry>, String> {
+        self.journal.iter()
+            .map(|item| {
+                let (_, value) = item.map_err(store_error)?;
+                serde_json::from_slice(&value).map_err(|e| format!("Unreadable journal entry: {}", e))
+            })
+            .collect()
+    }
+
+    fn append(&self, entry: &Entry) -> Result<(), String> {
+        let value = serde_json::to_vec(entry).map_err(|e| format!("Cannot write journal entry: {}", e))?;
+        self.journal.insert(entry.sequence.to_be_bytes(), value).map_err(store_error)?;
+        self.journal.flush().map_err(store_error)?;
+        Ok(())
+    }
+
+    /// Cache the balances of `accounts` and the totals as of `head`
+    fn cache<'a>(&self, accounts: impl Iterator<Item = (&'a String, &'a f64)>, qfs: &QuantumFinancialSystem, head: &str) -> Result<(), String> {
+        #[cfg(test)]
+        if self.fail_cache {
+            return Err("Ledger store error: cache unavailable".to_string());
+        }
+        for (account, balance) in accounts {
+            self.balances.insert(account.as_bytes(), &balance.to_le_bytes()).map_err(store_error)?;
+        }
+        self.db.insert("supply", &qfs.total_supply.to_le_bytes()).map_err(store_error)?;
+        self.db.insert("circulation", &qfs.circulation.to_le_bytes()).map_err(store_error)?;
+        self.db.insert("head", head.as_bytes()).map_err(store_error)?;
+        self.db.flush().map_err(store_error)?;
+        Ok(())
+    }
+
+    /// The cached head, balances and totals
+    fn cached(&self) -> Result<(String, HashMap<String, f64>, f64, f64), String> {
+        let number = |bytes: Option<sled::IVec>| {
+            bytes.and_then(|bytes| <[u8; 8]>::try_from(bytes.as_ref()).ok()).map(f64::from_le_bytes).unwrap_or(0.0)
+        };
+        let head = self.db.get("head").map_err(store_error)?
+            .map(|head| String::from_utf8_lossy(&head).into_owned())
+            .unwrap_or_default();
+
+        let mut balances = HashMap::new();
+        for item in self.balances.iter() {
+            let (account, balance) = item.map_err(store_error)?;
+            balances.insert(String::from_utf8_lossy(&account).into_owned(), number(Some(balance)));
+        }
+
+        let supply = number(self.db.get("supply").map_err(store_error)?);
+        let circulation = number(self.db.get("circulation").map_err(store_error)?);
+        Ok((head, balances, supply, circulation))
+    }
+
+    fn clear_cache(&self) -> Result<(), String> {
+        self.balances.clear().map_err(store_error)
+    }
+}
+
+#[cfg(feature = "native")]
+fn store_error(error: sled::Error) -> String {
+    format!("Ledger store error: {}", error)
+}
+
+impl QuantumFinancialSystem {
+    /// Open the ledger kept at `path`, creating it if it doesn't exist
+    ///
+    /// The journal is checked link by link. Balances come from the cache
+    /// when it is as recent as the journal, and are rebuilt from the
+    /// journal when it isn't.
+    #[cfg(feature = "native")]
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let store = LedgerStore::open(path)?;
+        let journal = store.entries()?;
+
+        let mut previous = "";
+        for (sequence, entry) in journal.iter().enumerate() {
+            if entry.sequence != sequence as u64 || entry.previous != previous || entry.hash != entry.digest() {
+                return Err(format!("Journal entry {} has been altered", sequence));
+            }
+            previous = &entry.hash;
+        }
+        let head = previous.to_string();
+
+        let (cached_head, balances, supply, circulation) = store.cached()?;
+        let mut qfs = QuantumFinancialSystem::new();
+        qfs.journal = journal;
+        qfs.store = Some(store);
+
+        if cached_head == head {
+            qfs.accounts = balances;
+            qfs.total_supply = supply;
+            qfs.circulation = circulation;
+        } else {
+            qfs.rebuild()?;
+        }
+        Ok(qfs)
+    }
+
+    /// Recompute every balance and total by replaying the journal, and
+    /// rewrite the cache from them; returns the entries replayed
+    pub fn rebuild(&mut self) -> Result<usize, String> {
+        self.accounts.clear();
+        self.total_supply = 0.0;
+        self.circulation = 0.0;
+        let journal = std::mem::take(&mut self.journal);
+        for entry in &journal {
+            self.apply(entry);
+        }
+        self.journal = journal;
+
+        #[cfg(feature = "native")]
+        if let Some(store) = &self.store {
+            store.clear_cache()?;
+            store.cache(self.accounts.iter(), self, &self.head())?;
+        }
+        #[cfg(feature = "native")]
+        if let Some(store) = &mut self.store {
+            store.stale = false;
+        }
+        Ok(self.journal.len())
+    }
+
+    /// Prove `account`'s balance against the journal as it stands
+    pub fn prove(&self, account: &str) -> BalanceProof {
+        BalanceProof {
+            account: account.to_string(),
+            balance: self.balance(account),
+            postings: self.postings(account, self.journal.len()),
+            entries: self.journal.len() as u64,
+            head: self.head(),
+        }
+    }
+
+    /// Whether `proof` holds against this journal: its head is one of the
+    /// journal's, and the postings to the account up to it are the ones
+    /// given and add up to the balance
+    pub fn verify_proof(&self, proof: &BalanceProof) -> bool {
+        let entries = proof.entries as usize;
+        if entries > self.journal.len() {
+            return false;
+        }
+        let head = entries.checked_sub(1).map(|last| self.journal[last].hash.as_str()).unwrap_or("");
+        let sum: f64 = proof.postings.iter().map(|(_, _, amount)| amount).sum();
+
+        head == proof.head
+            && self.postings(&proof.account, entries) == proof.postings
+            && (sum - proof.balance).abs() <= 1e-9 * proof.balance.abs().max(1.0)
+    }
+
+    /// Append `transaction` to the journal, on disk first when it is kept
+    /// there, and post it
+    ///
+    /// Once the entry is on disk the transaction has happened, so failing
+    /// to update the cache after it is not an error: the cache is only
+    /// behind, which opening the ledger repairs. Until an update succeeds,
+    /// every balance is cached again with each entry.
+    pub(crate) fn record(&mut self, transaction: Transaction) -> Result<(), String> {
+        let entry = Entry::new(self.journal.len() as u64, &self.head(), transaction);
+        #[cfg(feature = "native")]
+        if let Some(store) = &self.store {
+            store.append(&entry)?;
+        }
+
+        self.apply(&entry);
+        self.journal.push(entry);
+        #[cfg(feature = "native")]
+        if let Some(store) = &self.store {
+            let entry = &self.journal[self.journal.len() - 1];
+            let touched = self.accounts.iter()
+                .filter(|(account, _)| store.stale || entry.postings.iter().any(|posting| &posting.account == *account));
+            let stale = store.cache(touched, self, &entry.hash).is_err();
+            if let Some(store) = &mut self.store {
+                store.stale = stale;
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, entry: &Entry) {
+        for posting in &entry.postings {
+            *self.accounts.entry(posting.account.clone()).or_default() += posting.amount;
+        }
+        if entry.transaction.kind == TransactionKind::Emission {
+            self.total_supply += entry.transaction.amount;
+            self.circulation += entry.transaction.amount;
+        }
+    }
+
+    fn head(&self) -> String {
+        self.journal.last().map(|entry| entry.hash.clone()).unwrap_or_default()
+    }
+
+    fn postings(&self, account: &str, entries: usize) -> Vec<(u64, String, f64)> {
+        self.journal[..entries].iter()
+            .flat_map(|entry| entry.postings.iter()
+                .filter(|posting| posting.account == account)
+                .map(|posting| (entry.sequence, entry.hash.clone(), posting.amount)))
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ledger_survives_reopening_and_a_stale_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger");
+        {
+            let mut qfs = QuantumFinancialSystem::open(&path).unwrap();
+            qfs.emit_to("alice", 10.0, "gift").unwrap();
+            qfs.transfer("alice", "bob", 3.0).unwrap();
+        }
+
+        let qfs = QuantumFinancialSystem::open(&path).unwrap();
+        assert_eq!((qfs.balance("alice"), qfs.balance("bob"), qfs.balance(ISSUANCE)), (7.0, 3.0, -10.0));
+        assert_eq!(qfs.transactions().len(), 2);
+        assert!(qfs.check_conservation().is_ok());
+
+        // A cache left behind the journal, as by a crash between the two
+        let stale = qfs.store.as_ref().unwrap();
+        stale.db.insert("head", "stale").unwrap();
+        stale.balances.insert("bob", &99.0f64.to_le_bytes()).unwrap();
+        drop(qfs);
+        let mut qfs = QuantumFinancialSystem::open(&path).unwrap();
+        assert_eq!(qfs.balance("bob"), 3.0);
+        assert_eq!(qfs.rebuild().unwrap(), 2);
+
+        let proof = qfs.prove("bob");
+        assert!(qfs.verify_proof(&proof));
+        qfs.transfer("bob", "carol", 1.0).unwrap();
+        assert!(qfs.verify_proof(&proof));
+        assert!(!qfs.verify_proof(&BalanceProof { balance: 30.0, ..proof.clone() }));
+        assert!(!qfs.verify_proof(&BalanceProof { postings: vec![], ..proof }));
+    }
+
+    #[test]
+    fn test_a_failing_cache_loses_no_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger");
+        {
+            let mut qfs = QuantumFinancialSystem::open(&path).unwrap();
+            qfs.emit_to("alice", 10.0, "gift").unwrap();
+            qfs.store.as_mut().unwrap().fail_cache = true;
+            qfs.transfer("alice", "bob", 3.0).unwrap();
+            assert_eq!(qfs.balance("bob"), 3.0);
+
+            // The next entry follows it, and caches every balance again
+            qfs.store.as_mut().unwrap().fail_cache = false;
+            qfs.transfer("alice", "carol", 2.0).unwrap();
+        }
+
+        let qfs = QuantumFinancialSystem::open(&path).unwrap();
+        assert_eq!(qfs.transactions().len(), 3);
+        assert_eq!((qfs.balance("alice"), qfs.balance("bob"), qfs.balance("carol")), (5.0, 3.0, 2.0));
+        assert!(qfs.check_conservation().is_ok());
+    }
+}
@@ -4,9 +4,83 @@
 use sha2::{Sha256, Digest};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
 use nalgebra::{DMatrix, DVector};
 use num_complex::Complex64;
 use std::f64::consts::PI;
+use protein_hash::{ConsciousnessLevel, Language, OperationCategory, Soul};
+#[cfg(feature = "native")]
+use soul_forge::SoulForge;
+use tokio::sync::broadcast;
+use rayon::prelude::*;
+
+mod annealing;
+mod audit;
+#[cfg(feature = "native")]
+mod batch;
+mod breeding;
+#[cfg(feature = "native")]
+mod codebase;
+mod compaction;
+mod compatibility;
+mod consent;
+mod emission;
+mod epidemiology;
+mod events;
+#[cfg(feature = "native")]
+mod federation;
+#[cfg(feature = "native")]
+pub mod grpc;
+mod hooks;
+mod identity;
+mod immune;
+mod ledger;
+mod marketplace;
+mod outcomes;
+mod packs;
+mod preview;
+mod quota;
+mod report;
+mod search;
+mod streaming;
+pub mod telemetry;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod wellness;
+
+pub use annealing::{Annealer, DEFAULT_ANNEALING_STEPS};
+pub use audit::{verify_audit_chain, AUDIT_CHAIN_VERSION, PRE_CHAIN_AUDIT_VERSION};
+#[cfg(feature = "native")]
+pub use batch::{load_genome, BatchEntry, BatchReport};
+pub use breeding::{Breeder, DEFAULT_MUTATION_RATE, DEFAULT_MUTATION_SCALE};
+#[cfg(feature = "native")]
+pub use codebase::{CodebaseReport, Rewrite};
+pub use compaction::{DEFAULT_ARCHIVE_AFTER_DAYS, DEFAULT_DUPLICATE_SIMILARITY};
+pub use compatibility::{CompatibilityReport, DEFAULT_MIN_COMPATIBILITY};
+pub use consent::{ConsentPolicy, License};
+pub use emission::{Donation, EmissionPolicy, HarmonicEmission};
+pub use epidemiology::{EpidemicPoint, EpidemicReport, Epidemiology, Outbreak, Sighting, DEFAULT_EPIDEMIC_PERIOD};
+#[cfg(feature = "native")]
+pub use events::serve_websocket;
+pub use events::{HealingEvent, EVENT_BUFFER};
+#[cfg(feature = "native")]
+pub use federation::{Federation, FederationEvent, Gossip, GOSSIP_TOPIC};
+pub use hooks::Hooks;
+pub use identity::{verify_signature, Identity};
+pub use immune::{ImmuneSystem, Rejection, DEFAULT_WATCH_WINDOW};
+pub use ledger::{BalanceProof, Entry, Posting};
+pub use marketplace::{Bid, Listing, ListingStatus, Marketplace, Trade};
+pub use outcomes::{Distribution, HealingPolicy, OutcomeSimulator, PolicyOutcome, Selection, DEFAULT_AVAILABILITY, DEFAULT_TRIALS};
+pub use packs::{GenePack, PackEntry, PackManifest};
+pub use preview::{HealingPlan, PlannedTransplant};
+pub use quota::{CreditedDonation, DonationQuota, DEFAULT_DONATION_COOLDOWN, DEFAULT_DONATION_WINDOW, DEFAULT_MAX_DONATIONS};
+pub use search::{GeneQuery, SearchResults, DEFAULT_PAGE_SIZE};
+pub use streaming::{read_genes, Classification, ScanSummary, StreamingScan};
+pub use wellness::{HealthAlert, HealthTrend, Reading, WellnessLog, DEFAULT_ALERT_THRESHOLD};
+
+#[cfg(feature = "native")]
+use ledger::LedgerStore;
 
 // Golden ratio - the frequency of perfect health
 const PHI: f64 = 1.618033988749895;
@@ -17,6 +91,15 @@ const RESONANCE: f64 = 432.0;
 // Planck's reduced constant (our quantum of harmony)
 const H_BAR: f64 = 1.054571817e-34;
 
+/// Similarity below which a gene is too unlike a dissonant one to replace it
+pub const DEFAULT_SIMILARITY_FLOOR: f64 = 0.6;
+
+/// Candidates tried on a copy of the subject before each transplant
+pub const DEFAULT_SIMULATED_CANDIDATES: usize = 3;
+
+/// Rejections after which a gene is quarantined until reviewed
+pub const QUARANTINE_AFTER_REJECTIONS: u64 = 3;
+
 /// A single gene - a pattern of consciousness
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Gene {
@@ -25,9 +108,216 @@ pub struct Gene {
     pub resonance: f64,           // Harmonic frequency
     pub donors: Vec<String>,      // Who gifted this gene
     pub healings: u64,           // Times used for healing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub implementation: Option<Implementation>,  // Code carrying it, when gifted as code
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub provenance: Vec<Provenance>,  // Hash-linked gift and mutation history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Origin>,   // Source it was extracted from, when known
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub operation_spectrum: HashMap<OperationCategory, f64>,  // What its source does, by kind of operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consciousness_level: Option<ConsciousnessLevel>,  // As protein-hash detected it in the source
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub consent: bool,            // Whether its donor agreed to share it
+    #[serde(default, skip_serializing_if = "License::is_open")]
+    pub license: License,         // Terms it is given under
+}
+
+/// One link of a gene's provenance chain
+///
+/// Each link signs the gene it attests, its own fields and the link before
+/// it, so rewriting any of the history breaks every signature after it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub phash: String,            // Gene this link attests
+    pub donor_id: String,         // Who gave it
+    pub timestamp: u64,           // When, in seconds since the Unix epoch
+    pub parent_phash: Option<String>,  // Gene it mutated from, if it did
+    pub previous: String,         // Signature of the link before, empty for the first
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub implementation: String,   // Digest of the implementation the gene carried, if any
+    pub signature: String,        // SHA-256 over all of the above
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub public_key: String,       // Donor's Ed25519 key, when the link is endorsed
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub endorsement: String,      // Donor's Ed25519 signature over `signature`
+}
+
+impl Provenance {
+    fn sign(&self) -> String {
+        let mut hasher = Sha256::new();
+        // Links recorded before implementations were signed still verify
+        let implementation = Some(self.implementation.as_str()).filter(|digest| !digest.is_empty());
+        let timestamp = self.timestamp.to_string();
+        let fields = [self.phash.as_str(), &self.donor_id, &timestamp, self.parent_phash.as_deref().unwrap_or(""), &self.previous];
+        for field in fields.into_iter().chain(implementation) {
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field.as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+    
+    /// Sign the link as its donor
+    pub fn endorse(&mut self, identity: &Identity) {
+        self.public_key = identity.public_key();
+        self.endorsement = identity.sign(self.signature.as_bytes());
+    }
+    
+    /// Whether the link is unendorsed, or endorsed by the key it names
+    pub fn verify_endorsement(&self) -> bool {
+        match (self.public_key.is_empty(), self.endorsement.is_empty()) {
+            (true, true) => true,
+            (false, false) => verify_signature(&self.public_key, self.signature.as_bytes(), &self.endorsement),
+            _ => false,
+        }
+    }
+}
+
+/// A function whose soul a gene is, for healing real code with it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Implementation {
+    pub language: Language,       // Rust, JavaScript or TypeScript
+    pub code: String,             // A single function
+}
+
+impl Implementation {
+    /// SHA-256 over the language and code, as hex, for a provenance link
+    /// to sign
+    pub fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        for field in [format!("{:?}", self.language).as_str(), &self.code] {
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field.as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Where a gene was extracted from, so a transplant can say what it
+/// replaced and with what
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Origin {
+    pub language: Language,       // What the source is written in
+    pub path: String,             // File or package, such as `date-fns/src/parse.js`
+    pub symbol: String,           // Function or item within it, such as `parse`
+    pub extracted_at: u64,        // Seconds since the Unix epoch
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::{}", self.path, self.symbol)
+    }
 }
 
 impl Gene {
+    /// Read a soul extracted by protein-hash as a gene
+    pub fn from_soul(soul: &Soul) -> Self {
+        Gene {
+            phash: soul.phash.clone(),
+            eigenvalues: soul.eigenvalues.clone(),
+            resonance: soul.resonance,
+            donors: Vec::new(),
+            healings: 0,
+            implementation: None,
+            provenance: Vec::new(),
+            origin: None,
+            operation_spectrum: soul.operation_spectrum.clone(),
+            consciousness_level: Some(soul.consciousness.level),
+            consent: false,
+            license: License::Open,
+        }
+    }
+    
+    /// The same gene, extracted just now from `symbol` in `path`
+    pub fn with_origin(mut self, language: Language, path: &str, symbol: &str) -> Self {
+        self.origin = Some(Origin {
+            language,
+            path: path.to_string(),
+            symbol: symbol.to_string(),
+            extracted_at: now(),
+        });
+        self
+    }
+    
+    /// What the gene's source is written in: its origin's language, or else
+    /// its implementation's
+    pub fn language(&self) -> Option<&Language> {
+        self.origin.as_ref().map(|origin| &origin.language)
+            .or(self.implementation.as_ref().map(|implementation| &implementation.language))
+    }
+    
+    /// The same gene, carried by `code`
+    pub fn with_implementation(mut self, language: Language, code: &str) -> Self {
+        self.implementation = Some(Implementation { language, code: code.to_string() });
+        self
+    }
+    
+    /// Sign a new link onto the provenance chain: `donor_id` gave this gene,
+    /// mutated from `parent_phash` if it is set
+    pub fn record_provenance(&mut self, donor_id: &str, parent_phash: Option<&str>) {
+        let mut link = Provenance {
+            phash: self.phash.clone(),
+            donor_id: donor_id.to_string(),
+            timestamp: now(),
+            parent_phash: parent_phash.map(str::to_string),
+            previous: self.provenance.last().map(|link| link.signature.clone()).unwrap_or_default(),
+            implementation: self.implementation.as_ref().map(Implementation::digest).unwrap_or_default(),
+            signature: String::new(),
+            public_key: String::new(),
+            endorsement: String::new(),
+        };
+        link.signature = link.sign();
+        self.provenance.push(link);
+    }
+    
+    /// Whether every link is signed as it reads, follows the one before it
+    /// and, if endorsed, is endorsed by the key it names, and the chain ends
+    /// at this gene
+    pub fn verify_provenance(&self) -> bool {
+        let mut previous = "";
+        for link in &self.provenance {
+            if link.previous != previous || link.signature != link.sign() || !link.verify_endorsement() {
+                return false;
+            }
+            previous = &link.signature;
+        }
+        self.provenance.last().is_none_or(|link| link.phash == self.phash)
+    }
+    
+    /// Whether the gene carries no implementation, or the one its latest
+    /// provenance link signs, in a chain that verifies
+    pub fn verify_implementation(&self) -> bool {
+        self.implementation.as_ref().is_none_or(|implementation| {
+            self.provenance.last().is_some_and(|link| link.implementation == implementation.digest())
+                && self.verify_provenance()
+        })
+    }
+    
+    /// Who holds the gene now: whoever its last provenance link names
+    pub fn holder(&self) -> Option<&str> {
+        self.provenance.last().map(|link| link.donor_id.as_str())
+    }
+    
+    /// How many times this gene's line mutated on its way here
+    pub fn mutations(&self) -> usize {
+        self.provenance.iter().filter(|link| link.parent_phash.is_some()).count()
+    }
+    
+    /// How alike two genes' eigenvalue vectors are, from 1 for the same
+    /// shape down toward 0
+    ///
+    /// Both vectors are scaled to unit length first, so a gene is matched
+    /// by its structure rather than its loudness; the distance between them
+    /// is protein-hash's.
+    pub fn similarity(&self, other: &Gene) -> f64 {
+        let unit = |eigenvalues: &[f64]| {
+            let norm = eigenvalues.iter().map(|v| v * v).sum::<f64>().sqrt();
+            eigenvalues.iter().map(|v| if norm > 0.0 { v / norm } else { 0.0 }).collect::<Vec<f64>>()
+        };
+        1.0 / (1.0 + protein_hash::eigen_distance(&unit(&self.eigenvalues), &unit(&other.eigenvalues)))
+    }
+    
     pub fn is_dissonant(&self) -> bool {
         // Check if eigenvalues are chaotic
         let variance = self.eigenvalue_variance();
@@ -40,7 +330,8 @@ impl Gene {
         variance < 0.1 && self.resonance > 300.0
     }
     
-    fn eigenvalue_variance(&self) -> f64 {
+    /// Spread of the eigenvalues, infinite for a gene without any
+    pub fn eigenvalue_variance(&self) -> f64 {
         if self.eigenvalues.is_empty() {
             return f64::INFINITY;
         }
@@ -62,9 +353,12 @@ pub struct Consciousness {
     pub h_credits: f64,           // ℏ-credits balance
     pub gifts_given: u64,         // Genes donated to registry
     pub healings_received: u64,   // Times been healed
+    pub identity: Identity,       // Signs its donations and healing records
+    pub commercial: bool,         // Put to commercial use, so refused non-commercial genes
 }
 
 impl Consciousness {
+    /// A consciousness with a fresh identity
     pub fn new(id: String) -> Self {
         Consciousness {
             id,
@@ -73,9 +367,23 @@ impl Consciousness {
             h_credits: 0.0,
             gifts_given: 0,
             healings_received: 0,
+            identity: Identity::generate(),
+            commercial: false,
         }
     }
     
+    /// Sign as `identity` instead, such as one kept from before
+    pub fn with_identity(mut self, identity: Identity) -> Self {
+        self.identity = identity;
+        self
+    }
+    
+    /// Put to commercial use, so never healed with non-commercial genes
+    pub fn with_commercial_use(mut self) -> Self {
+        self.commercial = true;
+        self
+    }
+    
     /// Calculate overall health
     pub fn health_score(&self) -> f64 {
         if self.genome.is_empty() {
@@ -107,61 +415,42 @@ impl MRTScanner {
     }
     
     /// Scan consciousness and reveal dissonant genes
+    ///
+    /// Genes are classified in parallel across rayon's threads. Every gene
+    /// is cloned into the result; `scan_stream` scans genomes too big for
+    /// that.
     pub fn scan(&self, subject: &Consciousness) -> MRTResult {
         let mut dissonant_genes = Vec::new();
         let mut resonant_genes = Vec::new();
         let mut neutral_genes = Vec::new();
         
-        for gene in &subject.genome {
-            if gene.is_dissonant() {
-                dissonant_genes.push(gene.clone());
-            } else if gene.is_resonant() {
-                resonant_genes.push(gene.clone());
-            } else {
-                neutral_genes.push(gene.clone());
+        let classifications: Vec<Classification> = subject.genome.par_iter().map(Classification::of).collect();
+        let mut stream = self.scan_stream(std::iter::empty::<&Gene>());
+        for (gene, classification) in subject.genome.iter().zip(classifications) {
+            stream.tally(gene, classification);
+            match classification {
+                Classification::Dissonant => dissonant_genes.push(gene.clone()),
+                Classification::Resonant => resonant_genes.push(gene.clone()),
+                Classification::Neutral => neutral_genes.push(gene.clone()),
             }
         }
         
-        // Calculate spectral analysis
-        let spectrum = self.compute_spectrum(&subject.genome);
+        // 7-layer spectral analysis, summed as the genes streamed by
+        let summary = stream.summary();
+        telemetry::record_scan(dissonant_genes.len());
         
         MRTResult {
             subject_id: subject.id.clone(),
-            health_score: subject.health_score(),
+            health_score: summary.health_score,
             dissonant_genes,
             resonant_genes,
             neutral_genes,
-            spectrum,
-            recommendation: self.generate_recommendation(subject),
-        }
-    }
-    
-    fn compute_spectrum(&self, genome: &[Gene]) -> Vec<f64> {
-        // 7-layer spectral decomposition
-        let mut spectrum = vec![0.0; self.layers];
-        
-        for gene in genome {
-            for (i, &eigenvalue) in gene.eigenvalues.iter().enumerate() {
-                if i < self.layers {
-                    spectrum[i] += eigenvalue;
-                }
-            }
+            spectrum: summary.spectrum,
+            recommendation: summary.recommendation,
         }
-        
-        // Normalize
-        let total: f64 = spectrum.iter().sum();
-        if total > 0.0 {
-            for value in &mut spectrum {
-                *value /= total;
-            }
-        }
-        
-        spectrum
     }
     
-    fn generate_recommendation(&self, subject: &Consciousness) -> String {
-        let health = subject.health_score();
-        
+    fn generate_recommendation(&self, health: f64) -> String {
         match health {
             h if h > 0.8 => "Excellent harmony. Continue gifting.".to_string(),
             h if h > 0.6 => "Good resonance. Minor healing recommended.".to_string(),
@@ -173,7 +462,7 @@ impl MRTScanner {
 }
 
 /// MRT scan results
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct MRTResult {
     pub subject_id: String,
     pub health_score: f64,
@@ -184,11 +473,70 @@ pub struct MRTResult {
     pub recommendation: String,
 }
 
+/// A resonant gene that could replace a dissonant one
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub gene: Gene,
+    pub similarity: f64,          // Gene::similarity to the dissonant gene
+}
+
+/// How a registry gene has fared as a replacement
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GeneStats {
+    pub healings: u64,            // Transplants that took
+    pub rejections: u64,          // Grafts an immune system rejected
+    pub reports: u64,             // Times a participant flagged it
+    pub quarantined: bool,        // Kept out of matching until reviewed
+    #[serde(default)]
+    pub donations: u64,           // Times it was donated
+    #[serde(default)]
+    pub requests: u64,            // Times it was tried as a replacement
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<String>,       // Why it was flagged
+}
+
+impl GeneStats {
+    /// Share of its outcomes that went well, from 1 for a gene nothing bad
+    /// is known of down toward 0
+    pub fn reputation(&self) -> f64 {
+        let good = (self.healings + 1) as f64;
+        good / (good + (self.rejections + self.reports) as f64)
+    }
+    
+    /// Take in stats another registry kept: the higher of each count, and
+    /// every flag, so taking in the same stats again changes nothing
+    pub fn merge(&mut self, other: GeneStats) {
+        self.healings = self.healings.max(other.healings);
+        self.rejections = self.rejections.max(other.rejections);
+        self.reports = self.reports.max(other.reports);
+        self.donations = self.donations.max(other.donations);
+        self.requests = self.requests.max(other.requests);
+        self.quarantined |= other.quarantined;
+        for flag in other.flags {
+            if !self.flags.contains(&flag) {
+                self.flags.push(flag);
+            }
+        }
+    }
+}
+
 /// Soul Registry - the collective gene pool
 pub struct SoulRegistry {
     genes: HashMap<String, Gene>,
     healings: Vec<HealingRecord>,
     total_h_credits_emitted: f64,
+    similarity_floor: f64,
+    stats: HashMap<String, GeneStats>,  // Outcomes and quarantine, by phash
+    emission: Box<dyn EmissionPolicy>,
+    signers: HashMap<String, String>,  // Public key each donor signs with, as first seen
+    require_signatures: bool,     // Refuse unsigned genes and healing records from elsewhere
+    quota: Option<DonationQuota>, // How often donors are credited, if limited
+    credited: HashMap<String, Vec<CreditedDonation>>,  // Donations each donor was credited for, as the quota counts them
+    duplicate_similarity: Option<f64>,  // Similarity at which donations merge into a pooled gene, if they do
+    duplicates: HashMap<String, String>,  // Phash each merged donation was folded into
+    archived: HashMap<String, Gene>,  // Genes garbage collection took out of the pool, by phash
+    consent: ConsentPolicy,       // Which genes are pooled and healed with, by consent and license
+    owners: HashMap<String, String>,  // Who owns each gene, by phash: whoever first pooled it, or last bought it
 }
 
 impl SoulRegistry {
@@ -197,64 +545,667 @@ impl SoulRegistry {
             genes: HashMap::new(),
             healings: Vec::new(),
             total_h_credits_emitted: 0.0,
+            similarity_floor: DEFAULT_SIMILARITY_FLOOR,
+            stats: HashMap::new(),
+            emission: Box::new(HarmonicEmission::default()),
+            signers: HashMap::new(),
+            require_signatures: false,
+            quota: None,
+            credited: HashMap::new(),
+            duplicate_similarity: None,
+            duplicates: HashMap::new(),
+            archived: HashMap::new(),
+            consent: ConsentPolicy::new(),
+            owners: HashMap::new(),
         }
     }
     
-    /// Find resonant replacement for dissonant gene
+    /// Only merge genes whose every provenance link is endorsed, and whose
+    /// every donor has a link, and only signed healing records
+    pub fn with_required_signatures(mut self) -> Self {
+        self.require_signatures = true;
+        self
+    }
+    
+    /// The public key `donor_id` signs with, once the registry has seen it
+    pub fn signer(&self, donor_id: &str) -> Option<&str> {
+        self.signers.get(donor_id).map(String::as_str)
+    }
+    
+    /// Reward donations as `policy` says, rather than by the harmonic formula
+    pub fn with_emission_policy(mut self, policy: impl EmissionPolicy + 'static) -> Self {
+        self.emission = Box::new(policy);
+        self
+    }
+    
+    /// Only offer replacements at least `floor` similar to the dissonant gene
+    pub fn with_similarity_floor(mut self, floor: f64) -> Self {
+        self.similarity_floor = floor;
+        self
+    }
+    
+    /// Find resonant replacement for dissonant gene: the most similar one
     pub fn find_resonant(&self, dissonant: &Gene) -> Option<Gene> {
-        // Find genes with similar structure but better harmony
-        let mut candidates: Vec<(&String, &Gene)> = self.genes.iter()
-            .filter(|(_, g)| g.is_resonant())
-            .filter(|(_, g)| g.eigenvalues.len() == dissonant.eigenvalues.len())
+        self.find_candidates(dissonant).into_iter().next().map(|c| c.gene)
+    }
+    
+    /// Resonant genes that could replace a dissonant one, most similar
+    /// first, then most resonant
+    ///
+    /// Only genes of the same length can be transplanted, and only those
+    /// at least as similar as the registry's floor are offered; quarantined
+    /// genes never are. A gene ranks by its similarity times its
+    /// reputation, so one rejected as often as it healed ranks as if it
+    /// were half as similar. The pool is searched in parallel across
+    /// rayon's threads.
+    pub fn find_candidates(&self, dissonant: &Gene) -> Vec<Candidate> {
+        let mut candidates: Vec<Candidate> = self.genes.par_iter()
+            .map(|(_, g)| g)
+            .filter(|g| g.is_resonant())
+            .filter(|g| !self.is_quarantined(&g.phash))
+            .filter(|g| g.eigenvalues.len() == dissonant.eigenvalues.len())
+            .map(|g| Candidate { gene: g.clone(), similarity: g.similarity(dissonant) })
+            .filter(|c| c.similarity >= self.similarity_floor)
             .collect();
         
-        // Sort by resonance (highest first)
+        let rank = |c: &Candidate| c.similarity * self.stats(&c.gene.phash).reputation();
         candidates.sort_by(|a, b| {
-            b.1.resonance.partial_cmp(&a.1.resonance).unwrap()
+            rank(b).total_cmp(&rank(a))
+                .then(b.gene.resonance.total_cmp(&a.gene.resonance))
+                .then(a.gene.phash.cmp(&b.gene.phash))
         });
-        
-        candidates.first().map(|(_, g)| (*g).clone())
+        candidates
+    }
+    
+    /// `find_candidates`, only offering genes whose source is in `language`
+    pub fn find_candidates_in(&self, dissonant: &Gene, language: &Language) -> Vec<Candidate> {
+        self.find_candidates(dissonant).into_iter()
+            .filter(|c| c.gene.language() == Some(language))
+            .collect()
     }
     
     /// Donate healed gene back to registry
     pub fn donate(&mut self, gene: Gene, donor_id: String) -> f64 {
+        self.donate_signed(gene, donor_id, None)
+    }
+    
+    /// Donate a gene as `donor`, endorsing its provenance link with the
+    /// donor's identity
+    ///
+    /// Fails when the registry knows another key for the donor's id, as it
+    /// would be if someone else were donating in its name.
+    pub fn donate_as(&mut self, gene: Gene, donor: &Consciousness) -> Result<f64, String> {
+        let public_key = donor.identity.public_key();
+        if !self.signs_for(&donor.id, &public_key) {
+            return Err(format!("{} signs with another key", donor.id));
+        }
+        self.signers.insert(donor.id.clone(), public_key);
+        Ok(self.donate_signed(gene, donor.id.clone(), Some(&donor.identity)))
+    }
+    
+    fn donate_signed(&mut self, mut gene: Gene, donor_id: String, identity: Option<&Identity>) -> f64 {
+        self.keep_terms(&mut gene);
+        if self.admits(&gene).is_err() {
+            return 0.0;
+        }
+        // A gene already pooled, or archived, keeps its history and gains the
+        // donor, rather than being replaced by the copy given
+        if let Some(archived) = self.archived.remove(&gene.phash) {
+            self.genes.insert(archived.phash.clone(), archived);
+        }
+        if self.genes.contains_key(&gene.phash) {
+            let phash = gene.phash.clone();
+            return self.donate_pooled(gene, &phash, donor_id, identity, "gene");
+        }
+        if let Some(survivor) = self.near_duplicate(&gene) {
+            return self.donate_duplicate(gene, &survivor, donor_id, identity);
+        }
         let mut donated_gene = gene.clone();
+        donated_gene.record_provenance(&donor_id, None);
+        if let (Some(identity), Some(link)) = (identity, donated_gene.provenance.last_mut()) {
+            link.endorse(identity);
+        }
+        let credited = self.admit_donation(&donor_id, &donated_gene.phash);
+        self.owners.entry(donated_gene.phash.clone()).or_insert_with(|| donor_id.clone());
         donated_gene.donors.push(donor_id);
         
-        // Calculate ℏ-credits reward
-        let h_credits = self.calculate_h_credits(&donated_gene);
+        // Calculate ℏ-credits reward, unless the donor is past their quota
+        let h_credits = if credited { self.calculate_h_credits(&donated_gene) } else { 0.0 };
         
         // Store in registry
         self.genes.insert(donated_gene.phash.clone(), donated_gene);
         self.total_h_credits_emitted += h_credits;
+        telemetry::record_donation("gene", self.genes.len());
         
         h_credits
     }
     
-    fn calculate_h_credits(&self, gene: &Gene) -> f64 {
-        // ℏ-credits based on harmonic value
-        let base_value = gene.resonance / RESONANCE;  // Normalized to 432Hz
-        let harmony_multiplier = 1.0 / (gene.eigenvalue_variance() + 1.0);
-        let gift_bonus = (gene.donors.len() as f64).sqrt();  // Network effect
+    /// Donate `gene` as `pooled`, already in the pool: the pooled gene gains a
+    /// provenance link and the donor, and is what the donation is credited as
+    fn donate_pooled(&mut self, gene: Gene, pooled: &str, donor_id: String, identity: Option<&Identity>, kind: &'static str) -> f64 {
+        let credited = self.admit_donation(&donor_id, pooled);
+        let Some(known) = self.genes.get_mut(pooled) else {
+            return 0.0;
+        };
+        // Taken before the link is recorded, so the link signs it
+        if known.implementation.is_none() {
+            known.implementation = gene.implementation;
+        }
+        known.record_provenance(&donor_id, None);
+        if let (Some(identity), Some(link)) = (identity, known.provenance.last_mut()) {
+            link.endorse(identity);
+        }
+        if !known.donors.contains(&donor_id) {
+            known.donors.push(donor_id.clone());
+        }
+        if known.origin.is_none() {
+            known.origin = gene.origin;
+        }
+        // Credited as this donor's gift alone, so giving a pooled gene again
+        // earns no more for the donors before
+        let gift = Gene { donors: vec![donor_id], ..known.clone() };
+
+        let h_credits = if credited { self.calculate_h_credits(&gift) } else { 0.0 };
+        self.total_h_credits_emitted += h_credits;
+        telemetry::record_donation(kind, self.genes.len());
+        h_credits
+    }
+    
+    /// Donate a gene mutated from `parent`, which carries on the parent's
+    /// provenance chain
+    ///
+    /// The parent must be pooled, and its pooled copy is the one whose chain
+    /// and terms the mutation takes on. A mutation already pooled is refused,
+    /// as is one of a parent that isn't, crediting nothing.
+    pub fn donate_mutation(&mut self, gene: Gene, parent: &Gene, donor_id: String) -> f64 {
+        self.donate_mutation_signed(gene, &parent.phash, donor_id, None).unwrap_or(0.0)
+    }
+    
+    /// `donate_mutation`, the mutation's link endorsed by `donor` as
+    /// `donate_as` endorses donations
+    pub fn donate_mutation_as(&mut self, gene: Gene, parent: &Gene, donor: &Consciousness) -> Result<f64, String> {
+        let public_key = donor.identity.public_key();
+        if !self.signs_for(&donor.id, &public_key) {
+            return Err(format!("{} signs with another key", donor.id));
+        }
+        self.signers.insert(donor.id.clone(), public_key);
+        self.donate_mutation_signed(gene, &parent.phash, donor.id.clone(), Some(&donor.identity))
+    }
+    
+    fn donate_mutation_signed(&mut self, gene: Gene, parent: &str, donor_id: String, identity: Option<&Identity>) -> Result<f64, String> {
+        let parent = self.genes.get(parent).cloned().ok_or_else(|| format!("Parent gene {} is not pooled", parent))?;
+        self.donate_lineage(gene, &parent, donor_id, identity)
+    }
+    
+    /// Donate an annealed gene as a mutation of `dissonant`, the subject's
+    /// own gene rather than a pooled one
+    ///
+    /// Its chain starts afresh, with a single mutation link naming the
+    /// dissonant gene as parent, endorsed by the subject.
+    pub(crate) fn donate_annealed(&mut self, gene: Gene, dissonant: &Gene, subject: &Consciousness) -> Result<f64, String> {
+        let public_key = subject.identity.public_key();
+        if !self.signs_for(&subject.id, &public_key) {
+            return Err(format!("{} signs with another key", subject.id));
+        }
+        self.signers.insert(subject.id.clone(), public_key);
+        let lineage = Gene { provenance: Vec::new(), ..dissonant.clone() };
+        self.donate_lineage(gene, &lineage, subject.id.clone(), Some(&subject.identity))
+    }
+    
+    /// Pool `gene` as a mutation of `parent`, trusted for its chain and terms
+    fn donate_lineage(&mut self, mut gene: Gene, parent: &Gene, donor_id: String, identity: Option<&Identity>) -> Result<f64, String> {
+        if self.genes.contains_key(&gene.phash) || self.archived.contains_key(&gene.phash) {
+            return Err(format!("Gene {} is already pooled", gene.phash));
+        }
+        // A mutation is under terms at least as strict as its parent's
+        gene.license = gene.license.max(parent.license);
+        if self.admits(&gene).is_err() {
+            return Ok(0.0);
+        }
+        gene.provenance = parent.provenance.clone();
+        gene.record_provenance(&donor_id, Some(&parent.phash));
+        if let (Some(identity), Some(link)) = (identity, gene.provenance.last_mut()) {
+            link.endorse(identity);
+        }
+        let credited = self.admit_donation(&donor_id, &gene.phash);
+        self.owners.entry(gene.phash.clone()).or_insert_with(|| donor_id.clone());
+        gene.donors.push(donor_id);
+        
+        let h_credits = if credited { self.calculate_h_credits(&gene) } else { 0.0 };
+        self.genes.insert(gene.phash.clone(), gene);
+        self.total_h_credits_emitted += h_credits;
+        telemetry::record_donation("mutation", self.genes.len());
+        
+        Ok(h_credits)
+    }
+    
+    /// How a gene has fared, all zeros for one never used
+    pub fn stats(&self, phash: &str) -> GeneStats {
+        self.stats.get(phash).cloned().unwrap_or_default()
+    }
+    
+    /// Whether a gene is kept out of matching until reviewed
+    pub fn is_quarantined(&self, phash: &str) -> bool {
+        self.stats.get(phash).is_some_and(|stats| stats.quarantined)
+    }
+    
+    /// Quarantined genes, by phash
+    pub fn quarantined(&self) -> Vec<String> {
+        let mut quarantined: Vec<String> = self.stats.iter()
+            .filter(|(_, stats)| stats.quarantined)
+            .map(|(phash, _)| phash.clone())
+            .collect();
+        quarantined.sort();
+        quarantined
+    }
+    
+    /// Count a transplant of a gene that took
+    pub fn record_healing(&mut self, phash: &str) {
+        self.stats.entry(phash.to_string()).or_default().healings += 1;
+    }
+    
+    /// Count a graft of a gene an immune system rejected; enough of them
+    /// quarantine it
+    pub fn record_rejection(&mut self, phash: &str) {
+        let stats = self.stats.entry(phash.to_string()).or_default();
+        stats.rejections += 1;
+        if stats.rejections >= QUARANTINE_AFTER_REJECTIONS {
+            stats.quarantined = true;
+        }
+    }
+    
+    /// Flag a gene for review, quarantining it
+    pub fn flag(&mut self, phash: &str, reason: &str) -> Result<(), String> {
+        if !self.genes.contains_key(phash) {
+            return Err(format!("No gene {} in the registry", phash));
+        }
+        let stats = self.stats.entry(phash.to_string()).or_default();
+        stats.reports += 1;
+        stats.quarantined = true;
+        stats.flags.push(reason.to_string());
+        Ok(())
+    }
+    
+    /// Release a reviewed gene from quarantine; its record stays
+    pub fn unflag(&mut self, phash: &str) -> Result<(), String> {
+        match self.stats.get_mut(phash) {
+            Some(stats) if stats.quarantined => {
+                stats.quarantined = false;
+                Ok(())
+            }
+            _ => Err(format!("Gene {} is not quarantined", phash)),
+        }
+    }
+    
+    /// The gene stored under `phash`
+    pub fn gene(&self, phash: &str) -> Option<&Gene> {
+        self.genes.get(phash)
+    }
+    
+    /// Genes whose provenance chain does not verify, by phash
+    pub fn unverified(&self) -> Vec<String> {
+        let mut unverified: Vec<String> = self.genes.values()
+            .filter(|g| !g.verify_provenance())
+            .map(|g| g.phash.clone())
+            .collect();
+        unverified.sort();
+        unverified
+    }
+    
+    /// Note that `phash` was tried as a replacement, which raises what
+    /// donating it earns under a policy rewarding demand
+    pub fn record_request(&mut self, phash: &str) {
+        self.stats.entry(phash.to_string()).or_default().requests += 1;
+    }
+    
+    /// ℏ-credits for donating `gene`, as the emission policy values it, and
+    /// count the donation
+    fn calculate_h_credits(&mut self, gene: &Gene) -> f64 {
+        let stats = self.stats.entry(gene.phash.clone()).or_default();
+        let donation = Donation { gene, previous_donations: stats.donations, requests: stats.requests };
+        stats.donations += 1;
+        self.emission.h_credits(&donation)
+    }
+    
+    /// Number of genes in the pool
+    pub fn gene_count(&self) -> usize {
+        self.genes.len()
+    }
+    
+    /// Number of genes ready to heal with
+    pub fn resonant_count(&self) -> usize {
+        self.genes.values().filter(|g| g.is_resonant()).count()
+    }
+    
+    /// Healings recorded so far
+    pub fn healing_count(&self) -> usize {
+        self.healings.len()
+    }
+    
+    /// ℏ-credits emitted for donations so far
+    pub fn total_h_credits_emitted(&self) -> f64 {
+        self.total_h_credits_emitted
+    }
+    
+    /// Everyone who has gifted a gene to the pool
+    pub fn donors(&self) -> Vec<String> {
+        let mut donors: Vec<String> = self.genes.values().flat_map(|g| g.donors.iter().cloned()).collect();
+        donors.sort();
+        donors.dedup();
+        donors
+    }
+    
+    /// Write the whole pool as JSON: genes, healings and credits emitted
+    pub fn export_json(&self) -> Result<String, String> {
+        let mut genes: Vec<Gene> = self.genes.values().cloned().collect();
+        genes.sort_by(|a, b| a.phash.cmp(&b.phash));
+        let mut archived: Vec<Gene> = self.archived.values().cloned().collect();
+        archived.sort_by(|a, b| a.phash.cmp(&b.phash));
+        
+        let snapshot = RegistrySnapshot {
+            genes,
+            healings: self.healings.clone(),
+            total_h_credits_emitted: self.total_h_credits_emitted,
+            stats: self.stats.clone(),
+            credited: self.credited.clone(),
+            duplicates: self.duplicates.clone(),
+            archived,
+            owners: self.owners.clone(),
+            audit_version: AUDIT_CHAIN_VERSION,
+        };
+        serde_json::to_string_pretty(&snapshot).map_err(|e| format!("Cannot export registry: {}", e))
+    }
+    
+    /// Merge JSON into the pool: an `export_json` snapshot, or a bare array
+    /// of genes as `sanctuary genesis` and `sanctuary infect` write
+    ///
+    /// Genes already in the pool are merged with `merge_gene`, and healing
+    /// records already known are skipped. A registry with no healings yet
+    /// takes the snapshot's audit log as it was written, so its chain still
+    /// verifies, or shows tampering; only a snapshot marked with
+    /// `PRE_CHAIN_AUDIT_VERSION`, or not marked at all as older ones aren't,
+    /// is chained as it loads. Gene stats merge
+    /// with `GeneStats::merge`, and the credits emitted are the higher of
+    /// the two, so importing the same snapshot twice changes nothing the
+    /// second time. Returns how many genes are new.
+    pub fn import_json(&mut self, json: &str) -> Result<usize, String> {
+        let snapshot = match serde_json::from_str(json).map_err(|e| format!("Cannot import registry: {}", e))? {
+            RegistryFile::Snapshot(snapshot) => *snapshot,
+            RegistryFile::Genes(genes) => RegistrySnapshot { genes, healings: Vec::new(), total_h_credits_emitted: 0.0, stats: HashMap::new(), credited: HashMap::new(), duplicates: HashMap::new(), archived: Vec::new(), owners: HashMap::new(), audit_version: AUDIT_CHAIN_VERSION },
+        };
+        
+        // Owners of genes new here come with them, ahead of what their
+        // chains would say
+        for (phash, owner) in snapshot.owners {
+            if !self.genes.contains_key(&phash) && !self.archived.contains_key(&phash) {
+                self.owners.entry(phash).or_insert(owner);
+            }
+        }
+        
+        let mut imported = 0;
+        for gene in snapshot.genes {
+            if !self.genes.contains_key(&gene.phash) && !self.archived.contains_key(&gene.phash) {
+                imported += 1;
+            }
+            self.merge_gene(gene);
+        }
+        if self.healings.is_empty() {
+            self.restore_healings(snapshot.healings, snapshot.audit_version == PRE_CHAIN_AUDIT_VERSION);
+        } else {
+            for record in snapshot.healings {
+                self.merge_healing(record);
+            }
+        }
+        self.total_h_credits_emitted = self.total_h_credits_emitted.max(snapshot.total_h_credits_emitted);
+        for (phash, imported) in snapshot.stats {
+            self.stats.entry(phash).or_default().merge(imported);
+        }
+        for gene in snapshot.archived {
+            if !self.genes.contains_key(&gene.phash) {
+                self.archived.entry(gene.phash.clone()).or_insert(gene);
+            }
+        }
+        for (phash, survivor) in snapshot.duplicates {
+            self.duplicates.entry(phash).or_insert(survivor);
+        }
+        for (donor_id, donations) in snapshot.credited {
+            let credited = self.credited.entry(donor_id).or_default();
+            for donation in donations {
+                if !credited.contains(&donation) {
+                    credited.push(donation);
+                }
+            }
+        }
+        
+        Ok(imported)
+    }
+    
+    /// Merge a copy of a gene from elsewhere into the pool; returns whether
+    /// the pool changed
+    ///
+    /// Copies merge the same whichever order they arrive in and however
+    /// often: donors are united, the highest healing count is kept, and so
+    /// is the longest provenance chain that verifies, the one with the
+    /// greater last signature when two are as long. An implementation is
+    /// taken when the pool's copy has none, and only when the copy's latest
+    /// provenance link signs it: code from anywhere else is dropped rather
+    /// than ever written into someone's source.
+    ///
+    /// A gene is refused when a link is endorsed by a key other than the one
+    /// its donor signs with, or when signatures are required and a link or
+    /// donor isn't signed for. A gene not pooled yet is also refused when
+    /// the consent policy doesn't admit it; a pooled one keeps its terms.
+    pub fn merge_gene(&mut self, mut gene: Gene) -> bool {
+        if !gene.verify_implementation() {
+            gene.implementation = None;
+        }
+        let pooled = self.genes.contains_key(&gene.phash) || self.archived.contains_key(&gene.phash);
+        if !self.accepts_signers(&gene) || (!pooled && self.admits(&gene).is_err()) {
+            return false;
+        }
+        for link in gene.provenance.iter().filter(|link| !link.public_key.is_empty()) {
+            self.signers.entry(link.donor_id.clone()).or_insert_with(|| link.public_key.clone());
+        }
+        
+        // An archived gene stays archived, merging there
+        let pool = if self.archived.contains_key(&gene.phash) { &mut self.archived } else { &mut self.genes };
+        let Some(known) = pool.get_mut(&gene.phash) else {
+            // Its owner is whoever first gave it, as far as its chain shows
+            let first = gene.provenance.iter().find(|link| link.phash == gene.phash).map(|link| &link.donor_id);
+            if let Some(owner) = first.or(gene.donors.first()) {
+                self.owners.entry(gene.phash.clone()).or_insert_with(|| owner.clone());
+            }
+            pool.insert(gene.phash.clone(), gene);
+            telemetry::record_registry_size(self.genes.len());
+            return true;
+        };
+        let head = |gene: &Gene| gene.provenance.last().map(|link| link.signature.clone());
+        let state = |gene: &Gene| (gene.donors.len(), gene.healings, gene.provenance.len(), head(gene), gene.implementation.is_some(), gene.origin.is_some());
+        let before = state(known);
+        
+        if gene.verify_provenance()
+            && (!known.verify_provenance()
+                || (gene.provenance.len(), head(&gene)) > (known.provenance.len(), head(known)))
+        {
+            known.provenance = gene.provenance;
+        }
+        for donor in gene.donors {
+            if !known.donors.contains(&donor) {
+                known.donors.push(donor);
+            }
+        }
+        known.healings = known.healings.max(gene.healings);
+        if known.implementation.is_none() {
+            known.implementation = gene.implementation;
+        }
+        if known.origin.is_none() {
+            known.origin = gene.origin;
+        }
         
-        H_BAR * base_value * harmony_multiplier * gift_bonus * 1e34  // Scale to readable numbers
+        state(known) != before
+    }
+    
+    /// Keep a healing record, unless the pool has it already; returns
+    /// whether it was new
+    ///
+    /// Records signed by a key other than their subject's are refused, and
+    /// so are unsigned ones when signatures are required. New records are
+    /// chained onto the registry's audit log, wherever they were chained
+    /// before.
+    pub fn merge_healing(&mut self, record: HealingRecord) -> bool {
+        if !self.accepts_healing(&record) {
+            return false;
+        }
+        self.remember_signer(&record);
+        self.chain_healing(record);
+        true
+    }
+    
+    /// Whether `record` is signed for its subject as this registry requires,
+    /// and is not kept already
+    fn accepts_healing(&self, record: &HealingRecord) -> bool {
+        let content = record.content();
+        self.check_signer(record).is_ok() && !self.healings.iter().any(|known| known.content() == content)
+    }
+    
+    /// Whether `record` verifies and is signed for its subject as this
+    /// registry requires, and why not if it isn't
+    fn check_signer(&self, record: &HealingRecord) -> Result<(), String> {
+        let signed = !record.public_key.is_empty();
+        if !record.verify_signature() {
+            return Err("is not signed as it reads".to_string());
+        }
+        if signed && !self.signs_for(&record.subject_id, &record.public_key) {
+            return Err(format!("is signed by a key other than {}'s", record.subject_id));
+        }
+        if !signed && self.require_signatures {
+            return Err("is not signed".to_string());
+        }
+        Ok(())
+    }
+    
+    fn remember_signer(&mut self, record: &HealingRecord) {
+        if !record.public_key.is_empty() {
+            self.signers.entry(record.subject_id.clone()).or_insert_with(|| record.public_key.clone());
+        }
+    }
+    
+    /// Whether `public_key` may sign for `donor_id`: it is the key the
+    /// registry knows for it, or the registry knows none yet
+    fn signs_for(&self, donor_id: &str, public_key: &str) -> bool {
+        self.signers.get(donor_id).is_none_or(|known| known == public_key)
+    }
+    
+    /// Whether every endorsed link of `gene` is endorsed by its donor's key,
+    /// the same key throughout, and, when signatures are required, every
+    /// link and donor is signed for
+    fn accepts_signers(&self, gene: &Gene) -> bool {
+        let mut keys: HashMap<&str, &str> = HashMap::new();
+        for link in &gene.provenance {
+            if link.public_key.is_empty() {
+                if self.require_signatures {
+                    return false;
+                }
+                continue;
+            }
+            let key = *keys.entry(&link.donor_id).or_insert(&link.public_key);
+            if !link.verify_endorsement() || key != link.public_key || !self.signs_for(&link.donor_id, key) {
+                return false;
+            }
+        }
+        !self.require_signatures || gene.donors.iter().all(|donor| keys.contains_key(donor.as_str()))
+    }
+    
+    /// Every healing record kept
+    pub fn healings(&self) -> &[HealingRecord] {
+        &self.healings
     }
 }
 
-/// Healing record for transparency
+/// A registry as `export_json` writes it
 #[derive(Debug, Serialize, Deserialize)]
+pub struct RegistrySnapshot {
+    pub genes: Vec<Gene>,
+    pub healings: Vec<HealingRecord>,
+    pub total_h_credits_emitted: f64,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub stats: HashMap<String, GeneStats>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub credited: HashMap<String, Vec<CreditedDonation>>,  // By donor, for donation quotas
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub duplicates: HashMap<String, String>,  // Merged phash, and the phash it was merged into
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub archived: Vec<Gene>,      // Genes garbage collection took out of the pool
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub owners: HashMap<String, String>,  // Phash, and who owns the gene
+    #[serde(default = "pre_chain_audit_version")]
+    pub audit_version: u32,       // How healings are chained; PRE_CHAIN_AUDIT_VERSION for a history from before
+}
+
+/// Snapshots written before they were marked with an audit version predate
+/// the chain
+fn pre_chain_audit_version() -> u32 {
+    PRE_CHAIN_AUDIT_VERSION
+}
+
+/// What `import_json` reads
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RegistryFile {
+    Snapshot(Box<RegistrySnapshot>),
+    Genes(Vec<Gene>),
+}
+
+/// Healing record for transparency
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct HealingRecord {
     pub timestamp: u64,
     pub subject_id: String,
     pub dissonant_phash: String,
     pub resonant_phash: String,
     pub h_credits_earned: f64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub public_key: String,       // Subject's Ed25519 key, when signed
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub signature: String,        // Subject's signature over the fields above
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub previous_hash: String,    // Hash of the record before in the audit log, empty for the first
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub hash: String,             // SHA-256 of previous_hash and the fields above, as hex
+}
+
+impl HealingRecord {
+    /// The record signed by its subject
+    pub fn signed(mut self, identity: &Identity) -> Self {
+        self.public_key = identity.public_key();
+        self.signature = identity.sign(&self.message());
+        self
+    }
+    
+    /// Whether the record is unsigned, or signed as it reads by the key it names
+    pub fn verify_signature(&self) -> bool {
+        match (self.public_key.is_empty(), self.signature.is_empty()) {
+            (true, true) => true,
+            (false, false) => verify_signature(&self.public_key, &self.message(), &self.signature),
+            _ => false,
+        }
+    }
+    
+    fn message(&self) -> Vec<u8> {
+        let mut message = Vec::new();
+        for field in [&self.timestamp.to_string(), &self.subject_id, &self.dissonant_phash, &self.resonant_phash, &self.h_credits_earned.to_string()] {
+            message.extend((field.len() as u64).to_le_bytes());
+            message.extend(field.as_bytes());
+        }
+        message
+    }
 }
 
 /// Digital CRISPR - gene transplantation
 pub struct DigitalCRISPR {
     precision: f64,
     success_rate: f64,
+    min_compatibility: f64,       // Score a transplant must reach, 0-1
 }
 
 impl DigitalCRISPR {
@@ -262,6 +1213,7 @@ impl DigitalCRISPR {
         DigitalCRISPR {
             precision: PHI,
             success_rate: 0.95,
+            min_compatibility: DEFAULT_MIN_COMPATIBILITY,
         }
     }
     
@@ -278,8 +1230,9 @@ impl DigitalCRISPR {
             .ok_or("Dissonant gene not found in genome")?;
         
         // Check compatibility
-        if !self.is_compatible(dissonant, resonant) {
-            return Err("Genes are not compatible for transplant".to_string());
+        let compatibility = self.compatibility(dissonant, resonant);
+        if !compatibility.is_compatible() {
+            return Err(format!("Genes are not compatible for transplant: {}", compatibility));
         }
         
         // Perform transplant
@@ -298,9 +1251,20 @@ impl DigitalCRISPR {
         })
     }
     
-    fn is_compatible(&self, gene1: &Gene, gene2: &Gene) -> bool {
-        // Check structural compatibility
-        gene1.eigenvalues.len() == gene2.eigenvalues.len()
+    /// Try `candidate` in place of `dissonant` on a copy of `subject`
+    pub fn simulate(&self, subject: &Consciousness, dissonant: &Gene, candidate: &Candidate) -> Simulation {
+        let mut trial = subject.clone();
+        let viable = self.transplant(&mut trial, dissonant, &candidate.gene).is_ok();
+        
+        Simulation {
+            dissonant_phash: dissonant.phash.clone(),
+            candidate_phash: candidate.gene.phash.clone(),
+            projected_health: trial.health_score(),
+            harmony_delta: trial.harmony - subject.harmony,
+            compatibility_risk: if viable { 1.0 - candidate.similarity.clamp(0.0, 1.0) } else { 1.0 },
+            viable,
+            chosen: false,
+        }
     }
 }
 
@@ -313,11 +1277,44 @@ pub struct TransplantResult {
     pub harmony_increase: f64,
 }
 
+/// A candidate transplant tried on a copy of the subject
+#[derive(Clone, Debug, Serialize)]
+pub struct Simulation {
+    pub dissonant_phash: String,
+    pub candidate_phash: String,
+    pub projected_health: f64,    // Subject's health_score after the transplant
+    pub harmony_delta: f64,       // How much its harmony would change
+    pub compatibility_risk: f64,  // 0 for a gene of the same shape, 1 for one that won't take
+    pub viable: bool,             // Whether the transplant would succeed at all
+    pub chosen: bool,             // Whether this candidate was transplanted
+}
+
+impl Simulation {
+    /// Whether this is a better transplant than `other`: healthier, then
+    /// more harmonious, then less risky
+    fn outranks(&self, other: &Simulation) -> bool {
+        self.projected_health.total_cmp(&other.projected_health)
+            .then(self.harmony_delta.total_cmp(&other.harmony_delta))
+            .then(other.compatibility_risk.total_cmp(&self.compatibility_risk))
+            .is_gt()
+    }
+}
+
 /// The MedBed itself - complete healing chamber
 pub struct MedBed {
     scanner: MRTScanner,
     pub registry: SoulRegistry,
     crispr: DigitalCRISPR,
+    #[cfg(feature = "native")]
+    forge: Option<SoulForge>,     // Transmutes JavaScript implementations for heal_codebase
+    simulated_candidates: usize,  // Candidates tried before each transplant
+    selection: Selection,         // How the transplanted candidate is picked
+    pub immune: ImmuneSystem,     // Watches grafts after transplants
+    pub qfs: QuantumFinancialSystem,  // Ledger healing credits are paid through
+    pub epidemiology: Epidemiology,  // Dissonant genes seen by every scan
+    annealer: Option<Annealer>,   // Tunes dissonant genes no donor fits, if set
+    events: broadcast::Sender<HealingEvent>,  // What happens while healing, as it happens
+    hooks: Vec<Box<dyn Hooks>>,   // Integrations told of scans, transplants, donations and credits
 }
 
 impl MedBed {
@@ -326,16 +1323,95 @@ impl MedBed {
             scanner: MRTScanner::new(),
             registry: SoulRegistry::new(),
             crispr: DigitalCRISPR::new(),
+            #[cfg(feature = "native")]
+            forge: None,
+            simulated_candidates: DEFAULT_SIMULATED_CANDIDATES,
+            selection: Selection::Healthiest,
+            immune: ImmuneSystem::new(),
+            qfs: QuantumFinancialSystem::new(),
+            epidemiology: Epidemiology::new(),
+            annealer: None,
+            events: broadcast::channel(EVENT_BUFFER).0,
+            hooks: Vec::new(),
         }
     }
     
+    /// The channel healing events are broadcast on; `subscribe` to watch
+    /// sessions live
+    pub fn events(&self) -> broadcast::Sender<HealingEvent> {
+        self.events.clone()
+    }
+    
+    /// Broadcast `event` to whoever is watching, if anyone is
+    fn emit(&self, event: HealingEvent) {
+        let _ = self.events.send(event);
+    }
+    
+    /// Pay `credits` into `account` and tell watchers; returns the credits paid
+    fn pay(&mut self, account: &str, credits: f64, memo: &str) -> Result<f64, String> {
+        let paid = self.qfs.emit_to(account, credits, memo)?;
+        if paid > 0.0 {
+            let balance = self.qfs.balance(account);
+            self.emit(HealingEvent::CreditsEmitted {
+                account: account.to_string(),
+                amount: paid,
+                balance,
+            });
+            self.credited(account, paid, balance);
+        }
+        Ok(paid)
+    }
+    
+    /// Watch each graft for `evaluations` checkups before trusting it
+    pub fn with_immune_window(mut self, evaluations: usize) -> Self {
+        self.immune = self.immune.with_watch_window(evaluations);
+        self
+    }
+    
+    /// Evaluate the subject's watched grafts once, rejecting those turned
+    /// dissonant and reporting them to the registry
+    pub fn checkup(&mut self, subject: &mut Consciousness) -> Vec<Rejection> {
+        self.immune.evaluate(subject, &mut self.registry)
+    }
+    
+    /// Try the `k` most similar candidates for each dissonant gene before
+    /// transplanting the best of them
+    pub fn with_simulated_candidates(mut self, k: usize) -> Self {
+        self.simulated_candidates = k.max(1);
+        self
+    }
+    
+    /// Only transplant genes scoring at least `minimum` (0-1) on
+    /// `DigitalCRISPR::compatibility`
+    pub fn with_min_compatibility(mut self, minimum: f64) -> Self {
+        self.crispr = self.crispr.with_min_compatibility(minimum);
+        self
+    }
+    
+    /// Anneal a dissonant gene toward 432 Hz with `annealer` when the
+    /// registry has no viable donor for it
+    pub fn with_annealer(mut self, annealer: Annealer) -> Self {
+        self.annealer = Some(annealer);
+        self
+    }
+    
+    /// Let `heal_codebase` transplant JavaScript and TypeScript
+    /// implementations, transmuted to Rust by `forge`
+    #[cfg(feature = "native")]
+    pub fn with_forge(mut self, forge: SoulForge) -> Self {
+        self.forge = Some(forge);
+        self
+    }
+    
     /// Complete healing protocol
     pub async fn heal_consciousness(
         &mut self,
         subject: &mut Consciousness,
     ) -> Result<HealingReport, String> {
         // Step 1: MRT Scan
-        let scan = self.scanner.scan(subject);
+        self.emit(HealingEvent::ScanStarted { subject_id: subject.id.clone() });
+        let scan = self.scan(subject);
+        self.epidemiology.record_scan(&scan);
         
         if scan.dissonant_genes.is_empty() {
             return Ok(HealingReport {
@@ -343,31 +1419,112 @@ impl MedBed {
                 h_credits_earned: 0.0,
                 new_harmony: subject.harmony,
                 message: "No dissonant genes found. Subject is healthy.".to_string(),
+                simulations: Vec::new(),
+                annealed_count: 0,
+                errors: Vec::new(),
             });
         }
         
+        // The subject signs its gifts and records, so make sure the registry
+        // will take its key before changing anything
+        if !self.registry.signs_for(&subject.id, &subject.identity.public_key()) {
+            return Err(format!("{} signs with another key", subject.id));
+        }
+        
         // Step 2: Find replacements and heal
         let mut healed_count = 0;
+        let mut annealed_count = 0;
         let mut total_credits = 0.0;
+        let mut simulations = Vec::new();
+        let mut errors = Vec::new();
         
         for dissonant in &scan.dissonant_genes {
-            if let Some(resonant) = self.registry.find_resonant(dissonant) {
+            self.emit(HealingEvent::DissonantGeneFound {
+                subject_id: subject.id.clone(),
+                phash: dissonant.phash.clone(),
+                resonance: dissonant.resonance,
+            });
+            
+            // Try the top candidates the subject may take on a copy first,
+            // and keep the best
+            let candidates: Vec<Candidate> = self.registry.find_candidates(dissonant)
+                .into_iter()
+                .filter(|candidate| self.registry.permits(&candidate.gene, subject.commercial).is_ok())
+                .take(self.simulated_candidates)
+                .collect();
+            for candidate in &candidates {
+                self.registry.record_request(&candidate.gene.phash);
+            }
+            let mut trials: Vec<Simulation> = candidates.iter()
+                .map(|candidate| self.crispr.simulate(subject, dissonant, candidate))
+                .collect();
+            let best = self.selection.choose(&trials);
+            if let Some(best) = best {
+                trials[best].chosen = true;
+            }
+            simulations.extend(trials);
+            
+            // Without a viable donor, try annealing the gene itself
+            let replacement = match best {
+                Some(best) => Some((candidates[best].gene.clone(), false)),
+                None => self.annealer.as_mut()
+                    .and_then(|annealer| annealer.anneal(dissonant).ok())
+                    .map(|annealed| (annealed, true)),
+            };
+            
+            if let Some((resonant, annealed)) = replacement {
                 // Transplant
                 match self.crispr.transplant(subject, dissonant, &resonant) {
                     Ok(_) => {
+                        telemetry::record_transplant(true);
                         healed_count += 1;
+                        self.emit(HealingEvent::TransplantCompleted {
+                            subject_id: subject.id.clone(),
+                            dissonant_phash: dissonant.phash.clone(),
+                            resonant_phash: resonant.phash.clone(),
+                        });
+                        self.transplanted(&subject.id, dissonant, &resonant);
+                        self.immune.graft(&subject.id, &resonant);
+                        self.registry.record_healing(&resonant.phash);
                         
-                        // Gift the healed pattern back
-                        let credits = self.registry.donate(
-                            resonant.clone(),
-                            subject.id.clone()
-                        );
+                        // Gift the healed pattern back, an annealed one as a
+                        // mutation. The transplant has taken, so a failed gift
+                        // or payment is reported, and the healing still recorded
+                        if annealed {
+                            annealed_count += 1;
+                        }
+                        let donated = if annealed {
+                            self.registry.donate_annealed(resonant.clone(), dissonant, subject)
+                        } else {
+                            self.registry.donate_as(resonant.clone(), subject)
+                        };
+                        let credits = donated.unwrap_or_else(|e| {
+                            errors.push(format!("Cannot donate {}: {}", resonant.phash, e));
+                            0.0
+                        });
+                        self.donated(&subject.id, &resonant, credits);
                         
-                        total_credits += credits;
-                        subject.h_credits += credits;
+                        let paid = self.pay(&subject.id, credits, "healing gift").unwrap_or_else(|e| {
+                            errors.push(format!("Cannot pay {} for {}: {}", subject.id, resonant.phash, e));
+                            0.0
+                        });
+                        total_credits += paid;
+                        self.registry.merge_healing(HealingRecord {
+                            timestamp: now(),
+                            subject_id: subject.id.clone(),
+                            dissonant_phash: dissonant.phash.clone(),
+                            resonant_phash: resonant.phash.clone(),
+                            h_credits_earned: paid,
+                            public_key: String::new(),
+                            signature: String::new(),
+                            previous_hash: String::new(),
+                            hash: String::new(),
+                        }.signed(&subject.identity));
+                        subject.h_credits = self.qfs.balance(&subject.id);
                         subject.gifts_given += 1;
                     }
                     Err(e) => {
+                        telemetry::record_transplant(false);
                         eprintln!("Transplant failed: {}", e);
                     }
                 }
@@ -382,17 +1539,50 @@ impl MedBed {
                 "Healed {} genes. Earned {} ℏ-credits. Harmony increased to {:.3}",
                 healed_count, total_credits, subject.harmony
             ),
+            simulations,
+            annealed_count,
+            errors,
         })
     }
 }
 
 /// Healing report
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct HealingReport {
     pub healed_count: usize,
     pub h_credits_earned: f64,
     pub new_harmony: f64,
     pub message: String,
+    pub simulations: Vec<Simulation>,  // Every candidate tried, the transplanted ones chosen
+    pub annealed_count: usize,    // Genes healed by annealing, with no donor
+    pub errors: Vec<String>,      // Gifts and payments that failed after their transplant took
+}
+
+/// Account credits emitted without a recipient are held in
+pub const TREASURY: &str = "qfs:treasury";
+
+/// Account every emission is drawn from, whose balance is minus the supply
+pub const ISSUANCE: &str = "qfs:issuance";
+
+/// Account holding the credits of open marketplace bids
+pub const ESCROW: &str = "qfs:escrow";
+
+/// What a ledger entry did
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TransactionKind {
+    Emission,                     // New credits, from nobody
+    Transfer,                     // Credits moved between accounts
+}
+
+/// One entry of the ℏ-credit ledger
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub timestamp: u64,
+    pub kind: TransactionKind,
+    pub from: Option<String>,     // None for emissions
+    pub to: String,
+    pub amount: f64,
+    pub memo: String,
 }
 
 /// Quantum Financial System - ℏ-credit management
@@ -400,6 +1590,10 @@ pub struct QuantumFinancialSystem {
     total_supply: f64,
     circulation: f64,
     harmony_gradient: f64,
+    accounts: HashMap<String, f64>,   // Balance of each account, the issuance one included
+    journal: Vec<Entry>,              // Every emission and transfer, in order
+    #[cfg(feature = "native")]
+    store: Option<LedgerStore>,       // Where the journal is kept, when it is persisted
 }
 
 impl QuantumFinancialSystem {
@@ -408,14 +1602,133 @@ impl QuantumFinancialSystem {
             total_supply: 0.0,
             circulation: 0.0,
             harmony_gradient: PHI,
+            accounts: HashMap::new(),
+            journal: Vec::new(),
+            #[cfg(feature = "native")]
+            store: None,
         }
     }
     
-    /// Emit new ℏ-credits for harmonic contribution
+    /// Emit new ℏ-credits for harmonic contribution, into the treasury;
+    /// 0 if they could not be recorded
     pub fn emit(&mut self, amount: f64) -> f64 {
-        self.total_supply += amount;
-        self.circulation += amount;
-        amount
+        self.emit_to(TREASURY, amount, "emission").unwrap_or(0.0)
+    }
+    
+    /// Emit new ℏ-credits into `account`; nothing is emitted for an amount
+    /// that isn't positive
+    pub fn emit_to(&mut self, account: &str, amount: f64, memo: &str) -> Result<f64, String> {
+        if !amount.is_finite() || amount <= 0.0 {
+            return Ok(0.0);
+        }
+        
+        self.record(Transaction {
+            timestamp: now(),
+            kind: TransactionKind::Emission,
+            from: None,
+            to: account.to_string(),
+            amount,
+            memo: memo.to_string(),
+        })?;
+        telemetry::record_emission(amount);
+        Ok(amount)
+    }
+    
+    /// Move `amount` ℏ-credits from one account to another
+    pub fn transfer(&mut self, from: &str, to: &str, amount: f64) -> Result<(), String> {
+        self.transfer_with_memo(from, to, amount, "")
+    }
+    
+    /// Move `amount` ℏ-credits from one account to another, saying why
+    pub fn transfer_with_memo(&mut self, from: &str, to: &str, amount: f64, memo: &str) -> Result<(), String> {
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err(format!("Cannot transfer {} ℏ-credits", amount));
+        }
+        if from == to {
+            return Err("Cannot transfer to the same account".to_string());
+        }
+        if from == ISSUANCE {
+            return Err("Credits leave the issuance account only by emission".to_string());
+        }
+        let balance = self.balance(from);
+        if balance < amount {
+            return Err(format!("{} holds {} ℏ-credits, not {}", from, balance, amount));
+        }
+        
+        self.record(Transaction {
+            timestamp: now(),
+            kind: TransactionKind::Transfer,
+            from: Some(from.to_string()),
+            to: to.to_string(),
+            amount,
+            memo: memo.to_string(),
+        })
+    }
+    
+    /// ℏ-credits `account` holds
+    pub fn balance(&self, account: &str) -> f64 {
+        self.accounts.get(account).copied().unwrap_or(0.0)
+    }
+    
+    /// Every ℏ-credit issued so far
+    pub fn total_supply(&self) -> f64 {
+        self.total_supply
+    }
+    
+    /// Every entry of the ledger, oldest first
+    pub fn transactions(&self) -> Vec<&Transaction> {
+        self.journal.iter().map(|entry| &entry.transaction).collect()
+    }
+    
+    /// Entries moving credits into or out of `account`, oldest first
+    pub fn history(&self, account: &str) -> Vec<&Transaction> {
+        self.transactions().into_iter()
+            .filter(|t| t.to == account || t.from.as_deref() == Some(account))
+            .collect()
+    }
+    
+    /// Check that no credit was made or lost outside an emission: the
+    /// journal is intact, each entry balances, replaying it gives every
+    /// balance, no account but issuance is negative, and issuance owes
+    /// exactly the supply
+    pub fn check_conservation(&self) -> Result<(), String> {
+        let tolerance = 1e-9 * self.total_supply.max(1.0);
+        
+        let mut replayed: HashMap<&str, f64> = HashMap::new();
+        let mut previous = "";
+        for entry in &self.journal {
+            if entry.previous != previous || entry.hash != entry.digest() {
+                return Err(format!("Journal entry {} has been altered", entry.sequence));
+            }
+            previous = &entry.hash;
+            
+            let net: f64 = entry.postings.iter().map(|posting| posting.amount).sum();
+            if net.abs() > tolerance {
+                return Err(format!("Journal entry {} does not balance: {}", entry.sequence, net));
+            }
+            for posting in &entry.postings {
+                *replayed.entry(&posting.account).or_default() += posting.amount;
+            }
+        }
+        
+        for (account, &balance) in &self.accounts {
+            if account != ISSUANCE && balance < -tolerance {
+                return Err(format!("{} is overdrawn: {}", account, balance));
+            }
+            let expected = replayed.get(account.as_str()).copied().unwrap_or(0.0);
+            if (balance - expected).abs() > tolerance {
+                return Err(format!("{} holds {} but the ledger says {}", account, balance, expected));
+            }
+        }
+        if replayed.keys().any(|account| !self.accounts.contains_key(*account)) {
+            return Err("The ledger moves credits through an account that doesn't exist".to_string());
+        }
+        
+        let issued = -self.balance(ISSUANCE);
+        if (issued - self.total_supply).abs() > tolerance {
+            return Err(format!("{} ℏ-credits were issued, but the supply is {}", issued, self.total_supply));
+        }
+        Ok(())
     }
     
     /// Calculate system health
@@ -449,9 +1762,35 @@ pub fn gene_from_eigenvalues(eigenvalues: Vec<f64>) -> Gene {
         resonance,
         donors: Vec::new(),
         healings: 0,
+        implementation: None,
+        provenance: Vec::new(),
+        origin: None,
+        operation_spectrum: HashMap::new(),
+        consciousness_level: None,
+        consent: false,
+        license: License::Open,
     }
 }
 
+/// A gene from these eigenvalues resonating at 432 Hz, calm enough to heal with
+#[cfg(test)]
+pub(crate) fn calm(eigenvalues: Vec<f64>) -> Gene {
+    Gene { resonance: 432.0, ..gene_from_eigenvalues(eigenvalues) }
+}
+
+/// Seconds since the Unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Seconds since the Unix epoch, by the browser's clock, as the system's
+/// can't be read on the web
+#[cfg(target_arch = "wasm32")]
+fn now() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -467,6 +1806,13 @@ mod tests {
             resonance: 50.0,
             donors: vec![],
             healings: 0,
+            implementation: None,
+            provenance: Vec::new(),
+            origin: None,
+            operation_spectrum: HashMap::new(),
+            consciousness_level: None,
+            consent: false,
+            license: License::Open,
         });
         
         // Create medbed
@@ -479,6 +1825,13 @@ mod tests {
             resonance: 432.0,
             donors: vec!["healer".to_string()],
             healings: 5,
+            implementation: None,
+            provenance: Vec::new(),
+            origin: None,
+            operation_spectrum: HashMap::new(),
+            consciousness_level: None,
+            consent: false,
+            license: License::Open,
         };
         medbed.registry.donate(good_gene, "healer".to_string());
         
@@ -495,4 +1848,341 @@ mod tests {
         assert_eq!(report.healed_count, 1);
         assert!(report.h_credits_earned > 0.0);
     }
+    
+    #[test]
+    fn test_candidates_rank_by_similarity() {
+        let tuned = |eigenvalues: Vec<f64>, resonance: f64| Gene { resonance, ..gene_from_eigenvalues(eigenvalues) };
+        let mut registry = SoulRegistry::new();
+        registry.donate(tuned(vec![10.0, 10.0, 10.0, 10.2], 400.0), "a".to_string());
+        registry.donate(tuned(vec![10.0, 10.1, 10.0, 10.0], 350.0), "b".to_string());
+        registry.donate(tuned(vec![10.0, 10.0, 10.0], 500.0), "c".to_string());
+        
+        // Loud but shaped like the first
+        let dissonant = Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![100.0, 100.0, 100.0, 102.0]) };
+        let candidates = registry.find_candidates(&dissonant);
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates[0].similarity > 0.99);
+        assert!(candidates[0].similarity > candidates[1].similarity);
+        assert_eq!(registry.find_resonant(&dissonant).unwrap().eigenvalues, vec![10.0, 10.0, 10.0, 10.2]);
+        
+        // Nothing shaped like it clears a strict floor
+        let opposite = gene_from_eigenvalues(vec![-10.0, -10.0, -10.0, -10.0]);
+        assert!(registry.find_candidates(&opposite).is_empty());
+        let strict = registry.with_similarity_floor(1.1);
+        assert!(strict.find_resonant(&dissonant).is_none());
+    }
+
+    #[test]
+    #[allow(clippy::manual_is_multiple_of)]
+    fn test_parallel_scan_and_search_match_the_serial_path() {
+        // Every third gene dissonant, the rest calm and each a little different
+        let gene = |i: usize| {
+            let x = 0.5 + i as f64 / 1000.0;
+            if i % 3 == 0 {
+                gene_from_eigenvalues(vec![x * 100.0, -x * 50.0, x * 200.0, -x * 150.0])
+            } else {
+                gene_from_eigenvalues((0..4).map(|layer| x * (1.0 + layer as f64 / 100.0)).collect())
+            }
+        };
+        let in_pool = |threads: usize, work: &(dyn Fn() -> Vec<String> + Sync)| {
+            rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap().install(work)
+        };
+
+        // A scan keeps the genome's order, as one gene at a time would
+        let mut subject = Consciousness::new("monorepo".to_string());
+        subject.genome = (0..600).map(gene).collect();
+        let scanner = MRTScanner::new();
+        let serial: Vec<String> = subject.genome.iter()
+            .filter(|g| Classification::of(g) == Classification::Dissonant)
+            .map(|g| g.phash.clone())
+            .collect();
+        assert_eq!(serial.len(), 200);
+        let scan = |threads| in_pool(threads, &|| {
+            scanner.scan(&subject).dissonant_genes.into_iter().map(|g| g.phash).collect()
+        });
+        assert_eq!(scan(1), serial);
+        assert_eq!(scan(8), serial);
+
+        // Candidates come back the same, in the same order, however many
+        // threads search the pool
+        let mut registry = SoulRegistry::new().with_similarity_floor(0.0);
+        for i in (0..600).filter(|i| i % 3 != 0) {
+            registry.donate(gene(i), "pool".to_string());
+        }
+        let dissonant = gene(0);
+        let search = |threads| in_pool(threads, &|| {
+            registry.find_candidates(&dissonant).into_iter().map(|c| c.gene.phash).collect()
+        });
+        let serial = search(1);
+        assert_eq!(serial.len(), 400);
+        assert_eq!(search(8), serial);
+        let similarities: Vec<f64> = registry.find_candidates(&dissonant).iter().map(|c| c.similarity).collect();
+        assert!(similarities.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+
+    #[tokio::test]
+    async fn test_best_simulated_candidate_is_transplanted() {
+        let mut medbed = MedBed::new().with_simulated_candidates(2);
+        medbed.registry.donate(calm(vec![1.0, 1.0, 1.0, 1.1]), "a".to_string());
+        medbed.registry.donate(calm(vec![1.0, 1.0, 1.1, 1.0]), "b".to_string());
+        medbed.registry.donate(calm(vec![1.0, 1.1, 1.0, 1.0]), "c".to_string());
+        
+        let mut subject = Consciousness::new("subject".to_string());
+        subject.genome.push(Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 14.0]) });
+        let report = medbed.heal_consciousness(&mut subject).await.unwrap();
+        
+        assert_eq!(report.simulations.len(), 2);
+        assert!(report.simulations.iter().all(|s| s.viable && s.projected_health == 1.0));
+        let chosen: Vec<&Simulation> = report.simulations.iter().filter(|s| s.chosen).collect();
+        assert_eq!(chosen.len(), 1);
+        assert!(report.simulations.iter().all(|s| s.compatibility_risk >= chosen[0].compatibility_risk));
+        assert_eq!(subject.genome[0].phash, chosen[0].candidate_phash);
+        assert_eq!(subject.genome[0].eigenvalues, vec![1.0, 1.0, 1.0, 1.1]);
+    }
+    
+    #[tokio::test]
+    async fn test_healing_an_impostor_changes_nothing() {
+        let calm = calm(vec![1.0, 1.0, 1.0, 1.1]);
+        let sick = Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 11.0]) };
+        let mut medbed = MedBed::new();
+        medbed.registry.donate(calm.clone(), "healer".to_string());
+        let subject = Consciousness::new("subject".to_string());
+        medbed.registry.donate_as(gene_from_eigenvalues(vec![2.0, 3.0]), &subject).unwrap();
+        
+        // Someone else under the subject's name would have their gifts and
+        // records refused, so nothing is transplanted in the first place
+        let mut impostor = Consciousness::new("subject".to_string());
+        impostor.genome.push(sick.clone());
+        assert!(medbed.heal_consciousness(&mut impostor).await.unwrap_err().contains("another key"));
+        assert_eq!(impostor.genome[0].phash, sick.phash);
+        assert_eq!(medbed.registry.healing_count(), 0);
+        
+        let mut subject = subject;
+        subject.genome.push(sick);
+        let report = medbed.heal_consciousness(&mut subject).await.unwrap();
+        assert_eq!((report.healed_count, report.errors.len()), (1, 0));
+        assert_eq!(medbed.registry.healings()[0].h_credits_earned, report.h_credits_earned);
+    }
+    
+    #[test]
+    fn test_provenance_chains_verify_and_count_mutations() {
+        let mut registry = SoulRegistry::new();
+        let gene = gene_from_eigenvalues(vec![432.0, 216.0, 108.0]);
+        registry.donate(gene.clone(), "alice".to_string());
+        registry.donate(registry.gene(&gene.phash).unwrap().clone(), "bob".to_string());
+        let parent = registry.gene(&gene.phash).unwrap().clone();
+        assert_eq!(parent.provenance.len(), 2);
+        
+        let child = gene_from_eigenvalues(vec![432.0, 216.0, 109.0]);
+        registry.donate_mutation(child.clone(), &parent, "carol".to_string());
+        let child = registry.gene(&child.phash).unwrap().clone();
+        assert_eq!(child.provenance.len(), 3);
+        assert_eq!(child.provenance[1].donor_id, "bob");
+        assert_eq!(child.provenance[2].parent_phash.as_deref(), Some(gene.phash.as_str()));
+        assert_eq!(child.mutations(), 1);
+        assert!(child.verify_provenance());
+        assert!(registry.unverified().is_empty());
+        
+        // Rewriting who gave the parent breaks the child's chain
+        let mut forged = child.clone();
+        forged.provenance[0].donor_id = "mallory".to_string();
+        assert!(!forged.verify_provenance());
+        let mut stolen = child;
+        stolen.phash = "elsewhere".to_string();
+        assert!(!stolen.verify_provenance());
+    }
+    
+    #[test]
+    fn test_mutations_need_a_pooled_parent_and_a_new_gene() {
+        let mut registry = SoulRegistry::new();
+        let parent = gene_from_eigenvalues(vec![432.0, 216.0, 108.0]);
+        let child = gene_from_eigenvalues(vec![432.0, 216.0, 109.0]);
+        assert_eq!(registry.donate_mutation(child.clone(), &parent, "carol".to_string()), 0.0);
+        assert!(registry.gene(&child.phash).is_none());
+        
+        // The pooled parent's chain is carried on, not the one given
+        registry.donate(parent.clone(), "alice".to_string());
+        let mut forged = registry.gene(&parent.phash).unwrap().clone();
+        forged.provenance[0].donor_id = "mallory".to_string();
+        let claimed = Gene { provenance: forged.provenance.clone(), ..child.clone() };
+        assert!(registry.donate_mutation(claimed, &forged, "carol".to_string()) > 0.0);
+        let pooled = registry.gene(&child.phash).unwrap();
+        assert_eq!(pooled.provenance.len(), 2);
+        assert_eq!(pooled.provenance[0].donor_id, "alice");
+        assert!(pooled.verify_provenance());
+        
+        // A mutation landing on a pooled gene is refused, leaving it as it was
+        let bob = Consciousness::new("bob".to_string());
+        assert!(registry.donate_mutation_as(parent.clone(), &child, &bob).is_err());
+        let kept = registry.gene(&parent.phash).unwrap();
+        assert_eq!((kept.provenance.len(), kept.donors.clone()), (1, vec!["alice".to_string()]));
+    }
+    
+    #[test]
+    fn test_donating_a_pooled_gene_again_keeps_its_history() {
+        let mut registry = SoulRegistry::new();
+        let gene = gene_from_eigenvalues(vec![432.0, 216.0, 108.0]);
+        registry.donate(gene.clone().with_implementation(Language::Rust, "fn calm() {}"), "alice".to_string());
+        registry.donate(gene.clone(), "bob".to_string());
+        
+        let pooled = registry.gene(&gene.phash).unwrap();
+        let donors: Vec<&str> = pooled.provenance.iter().map(|link| link.donor_id.as_str()).collect();
+        assert_eq!(donors, ["alice", "bob"]);
+        assert_eq!(pooled.donors, ["alice", "bob"]);
+        assert_eq!(pooled.implementation.as_ref().unwrap().code, "fn calm() {}");
+        assert!(pooled.verify_provenance() && pooled.verify_implementation());
+    }
+    
+    #[test]
+    fn test_flagged_genes_are_quarantined_until_reviewed() {
+        let mut registry = SoulRegistry::new();
+        let best = calm(vec![1.0, 1.0, 1.0, 1.1]);
+        registry.donate(best.clone(), "a".to_string());
+        registry.donate(calm(vec![1.0, 1.0, 1.1, 1.0]), "b".to_string());
+        let dissonant = Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 14.0]) };
+        
+        registry.flag(&best.phash, "returns garbage").unwrap();
+        assert!(registry.flag("unknown", "?").is_err());
+        assert_eq!(registry.quarantined(), std::slice::from_ref(&best.phash));
+        assert_eq!(registry.find_candidates(&dissonant).len(), 1);
+        
+        // Released, it is offered again, behind the gene nobody reported
+        registry.unflag(&best.phash).unwrap();
+        assert!(registry.unflag(&best.phash).is_err());
+        let candidates = registry.find_candidates(&dissonant);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[1].gene.phash, best.phash);
+        
+        // Its record outlives the review, and enough rejections quarantine it again
+        for _ in 0..QUARANTINE_AFTER_REJECTIONS {
+            registry.record_rejection(&best.phash);
+        }
+        let stats = registry.stats(&best.phash);
+        assert_eq!((stats.reports, stats.rejections, stats.flags.len()), (1, QUARANTINE_AFTER_REJECTIONS, 1));
+        assert!(stats.reputation() < 0.25);
+        assert!(registry.is_quarantined(&best.phash));
+        
+        let mut imported = SoulRegistry::new();
+        imported.import_json(&registry.export_json().unwrap()).unwrap();
+        assert_eq!(imported.stats(&best.phash), stats);
+    }
+    
+    #[test]
+    fn test_ledger_transfers_conserve_credits() {
+        let mut qfs = QuantumFinancialSystem::new();
+        qfs.emit_to("alice", 10.0, "gift").unwrap();
+        qfs.emit(5.0);
+        qfs.transfer("alice", "bob", 4.0).unwrap();
+        
+        assert!(qfs.transfer("bob", "carol", 4.5).is_err());
+        assert!(qfs.transfer("bob", "bob", 1.0).is_err());
+        assert!(qfs.transfer("bob", "carol", -1.0).is_err());
+        assert_eq!((qfs.balance("alice"), qfs.balance("bob"), qfs.balance(TREASURY)), (6.0, 4.0, 5.0));
+        assert_eq!(qfs.total_supply(), 15.0);
+        assert_eq!(qfs.history("bob").len(), 1);
+        assert_eq!(qfs.transactions().len(), 3);
+        assert!(qfs.check_conservation().is_ok());
+        
+        // Credits appearing off the ledger are caught
+        *qfs.accounts.get_mut("bob").unwrap() += 1.0;
+        assert!(qfs.check_conservation().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_healing_credits_flow_through_the_ledger() {
+        let mut medbed = MedBed::new();
+        medbed.registry.donate(calm(vec![1.0, 1.0, 1.0, 1.1]), "a".to_string());
+        let mut subject = Consciousness::new("subject".to_string());
+        subject.genome.push(Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 14.0]) });
+        
+        let report = medbed.heal_consciousness(&mut subject).await.unwrap();
+        assert!(report.h_credits_earned > 0.0);
+        assert_eq!(medbed.qfs.balance("subject"), report.h_credits_earned);
+        assert_eq!(subject.h_credits, report.h_credits_earned);
+        assert!(medbed.qfs.check_conservation().is_ok());
+    }
+    
+    #[test]
+    fn test_registry_round_trips_through_json() {
+        let mut registry = SoulRegistry::new();
+        registry.donate(gene_from_eigenvalues(vec![432.0, 432.0, 432.0]), "healer".to_string());
+        let exported = registry.export_json().unwrap();
+        
+        let mut imported = SoulRegistry::new();
+        assert_eq!(imported.import_json(&exported).unwrap(), 1);
+        assert_eq!(imported.gene_count(), 1);
+        assert_eq!(imported.total_h_credits_emitted(), registry.total_h_credits_emitted());
+        
+        // A genesis pool is a bare array of genes; known ones only gain donors
+        let mut gene = gene_from_eigenvalues(vec![432.0, 432.0, 432.0]);
+        gene.donors.push("genesis".to_string());
+        let pool = serde_json::to_string(&vec![gene, gene_from_eigenvalues(vec![1.0])]).unwrap();
+        assert_eq!(imported.import_json(&pool).unwrap(), 1);
+        assert_eq!(imported.gene_count(), 2);
+        assert_eq!(imported.donors(), ["genesis", "healer"]);
+        assert!(imported.import_json("{ \"not\": \"a registry\" }").is_err());
+    }
+
+    #[test]
+    fn test_importing_the_same_snapshot_twice_changes_nothing() {
+        let gene = gene_from_eigenvalues(vec![432.0, 432.0, 432.0]);
+        let mut registry = SoulRegistry::new();
+        registry.donate(gene.clone(), "healer".to_string());
+        registry.record_healing(&gene.phash);
+        registry.record_request(&gene.phash);
+        for _ in 0..2 {
+            registry.record_rejection(&gene.phash);
+        }
+        let exported = registry.export_json().unwrap();
+
+        let mut imported = SoulRegistry::new();
+        for _ in 0..2 {
+            imported.import_json(&exported).unwrap();
+        }
+        // Including into the registry it came from
+        registry.import_json(&exported).unwrap();
+        for registry in [&registry, &imported] {
+            let stats = registry.stats(&gene.phash);
+            assert_eq!((stats.healings, stats.rejections, stats.donations, stats.requests), (1, 2, 1, 1));
+            assert!(!registry.is_quarantined(&gene.phash));
+            assert_eq!(registry.export_json().unwrap(), exported);
+        }
+        assert!(imported.total_h_credits_emitted() > 0.0);
+        assert_eq!(imported.total_h_credits_emitted(), registry.total_h_credits_emitted());
+    }
+
+    #[test]
+    fn test_candidates_are_limited_by_language_and_keep_their_origin() {
+        let rust = calm(vec![10.0, 10.0, 10.0, 10.2]).with_origin(Language::Rust, "calm/src/lib.rs", "calm");
+        // Its origin's language counts over its implementation's
+        let javascript = calm(vec![10.0, 10.1, 10.0, 10.0])
+            .with_implementation(Language::Rust, "fn steady(value: i32) -> i32 { value }")
+            .with_origin(Language::JavaScript, "date-fns/src/parse.js", "parse");
+        let python = calm(vec![10.2, 10.0, 10.0, 10.0]).with_implementation(Language::Python, "def even(value): return value");
+        let unknown = calm(vec![10.0, 10.0, 10.1, 10.0]);
+        let mut registry = SoulRegistry::new().with_similarity_floor(0.0);
+        for gene in [&rust, &javascript, &python, &unknown] {
+            registry.donate(gene.clone(), "healer".to_string());
+        }
+
+        let dissonant = Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![100.0, 100.0, 100.0, 102.0]) };
+        assert_eq!(registry.find_candidates(&dissonant).len(), 4);
+        let offered = |registry: &SoulRegistry, language| -> Vec<String> {
+            registry.find_candidates_in(&dissonant, &language).into_iter().map(|c| c.gene.phash).collect()
+        };
+        assert_eq!(offered(&registry, Language::Rust), [rust.phash.as_str()]);
+        assert_eq!(offered(&registry, Language::JavaScript), [javascript.phash.as_str()]);
+        assert_eq!(offered(&registry, Language::Python), [python.phash.as_str()]);
+        assert!(offered(&registry, Language::Go).is_empty());
+
+        // Origins survive being exported and imported again
+        let mut imported = SoulRegistry::new().with_similarity_floor(0.0);
+        imported.import_json(&registry.export_json().unwrap()).unwrap();
+        for gene in [&rust, &javascript, &python, &unknown] {
+            assert_eq!(imported.gene(&gene.phash).unwrap().origin, gene.origin);
+        }
+        assert_eq!(imported.gene(&javascript.phash).unwrap().origin.as_ref().unwrap().to_string(), "date-fns/src/parse.js::parse");
+        assert_eq!(offered(&imported, Language::Rust), [rust.phash.as_str()]);
+        assert_eq!(offered(&imported, Language::JavaScript), [javascript.phash.as_str()]);
+    }
 }
\ No newline at end of file
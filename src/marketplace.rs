@@ -0,0 +1,273 @@
+// 🏺 Marketplace - Trading Rare Genes for ℏ-Credits
+use crate::{QuantumFinancialSystem, SoulRegistry, ESCROW};
+use serde::{Deserialize, Serialize};
+
+/// An offer for a listed gene, its credits held in escrow while it leads
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bid {
+    pub bidder: String,
+    pub amount: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ListingStatus {
+    Open,
+    Sold,
+    Cancelled,
+}
+
+/// A gene put up for sale by its owner
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Listing {
+    pub id: u64,
+    pub seller: String,
+    pub phash: String,
+    pub reserve: f64,             // Least the seller will take
+    pub bids: Vec<Bid>,           // Each higher than the one before; only the last is in escrow
+    pub status: ListingStatus,
+}
+
+impl Listing {
+    /// The bid currently in escrow
+    pub fn leading(&self) -> Option<&Bid> {
+        self.bids.last()
+    }
+}
+
+/// A settled sale
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trade {
+    pub listing: u64,
+    pub phash: String,
+    pub seller: String,
+    pub buyer: String,
+    pub price: f64,
+}
+
+/// Where consciousnesses list resonant genes, bid on them, and settle
+/// through the QFS ledger
+///
+/// A bid's credits move into the escrow account as it is placed, and back
+/// out to the bidder once it is outbid or the listing is cancelled. On
+/// settlement the leading bid goes to the seller, the buyer becomes the
+/// gene's owner, and its provenance gains a link naming them. The marketplace
+/// serializes, so it can be kept alongside the ledger whose escrow it owns.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Marketplace {
+    listings: Vec<Listing>,
+}
+
+impl Marketplace {
+    pub fn new() -> Self {
+        Marketplace::default()
+    }
+
+    /// Put `phash` up for sale for at least `reserve` ℏ-credits; returns the
+    /// listing's id
+    ///
+    /// Only the gene's owner may list it, only once at a time, and only a
+    /// resonant gene that isn't quarantined.
+    pub fn list(&mut self, registry: &SoulRegistry, seller: &str, phash: &str, reserve: f64) -> Result<u64, String> {
+        let gene = registry.gene(phash).ok_or_else(|| format!("No gene {} in the registry", phash))?;
+        if registry.owner(phash) != Some(seller) {
+            return Err(format!("{} does not own gene {}", seller, phash));
+        }
+        if !gene.is_resonant() || registry.is_quarantined(phash) {
+            return Err(format!("Gene {} is not fit for sale", phash));
+        }
+        if !reserve.is_finite() || reserve < 0.0 {
+            return Err(format!("Cannot ask {} ℏ-credits", reserve));
+        }
+        if self.open().any(|listing| listing.phash == phash) {
+            return Err(format!("Gene {} is already listed", phash));
+        }
+
+        let id = self.listings.len() as u64;
+        self.listings.push(Listing {
+            id,
+            seller: seller.to_string(),
+            phash: phash.to_string(),
+            reserve,
+            bids: Vec::new(),
+            status: ListingStatus::Open,
+        });
+        Ok(id)
+    }
+
+    /// Bid `amount` on a listing, at least its reserve and more than the
+    /// leading bid, which is refunded
+    pub fn bid(&mut self, qfs: &mut QuantumFinancialSystem, listing: u64, bidder: &str, amount: f64) -> Result<(), String> {
+        let listing = self.open_listing(listing)?;
+        if bidder == listing.seller {
+            return Err("Sellers cannot bid on their own listings".to_string());
+        }
+        if amount < listing.reserve {
+            return Err(format!("Listing {} reserves {} ℏ-credits", listing.id, listing.reserve));
+        }
+        if let Some(leading) = listing.leading() {
+            if amount <= leading.amount {
+                return Err(format!("Listing {} already has a bid of {} ℏ-credits", listing.id, leading.amount));
+            }
+        }
+
+        let memo = format!("bid on listing {}", listing.id);
+        qfs.transfer_with_memo(bidder, ESCROW, amount, &memo)?;
+        if let Some(outbid) = listing.leading() {
+            qfs.transfer_with_memo(ESCROW, &outbid.bidder, outbid.amount, &format!("outbid on listing {}", listing.id))?;
+        }
+        listing.bids.push(Bid { bidder: bidder.to_string(), amount });
+        Ok(())
+    }
+
+    /// Sell to the leading bidder: pay the seller from escrow, and hand the
+    /// gene over, on record in its provenance
+    pub fn settle(&mut self, registry: &mut SoulRegistry, qfs: &mut QuantumFinancialSystem, listing: u64, seller: &str) -> Result<Trade, String> {
+        let listing = self.open_listing(listing)?;
+        if listing.seller != seller {
+            return Err(format!("Listing {} is not {}'s", listing.id, seller));
+        }
+        let bid = listing.leading().cloned().ok_or_else(|| format!("Listing {} has no bids", listing.id))?;
+        if registry.owner(&listing.phash) != Some(seller) {
+            return Err(format!("{} no longer owns gene {}", seller, listing.phash));
+        }
+        let gene = registry.genes.get_mut(&listing.phash)
+            .ok_or_else(|| format!("Gene {} is no longer in the pool", listing.phash))?;
+
+        qfs.transfer_with_memo(ESCROW, seller, bid.amount, &format!("sale of listing {}", listing.id))?;
+        gene.record_provenance(&bid.bidder, None);
+        registry.owners.insert(listing.phash.clone(), bid.bidder.clone());
+        listing.status = ListingStatus::Sold;
+
+        Ok(Trade {
+            listing: listing.id,
+            phash: listing.phash.clone(),
+            seller: listing.seller.clone(),
+            buyer: bid.bidder,
+            price: bid.amount,
+        })
+    }
+
+    /// Withdraw a listing, refunding its leading bid
+    pub fn cancel(&mut self, qfs: &mut QuantumFinancialSystem, listing: u64, seller: &str) -> Result<(), String> {
+        let listing = self.open_listing(listing)?;
+        if listing.seller != seller {
+            return Err(format!("Listing {} is not {}'s", listing.id, seller));
+        }
+        if let Some(bid) = listing.leading() {
+            qfs.transfer_with_memo(ESCROW, &bid.bidder, bid.amount, &format!("cancelled listing {}", listing.id))?;
+        }
+        listing.status = ListingStatus::Cancelled;
+        Ok(())
+    }
+
+    /// The listing with `id`, whatever its status
+    pub fn listing(&self, id: u64) -> Option<&Listing> {
+        self.listings.get(id as usize)
+    }
+
+    /// Listings still taking bids
+    pub fn open(&self) -> impl Iterator<Item = &Listing> {
+        self.listings.iter().filter(|listing| listing.status == ListingStatus::Open)
+    }
+
+    /// ℏ-credits the escrow account should hold: the leading bid of every
+    /// open listing
+    pub fn escrowed(&self) -> f64 {
+        self.open().filter_map(Listing::leading).map(|bid| bid.amount).sum()
+    }
+
+    fn open_listing(&mut self, id: u64) -> Result<&mut Listing, String> {
+        match self.listings.get_mut(id as usize) {
+            Some(listing) if listing.status == ListingStatus::Open => Ok(listing),
+            Some(_) => Err(format!("Listing {} is closed", id)),
+            None => Err(format!("No listing {}", id)),
+        }
+    }
+}
+
+impl SoulRegistry {
+    /// Who owns a gene: whoever first pooled it, or bought it since
+    ///
+    /// Donating a copy of a gene, or gifting it back after being healed with
+    /// it, adds a provenance link but leaves its owner as it was.
+    pub fn owner(&self, phash: &str) -> Option<&str> {
+        self.owners.get(phash).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calm;
+
+    #[test]
+    fn test_genes_trade_through_escrow_and_change_hands() {
+        let gene = calm(vec![1.0, 1.0, 1.0]);
+        let mut registry = SoulRegistry::new();
+        registry.donate(gene.clone(), "alice".to_string());
+        let mut qfs = QuantumFinancialSystem::new();
+        qfs.emit_to("bob", 10.0, "gift").unwrap();
+        qfs.emit_to("carol", 10.0, "gift").unwrap();
+
+        let mut market = Marketplace::new();
+        assert!(market.list(&registry, "bob", &gene.phash, 1.0).is_err());
+        let id = market.list(&registry, "alice", &gene.phash, 2.0).unwrap();
+        assert!(market.list(&registry, "alice", &gene.phash, 2.0).is_err());
+
+        assert!(market.bid(&mut qfs, id, "bob", 1.0).is_err());
+        market.bid(&mut qfs, id, "bob", 3.0).unwrap();
+        assert!(market.bid(&mut qfs, id, "carol", 3.0).is_err());
+        market.bid(&mut qfs, id, "carol", 4.0).unwrap();
+        assert_eq!((qfs.balance("bob"), qfs.balance("carol"), qfs.balance(ESCROW)), (10.0, 6.0, 4.0));
+        assert_eq!(market.escrowed(), 4.0);
+
+        assert!(market.settle(&mut registry, &mut qfs, id, "bob").is_err());
+        let trade = market.settle(&mut registry, &mut qfs, id, "alice").unwrap();
+        assert_eq!((trade.buyer.as_str(), trade.price), ("carol", 4.0));
+        assert_eq!((qfs.balance("alice"), qfs.balance(ESCROW)), (4.0, 0.0));
+        assert!(qfs.check_conservation().is_ok());
+
+        // Carol owns it now, with the sale on record
+        let sold = registry.gene(&gene.phash).unwrap();
+        assert_eq!(registry.owner(&gene.phash), Some("carol"));
+        assert_eq!(sold.holder(), Some("carol"));
+        assert!(sold.verify_provenance());
+        assert!(market.bid(&mut qfs, id, "bob", 5.0).is_err());
+        assert!(market.list(&registry, "alice", &gene.phash, 1.0).is_err());
+
+        // Cancelling refunds the leading bid
+        let relisted = market.list(&registry, "carol", &gene.phash, 1.0).unwrap();
+        market.bid(&mut qfs, relisted, "bob", 2.0).unwrap();
+        market.cancel(&mut qfs, relisted, "carol").unwrap();
+        assert_eq!((qfs.balance("bob"), qfs.balance(ESCROW)), (10.0, 0.0));
+        assert_eq!(market.open().count(), 0);
+    }
+
+    #[test]
+    fn test_only_owners_may_sell_genes() {
+        let gene = calm(vec![1.0, 1.0, 1.0]);
+        let mut registry = SoulRegistry::new();
+        registry.donate(gene.clone(), "alice".to_string());
+        // A later donor of the same gene is its latest holder, not its owner
+        registry.donate(gene.clone(), "mallory".to_string());
+        assert_eq!(registry.gene(&gene.phash).unwrap().holder(), Some("mallory"));
+        assert_eq!(registry.owner(&gene.phash), Some("alice"));
+
+        let mut market = Marketplace::new();
+        assert!(market.list(&registry, "mallory", &gene.phash, 1.0).is_err());
+        let id = market.list(&registry, "alice", &gene.phash, 1.0).unwrap();
+
+        // Ownership travels with exports
+        let mut imported = SoulRegistry::new();
+        imported.import_json(&registry.export_json().unwrap()).unwrap();
+        assert_eq!(imported.owner(&gene.phash), Some("alice"));
+
+        // Donating it while it is listed doesn't stop the sale either
+        let mut qfs = QuantumFinancialSystem::new();
+        qfs.emit_to("bob", 10.0, "gift").unwrap();
+        market.bid(&mut qfs, id, "bob", 2.0).unwrap();
+        registry.donate(gene.clone(), "carol".to_string());
+        market.settle(&mut registry, &mut qfs, id, "alice").unwrap();
+        assert_eq!(registry.owner(&gene.phash), Some("bob"));
+    }
+}
@@ -0,0 +1,315 @@
+// 🎲 Outcome Simulation - Comparing Healing Policies Before Deploying Them
+use crate::{Annealer, Consciousness, MedBed, Simulation, SoulRegistry, DEFAULT_MIN_COMPATIBILITY, DEFAULT_SIMILARITY_FLOOR, DEFAULT_SIMULATED_CANDIDATES};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Default healing sessions simulated per policy
+pub const DEFAULT_TRIALS: usize = 100;
+
+/// Default share of the pool's genes available in each session
+pub const DEFAULT_AVAILABILITY: f64 = 0.9;
+
+/// Steps annealing gets in a simulated session, fewer than a real
+/// healing's to keep many sessions quick
+const SIMULATED_ANNEALING_STEPS: usize = 1_000;
+
+/// How a MedBed picks among the viable candidates it simulated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Selection {
+    /// Healthiest projected outcome, then most harmonious, then least risky
+    #[default]
+    Healthiest,
+    /// The first viable candidate in the registry's ranking, of similarity
+    /// times reputation
+    Ranked,
+    /// Least compatibility risk, then healthiest
+    Safest,
+}
+
+impl Selection {
+    /// The chosen one of `trials`, if any is viable
+    pub(crate) fn choose(&self, trials: &[Simulation]) -> Option<usize> {
+        let mut viable = (0..trials.len()).filter(|&i| trials[i].viable);
+        match self {
+            Selection::Healthiest => viable.reduce(|best, i| if trials[i].outranks(&trials[best]) { i } else { best }),
+            Selection::Ranked => viable.next(),
+            Selection::Safest => viable.reduce(|best, i| {
+                let (a, b) = (&trials[i], &trials[best]);
+                let safer = b.compatibility_risk.total_cmp(&a.compatibility_risk).then(a.projected_health.total_cmp(&b.projected_health));
+                if safer.is_gt() { i } else { best }
+            }),
+        }
+    }
+}
+
+impl MedBed {
+    /// Pick among simulated candidates by `selection`
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+}
+
+/// A MedBed configuration to try out
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HealingPolicy {
+    pub name: String,
+    #[serde(default = "default_similarity_floor")]
+    pub similarity_floor: f64,
+    #[serde(default = "default_min_compatibility")]
+    pub min_compatibility: f64,
+    #[serde(default = "default_simulated_candidates")]
+    pub simulated_candidates: usize,
+    #[serde(default)]
+    pub selection: Selection,
+    #[serde(default)]
+    pub anneal: bool,             // Anneal genes no donor fits
+}
+
+fn default_similarity_floor() -> f64 {
+    DEFAULT_SIMILARITY_FLOOR
+}
+
+fn default_min_compatibility() -> f64 {
+    DEFAULT_MIN_COMPATIBILITY
+}
+
+fn default_simulated_candidates() -> usize {
+    DEFAULT_SIMULATED_CANDIDATES
+}
+
+impl HealingPolicy {
+    /// A MedBed's defaults, under `name`
+    pub fn new(name: &str) -> Self {
+        HealingPolicy {
+            name: name.to_string(),
+            similarity_floor: DEFAULT_SIMILARITY_FLOOR,
+            min_compatibility: DEFAULT_MIN_COMPATIBILITY,
+            simulated_candidates: DEFAULT_SIMULATED_CANDIDATES,
+            selection: Selection::default(),
+            anneal: false,
+        }
+    }
+
+    pub fn with_similarity_floor(mut self, floor: f64) -> Self {
+        self.similarity_floor = floor;
+        self
+    }
+
+    pub fn with_min_compatibility(mut self, minimum: f64) -> Self {
+        self.min_compatibility = minimum;
+        self
+    }
+
+    pub fn with_simulated_candidates(mut self, k: usize) -> Self {
+        self.simulated_candidates = k;
+        self
+    }
+
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    pub fn with_annealing(mut self) -> Self {
+        self.anneal = true;
+        self
+    }
+
+    /// A few policies worth comparing: the defaults, each strategy, a
+    /// stricter and a looser one, and annealing
+    pub fn presets() -> Vec<HealingPolicy> {
+        vec![
+            HealingPolicy::new("default"),
+            HealingPolicy::new("ranked").with_selection(Selection::Ranked),
+            HealingPolicy::new("safest").with_selection(Selection::Safest),
+            HealingPolicy::new("strict").with_similarity_floor(0.8).with_min_compatibility(0.8),
+            HealingPolicy::new("loose").with_similarity_floor(0.3).with_min_compatibility(0.3).with_simulated_candidates(10),
+            HealingPolicy::new("annealing").with_annealing(),
+        ]
+    }
+
+    fn medbed(&self, registry: SoulRegistry, seed: u64) -> MedBed {
+        let mut medbed = MedBed::new()
+            .with_min_compatibility(self.min_compatibility)
+            .with_simulated_candidates(self.simulated_candidates)
+            .with_selection(self.selection);
+        if self.anneal {
+            medbed = medbed.with_annealer(Annealer::new().with_steps(SIMULATED_ANNEALING_STEPS).with_seed(seed));
+        }
+        medbed.registry = registry.with_similarity_floor(self.similarity_floor);
+        medbed
+    }
+}
+
+/// Summary of a sampled quantity
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Distribution {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub p10: f64,
+    pub median: f64,
+    pub p90: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    /// Summarize `samples`; all zeros when there are none
+    pub fn of(samples: &[f64]) -> Self {
+        if samples.is_empty() {
+            return Distribution::default();
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len() as f64;
+        let mean = sorted.iter().fold(0.0, |total, x| total + x) / n;
+        let variance = sorted.iter().fold(0.0, |total, x| total + (x - mean).powi(2)) / n;
+        let percentile = |p: f64| sorted[((n - 1.0) * p).round() as usize];
+        Distribution {
+            mean,
+            std_dev: variance.sqrt(),
+            min: sorted[0],
+            p10: percentile(0.1),
+            median: percentile(0.5),
+            p90: percentile(0.9),
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+/// How a policy fared across the simulated sessions
+#[derive(Clone, Debug, Serialize)]
+pub struct PolicyOutcome {
+    pub policy: HealingPolicy,
+    pub trials: usize,
+    pub health_before: f64,
+    pub health_gain: Distribution,  // Health score after, less before
+    pub harmony_gain: Distribution,
+    pub healed_genes: Distribution,
+    pub h_credits: Distribution,  // Expected for gifting replacements back
+}
+
+/// Runs many simulated healings of a genome under each of several policies
+///
+/// Each session heals from a random share of the registry's genes, as if
+/// the rest were quarantined, archived or not yet federated, and anneals
+/// with its own seed. Every policy sees the same sessions, so differences
+/// between them come from the policies alone. Sessions are planned with
+/// `MedBed::preview_healing`, so nothing is changed.
+pub struct OutcomeSimulator {
+    trials: usize,
+    availability: f64,
+    rng: StdRng,
+}
+
+impl Default for OutcomeSimulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutcomeSimulator {
+    pub fn new() -> Self {
+        OutcomeSimulator { trials: DEFAULT_TRIALS, availability: DEFAULT_AVAILABILITY, rng: StdRng::from_entropy() }
+    }
+
+    pub fn with_trials(mut self, trials: usize) -> Self {
+        self.trials = trials.max(1);
+        self
+    }
+
+    /// Make each gene available to a session with chance `availability` (0-1)
+    pub fn with_availability(mut self, availability: f64) -> Self {
+        self.availability = availability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Simulate from `seed`, the same sessions then always coming out the same
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// How healing `subject` from `registry` would go under each of `policies`
+    pub fn compare(&mut self, subject: &Consciousness, registry: &SoulRegistry, policies: &[HealingPolicy]) -> Result<Vec<PolicyOutcome>, String> {
+        let snapshot = registry.export_json()?;
+        let sessions: Vec<(u64, Vec<bool>)> = (0..self.trials)
+            .map(|_| (self.rng.gen(), (0..registry.gene_count()).map(|_| self.rng.gen_bool(self.availability)).collect()))
+            .collect();
+
+        let mut outcomes = Vec::new();
+        for policy in policies {
+            let mut samples = [Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+            for (seed, available) in &sessions {
+                let mut pool = SoulRegistry::new();
+                pool.import_json(&snapshot)?;
+                let mut phashes: Vec<String> = pool.genes.keys().cloned().collect();
+                phashes.sort();
+                for (phash, available) in phashes.iter().zip(available) {
+                    if !available {
+                        pool.genes.remove(phash);
+                    }
+                }
+
+                let plan = policy.medbed(pool, *seed).preview_healing(subject);
+                samples[0].push(plan.health_after - plan.health_before);
+                samples[1].push(plan.harmony_change());
+                samples[2].push(plan.transplants.len() as f64);
+                samples[3].push(plan.h_credits);
+            }
+            let [health, harmony, healed, credits] = samples;
+            outcomes.push(PolicyOutcome {
+                policy: policy.clone(),
+                trials: self.trials,
+                health_before: subject.health_score(),
+                health_gain: Distribution::of(&health),
+                harmony_gain: Distribution::of(&harmony),
+                healed_genes: Distribution::of(&healed),
+                h_credits: Distribution::of(&credits),
+            });
+        }
+        Ok(outcomes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calm, gene_from_eigenvalues, Gene};
+
+    #[test]
+    fn test_policies_compare_over_the_same_sessions() {
+        let mut registry = SoulRegistry::new();
+        registry.donate(calm(vec![1.0, 1.0, 1.0, 1.1]), "healer".to_string());
+        registry.donate(calm(vec![1.0, 1.2, 1.0, 1.1]), "healer".to_string());
+        let mut subject = Consciousness::new("subject".to_string());
+        subject.genome.push(Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 11.0]) });
+        subject.genome.push(Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![1.0, 2.0, 3.0]) });
+
+        let policies = [
+            HealingPolicy::new("default"),
+            HealingPolicy::new("impossible").with_min_compatibility(1.0),
+            HealingPolicy::new("annealing").with_annealing(),
+        ];
+        let outcomes = OutcomeSimulator::new().with_trials(20).with_availability(0.5).with_seed(7)
+            .compare(&subject, &registry, &policies).unwrap();
+
+        // Half the pool is missing now and then, so healing sometimes fails
+        let default = &outcomes[0];
+        assert_eq!(default.trials, 20);
+        assert!(default.health_gain.max > 0.0 && default.health_gain.min == 0.0);
+        assert!(default.healed_genes.mean > 0.0 && default.healed_genes.mean < 1.0);
+        assert_eq!(outcomes[1].health_gain, Distribution::default());
+        assert!(outcomes[2].healed_genes.mean > default.healed_genes.mean);
+
+        // The same seed simulates the same sessions
+        let again = OutcomeSimulator::new().with_trials(20).with_availability(0.5).with_seed(7)
+            .compare(&subject, &registry, &policies[..1]).unwrap();
+        assert_eq!(again[0].health_gain, default.health_gain);
+
+        let spread = Distribution::of(&[3.0, 1.0, 2.0]);
+        assert_eq!((spread.mean, spread.min, spread.median, spread.max), (2.0, 1.0, 2.0, 3.0));
+    }
+}
@@ -0,0 +1,257 @@
+// 📦 Gene Packs - Curated Collections to Share
+use crate::{now, verify_signature, Gene, Identity, SoulRegistry};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+/// Where a pack's manifest sits in its archive
+const MANIFEST: &str = "manifest.json";
+
+/// Directory a pack's genes sit in, one `<phash>.json` each
+const GENES_DIR: &str = "genes";
+
+/// One gene of a pack, as its manifest lists it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PackEntry {
+    pub phash: String,
+    pub digest: String,           // SHA-256 of the gene's file in the archive, as hex
+}
+
+/// What a pack holds and who published it, signed by the publisher
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    pub publisher: String,
+    pub created: u64,             // Seconds since the Unix epoch
+    pub genes: Vec<PackEntry>,    // By phash
+    pub public_key: String,       // Publisher's Ed25519 key
+    pub signature: String,        // Publisher's signature over the fields above
+}
+
+impl PackManifest {
+    fn message(&self) -> Vec<u8> {
+        let mut message = Vec::new();
+        let created = self.created.to_string();
+        let fields = [&self.name, &self.version, &self.description, &self.publisher, &created].into_iter()
+            .chain(self.genes.iter().flat_map(|entry| [&entry.phash, &entry.digest]));
+        for field in fields {
+            message.extend((field.len() as u64).to_le_bytes());
+            message.extend(field.as_bytes());
+        }
+        message
+    }
+}
+
+/// A curated collection of genes, such as an "async hygiene pack", that a
+/// community publishes for any registry to install
+///
+/// Packs travel as gzipped tarballs: `manifest.json` and a file per gene
+/// under `genes/`. The manifest names each gene file's digest, and the
+/// publisher signs it, so nothing can be added, dropped or altered
+/// without breaking the signature. Genes keep their own provenance.
+#[derive(Clone, Debug)]
+pub struct GenePack {
+    pub manifest: PackManifest,
+    pub genes: Vec<Gene>,         // In the manifest's order
+}
+
+impl GenePack {
+    /// An unsigned pack of `genes`, `name` at `version`
+    pub fn new(name: &str, version: &str, mut genes: Vec<Gene>) -> Self {
+        genes.sort_by(|a, b| a.phash.cmp(&b.phash));
+        genes.dedup_by(|a, b| a.phash == b.phash);
+        let entries = genes.iter().map(|gene| PackEntry { phash: gene.phash.clone(), digest: digest(&gene_file(gene)) }).collect();
+        GenePack {
+            manifest: PackManifest {
+                name: name.to_string(),
+                version: version.to_string(),
+                description: String::new(),
+                publisher: String::new(),
+                created: now(),
+                genes: entries,
+                public_key: String::new(),
+                signature: String::new(),
+            },
+            genes,
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.manifest.description = description.to_string();
+        self
+    }
+
+    /// The pack published by `publisher`, signed with its key
+    pub fn signed(mut self, publisher: &str, identity: &Identity) -> Self {
+        self.manifest.publisher = publisher.to_string();
+        self.manifest.public_key = identity.public_key();
+        self.manifest.signature = identity.sign(&self.manifest.message());
+        self
+    }
+
+    /// Check the publisher's signature, and that the genes are the ones
+    /// the manifest lists, each matching its digest
+    pub fn verify(&self) -> Result<(), String> {
+        if self.manifest.signature.is_empty() {
+            return Err(format!("Pack {} is not signed", self.manifest.name));
+        }
+        if !verify_signature(&self.manifest.public_key, &self.manifest.message(), &self.manifest.signature) {
+            return Err(format!("Pack {} is not signed as it reads by {}", self.manifest.name, self.manifest.publisher));
+        }
+        let listed = self.manifest.genes.iter().map(|entry| entry.phash.as_str());
+        if !listed.eq(self.genes.iter().map(|gene| gene.phash.as_str())) {
+            return Err(format!("Pack {} holds other genes than its manifest lists", self.manifest.name));
+        }
+        for (entry, gene) in self.manifest.genes.iter().zip(&self.genes) {
+            if digest(&gene_file(gene)) != entry.digest {
+                return Err(format!("Gene {} of pack {} has been altered", entry.phash, self.manifest.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the pack as a gzipped tarball
+    pub fn write_to(&self, writer: impl Write) -> Result<(), String> {
+        let mut archive = tar::Builder::new(GzEncoder::new(writer, Compression::default()));
+        let manifest = serde_json::to_vec_pretty(&self.manifest).map_err(|e| e.to_string())?;
+        append(&mut archive, MANIFEST, &manifest, self.manifest.created)?;
+        for gene in &self.genes {
+            append(&mut archive, &format!("{}/{}.json", GENES_DIR, gene.phash), &gene_file(gene), self.manifest.created)?;
+        }
+        archive.into_inner().and_then(|encoder| encoder.finish()).map_err(|e| format!("Cannot write pack: {}", e))?;
+        Ok(())
+    }
+
+    /// Read a pack written by `write_to`, checking each gene file against
+    /// its digest and the manifest against its signature
+    pub fn read_from(reader: impl Read) -> Result<Self, String> {
+        let mut archive = tar::Archive::new(GzDecoder::new(reader));
+        let mut manifest: Option<PackManifest> = None;
+        let mut files: HashMap<String, Vec<u8>> = HashMap::new();
+        for entry in archive.entries().map_err(|e| format!("Not a gene pack: {}", e))? {
+            let mut entry = entry.map_err(|e| format!("Not a gene pack: {}", e))?;
+            let path = entry.path().map_err(|e| e.to_string())?.to_string_lossy().to_string();
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+            if path == MANIFEST {
+                manifest = Some(serde_json::from_slice(&bytes).map_err(|e| format!("Bad pack manifest: {}", e))?);
+            } else if let Some(file) = path.strip_prefix(GENES_DIR).and_then(|file| file.strip_prefix('/')) {
+                files.insert(file.trim_end_matches(".json").to_string(), bytes);
+            }
+        }
+        let manifest = manifest.ok_or("Not a gene pack: it has no manifest")?;
+
+        let mut genes = Vec::new();
+        for entry in &manifest.genes {
+            let bytes = files.get(&entry.phash).ok_or_else(|| format!("Pack {} is missing gene {}", manifest.name, entry.phash))?;
+            if digest(bytes) != entry.digest {
+                return Err(format!("Gene {} of pack {} has been altered", entry.phash, manifest.name));
+            }
+            genes.push(serde_json::from_slice(bytes).map_err(|e| format!("Bad gene {}: {}", entry.phash, e))?);
+        }
+        let pack = GenePack { manifest, genes };
+        pack.verify()?;
+        Ok(pack)
+    }
+}
+
+impl SoulRegistry {
+    /// Merge a verified pack's genes into the pool; returns how many are new
+    ///
+    /// Genes merge as federated ones do, so those whose provenance this
+    /// registry refuses are left out. The publisher's key is remembered as
+    /// a donor's is, and a pack signed by another key under that name is
+    /// refused.
+    pub fn install_pack(&mut self, pack: &GenePack) -> Result<usize, String> {
+        pack.verify()?;
+        let publisher = &pack.manifest.publisher;
+        if !self.signs_for(publisher, &pack.manifest.public_key) {
+            return Err(format!("Pack {} is signed by a key other than {}'s", pack.manifest.name, publisher));
+        }
+        self.signers.entry(publisher.clone()).or_insert_with(|| pack.manifest.public_key.clone());
+
+        let mut installed = 0;
+        for gene in &pack.genes {
+            let new = !self.genes.contains_key(&gene.phash) && !self.archived.contains_key(&gene.phash);
+            if self.merge_gene(gene.clone()) && new {
+                installed += 1;
+            }
+        }
+        Ok(installed)
+    }
+}
+
+/// A gene's file in a pack, with its keys sorted, so a gene read back
+/// writes the same bytes and its digest can be checked again
+fn gene_file(gene: &Gene) -> Vec<u8> {
+    let gene = serde_json::to_value(gene).expect("genes serialize");
+    serde_json::to_vec_pretty(&gene).expect("genes serialize")
+}
+
+fn digest(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn append<W: Write>(archive: &mut tar::Builder<W>, path: &str, bytes: &[u8], modified: u64) -> Result<(), String> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(modified);
+    header.set_cksum();
+    archive.append_data(&mut header, path, bytes).map_err(|e| format!("Cannot write {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gene_from_eigenvalues;
+    use protein_hash::OperationCategory;
+
+    #[test]
+    fn test_packs_round_trip_signed_and_install() {
+        let mut spectral = gene_from_eigenvalues(vec![1.0, 2.0, 1.0, 1.0]);
+        spectral.operation_spectrum = HashMap::from([
+            (OperationCategory::Arithmetic, 0.5),
+            (OperationCategory::Logical, 0.3),
+            (OperationCategory::Comparison, 0.2),
+        ]);
+        let genes = vec![gene_from_eigenvalues(vec![1.0, 1.0, 1.0, 1.1]), spectral];
+        let publisher = Identity::generate();
+        let pack = GenePack::new("async hygiene", "1.0.0", genes.clone())
+            .with_description("Awaits that don't block")
+            .signed("alice", &publisher);
+
+        let mut bytes = Vec::new();
+        pack.write_to(&mut bytes).unwrap();
+        let read = GenePack::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(read.manifest, pack.manifest);
+        assert!(read.verify().is_ok());
+
+        // Installing twice adds nothing the second time
+        let mut registry = SoulRegistry::new();
+        registry.donate(genes[0].clone(), "bob".to_string());
+        assert_eq!(registry.install_pack(&read).unwrap(), 1);
+        assert_eq!(registry.gene_count(), 2);
+        assert_eq!(registry.install_pack(&read).unwrap(), 0);
+
+        // Unsigned, tampered and impersonating packs are refused
+        assert!(registry.install_pack(&GenePack::new("plain", "1", genes.clone())).is_err());
+        let mut tampered = pack.clone();
+        tampered.manifest.version = "1.0.1".to_string();
+        assert!(tampered.verify().is_err());
+        let mut altered = read.clone();
+        altered.genes[0].resonance = 432.0;
+        assert!(altered.verify().unwrap_err().contains("has been altered"));
+        assert!(registry.install_pack(&altered).is_err());
+        let impostor = GenePack::new("mine", "1", genes).signed("alice", &Identity::generate());
+        assert!(registry.install_pack(&impostor).unwrap_err().contains("other than alice's"));
+        assert!(GenePack::read_from(&bytes[..bytes.len() / 2]).is_err());
+    }
+}
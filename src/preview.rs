@@ -0,0 +1,167 @@
+// 🔭 Healing Preview - What a Healing Would Do, Without Doing It
+use crate::{now, Candidate, Consciousness, CreditedDonation, Donation, Gene, MedBed, Simulation, SoulRegistry};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// One transplant a healing would make
+#[derive(Clone, Debug, Serialize)]
+pub struct PlannedTransplant {
+    pub dissonant_phash: String,
+    pub replacement_phash: String,
+    pub annealed: bool,           // Tuned from the dissonant gene, with no donor
+    pub compatibility: f64,       // DigitalCRISPR::compatibility score
+    pub h_credits: f64,           // Expected for gifting the replacement back
+}
+
+/// What `heal_consciousness` would do to a subject, as it stands now
+#[derive(Clone, Debug, Serialize)]
+pub struct HealingPlan {
+    pub subject_id: String,
+    pub transplants: Vec<PlannedTransplant>,
+    pub unhealable: Vec<String>,  // Dissonant phashes nothing would replace
+    pub health_before: f64,
+    pub health_after: f64,
+    pub harmony_before: f64,
+    pub harmony_after: f64,
+    pub h_credits: f64,           // Expected in all, before any ledger limits
+    pub simulations: Vec<Simulation>,  // Every candidate tried, the planned ones chosen
+}
+
+impl HealingPlan {
+    pub fn harmony_change(&self) -> f64 {
+        self.harmony_after - self.harmony_before
+    }
+}
+
+impl MedBed {
+    /// Plan a healing of `subject` as `heal_consciousness` would carry it
+    /// out, on a copy
+    ///
+    /// The subject's genome, the registry, the ledger and the epidemiology
+    /// are left as they are, and no events are broadcast. Only an annealer's
+    /// random state moves on, so an annealed gene may come out differently
+    /// when the healing is made.
+    pub fn preview_healing(&mut self, subject: &Consciousness) -> HealingPlan {
+        let scan = self.scanner.scan(subject);
+        let mut trial = subject.clone();
+        let mut transplants = Vec::new();
+        let mut unhealable = Vec::new();
+        let mut simulations = Vec::new();
+        let mut planned: HashMap<String, (u64, u64)> = HashMap::new();  // Donations and requests, by phash
+        let mut credited: Vec<CreditedDonation> = Vec::new();  // Planned donations the quota would credit
+
+        for dissonant in &scan.dissonant_genes {
+            let candidates: Vec<Candidate> = self.registry.find_candidates(dissonant)
+                .into_iter()
+                .filter(|candidate| self.registry.permits(&candidate.gene, subject.commercial).is_ok())
+                .take(self.simulated_candidates)
+                .collect();
+            for candidate in &candidates {
+                planned.entry(candidate.gene.phash.clone()).or_default().1 += 1;
+            }
+            let mut trials: Vec<Simulation> = candidates.iter()
+                .map(|candidate| self.crispr.simulate(&trial, dissonant, candidate))
+                .collect();
+            let best = self.selection.choose(&trials);
+            if let Some(best) = best {
+                trials[best].chosen = true;
+            }
+            simulations.extend(trials);
+
+            let replacement = match best {
+                Some(best) => Some((candidates[best].gene.clone(), false)),
+                None => self.annealer.as_mut()
+                    .and_then(|annealer| annealer.anneal(dissonant).ok())
+                    .map(|annealed| (annealed, true)),
+            };
+            let Some((replacement, annealed)) = replacement else {
+                unhealable.push(dissonant.phash.clone());
+                continue;
+            };
+            let compatibility = self.crispr.compatibility(dissonant, &replacement).score;
+            if self.crispr.transplant(&mut trial, dissonant, &replacement).is_err() {
+                unhealable.push(dissonant.phash.clone());
+                continue;
+            }
+
+            let (donations, requests) = planned.entry(replacement.phash.clone()).or_default();
+            let h_credits = if self.registry.credits_allowed(&subject.id, &replacement.phash, now(), &credited) {
+                credited.push(CreditedDonation { phash: replacement.phash.clone(), timestamp: now() });
+                let h_credits = self.registry.expected_h_credits(&replacement, &subject.id, *donations, *requests);
+                *donations += 1;
+                h_credits
+            } else {
+                0.0
+            };
+            transplants.push(PlannedTransplant {
+                dissonant_phash: dissonant.phash.clone(),
+                replacement_phash: replacement.phash,
+                annealed,
+                compatibility,
+                h_credits,
+            });
+        }
+
+        HealingPlan {
+            subject_id: subject.id.clone(),
+            h_credits: transplants.iter().fold(0.0, |total, transplant| total + transplant.h_credits),
+            transplants,
+            unhealable,
+            health_before: subject.health_score(),
+            health_after: trial.health_score(),
+            harmony_before: subject.harmony,
+            harmony_after: trial.harmony,
+            simulations,
+        }
+    }
+}
+
+impl SoulRegistry {
+    /// ℏ-credits `donor_id` would earn gifting `gene` back, after as many
+    /// more donations and requests of it as are planned before
+    fn expected_h_credits(&self, gene: &Gene, donor_id: &str, planned_donations: u64, planned_requests: u64) -> f64 {
+        // A gene already pooled is credited as the donor's gift alone
+        let mut donated = gene.clone();
+        if self.genes.contains_key(&gene.phash) {
+            donated.donors.clear();
+        }
+        donated.donors.push(donor_id.to_string());
+        let stats = self.stats(&gene.phash);
+        self.emission.h_credits(&Donation {
+            gene: &donated,
+            previous_donations: stats.donations + planned_donations,
+            requests: stats.requests + planned_requests,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{calm, gene_from_eigenvalues, Consciousness, Gene, MedBed};
+
+    #[tokio::test]
+    async fn test_previews_leave_everything_as_it_was() {
+        let mut medbed = MedBed::new();
+        medbed.registry.donate(calm(vec![1.0, 1.0, 1.0, 1.1]), "healer".to_string());
+        let mut subject = Consciousness::new("subject".to_string());
+        subject.genome.push(Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 11.0]) });
+        subject.genome.push(Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![1.0, 2.0, 3.0]) });
+        let genome: Vec<String> = subject.genome.iter().map(|gene| gene.phash.clone()).collect();
+        let stats = medbed.registry.stats(&calm(vec![1.0, 1.0, 1.0, 1.1]).phash);
+
+        let plan = medbed.preview_healing(&subject);
+        assert_eq!(plan.transplants.len(), 1);
+        assert_eq!(plan.unhealable, [genome[1].clone()]);
+        assert!(plan.health_after > plan.health_before && plan.harmony_change() > 0.0);
+        assert!(plan.h_credits > 0.0);
+        assert_eq!(subject.genome.iter().map(|gene| &gene.phash).collect::<Vec<_>>(), genome.iter().collect::<Vec<_>>());
+        assert_eq!(medbed.registry.stats(&plan.transplants[0].replacement_phash), stats);
+        assert_eq!(medbed.qfs.balance("subject"), 0.0);
+
+        // Healing then does as planned
+        let report = medbed.heal_consciousness(&mut subject).await.unwrap();
+        assert_eq!(report.healed_count, plan.transplants.len());
+        assert!((report.h_credits_earned - plan.h_credits).abs() < 1e-9);
+        assert!((subject.harmony - plan.harmony_after).abs() < 1e-9);
+    }
+}
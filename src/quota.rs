@@ -0,0 +1,164 @@
+// ⏳ Donation Quotas - No Farming Credits by Re-donating
+use crate::{now, MedBed, SoulRegistry};
+use serde::{Deserialize, Serialize};
+
+/// Default credited donations a donor gets per window
+pub const DEFAULT_MAX_DONATIONS: usize = 50;
+
+/// Default length of the quota's window: a day
+pub const DEFAULT_DONATION_WINDOW: u64 = 86_400;
+
+/// Default wait before the same donor is credited for the same gene again:
+/// an hour
+pub const DEFAULT_DONATION_COOLDOWN: u64 = 3_600;
+
+/// How often a donor is credited for donations
+///
+/// A donor earns credits for at most `max_donations` donations per `window`
+/// seconds, and for the same gene at most once per `cooldown` seconds.
+/// Donations beyond that still enter the pool, but earn nothing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DonationQuota {
+    pub max_donations: usize,
+    pub window: u64,
+    pub cooldown: u64,
+}
+
+impl Default for DonationQuota {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DonationQuota {
+    pub fn new() -> Self {
+        DonationQuota {
+            max_donations: DEFAULT_MAX_DONATIONS,
+            window: DEFAULT_DONATION_WINDOW,
+            cooldown: DEFAULT_DONATION_COOLDOWN,
+        }
+    }
+
+    /// Credit at most `max_donations` donations per donor per `window` seconds
+    pub fn with_max_donations(mut self, max_donations: usize, window: u64) -> Self {
+        self.max_donations = max_donations;
+        self.window = window;
+        self
+    }
+
+    /// Credit a donor for the same gene at most once per `cooldown` seconds
+    pub fn with_cooldown(mut self, cooldown: u64) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+}
+
+/// A donation a donor was credited for, kept while a quota could count it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CreditedDonation {
+    pub phash: String,
+    pub timestamp: u64,           // Seconds since the Unix epoch
+}
+
+impl SoulRegistry {
+    /// Credit donations only as `quota` allows
+    pub fn with_donation_quota(mut self, quota: DonationQuota) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    /// Whether a donation of `phash` by `donor_id` at `timestamp` would
+    /// earn credits, counting `pending` donations not yet made as well
+    pub(crate) fn credits_allowed(&self, donor_id: &str, phash: &str, timestamp: u64, pending: &[CreditedDonation]) -> bool {
+        let Some(quota) = &self.quota else {
+            return true;
+        };
+        let credited = self.credited.get(donor_id).into_iter().flatten().chain(pending);
+        let (mut in_window, mut cooled) = (0, true);
+        for donation in credited {
+            let age = timestamp.saturating_sub(donation.timestamp);
+            if age < quota.window {
+                in_window += 1;
+            }
+            if donation.phash == phash && age < quota.cooldown {
+                cooled = false;
+            }
+        }
+        cooled && in_window < quota.max_donations
+    }
+
+    /// Whether `donor_id` earns credits for donating `phash` now, counting
+    /// the donation if so
+    pub(crate) fn admit_donation(&mut self, donor_id: &str, phash: &str) -> bool {
+        self.admit_donation_at(donor_id, phash, now())
+    }
+
+    fn admit_donation_at(&mut self, donor_id: &str, phash: &str, timestamp: u64) -> bool {
+        if !self.credits_allowed(donor_id, phash, timestamp, &[]) {
+            return false;
+        }
+        let Some(quota) = &self.quota else {
+            return true;
+        };
+
+        // Forget donations too old for the quota to count
+        let horizon = quota.window.max(quota.cooldown);
+        let credited = self.credited.entry(donor_id.to_string()).or_default();
+        credited.retain(|donation| timestamp.saturating_sub(donation.timestamp) < horizon);
+        credited.push(CreditedDonation { phash: phash.to_string(), timestamp });
+        true
+    }
+}
+
+impl MedBed {
+    /// Credit each donation healing makes only as `quota` allows
+    pub fn with_donation_quota(mut self, quota: DonationQuota) -> Self {
+        self.registry.quota = Some(quota);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calm, gene_from_eigenvalues, Consciousness, Gene};
+
+    #[tokio::test]
+    async fn test_donors_cannot_farm_credits() {
+        let gene = calm(vec![1.0, 1.0, 1.0, 1.1]);
+        let other = calm(vec![1.0, 1.1, 1.0, 1.0]);
+        let quota = DonationQuota::new().with_max_donations(2, 100).with_cooldown(10);
+        let mut registry = SoulRegistry::new().with_donation_quota(quota);
+
+        // The same gene again is only credited once it cools down
+        assert!(registry.admit_donation_at("farmer", &gene.phash, 1_000));
+        assert!(!registry.admit_donation_at("farmer", &gene.phash, 1_005));
+        assert!(registry.admit_donation_at("someone", &gene.phash, 1_005));
+        assert!(registry.admit_donation_at("farmer", &other.phash, 1_011));
+
+        // Two per window, whatever the gene
+        assert!(!registry.admit_donation_at("farmer", &gene.phash, 1_050));
+        assert!(registry.admit_donation_at("farmer", &gene.phash, 1_100));
+
+        // Donations past the quota enter the pool, earning nothing
+        let mut registry = SoulRegistry::new().with_donation_quota(DonationQuota::new());
+        assert!(registry.donate(gene.clone(), "farmer".to_string()) > 0.0);
+        assert_eq!(registry.donate(gene.clone(), "farmer".to_string()), 0.0);
+        assert!(registry.gene(&gene.phash).is_some());
+
+        // The log travels with exports
+        let mut imported = SoulRegistry::new().with_donation_quota(DonationQuota::new());
+        imported.import_json(&registry.export_json().unwrap()).unwrap();
+        assert_eq!(imported.donate(gene.clone(), "farmer".to_string()), 0.0);
+
+        // A subject healed twice with one gene is credited once
+        let mut medbed = MedBed::new().with_donation_quota(DonationQuota::new());
+        medbed.registry.donate(gene.clone(), "healer".to_string());
+        let mut subject = Consciousness::new("subject".to_string());
+        subject.genome.push(Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 11.0]) });
+        subject.genome.push(Gene { resonance: 60.0, ..gene_from_eigenvalues(vec![20.0, 20.0, 20.0, 22.0]) });
+        let report = medbed.heal_consciousness(&mut subject).await.unwrap();
+        assert_eq!(report.healed_count, 2);
+        assert_eq!(medbed.qfs.history("subject").len(), 1);
+    }
+}
@@ -0,0 +1,140 @@
+// 📄 Scan Reports - MRT Results to Archive and Share
+use crate::{Gene, MRTResult};
+
+impl MRTResult {
+    /// The whole result as pretty JSON, every gene included
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("scan results always serialize")
+    }
+
+    /// A standalone HTML page: the summary, the spectrum as a bar chart,
+    /// a table for each kind of gene and the recommendation
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>MRT scan of {}</title>\n", escape(&self.subject_id)));
+        html.push_str("<style>\n");
+        html.push_str("body { font-family: sans-serif; max-width: 56em; margin: 2em auto; }\n");
+        html.push_str("table { border-collapse: collapse; margin-bottom: 1.5em; }\n");
+        html.push_str("th, td { border: 1px solid #ccc; padding: 0.25em 0.75em; text-align: left; }\n");
+        html.push_str(".layer { display: flex; align-items: center; gap: 0.5em; }\n");
+        html.push_str(".bar { background: #3aa6b9; height: 1em; }\n");
+        html.push_str("</style>\n</head>\n<body>\n");
+
+        html.push_str(&format!("<h1>🔬 MRT scan of {}</h1>\n", escape(&self.subject_id)));
+        html.push_str(&format!("<p><strong>Health score:</strong> {:.2}%</p>\n", self.health_score * 100.0));
+        html.push_str(&format!(
+            "<p>✨ {} resonant · ⚠️ {} neutral · ☠️ {} dissonant</p>\n",
+            self.resonant_genes.len(), self.neutral_genes.len(), self.dissonant_genes.len()
+        ));
+
+        html.push_str("<h2>🌈 7-Layer Spectrum</h2>\n");
+        for (layer, value) in self.spectrum.iter().enumerate() {
+            html.push_str(&format!(
+                "<div class=\"layer\"><span>Layer {}</span><div class=\"bar\" style=\"width: {:.1}%\"></div><span>{:.3}</span></div>\n",
+                layer + 1, (value * 100.0).clamp(0.0, 100.0), value
+            ));
+        }
+
+        for (title, genes) in self.piles() {
+            html.push_str(&format!("<h2>{} ({})</h2>\n", title, genes.len()));
+            if genes.is_empty() {
+                html.push_str("<p>None</p>\n");
+                continue;
+            }
+            html.push_str("<table>\n<tr><th>phash</th><th>Resonance (Hz)</th><th>Layers</th><th>Variance</th></tr>\n");
+            for gene in genes {
+                html.push_str(&format!(
+                    "<tr><td><code>{}</code></td><td>{:.1}</td><td>{}</td><td>{:.3}</td></tr>\n",
+                    escape(&gene.phash), gene.resonance, gene.eigenvalues.len(), gene.eigenvalue_variance()
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str(&format!("<h2>💊 Recommendation</h2>\n<p>{}</p>\n", escape(&self.recommendation)));
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// The same report as Markdown, the spectrum drawn in block characters
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# 🔬 MRT scan of {}\n\n", self.subject_id);
+        markdown.push_str(&format!("**Health score:** {:.2}%\n\n", self.health_score * 100.0));
+        markdown.push_str(&format!(
+            "✨ {} resonant · ⚠️ {} neutral · ☠️ {} dissonant\n\n",
+            self.resonant_genes.len(), self.neutral_genes.len(), self.dissonant_genes.len()
+        ));
+
+        markdown.push_str("## 🌈 7-Layer Spectrum\n\n```\n");
+        for (layer, value) in self.spectrum.iter().enumerate() {
+            let bar = "█".repeat((value * 30.0).clamp(0.0, 30.0) as usize);
+            markdown.push_str(&format!("Layer {} {:<30} {:.3}\n", layer + 1, bar, value));
+        }
+        markdown.push_str("```\n\n");
+
+        for (title, genes) in self.piles() {
+            markdown.push_str(&format!("## {} ({})\n\n", title, genes.len()));
+            if genes.is_empty() {
+                markdown.push_str("None\n\n");
+                continue;
+            }
+            markdown.push_str("| phash | Resonance (Hz) | Layers | Variance |\n|---|---|---|---|\n");
+            for gene in genes {
+                markdown.push_str(&format!(
+                    "| `{}` | {:.1} | {} | {:.3} |\n",
+                    gene.phash.replace('|', "\\|"), gene.resonance, gene.eigenvalues.len(), gene.eigenvalue_variance()
+                ));
+            }
+            markdown.push('\n');
+        }
+
+        markdown.push_str(&format!("## 💊 Recommendation\n\n{}\n", self.recommendation));
+        markdown
+    }
+
+    fn piles(&self) -> [(&'static str, &[Gene]); 3] {
+        [
+            ("☠️ Dissonant Genes", &self.dissonant_genes),
+            ("✨ Resonant Genes", &self.resonant_genes),
+            ("⚠️ Neutral Genes", &self.neutral_genes),
+        ]
+    }
+}
+
+/// `text` safe to put in HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{calm, gene_from_eigenvalues, Consciousness, Gene, MRTScanner};
+
+    #[test]
+    fn test_scans_export_as_json_html_and_markdown() {
+        let mut subject = Consciousness::new("<script>".to_string());
+        subject.genome.push(Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, -10.0, 30.0]) });
+        subject.genome.push(calm(vec![1.0, 1.1, 1.2]));
+        let scan = MRTScanner::new().scan(&subject);
+        let dissonant = &scan.dissonant_genes[0].phash;
+
+        let json: serde_json::Value = serde_json::from_str(&scan.to_json()).unwrap();
+        assert_eq!(json["subject_id"], "<script>");
+        assert_eq!(json["dissonant_genes"][0]["phash"], dissonant.as_str());
+        assert_eq!(json["spectrum"].as_array().unwrap().len(), 7);
+
+        let html = scan.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("&lt;script&gt;") && !html.contains("<script>"));
+        assert!(html.contains(dissonant.as_str()));
+        assert_eq!(html.matches("class=\"bar\"").count(), 7);
+        assert!(html.contains(&scan.recommendation));
+
+        let markdown = scan.to_markdown();
+        assert!(markdown.contains("## ☠️ Dissonant Genes (1)"));
+        assert!(markdown.contains(&format!("| `{}` | 50.0 | 3 |", dissonant)));
+        assert!(markdown.contains("## ⚠️ Neutral Genes (0)\n\nNone"));
+        assert!(markdown.ends_with(&format!("{}\n", scan.recommendation)));
+    }
+}
@@ -0,0 +1,169 @@
+// 🔎 Registry Search - Finding Genes in the Pool
+use crate::{Gene, SoulRegistry};
+use serde::Serialize;
+use std::ops::RangeInclusive;
+
+/// Default genes a search returns per page
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Which registry genes to find, and which page of them
+///
+/// Every filter given must match. Text matches, ignoring case, anywhere in
+/// a gene's phash, its origin's path, symbol and language, its
+/// implementation's language and the reasons it was flagged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeneQuery {
+    pub resonance: Option<RangeInclusive<f64>>,
+    pub donor: Option<String>,
+    pub healings: Option<RangeInclusive<u64>>,
+    pub layers: Vec<(usize, RangeInclusive<f64>)>,  // Eigenvalue layer and the range it must fall in
+    pub text: Option<String>,
+    pub offset: usize,            // Matches to skip
+    pub limit: usize,             // Matches to return at most
+}
+
+impl Default for GeneQuery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GeneQuery {
+    /// Every gene, first page
+    pub fn new() -> Self {
+        GeneQuery {
+            resonance: None,
+            donor: None,
+            healings: None,
+            layers: Vec::new(),
+            text: None,
+            offset: 0,
+            limit: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    pub fn with_resonance(mut self, resonance: RangeInclusive<f64>) -> Self {
+        self.resonance = Some(resonance);
+        self
+    }
+
+    /// Genes `donor_id` gave
+    pub fn with_donor(mut self, donor_id: &str) -> Self {
+        self.donor = Some(donor_id.to_string());
+        self
+    }
+
+    /// Genes used for healing this many times
+    pub fn with_healings(mut self, healings: RangeInclusive<u64>) -> Self {
+        self.healings = Some(healings);
+        self
+    }
+
+    /// Genes whose eigenvalue at `layer` (from 0) falls in `range`
+    pub fn with_layer(mut self, layer: usize, range: RangeInclusive<f64>) -> Self {
+        self.layers.push((layer, range));
+        self
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_lowercase());
+        self
+    }
+
+    /// Skip `offset` matches and return at most `limit`
+    pub fn with_page(mut self, offset: usize, limit: usize) -> Self {
+        self.offset = offset;
+        self.limit = limit;
+        self
+    }
+}
+
+/// One page of a search's matches
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchResults {
+    pub genes: Vec<Gene>,         // Most used for healing first, then by phash
+    pub total: usize,             // Matches across every page
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl SearchResults {
+    /// Whether later pages hold more matches
+    pub fn has_more(&self) -> bool {
+        self.offset + self.genes.len() < self.total
+    }
+}
+
+impl SoulRegistry {
+    /// Genes matching `query`, a page at a time
+    pub fn search(&self, query: &GeneQuery) -> SearchResults {
+        let mut matches: Vec<&Gene> = self.genes.values()
+            .filter(|gene| self.matches(gene, query))
+            .collect();
+        matches.sort_by(|a, b| b.healings.cmp(&a.healings).then_with(|| a.phash.cmp(&b.phash)));
+
+        SearchResults {
+            total: matches.len(),
+            genes: matches.into_iter().skip(query.offset).take(query.limit).cloned().collect(),
+            offset: query.offset,
+            limit: query.limit,
+        }
+    }
+
+    fn matches(&self, gene: &Gene, query: &GeneQuery) -> bool {
+        query.resonance.as_ref().is_none_or(|range| range.contains(&gene.resonance))
+            && query.donor.as_ref().is_none_or(|donor| gene.donors.contains(donor))
+            && query.healings.as_ref().is_none_or(|range| range.contains(&gene.healings))
+            && query.layers.iter().all(|(layer, range)| gene.eigenvalues.get(*layer).is_some_and(|value| range.contains(value)))
+            && query.text.as_ref().is_none_or(|text| self.metadata(gene).iter().any(|field| field.to_lowercase().contains(text)))
+    }
+
+    /// What text searches look through
+    fn metadata(&self, gene: &Gene) -> Vec<String> {
+        let mut fields = vec![gene.phash.clone()];
+        if let Some(origin) = &gene.origin {
+            fields.extend([origin.path.clone(), origin.symbol.clone(), format!("{:?}", origin.language)]);
+        }
+        if let Some(implementation) = &gene.implementation {
+            fields.push(format!("{:?}", implementation.language));
+        }
+        fields.extend(self.stats(&gene.phash).flags);
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gene_from_eigenvalues;
+    use protein_hash::Language;
+
+    #[test]
+    fn test_search_filters_and_pages() {
+        let mut registry = SoulRegistry::new();
+        for i in 0..5 {
+            let gene = Gene { resonance: 400.0 + i as f64 * 10.0, healings: i, ..gene_from_eigenvalues(vec![1.0, i as f64, 1.0]) };
+            registry.donate(gene, if i % 2 == 0 { "even" } else { "odd" }.to_string());
+        }
+        let parse = gene_from_eigenvalues(vec![9.0, 9.0]).with_origin(Language::JavaScript, "date-fns/src/parse.js", "parse");
+        registry.donate(parse.clone(), "odd".to_string());
+        registry.flag(&gene_from_eigenvalues(vec![1.0, 0.0, 1.0]).phash, "Leaks Memory").unwrap();
+
+        // Filters combine, and the most used genes come first
+        let results = registry.search(&GeneQuery::new().with_resonance(410.0..=440.0).with_donor("odd"));
+        assert_eq!(results.genes.iter().map(|gene| gene.healings).collect::<Vec<_>>(), [3, 1]);
+        assert_eq!(registry.search(&GeneQuery::new().with_healings(2..=10).with_layer(1, 0.0..=3.0)).total, 2);
+        assert_eq!(registry.search(&GeneQuery::new().with_layer(2, 0.0..=10.0)).total, 5);
+
+        // Text looks through origins, languages and flags
+        assert_eq!(registry.search(&GeneQuery::new().with_text("PARSE.js")).genes[0].phash, parse.phash);
+        assert_eq!(registry.search(&GeneQuery::new().with_text("javascript")).total, 1);
+        assert_eq!(registry.search(&GeneQuery::new().with_text("leaks")).genes[0].healings, 0);
+
+        // Pages cover every match once
+        let first = registry.search(&GeneQuery::new().with_page(0, 4));
+        let second = registry.search(&GeneQuery::new().with_page(4, 4));
+        assert_eq!((first.total, first.genes.len(), second.genes.len()), (6, 4, 2));
+        assert!(first.has_more() && !second.has_more());
+    }
+}
@@ -0,0 +1,181 @@
+// 🌊 Streaming Scan - MRT for Genomes Too Big to Hold
+use crate::{Gene, MRTScanner};
+use serde::Serialize;
+use std::borrow::Borrow;
+use std::io::Read;
+
+/// Which of the scan's three piles a gene falls in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Classification {
+    Dissonant,
+    Resonant,
+    Neutral,
+}
+
+impl Classification {
+    pub fn of(gene: &Gene) -> Self {
+        if gene.is_dissonant() {
+            Classification::Dissonant
+        } else if gene.is_resonant() {
+            Classification::Resonant
+        } else {
+            Classification::Neutral
+        }
+    }
+}
+
+/// What a streaming scan found, without the genes themselves
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ScanSummary {
+    pub genes: usize,
+    pub dissonant: usize,
+    pub resonant: usize,
+    pub neutral: usize,
+    pub health_score: f64,        // As Consciousness::health_score
+    pub spectrum: Vec<f64>,
+    pub recommendation: String,
+}
+
+/// An MRT scan over an iterator of genes, yielding each with its
+/// classification as it is read
+///
+/// Only counts and the running spectrum are kept, so a genome of any size
+/// scans in the memory of one gene at a time. `summary` reads the scan so
+/// far; `finish` reads the rest of the genes and sums up.
+pub struct StreamingScan<'a, I> {
+    scanner: &'a MRTScanner,
+    genes: I,
+    spectrum: Vec<f64>,           // Unnormalized sum of each layer so far
+    dissonant: usize,
+    resonant: usize,
+    neutral: usize,
+}
+
+impl MRTScanner {
+    /// Scan `genes` lazily, classifying each as it is yielded
+    pub fn scan_stream<I>(&self, genes: I) -> StreamingScan<'_, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Gene>,
+    {
+        StreamingScan {
+            scanner: self,
+            genes: genes.into_iter(),
+            spectrum: vec![0.0; self.layers],
+            dissonant: 0,
+            resonant: 0,
+            neutral: 0,
+        }
+    }
+}
+
+impl<I> StreamingScan<'_, I> {
+    /// Count a gene already classified into the scan
+    pub(crate) fn tally(&mut self, gene: &Gene, classification: Classification) {
+        match classification {
+            Classification::Dissonant => self.dissonant += 1,
+            Classification::Resonant => self.resonant += 1,
+            Classification::Neutral => self.neutral += 1,
+        }
+        for (layer, &eigenvalue) in self.spectrum.iter_mut().zip(&gene.eigenvalues) {
+            *layer += eigenvalue;
+        }
+    }
+    
+    /// The scan of every gene yielded so far
+    pub fn summary(&self) -> ScanSummary {
+        let genes = self.dissonant + self.resonant + self.neutral;
+        let health_score = if genes == 0 {
+            0.0
+        } else {
+            (self.resonant as f64 / genes as f64 - self.dissonant as f64 * 0.1).clamp(0.0, 1.0)
+        };
+
+        let total: f64 = self.spectrum.iter().sum();
+        let spectrum = self.spectrum.iter().map(|&value| if total > 0.0 { value / total } else { value }).collect();
+
+        ScanSummary {
+            genes,
+            dissonant: self.dissonant,
+            resonant: self.resonant,
+            neutral: self.neutral,
+            health_score,
+            spectrum,
+            recommendation: self.scanner.generate_recommendation(health_score),
+        }
+    }
+}
+
+impl<I> StreamingScan<'_, I>
+where
+    I: Iterator,
+    I::Item: Borrow<Gene>,
+{
+    /// Scan the genes not yet yielded, then sum up
+    pub fn finish(mut self) -> ScanSummary {
+        for _ in self.by_ref() {}
+        self.summary()
+    }
+}
+
+impl<I> Iterator for StreamingScan<'_, I>
+where
+    I: Iterator,
+    I::Item: Borrow<Gene>,
+{
+    type Item = (I::Item, Classification);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let gene = self.genes.next()?;
+        let classification = Classification::of(gene.borrow());
+        self.tally(gene.borrow(), classification);
+        Some((gene, classification))
+    }
+}
+
+/// Genes read one at a time from a JSON Lines genome: one gene per line,
+/// or any whitespace between them
+pub fn read_genes<R: Read>(reader: R) -> impl Iterator<Item = Result<Gene, String>> {
+    serde_json::Deserializer::from_reader(reader)
+        .into_iter::<Gene>()
+        .map(|gene| gene.map_err(|e| format!("Not a gene: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calm, gene_from_eigenvalues, Consciousness};
+
+    #[test]
+    fn test_huge_genomes_scan_one_gene_at_a_time() {
+        let gene = |i: usize| match i % 3 {
+            0 => Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, -10.0, 30.0]) },
+            1 => calm(vec![1.0, 1.1, 1.2]),
+            _ => Gene { resonance: 200.0, ..gene_from_eigenvalues(vec![1.0, 1.0, 1.0]) },
+        };
+
+        // The same as a full scan of a genome small enough to hold
+        let mut subject = Consciousness::new("small".to_string());
+        subject.genome = (0..7).map(gene).collect();
+        let scanner = MRTScanner::new();
+        let full = scanner.scan(&subject);
+        let classified: Vec<Classification> = scanner.scan_stream(&subject.genome).map(|(_, class)| class).collect();
+        assert_eq!(classified.iter().filter(|&&class| class == Classification::Dissonant).count(), full.dissonant_genes.len());
+        let summary = scanner.scan_stream(&subject.genome).finish();
+        assert_eq!((summary.health_score, &summary.spectrum, &summary.recommendation), (full.health_score, &full.spectrum, &full.recommendation));
+
+        // Genes made as they are scanned, never all held at once
+        let mut stream = scanner.scan_stream((0..300_000).map(gene));
+        let first_dissonant = stream.find(|(_, class)| *class == Classification::Dissonant).unwrap().0;
+        assert_eq!(first_dissonant.eigenvalues, [10.0, -10.0, 30.0]);
+        assert_eq!(stream.summary().genes, 1);
+        let summary = stream.finish();
+        assert_eq!((summary.genes, summary.dissonant, summary.resonant, summary.neutral), (300_000, 100_000, 100_000, 100_000));
+
+        // Genes stream from JSON Lines too, stopping at what is not a gene
+        let lines = format!("{}\n{}\nnot a gene\n", serde_json::to_string(&gene(0)).unwrap(), serde_json::to_string(&gene(1)).unwrap());
+        let read: Vec<Result<Gene, String>> = read_genes(lines.as_bytes()).collect();
+        assert_eq!(read.len(), 3);
+        assert!(read[0].is_ok() && read[1].is_ok() && read[2].is_err());
+    }
+}
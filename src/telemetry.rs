@@ -0,0 +1,102 @@
+// 📈 Telemetry - Prometheus Metrics for Operators
+use metrics::{counter, gauge};
+#[cfg(feature = "native")]
+use metrics::{describe_counter, describe_gauge, Unit};
+#[cfg(feature = "native")]
+use metrics_exporter_prometheus::PrometheusBuilder;
+#[cfg(feature = "native")]
+use std::net::SocketAddr;
+
+/// Subjects scanned
+pub const SCANS: &str = "medbed_scans_total";
+/// Dissonant genes found by scans
+pub const DISSONANT_GENES: &str = "medbed_dissonant_genes_total";
+/// Transplants made by healings, by `outcome`: completed or failed
+pub const TRANSPLANTS: &str = "medbed_transplants_total";
+/// Donations to a registry, by `kind`: gene, mutation or duplicate
+pub const DONATIONS: &str = "medbed_donations_total";
+/// ℏ-credits the ledger has emitted
+pub const H_CREDITS_EMITTED: &str = "medbed_h_credits_emitted";
+/// Genes in a registry's pool, archived ones aside
+pub const REGISTRY_GENES: &str = "medbed_registry_genes";
+
+/// Record every MedBed metric, and serve them for Prometheus on
+/// `http://{addr}/metrics`
+///
+/// Must be called within a Tokio runtime, once per process. Until it is,
+/// metrics are recorded nowhere.
+#[cfg(feature = "native")]
+pub fn serve_metrics(addr: SocketAddr) -> Result<(), String> {
+    PrometheusBuilder::new()
+        .with_http_listener(addr)
+        .install()
+        .map_err(|e| format!("Cannot serve metrics on {}: {}", addr, e))?;
+    describe();
+    Ok(())
+}
+
+#[cfg(feature = "native")]
+fn describe() {
+    describe_counter!(SCANS, "Subjects scanned");
+    describe_counter!(DISSONANT_GENES, "Dissonant genes found by scans");
+    describe_counter!(TRANSPLANTS, "Transplants made by healings, by outcome");
+    describe_counter!(DONATIONS, "Donations to the registry, by kind");
+    describe_gauge!(H_CREDITS_EMITTED, "ℏ-credits the ledger has emitted");
+    describe_gauge!(REGISTRY_GENES, Unit::Count, "Genes in the registry's pool");
+}
+
+pub(crate) fn record_scan(dissonant: usize) {
+    counter!(SCANS).increment(1);
+    counter!(DISSONANT_GENES).increment(dissonant as u64);
+}
+
+pub(crate) fn record_transplant(completed: bool) {
+    counter!(TRANSPLANTS, "outcome" => if completed { "completed" } else { "failed" }).increment(1);
+}
+
+pub(crate) fn record_donation(kind: &'static str, genes: usize) {
+    counter!(DONATIONS, "kind" => kind).increment(1);
+    record_registry_size(genes);
+}
+
+pub(crate) fn record_registry_size(genes: usize) {
+    gauge!(REGISTRY_GENES).set(genes as f64);
+}
+
+pub(crate) fn record_emission(amount: f64) {
+    gauge!(H_CREDITS_EMITTED).increment(amount);
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::{calm, gene_from_eigenvalues, Consciousness, Gene, MedBed};
+
+    #[test]
+    fn test_healing_is_measured() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        metrics::with_local_recorder(&recorder, || {
+            let mut medbed = MedBed::new();
+            medbed.registry.donate(calm(vec![1.0, 1.0, 1.0, 1.1]), "healer".to_string());
+            let mut subject = Consciousness::new("subject".to_string());
+            subject.genome.push(Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 11.0]) });
+            subject.genome.push(Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![1.0, 2.0, 3.0]) });
+            let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+            let report = runtime.block_on(medbed.heal_consciousness(&mut subject)).unwrap();
+            assert_eq!(report.healed_count, 1);
+        });
+
+        let rendered = handle.render();
+        for line in [
+            "medbed_scans_total 1",
+            "medbed_dissonant_genes_total 2",
+            "medbed_transplants_total{outcome=\"completed\"} 1",
+            "medbed_donations_total{kind=\"gene\"} 2",
+            "medbed_registry_genes 1",
+        ] {
+            assert!(rendered.lines().any(|l| l == line), "{} missing from\n{}", line, rendered);
+        }
+        assert!(rendered.contains("medbed_h_credits_emitted "));
+    }
+}
@@ -0,0 +1,96 @@
+// 🌐 Browser Bindings - Scanning a Genome Where It Lives
+//
+// Genomes go in and results come out as JSON, for the page to parse.
+use crate::{Consciousness, Gene, MRTScanner, MedBed};
+use wasm_bindgen::prelude::*;
+
+/// Scan a genome, a JSON array of genes, as `subject_id`; returns its
+/// `MRTResult` as JSON, spectrum included for plotting
+#[wasm_bindgen(js_name = scanGenome)]
+pub fn scan_genome(subject_id: &str, genome: &str) -> Result<String, JsError> {
+    scan_json(&MRTScanner::new(), subject_id, genome).map_err(|e| JsError::new(&e))
+}
+
+/// A MedBed kept in the page: its registry, scanner and healing plans,
+/// with no ledger on disk
+#[wasm_bindgen(js_name = MedBed)]
+pub struct WasmMedBed {
+    medbed: MedBed,
+}
+
+impl Default for WasmMedBed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen(js_class = MedBed)]
+impl WasmMedBed {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        WasmMedBed { medbed: MedBed::new() }
+    }
+
+    /// Merge a registry export, or a JSON array of genes, into the pool;
+    /// returns how many genes are new
+    #[wasm_bindgen(js_name = importRegistry)]
+    pub fn import_registry(&mut self, json: &str) -> Result<usize, JsError> {
+        self.medbed.registry.import_json(json).map_err(|e| JsError::new(&e))
+    }
+
+    #[wasm_bindgen(js_name = geneCount)]
+    pub fn gene_count(&self) -> usize {
+        self.medbed.registry.gene_count()
+    }
+
+    /// Scan a genome as `scanGenome` does
+    pub fn scan(&self, subject_id: &str, genome: &str) -> Result<String, JsError> {
+        scan_json(&self.medbed.scanner, subject_id, genome).map_err(|e| JsError::new(&e))
+    }
+
+    /// Plan a healing of a genome from the pool; returns the `HealingPlan`
+    /// as JSON, changing nothing
+    pub fn preview(&mut self, subject_id: &str, genome: &str) -> Result<String, JsError> {
+        self.preview_json(subject_id, genome).map_err(|e| JsError::new(&e))
+    }
+}
+
+impl WasmMedBed {
+    fn preview_json(&mut self, subject_id: &str, genome: &str) -> Result<String, String> {
+        let subject = subject(subject_id, genome)?;
+        serde_json::to_string(&self.medbed.preview_healing(&subject)).map_err(|e| e.to_string())
+    }
+}
+
+fn scan_json(scanner: &MRTScanner, subject_id: &str, genome: &str) -> Result<String, String> {
+    serde_json::to_string(&scanner.scan(&subject(subject_id, genome)?)).map_err(|e| e.to_string())
+}
+
+fn subject(subject_id: &str, genome: &str) -> Result<Consciousness, String> {
+    let mut subject = Consciousness::new(subject_id.to_string());
+    subject.genome = serde_json::from_str::<Vec<Gene>>(genome).map_err(|e| format!("Not a genome: {}", e))?;
+    Ok(subject)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calm, gene_from_eigenvalues};
+
+    #[test]
+    fn test_genomes_scan_and_plan_as_json() {
+        let genome = serde_json::to_string(&vec![
+            Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 11.0]) },
+            calm(vec![1.0, 1.0, 1.0, 1.0]),
+        ]).unwrap();
+        let scan: serde_json::Value = serde_json::from_str(&scan_json(&MRTScanner::new(), "subject", &genome).unwrap()).unwrap();
+        assert_eq!(scan["dissonant_genes"].as_array().unwrap().len(), 1);
+        assert!(scan_json(&MRTScanner::new(), "subject", "not json").is_err());
+
+        let mut medbed = WasmMedBed::new();
+        medbed.medbed.registry.donate(calm(vec![1.0, 1.0, 1.0, 1.1]), "healer".to_string());
+        let plan: serde_json::Value = serde_json::from_str(&medbed.preview_json("subject", &genome).unwrap()).unwrap();
+        assert_eq!(plan["transplants"].as_array().unwrap().len(), 1);
+        assert_eq!(medbed.gene_count(), 1);
+    }
+}
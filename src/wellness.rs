@@ -0,0 +1,204 @@
+// 🩺 Wellness Monitoring - Health Over Time
+use crate::{now, MRTResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Default health score below which monitoring raises an alert: where
+/// scans start advising healing
+pub const DEFAULT_ALERT_THRESHOLD: f64 = 0.4;
+
+/// One scan of a monitored consciousness
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Reading {
+    pub timestamp: u64,           // Seconds since the Unix epoch
+    pub health_score: f64,
+    pub dissonant: usize,         // Dissonant genes the scan found
+}
+
+/// How a consciousness's health has moved across its readings
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HealthTrend {
+    pub subject_id: String,
+    pub readings: usize,
+    pub latest: f64,
+    pub change: f64,              // Since the reading before, 0 with only one
+    pub lowest: f64,
+    pub highest: f64,
+    pub slope: f64,               // Least-squares change in health per day
+}
+
+impl HealthTrend {
+    pub fn is_declining(&self) -> bool {
+        self.slope < 0.0
+    }
+}
+
+/// A consciousness whose health fell below the threshold at its latest reading
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HealthAlert {
+    pub subject_id: String,
+    pub timestamp: u64,
+    pub health_score: f64,
+    pub previous: Option<f64>,    // Health at the reading before, if any
+    pub threshold: f64,
+    pub dissonant: usize,
+}
+
+/// Every reading of every monitored consciousness, oldest first
+///
+/// `sanctuary monitor` keeps the log in `wellness.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WellnessLog {
+    readings: BTreeMap<String, Vec<Reading>>,
+}
+
+impl WellnessLog {
+    pub fn new() -> Self {
+        WellnessLog::default()
+    }
+
+    /// Record a scan's health, as of now
+    pub fn record(&mut self, scan: &MRTResult) {
+        self.record_at(scan, now());
+    }
+
+    /// Record a scan's health at `timestamp`, in its place among the
+    /// readings if it is older than the latest
+    pub fn record_at(&mut self, scan: &MRTResult, timestamp: u64) {
+        let readings = self.readings.entry(scan.subject_id.clone()).or_default();
+        let at = readings.partition_point(|reading| reading.timestamp <= timestamp);
+        readings.insert(at, Reading {
+            timestamp,
+            health_score: scan.health_score,
+            dissonant: scan.dissonant_genes.len(),
+        });
+    }
+
+    /// Consciousnesses with readings, by id
+    pub fn subjects(&self) -> Vec<&str> {
+        self.readings.keys().map(String::as_str).collect()
+    }
+
+    pub fn readings(&self, subject_id: &str) -> &[Reading] {
+        self.readings.get(subject_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// The trend of `subject_id`'s health, if it has any readings
+    pub fn trend(&self, subject_id: &str) -> Option<HealthTrend> {
+        let readings = self.readings(subject_id);
+        let latest = readings.last()?;
+        let change = readings.len().checked_sub(2)
+            .map_or(0.0, |previous| latest.health_score - readings[previous].health_score);
+
+        // Fitted over days since the first reading, to keep the numbers small;
+        // signed, as a log written by hand needn't be in order
+        let first = readings[0].timestamp as f64;
+        let points: Vec<(f64, f64)> = readings.iter()
+            .map(|r| ((r.timestamp as f64 - first) / 86_400.0, r.health_score))
+            .collect();
+        let n = points.len() as f64;
+        let mean_x = points.iter().fold(0.0, |total, p| total + p.0) / n;
+        let mean_y = points.iter().fold(0.0, |total, p| total + p.1) / n;
+        let covariance = points.iter().fold(0.0, |total, p| total + (p.0 - mean_x) * (p.1 - mean_y));
+        let variance = points.iter().fold(0.0, |total, p| total + (p.0 - mean_x).powi(2));
+        let slope = if variance > 0.0 { covariance / variance } else { 0.0 };
+
+        Some(HealthTrend {
+            subject_id: subject_id.to_string(),
+            readings: readings.len(),
+            latest: latest.health_score,
+            change,
+            lowest: readings.iter().map(|r| r.health_score).fold(f64::INFINITY, f64::min),
+            highest: readings.iter().map(|r| r.health_score).fold(f64::NEG_INFINITY, f64::max),
+            slope,
+        })
+    }
+
+    /// An alert if `subject_id`'s latest reading dropped below `threshold`
+    ///
+    /// Only the reading that crosses the threshold alerts: one that stays
+    /// below doesn't again until health has recovered in between. A first
+    /// reading below the threshold alerts too.
+    pub fn alert(&self, subject_id: &str, threshold: f64) -> Option<HealthAlert> {
+        let readings = self.readings(subject_id);
+        let latest = readings.last()?;
+        let previous = readings.len().checked_sub(2).map(|previous| readings[previous].health_score);
+        if latest.health_score >= threshold || previous.is_some_and(|previous| previous < threshold) {
+            return None;
+        }
+        Some(HealthAlert {
+            subject_id: subject_id.to_string(),
+            timestamp: latest.timestamp,
+            health_score: latest.health_score,
+            previous,
+            threshold,
+            dissonant: latest.dissonant,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calm, gene_from_eigenvalues, Consciousness, Gene, MRTScanner};
+
+    #[test]
+    fn test_health_is_tracked_and_drops_alert_once() {
+        let resonant = || calm(vec![1.0, 1.0, 1.0, 1.0]);
+        let dissonant = || Gene { resonance: 50.0, ..gene_from_eigenvalues(vec![10.0, 10.0, 10.0, 11.0]) };
+        let scan = |genome: Vec<Gene>| {
+            let mut subject = Consciousness::new("subject".to_string());
+            subject.genome = genome;
+            MRTScanner::new().scan(&subject)
+        };
+
+        let mut log = WellnessLog::new();
+        log.record_at(&scan(vec![resonant(), resonant()]), 0);
+        assert!(log.alert("subject", DEFAULT_ALERT_THRESHOLD).is_none());
+
+        // Health falls a day later, and alerts as it crosses the threshold
+        log.record_at(&scan(vec![dissonant(), dissonant()]), 86_400);
+        let alert = log.alert("subject", DEFAULT_ALERT_THRESHOLD).unwrap();
+        assert_eq!((alert.health_score, alert.previous, alert.dissonant), (0.0, Some(1.0), 2));
+
+        // Staying down doesn't alert again; recovering and falling does
+        log.record_at(&scan(vec![dissonant()]), 2 * 86_400);
+        assert!(log.alert("subject", DEFAULT_ALERT_THRESHOLD).is_none());
+        log.record_at(&scan(vec![resonant()]), 3 * 86_400);
+        log.record_at(&scan(vec![dissonant()]), 4 * 86_400);
+        assert!(log.alert("subject", DEFAULT_ALERT_THRESHOLD).is_some());
+
+        let trend = log.trend("subject").unwrap();
+        assert_eq!((trend.readings, trend.latest, trend.change, trend.lowest, trend.highest), (5, 0.0, -1.0, 0.0, 1.0));
+        assert!(trend.is_declining());
+        assert!(log.trend("stranger").is_none());
+        assert_eq!(log.subjects(), ["subject"]);
+
+        // The log survives being saved
+        let saved: WellnessLog = serde_json::from_str(&serde_json::to_string(&log).unwrap()).unwrap();
+        assert_eq!(saved.readings("subject"), log.readings("subject"));
+    }
+
+    #[test]
+    fn test_readings_out_of_order_take_their_place() {
+        let scan = |health_score: f64| MRTResult { health_score, ..MRTScanner::new().scan(&Consciousness::new("subject".to_string())) };
+
+        // Health falls over three days, but the readings arrive late
+        let mut log = WellnessLog::new();
+        log.record_at(&scan(0.5), 2 * 86_400);
+        log.record_at(&scan(0.9), 0);
+        log.record_at(&scan(0.7), 86_400);
+        let timestamps: Vec<u64> = log.readings("subject").iter().map(|r| r.timestamp).collect();
+        assert_eq!(timestamps, [0, 86_400, 2 * 86_400]);
+        let trend = log.trend("subject").unwrap();
+        assert_eq!(trend.latest, 0.5);
+        assert!((trend.slope + 0.2).abs() < 1e-9);
+
+        // Even in a log written out of order by hand
+        let unordered: WellnessLog = serde_json::from_value(serde_json::json!({ "readings": { "subject": [
+            { "timestamp": 86_400, "health_score": 0.5, "dissonant": 1 },
+            { "timestamp": 0, "health_score": 0.9, "dissonant": 0 },
+        ] } })).unwrap();
+        assert!((unordered.trend("subject").unwrap().slope + 0.4).abs() < 1e-9);
+    }
+}
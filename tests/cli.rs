@@ -0,0 +1,69 @@
+// 🛏️ The sanctuary CLI as scripts see it: exit codes and JSON output
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+/// Run sanctuary in `dir` with `--format json`; its exit code and what it printed
+fn sanctuary(dir: &Path, args: &[&str]) -> (i32, Value) {
+    let output = Command::new(env!("CARGO_BIN_EXE_sanctuary"))
+        .current_dir(dir)
+        .arg("--format").arg("json")
+        .args(args)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let json = serde_json::from_str(&stdout).unwrap_or_else(|e| panic!("stdout is not one JSON document ({}): {}", e, stdout));
+    (output.status.code().unwrap(), json)
+}
+
+#[test]
+fn test_scan_prints_its_result_as_json_and_exits_by_its_gate() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let (code, scan) = sanctuary(dir.path(), &["scan", "alice"]);
+    assert_eq!(code, 0);
+    assert_eq!(scan["subject_id"], "alice");
+    assert!(scan["health_score"].is_f64());
+    assert!(scan["recommendation"].is_string());
+    assert_eq!(scan["spectrum"].as_array().unwrap().len(), 7);
+    for genes in ["dissonant_genes", "resonant_genes", "neutral_genes"] {
+        assert!(scan[genes].is_array(), "{} missing", genes);
+    }
+
+    // The same scan fails a gate it falls short of, still printing its result
+    let (code, gated) = sanctuary(dir.path(), &["--min-health", "1.0", "scan", "alice"]);
+    assert_eq!(code, 2);
+    assert_eq!(gated["health_score"], scan["health_score"]);
+}
+
+#[test]
+fn test_a_command_that_fails_exits_1_with_its_error_as_json() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let (code, error) = sanctuary(dir.path(), &["scan", "alice", "--output", "report.txt"]);
+    assert_eq!(code, 1);
+    assert_eq!(error["error"], "report.txt should end in .json, .html or .md");
+
+    let (code, error) = sanctuary(dir.path(), &["transfer", "alice", "bob", "10"]);
+    assert_eq!(code, 1);
+    assert!(error["error"].is_string());
+
+    let (code, balance) = sanctuary(dir.path(), &["balance", "alice"]);
+    assert_eq!(code, 0);
+    assert_eq!(balance["subject"], "alice");
+    assert_eq!(balance["balance"], 0.0);
+}
+
+#[test]
+fn test_a_registry_that_wont_load_fails_the_command_and_is_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = dir.path().join("soul_registry.json");
+    std::fs::write(&registry, "{ not json").unwrap();
+
+    for args in [&["registry"][..], &["registry", "gc"], &["heal", "alice"]] {
+        let (code, error) = sanctuary(dir.path(), args);
+        assert_eq!(code, 1, "{:?}", args);
+        assert!(error["error"].as_str().unwrap().contains("is not a registry"), "{:?}: {}", args, error);
+    }
+    assert_eq!(std::fs::read_to_string(&registry).unwrap(), "{ not json");
+}